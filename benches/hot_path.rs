@@ -0,0 +1,130 @@
+//! Microbenchmarks for the per-probe hot path
+//!
+//! Run with `cargo bench`. For a quick end-to-end number without criterion,
+//! `ttl --bench-parse benches/corpus.txt` replays the same corpus.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use ttl::bench::{
+    build_echo_request, build_tcp_syn, build_udp_payload, parse_corpus, parse_icmp_response,
+    replay_corpus,
+};
+use ttl::config::UdpIdEncoding;
+use ttl::state::link::p2p_prefix;
+use ttl::state::{Hop, ProbeId, ResponderAlias, find_alias};
+
+const CORPUS: &str = include_str!("corpus.txt");
+
+fn packet_building(c: &mut Criterion) {
+    let probe_id = ProbeId::new(12, 3);
+    let src = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 100));
+    let dst = IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34));
+
+    c.bench_function("build_echo_request", |b| {
+        b.iter(|| {
+            build_echo_request(
+                black_box(0x4d2e),
+                probe_id.to_sequence(),
+                0x5eed,
+                64,
+                false,
+                None,
+            )
+        })
+    });
+    c.bench_function("build_udp_payload", |b| {
+        b.iter(|| build_udp_payload(black_box(probe_id)))
+    });
+    c.bench_function("build_tcp_syn", |b| {
+        b.iter(|| build_tcp_syn(black_box(probe_id), 50000, 443, src, dst))
+    });
+}
+
+fn response_parsing(c: &mut Criterion) {
+    let packets = parse_corpus(CORPUS).unwrap();
+    let time_exceeded = &packets[0];
+    let echo_reply = packets
+        .iter()
+        .find(|p| p.responder == IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)))
+        .unwrap();
+
+    c.bench_function("parse_time_exceeded_v4", |b| {
+        b.iter(|| {
+            parse_icmp_response(
+                black_box(&time_exceeded.data),
+                time_exceeded.responder,
+                time_exceeded.identifier,
+                false,
+                UdpIdEncoding::Payload,
+            )
+        })
+    });
+    c.bench_function("parse_echo_reply_v4", |b| {
+        b.iter(|| {
+            parse_icmp_response(
+                black_box(&echo_reply.data),
+                echo_reply.responder,
+                echo_reply.identifier,
+                false,
+                UdpIdEncoding::Payload,
+            )
+        })
+    });
+    c.bench_function("replay_corpus", |b| {
+        b.iter(|| replay_corpus(black_box(&packets), packets.len() as u64))
+    });
+}
+
+fn prefix_matching(c: &mut Criterion) {
+    // Roughly the size of a PeeringDB IX prefix table
+    let aliases: Vec<ResponderAlias> = (0..1000u32)
+        .map(|i| {
+            let spec = format!("ix{}={}.{}.0.0/16", i, 100 + i / 256, i % 256);
+            ResponderAlias::parse(&spec).unwrap()
+        })
+        .collect();
+    let miss = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+    let hit = IpAddr::V4(Ipv4Addr::new(103, 231, 1, 1));
+
+    c.bench_function("find_alias_miss_1000", |b| {
+        b.iter(|| find_alias(black_box(&aliases), miss))
+    });
+    c.bench_function("find_alias_hit_1000", |b| {
+        b.iter(|| find_alias(black_box(&aliases), hit))
+    });
+    c.bench_function("p2p_prefix", |b| {
+        let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let z = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        b.iter(|| p2p_prefix(black_box(a), black_box(z)))
+    });
+}
+
+fn stats_updates(c: &mut Criterion) {
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut hop = Hop::new(5);
+
+    c.bench_function("hop_record_response", |b| {
+        b.iter(|| {
+            hop.record_sent();
+            hop.record_response(black_box(ip), Duration::from_micros(12_345));
+        })
+    });
+    c.bench_function("hop_record_timeout", |b| {
+        b.iter(|| {
+            hop.record_sent();
+            hop.record_timeout();
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    packet_building,
+    response_parsing,
+    prefix_matching,
+    stats_updates
+);
+criterion_main!(benches);
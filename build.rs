@@ -0,0 +1,40 @@
+//! Build metadata for `ttl --build-info`
+//!
+//! Records the git commit, target triple, profile and compiler version as
+//! `TTL_*` environment variables for `env!`/`option_env!`. Everything is
+//! optional: a source tarball without git or an unusual toolchain still
+//! builds, with those fields left out.
+
+use std::process::Command;
+
+fn output(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(cmd).args(args).output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(out.stdout).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+
+    if let Some(commit) = output("git", &["rev-parse", "--short=12", "HEAD"]) {
+        println!("cargo:rustc-env=TTL_GIT_COMMIT={}", commit);
+    }
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    if let Some(version) = output(&rustc, &["--version"]) {
+        println!("cargo:rustc-env=TTL_RUSTC_VERSION={}", version);
+    }
+    for (var, name) in [
+        ("TARGET", "TTL_BUILD_TARGET"),
+        ("PROFILE", "TTL_BUILD_PROFILE"),
+    ] {
+        if let Ok(value) = std::env::var(var) {
+            println!("cargo:rustc-env={}={}", name, value);
+        }
+    }
+}
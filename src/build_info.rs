@@ -0,0 +1,206 @@
+//! `ttl --build-info`: what this binary was built with, as JSON
+//!
+//! Meant to be pasted into bug reports verbatim: version and commit,
+//! compiler and target (recorded by `build.rs`), cargo features, the
+//! lookup providers and probe protocols compiled in, and the data files
+//! and caches it would use, with the format version each one is on.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::lookup::{asn, cache, geo::GeoLookup, ix, prefix, rir};
+
+/// Build and runtime capabilities of this binary
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rustc: Option<&'static str>,
+    /// Optional cargo features enabled in this build
+    pub features: Vec<&'static str>,
+    /// `-p` values this build can probe with
+    pub protocols: Vec<&'static str>,
+    pub lookups: Vec<LookupProvider>,
+    pub data_files: Vec<DataFile>,
+}
+
+/// An enrichment lookup and where its answers come from
+#[derive(Debug, Serialize)]
+pub struct LookupProvider {
+    pub name: &'static str,
+    pub source: String,
+}
+
+/// A data file or cache, and the state of the copy on disk
+#[derive(Debug, Serialize)]
+pub struct DataFile {
+    pub name: &'static str,
+    /// First path that exists, or the preferred location if none does
+    pub path: PathBuf,
+    pub present: bool,
+    /// Cache format this build reads and writes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format_version: Option<u32>,
+    /// Format of the file on disk, when it can be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found_version: Option<u32>,
+    /// When the cached data was downloaded (Unix seconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<u64>,
+}
+
+/// Fields every lookup cache file starts with
+#[derive(Deserialize)]
+struct CacheHeader {
+    version: u32,
+    fetched_at: u64,
+}
+
+impl BuildInfo {
+    pub fn collect() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: option_env!("TTL_GIT_COMMIT"),
+            target: option_env!("TTL_BUILD_TARGET"),
+            profile: option_env!("TTL_BUILD_PROFILE"),
+            rustc: option_env!("TTL_RUSTC_VERSION"),
+            features: enabled_features(),
+            protocols: vec!["icmp", "udp", "tcp"],
+            lookups: vec![
+                LookupProvider {
+                    name: "asn",
+                    source: format!("Team Cymru DNS ({})", asn::CYMRU_ZONE),
+                },
+                LookupProvider {
+                    name: "rdns",
+                    source: "DNS PTR via system resolver or --dns-server".to_string(),
+                },
+                LookupProvider {
+                    name: "geoip",
+                    source: "MaxMind GeoLite2 database (local file)".to_string(),
+                },
+                LookupProvider {
+                    name: "ix",
+                    source: format!("PeeringDB ({})", ix::PEERINGDB_API),
+                },
+                LookupProvider {
+                    name: "rir",
+                    source: "RIR delegated-extended statistics".to_string(),
+                },
+                LookupProvider {
+                    name: "prefix",
+                    source: format!("RIPEstat ({})", prefix::NETWORK_INFO_URL),
+                },
+            ],
+            data_files: vec![
+                cache_file("peeringdb_ix", ix::CACHE_FILE, ix::CACHE_VERSION),
+                cache_file("rir_delegations", rir::CACHE_FILE, rir::CACHE_VERSION),
+                geoip_file(),
+            ],
+        }
+    }
+}
+
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "desktop-notify") {
+        features.push("desktop-notify");
+    }
+    features
+}
+
+fn cache_file(name: &'static str, (dir, file): (&str, &str), version: u32) -> DataFile {
+    let path = cache::root().join(dir).join(file);
+    let header = read_header(&path);
+    DataFile {
+        name,
+        present: path.exists(),
+        format_version: Some(version),
+        found_version: header.as_ref().map(|h| h.version),
+        fetched_at: header.map(|h| h.fetched_at),
+        path,
+    }
+}
+
+fn read_header(path: &Path) -> Option<CacheHeader> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+/// GeoLite2 database found on the default search path (--geoip-db aside)
+fn geoip_file() -> DataFile {
+    let paths = GeoLookup::default_paths();
+    let found = paths.iter().find(|p| p.exists()).cloned();
+    DataFile {
+        name: "geoip",
+        present: found.is_some(),
+        path: found
+            .or_else(|| paths.into_iter().next())
+            .unwrap_or_default(),
+        format_version: None,
+        found_version: None,
+        fetched_at: None,
+    }
+}
+
+/// Print the build info as pretty JSON
+pub fn write_build_info<W: Write>(mut writer: W) -> Result<()> {
+    serde_json::to_writer_pretty(&mut writer, &BuildInfo::collect())?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_json() {
+        let mut out = Vec::new();
+        write_build_info(&mut out).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            value["protocols"],
+            serde_json::json!(["icmp", "udp", "tcp"])
+        );
+        assert_eq!(
+            value["features"]
+                .as_array()
+                .unwrap()
+                .contains(&"desktop-notify".into()),
+            cfg!(feature = "desktop-notify")
+        );
+        let files = value["data_files"].as_array().unwrap();
+        assert_eq!(files[0]["name"], "peeringdb_ix");
+        assert_eq!(files[0]["format_version"], 1);
+        assert!(value["lookups"].as_array().unwrap().len() >= 5);
+    }
+
+    #[test]
+    fn test_cache_header() {
+        let dir = std::env::temp_dir().join(format!("ttl-build-info-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cache.json");
+        std::fs::write(
+            &path,
+            r#"{"version":1,"fetched_at":1700000000,"ranges":[]}"#,
+        )
+        .unwrap();
+        let header = read_header(&path).unwrap();
+        assert_eq!((header.version, header.fetched_at), (1, 1_700_000_000));
+        std::fs::write(&path, "not json").unwrap();
+        assert!(read_header(&path).is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,10 +1,18 @@
-use clap::Parser;
+use clap::{Arg, CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::time::Duration;
 
+use crate::config::LookupLimits;
+use crate::export::{DEFAULT_REDACT, Redaction, ReportColumn, ReportOptions};
+use crate::state::{EcnCodepoint, NumberFormat, ResponderAlias, Slo};
+
+/// Probe interval when neither -i nor config.toml sets one
+pub const DEFAULT_INTERVAL_SECS: f64 = 1.0;
+
 /// Modern traceroute/mtr-style TUI with hop stats and optional ASN/geo enrichment
 #[derive(Parser, Debug, Clone)]
 #[command(name = "ttl")]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 #[command(after_help = "\
 EXAMPLES:
     Basic tracing:
@@ -20,41 +28,104 @@ EXAMPLES:
 
     Path MTU discovery:
         ttl --pmtud 8.8.8.8              # Find max packet size
+        ttl --df-sweep 8.8.8.8           # Max unfragmented size at each hop
 
     QoS testing:
         ttl --dscp 46 host               # Test VoIP traffic class
+        ttl --ecn host                   # Find where ECN marking is cleared
 
     Export results:
         ttl -c 100 --json host > out.json
 
+    Nightly health check (combined summary for a list of hosts):
+        ttl -c 10 --report --targets-file hosts.txt --parallel 8
+
+    Status bars (tmux, i3bar, waybar):
+        ttl --status-line 1.1.1.1
+
+    Many targets without bursting the first hop:
+        ttl --schedule round-robin --batch 10 --global-rate 100 $(cat hosts.txt)
+
+    Track an SLO (error budget in the title bar and report):
+        ttl --slo 'p95<80ms,loss<1%' 1.1.1.1
+
+    Compare vantage points:
+        ttl merge office-a.json office-b.json
+
+    Remote agent:
+        ttl agent --token s3cret                         # on the probe host (127.0.0.1:7447)
+        ssh -N -L 7447:127.0.0.1:7447 probe-host &       # tunnel from your workstation
+        ttl --via localhost --agent-token s3cret 8.8.8.8
+        ttl agent --health localhost --token s3cret      # check a running agent
+
 DETECTION INDICATORS:
-    [NAT]  - Source port rewriting detected (affects multi-flow accuracy)
+    [NAT @n] - Source port/address rewritten before hop n (affects multi-flow accuracy)
     [RL?]  - Router rate-limiting ICMP (loss may be artificial)
     [ASYM] - Asymmetric routing detected (return path differs)
     [TTL!] - TTL manipulation detected (middlebox modifying TTL)
+    [MPLS?] - MPLS tunnel suspected from TTL and RTT patterns
+    [DSCP a>b @n] - DSCP marking rewritten from a to b before hop n
+    [ECN cleared @n] - ECN marking (--ecn) cleared before hop n
     !      - Route flap at this hop (path instability)
     ~      - Asymmetric routing suspected at this hop
     ^      - TTL manipulation suspected at this hop
+    =      - Hop inside (or just after a hidden) suspected MPLS tunnel
+    #      - DSCP marking rewritten before this hop
+    ECMP ×N - N next hops seen across --flows (N+ while more may be unseen)
 
 For detailed documentation: https://github.com/lance0/ttl/blob/master/docs/FEATURES.md
 ")]
 pub struct Args {
     /// Target hosts to trace (IP address or hostname)
-    #[arg(required_unless_present_any = ["completions", "replay"])]
+    #[arg(required_unless_present_any = ["completions", "replay", "targets_file", "bench_parse", "build_info"])]
     pub targets: Vec<String>,
 
+    /// Read more targets from a file, one per line (# starts a comment, - reads stdin)
+    #[arg(long = "targets-file", value_name = "PATH")]
+    pub targets_file: Option<std::path::PathBuf>,
+
     /// Number of probe rounds (0 = infinite). Each round sends probes to all TTLs.
     #[arg(short = 'c', long = "count", default_value = "0")]
     pub count: u64,
 
-    /// Probe interval in seconds
-    #[arg(short = 'i', long = "interval", default_value = "1.0")]
-    pub interval: f64,
+    /// Stop after this much wall-clock time, e.g. 90s, 5m, 1h30m (alongside or instead of -c)
+    #[arg(long = "duration", value_name = "TIME", value_parser = parse_duration_spec)]
+    pub duration: Option<Duration>,
+
+    /// Also keep stats over a sliding window of recent results, e.g. 5m ('w' toggles in the TUI)
+    #[arg(long = "stats-window", value_name = "TIME", value_parser = parse_duration_spec)]
+    pub stats_window: Option<Duration>,
+
+    /// Probe interval in seconds [default: 1.0, or `interval` from config.toml]
+    #[arg(short = 'i', long = "interval", value_name = "INTERVAL")]
+    pub interval: Option<f64>,
 
     /// Maximum TTL (hops)
     #[arg(short = 'm', long = "max-ttl", default_value = "30")]
     pub max_ttl: u8,
 
+    /// First TTL to probe; hops before it are skipped (with -m, probes
+    /// only a TTL window)
+    #[arg(short = 'f', long = "first-ttl", value_name = "TTL", default_value = "1",
+          value_parser = clap::value_parser!(u8).range(1..))]
+    pub first_ttl: u8,
+
+    /// Stop probing past this many consecutive silent hops until the
+    /// destination is found (TTLs beyond are re-checked every 10 rounds)
+    #[arg(long = "gap-limit", value_name = "HOPS",
+          value_parser = clap::value_parser!(u8).range(1..))]
+    pub gap_limit: Option<u8>,
+
+    /// Low-impact mode: probe TTL t every FACTOR^(t-1) rounds (e.g. 1.15),
+    /// so distant hops see fewer probes than the local segment
+    #[arg(long = "ttl-decay", value_name = "FACTOR", value_parser = parse_ttl_decay)]
+    pub ttl_decay: Option<f64>,
+
+    /// Probe hops that show heavy loss or ICMP rate limiting less often,
+    /// down to every 8th round, and speed back up once they recover
+    #[arg(long = "adaptive")]
+    pub adaptive: bool,
+
     /// Probe protocol (auto, icmp, udp, tcp)
     #[arg(short = 'p', long = "protocol", default_value = "auto")]
     pub protocol: String,
@@ -67,6 +138,12 @@ pub struct Args {
     #[arg(long = "fixed-port")]
     pub port_fixed: bool,
 
+    /// Where UDP probes carry their ID: the payload, or the UDP checksum
+    /// (survives routers that quote only 8 bytes of the probe)
+    #[arg(long = "udp-id", value_name = "FIELD", default_value = "payload",
+          value_parser = ["payload", "checksum"])]
+    pub udp_id: String,
+
     /// Number of flows for multi-path ECMP detection (1 = classic mode)
     #[arg(long = "flows", default_value = "1")]
     pub flows: u8,
@@ -75,6 +152,14 @@ pub struct Args {
     #[arg(long = "src-port", default_value = "50000")]
     pub src_port: u16,
 
+    /// Source ports flows may use, e.g. 33434-33534 (flows are spread across it)
+    #[arg(
+        long = "src-port-range",
+        value_name = "FIRST-LAST",
+        conflicts_with = "src_port"
+    )]
+    pub src_port_range: Option<String>,
+
     /// Probe timeout in seconds
     #[arg(long = "timeout", default_value = "3")]
     pub timeout: f64,
@@ -87,6 +172,27 @@ pub struct Args {
     #[arg(short = '6', long = "ipv6")]
     pub ipv6: bool,
 
+    /// Resolve targets with this DNS server (IP or IP:PORT) instead of the system resolver
+    #[arg(long = "dns-server", value_name = "ADDR", value_parser = parse_dns_server)]
+    pub dns_server: Option<std::net::SocketAddr>,
+
+    /// Trace the Nth address a hostname resolves to (1-based, IPv4 listed first)
+    #[arg(long = "address-index", value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub address_index: Option<u16>,
+
+    /// List every address a hostname resolves to and ask which one to trace
+    #[arg(long = "pick-address", conflicts_with = "address_index")]
+    pub pick_address: bool,
+
+    /// For dual-stack hostnames, trial-trace IPv4 and IPv6 briefly and keep the
+    /// family that reaches the destination in fewer hops (then lower RTT)
+    #[arg(long = "best-family", conflicts_with_all = ["ipv4", "ipv6", "address_index", "pick_address", "source_ip"])]
+    pub best_family: bool,
+
+    /// Don't prompt: trace the first address of an ambiguous hostname
+    #[arg(short = 'y', long = "yes", conflicts_with = "pick_address")]
+    pub yes: bool,
+
     /// Skip reverse DNS lookups
     #[arg(long = "no-dns")]
     pub no_dns: bool,
@@ -103,6 +209,81 @@ pub struct Args {
     #[arg(long = "no-ix")]
     pub no_ix: bool,
 
+    /// Track each hop's BGP prefix and origin AS via RIPEstat, flagging
+    /// more-specifics and origin changes that appear mid-session
+    #[arg(long = "prefix-watch")]
+    pub prefix_watch: bool,
+
+    /// Make no network calls besides probes: lookups use only local
+    /// databases and caches, targets must be IP addresses
+    #[arg(long = "offline")]
+    pub offline: bool,
+
+    /// Only let lookups contact HOST: a name (subdomains included), address
+    /// or CIDR range (repeatable; also `allowed_hosts` in config.toml)
+    #[arg(long = "allow-host", value_name = "HOST")]
+    pub allow_host: Vec<String>,
+
+    /// Keep lookup caches (PeeringDB, RIR data) in memory only instead of
+    /// under ~/.cache/ttl
+    #[arg(long = "no-disk-cache")]
+    pub no_disk_cache: bool,
+
+    /// This host's position as LAT,LON for hop distances and the speed-of-light
+    /// RTT floor (default: geolocate the source address or first hop)
+    #[arg(long = "origin", value_name = "LAT,LON", value_parser = crate::state::GeoPoint::parse)]
+    pub origin: Option<crate::state::GeoPoint>,
+
+    /// Count several responder addresses as one device: NAME=ADDR[,ADDR...],
+    /// prefixes allowed (repeatable)
+    #[arg(long = "alias", value_name = "NAME=ADDRS")]
+    pub alias: Vec<String>,
+
+    /// Friendly name for a responder address or prefix: ADDR=LABEL, most
+    /// specific prefix wins (repeatable)
+    #[arg(long = "label", value_name = "ADDR=LABEL")]
+    pub label: Vec<String>,
+
+    /// Show labels next to the reverse DNS name or instead of it
+    #[arg(long = "label-mode", value_name = "MODE", value_parser = ["alongside", "replace"])]
+    pub label_mode: Option<String>,
+
+    /// Shorten long host names: smart (keep leading labels, drop the
+    /// domain), end, or middle
+    #[arg(long = "host-trim", value_name = "STYLE", value_parser = ["smart", "end", "middle"])]
+    pub host_trim: Option<String>,
+
+    /// Run an external command on a hook: responder=CMD (enrich each new
+    /// responder) or export=CMD (receive exported sessions) (repeatable)
+    #[arg(long = "plugin", value_name = "HOOK=CMD")]
+    pub plugin: Vec<String>,
+
+    /// POST each new responder to URL and show the key/value annotations it
+    /// returns (e.g. owner and device name from a CMDB)
+    #[arg(long = "enrich-url", value_name = "URL", env = "TTL_ENRICH_URL")]
+    pub enrich_url: Option<String>,
+
+    /// Bearer token for the --enrich-url endpoint
+    #[arg(
+        long = "enrich-token",
+        env = "TTL_ENRICH_TOKEN",
+        hide_env_values = true,
+        requires = "enrich_url"
+    )]
+    pub enrich_token: Option<String>,
+
+    /// Merge responders at a hop that share a reverse DNS name
+    #[arg(long = "merge-by-hostname")]
+    pub merge_by_hostname: bool,
+
+    /// Max concurrent lookups per worker: N for all, or per kind (dns=4,asn=2,geo=20,ix=1,prefix=2,plugin=4,enrich=4)
+    #[arg(long = "lookup-concurrency", value_name = "SPEC")]
+    pub lookup_concurrency: Option<String>,
+
+    /// Seconds between lookup worker scans: N for all, or per kind (dns=0.5,ix=5)
+    #[arg(long = "lookup-interval", value_name = "SPEC")]
+    pub lookup_interval: Option<String>,
+
     /// Path to MaxMind GeoLite2 database file
     #[arg(long = "geoip-db")]
     pub geoip_db: Option<String>,
@@ -111,6 +292,10 @@ pub struct Args {
     #[arg(long = "no-tui")]
     pub no_tui: bool,
 
+    /// Print one compact status line per interval (for tmux, i3bar, waybar)
+    #[arg(long = "status-line", conflicts_with_all = ["no_tui", "json", "csv", "report"])]
+    pub status_line: bool,
+
     /// Output JSON (batch mode, requires -c)
     #[arg(long = "json")]
     pub json: bool,
@@ -123,6 +308,44 @@ pub struct Args {
     #[arg(long = "report")]
     pub report: bool,
 
+    /// Don't truncate hostnames in report output
+    #[arg(long = "report-wide")]
+    pub report_wide: bool,
+
+    /// Report columns and their order, mtr-style (e.g. LSABWVJ)
+    /// L=loss D=drop R=recv S=sent N=last B=min A=avg W=max V=stddev J=jitter M=jitter avg X=jitter max
+    /// U=availability (minutes answered)
+    #[arg(long = "order", value_name = "FIELDS")]
+    pub order: Option<String>,
+
+    /// Omit the report preamble and column header
+    #[arg(long = "no-header")]
+    pub no_header: bool,
+
+    /// With several targets, print only the combined summary table (--report)
+    #[arg(long = "summary-only", requires = "report")]
+    pub summary_only: bool,
+
+    /// Report progress on stderr during --json/--csv/--report runs (one JSON line per target each second)
+    #[arg(long = "progress", value_name = "FORMAT", value_parser = ["json"])]
+    pub progress: Option<String>,
+
+    /// Label the session for grouping downstream (e.g. site=fra1); repeatable,
+    /// carried into JSON, CSV, report and progress output
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+
+    /// Redact exports for public sharing: private, hostnames, hops=N, mask
+    /// (comma-separated; --redact alone = private,hostnames)
+    #[arg(long = "redact", value_name = "WHAT", num_args = 0..=1, require_equals = true,
+          default_missing_value = DEFAULT_REDACT)]
+    pub redact: Option<String>,
+
+    /// Trace at most N targets at a time (round-robin scheduling in batches of N)
+    #[arg(long = "parallel", value_name = "N", value_parser = clap::value_parser!(u16).range(1..),
+          conflicts_with = "batch")]
+    pub parallel: Option<u16>,
+
     /// Replay a saved session
     #[arg(long = "replay")]
     pub replay: Option<String>,
@@ -131,6 +354,22 @@ pub struct Args {
     #[arg(long = "theme", default_value = "default")]
     pub theme: String,
 
+    /// Show timestamps in local time instead of UTC (JSON exports stay UTC)
+    #[arg(long = "local-time")]
+    pub local_time: bool,
+
+    /// Number formatting locale for the TUI and text report (e.g. de, en_US, fr, auto = from LANG; default C)
+    #[arg(long = "locale", value_name = "LOCALE")]
+    pub locale: Option<String>,
+
+    /// Language of the TUI: en, de, or auto (from LC_MESSAGES/LANG); default en
+    #[arg(long = "lang", value_name = "LANG", env = "TTL_LANG")]
+    pub lang: Option<String>,
+
+    /// Show RTTs in µs, ms or s as fits instead of always ms
+    #[arg(long = "auto-units")]
+    pub auto_units: bool,
+
     /// Bind probes to specific network interface (e.g., eth0, wlan0)
     #[arg(long = "interface")]
     pub interface: Option<String>,
@@ -139,10 +378,22 @@ pub struct Args {
     #[arg(long = "recv-any", requires = "interface")]
     pub recv_any: bool,
 
+    /// ICMP Redirects sent to us: drop them, record an event, or also warn
+    /// that first-hop results may be affected
+    #[arg(long = "redirects", value_name = "POLICY", default_value = "log",
+          value_parser = ["ignore", "log", "warn"])]
+    pub redirects: String,
+
     /// DSCP value for QoS testing (0-63)
     #[arg(long = "dscp", value_parser = clap::value_parser!(u8).range(0..=63))]
     pub dscp: Option<u8>,
 
+    /// Mark probes ECN-capable (ECT(0), or =ect1) and show where along the
+    /// path the marking is cleared
+    #[arg(long = "ecn", value_name = "CODEPOINT", num_args = 0..=1, require_equals = true,
+          default_missing_value = "ect0", value_parser = EcnCodepoint::parse)]
+    pub ecn: Option<EcnCodepoint>,
+
     /// Probe packet size in bytes (36-1500 for IPv4, 56-1500 for IPv6)
     /// Includes IP + protocol headers. Smaller values are clamped to minimum.
     #[arg(long = "size", value_parser = clap::value_parser!(u16).range(36..=1500), conflicts_with = "pmtud")]
@@ -152,6 +403,11 @@ pub struct Args {
     #[arg(long = "pmtud")]
     pub pmtud: bool,
 
+    /// Sweep Don't Fragment probe sizes at every hop to show where along the
+    /// path large packets stop fitting (ICMP only)
+    #[arg(long = "df-sweep", conflicts_with_all = ["pmtud", "size"])]
+    pub df_sweep: bool,
+
     /// Maximum probes per second (0 = unlimited)
     #[arg(long = "rate", value_parser = clap::value_parser!(u32).range(0..=10000))]
     pub rate: Option<u32>,
@@ -160,15 +416,445 @@ pub struct Args {
     #[arg(long = "source-ip", value_name = "IP")]
     pub source_ip: Option<std::net::IpAddr>,
 
+    /// Trace from each of these local addresses at once and compare the
+    /// paths (e.g. two uplinks on a multihomed host); one session per source
+    #[arg(long = "sources", value_name = "IP,IP", value_delimiter = ',',
+          conflicts_with_all = ["source_ip", "best_family", "via", "replay"])]
+    pub sources: Vec<std::net::IpAddr>,
+
+    /// Multi-target scheduling: all at once, spread over the interval, or batches taking turns
+    #[arg(long = "schedule", value_name = "MODE", default_value = "parallel",
+          value_parser = ["parallel", "staggered", "round-robin"])]
+    pub schedule: String,
+
+    /// Targets probed per interval with --schedule round-robin
+    #[arg(long = "batch", value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub batch: Option<u16>,
+
+    /// Probes per second shared by all targets
+    #[arg(long = "global-rate", value_name = "PPS", value_parser = clap::value_parser!(u32).range(1..=10000))]
+    pub global_rate: Option<u32>,
+
+    /// Probes --global-rate allows back to back before spacing them out
+    #[arg(long = "global-burst", value_name = "PROBES", requires = "global_rate", value_parser = clap::value_parser!(u32).range(1..=10000))]
+    pub global_burst: Option<u32>,
+
+    /// Receive socket buffer size (e.g. 4M); raise if the diagnostics panel reports kernel drops
+    #[arg(long = "recv-buffer", value_name = "BYTES", value_parser = parse_byte_size)]
+    pub recv_buffer: Option<usize>,
+
+    /// Probe socket send buffer size (e.g. 256K)
+    #[arg(long = "send-buffer", value_name = "BYTES", value_parser = parse_byte_size)]
+    pub send_buffer: Option<usize>,
+
+    /// Time ttl's own drawing, lock waits and receiver backlog (shown in the
+    /// diagnostics panel; nothing is sent anywhere)
+    #[arg(long = "perf")]
+    pub perf: bool,
+
+    /// Keep the raw bytes of replies that matched no probe, shown in the
+    /// diagnostics panel; with =FILE also append each as a hex dump there
+    #[arg(long = "dump-unmatched", value_name = "FILE", num_args = 0..=1, require_equals = true)]
+    pub dump_unmatched: Option<Option<std::path::PathBuf>>,
+
+    /// Run probe engines on their own thread and runtime, at raised priority,
+    /// so redraws and lookups don't delay probes
+    #[arg(long = "probe-thread")]
+    pub probe_thread: bool,
+
+    /// Pin the probe and receiver threads to this CPU (Linux; implies --probe-thread)
+    #[arg(long = "probe-cpu", value_name = "CPU")]
+    pub probe_cpu: Option<usize>,
+
+    /// Rounds a new responder must answer in a row before a path change is logged
+    #[arg(long = "flap-hold", value_name = "ROUNDS", default_value_t = crate::state::DEFAULT_FLAP_HOLD,
+          value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub flap_hold: u32,
+
+    /// After a path change, count but don't log further changes at that hop for ROUNDS
+    #[arg(long = "flap-cooldown", value_name = "ROUNDS", default_value_t = 0)]
+    pub flap_cooldown: u64,
+
+    /// After a confirmed path change, archive and clear the statistics of the
+    /// changed hop and the hops after it
+    #[arg(long = "reset-on-path-change")]
+    pub reset_on_path_change: bool,
+
+    /// Gray out responders that haven't answered in the last ROUNDS rounds (0 = never)
+    #[arg(long = "stale-after", value_name = "ROUNDS", default_value_t = crate::state::DEFAULT_STALE_AFTER)]
+    pub stale_after: u64,
+
+    /// Record an alert when destination loss over the last 60 probes reaches PCT
+    #[arg(long = "alert-loss", value_name = "PCT")]
+    pub alert_loss: Option<f64>,
+
+    /// Desktop notifications for path changes, outages and alerts
+    #[arg(long = "notify")]
+    pub notify: bool,
+
+    /// Write path changes, outages and alerts to syslog or journald
+    #[arg(long = "event-log", value_name = "SINK", value_parser = ["syslog", "journald"])]
+    pub event_log: Option<String>,
+
+    /// Latency/loss objective for every target, e.g. "p95<80ms,loss<1%"
+    #[arg(long = "slo", value_name = "SPEC")]
+    pub slo: Option<String>,
+
+    /// Send a UDP stream at RATE bits/s (e.g. 20M, at most 1G) to
+    /// --load-target for 20s after a 10s idle baseline and grade the RTT
+    /// increase (bufferbloat test)
+    #[arg(long = "load", value_name = "RATE", value_parser = parse_bit_rate,
+          requires = "load_target")]
+    pub load: Option<u64>,
+
+    /// Host and UDP port that receives the --load stream; use one you run
+    /// (e.g. an iperf server), since the stream is real traffic
+    #[arg(long = "load-target", value_name = "HOST:PORT", value_parser = parse_load_target,
+          requires = "load")]
+    pub load_target: Option<std::net::SocketAddr>,
+
+    /// On destination down/up: ring the terminal bell and/or flash the TUI
+    /// status bar (comma-separated)
+    #[arg(long = "dest-alert", value_name = "ACTIONS", value_delimiter = ',',
+          value_parser = ["bell", "flash"])]
+    pub dest_alert: Vec<String>,
+
+    /// POST a JSON payload to URL when the destination goes down or comes back up
+    #[arg(long = "dest-webhook", value_name = "URL")]
+    pub dest_webhook: Option<String>,
+
+    /// Also serve a read-only view of the TUI to browsers at ADDR
+    /// (e.g. 127.0.0.1:8080), and path signature metrics at /metrics
+    #[arg(long = "serve-tui", value_name = "ADDR")]
+    pub serve_tui: Option<std::net::SocketAddr>,
+
+    /// Serve path signature metrics for Prometheus at http://ADDR/metrics
+    /// (e.g. 127.0.0.1:9464), without the browser view
+    #[arg(long = "metrics", value_name = "ADDR")]
+    pub metrics: Option<std::net::SocketAddr>,
+
+    /// Let --serve-tui and --metrics listen on a non-loopback address. Neither
+    /// has authentication: anyone who can reach the port can read them
+    #[arg(long = "allow-remote")]
+    pub allow_remote: bool,
+
+    /// Run the trace on a remote agent (host[:port], see `ttl agent`)
+    #[arg(long = "via", value_name = "AGENT")]
+    pub via: Option<String>,
+
+    /// Shared secret for the remote agent
+    #[arg(
+        long = "agent-token",
+        env = "TTL_AGENT_TOKEN",
+        hide_env_values = true,
+        requires = "via"
+    )]
+    pub agent_token: Option<String>,
+
     /// Generate shell completions and exit
     #[arg(long, value_name = "SHELL", value_parser = ["bash", "zsh", "fish", "powershell"])]
     pub completions: Option<String>,
+
+    /// Print version, enabled features, lookup providers and cache versions
+    /// as JSON and exit (for bug reports)
+    #[arg(long = "build-info")]
+    pub build_info: bool,
+
+    /// Time the response parser against a captured corpus and exit
+    #[arg(long = "bench-parse", value_name = "CORPUS", hide = true)]
+    pub bench_parse: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands that work on saved sessions instead of tracing
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Merge saved sessions toward the same target from several vantage points
+    Merge {
+        /// Saved JSON sessions (from --json or TUI export)
+        #[arg(required = true, num_args = 2.., value_name = "FILE")]
+        files: Vec<String>,
+
+        /// Output the merged path as JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Report trends over saved sessions: path stability, loss per hop per day, latency regressions
+    Analyze {
+        /// Saved sessions: --json files, or JSONL with one session per line
+        #[arg(required = true, value_name = "FILE")]
+        files: Vec<String>,
+
+        /// Target to analyze (name or address; default: the first session's)
+        #[arg(long = "target", value_name = "HOST")]
+        target: Option<String>,
+
+        /// Only sessions started within this long (e.g. 7d, 12h)
+        #[arg(long = "since", value_name = "TIME", value_parser = parse_duration_spec)]
+        since: Option<Duration>,
+
+        /// Output the analysis as JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Manage the lookup caches under ~/.cache/ttl
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Show the effective configuration or write a starter config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Run traces on behalf of remote clients (`ttl --via`)
+    Agent {
+        /// Address to listen on (default: 127.0.0.1:7447; other hosts need
+        /// --allow-remote)
+        #[arg(long = "listen", value_name = "ADDR")]
+        listen: Option<std::net::SocketAddr>,
+
+        /// Accept clients on a non-loopback --listen address. The channel is
+        /// not encrypted: the token and results cross the network in the clear
+        #[arg(long = "allow-remote", conflicts_with = "health")]
+        allow_remote: bool,
+
+        /// Print a running agent's health as JSON instead of listening
+        /// (host[:port]; exits non-zero if it doesn't answer)
+        #[arg(long = "health", value_name = "ADDR", conflicts_with = "listen")]
+        health: Option<String>,
+
+        /// Shared secret clients must present
+        #[arg(long = "token", env = "TTL_AGENT_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+}
+
+/// `ttl cache` actions
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheAction {
+    /// Delete all cached lookup data
+    Purge,
+}
+
+/// `ttl config` actions
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigAction {
+    /// Print the settings a trace would run with: defaults, then config.toml,
+    /// then any trace flags given after `--`
+    Show {
+        /// Print JSON instead of TOML
+        #[arg(long = "json")]
+        json: bool,
+
+        /// Trace flags to apply, e.g. `-- -p tcp -i 0.5`
+        #[arg(last = true, value_name = "FLAGS")]
+        flags: Vec<String>,
+    },
+    /// Write a commented starter config.toml
+    Init {
+        /// Replace an existing config file
+        #[arg(long = "force")]
+        force: bool,
+    },
+}
+
+/// Parse a `FIRST-LAST` port range
+fn parse_port_range(spec: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("Invalid --src-port-range '{}', expected FIRST-LAST", spec);
+    let (first, last) = spec.split_once('-').ok_or_else(invalid)?;
+    let first: u16 = first.trim().parse().map_err(|_| invalid())?;
+    let last: u16 = last.trim().parse().map_err(|_| invalid())?;
+    if first == 0 || first > last {
+        return Err(format!(
+            "--src-port-range '{}' must be FIRST-LAST with 1 <= FIRST <= LAST",
+            spec
+        ));
+    }
+    Ok((first, last))
+}
+
+/// Targets listed in a targets file: one per line, blank lines and
+/// `#` comments ignored
+fn parse_targets_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|target| !target.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse a wall-clock duration: plain seconds (`90`) or number/unit pairs
+/// with s, m, h or d (`90s`, `5m`, `1h30m`)
+fn parse_duration_spec(spec: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid duration '{}', expected e.g. 90s, 5m or 1h30m",
+            spec
+        )
+    };
+    let spec = spec.trim();
+    if let Ok(secs) = spec.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs)
+            .ok()
+            .filter(|d| !d.is_zero())
+            .ok_or_else(invalid);
+    }
+
+    let mut total = 0.0;
+    let mut number = String::new();
+    for c in spec.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1.0,
+            'm' => 60.0,
+            'h' => 3600.0,
+            'd' => 86400.0,
+            _ => return Err(invalid()),
+        };
+        let value: f64 = number.parse().map_err(|_| invalid())?;
+        total += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Duration::try_from_secs_f64(total)
+        .ok()
+        .filter(|d| !d.is_zero())
+        .ok_or_else(invalid)
+}
+
+/// Parse a DNS server address, port 53 unless given (`1.1.1.1`, `[2606:4700::1111]:53`)
+fn parse_dns_server(spec: &str) -> Result<std::net::SocketAddr, String> {
+    let spec = spec.trim();
+    if let Ok(ip) = spec.parse::<std::net::IpAddr>() {
+        return Ok((ip, 53).into());
+    }
+    spec.parse().map_err(|_| {
+        format!(
+            "Invalid DNS server '{}', expected IP or IP:PORT, e.g. 1.1.1.1 or [::1]:5353",
+            spec
+        )
+    })
+}
+
+/// Parse the `--load` receiver: HOST:PORT, resolved now (`iperf.example.net:5001`)
+fn parse_load_target(spec: &str) -> Result<std::net::SocketAddr, String> {
+    use std::net::ToSocketAddrs;
+    let spec = spec.trim();
+    let invalid = || format!("Invalid load target '{}', expected HOST:PORT", spec);
+    let (_, port) = spec.rsplit_once(':').ok_or_else(invalid)?;
+    if port.parse::<u16>().map_or(true, |p| p == 0) {
+        return Err(invalid());
+    }
+    spec.to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve load target '{}': {}", spec, e))?
+        .next()
+        .ok_or_else(|| format!("Load target '{}' has no addresses", spec))
+}
+
+/// Parse a byte count with an optional K/M suffix (powers of 1024)
+fn parse_byte_size(spec: &str) -> Result<usize, String> {
+    let invalid = || {
+        format!(
+            "Invalid size '{}', expected BYTES, e.g. 262144, 256K or 4M",
+            spec
+        )
+    };
+    let trimmed = spec.trim();
+    let (digits, multiplier) = match trimmed.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&trimmed[..i], 1024),
+        Some((i, 'm' | 'M')) => (&trimmed[..i], 1024 * 1024),
+        _ => (trimmed, 1),
+    };
+    let n: usize = digits.parse().map_err(|_| invalid())?;
+    n.checked_mul(multiplier)
+        .filter(|&bytes| (1024..=1 << 30).contains(&bytes))
+        .ok_or_else(|| format!("Size '{}' must be between 1K and 1024M", spec))
+}
+
+/// Parse a `--tag key=value` pair
+fn parse_tag(spec: &str) -> Result<(String, String), String> {
+    let (key, value) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid --tag '{}', expected KEY=VALUE", spec))?;
+    let key = key.trim();
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    if key.is_empty() || !key.chars().all(valid) {
+        return Err(format!(
+            "Invalid --tag key '{}': use letters, digits, '_', '-' or '.'",
+            key
+        ));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Parse a `--ttl-decay` factor
+fn parse_ttl_decay(spec: &str) -> Result<f64, String> {
+    let factor: f64 = spec
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid --ttl-decay '{}', expected e.g. 1.15", spec))?;
+    if !(factor > 1.0 && factor <= crate::config::MAX_TTL_DECAY) {
+        return Err(format!(
+            "--ttl-decay must be above 1.0 and at most {}",
+            crate::config::MAX_TTL_DECAY
+        ));
+    }
+    Ok(factor)
+}
+
+/// Parse a bit rate with an optional k/M/G suffix (powers of 1000)
+fn parse_bit_rate(spec: &str) -> Result<u64, String> {
+    let invalid = || {
+        format!(
+            "Invalid rate '{}', expected bits/s, e.g. 500k, 20M or 1G",
+            spec
+        )
+    };
+    let trimmed = spec.trim();
+    let (digits, multiplier) = match trimmed.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&trimmed[..i], 1e3),
+        Some((i, 'm' | 'M')) => (&trimmed[..i], 1e6),
+        Some((i, 'g' | 'G')) => (&trimmed[..i], 1e9),
+        _ => (trimmed, 1.0),
+    };
+    let n: f64 = digits.parse().map_err(|_| invalid())?;
+    let bps = n * multiplier;
+    if !(1e4..=1e9).contains(&bps) {
+        return Err(format!("Rate '{}' must be between 10k and 1G", spec));
+    }
+    Ok(bps as u64)
 }
 
 impl Args {
+    /// Parse trace flags given to `ttl config show`, where targets are
+    /// optional since none are traced
+    pub fn parse_flags(flags: &[String]) -> Result<Self, clap::Error> {
+        let command = Self::command().mut_arg("targets", |_| {
+            Arg::new("targets")
+                .num_args(0..)
+                .action(clap::ArgAction::Append)
+        });
+        let matches = command
+            .try_get_matches_from(std::iter::once("ttl").chain(flags.iter().map(|f| f.as_str())))?;
+        Self::from_arg_matches(&matches)
+    }
+
     /// Get probe interval as Duration
     pub fn interval_duration(&self) -> Duration {
-        Duration::from_secs_f64(self.interval)
+        Duration::from_secs_f64(self.interval_secs())
+    }
+
+    /// Probe interval in seconds, defaulting to one second
+    pub fn interval_secs(&self) -> f64 {
+        self.interval.unwrap_or(DEFAULT_INTERVAL_SECS)
     }
 
     /// Get timeout as Duration
@@ -176,15 +862,282 @@ impl Args {
         Duration::from_secs_f64(self.timeout)
     }
 
+    /// First and last port from --src-port-range, if given and valid
+    pub fn src_port_range(&self) -> Option<(u16, u16)> {
+        self.src_port_range
+            .as_deref()
+            .and_then(|spec| parse_port_range(spec).ok())
+    }
+
+    /// Warnings about a --src-port-range that works but may misbehave
+    pub fn src_port_warnings(&self) -> Vec<String> {
+        let Some((first, last)) = self.src_port_range() else {
+            return Vec::new();
+        };
+        let mut warnings = Vec::new();
+        if first < 1024 {
+            warnings.push(format!(
+                "source ports below 1024 ({}-{}) need root or CAP_NET_BIND_SERVICE for UDP probes",
+                first, last
+            ));
+        }
+        let (eph_first, eph_last) = crate::probe::ephemeral_port_range();
+        if first <= eph_last && last >= eph_first {
+            warnings.push(format!(
+                "source ports {}-{} overlap the ephemeral range {}-{}; other sockets may \
+                 already hold some of them",
+                first, last, eph_first, eph_last
+            ));
+        }
+        warnings
+    }
+
+    /// Append the targets listed in --targets-file (`-` reads stdin)
+    pub fn load_targets_file(&mut self) -> Result<(), String> {
+        let Some(ref path) = self.targets_file else {
+            return Ok(());
+        };
+        let text = if path.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())
+        } else {
+            std::fs::read_to_string(path)
+        }
+        .map_err(|e| format!("Cannot read --targets-file {}: {}", path.display(), e))?;
+
+        let targets = parse_targets_list(&text);
+        if targets.is_empty() {
+            return Err(format!(
+                "--targets-file {} lists no targets",
+                path.display()
+            ));
+        }
+        self.targets.extend(targets);
+        Ok(())
+    }
+
     /// Check if running in batch mode (non-interactive)
     pub fn is_batch_mode(&self) -> bool {
         self.json || self.csv || self.report
     }
 
+    /// What --redact removes from exports (specs were checked by validate)
+    pub fn redaction(&self) -> Option<Redaction> {
+        self.redact
+            .as_deref()
+            .and_then(|spec| Redaction::parse(spec).ok())
+    }
+
+    /// Build text report layout from --report-wide/--order/--no-header
+    pub fn report_options(&self) -> ReportOptions {
+        let mut opts = ReportOptions {
+            wide: self.report_wide,
+            header: !self.no_header,
+            ..Default::default()
+        };
+        if let Some(cols) = self
+            .order
+            .as_deref()
+            .and_then(|o| ReportColumn::parse_order(o).ok())
+        {
+            opts.columns = cols;
+        }
+        opts
+    }
+
     /// Validate arguments
     pub fn validate(&self) -> Result<(), String> {
-        if self.is_batch_mode() && self.count == 0 {
-            return Err("Batch output modes (--json, --csv, --report) require -c to be set".into());
+        if self.is_batch_mode() && self.count == 0 && self.duration.is_none() {
+            return Err(
+                "Batch output modes (--json, --csv, --report) require -c or --duration to be set"
+                    .into(),
+            );
+        }
+
+        if self.progress.is_some() && !self.is_batch_mode() {
+            return Err("--progress requires --json, --csv or --report".into());
+        }
+
+        if let Some(ref spec) = self.redact {
+            Redaction::parse(spec).map_err(|e| format!("--redact: {}", e))?;
+        }
+
+        if let Some(ref order) = self.order {
+            ReportColumn::parse_order(order)?;
+        }
+
+        let mut lookups = LookupLimits::default();
+        if let Some(ref spec) = self.lookup_concurrency {
+            lookups.apply_concurrency(spec)?;
+        }
+        if let Some(ref spec) = self.lookup_interval {
+            lookups.apply_interval(spec)?;
+        }
+
+        if self.batch.is_some() && self.schedule != "round-robin" {
+            return Err("--batch requires --schedule round-robin".into());
+        }
+
+        if self.parallel.is_some() && self.schedule == "staggered" {
+            return Err("--parallel runs targets round-robin; drop --schedule staggered".into());
+        }
+
+        if let Some(pct) = self.alert_loss
+            && !(pct > 0.0 && pct <= 100.0)
+        {
+            return Err("--alert-loss must be between 0 and 100".into());
+        }
+
+        if let Some(ref spec) = self.slo {
+            Slo::parse(spec).map_err(|e| format!("--slo: {}", e))?;
+        }
+
+        if let Some(ref locale) = self.locale {
+            NumberFormat::for_locale(locale).map_err(|e| format!("--locale: {}", e))?;
+        }
+        if let Some(ref lang) = self.lang {
+            crate::state::Lang::for_locale(lang).map_err(|e| format!("--lang: {}", e))?;
+        }
+
+        for spec in &self.alias {
+            ResponderAlias::parse(spec).map_err(|e| format!("--alias: {}", e))?;
+        }
+        crate::state::ResponderLabels::parse(self.label.iter().map(String::as_str))
+            .map_err(|e| format!("--label: {}", e))?;
+        // config.toml's label_mode bypasses clap's value check
+        if let Some(ref mode) = self.label_mode
+            && !matches!(mode.as_str(), "alongside" | "replace")
+        {
+            return Err(format!("--label-mode: unknown mode '{}'", mode));
+        }
+        if let Some(ref style) = self.host_trim
+            && !matches!(style.as_str(), "smart" | "end" | "middle")
+        {
+            return Err(format!("--host-trim: unknown style '{}'", style));
+        }
+        for spec in &self.plugin {
+            crate::config::Plugin::parse(spec).map_err(|e| format!("--plugin: {}", e))?;
+        }
+
+        if self.notify && !cfg!(feature = "desktop-notify") {
+            return Err("--notify needs ttl built with the 'desktop-notify' feature".into());
+        }
+
+        if self.offline {
+            if let Some(target) = self
+                .targets
+                .iter()
+                .find(|t| t.parse::<std::net::IpAddr>().is_err())
+            {
+                return Err(format!(
+                    "--offline needs IP address targets ('{}' needs a DNS lookup)",
+                    target
+                ));
+            }
+            let online = [
+                (self.prefix_watch, "--prefix-watch"),
+                (self.dest_webhook.is_some(), "--dest-webhook"),
+                (self.enrich_url.is_some(), "--enrich-url"),
+                (self.dns_server.is_some(), "--dns-server"),
+                (self.via.is_some(), "--via"),
+            ];
+            if let Some((_, flag)) = online.iter().find(|(set, _)| *set) {
+                return Err(format!("{} needs network access, not --offline", flag));
+            }
+        }
+
+        if let Some(ref url) = self.dest_webhook {
+            match reqwest::Url::parse(url) {
+                Ok(u) if matches!(u.scheme(), "http" | "https") => {}
+                _ => return Err(format!("--dest-webhook: '{}' is not an http(s) URL", url)),
+            }
+        }
+
+        if let Some(ref url) = self.enrich_url {
+            match reqwest::Url::parse(url) {
+                Ok(u) if matches!(u.scheme(), "http" | "https") => {}
+                _ => return Err(format!("--enrich-url: '{}' is not an http(s) URL", url)),
+            }
+        }
+
+        if self.serve_tui.is_some() && self.replay.is_some() {
+            return Err("--serve-tui cannot be combined with --replay".into());
+        }
+        if self.metrics.is_some() && self.replay.is_some() {
+            return Err("--metrics cannot be combined with --replay".into());
+        }
+        for (option, addr) in [("--serve-tui", self.serve_tui), ("--metrics", self.metrics)] {
+            if let Some(addr) = addr
+                && !addr.ip().is_loopback()
+                && !self.allow_remote
+            {
+                return Err(format!(
+                    "{} {} would show the trace to anyone who can reach it, without \
+                     authentication; use a loopback address (and an SSH tunnel) or add \
+                     --allow-remote",
+                    option, addr
+                ));
+            }
+        }
+        if self.allow_remote && self.serve_tui.is_none() && self.metrics.is_none() {
+            return Err("--allow-remote only applies to --serve-tui and --metrics".into());
+        }
+
+        if self.via.is_some() {
+            if self.notify {
+                return Err("--via does not support --notify yet".into());
+            }
+            if !self.dest_alert.is_empty() || self.dest_webhook.is_some() {
+                return Err("--via does not support --dest-alert or --dest-webhook yet".into());
+            }
+            if self.agent_token.is_none() {
+                return Err("--via requires --agent-token (or TTL_AGENT_TOKEN)".into());
+            }
+            if self.no_tui && !self.is_batch_mode() {
+                return Err("--via does not support --no-tui streaming output".into());
+            }
+            if self.status_line {
+                return Err("--via does not support --status-line".into());
+            }
+            if self.replay.is_some() {
+                return Err("--via cannot be combined with --replay".into());
+            }
+            if self.serve_tui.is_some() {
+                return Err("--via does not support --serve-tui yet".into());
+            }
+            if self.metrics.is_some() {
+                return Err("--via does not support --metrics yet".into());
+            }
+            if !self.plugin.is_empty() {
+                return Err("--via does not support --plugin yet".into());
+            }
+            if self.enrich_url.is_some() {
+                return Err("--via does not support --enrich-url yet".into());
+            }
+            if self.load.is_some() {
+                return Err("--via does not support --load".into());
+            }
+            if self.pick_address {
+                return Err("--via does not support --pick-address; use --address-index".into());
+            }
+        }
+
+        if !self.sources.is_empty() {
+            if self.sources.len() < 2 {
+                return Err(
+                    "--sources needs at least two addresses; use --source-ip for one".into(),
+                );
+            }
+            if let Some((i, ip)) = self
+                .sources
+                .iter()
+                .enumerate()
+                .find(|(i, ip)| self.sources[..*i].contains(ip))
+            {
+                return Err(format!("--sources lists {} twice (entry {})", ip, i + 1));
+            }
+            if self.targets.len() != 1 {
+                return Err("--sources traces a single target".into());
+            }
         }
 
         if self.ipv4 && self.ipv6 {
@@ -199,7 +1152,11 @@ impl Args {
             ));
         }
 
-        if self.interval <= 0.0 {
+        if self.df_sweep && matches!(protocol.as_str(), "udp" | "tcp") {
+            return Err("--df-sweep sends ICMP probes; use -p icmp or auto".into());
+        }
+
+        if self.interval_secs() <= 0.0 {
             return Err("Interval must be positive".into());
         }
 
@@ -217,6 +1174,13 @@ impl Args {
             return Err(format!("Max TTL cannot exceed {}", MAX_SAFE_TTL));
         }
 
+        if self.first_ttl > self.max_ttl {
+            return Err(format!(
+                "First TTL ({}) cannot exceed max TTL ({})",
+                self.first_ttl, self.max_ttl
+            ));
+        }
+
         // Validate flows count
         if self.flows == 0 {
             return Err("Flows must be at least 1".into());
@@ -229,10 +1193,21 @@ impl Args {
             ));
         }
 
+        if let Some(ref spec) = self.src_port_range {
+            let (first, last) = parse_port_range(spec)?;
+            let ports = (last - first) as u32 + 1;
+            if ports < self.flows as u32 {
+                return Err(format!(
+                    "--src-port-range {} has {} port(s), fewer than --flows {}",
+                    spec, ports, self.flows
+                ));
+            }
+        }
+
         // Validate src_port + (flows - 1) doesn't overflow u16
         // Ports used are src_port, src_port+1, ..., src_port+(flows-1)
         let max_port = self.src_port as u32 + (self.flows as u32 - 1);
-        if max_port > u16::MAX as u32 {
+        if self.src_port_range.is_none() && max_port > u16::MAX as u32 {
             return Err(format!(
                 "src_port ({}) + flows ({}) would use port {} (max 65535)",
                 self.src_port, self.flows, max_port
@@ -242,12 +1217,13 @@ impl Args {
         // Validate timeout vs interval to prevent probe sequence wrap
         // ProbeId.seq is u8 (0-255), so sequence wraps every 256 intervals
         // If timeout > 256 * interval, old probes may still be pending when seq wraps
-        if self.timeout > 256.0 * self.interval {
+        let interval = self.interval_secs();
+        if self.timeout > 256.0 * interval {
             return Err(format!(
                 "Timeout ({:.1}s) cannot exceed 256 × interval ({:.1}s = {:.1}s) to prevent sequence wrap",
                 self.timeout,
-                self.interval,
-                256.0 * self.interval
+                interval,
+                256.0 * interval
             ));
         }
 
@@ -273,41 +1249,650 @@ mod tests {
     fn make_args(overrides: impl FnOnce(&mut Args)) -> Args {
         let mut args = Args {
             targets: vec!["8.8.8.8".to_string()],
+            targets_file: None,
             count: 0,
-            interval: 1.0,
+            duration: None,
+            stats_window: None,
+            interval: None,
             max_ttl: 30,
+            first_ttl: 1,
+            adaptive: false,
+            gap_limit: None,
+            ttl_decay: None,
             protocol: "auto".to_string(),
             port: None,
             port_fixed: false,
             flows: 1,
             src_port: 50000,
+            src_port_range: None,
             timeout: 3.0,
             ipv4: false,
             ipv6: false,
+            dns_server: None,
+            address_index: None,
+            pick_address: false,
+            best_family: false,
+            yes: false,
             no_dns: false,
             no_asn: false,
             no_geo: false,
             no_ix: false,
+            prefix_watch: false,
+            offline: false,
+            allow_host: vec![],
+            no_disk_cache: false,
+            origin: None,
+            alias: Vec::new(),
+            label: Vec::new(),
+            label_mode: None,
+            host_trim: None,
+            plugin: Vec::new(),
+            enrich_url: None,
+            enrich_token: None,
+            merge_by_hostname: false,
+            lookup_concurrency: None,
+            lookup_interval: None,
             geoip_db: None,
             no_tui: false,
+            status_line: false,
             json: false,
             csv: false,
             report: false,
+            report_wide: false,
+            order: None,
+            no_header: false,
+            summary_only: false,
+            progress: None,
+            redact: None,
+            parallel: None,
             replay: None,
             theme: "default".to_string(),
+            local_time: false,
+            locale: None,
+            lang: None,
+            auto_units: false,
             interface: None,
+            tags: vec![],
             recv_any: false,
+            redirects: "log".to_string(),
+            udp_id: "payload".to_string(),
             dscp: None,
+            ecn: None,
             size: None,
             pmtud: false,
+            df_sweep: false,
             rate: None,
             source_ip: None,
+            sources: Vec::new(),
+            schedule: "parallel".to_string(),
+            batch: None,
+            global_rate: None,
+            global_burst: None,
+            recv_buffer: None,
+            send_buffer: None,
+            perf: false,
+            dump_unmatched: None,
+            probe_thread: false,
+            probe_cpu: None,
+            flap_hold: crate::state::DEFAULT_FLAP_HOLD,
+            flap_cooldown: 0,
+            reset_on_path_change: false,
+            stale_after: crate::state::DEFAULT_STALE_AFTER,
+            alert_loss: None,
+            notify: false,
+            event_log: None,
+            slo: None,
+            load: None,
+            load_target: None,
+            dest_alert: vec![],
+            dest_webhook: None,
+            serve_tui: None,
+            metrics: None,
+            allow_remote: false,
+            via: None,
+            agent_token: None,
             completions: None,
+            build_info: false,
+            bench_parse: None,
+            command: None,
         };
         overrides(&mut args);
         args
     }
 
+    #[test]
+    fn test_merge_subcommand_parses() {
+        let args = Args::try_parse_from(["ttl", "merge", "a.json", "b.json"]).unwrap();
+        assert!(args.targets.is_empty());
+        match args.command {
+            Some(Command::Merge { files, json }) => {
+                assert_eq!(files, vec!["a.json", "b.json"]);
+                assert!(!json);
+            }
+            _ => panic!("expected merge subcommand"),
+        }
+
+        // Plain targets still work
+        let args = Args::try_parse_from(["ttl", "8.8.8.8"]).unwrap();
+        assert!(args.command.is_none());
+
+        // Merging needs at least two files
+        assert!(Args::try_parse_from(["ttl", "merge", "a.json"]).is_err());
+
+        let args =
+            Args::try_parse_from(["ttl", "analyze", "--since", "7d", "history.jsonl"]).unwrap();
+        match args.command {
+            Some(Command::Analyze {
+                files,
+                target,
+                since,
+                ..
+            }) => {
+                assert_eq!(files, vec!["history.jsonl"]);
+                assert!(target.is_none());
+                assert_eq!(since, Some(Duration::from_secs(7 * 86400)));
+            }
+            _ => panic!("expected analyze subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_config_subcommand_parses() {
+        let args =
+            Args::try_parse_from(["ttl", "config", "show", "--json", "--", "-p", "tcp"]).unwrap();
+        let Some(Command::Config {
+            action: ConfigAction::Show { json, flags },
+        }) = args.command
+        else {
+            panic!("expected config show");
+        };
+        assert!(json);
+
+        // Trace flags parse without a target
+        let args = Args::parse_flags(&flags).unwrap();
+        assert_eq!(args.protocol, "tcp");
+        assert!(args.targets.is_empty());
+        assert!(Args::parse_flags(&["--bogus".to_string()]).is_err());
+
+        let args = Args::try_parse_from(["ttl", "config", "init", "--force"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Config {
+                action: ConfigAction::Init { force: true }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_via_requires_token() {
+        let args = make_args(|a| a.via = Some("probe1".to_string()));
+        assert!(args.validate().unwrap_err().contains("--agent-token"));
+
+        let args = make_args(|a| {
+            a.via = Some("probe1".to_string());
+            a.agent_token = Some("secret".to_string());
+        });
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_status_line_conflicts_with_batch_output() {
+        assert!(Args::try_parse_from(["ttl", "--status-line", "8.8.8.8"]).is_ok());
+        assert!(Args::try_parse_from(["ttl", "--status-line", "--report", "8.8.8.8"]).is_err());
+        assert!(Args::try_parse_from(["ttl", "--status-line", "--no-tui", "8.8.8.8"]).is_err());
+    }
+
+    #[test]
+    fn test_lookup_limits_from_args() {
+        let args = make_args(|a| {
+            a.lookup_concurrency = Some("2".to_string());
+            a.lookup_interval = Some("dns=1.5, ix=10".to_string());
+        });
+        assert!(args.validate().is_ok());
+        let lookups = crate::config::Config::from(&args).lookups;
+        assert_eq!(lookups.geo.concurrency, 2);
+        assert_eq!(lookups.dns.interval, Duration::from_millis(1500));
+        assert_eq!(lookups.ix.interval, Duration::from_secs(10));
+        assert_eq!(lookups.asn.interval, Duration::from_millis(500));
+
+        let args = make_args(|a| a.lookup_concurrency = Some("whois=3".to_string()));
+        assert!(args.validate().unwrap_err().contains("'whois'"));
+        let args = make_args(|a| a.lookup_concurrency = Some("0".to_string()));
+        assert!(args.validate().is_err());
+        let args = make_args(|a| a.lookup_interval = Some("dns".to_string()));
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_probe_cpu_implies_probe_thread() {
+        let config = crate::config::Config::from(&Args::try_parse_from(["ttl", "a"]).unwrap());
+        assert!(!config.probe_thread);
+        let config = crate::config::Config::from(
+            &Args::try_parse_from(["ttl", "--probe-cpu", "2", "a"]).unwrap(),
+        );
+        assert!(config.probe_thread);
+        assert_eq!(config.probe_cpu, Some(2));
+    }
+
+    #[test]
+    fn test_schedule_from_args() {
+        let args = Args::try_parse_from([
+            "ttl",
+            "--schedule",
+            "round-robin",
+            "--batch",
+            "5",
+            "--global-rate",
+            "200",
+            "--global-burst",
+            "20",
+            "a",
+            "b",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.schedule, crate::config::Schedule::RoundRobin);
+        assert_eq!(config.schedule_batch, Some(5));
+        assert_eq!(config.global_rate, Some(200));
+        assert_eq!(config.global_burst, Some(20));
+        assert!(Args::try_parse_from(["ttl", "--global-burst", "5", "a"]).is_err());
+
+        let args = make_args(|a| a.batch = Some(5));
+        assert!(args.validate().unwrap_err().contains("round-robin"));
+        assert!(Args::try_parse_from(["ttl", "--schedule", "random", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_report_order_validated() {
+        let args = make_args(|a| a.order = Some("LSZ".to_string()));
+        assert!(args.validate().unwrap_err().contains("'Z'"));
+
+        let args = make_args(|a| {
+            a.order = Some("LA".to_string());
+            a.no_header = true;
+        });
+        assert!(args.validate().is_ok());
+        let opts = args.report_options();
+        assert_eq!(opts.columns.len(), 2);
+        assert!(!opts.header);
+    }
+
+    #[test]
+    fn test_offline_validated() {
+        let args = make_args(|a| {
+            a.offline = true;
+            a.targets = vec!["192.0.2.1".to_string(), "example.com".to_string()];
+        });
+        assert!(args.validate().unwrap_err().contains("'example.com'"));
+
+        let args = make_args(|a| {
+            a.offline = true;
+            a.prefix_watch = true;
+        });
+        assert!(args.validate().unwrap_err().contains("--prefix-watch"));
+
+        let args = make_args(|a| a.offline = true);
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert!(!config.dns_enabled && !config.asn_enabled);
+        assert!(config.net_policy().offline);
+    }
+
+    #[test]
+    fn test_plugin_validated() {
+        let args = make_args(|a| a.plugin = vec!["startup=./x".to_string()]);
+        assert!(args.validate().is_err());
+        let args = make_args(|a| a.plugin = vec!["export=".to_string()]);
+        assert!(args.validate().is_err());
+
+        let args = make_args(|a| {
+            a.plugin = vec![
+                "responder=/opt/cmdb-lookup --site fra".to_string(),
+                "export=./to-influx".to_string(),
+            ]
+        });
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.plugins.len(), 2);
+        assert_eq!(config.plugins[0].hook, crate::config::PluginHook::Responder);
+        assert_eq!(config.plugins[0].command, "/opt/cmdb-lookup --site fra");
+    }
+
+    #[test]
+    fn test_serve_tui_validated() {
+        let args = make_args(|a| {
+            a.serve_tui = Some("127.0.0.1:8080".parse().unwrap());
+            a.replay = Some("trace.json".to_string());
+        });
+        assert!(args.validate().is_err());
+        let args = make_args(|a| a.serve_tui = Some("127.0.0.1:8080".parse().unwrap()));
+        assert!(args.validate().is_ok());
+
+        // Other hosts only with --allow-remote
+        let args = make_args(|a| a.serve_tui = Some("0.0.0.0:8080".parse().unwrap()));
+        assert!(args.validate().unwrap_err().contains("--allow-remote"));
+        let args = make_args(|a| {
+            a.serve_tui = Some("0.0.0.0:8080".parse().unwrap());
+            a.allow_remote = true;
+        });
+        assert!(args.validate().is_ok());
+        let args = make_args(|a| a.allow_remote = true);
+        assert!(args.validate().is_err());
+
+        // --metrics follows the same rule
+        let args = make_args(|a| a.metrics = Some("127.0.0.1:9464".parse().unwrap()));
+        assert!(args.validate().is_ok());
+        let args = make_args(|a| a.metrics = Some("[::]:9464".parse().unwrap()));
+        assert!(args.validate().unwrap_err().starts_with("--metrics"));
+        let args = make_args(|a| {
+            a.metrics = Some("[::]:9464".parse().unwrap());
+            a.allow_remote = true;
+        });
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_dest_webhook_validated() {
+        let args = make_args(|a| a.dest_webhook = Some("ftp://example.com/hook".to_string()));
+        assert!(args.validate().unwrap_err().contains("--dest-webhook"));
+
+        let args = make_args(|a| {
+            a.dest_alert = vec!["bell".to_string(), "flash".to_string()];
+            a.dest_webhook = Some("https://example.com/hook".to_string());
+        });
+        assert!(args.validate().is_ok());
+        let alert = crate::config::Config::from(&args).dest_alert;
+        assert!(alert.bell && alert.flash);
+        assert_eq!(alert.webhook.as_deref(), Some("https://example.com/hook"));
+    }
+
+    #[test]
+    fn test_df_sweep_needs_icmp() {
+        let args = make_args(|a| {
+            a.df_sweep = true;
+            a.protocol = "udp".to_string();
+        });
+        assert!(args.validate().unwrap_err().contains("--df-sweep"));
+        let args = make_args(|a| a.df_sweep = true);
+        assert!(args.validate().is_ok());
+        assert!(crate::config::Config::from(&args).df_sweep);
+    }
+
+    #[test]
+    fn test_enrich_url_validated() {
+        let args = make_args(|a| a.enrich_url = Some("cmdb.example.com".to_string()));
+        assert!(args.validate().unwrap_err().contains("--enrich-url"));
+
+        let args = make_args(|a| {
+            a.enrich_url = Some("https://cmdb.example.com/ttl".to_string());
+            a.offline = true;
+        });
+        assert!(args.validate().unwrap_err().contains("--offline"));
+
+        let args = make_args(|a| {
+            a.enrich_url = Some("https://cmdb.example.com/ttl".to_string());
+            a.enrich_token = Some("s3cret".to_string());
+        });
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert_eq!(
+            config.enrich_url.as_deref(),
+            Some("https://cmdb.example.com/ttl")
+        );
+        assert_eq!(config.enrich_token.as_deref(), Some("s3cret"));
+        // The token never ends up in saved sessions
+        assert!(!serde_json::to_string(&config).unwrap().contains("s3cret"));
+    }
+
+    #[test]
+    fn test_src_port_range() {
+        let args = make_args(|a| {
+            a.flows = 4;
+            a.src_port_range = Some("33434-33533".to_string());
+        });
+        assert!(args.validate().is_ok());
+        let ports = crate::config::Config::from(&args).flow_ports();
+        let assigned: Vec<u16> = (0..4).map(|f| ports.port(f)).collect();
+        assert_eq!(assigned, vec![33434, 33459, 33484, 33509]);
+        assert_eq!(ports.flow(33459), Some(1));
+        assert_eq!(ports.flow(33460), None);
+        assert_eq!(ports.flow(33534), None);
+
+        let args = make_args(|a| {
+            a.flows = 4;
+            a.src_port_range = Some("40000-40002".to_string());
+        });
+        assert!(args.validate().unwrap_err().contains("fewer than --flows"));
+        for bad in ["40000", "40010-40000", "0-10", "a-b"] {
+            let args = make_args(|a| a.src_port_range = Some(bad.to_string()));
+            assert!(args.validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_targets_list() {
+        let text =
+            "# nightly list\n8.8.8.8\n\n  example.com   # web\n\t1.1.1.1\n#disabled.example\n";
+        assert_eq!(
+            parse_targets_list(text),
+            vec!["8.8.8.8", "example.com", "1.1.1.1"]
+        );
+        assert!(parse_targets_list("# empty\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_parallel_sets_round_robin() {
+        let args = make_args(|a| {
+            a.count = 5;
+            a.report = true;
+            a.parallel = Some(8);
+        });
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.schedule, crate::config::Schedule::RoundRobin);
+        assert_eq!(config.schedule_batch, Some(8));
+
+        let args = make_args(|a| {
+            a.parallel = Some(8);
+            a.schedule = "staggered".to_string();
+        });
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_spec() {
+        assert_eq!(parse_duration_spec("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration_spec("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration_spec("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration_spec("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration_spec("1d"), Ok(Duration::from_secs(86400)));
+        for bad in ["", "0", "0s", "5x", "m", "5m3", "-1", "1h 30m"] {
+            assert!(parse_duration_spec(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_first_ttl() {
+        let args = Args::try_parse_from(["ttl", "-f", "5", "-m", "12", "8.8.8.8"]).unwrap();
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert_eq!((config.first_ttl, config.max_ttl), (5, 12));
+        // --ttl-decay counts from the first TTL probed
+        let args =
+            Args::try_parse_from(["ttl", "-f", "5", "--ttl-decay", "1.5", "8.8.8.8"]).unwrap();
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.ttl_period(5), 1);
+        assert_eq!(config.ttl_period(6), 2);
+
+        let args = Args::try_parse_from(["ttl", "-f", "20", "-m", "10", "8.8.8.8"]).unwrap();
+        assert!(args.validate().is_err());
+        assert!(Args::try_parse_from(["ttl", "-f", "0", "8.8.8.8"]).is_err());
+    }
+
+    #[test]
+    fn test_ttl_decay() {
+        let args = Args::try_parse_from(["ttl", "--ttl-decay", "1.5", "8.8.8.8"]).unwrap();
+        let config = crate::config::Config::from(&args);
+        let periods: Vec<u64> = [1, 2, 3, 5, 30].map(|t| config.ttl_period(t)).to_vec();
+        assert_eq!(periods, vec![1, 2, 2, 5, crate::config::MAX_TTL_PERIOD]);
+        assert_eq!(
+            crate::config::Config::from(&make_args(|_| {})).ttl_period(30),
+            1
+        );
+
+        for bad in ["1.0", "0.5", "5", "fast"] {
+            assert!(Args::try_parse_from(["ttl", "--ttl-decay", bad, "8.8.8.8"]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_tags() {
+        let args = Args::try_parse_from([
+            "ttl",
+            "--tag",
+            "site=fra1",
+            "--tag",
+            "circuit=ISP-A",
+            "--tag",
+            "site=ams2",
+            "8.8.8.8",
+        ])
+        .unwrap();
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.tags_label(","), "circuit=ISP-A,site=ams2");
+
+        for bad in ["site", "=fra1", "si te=fra1"] {
+            assert!(Args::try_parse_from(["ttl", "--tag", bad, "8.8.8.8"]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_progress_requires_batch_mode() {
+        let args = make_args(|a| a.progress = Some("json".to_string()));
+        assert!(args.validate().unwrap_err().contains("--progress"));
+        let args = make_args(|a| {
+            a.progress = Some("json".to_string());
+            a.json = true;
+            a.count = 10;
+        });
+        assert!(args.validate().is_ok());
+        assert!(Args::try_parse_from(["ttl", "--progress", "text", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_sources_validated() {
+        let a: std::net::IpAddr = "192.0.2.10".parse().unwrap();
+        let b: std::net::IpAddr = "198.51.100.7".parse().unwrap();
+        let args = make_args(|x| x.sources = vec![a, b]);
+        assert!(args.validate().is_ok());
+        let args = make_args(|x| x.sources = vec![a]);
+        assert!(args.validate().unwrap_err().contains("at least two"));
+        let args = make_args(|x| x.sources = vec![a, b, a]);
+        assert!(args.validate().unwrap_err().contains("twice"));
+        let args = make_args(|x| {
+            x.sources = vec![a, b];
+            x.targets.push("example.org".to_string());
+        });
+        assert!(args.validate().unwrap_err().contains("single target"));
+
+        let parsed =
+            Args::try_parse_from(["ttl", "--sources", "192.0.2.10,198.51.100.7", "a"]).unwrap();
+        assert_eq!(parsed.sources, vec![a, b]);
+        assert!(
+            Args::try_parse_from([
+                "ttl",
+                "--sources",
+                "192.0.2.10,198.51.100.7",
+                "--source-ip",
+                "192.0.2.10",
+                "a"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_duration_allows_batch_mode() {
+        let args = make_args(|a| a.report = true);
+        assert!(args.validate().unwrap_err().contains("--duration"));
+        let args = make_args(|a| {
+            a.report = true;
+            a.duration = Some(Duration::from_secs(300));
+        });
+        assert!(args.validate().is_ok());
+        assert_eq!(
+            crate::config::Config::from(&args).duration,
+            Some(Duration::from_secs(300))
+        );
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("262144"), Ok(262144));
+        assert_eq!(parse_byte_size("256K"), Ok(256 * 1024));
+        assert_eq!(parse_byte_size("4m"), Ok(4 * 1024 * 1024));
+        for bad in ["", "K", "4G", "-1", "512", "2048M"] {
+            assert!(parse_byte_size(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_dns_server() {
+        assert_eq!(
+            parse_dns_server("1.1.1.1"),
+            Ok("1.1.1.1:53".parse().unwrap())
+        );
+        assert_eq!(
+            parse_dns_server("[2606:4700::1111]:5353"),
+            Ok("[2606:4700::1111]:5353".parse().unwrap())
+        );
+        assert_eq!(parse_dns_server("::1"), Ok("[::1]:53".parse().unwrap()));
+        assert!(parse_dns_server("dns.google").is_err());
+        assert!(parse_dns_server("1.1.1.1:x").is_err());
+    }
+
+    #[test]
+    fn test_load_needs_target() {
+        assert_eq!(
+            parse_load_target("192.0.2.9:5001"),
+            Ok("192.0.2.9:5001".parse().unwrap())
+        );
+        assert_eq!(
+            parse_load_target("[2001:db8::9]:5001"),
+            Ok("[2001:db8::9]:5001".parse().unwrap())
+        );
+        for bad in ["192.0.2.9", "192.0.2.9:0", "host:x"] {
+            assert!(parse_load_target(bad).is_err(), "{}", bad);
+        }
+        assert!(Args::try_parse_from(["ttl", "--load", "20M", "8.8.8.8"]).is_err());
+        let args = Args::try_parse_from([
+            "ttl",
+            "--load",
+            "20M",
+            "--load-target",
+            "192.0.2.9:5001",
+            "8.8.8.8",
+        ])
+        .unwrap();
+        let load = crate::config::Config::from(&args).load.unwrap();
+        assert_eq!(
+            (load.target, load.port),
+            ("192.0.2.9".parse().unwrap(), 5001)
+        );
+    }
+
+    #[test]
+    fn test_parse_bit_rate() {
+        assert_eq!(parse_bit_rate("20M"), Ok(20_000_000));
+        assert_eq!(parse_bit_rate("500k"), Ok(500_000));
+        assert_eq!(parse_bit_rate("1G"), Ok(1_000_000_000));
+        assert_eq!(parse_bit_rate("100000"), Ok(100_000));
+        for bad in ["", "M", "fast", "1k", "1.5G", "20G", "-5M"] {
+            assert!(parse_bit_rate(bad).is_err(), "{}", bad);
+        }
+    }
+
     #[test]
     fn test_src_port_flows_valid_at_max() {
         // src_port=65520, flows=16 uses ports 65520..65535 (valid)
@@ -334,7 +1919,7 @@ mod tests {
         // timeout=256s with interval=1s is exactly at the limit
         let args = make_args(|a| {
             a.timeout = 256.0;
-            a.interval = 1.0;
+            a.interval = Some(1.0);
         });
         assert!(args.validate().is_ok());
     }
@@ -344,7 +1929,7 @@ mod tests {
         // timeout=257s with interval=1s exceeds 256 × interval
         let args = make_args(|a| {
             a.timeout = 257.0;
-            a.interval = 1.0;
+            a.interval = Some(1.0);
         });
         let err = args.validate().unwrap_err();
         assert!(err.contains("sequence wrap"));
@@ -355,9 +1940,86 @@ mod tests {
         // With 0.1s interval, timeout must be <= 25.6s
         let args = make_args(|a| {
             a.timeout = 30.0;
-            a.interval = 0.1;
+            a.interval = Some(0.1);
         });
         let err = args.validate().unwrap_err();
         assert!(err.contains("sequence wrap"));
     }
+
+    #[test]
+    fn test_locale_validated() {
+        let args = make_args(|a| a.locale = Some("de_DE.UTF-8".to_string()));
+        assert!(args.validate().is_ok());
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.number_format.decimal, ',');
+
+        let args = make_args(|a| a.locale = Some("klingon".to_string()));
+        assert!(args.validate().unwrap_err().contains("--locale"));
+        let args = make_args(|a| a.lang = Some("de_DE.UTF-8".to_string()));
+        assert!(args.validate().is_ok());
+        let args = make_args(|a| a.lang = Some("tlh".to_string()));
+        assert!(args.validate().unwrap_err().contains("--lang"));
+    }
+
+    #[test]
+    fn test_redact_flag() {
+        // Bare --redact doesn't swallow the target
+        let args = Args::try_parse_from(["ttl", "--redact", "8.8.8.8"]).unwrap();
+        assert_eq!(args.targets, ["8.8.8.8"]);
+        assert_eq!(args.redaction(), Redaction::parse(DEFAULT_REDACT).ok());
+
+        let args = Args::try_parse_from(["ttl", "--redact=hops=2", "8.8.8.8"]).unwrap();
+        assert_eq!(args.redaction().unwrap().hops, 2);
+        assert!(!args.redaction().unwrap().hostnames);
+
+        let args = make_args(|a| a.redact = Some("bogus".to_string()));
+        assert!(args.validate().unwrap_err().contains("--redact"));
+    }
+
+    #[test]
+    fn test_ecn_codepoint_optional() {
+        let args = Args::try_parse_from(["ttl", "--ecn", "8.8.8.8"]).unwrap();
+        assert_eq!(args.ecn, Some(EcnCodepoint::Ect0));
+        assert_eq!(args.targets, ["8.8.8.8"]);
+        let args = Args::try_parse_from(["ttl", "--ecn=ect1", "8.8.8.8"]).unwrap();
+        assert_eq!(args.ecn, Some(EcnCodepoint::Ect1));
+        assert!(Args::try_parse_from(["ttl", "--ecn=ce", "8.8.8.8"]).is_err());
+
+        let args = make_args(|a| {
+            a.dscp = Some(46);
+            a.ecn = Some(EcnCodepoint::Ect1);
+        });
+        assert_eq!(
+            crate::config::Config::from(&args).tos(),
+            Some(46 << 2 | 0b01)
+        );
+    }
+
+    #[test]
+    fn test_dump_unmatched_file_optional() {
+        let args = Args::try_parse_from(["ttl", "--dump-unmatched", "8.8.8.8"]).unwrap();
+        assert_eq!(args.dump_unmatched, Some(None));
+        assert_eq!(args.targets, ["8.8.8.8"]);
+
+        let args = Args::try_parse_from(["ttl", "--dump-unmatched=missed.txt", "8.8.8.8"]).unwrap();
+        assert_eq!(
+            args.dump_unmatched,
+            Some(Some(std::path::PathBuf::from("missed.txt")))
+        );
+    }
+
+    #[test]
+    fn test_label_validated() {
+        let args = make_args(|a| a.label = vec!["10.0.0.1=office-fw".to_string()]);
+        assert!(args.validate().is_ok());
+
+        let args = make_args(|a| a.label = vec!["office-fw".to_string()]);
+        assert!(args.validate().unwrap_err().contains("--label"));
+
+        // As if read from config.toml, past clap's value check
+        let args = make_args(|a| a.label_mode = Some("instead".to_string()));
+        assert!(args.validate().unwrap_err().contains("--label-mode"));
+        let args = make_args(|a| a.host_trim = Some("start".to_string()));
+        assert!(args.validate().unwrap_err().contains("--host-trim"));
+    }
 }
@@ -1,8 +1,21 @@
 use crate::cli::Args;
+use crate::export::Redaction;
+use crate::lookup::policy::NetPolicy;
+use crate::state::{
+    DEFAULT_FLAP_HOLD, DEFAULT_STALE_AFTER, EcnCodepoint, GeoPoint, HostTrim, LabelMode, Lang,
+    LoadSpec, NumberFormat, ResponderAlias, ResponderLabels, Slo,
+};
+use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::IpAddr;
 use std::time::Duration;
 
+/// Largest `--ttl-decay` factor accepted
+pub const MAX_TTL_DECAY: f64 = 4.0;
+/// Longest gap between probes at one TTL under `--ttl-decay`, in rounds
+pub const MAX_TTL_PERIOD: u64 = 60;
+
 /// Probe protocol type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ProbeProtocol {
@@ -14,6 +27,138 @@ pub enum ProbeProtocol {
     Tcp,
 }
 
+/// Where `--event-log` writes notable events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventLog {
+    /// Local syslog socket (/dev/log)
+    Syslog,
+    /// systemd-journald native protocol
+    Journald,
+}
+
+/// Actions taken when the destination goes down or comes back up
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestAlert {
+    /// Ring the terminal bell
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bell: bool,
+    /// Flash the TUI status bar
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub flash: bool,
+    /// POST a JSON payload to this URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+impl DestAlert {
+    pub fn is_off(&self) -> bool {
+        !self.bell && !self.flash && self.webhook.is_none()
+    }
+}
+
+/// When a plugin command runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginHook {
+    /// Once per new responder; its JSON output is added to the responder
+    Responder,
+    /// With the session JSON whenever a session is exported
+    Export,
+}
+
+impl PluginHook {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginHook::Responder => "responder",
+            PluginHook::Export => "export",
+        }
+    }
+}
+
+/// External command run on a hook (`--plugin HOOK=CMD`, `[[plugin]]`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plugin {
+    pub hook: PluginHook,
+    /// Program and arguments, split on whitespace
+    pub command: String,
+}
+
+impl Plugin {
+    /// Parse `HOOK=CMD`, e.g. `responder=/usr/local/bin/cmdb-lookup --site fra`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (hook, command) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid plugin '{}', expected HOOK=COMMAND", spec))?;
+        let hook = match hook.trim() {
+            "responder" => PluginHook::Responder,
+            "export" => PluginHook::Export,
+            other => {
+                return Err(format!(
+                    "Unknown plugin hook '{}', expected responder or export",
+                    other
+                ));
+            }
+        };
+        if command.trim().is_empty() {
+            return Err(format!("Plugin '{}' has no command", spec));
+        }
+        Ok(Self {
+            hook,
+            command: command.trim().to_string(),
+        })
+    }
+}
+
+/// How probe rounds of several targets are spread over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Schedule {
+    /// Every target probes every interval, all at once
+    #[default]
+    Parallel,
+    /// Every target probes every interval, start times spread over the interval
+    Staggered,
+    /// Batches of targets take turns, one batch per interval
+    RoundRobin,
+}
+
+/// What to do with ICMP Redirects sent to us (--redirects)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedirectPolicy {
+    /// Don't record them
+    Ignore,
+    /// Record an event the first time each router/gateway pair is seen
+    #[default]
+    Log,
+    /// Also flag the session, since first-hop results may be affected
+    Warn,
+}
+
+impl RedirectPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Where UDP probes carry their probe ID (--udp-id)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UdpIdEncoding {
+    /// The first two payload bytes
+    #[default]
+    Payload,
+    /// The UDP checksum, steered by two payload bytes (Paris traceroute)
+    Checksum,
+}
+
+impl UdpIdEncoding {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Runtime configuration derived from CLI args
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -24,21 +169,54 @@ pub struct Config {
     pub interval: Duration,
     /// Maximum TTL
     pub max_ttl: u8,
+    /// First TTL probed; hops before it are skipped
+    #[serde(default = "default_first_ttl", skip_serializing_if = "is_first_hop")]
+    pub first_ttl: u8,
+    /// Consecutive silent hops after which higher TTLs are only re-checked
+    /// now and then (None = always probe up to max_ttl)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_limit: Option<u8>,
+    /// Probe TTL t only every `ttl_decay^(t-1)` rounds (None = every round)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_decay: Option<f64>,
+    /// Probe lossy or rate-limited hops less often (--adaptive)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub adaptive: bool,
     /// Probe timeout
     #[serde(with = "duration_serde")]
     pub timeout: Duration,
+    /// Stop probing after this much wall-clock time (None = no limit)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_duration_serde"
+    )]
+    pub duration: Option<Duration>,
+    /// Also keep stats over this sliding window of recent results
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_duration_serde"
+    )]
+    pub stats_window: Option<Duration>,
     /// Probe protocol
     pub protocol: ProbeProtocol,
     /// Port for UDP/TCP probes
     pub port: Option<u16>,
     /// Use fixed port (disable per-TTL variation)
     pub port_fixed: bool,
+    /// Where UDP probes carry their ID
+    #[serde(default, skip_serializing_if = "UdpIdEncoding::is_default")]
+    pub udp_id: UdpIdEncoding,
     /// Number of flows for multi-path ECMP detection
     #[serde(default = "default_flows")]
     pub flows: u8,
     /// Base source port for flow identification
     #[serde(default = "default_src_port")]
     pub src_port_base: u16,
+    /// Last source port flows may use (None = one port per flow from the base)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src_port_last: Option<u16>,
     /// Enable reverse DNS lookups
     pub dns_enabled: bool,
     /// Enable ASN enrichment
@@ -47,35 +225,426 @@ pub struct Config {
     pub geo_enabled: bool,
     /// Enable IX detection (PeeringDB)
     pub ix_enabled: bool,
+    /// Track each responder's BGP prefix and origin (RIPEstat)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prefix_watch: bool,
+    /// No network calls besides probes (lookups use local data and caches)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub offline: bool,
+    /// Hosts lookups may contact (empty = any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// Keep lookup caches in memory only (nothing under ~/.cache/ttl)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_disk_cache: bool,
+    /// Where this host is, for hop distances (None = geolocate)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<GeoPoint>,
+    /// Responder addresses to track as one device per hop
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<ResponderAlias>,
+    /// Merge responders at a hop that share a reverse DNS name
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub merge_hostnames: bool,
+    /// Friendly names for responder addresses (--label). Not exported: the
+    /// labels that matched are on the responders
+    #[serde(skip)]
+    pub labels: ResponderLabels,
+    /// Show labels next to the reverse DNS name or in its place
+    #[serde(default, skip_serializing_if = "LabelMode::is_default")]
+    pub label_mode: LabelMode,
+    /// How host names too long for their column are shortened
+    #[serde(default, skip_serializing_if = "HostTrim::is_default")]
+    pub host_trim: HostTrim,
     /// Network interface to bind sockets to
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub interface: Option<String>,
+    /// User labels from `--tag key=value` (a repeated key keeps the last value)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
     /// Don't bind receiver to interface (for asymmetric routing)
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub recv_any: bool,
+    /// Handling of ICMP Redirects
+    #[serde(default, skip_serializing_if = "RedirectPolicy::is_default")]
+    pub redirects: RedirectPolicy,
     /// DSCP value for QoS testing (0-63)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dscp: Option<u8>,
+    /// ECN codepoint probes are marked with (`--ecn`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecn: Option<EcnCodepoint>,
     /// Probe packet size in bytes (includes IP+ICMP headers)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub packet_size: Option<u16>,
     /// Enable Path MTU discovery mode
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub pmtud: bool,
+    /// Sweep DF probe sizes at every hop (`--df-sweep`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub df_sweep: bool,
     /// Maximum probes per second (None = unlimited)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rate: Option<u32>,
     /// Source IP address for probes
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub source_ip: Option<IpAddr>,
+    /// Local addresses traced side by side (--sources); each session gets
+    /// one of them as `source_ip`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<IpAddr>,
+    /// Multi-target scheduling strategy
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// Targets per batch for round-robin scheduling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_batch: Option<usize>,
+    /// Probes per second across all targets (None = unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_rate: Option<u32>,
+    /// Probes --global-rate lets through back to back (None = 1, no burst)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_burst: Option<u32>,
+    /// Requested SO_RCVBUF for the receive socket in bytes (None = OS default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_buffer: Option<usize>,
+    /// Requested SO_SNDBUF for probe sockets in bytes (None = OS default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_buffer: Option<usize>,
+    /// Probe engines on a dedicated thread, with the receiver at raised
+    /// priority (--probe-thread)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub probe_thread: bool,
+    /// CPU the probe and receiver threads are pinned to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_cpu: Option<usize>,
+    /// Consecutive replies a new responder needs before a path change is logged
+    #[serde(default = "default_flap_hold")]
+    pub flap_hold: u32,
+    /// Replies after a path change during which further changes at that hop
+    /// are counted but not logged
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub flap_cooldown: u64,
+    /// Archive and clear per-hop statistics from a hop on after a confirmed
+    /// path change there
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reset_on_path_change: bool,
+    /// Rounds without a reply after which a responder is shown as stale
+    /// (0 = never)
+    #[serde(default = "default_stale_after")]
+    pub stale_after: u64,
+    /// Alert when destination loss over the recent window reaches this percentage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_loss: Option<f64>,
+    /// Show desktop notifications for alerts and path changes
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub notify: bool,
+    /// Display timestamps in local time instead of UTC (exports stay UTC)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local_time: bool,
+    /// Separators and RTT units for displayed numbers (exports stay plain)
+    #[serde(default, skip_serializing_if = "NumberFormat::is_default")]
+    pub number_format: NumberFormat,
+    /// Language of the TUI
+    #[serde(default, skip_serializing_if = "Lang::is_default")]
+    pub lang: Lang,
+    /// Forward path changes, outages and alerts to syslog or journald
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_log: Option<EventLog>,
+    /// Latency/loss objective evaluated at the destination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slo: Option<Slo>,
+    /// Send a UDP load stream while tracing and grade latency under load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<LoadSpec>,
+    /// Bell, flash or webhook on destination up/down transitions
+    #[serde(default, skip_serializing_if = "DestAlert::is_off")]
+    pub dest_alert: DestAlert,
+    /// External commands for enrichment and export
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<Plugin>,
+    /// HTTP endpoint asked about each new responder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrich_url: Option<String>,
+    /// Bearer token for `enrich_url`; never saved
+    #[serde(skip)]
+    pub enrich_token: Option<String>,
+    /// Concurrency and polling interval for each enrichment worker
+    #[serde(default)]
+    pub lookups: LookupLimits,
+    /// What `--redact` removes from exports (the export notes it itself)
+    #[serde(skip)]
+    pub redact: Option<Redaction>,
+}
+
+impl Config {
+    /// Rounds between probes at `ttl` under `--ttl-decay` (1 = every round),
+    /// counting from the first TTL probed
+    pub fn ttl_period(&self, ttl: u8) -> u64 {
+        match self.ttl_decay {
+            Some(decay) => {
+                let distance = ttl.saturating_sub(self.first_ttl);
+                (decay.powi(distance as i32).round() as u64).clamp(1, MAX_TTL_PERIOD)
+            }
+            None => 1,
+        }
+    }
+
+    /// Tags as `key=value` pairs joined by `sep` (empty without tags)
+    pub fn tags_label(&self, sep: &str) -> String {
+        self.tags
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(sep)
+    }
+
+    /// Which outside hosts lookups may contact
+    pub fn net_policy(&self) -> NetPolicy {
+        NetPolicy {
+            offline: self.offline,
+            allowed_hosts: self.allowed_hosts.clone(),
+        }
+    }
+
+    /// Commands of the plugins on `hook`, in order
+    pub fn plugin_commands(&self, hook: PluginHook) -> Vec<String> {
+        self.plugins
+            .iter()
+            .filter(|p| p.hook == hook)
+            .map(|p| p.command.clone())
+            .collect()
+    }
+
+    /// ToS / traffic class byte for probes: DSCP above the ECN bits, if
+    /// either is set
+    pub fn tos(&self) -> Option<u8> {
+        if self.dscp.is_none() && self.ecn.is_none() {
+            return None;
+        }
+        Some(self.dscp.unwrap_or(0) << 2 | self.ecn.map_or(0, |ecn| ecn.bits()))
+    }
+
+    /// Source port assignment for UDP/TCP flows
+    pub fn flow_ports(&self) -> FlowPorts {
+        let flows = self.flows.max(1);
+        let last = self
+            .src_port_last
+            .unwrap_or_else(|| self.src_port_base.saturating_add(flows as u16 - 1));
+        FlowPorts::new(self.src_port_base, last, flows)
+    }
+
+    /// Format a timestamp for display, in UTC or (with `local_time`) the
+    /// system time zone. `%Z` names the zone either way.
+    pub fn format_time(&self, at: &DateTime<Utc>, fmt: &str) -> String {
+        if self.local_time {
+            at.with_timezone(&Local).format(fmt).to_string()
+        } else {
+            at.format(fmt).to_string()
+        }
+    }
+}
+
+/// Source ports used for flow variation: one per flow, spread evenly over
+/// `first..=last` so a narrow firewall allowance still gets distinct flows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowPorts {
+    first: u16,
+    stride: u16,
+    flows: u8,
+}
+
+impl FlowPorts {
+    pub fn new(first: u16, last: u16, flows: u8) -> Self {
+        let span = last.saturating_sub(first) as u32 + 1;
+        Self {
+            first,
+            stride: (span / flows.max(1) as u32).clamp(1, u16::MAX as u32) as u16,
+            flows: flows.max(1),
+        }
+    }
+
+    /// Source port for a flow
+    pub fn port(&self, flow_id: u8) -> u16 {
+        self.first + self.stride * flow_id as u16
+    }
+
+    /// Flow a quoted source port belongs to, if it is one of ours
+    pub fn flow(&self, port: u16) -> Option<u8> {
+        let offset = port.checked_sub(self.first)?;
+        if offset % self.stride != 0 {
+            return None;
+        }
+        let flow = offset / self.stride;
+        (flow < self.flows as u16).then_some(flow as u8)
+    }
+}
+
+/// Throttle settings for one kind of lookup worker
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LookupLimit {
+    /// Maximum lookups in flight per tick
+    pub concurrency: usize,
+    /// How often the worker scans sessions for new IPs
+    #[serde(with = "duration_serde")]
+    pub interval: Duration,
+}
+
+impl LookupLimit {
+    const fn new(concurrency: usize, interval_ms: u64) -> Self {
+        Self {
+            concurrency,
+            interval: Duration::from_millis(interval_ms),
+        }
+    }
+}
+
+/// Per-kind lookup throttling (reverse DNS, ASN, GeoIP, IX, BGP prefix,
+/// responder plugins, enrichment endpoint)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LookupLimits {
+    pub dns: LookupLimit,
+    pub asn: LookupLimit,
+    pub geo: LookupLimit,
+    pub ix: LookupLimit,
+    pub prefix: LookupLimit,
+    pub plugin: LookupLimit,
+    pub enrich: LookupLimit,
+}
+
+impl Default for LookupLimits {
+    fn default() -> Self {
+        Self {
+            dns: LookupLimit::new(10, 500),
+            asn: LookupLimit::new(10, 500),
+            // GeoIP is a local database read, so it can go wider
+            geo: LookupLimit::new(20, 500),
+            ix: LookupLimit::new(10, 500),
+            // RIPEstat is a shared public service; stay well under its limits
+            prefix: LookupLimit::new(4, 5000),
+            // Each lookup is a process spawn
+            plugin: LookupLimit::new(4, 1000),
+            enrich: LookupLimit::new(4, 1000),
+        }
+    }
+}
+
+impl LookupLimits {
+    /// Upper bound for `--lookup-concurrency`
+    pub const MAX_CONCURRENCY: usize = 100;
+    /// Bounds for `--lookup-interval`, in seconds
+    pub const MIN_INTERVAL_SECS: f64 = 0.05;
+    pub const MAX_INTERVAL_SECS: f64 = 60.0;
+
+    fn kind_mut(&mut self, kind: &str) -> Option<&mut LookupLimit> {
+        match kind {
+            "dns" => Some(&mut self.dns),
+            "asn" => Some(&mut self.asn),
+            "geo" => Some(&mut self.geo),
+            "ix" => Some(&mut self.ix),
+            "prefix" => Some(&mut self.prefix),
+            "plugin" => Some(&mut self.plugin),
+            "enrich" => Some(&mut self.enrich),
+            _ => None,
+        }
+    }
+
+    /// Apply a spec that is either a bare value for every kind ("4") or a
+    /// comma-separated list of per-kind values ("dns=4,ix=1")
+    fn apply_spec(
+        &mut self,
+        spec: &str,
+        flag: &str,
+        mut set: impl FnMut(&mut LookupLimit, &str) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if !spec.contains('=') {
+            for limit in [
+                &mut self.dns,
+                &mut self.asn,
+                &mut self.geo,
+                &mut self.ix,
+                &mut self.prefix,
+                &mut self.plugin,
+                &mut self.enrich,
+            ] {
+                set(limit, spec.trim())?;
+            }
+            return Ok(());
+        }
+        for part in spec.split(',') {
+            let (kind, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid {} entry '{}', expected KIND=VALUE", flag, part))?;
+            let limit = self.kind_mut(kind.trim()).ok_or_else(|| {
+                format!(
+                    "Unknown lookup kind '{}' in {}. Valid kinds: dns, asn, geo, ix, prefix, plugin, enrich",
+                    kind.trim(),
+                    flag
+                )
+            })?;
+            set(limit, value.trim())?;
+        }
+        Ok(())
+    }
+
+    /// Apply a `--lookup-concurrency` spec
+    pub fn apply_concurrency(&mut self, spec: &str) -> Result<(), String> {
+        self.apply_spec(spec, "--lookup-concurrency", |limit, value| {
+            match value.parse::<usize>() {
+                Ok(n) if (1..=Self::MAX_CONCURRENCY).contains(&n) => {
+                    limit.concurrency = n;
+                    Ok(())
+                }
+                _ => Err(format!(
+                    "Lookup concurrency must be 1-{}, got '{}'",
+                    Self::MAX_CONCURRENCY,
+                    value
+                )),
+            }
+        })
+    }
+
+    /// Apply a `--lookup-interval` spec (seconds)
+    pub fn apply_interval(&mut self, spec: &str) -> Result<(), String> {
+        self.apply_spec(spec, "--lookup-interval", |limit, value| {
+            match value.parse::<f64>() {
+                Ok(secs) if (Self::MIN_INTERVAL_SECS..=Self::MAX_INTERVAL_SECS).contains(&secs) => {
+                    limit.interval = Duration::from_secs_f64(secs);
+                    Ok(())
+                }
+                _ => Err(format!(
+                    "Lookup interval must be {}-{} seconds, got '{}'",
+                    Self::MIN_INTERVAL_SECS,
+                    Self::MAX_INTERVAL_SECS,
+                    value
+                )),
+            }
+        })
+    }
 }
 
 fn default_flows() -> u8 {
     1
 }
+fn default_first_ttl() -> u8 {
+    1
+}
+fn is_first_hop(ttl: &u8) -> bool {
+    *ttl == 1
+}
 fn default_src_port() -> u16 {
     50000
 }
+fn default_flap_hold() -> u32 {
+    DEFAULT_FLAP_HOLD
+}
+fn default_stale_after() -> u64 {
+    DEFAULT_STALE_AFTER
+}
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
 
 impl Default for Config {
     fn default() -> Self {
@@ -83,23 +652,72 @@ impl Default for Config {
             count: None,
             interval: Duration::from_secs(1),
             max_ttl: 30,
+            first_ttl: 1,
+            gap_limit: None,
+            ttl_decay: None,
+            adaptive: false,
             timeout: Duration::from_secs(3),
+            duration: None,
+            stats_window: None,
             protocol: ProbeProtocol::Icmp,
             port: None,
             port_fixed: false,
+            udp_id: UdpIdEncoding::default(),
             flows: 1,
             src_port_base: 50000,
+            src_port_last: None,
             dns_enabled: true,
             asn_enabled: true,
             geo_enabled: true,
             ix_enabled: true,
+            prefix_watch: false,
+            offline: false,
+            allowed_hosts: Vec::new(),
+            no_disk_cache: false,
+            origin: None,
+            aliases: Vec::new(),
+            labels: ResponderLabels::default(),
+            label_mode: LabelMode::default(),
+            host_trim: HostTrim::default(),
+            merge_hostnames: false,
             interface: None,
+            tags: BTreeMap::new(),
             recv_any: false,
+            redirects: RedirectPolicy::default(),
             dscp: None,
+            ecn: None,
             packet_size: None,
             pmtud: false,
+            df_sweep: false,
             rate: None,
             source_ip: None,
+            sources: Vec::new(),
+            schedule: Schedule::Parallel,
+            schedule_batch: None,
+            global_rate: None,
+            global_burst: None,
+            recv_buffer: None,
+            send_buffer: None,
+            probe_thread: false,
+            probe_cpu: None,
+            flap_hold: DEFAULT_FLAP_HOLD,
+            flap_cooldown: 0,
+            reset_on_path_change: false,
+            stale_after: DEFAULT_STALE_AFTER,
+            alert_loss: None,
+            notify: false,
+            local_time: false,
+            number_format: NumberFormat::default(),
+            lang: Lang::default(),
+            event_log: None,
+            slo: None,
+            load: None,
+            dest_alert: DestAlert::default(),
+            plugins: Vec::new(),
+            enrich_url: None,
+            enrich_token: None,
+            lookups: LookupLimits::default(),
+            redact: None,
         }
     }
 }
@@ -120,6 +738,15 @@ impl From<&Args> for Config {
             ProbeProtocol::Icmp => None,
         });
 
+        // Specs were checked by Args::validate; invalid entries keep defaults
+        let mut lookups = LookupLimits::default();
+        if let Some(ref spec) = args.lookup_concurrency {
+            let _ = lookups.apply_concurrency(spec);
+        }
+        if let Some(ref spec) = args.lookup_interval {
+            let _ = lookups.apply_interval(spec);
+        }
+
         Self {
             count: if args.count == 0 {
                 None
@@ -128,23 +755,133 @@ impl From<&Args> for Config {
             },
             interval: args.interval_duration(),
             max_ttl: args.max_ttl,
+            first_ttl: args.first_ttl,
+            gap_limit: args.gap_limit,
+            ttl_decay: args.ttl_decay,
+            adaptive: args.adaptive,
             timeout: args.timeout_duration(),
+            duration: args.duration,
+            stats_window: args.stats_window,
             protocol,
             port,
             port_fixed: args.port_fixed,
+            udp_id: match args.udp_id.as_str() {
+                "checksum" => UdpIdEncoding::Checksum,
+                _ => UdpIdEncoding::Payload,
+            },
             flows: args.flows,
-            src_port_base: args.src_port,
-            dns_enabled: !args.no_dns,
-            asn_enabled: !args.no_asn,
+            src_port_base: args
+                .src_port_range()
+                .map_or(args.src_port, |(first, _)| first),
+            src_port_last: args.src_port_range().map(|(_, last)| last),
+            dns_enabled: !args.no_dns && !args.offline,
+            asn_enabled: !args.no_asn && !args.offline,
             geo_enabled: !args.no_geo,
             ix_enabled: !args.no_ix,
+            prefix_watch: args.prefix_watch,
+            offline: args.offline,
+            allowed_hosts: args.allow_host.clone(),
+            no_disk_cache: args.no_disk_cache,
+            origin: args.origin,
+            // Specs were checked by Args::validate
+            aliases: args
+                .alias
+                .iter()
+                .filter_map(|spec| ResponderAlias::parse(spec).ok())
+                .collect(),
+            merge_hostnames: args.merge_by_hostname,
+            // Specs were checked by Args::validate
+            labels: ResponderLabels::parse(args.label.iter().map(String::as_str))
+                .unwrap_or_default(),
+            label_mode: match args.label_mode.as_deref() {
+                Some("replace") => LabelMode::Replace,
+                _ => LabelMode::Alongside,
+            },
+            host_trim: match args.host_trim.as_deref() {
+                Some("end") => HostTrim::End,
+                Some("middle") => HostTrim::Middle,
+                _ => HostTrim::Smart,
+            },
             interface: args.interface.clone(),
+            tags: args.tags.iter().cloned().collect(),
             recv_any: args.recv_any,
+            redirects: match args.redirects.as_str() {
+                "ignore" => RedirectPolicy::Ignore,
+                "warn" => RedirectPolicy::Warn,
+                _ => RedirectPolicy::Log,
+            },
             dscp: args.dscp,
+            ecn: args.ecn,
             packet_size: args.size,
             pmtud: args.pmtud,
+            df_sweep: args.df_sweep,
             rate: args.rate,
             source_ip: args.source_ip,
+            sources: args.sources.clone(),
+            // --parallel N runs targets in turns, N at a time
+            schedule: match args.schedule.as_str() {
+                _ if args.parallel.is_some() => Schedule::RoundRobin,
+                "staggered" => Schedule::Staggered,
+                "round-robin" => Schedule::RoundRobin,
+                _ => Schedule::Parallel,
+            },
+            schedule_batch: args.parallel.or(args.batch).map(usize::from),
+            global_rate: args.global_rate,
+            global_burst: args.global_burst,
+            recv_buffer: args.recv_buffer,
+            send_buffer: args.send_buffer,
+            probe_thread: args.probe_thread || args.probe_cpu.is_some(),
+            probe_cpu: args.probe_cpu,
+            flap_hold: args.flap_hold,
+            flap_cooldown: args.flap_cooldown,
+            reset_on_path_change: args.reset_on_path_change,
+            stale_after: args.stale_after,
+            alert_loss: args.alert_loss,
+            notify: args.notify,
+            local_time: args.local_time,
+            // Checked by Args::validate
+            number_format: NumberFormat {
+                auto_units: args.auto_units,
+                ..args
+                    .locale
+                    .as_deref()
+                    .and_then(|l| NumberFormat::for_locale(l).ok())
+                    .unwrap_or_default()
+            },
+            lang: args
+                .lang
+                .as_deref()
+                .and_then(|l| Lang::for_locale(l).ok())
+                .unwrap_or_default(),
+            event_log: match args.event_log.as_deref() {
+                Some("syslog") => Some(EventLog::Syslog),
+                Some("journald") => Some(EventLog::Journald),
+                _ => None,
+            },
+            slo: args.slo.as_deref().and_then(|s| Slo::parse(s).ok()),
+            load: args
+                .load
+                .zip(args.load_target)
+                .map(|(rate_bps, target)| LoadSpec {
+                    rate_bps,
+                    target: target.ip(),
+                    port: target.port(),
+                }),
+            dest_alert: DestAlert {
+                bell: args.dest_alert.iter().any(|a| a == "bell"),
+                flash: args.dest_alert.iter().any(|a| a == "flash"),
+                webhook: args.dest_webhook.clone(),
+            },
+            // Specs were checked by Args::validate
+            plugins: args
+                .plugin
+                .iter()
+                .filter_map(|spec| Plugin::parse(spec).ok())
+                .collect(),
+            enrich_url: args.enrich_url.clone(),
+            enrich_token: args.enrich_token.clone(),
+            lookups,
+            redact: args.redaction(),
         }
     }
 }
@@ -169,3 +906,23 @@ mod duration_serde {
         Ok(Duration::from_secs_f64(secs))
     }
 }
+
+mod option_duration_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        duration.map(|d| d.as_secs_f64()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = Option::<f64>::deserialize(deserializer)?;
+        Ok(secs.map(Duration::from_secs_f64))
+    }
+}
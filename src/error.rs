@@ -0,0 +1,127 @@
+//! Error type for the library API
+//!
+//! Embedding applications can match on `TtlError` to tell a missing
+//! privilege from a broken socket, a failed lookup, an export that couldn't
+//! be written or an invalid option. Inside the binary these convert into
+//! `anyhow::Error` with `?` like any other error.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Result with `TtlError` as the default error
+pub type Result<T, E = TtlError> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum TtlError {
+    /// Creating, configuring or using a socket failed
+    Socket(io::Error),
+    /// Raw sockets need privileges the process lacks; the message says how
+    /// to grant them
+    Permission(String),
+    /// An enrichment lookup (reverse DNS, ASN, GeoIP, IX) couldn't be set up
+    Lookup(String),
+    /// Writing or converting an export failed
+    Export {
+        message: String,
+        source: Option<Box<dyn Error + Send + Sync>>,
+    },
+    /// An option or config file value is invalid
+    Config(String),
+}
+
+impl TtlError {
+    /// Export failure without an underlying error
+    pub fn export(message: impl Into<String>) -> Self {
+        Self::Export {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Export failure caused by `source`
+    pub fn export_from(
+        message: impl Into<String>,
+        source: impl Into<Box<dyn Error + Send + Sync>>,
+    ) -> Self {
+        Self::Export {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// Short name of the variant, for logs and machine-readable output
+    #[allow(dead_code)]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Socket(_) => "socket",
+            Self::Permission(_) => "permission",
+            Self::Lookup(_) => "lookup",
+            Self::Export { .. } => "export",
+            Self::Config(_) => "config",
+        }
+    }
+}
+
+impl fmt::Display for TtlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Socket(e) => write!(f, "socket error: {}", e),
+            Self::Permission(message) | Self::Lookup(message) | Self::Config(message) => {
+                f.write_str(message)
+            }
+            Self::Export {
+                message,
+                source: Some(source),
+            } => write!(f, "{}: {}", message, source),
+            Self::Export { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl Error for TtlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Socket(e) => Some(e),
+            Self::Export {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TtlError {
+    fn from(e: io::Error) -> Self {
+        Self::Socket(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_source() {
+        let e = TtlError::from(io::Error::from(io::ErrorKind::AddrInUse));
+        assert_eq!(e.kind(), "socket");
+        assert!(e.to_string().starts_with("socket error: "));
+        assert!(e.source().is_some());
+
+        let e = TtlError::export_from("writing CSV failed", io::Error::other("disk full"));
+        assert_eq!(e.to_string(), "writing CSV failed: disk full");
+        assert!(e.source().is_some());
+
+        let e = TtlError::export("No sessions to merge");
+        assert_eq!(e.to_string(), "No sessions to merge");
+        assert!(e.source().is_none());
+
+        // Converts into anyhow for the binary, keeping the variant
+        let e: anyhow::Error = TtlError::Config("bad".into()).into();
+        assert!(matches!(
+            e.downcast_ref::<TtlError>(),
+            Some(TtlError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,388 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::IpAddr;
+
+use crate::error::{Result, TtlError};
+use crate::state::Session;
+
+/// Alternate paths listed in the report
+const TOP_PATHS: usize = 5;
+/// A day's median RTT counts as a regression when it is this much above the
+/// median of the days before it, both relatively and in milliseconds
+const REGRESSION_RATIO: f64 = 1.2;
+const REGRESSION_MIN_MS: f64 = 5.0;
+
+/// A path as the primary responder at each TTL (None = no reply)
+pub type PathKey = Vec<Option<IpAddr>>;
+
+/// How often one path was seen across the runs
+#[derive(Debug, Clone, Serialize)]
+pub struct PathSeen {
+    pub hops: PathKey,
+    pub runs: usize,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Per-TTL figures for one day, across that day's runs
+#[derive(Debug, Clone, Serialize)]
+pub struct HopDay {
+    pub ttl: u8,
+    pub loss_p50: f64,
+    pub loss_p95: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_p50_ms: Option<f64>,
+}
+
+/// One UTC day of history
+#[derive(Debug, Clone, Serialize)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub runs: usize,
+    pub hops: Vec<HopDay>,
+}
+
+/// A day whose median RTT at a TTL rose well above the days before it
+#[derive(Debug, Clone, Serialize)]
+pub struct Regression {
+    pub date: NaiveDate,
+    pub ttl: u8,
+    pub baseline_ms: f64,
+    pub rtt_ms: f64,
+}
+
+/// Trends over saved sessions toward one target (`ttl analyze`)
+#[derive(Debug, Clone, Serialize)]
+pub struct Analysis {
+    pub target: String,
+    pub runs: usize,
+    pub first_run: DateTime<Utc>,
+    pub last_run: DateTime<Utc>,
+    /// Runs whose path differed from the run before
+    pub path_changes: usize,
+    /// Share of runs on the most common path
+    pub stability_pct: f64,
+    /// Distinct paths, most frequent first (at most TOP_PATHS)
+    pub paths: Vec<PathSeen>,
+    pub distinct_paths: usize,
+    pub days: Vec<DayStats>,
+    pub regressions: Vec<Regression>,
+}
+
+/// Nearest-rank percentile, as `ResponderStats::percentile`
+fn percentile(values: &mut [f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let idx = ((p / 100.0) * (values.len() - 1) as f64).round() as usize;
+    Some(values[idx.min(values.len() - 1)])
+}
+
+/// Last TTL worth looking at in a run
+fn last_ttl(session: &Session) -> u8 {
+    session.dest_ttl.unwrap_or_else(|| {
+        session
+            .hops
+            .iter()
+            .filter(|h| h.received > 0)
+            .map(|h| h.ttl)
+            .max()
+            .unwrap_or(0)
+    })
+}
+
+fn path_key(session: &Session) -> PathKey {
+    (1..=last_ttl(session))
+        .map(|ttl| session.hop(ttl).and_then(|h| h.primary))
+        .collect()
+}
+
+/// Whether `session` traced `target` (the name it was given, or its address)
+fn traces(session: &Session, target: &str) -> bool {
+    session.target.original == target || session.target.resolved.to_string() == target
+}
+
+/// Analyze saved sessions: keep the ones toward `target` (or toward the
+/// first session's target) started at or after `since`, oldest first.
+pub fn analyze_history(
+    sessions: &[Session],
+    target: Option<&str>,
+    since: Option<DateTime<Utc>>,
+) -> Result<Analysis> {
+    let target = match target {
+        Some(t) => t.to_string(),
+        None => match sessions.first() {
+            Some(s) => s.target.original.clone(),
+            None => return Err(TtlError::export("No sessions to analyze")),
+        },
+    };
+    let mut runs: Vec<&Session> = sessions
+        .iter()
+        .filter(|s| traces(s, &target))
+        .filter(|s| since.is_none_or(|since| s.started_at >= since))
+        .collect();
+    runs.sort_by_key(|s| s.started_at);
+    let (Some(first), Some(last)) = (runs.first(), runs.last()) else {
+        return Err(TtlError::export(format!(
+            "No saved sessions for {} in the selected time range",
+            target
+        )));
+    };
+    let (first_run, last_run) = (first.started_at, last.started_at);
+
+    // Path stability and alternate paths
+    let keys: Vec<PathKey> = runs.iter().map(|s| path_key(s)).collect();
+    let path_changes = keys.windows(2).filter(|w| w[0] != w[1]).count();
+    let mut paths: Vec<PathSeen> = Vec::new();
+    for (key, session) in keys.iter().zip(&runs) {
+        match paths.iter_mut().find(|p| &p.hops == key) {
+            Some(seen) => {
+                seen.runs += 1;
+                seen.last_seen = session.started_at;
+            }
+            None => paths.push(PathSeen {
+                hops: key.clone(),
+                runs: 1,
+                first_seen: session.started_at,
+                last_seen: session.started_at,
+            }),
+        }
+    }
+    // Stable sort: ties keep first-seen order
+    paths.sort_by_key(|p| std::cmp::Reverse(p.runs));
+    let distinct_paths = paths.len();
+    let stability_pct = paths[0].runs as f64 / runs.len() as f64 * 100.0;
+    paths.truncate(TOP_PATHS);
+
+    // Loss and RTT per TTL per day
+    let mut by_day: BTreeMap<NaiveDate, Vec<&Session>> = BTreeMap::new();
+    for session in &runs {
+        by_day
+            .entry(session.started_at.date_naive())
+            .or_default()
+            .push(session);
+    }
+    let mut days = Vec::new();
+    for (date, day_runs) in &by_day {
+        let max_ttl = day_runs.iter().map(|s| last_ttl(s)).max().unwrap_or(0);
+        let mut hops = Vec::new();
+        for ttl in 1..=max_ttl {
+            let probed: Vec<_> = day_runs
+                .iter()
+                .filter(|s| ttl <= last_ttl(s))
+                .filter_map(|s| s.hop(ttl))
+                .filter(|h| h.sent > 0)
+                .collect();
+            let mut loss: Vec<f64> = probed.iter().map(|h| h.loss_pct()).collect();
+            let mut rtt: Vec<f64> = probed
+                .iter()
+                .filter_map(|h| h.primary_stats())
+                .filter(|s| s.received > 0)
+                .map(|s| s.avg_rtt().as_secs_f64() * 1000.0)
+                .collect();
+            let (Some(loss_p50), Some(loss_p95)) =
+                (percentile(&mut loss, 50.0), percentile(&mut loss, 95.0))
+            else {
+                continue;
+            };
+            hops.push(HopDay {
+                ttl,
+                loss_p50,
+                loss_p95,
+                rtt_p50_ms: percentile(&mut rtt, 50.0),
+            });
+        }
+        days.push(DayStats {
+            date: *date,
+            runs: day_runs.len(),
+            hops,
+        });
+    }
+
+    // Latency regressions: each day against the median of the days before
+    let mut regressions = Vec::new();
+    for (i, day) in days.iter().enumerate().skip(1) {
+        for hop in &day.hops {
+            let Some(rtt_ms) = hop.rtt_p50_ms else {
+                continue;
+            };
+            let mut before: Vec<f64> = days[..i]
+                .iter()
+                .flat_map(|d| d.hops.iter())
+                .filter(|h| h.ttl == hop.ttl)
+                .filter_map(|h| h.rtt_p50_ms)
+                .collect();
+            let Some(baseline_ms) = percentile(&mut before, 50.0) else {
+                continue;
+            };
+            if rtt_ms > baseline_ms * REGRESSION_RATIO && rtt_ms - baseline_ms > REGRESSION_MIN_MS {
+                regressions.push(Regression {
+                    date: day.date,
+                    ttl: hop.ttl,
+                    baseline_ms,
+                    rtt_ms,
+                });
+            }
+        }
+    }
+
+    Ok(Analysis {
+        target,
+        runs: runs.len(),
+        first_run,
+        last_run,
+        path_changes,
+        stability_pct,
+        paths,
+        distinct_paths,
+        days,
+        regressions,
+    })
+}
+
+fn render_path(path: &PathKey) -> String {
+    if path.is_empty() {
+        return "(no replies)".to_string();
+    }
+    path.iter()
+        .map(|ip| {
+            ip.map(|ip| ip.to_string())
+                .unwrap_or_else(|| "*".to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Write the analysis as a plain text report
+pub fn write_analysis_report<W: Write>(analysis: &Analysis, mut writer: W) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "ttl analyze for {} ({} runs, {} to {})",
+        analysis.target,
+        analysis.runs,
+        analysis.first_run.format("%Y-%m-%d %H:%M"),
+        analysis.last_run.format("%Y-%m-%d %H:%M")
+    )?;
+    writeln!(writer)?;
+
+    writeln!(
+        writer,
+        "Path stability: {:.0}% of runs on the most common path, {} distinct, {} changes",
+        analysis.stability_pct, analysis.distinct_paths, analysis.path_changes
+    )?;
+    for (i, path) in analysis.paths.iter().enumerate() {
+        let label = if i == 0 { "main" } else { "alt " };
+        writeln!(
+            writer,
+            "  {} {:>4} runs  {} .. {}  {}",
+            label,
+            path.runs,
+            path.first_seen.format("%m-%d %H:%M"),
+            path.last_seen.format("%m-%d %H:%M"),
+            render_path(&path.hops)
+        )?;
+    }
+    writeln!(writer)?;
+
+    writeln!(writer, "Per hop per day (loss p50/p95, median RTT):")?;
+    for day in &analysis.days {
+        writeln!(writer, "  {} ({} runs)", day.date, day.runs)?;
+        for hop in &day.hops {
+            let rtt = hop
+                .rtt_p50_ms
+                .map(|ms| format!("{:.1}ms", ms))
+                .unwrap_or_else(|| "-".to_string());
+            writeln!(
+                writer,
+                "    {:>3}  {:>5.1}% {:>5.1}%  {:>9}",
+                hop.ttl, hop.loss_p50, hop.loss_p95, rtt
+            )?;
+        }
+    }
+    writeln!(writer)?;
+
+    if analysis.regressions.is_empty() {
+        writeln!(writer, "Latency regressions: none")?;
+    } else {
+        writeln!(writer, "Latency regressions:")?;
+        for r in &analysis.regressions {
+            writeln!(
+                writer,
+                "  {} hop {}: {:.1}ms median, up from {:.1}ms",
+                r.date, r.ttl, r.rtt_ms, r.baseline_ms
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::Target;
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn run(day: u32, hour: u32, path: &[u8], rtt_ms: u64) -> Session {
+        let target = Target::new(
+            "example.com".to_string(),
+            IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+        );
+        let mut session = Session::new(target, Config::default());
+        session.started_at = Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap();
+        for (i, last) in path.iter().enumerate() {
+            let hop = session.hop_mut(i as u8 + 1).unwrap();
+            hop.record_sent();
+            hop.record_response(
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, *last)),
+                Duration::from_millis(rtt_ms),
+            );
+        }
+        session.dest_ttl = Some(path.len() as u8);
+        session
+    }
+
+    #[test]
+    fn test_analyze_paths_and_regressions() {
+        let sessions = vec![
+            run(2, 10, &[1, 2, 3], 10),
+            run(1, 10, &[1, 2, 3], 10),
+            run(1, 12, &[1, 9, 3], 10),
+            run(2, 12, &[1, 2, 3], 30),
+        ];
+        let analysis = analyze_history(&sessions, Some("example.com"), None).unwrap();
+        assert_eq!(analysis.runs, 4);
+        assert_eq!(analysis.distinct_paths, 2);
+        // Sorted by time: A, B, A, A
+        assert_eq!(analysis.path_changes, 2);
+        assert_eq!(analysis.stability_pct, 75.0);
+        assert_eq!(analysis.paths[0].runs, 3);
+        assert_eq!(analysis.days.len(), 2);
+        assert_eq!(analysis.days[0].hops[2].rtt_p50_ms, Some(10.0));
+        assert_eq!(analysis.regressions.len(), 3);
+        assert_eq!(analysis.regressions[0].rtt_ms, 30.0);
+
+        let mut buf = Vec::new();
+        write_analysis_report(&analysis, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.contains("75% of runs on the most common path"));
+        assert!(out.contains("10.0.0.1 -> 10.0.0.9 -> 10.0.0.3"));
+    }
+
+    #[test]
+    fn test_analyze_filters() {
+        let sessions = vec![run(1, 10, &[1, 2], 10), run(5, 10, &[1, 2], 10)];
+        let since = Utc.with_ymd_and_hms(2026, 3, 3, 0, 0, 0).unwrap();
+        let analysis = analyze_history(&sessions, None, Some(since)).unwrap();
+        assert_eq!(analysis.runs, 1);
+        assert!(analysis.regressions.is_empty());
+        assert!(analyze_history(&sessions, Some("93.184.216.34"), None).is_ok());
+        assert!(analyze_history(&sessions, Some("other.net"), None).is_err());
+        assert!(analyze_history(&[], None, None).is_err());
+    }
+}
@@ -1,14 +1,30 @@
-use anyhow::Result;
 use std::io::Write;
 
+use crate::error::{Result, TtlError};
 use crate::state::Session;
 
 /// Export session to CSV format
-pub fn export_csv<W: Write>(session: &Session, mut writer: W) -> Result<()> {
+pub fn export_csv<W: Write>(session: &Session, writer: W) -> Result<()> {
+    write_csv(session, writer).map_err(|e| TtlError::export_from("writing CSV failed", e))
+}
+
+fn write_csv<W: Write>(session: &Session, mut writer: W) -> std::io::Result<()> {
+    // Redacted exports say so up front (readers can skip '#' lines)
+    if let Some(ref redacted) = session.redacted {
+        writeln!(writer, "# redacted: {}", redacted)?;
+    }
+    if !session.config.tags.is_empty() {
+        writeln!(writer, "# tags: {}", session.config.tags_label(","))?;
+    }
+    // Session-wide notes don't belong to any row
+    for note in session.notes.iter().filter(|n| n.ttl.is_none()) {
+        writeln!(writer, "# note: {}", note.text.replace(['\r', '\n'], " "))?;
+    }
+
     // Write header
     writeln!(
         writer,
-        "ttl,ip,hostname,loss_pct,sent,recv,avg_ms,min_ms,max_ms,stddev_ms,jitter_ms"
+        "ttl,ip,hostname,loss_pct,sent,recv,avg_ms,min_ms,max_ms,stddev_ms,jitter_ms,notes,label"
     )?;
 
     // Write rows for each hop (only up to destination)
@@ -54,9 +70,22 @@ pub fn export_csv<W: Write>(session: &Session, mut writer: W) -> Result<()> {
             )
         };
 
+        // Hop notes share one cell
+        let notes: Vec<&str> = session
+            .notes
+            .iter()
+            .filter(|n| n.ttl == Some(hop.ttl))
+            .map(|n| n.text.as_str())
+            .collect();
+        let notes = notes.join("; ");
+        let label = hop
+            .primary_stats()
+            .and_then(|s| s.label.as_deref())
+            .unwrap_or_default();
+
         writeln!(
             writer,
-            "{},{},{},{:.1},{},{},{},{},{},{},{}",
+            "{},{},{},{:.1},{},{},{},{},{},{},{},{},{}",
             hop.ttl,
             ip,
             escape_csv(&hostname),
@@ -67,7 +96,9 @@ pub fn export_csv<W: Write>(session: &Session, mut writer: W) -> Result<()> {
             min,
             max,
             stddev,
-            jitter
+            jitter,
+            escape_csv(&notes),
+            escape_csv(label)
         )?;
     }
 
@@ -93,4 +124,33 @@ mod tests {
         assert_eq!(escape_csv("with,comma"), "\"with,comma\"");
         assert_eq!(escape_csv("with\"quote"), "\"with\"\"quote\"");
     }
+
+    #[test]
+    fn test_notes_column() {
+        use crate::config::Config;
+        use crate::state::Target;
+        use std::net::{IpAddr, Ipv4Addr};
+
+        let target = Target::new("t".to_string(), IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)));
+        let mut session = Session::new(target, Config::default());
+        for ttl in 1..=2 {
+            session.hop_mut(ttl).unwrap().record_sent();
+        }
+        let hop = session.hop_mut(2).unwrap();
+        hop.record_response(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), Default::default());
+        hop.responders
+            .values_mut()
+            .for_each(|s| s.label = Some("office-fw".into()));
+        session.add_note(None, "start");
+        session.add_note(Some(2), "edge, upstream");
+
+        let mut buf = Vec::new();
+        export_csv(&session, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines[0], "# note: start");
+        assert!(lines[1].ends_with(",notes,label"));
+        assert!(lines[2].ends_with(",,"));
+        assert!(lines[3].ends_with(",\"edge, upstream\",office-fw"));
+    }
 }
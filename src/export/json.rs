@@ -1,18 +1,19 @@
-use anyhow::Result;
 use std::io::Write;
 
+use crate::error::{Result, TtlError};
 use crate::state::Session;
 
 /// Export session to JSON
 pub fn export_json<W: Write>(session: &Session, writer: W) -> Result<()> {
-    serde_json::to_writer_pretty(writer, session)?;
-    Ok(())
+    serde_json::to_writer_pretty(writer, session)
+        .map_err(|e| TtlError::export_from("writing JSON failed", e))
 }
 
 /// Export session to JSON string
 #[allow(dead_code)]
 pub fn export_json_string(session: &Session) -> Result<String> {
-    Ok(serde_json::to_string_pretty(session)?)
+    serde_json::to_string_pretty(session)
+        .map_err(|e| TtlError::export_from("serializing JSON failed", e))
 }
 
 /// Export session to file with auto-generated name
@@ -21,7 +22,8 @@ pub fn export_json_file(session: &Session) -> Result<String> {
     let target = &session.target.original;
     let filename = format!("ttl-{}-{}.json", target, timestamp);
 
-    let file = std::fs::File::create(&filename)?;
+    let file = std::fs::File::create(&filename)
+        .map_err(|e| TtlError::export_from(format!("creating {} failed", filename), e))?;
     export_json(session, file)?;
 
     Ok(filename)
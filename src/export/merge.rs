@@ -0,0 +1,306 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::IpAddr;
+
+use crate::error::{Result, TtlError};
+use crate::state::Session;
+
+/// Width of each per-vantage column in the text view
+const VANTAGE_WIDTH: usize = 36;
+
+/// One hop as seen from a single vantage point
+#[derive(Debug, Clone, Serialize)]
+pub struct VantageHop {
+    pub ip: IpAddr,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u32>,
+    pub loss_pct: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_ms: Option<f64>,
+}
+
+/// A TTL row across all vantage points (None = no response from that vantage)
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedHop {
+    pub ttl: u8,
+    pub vantages: Vec<Option<VantageHop>>,
+}
+
+/// AS-level path from one vantage point
+#[derive(Debug, Clone, Serialize)]
+pub struct AsPath {
+    pub vantage: String,
+    pub asns: Vec<u32>,
+}
+
+/// Sessions toward one target, merged into a per-TTL path graph
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedPath {
+    pub target: String,
+    pub vantages: Vec<String>,
+    pub hops: Vec<MergedHop>,
+    pub as_paths: Vec<AsPath>,
+    /// ASNs that appear on every vantage's path, in first-seen order
+    pub shared_asns: Vec<u32>,
+    /// AS names keyed by number
+    pub as_names: BTreeMap<u32, String>,
+}
+
+/// Merge sessions that traced the same target from different vantage points.
+///
+/// Each entry pairs a vantage label (e.g. the file stem) with its session.
+/// Sessions must share either the original target string or the resolved
+/// address, so anycast names that resolve differently per office still merge.
+pub fn merge_sessions(sessions: &[(String, Session)]) -> Result<MergedPath> {
+    let Some((_, first)) = sessions.first() else {
+        return Err(TtlError::export("No sessions to merge"));
+    };
+    for (label, s) in sessions {
+        if s.target.original != first.target.original && s.target.resolved != first.target.resolved
+        {
+            return Err(TtlError::export(format!(
+                "Session '{}' traces {} ({}), expected {} ({})",
+                label,
+                s.target.original,
+                s.target.resolved,
+                first.target.original,
+                first.target.resolved
+            )));
+        }
+    }
+
+    let max_ttl = sessions
+        .iter()
+        .map(|(_, s)| s.dest_ttl.unwrap_or(s.config.max_ttl))
+        .max()
+        .unwrap_or(0);
+
+    let mut as_names: BTreeMap<u32, String> = BTreeMap::new();
+    let mut hops = Vec::new();
+    for ttl in 1..=max_ttl {
+        let vantages: Vec<Option<VantageHop>> = sessions
+            .iter()
+            .map(|(_, s)| {
+                let hop = s.hop(ttl)?;
+                if hop.sent == 0 || ttl > s.dest_ttl.unwrap_or(s.config.max_ttl) {
+                    return None;
+                }
+                let stats = hop.primary_stats()?;
+                if let Some(ref asn) = stats.asn {
+                    as_names
+                        .entry(asn.number)
+                        .or_insert_with(|| asn.name.clone());
+                }
+                Some(VantageHop {
+                    ip: stats.ip,
+                    hostname: stats.hostname.clone(),
+                    asn: stats.asn.as_ref().map(|a| a.number),
+                    loss_pct: hop.loss_pct(),
+                    avg_ms: (stats.received > 0).then(|| stats.avg_rtt().as_secs_f64() * 1000.0),
+                })
+            })
+            .collect();
+        if vantages.iter().any(Option::is_some) {
+            hops.push(MergedHop { ttl, vantages });
+        }
+    }
+
+    // Collapse each vantage's hops into an AS path (consecutive duplicates removed)
+    let as_paths: Vec<AsPath> = sessions
+        .iter()
+        .enumerate()
+        .map(|(i, (label, _))| {
+            let mut asns: Vec<u32> = Vec::new();
+            for asn in hops
+                .iter()
+                .filter_map(|h| h.vantages[i].as_ref().and_then(|v| v.asn))
+            {
+                if asns.last() != Some(&asn) {
+                    asns.push(asn);
+                }
+            }
+            AsPath {
+                vantage: label.clone(),
+                asns,
+            }
+        })
+        .collect();
+
+    let mut shared_asns: Vec<u32> = Vec::new();
+    if let Some(first_path) = as_paths.first() {
+        for asn in &first_path.asns {
+            if !shared_asns.contains(asn) && as_paths.iter().all(|p| p.asns.contains(asn)) {
+                shared_asns.push(*asn);
+            }
+        }
+    }
+
+    Ok(MergedPath {
+        target: first.target.original.clone(),
+        vantages: sessions.iter().map(|(l, _)| l.clone()).collect(),
+        hops,
+        as_paths,
+        shared_asns,
+        as_names,
+    })
+}
+
+impl MergedPath {
+    fn as_label(&self, asn: u32) -> String {
+        match self.as_names.get(&asn) {
+            Some(name) if !name.is_empty() => format!("AS{} ({})", asn, name),
+            _ => format!("AS{}", asn),
+        }
+    }
+}
+
+fn fit(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let truncated: String = s.chars().take(width - 1).collect();
+        format!("{}…", truncated)
+    }
+}
+
+/// Write the merged path as a side-by-side text table
+pub fn write_merged_report<W: Write>(merged: &MergedPath, mut writer: W) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "ttl merge for {} ({} vantage points)",
+        merged.target,
+        merged.vantages.len()
+    )?;
+    writeln!(writer)?;
+
+    let mut header = format!("{:>3}", "#");
+    for v in &merged.vantages {
+        header.push_str(&format!(
+            "  {:<width$}",
+            fit(v, VANTAGE_WIDTH),
+            width = VANTAGE_WIDTH
+        ));
+    }
+    writeln!(writer, "{}", header.trim_end())?;
+    writeln!(writer, "{}", "-".repeat(header.trim_end().chars().count()))?;
+
+    for hop in &merged.hops {
+        let mut line = format!("{:>3}", hop.ttl);
+        for v in &hop.vantages {
+            let cell = match v {
+                Some(v) => {
+                    let host = v.hostname.clone().unwrap_or_else(|| v.ip.to_string());
+                    let rtt = v
+                        .avg_ms
+                        .map(|ms| format!("{:.1}ms", ms))
+                        .unwrap_or_else(|| "-".to_string());
+                    let stats = format!(" {:.0}% {}", v.loss_pct, rtt);
+                    let host_width = VANTAGE_WIDTH.saturating_sub(stats.len());
+                    format!("{}{}", fit(&host, host_width), stats)
+                }
+                None => "* * *".to_string(),
+            };
+            line.push_str(&format!("  {:<width$}", cell, width = VANTAGE_WIDTH));
+        }
+        writeln!(writer, "{}", line.trim_end())?;
+    }
+
+    writeln!(writer)?;
+    writeln!(writer, "AS paths:")?;
+    for path in &merged.as_paths {
+        let asns: Vec<String> = path.asns.iter().map(|a| format!("AS{}", a)).collect();
+        let rendered = if asns.is_empty() {
+            "(no ASN data)".to_string()
+        } else {
+            asns.join(" -> ")
+        };
+        writeln!(writer, "  {}: {}", path.vantage, rendered)?;
+    }
+    if !merged.shared_asns.is_empty() {
+        let shared: Vec<String> = merged
+            .shared_asns
+            .iter()
+            .map(|a| merged.as_label(*a))
+            .collect();
+        writeln!(writer, "Shared by all: {}", shared.join(", "))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::{AsnInfo, Target};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn session_with_path(path: &[(Ipv4Addr, u32)]) -> Session {
+        let target = Target::new(
+            "example.com".to_string(),
+            IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+        );
+        let mut session = Session::new(target, Config::default());
+        for (i, (ip, asn)) in path.iter().enumerate() {
+            let hop = session.hop_mut(i as u8 + 1).unwrap();
+            hop.record_sent();
+            hop.record_response(IpAddr::V4(*ip), Duration::from_millis(5));
+            hop.responders.get_mut(&IpAddr::V4(*ip)).unwrap().asn = Some(AsnInfo {
+                number: *asn,
+                name: format!("NET{}", asn),
+                prefix: None,
+                ..Default::default()
+            });
+        }
+        session.dest_ttl = Some(path.len() as u8);
+        session
+    }
+
+    #[test]
+    fn test_merge_two_vantages() {
+        let a = session_with_path(&[
+            (Ipv4Addr::new(10, 0, 0, 1), 64500),
+            (Ipv4Addr::new(198, 51, 100, 1), 3356),
+            (Ipv4Addr::new(93, 184, 216, 34), 15133),
+        ]);
+        let b = session_with_path(&[
+            (Ipv4Addr::new(10, 1, 0, 1), 64501),
+            (Ipv4Addr::new(203, 0, 113, 1), 174),
+            (Ipv4Addr::new(198, 51, 100, 9), 3356),
+            (Ipv4Addr::new(93, 184, 216, 34), 15133),
+        ]);
+        let merged =
+            merge_sessions(&[("office-a".to_string(), a), ("office-b".to_string(), b)]).unwrap();
+
+        assert_eq!(merged.hops.len(), 4);
+        assert!(merged.hops[3].vantages[0].is_none());
+        assert_eq!(merged.as_paths[1].asns, vec![64501, 174, 3356, 15133]);
+        assert_eq!(merged.shared_asns, vec![3356, 15133]);
+
+        let mut buf = Vec::new();
+        write_merged_report(&merged, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.contains("office-a"));
+        assert!(out.contains("Shared by all: AS3356 (NET3356), AS15133 (NET15133)"));
+
+        // JSON carries the names the text view shows
+        let json = serde_json::to_value(&merged).unwrap();
+        assert_eq!(json["as_names"]["3356"], "NET3356");
+        assert_eq!(json["as_names"].as_object().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_merge_rejects_different_targets() {
+        let a = session_with_path(&[(Ipv4Addr::new(10, 0, 0, 1), 1)]);
+        let mut b = session_with_path(&[(Ipv4Addr::new(10, 0, 0, 1), 1)]);
+        b.target = Target::new(
+            "other.net".to_string(),
+            IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+        );
+        assert!(merge_sessions(&[("a".to_string(), a), ("b".to_string(), b)]).is_err());
+    }
+}
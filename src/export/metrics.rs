@@ -0,0 +1,109 @@
+//! Prometheus text exposition of path signatures (`/metrics` on
+//! `--metrics` and `--serve-tui`)
+//!
+//! One `ttl_path_signature_info` series per target carries the current
+//! signatures as labels, so `changes(...)` or a label comparison is enough
+//! to alert on a path change without scraping hop tables.
+
+use std::fmt::Write as _;
+
+use crate::state::Session;
+
+/// Quote a label value per the exposition format
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Metrics for every session, in the order given
+pub fn render_metrics<'a>(sessions: impl IntoIterator<Item = &'a Session>) -> String {
+    let sessions: Vec<&Session> = sessions.into_iter().collect();
+    let mut out = String::new();
+
+    out.push_str(
+        "# HELP ttl_path_signature_info Hash of the current path; labels change when it does\n",
+    );
+    out.push_str("# TYPE ttl_path_signature_info gauge\n");
+    for session in &sessions {
+        let Some(current) = session.path_signatures.current() else {
+            continue;
+        };
+        let _ = writeln!(
+            out,
+            "ttl_path_signature_info{{target=\"{}\",ip=\"{}\",signature=\"{}\",as_signature=\"{}\"}} 1",
+            label(&session.target.original),
+            session.target.resolved,
+            current.signature,
+            current.as_signature
+        );
+    }
+
+    out.push_str("# HELP ttl_path_signature_changes_total Times the path signature changed\n");
+    out.push_str("# TYPE ttl_path_signature_changes_total counter\n");
+    for session in &sessions {
+        let _ = writeln!(
+            out,
+            "ttl_path_signature_changes_total{{target=\"{}\",ip=\"{}\"}} {}",
+            label(&session.target.original),
+            session.target.resolved,
+            session.path_signatures.changes
+        );
+    }
+
+    out.push_str(
+        "# HELP ttl_path_signature_since_seconds When the current path was first seen (Unix time)\n",
+    );
+    out.push_str("# TYPE ttl_path_signature_since_seconds gauge\n");
+    for session in &sessions {
+        let Some(current) = session.path_signatures.current() else {
+            continue;
+        };
+        let _ = writeln!(
+            out,
+            "ttl_path_signature_since_seconds{{target=\"{}\",ip=\"{}\"}} {}",
+            label(&session.target.original),
+            session.target.resolved,
+            current.since.timestamp()
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::Target;
+    use crate::state::signature::SIGNATURE_HOLD;
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    #[test]
+    fn test_metrics() {
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut session = Session::new(Target::new("a\"b".into(), ip), Config::default());
+        let quiet = Session::new(Target::new("quiet".into(), ip), Config::default());
+
+        let hop = session.hop_mut(1).unwrap();
+        hop.record_sent();
+        hop.record_response(ip, Duration::from_millis(5));
+        for _ in 0..SIGNATURE_HOLD {
+            session.record_path_signature();
+        }
+        let signature = &session.path_signatures.current().unwrap().signature;
+
+        let out = render_metrics([&session, &quiet]);
+        assert!(out.contains(&format!(
+            "ttl_path_signature_info{{target=\"a\\\"b\",ip=\"192.0.2.1\",signature=\"{}\",",
+            signature
+        )));
+        assert!(
+            out.contains("ttl_path_signature_changes_total{target=\"quiet\",ip=\"192.0.2.1\"} 0")
+        );
+        assert_eq!(out.matches("ttl_path_signature_info{").count(), 1);
+        assert!(out.contains("# TYPE ttl_path_signature_changes_total counter"));
+    }
+}
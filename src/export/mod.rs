@@ -1,7 +1,21 @@
+pub mod analyze;
 pub mod csv;
 pub mod json;
+pub mod merge;
+pub mod metrics;
+pub mod progress;
+pub mod redact;
 pub mod report;
+pub mod status;
+pub mod summary;
 
+pub use analyze::*;
 pub use csv::*;
 pub use json::*;
+pub use merge::*;
+pub use metrics::*;
+pub use progress::*;
+pub use redact::*;
 pub use report::*;
+pub use status::*;
+pub use summary::*;
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::state::Session;
+
+/// How often `--progress json` reports while a batch run is tracing
+pub const PROGRESS_PERIOD: Duration = Duration::from_secs(1);
+
+/// One `--progress json` record, written to stderr as a line of JSON, e.g.
+///
+/// ```text
+/// {"type":"progress","target":"example.com","ip":"93.184.216.34","rounds":4,"rounds_total":10,"hops_discovered":9,"dest_reached":true,"dest_ttl":12,"elapsed_s":4.1}
+/// ```
+///
+/// The last record for each target has `"type":"done"`.
+#[derive(Debug, Serialize)]
+pub struct Progress<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub target: &'a str,
+    pub ip: IpAddr,
+    /// Probe rounds sent so far
+    pub rounds: u64,
+    /// Rounds requested with -c (absent with --duration only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounds_total: Option<u64>,
+    /// TTLs that have answered at least once
+    pub hops_discovered: usize,
+    pub dest_reached: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dest_ttl: Option<u8>,
+    /// Seconds since the session started
+    pub elapsed_s: f64,
+    /// `--tag` labels
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: &'a BTreeMap<String, String>,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(session: &'a Session, done: bool) -> Self {
+        // Every round probes TTL 1 upwards once per flow; the busiest hop
+        // has seen every round
+        let flows = session.config.flows.max(1) as u64;
+        let rounds = session.hops.iter().map(|h| h.sent).max().unwrap_or(0) / flows;
+        let elapsed = (chrono::Utc::now() - session.started_at)
+            .to_std()
+            .unwrap_or_default();
+        Self {
+            kind: if done { "done" } else { "progress" },
+            target: &session.target.original,
+            ip: session.target.resolved,
+            rounds,
+            rounds_total: session.config.count,
+            hops_discovered: session.hops.iter().filter(|h| h.received > 0).count(),
+            dest_reached: session.dest_ttl.is_some(),
+            dest_ttl: session.dest_ttl,
+            elapsed_s: (elapsed.as_secs_f64() * 10.0).round() / 10.0,
+            tags: &session.config.tags,
+        }
+    }
+}
+
+/// Write one progress line per session
+pub fn write_progress<'a, W: Write>(
+    sessions: impl IntoIterator<Item = &'a Session>,
+    done: bool,
+    mut writer: W,
+) -> std::io::Result<()> {
+    for session in sessions {
+        serde_json::to_writer(&mut writer, &Progress::new(session, done))?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::Target;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_progress_records() {
+        let target = Target::new(
+            "example.com".to_string(),
+            IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+        );
+        let config = Config {
+            count: Some(10),
+            ..Config::default()
+        };
+        let mut session = Session::new(target, config);
+        for ttl in 1..=3 {
+            let hop = session.hop_mut(ttl).unwrap();
+            for _ in 0..4 {
+                hop.record_sent();
+            }
+            if ttl < 3 {
+                hop.record_response(
+                    IpAddr::V4(Ipv4Addr::new(10, 0, 0, ttl)),
+                    Duration::from_millis(5),
+                );
+            }
+        }
+
+        let mut out = Vec::new();
+        write_progress([&session], false, &mut out).unwrap();
+        let line = String::from_utf8(out).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["type"], "progress");
+        assert_eq!(value["target"], "example.com");
+        assert_eq!(value["rounds"], 4);
+        assert_eq!(value["rounds_total"], 10);
+        assert_eq!(value["hops_discovered"], 2);
+        assert_eq!(value["dest_reached"], false);
+        assert!(value.get("dest_ttl").is_none());
+        assert!(value.get("tags").is_none());
+
+        session.dest_ttl = Some(2);
+        session.config.tags.insert("site".into(), "fra1".into());
+        let value = serde_json::to_value(Progress::new(&session, true)).unwrap();
+        assert_eq!(value["type"], "done");
+        assert_eq!(value["dest_ttl"], 2);
+        assert_eq!(value["tags"]["site"], "fra1");
+    }
+}
@@ -0,0 +1,458 @@
+//! Redaction of exported sessions (`--redact`)
+//!
+//! Makes a copy of a session that is safe to post publicly. Internal
+//! addresses (and every address in the first N hops) are swapped for
+//! stand-ins from the benchmarking and documentation ranges, so the path
+//! still reads as a path: the same address always gets the same stand-in
+//! within one export. Hostnames are replaced by a keyed hash (or masked),
+//! and plugin and enrichment data, which tends to name devices and owners,
+//! is dropped with them. The first hops lose their ASN and location too.
+//!
+//! The work is done on the session's JSON, so an address is caught wherever
+//! it appears: responders, events, notes, config. The result carries a
+//! `redacted` note describing what was removed.
+
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::error::{Result, TtlError};
+use crate::state::Session;
+
+/// What `--redact` removes from exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Redaction {
+    /// Replace private, shared (CGNAT), loopback and link-local addresses
+    pub private: bool,
+    /// Replace every hostname
+    pub hostnames: bool,
+    /// Replace everything about hops 1..=N (addresses, names, ASN, location)
+    pub hops: u8,
+    /// Hostnames become "redacted" instead of a hash (hashes keep repeated
+    /// names recognizable within the file)
+    pub mask: bool,
+}
+
+/// `--redact` with no value
+pub const DEFAULT_REDACT: &str = "private,hostnames";
+
+impl Redaction {
+    /// Parse a comma-separated list: `private`, `hostnames`, `hops=N`, `mask`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut redaction = Self {
+            private: false,
+            hostnames: false,
+            hops: 0,
+            mask: false,
+        };
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match item {
+                "private" => redaction.private = true,
+                "hostnames" => redaction.hostnames = true,
+                "mask" => redaction.mask = true,
+                _ => match item.strip_prefix("hops=") {
+                    Some(n) => {
+                        redaction.hops = n
+                            .parse()
+                            .map_err(|_| format!("Invalid hop count in '{}'", item))?;
+                    }
+                    None => {
+                        return Err(format!(
+                            "Unknown redaction '{}', expected private, hostnames, hops=N or mask",
+                            item
+                        ));
+                    }
+                },
+            }
+        }
+        if !redaction.private && !redaction.hostnames && redaction.hops == 0 {
+            return Err("Nothing to redact; use private, hostnames and/or hops=N".into());
+        }
+        Ok(redaction)
+    }
+
+    /// Note stored in the export, e.g. "private addresses, hostnames (hashed), hops 1-2"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.private {
+            parts.push("private addresses".to_string());
+        }
+        if self.hostnames {
+            let how = if self.mask { "masked" } else { "hashed" };
+            parts.push(format!("hostnames ({})", how));
+        }
+        match self.hops {
+            0 => {}
+            1 => parts.push("hop 1".to_string()),
+            n => parts.push(format!("hops 1-{}", n)),
+        }
+        parts.join(", ")
+    }
+}
+
+/// Whether an address only means something inside its own network
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let o = v4.octets();
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || (o[0] == 100 && (o[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // unique local
+                || (first & 0xffc0) == 0xfe80 // link-local
+        }
+    }
+}
+
+/// Swaps addresses and names for stand-ins, consistently within one export
+struct Redactor {
+    redaction: Redaction,
+    /// Addresses of the first `hops` hops, replaced even when public
+    forced: Vec<IpAddr>,
+    stand_ins: HashMap<IpAddr, IpAddr>,
+    next_v4: u32,
+    next_v6: u128,
+    /// Random key, so hashes can't be matched against a list of likely names
+    hasher: RandomState,
+}
+
+impl Redactor {
+    fn new(redaction: Redaction) -> Self {
+        Self {
+            redaction,
+            forced: Vec::new(),
+            stand_ins: HashMap::new(),
+            next_v4: 0,
+            next_v6: 0,
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn redacts(&self, ip: IpAddr) -> bool {
+        (self.redaction.private && is_internal(ip)) || self.forced.contains(&ip)
+    }
+
+    /// Stand-in for `ip`: 198.18.0.0/15 (RFC 2544) or 2001:db8::/32 (RFC 3849)
+    fn stand_in(&mut self, ip: IpAddr) -> IpAddr {
+        if let Some(&other) = self.stand_ins.get(&ip) {
+            return other;
+        }
+        let other = match ip {
+            IpAddr::V4(_) => {
+                self.next_v4 += 1;
+                IpAddr::V4(Ipv4Addr::from(
+                    u32::from(Ipv4Addr::new(198, 18, 0, 0)) + (self.next_v4 & 0x1ffff),
+                ))
+            }
+            IpAddr::V6(_) => {
+                self.next_v6 += 1;
+                IpAddr::V6(Ipv6Addr::from(
+                    u128::from(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0)) + self.next_v6,
+                ))
+            }
+        };
+        self.stand_ins.insert(ip, other);
+        other
+    }
+
+    fn hostname(&self, name: &str) -> String {
+        if self.redaction.mask {
+            "redacted".to_string()
+        } else {
+            format!("host-{:08x}", self.hasher.hash_one(name) as u32)
+        }
+    }
+
+    /// Redact an address or prefix ("10.0.0.0/24") in a single token
+    fn token(&mut self, token: &str) -> Option<String> {
+        let (addr, len) = match token.split_once('/') {
+            Some((addr, len)) if len.parse::<u8>().is_ok() => (addr, Some(len)),
+            _ => (token, None),
+        };
+        let ip: IpAddr = addr.parse().ok()?;
+        if !self.redacts(ip) {
+            return None;
+        }
+        let other = self.stand_in(ip);
+        Some(match len {
+            Some(len) => format!("{}/{}", other, len),
+            None => other.to_string(),
+        })
+    }
+
+    /// Redact addresses inside free text ("Path change at hop 3 (10.0.0.1)")
+    fn text(&mut self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while !rest.is_empty() {
+            let start = rest
+                .find(|c: char| c.is_ascii_hexdigit() || c == ':')
+                .unwrap_or(rest.len());
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !(c.is_ascii_hexdigit() || matches!(c, ':' | '.' | '/')))
+                .unwrap_or(rest.len());
+            let token = &rest[..end];
+            // A sentence-ending period isn't part of the address
+            let trimmed = token.trim_end_matches('.');
+            match self.token(trimmed) {
+                Some(other) => {
+                    out.push_str(&other);
+                    out.push_str(&token[trimmed.len()..]);
+                }
+                None => out.push_str(token),
+            }
+            rest = &rest[end..];
+        }
+        out
+    }
+
+    fn value(&mut self, value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                let redacted = self.text(s);
+                *s = redacted;
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.value(v)),
+            Value::Object(map) => self.object(map),
+            _ => {}
+        }
+    }
+
+    fn object(&mut self, map: &mut Map<String, Value>) {
+        let entries = std::mem::take(map);
+        for (key, mut value) in entries {
+            // Labels name internal devices just like PTR records do
+            if (key == "hostname" || key == "label")
+                && self.redaction.hostnames
+                && let Value::String(ref name) = value
+            {
+                value = Value::String(self.hostname(name));
+            } else {
+                self.value(&mut value);
+            }
+            // Responders are keyed by address
+            let key = self.token(&key).unwrap_or(key);
+            map.insert(key, value);
+        }
+    }
+}
+
+/// Copy of `session` with `redaction` applied and noted in `redacted`
+pub fn redact_session(session: &Session, redaction: Redaction) -> Result<Session> {
+    let mut redactor = Redactor::new(redaction);
+    let mut copy = session.clone();
+
+    // Everything about the first hops goes: they describe the local network
+    // and, with ASN and location, where it is
+    if redaction.hops > 0 {
+        for hop in copy.hops.iter_mut().filter(|h| h.ttl <= redaction.hops) {
+            for stats in hop.responders.values_mut() {
+                redactor.forced.push(stats.ip);
+                redactor.forced.extend(stats.aliases.iter().copied());
+                stats.hostname = None;
+                stats.asn = None;
+                stats.geo = None;
+                stats.ix = None;
+                stats.prefix_origin = None;
+                stats.mpls_labels = None;
+                stats.interface = None;
+                stats.plugin_data.clear();
+                stats.extra.clear();
+            }
+        }
+        copy.source_geo = None;
+        copy.config.origin = None;
+    }
+
+    // Plugin and enrichment annotations (CMDB device names, owners) name
+    // things the way hostnames do
+    if redaction.hostnames {
+        for stats in copy.hops.iter_mut().flat_map(|h| h.responders.values_mut()) {
+            stats.plugin_data.clear();
+            stats.extra.clear();
+        }
+    }
+
+    // Stand-ins numbered along the path, before the walk meets anything else
+    for hop in &copy.hops {
+        let mut ips: Vec<IpAddr> = hop.responders.keys().copied().collect();
+        ips.sort_unstable();
+        ips.retain(|&ip| redactor.redacts(ip));
+        for ip in ips {
+            redactor.stand_in(ip);
+        }
+    }
+
+    let mut value = serde_json::to_value(&copy)
+        .map_err(|e| TtlError::export_from("serializing session failed", e))?;
+    if redaction.hostnames
+        && let Some(original) = value.pointer_mut("/target/original")
+        && let Value::String(name) = original
+        && name.parse::<IpAddr>().is_err()
+    {
+        *name = redactor.hostname(name);
+    }
+    redactor.value(&mut value);
+    let mut redacted: Session = serde_json::from_value(value)
+        .map_err(|e| TtlError::export_from("redacted session no longer parses", e))?;
+
+    // Recent samples aren't serialized; carry them over for text exports
+    for (hop, orig) in redacted.hops.iter_mut().zip(&copy.hops) {
+        hop.recent_results = orig.recent_results.clone();
+        hop.late_samples = orig.late_samples.clone();
+        hop.window = orig.window.clone();
+        for (ip, orig_stats) in &orig.responders {
+            let ip = redactor.stand_ins.get(ip).unwrap_or(ip);
+            if let Some(stats) = hop.responders.get_mut(ip) {
+                stats.last_rtt = orig_stats.last_rtt;
+                stats.recent = orig_stats.recent.clone();
+                stats.recent_jitter = orig_stats.recent_jitter.clone();
+                stats.samples = orig_stats.samples.clone();
+                stats.window = orig_stats.window.clone();
+            }
+        }
+    }
+    redacted.redacted = Some(redaction.describe());
+    Ok(redacted)
+}
+
+/// `session` as it should be exported: redacted copy when `redaction` is set
+pub fn export_view(session: &Session, redaction: Option<Redaction>) -> Result<Cow<'_, Session>> {
+    match redaction {
+        Some(redaction) => redact_session(session, redaction).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(session)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::Target;
+    use std::time::Duration;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn sample_session() -> Session {
+        let mut session = Session::new(
+            Target::new("example.com".to_string(), ip("93.184.216.34")),
+            Config::default(),
+        );
+        for (ttl, addr, name) in [
+            (1, "192.168.1.1", "router.home.lan"),
+            (2, "100.64.0.1", "cgnat.isp.example"),
+            (3, "203.0.113.9", "core1.isp.example"),
+        ] {
+            let hop = session.hop_mut(ttl).unwrap();
+            hop.record_sent();
+            hop.record_response(ip(addr), Duration::from_millis(ttl as u64));
+            hop.responders.get_mut(&ip(addr)).unwrap().hostname = Some(name.to_string());
+        }
+        session.source_ip = Some(ip("192.168.1.20"));
+        session.add_note(Some(1), "reboot of 192.168.1.1 fixed it.");
+        session
+    }
+
+    #[test]
+    fn test_parse() {
+        let r = Redaction::parse(DEFAULT_REDACT).unwrap();
+        assert!(r.private && r.hostnames && !r.mask);
+        assert_eq!(r.hops, 0);
+        let r = Redaction::parse("hops=2,mask").unwrap();
+        assert_eq!(r.hops, 2);
+        assert_eq!(r.describe(), "hops 1-2");
+        assert!(Redaction::parse("mask").is_err());
+        assert!(Redaction::parse("hops=x").is_err());
+        assert!(Redaction::parse("everything").is_err());
+    }
+
+    #[test]
+    fn test_private_addresses_replaced_everywhere() {
+        let session = sample_session();
+        let out = redact_session(&session, Redaction::parse("private").unwrap()).unwrap();
+        let json = serde_json::to_string(&out).unwrap();
+        for leak in ["192.168.1.1", "192.168.1.20", "100.64.0.1"] {
+            assert!(!json.contains(leak), "{} leaked", leak);
+        }
+        // Public hop and hostnames untouched
+        assert!(json.contains("203.0.113.9"));
+        assert!(json.contains("core1.isp.example"));
+        assert_eq!(out.redacted.as_deref(), Some("private addresses"));
+
+        // Consistent stand-ins: the note names the same address as hop 1
+        let hop1 = out.hop(1).unwrap().primary.unwrap();
+        assert_eq!(hop1, ip("198.18.0.1"));
+        assert_eq!(out.notes[0].text, "reboot of 198.18.0.1 fixed it.");
+        assert_eq!(out.hop(1).unwrap().responders[&hop1].received, 1);
+    }
+
+    #[test]
+    fn test_hostnames_and_first_hops() {
+        let session = sample_session();
+        let out = redact_session(&session, Redaction::parse("hostnames,hops=1").unwrap()).unwrap();
+        let json = serde_json::to_string(&out).unwrap();
+        assert!(!json.contains("router.home.lan"));
+        assert!(!json.contains("core1.isp.example"));
+        assert!(!json.contains("example.com"));
+        // hops=1 replaces hop 1; the CGNAT hop 2 stays without `private`
+        assert!(!json.contains("192.168.1.1"));
+        assert!(json.contains("100.64.0.1"));
+
+        let stats = out.hop(3).unwrap().primary_stats().unwrap();
+        assert!(stats.hostname.as_ref().unwrap().starts_with("host-"));
+        assert!(
+            out.hop(1)
+                .unwrap()
+                .primary_stats()
+                .unwrap()
+                .hostname
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_hostnames_drop_annotations_on_every_hop() {
+        let mut session = sample_session();
+        let stats = session
+            .hop_mut(3)
+            .unwrap()
+            .responders
+            .get_mut(&ip("203.0.113.9"))
+            .unwrap();
+        stats
+            .extra
+            .insert("device".to_string(), "fra1-core-01 (netops)".to_string());
+        stats
+            .plugin_data
+            .insert("owner".to_string(), "alice@example.com".to_string());
+
+        let out = redact_session(&session, Redaction::parse("hostnames,hops=1").unwrap()).unwrap();
+        let json = serde_json::to_string(&out).unwrap();
+        assert!(!json.contains("fra1-core-01"));
+        assert!(!json.contains("alice@example.com"));
+        let stats = out.hop(3).unwrap().primary_stats().unwrap();
+        assert!(stats.extra.is_empty() && stats.plugin_data.is_empty());
+
+        // Without hostname redaction annotations stay
+        let out = redact_session(&session, Redaction::parse("private").unwrap()).unwrap();
+        assert!(
+            serde_json::to_string(&out)
+                .unwrap()
+                .contains("fra1-core-01")
+        );
+    }
+}
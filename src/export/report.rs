@@ -1,80 +1,687 @@
 use std::io::Write;
+use std::time::Duration;
 
-use crate::state::Session;
+use crate::state::paths::flow_list;
+use crate::state::{
+    Hop, HostTrim, LabelMode, NumberFormat, ResponderStats, Session, dscp_label, format_bit_rate,
+    mss_to_mtu, trim_host,
+};
+
+/// Host column width used when `--report-wide` is not set
+const HOST_WIDTH: usize = 46;
+/// Narrowest shortened name kept in front of the address
+const MIN_NAME_WIDTH: usize = 8;
+
+/// Column order used when `--order` is not given (matches the historical layout)
+pub const DEFAULT_REPORT_ORDER: &str = "LSABWVJ";
+
+/// A statistics column in the text report, selected with mtr-style letters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportColumn {
+    /// `L` - loss percentage
+    Loss,
+    /// `C` - loss caused at this hop (persists to the destination)
+    Caused,
+    /// `D` - dropped probes
+    Dropped,
+    /// `R` - received responses
+    Received,
+    /// `S` - sent probes
+    Sent,
+    /// `N` - newest (last) RTT
+    Last,
+    /// `B` - best (min) RTT
+    Best,
+    /// `A` - average RTT
+    Avg,
+    /// `W` - worst (max) RTT
+    Worst,
+    /// `V` - standard deviation
+    StdDev,
+    /// `J` - current jitter
+    Jitter,
+    /// `M` - mean jitter
+    JitterAvg,
+    /// `X` - worst jitter
+    JitterMax,
+    /// `T` - late replies (arrived after their probe timed out)
+    Late,
+    /// `I` - 95% confidence interval half-width for the average RTT
+    Ci95,
+    /// `U` - share of minutes the responder answered in (uptime)
+    Availability,
+}
+
+impl ReportColumn {
+    /// Map an mtr-style field letter to a column
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'L' => Some(Self::Loss),
+            'C' => Some(Self::Caused),
+            'D' => Some(Self::Dropped),
+            'R' => Some(Self::Received),
+            'S' => Some(Self::Sent),
+            'N' => Some(Self::Last),
+            'B' => Some(Self::Best),
+            'A' => Some(Self::Avg),
+            'W' => Some(Self::Worst),
+            'V' => Some(Self::StdDev),
+            'J' => Some(Self::Jitter),
+            'M' => Some(Self::JitterAvg),
+            'X' => Some(Self::JitterMax),
+            'T' => Some(Self::Late),
+            'I' => Some(Self::Ci95),
+            'U' => Some(Self::Availability),
+            _ => None,
+        }
+    }
+
+    /// Parse an `--order` string such as "LSABWVJ" into columns
+    pub fn parse_order(order: &str) -> Result<Vec<Self>, String> {
+        if order.is_empty() {
+            return Err("--order must name at least one column".into());
+        }
+        order
+            .chars()
+            .map(|c| {
+                Self::from_char(c).ok_or_else(|| {
+                    format!(
+                        "Unknown --order field '{}'. Valid fields: L C D R S N B A W V J M X T I U",
+                        c
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            Self::Loss => "Loss%",
+            Self::Caused => "Attr%",
+            Self::Dropped => "Drop",
+            Self::Received => "Recv",
+            Self::Sent => "Sent",
+            Self::Last => "Last",
+            Self::Best => "Min",
+            Self::Avg => "Avg",
+            Self::Worst => "Max",
+            Self::StdDev => "StdDev",
+            Self::Jitter => "Jitter",
+            Self::JitterAvg => "JAvg",
+            Self::JitterMax => "JMax",
+            Self::Late => "Late",
+            Self::Ci95 => "±95%",
+            Self::Availability => "Avail",
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Self::Loss
+            | Self::Caused
+            | Self::Dropped
+            | Self::Received
+            | Self::Sent
+            | Self::Late
+            | Self::Availability => 6,
+            _ => 8,
+        }
+    }
+
+    fn value(
+        self,
+        hop: &Hop,
+        stats: Option<&ResponderStats>,
+        caused: Option<f64>,
+        nf: &NumberFormat,
+    ) -> String {
+        // RTT columns only make sense once the primary responder has answered
+        let rtt = |f: fn(&ResponderStats) -> Option<Duration>| {
+            stats
+                .filter(|s| s.received > 0)
+                .and_then(f)
+                .map(|d| nf.rtt(d, 1))
+                .unwrap_or_else(|| "-".into())
+        };
+        match self {
+            Self::Loss => nf.percent(hop.loss_pct(), 1),
+            Self::Caused => caused
+                .map(|pct| nf.percent(pct, 1))
+                .unwrap_or_else(|| "-".into()),
+            Self::Dropped => nf.count(hop.sent.saturating_sub(hop.received)),
+            Self::Received => nf.count(hop.received),
+            Self::Sent => nf.count(hop.sent),
+            Self::Last => rtt(|s| s.last_rtt()),
+            Self::Best => rtt(|s| Some(s.min_rtt)),
+            Self::Avg => rtt(|s| Some(s.avg_rtt())),
+            Self::Worst => rtt(|s| Some(s.max_rtt)),
+            Self::StdDev => rtt(|s| Some(s.stddev())),
+            Self::Jitter => rtt(|s| Some(s.jitter())),
+            Self::JitterAvg => rtt(|s| Some(s.jitter_avg())),
+            Self::JitterMax => rtt(|s| Some(s.jitter_max())),
+            Self::Late => nf.count(hop.late),
+            Self::Ci95 => stats
+                .and_then(|s| s.mean_ci95)
+                .map(|us| nf.rtt(Duration::from_micros(us as u64), 1))
+                .unwrap_or_else(|| "-".into()),
+            Self::Availability => stats
+                .and_then(|s| hop.availability(s))
+                .map(|(pct, _)| nf.percent(pct, 0))
+                .unwrap_or_else(|| "-".into()),
+        }
+    }
+}
+
+/// Layout options for the text report
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    /// Size the host column to fit every hostname instead of truncating
+    pub wide: bool,
+    /// Print the preamble and column header
+    pub header: bool,
+    /// Statistics columns, in display order
+    pub columns: Vec<ReportColumn>,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            wide: false,
+            header: true,
+            columns: ReportColumn::parse_order(DEFAULT_REPORT_ORDER)
+                .expect("default order is valid"),
+        }
+    }
+}
+
+fn host_label(hop: &Hop, mode: LabelMode) -> String {
+    if let Some(stats) = hop.primary_stats() {
+        match stats.display_name(mode) {
+            Some(name) => format!("{} ({})", name, stats.ip),
+            None => stats.ip.to_string(),
+        }
+    } else if hop.received == 0 {
+        "* * *".to_string()
+    } else {
+        "???".to_string()
+    }
+}
+
+/// Host cell within `max_len` characters. The name is shortened first so
+/// the address stays whole.
+fn fit_host(hop: &Hop, mode: LabelMode, trim: HostTrim, max_len: usize) -> String {
+    let full = host_label(hop, mode);
+    if full.chars().count() <= max_len {
+        return full;
+    }
+    if let Some(stats) = hop.primary_stats()
+        && let Some(name) = stats.display_name(mode)
+    {
+        let addr = format!(" ({})", stats.ip);
+        let room = max_len.saturating_sub(addr.chars().count());
+        if room >= MIN_NAME_WIDTH {
+            return format!("{}{}", trim_host(&name, room, trim), addr);
+        }
+    }
+    trim_host(&full, max_len, trim)
+}
 
 /// Generate a text report similar to mtr --report
-pub fn generate_report<W: Write>(session: &Session, mut writer: W) -> std::io::Result<()> {
-    writeln!(
-        writer,
-        "ttl report for {} ({})",
-        session.target.original, session.target.resolved
-    )?;
-    writeln!(
-        writer,
-        "Started: {}",
-        session.started_at.format("%Y-%m-%d %H:%M:%S UTC")
-    )?;
-    if let Some(ref iface) = session.config.interface {
-        writeln!(writer, "Interface: {}", iface)?;
-    }
-    writeln!(writer)?;
-
-    // Header
-    writeln!(
-        writer,
-        "{:>3}  {:<46} {:>6} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8}",
-        "#", "Host", "Loss%", "Sent", "Avg", "Min", "Max", "StdDev", "Jitter"
-    )?;
-    writeln!(writer, "{}", "-".repeat(116))?;
+pub fn generate_report<W: Write>(session: &Session, writer: W) -> std::io::Result<()> {
+    generate_report_with(session, &ReportOptions::default(), writer)
+}
+
+/// Generate a text report using the given column layout
+pub fn generate_report_with<W: Write>(
+    session: &Session,
+    opts: &ReportOptions,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let nf = &session.config.number_format;
 
     // Only show hops up to the destination
     let max_ttl = session.dest_ttl.unwrap_or(session.config.max_ttl);
-    for hop in &session.hops {
-        if hop.sent == 0 || hop.ttl > max_ttl {
-            continue;
+    let hops: Vec<&Hop> = session
+        .hops
+        .iter()
+        .filter(|h| h.sent > 0 && h.ttl <= max_ttl)
+        .collect();
+
+    let hosts: Vec<String> = hops
+        .iter()
+        .map(|h| host_label(h, session.config.label_mode))
+        .collect();
+    let host_width = if opts.wide {
+        hosts
+            .iter()
+            .map(|h| h.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(HOST_WIDTH)
+    } else {
+        HOST_WIDTH
+    };
+
+    if opts.header {
+        writeln!(
+            writer,
+            "ttl report for {} ({})",
+            session.target.original, session.target.resolved
+        )?;
+        writeln!(
+            writer,
+            "Started: {}",
+            session
+                .config
+                .format_time(&session.started_at, "%Y-%m-%d %H:%M:%S %Z")
+        )?;
+        if session.target.addresses.len() > 1 {
+            let all: Vec<String> = session
+                .target
+                .addresses
+                .iter()
+                .map(|ip| ip.to_string())
+                .collect();
+            writeln!(writer, "Resolved: {}", all.join(", "))?;
+        }
+        if let Some(ref selection) = session.target.selection {
+            writeln!(writer, "Address: {}", selection)?;
+        }
+        if let Some(ref iface) = session.config.interface {
+            writeln!(writer, "Interface: {}", iface)?;
+        }
+        if !session.config.tags.is_empty() {
+            writeln!(writer, "Tags: {}", session.config.tags_label(", "))?;
         }
+        if let Some(ref redacted) = session.redacted {
+            writeln!(writer, "Redacted: {}", redacted)?;
+        }
+        writeln!(writer)?;
 
-        let host = if let Some(stats) = hop.primary_stats() {
-            if let Some(ref hostname) = stats.hostname {
-                format!("{} ({})", hostname, stats.ip)
-            } else {
-                stats.ip.to_string()
-            }
-        } else if hop.received == 0 {
-            "* * *".to_string()
+        let mut line = format!("{:>3}  {:<width$}", "#", "Host", width = host_width);
+        for col in &opts.columns {
+            line.push_str(&format!(" {:>width$}", col.header(), width = col.width()));
+        }
+        writeln!(writer, "{}", line)?;
+        writeln!(writer, "{}", "-".repeat(line.chars().count()))?;
+    }
+
+    let caused = session.attributable_loss();
+    for (hop, host) in hops.iter().zip(&hosts) {
+        let host = if opts.wide {
+            host.clone()
         } else {
-            "???".to_string()
+            fit_host(
+                hop,
+                session.config.label_mode,
+                session.config.host_trim,
+                HOST_WIDTH,
+            )
         };
+        let stats = hop.primary_stats();
+        let mut line = format!("{:>3}  {:<width$}", hop.ttl, host, width = host_width);
+        for col in &opts.columns {
+            line.push_str(&format!(
+                " {:>width$}",
+                col.value(hop, stats, caused.get(&hop.ttl).copied(), nf),
+                width = col.width()
+            ));
+        }
+        writeln!(writer, "{}", line)?;
+    }
 
-        let (avg, min, max, stddev, jitter) = if let Some(stats) = hop.primary_stats() {
-            if stats.received > 0 {
-                (
-                    format!("{:.1}ms", stats.avg_rtt().as_secs_f64() * 1000.0),
-                    format!("{:.1}ms", stats.min_rtt.as_secs_f64() * 1000.0),
-                    format!("{:.1}ms", stats.max_rtt.as_secs_f64() * 1000.0),
-                    format!("{:.1}ms", stats.stddev().as_secs_f64() * 1000.0),
-                    format!("{:.1}ms", stats.jitter().as_secs_f64() * 1000.0),
-                )
-            } else {
-                ("-".into(), "-".into(), "-".into(), "-".into(), "-".into())
-            }
+    if let Some(ref slo) = session.slo {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "SLO: {} ({})",
+            slo.slo,
+            if slo.met() { "met" } else { "MISSED" }
+        )?;
+        if let (Some(ms), Some(budget)) = (slo.slo.latency_ms, slo.latency_budget()) {
+            writeln!(
+                writer,
+                "  latency  {} of replies over {}ms (allowed {}), budget left {}",
+                nf.percent(slo.slow_pct(), 1),
+                ms,
+                nf.percent(100.0 - slo.slo.percentile, 1),
+                nf.percent(budget * 100.0, 0)
+            )?;
+        }
+        if let (Some(pct), Some(budget)) = (slo.slo.loss_pct, slo.loss_budget()) {
+            writeln!(
+                writer,
+                "  loss     {} of {} probes (allowed {}%), budget left {}",
+                nf.percent(slo.loss_pct(), 1),
+                nf.count(slo.probes),
+                pct,
+                nf.percent(budget * 100.0, 0)
+            )?;
+        }
+    }
+
+    if let Some(ref load) = session.load {
+        writeln!(writer)?;
+        let heading = format!(
+            "Latency under load ({} upload)",
+            format_bit_rate(load.spec.rate_bps)
+        );
+        match session.load_result() {
+            Some(result) => writeln!(
+                writer,
+                "{}: grade {}, {}{}ms at hop {}",
+                heading,
+                result.grade,
+                if result.delta_ms >= 0.0 { "+" } else { "" },
+                nf.number(result.delta_ms, 1),
+                result.ttl
+            )?,
+            None => writeln!(writer, "{}: no loaded replies yet", heading)?,
+        }
+        let ms = |d: Duration| nf.rtt(d, 1);
+        for (ttl, hop) in load.hops.iter().filter(|(ttl, _)| **ttl <= max_ttl) {
+            writeln!(
+                writer,
+                "  {:>3}  idle {:>9}  loaded {:>9}  {:>10}",
+                ttl,
+                hop.idle.mean().map(ms).unwrap_or_else(|| "-".into()),
+                hop.loaded.mean().map(ms).unwrap_or_else(|| "-".into()),
+                hop.delta_ms()
+                    .map(|d| format!("{}{}ms", if d >= 0.0 { "+" } else { "" }, nf.number(d, 1)))
+                    .unwrap_or_default()
+            )?;
+        }
+    }
+
+    let distances: Vec<_> = hops
+        .iter()
+        .filter_map(|hop| Some((hop, session.hop_distance(hop)?)))
+        .collect();
+    if let (Some((origin, source)), false) = (session.origin(), distances.is_empty()) {
+        writeln!(writer)?;
+        writeln!(writer, "Distance from {} ({}):", origin, source)?;
+        let ms = |d: Duration| nf.rtt(d, 1);
+        for (hop, distance) in distances {
+            let avg = hop.primary_stats().map(|s| s.avg_rtt()).unwrap_or_default();
+            writeln!(
+                writer,
+                "  {:>3}  {:>6} km  min {:>8}  avg {:>8}  {:>6}",
+                hop.ttl,
+                nf.number(distance.km, 0),
+                ms(distance.min_rtt),
+                ms(avg),
+                distance
+                    .inflation(avg)
+                    .map(|x| format!("{}x", nf.number(x, 1)))
+                    .unwrap_or_default()
+            )?;
+        }
+    }
+
+    let links = session.inferred_links();
+    if !links.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "Links:")?;
+        for link in &links {
+            writeln!(
+                writer,
+                "  hop {} -> {}  {} - {} (/{})",
+                link.near_ttl,
+                link.near_ttl + 1,
+                link.near,
+                link.far,
+                link.prefix_len
+            )?;
+        }
+    }
+
+    let remarks = session.dscp_remarks();
+    if !remarks.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "DSCP remarked:")?;
+        for remark in &remarks {
+            writeln!(
+                writer,
+                "  before hop {}: {} -> {}",
+                remark.ttl,
+                dscp_label(remark.from),
+                dscp_label(remark.to)
+            )?;
+        }
+    }
+
+    let changes = session.ecn_changes();
+    if !changes.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "ECN marking changed:")?;
+        for change in &changes {
+            writeln!(
+                writer,
+                "  before hop {}: {} -> {}{}",
+                change.ttl,
+                change.from.as_str(),
+                change.to.as_str(),
+                if change.bleached() { " (cleared)" } else { "" }
+            )?;
+        }
+    }
+
+    if let Some(hop) = session.first_nat_hop().and_then(|ttl| session.hop(ttl))
+        && let Some(nat) = hop.nat_info.as_ref()
+    {
+        let rewritten = if nat.port_rewritten > 0 {
+            "source port"
         } else {
-            ("-".into(), "-".into(), "-".into(), "-".into(), "-".into())
+            "source address"
         };
-
+        writeln!(writer)?;
         writeln!(
             writer,
-            "{:>3}  {:<46} {:>5.1}% {:>6} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            "NAT: {} rewritten before hop {} ({:.0}% of quoted probes)",
+            rewritten,
             hop.ttl,
-            host,
-            hop.loss_pct(),
-            hop.sent,
-            avg,
-            min,
-            max,
-            stddev,
-            jitter
+            nat.nat_percentage()
+        )?;
+    }
+
+    let tunnels = session.inferred_tunnels();
+    if !tunnels.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "Suspected MPLS tunnels:")?;
+        for tunnel in &tunnels {
+            writeln!(writer, "  {}", tunnel.describe())?;
+        }
+    }
+
+    let tree = session.path_tree();
+    let paths = tree.distinct_paths();
+    if paths.len() > 1 {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "ECMP paths ({} over {} flows):",
+            paths.len(),
+            tree.flows.len()
         )?;
+        for path in &paths {
+            let route: Vec<_> = path.hops.iter().map(|(_, ip)| ip.to_string()).collect();
+            writeln!(
+                writer,
+                "  flows {:<8} {}",
+                flow_list(&path.flows),
+                route.join(" > ")
+            )?;
+        }
+        for route in &tree.flows {
+            let Some(&(last_ttl, _)) = route.hops.last() else {
+                continue;
+            };
+            let port = route
+                .src_port
+                .map(|p| format!(" (:{})", p))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "  flow {}{}: {} sent, {} loss at hop {}",
+                route.flow_id,
+                port,
+                route.sent,
+                session.config.number_format.percent(route.loss_pct, 1),
+                last_ttl
+            )?;
+        }
+        for hop in &session.hops {
+            let Some(ecmp) = hop.ecmp else {
+                continue;
+            };
+            if let Some(badge) = ecmp.badge() {
+                writeln!(
+                    writer,
+                    "  hop {}: {} ({:.0}% confidence over {} flows)",
+                    hop.ttl,
+                    badge,
+                    ecmp.confidence * 100.0,
+                    ecmp.flows
+                )?;
+            }
+        }
+    }
+
+    if let Some(ref routing_loop) = session.routing_loop {
+        writeln!(writer)?;
+        writeln!(writer, "Routing loop: {}", routing_loop.describe())?;
+    }
+
+    if let Some(current) = session.path_signatures.current() {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "Path signature: {} (AS path {}), {} change{}",
+            current.signature,
+            current.as_signature,
+            session.path_signatures.changes,
+            if session.path_signatures.changes == 1 {
+                ""
+            } else {
+                "s"
+            }
+        )?;
+    }
+
+    let correlation = session.loss_correlation();
+    if !correlation.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "Loss correlation:")?;
+        for pair in &correlation {
+            let verdict = if pair.shared() {
+                ", likely one fault"
+            } else {
+                ""
+            };
+            writeln!(writer, "  {}{}", pair.describe(), verdict)?;
+        }
+    }
+
+    if let Some(redirect) = session.redirect_warning() {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "Warning: {} redirected traffic for this target to {} ({}x); \
+             first-hop results may not reflect the path packets actually take",
+            redirect.router, redirect.gateway, redirect.count
+        )?;
+    }
+
+    if let Some(estimate) = session.path_mtu() {
+        writeln!(writer)?;
+        writeln!(writer, "Path MTU: {} ({})", estimate.mtu, estimate.source)?;
+        let hints = &session.mtu_hints;
+        if let Some(mss) = hints.syn_ack_mss {
+            let mtu = mss_to_mtu(mss, session.target.resolved.is_ipv6());
+            writeln!(writer, "  SYN-ACK MSS  {} (MTU {})", mss, mtu)?;
+        }
+        if let Some(frag) = hints.frag_needed {
+            writeln!(writer, "  Frag needed  {} at hop {}", frag.mtu, frag.ttl)?;
+        }
+        if let Some(mtu) = session.pmtud.as_ref().and_then(|p| p.discovered_mtu) {
+            writeln!(writer, "  PMTUD        {}", mtu)?;
+        }
+    }
+
+    if let Some(ref sweep) = session.df_sweep
+        && !sweep.hops.is_empty()
+    {
+        writeln!(writer)?;
+        writeln!(writer, "DF sweep (largest unfragmented size per hop):")?;
+        for (ttl, hop) in &sweep.hops {
+            let size = hop
+                .max_unfragmented
+                .map_or_else(|| "-".to_string(), |s| s.to_string());
+            match hop.blocked {
+                Some(block) => writeln!(
+                    writer,
+                    "  hop {:<3} {:>5}  {} refused by {}",
+                    ttl, size, block.size, block.from
+                )?,
+                None => writeln!(writer, "  hop {:<3} {:>5}", ttl, size)?,
+            }
+        }
+        if let Some((ttl, block)) = sweep.first_block() {
+            let mtu = block
+                .mtu
+                .map(|m| format!(", next-hop MTU {}", m))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "  Frag needed from hop {} on, sent by {}{}",
+                ttl, block.from, mtu
+            )?;
+        }
+    }
+
+    for epoch in &session.path_epochs {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "Before path change at hop {} ({} -> {}), {} - {}:",
+            epoch.ttl,
+            epoch.from,
+            epoch.to,
+            session.config.format_time(&epoch.started_at, "%H:%M:%S"),
+            session.config.format_time(&epoch.ended_at, "%H:%M:%S"),
+        )?;
+        for hop in &epoch.hops {
+            writeln!(
+                writer,
+                "  {:>3}  {:<39}  loss {:>6}  avg {:>8}",
+                hop.ttl,
+                hop.primary
+                    .map(|ip| ip.to_string())
+                    .unwrap_or_else(|| "*".into()),
+                nf.percent(hop.loss_pct, 1),
+                hop.avg_ms
+                    .map(|ms| format!("{}ms", nf.number(ms, 1)))
+                    .unwrap_or_default()
+            )?;
+        }
+    }
+
+    if !session.notes.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "Notes:")?;
+        for note in &session.notes {
+            let scope = note
+                .ttl
+                .map(|t| format!("hop {}", t))
+                .unwrap_or_else(|| "session".to_string());
+            writeln!(
+                writer,
+                "  {}  [{}] {}",
+                session.config.format_time(&note.at, "%H:%M:%S"),
+                scope,
+                note.text
+            )?;
+        }
     }
 
     Ok(())
@@ -87,3 +694,486 @@ pub fn generate_report_string(session: &Session) -> String {
     generate_report(session, &mut buf).unwrap();
     String::from_utf8(buf).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::{EcnCodepoint, Target};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn sample_session() -> Session {
+        let target = Target::new(
+            "example.com".to_string(),
+            IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+        );
+        let mut session = Session::new(target, Config::default());
+        let hop = session.hop_mut(1).unwrap();
+        hop.record_sent();
+        hop.record_response(
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            Duration::from_millis(2),
+        );
+        session
+    }
+
+    #[test]
+    fn test_notes_section() {
+        let mut session = sample_session();
+        session.add_note(Some(1), "home router");
+        let out = generate_report_string(&session);
+        assert!(out.contains("Notes:"));
+        assert!(out.contains("[hop 1] home router"));
+    }
+
+    #[test]
+    fn test_tags_header() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Tags:"));
+        session.config.tags.insert("site".into(), "fra1".into());
+        session.config.tags.insert("circuit".into(), "ISP-A".into());
+        assert!(generate_report_string(&session).contains("Tags: circuit=ISP-A, site=fra1"));
+    }
+
+    #[test]
+    fn test_links_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Links:"));
+
+        let hop = session.hop_mut(2).unwrap();
+        hop.record_sent();
+        hop.record_response(
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)),
+            Duration::from_millis(3),
+        );
+        let out = generate_report_string(&session);
+        assert!(out.contains("Links:\n  hop 1 -> 2  192.168.1.1 - 192.168.1.2 (/30)"));
+    }
+
+    #[test]
+    fn test_ecn_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("ECN marking changed:"));
+        let hop = session.hop_mut(1).unwrap();
+        for _ in 0..3 {
+            hop.record_quoted_ecn(EcnCodepoint::Ect0, EcnCodepoint::NotEct);
+        }
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains("ECN marking changed:\n  before hop 1: ECT(0) -> Not-ECT (cleared)"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_nat_line() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("NAT:"));
+        session
+            .hop_mut(1)
+            .unwrap()
+            .record_nat_check((Some(50000), Some(1)), (Some(50000), Some(2)));
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains("NAT: source address rewritten before hop 1 (100% of quoted probes)"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_dscp_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("DSCP remarked:"));
+
+        let hop = session.hop_mut(1).unwrap();
+        for _ in 0..3 {
+            hop.record_quoted_dscp(46, 0);
+        }
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains("DSCP remarked:\n  before hop 1: 46/EF -> 0/BE"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_tunnels_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Suspected MPLS tunnels:"));
+
+        // Two LSRs quoting IP TTL 2 and 3
+        for (ttl, quoted) in [(2, 2), (3, 3)] {
+            let hop = session.hop_mut(ttl).unwrap();
+            hop.record_sent();
+            hop.record_response(
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, ttl)),
+                Duration::from_millis(20),
+            );
+            hop.record_ttl_manip_check(quoted);
+        }
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains("Suspected MPLS tunnels:\n  hops 2-3 (qTTL, RTT plateau)"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_loss_correlation_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Loss correlation"));
+
+        // Hops 2 and 3 drop the same rounds
+        for seq in 0..30u8 {
+            for ttl in 2..=3 {
+                session.record_round_outcome(seq, 0, ttl, seq % 6 == 0);
+            }
+        }
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains(
+                "Loss correlation:\n  hops 2 and 3: 5 of 5/5 lost rounds shared (phi 1.00), \
+                 likely one fault"
+            ),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_ecmp_paths_section() {
+        let mut session = sample_session();
+        session.config.flows = 2;
+        assert!(!generate_report_string(&session).contains("ECMP paths"));
+
+        // Flow 0 through 10.0.0.2, flow 1 through 10.0.0.3
+        for ttl in 1..=2u8 {
+            let hop = session.hop_mut(ttl).unwrap();
+            for flow in 0..2u8 {
+                hop.record_flow_sent(flow);
+                let ip = if ttl == 1 { 1 } else { 2 + flow };
+                hop.record_flow_response(
+                    flow,
+                    IpAddr::V4(Ipv4Addr::new(10, 0, 0, ip)),
+                    Default::default(),
+                );
+            }
+        }
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains(
+                "ECMP paths (2 over 2 flows):\n  flows 0        10.0.0.1 > 10.0.0.2\n  \
+                 flows 1        10.0.0.1 > 10.0.0.3\n  flow 0: 1 sent, 0.0% loss at hop 2"
+            ),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("  hop 2: ECMP ×2+ (0% confidence over 2 flows)\n"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_df_sweep_section() {
+        let mut session = sample_session();
+        session.df_sweep = Some(crate::state::sweep::DfSweep::new(false));
+        assert!(!generate_report_string(&session).contains("DF sweep"));
+
+        let router = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let sweep = session.df_sweep.as_mut().unwrap();
+        sweep.record_answer(1, 1500);
+        sweep.record_answer(2, 1400);
+        sweep.record_frag_needed(3, 1500, router, Some(1420));
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains(
+                "DF sweep (largest unfragmented size per hop):\n  hop 1    1500\n  \
+                 hop 2    1400\n  hop 3       -  1500 refused by 10.0.0.2\n  \
+                 Frag needed from hop 3 on, sent by 10.0.0.2, next-hop MTU 1420\n"
+            ),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_path_signature_line() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Path signature"));
+
+        for _ in 0..crate::state::signature::SIGNATURE_HOLD {
+            session.record_path_signature();
+        }
+        let current = session.path_signatures.current().unwrap().clone();
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains(&format!(
+                "Path signature: {} (AS path {}), 0 changes",
+                current.signature, current.as_signature
+            )),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_path_mtu_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Path MTU:"));
+
+        session.mtu_hints.record_mss(1460);
+        session.mtu_hints.record_frag_needed(3, 1400);
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains(
+                "Path MTU: 1400 (frag needed at hop 3)\n  SYN-ACK MSS  1460 (MTU 1500)\n  Frag needed  1400 at hop 3\n"
+            ),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_redirect_warning() {
+        let mut session = sample_session();
+        let router = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        let gateway = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 254));
+        session.record_redirect(router, gateway);
+        // Recorded, but only called out with --redirects warn
+        assert!(!generate_report_string(&session).contains("Warning:"));
+
+        session.config.redirects = crate::config::RedirectPolicy::Warn;
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains(
+                "Warning: 192.168.1.1 redirected traffic for this target to 192.168.1.254 (1x)"
+            ),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_load_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Latency under load"));
+
+        let spec = crate::state::LoadSpec {
+            rate_bps: 20_000_000,
+            target: "192.0.2.9".parse().unwrap(),
+            port: 5001,
+        };
+        session.load = Some(crate::state::LoadTest::new(spec));
+        session.record_load_rtt(1, Duration::from_millis(2));
+        let out = generate_report_string(&session);
+        assert!(out.contains("Latency under load (20 Mbit/s upload): no loaded replies yet"));
+
+        session.load.as_mut().unwrap().phase = crate::state::LoadPhase::Loaded;
+        session.record_load_rtt(1, Duration::from_millis(52));
+        let out = generate_report_string(&session);
+        assert!(out.contains("grade B, +50.0ms at hop 1"), "{}", out);
+        assert!(
+            out.contains("idle     2.0ms  loaded    52.0ms     +50.0ms"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_distance_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("Distance from"));
+
+        session.config.origin = Some(crate::state::GeoPoint {
+            lat: 52.52,
+            lon: 13.40,
+        });
+        let hop = session.hop_mut(1).unwrap();
+        hop.responders.values_mut().next().unwrap().geo = Some(crate::state::GeoInfo {
+            city: Some("New York".to_string()),
+            region: None,
+            country: "US".to_string(),
+            latitude: Some(40.71),
+            longitude: Some(-74.01),
+        });
+        let out = generate_report_string(&session);
+        assert!(
+            out.contains("Distance from 52.52,13.40 (configured):"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("    1    6385 km  min   63.9ms  avg    2.0ms    0.0x"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_slo_section() {
+        let mut session = sample_session();
+        assert!(!generate_report_string(&session).contains("SLO:"));
+
+        session.set_slo(Some(crate::state::Slo::parse("p95<5ms,loss<1%").unwrap()));
+        session.record_dest_reached(1, Duration::from_millis(2));
+        session.record_dest_timeout(1);
+        let out = generate_report_string(&session);
+        assert!(out.contains("SLO: p95<5ms,loss<1% (MISSED)"));
+        assert!(out.contains("loss     50.0% of 2 probes (allowed 1%), budget left 0%"));
+    }
+
+    #[test]
+    fn test_parse_order() {
+        let cols = ReportColumn::parse_order("LAN").unwrap();
+        assert_eq!(
+            cols,
+            vec![ReportColumn::Loss, ReportColumn::Avg, ReportColumn::Last]
+        );
+        assert!(ReportColumn::parse_order("LQ").unwrap_err().contains("'Q'"));
+        assert!(ReportColumn::parse_order("").is_err());
+    }
+
+    #[test]
+    fn test_no_header_omits_preamble() {
+        let session = sample_session();
+        let opts = ReportOptions {
+            header: false,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        generate_report_with(&session, &opts, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert_eq!(out.lines().count(), 1);
+        assert!(out.starts_with("  1  192.168.1.1"));
+    }
+
+    #[test]
+    fn test_order_selects_columns() {
+        let session = sample_session();
+        let opts = ReportOptions {
+            columns: ReportColumn::parse_order("RS").unwrap(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        generate_report_with(&session, &opts, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let header = out.lines().find(|l| l.contains("Host")).unwrap();
+        assert!(header.trim_end().ends_with("Recv   Sent"));
+        assert!(!header.contains("Avg"));
+    }
+
+    #[test]
+    fn test_late_column() {
+        let mut session = sample_session();
+        let hop = session.hop_mut(1).unwrap();
+        hop.record_sent();
+        hop.record_timeout();
+        hop.record_late(Duration::from_millis(2500));
+        let opts = ReportOptions {
+            columns: ReportColumn::parse_order("LT").unwrap(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        generate_report_with(&session, &opts, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.contains("Loss%   Late"));
+        assert!(out.lines().last().unwrap().ends_with(" 50.0%      1"));
+    }
+
+    #[test]
+    fn test_caused_loss_column() {
+        let mut session = sample_session();
+        let hop = session.hop_mut(2).unwrap();
+        hop.record_sent();
+        hop.record_timeout();
+        let opts = ReportOptions {
+            columns: ReportColumn::parse_order("LC").unwrap(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        generate_report_with(&session, &opts, &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.contains("Loss%  Attr%"));
+        assert!(
+            out.lines()
+                .any(|l| l.starts_with("  1") && l.ends_with("0.0%   0.0%"))
+        );
+        // A hop that never answered has nothing to attribute
+        assert!(
+            out.lines()
+                .any(|l| l.starts_with("  2") && l.ends_with("100.0%      -"))
+        );
+    }
+
+    #[test]
+    fn test_wide_keeps_long_hostnames() {
+        let mut session = sample_session();
+        let long_name = "a".repeat(80);
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        session
+            .hop_mut(1)
+            .unwrap()
+            .responders
+            .get_mut(&ip)
+            .unwrap()
+            .hostname = Some(long_name.clone());
+
+        let narrow = generate_report_string(&session);
+        assert!(!narrow.contains(&long_name));
+        assert!(narrow.contains('…'));
+
+        let opts = ReportOptions {
+            wide: true,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        generate_report_with(&session, &opts, &mut buf).unwrap();
+        assert!(String::from_utf8(buf).unwrap().contains(&long_name));
+    }
+
+    #[test]
+    fn test_long_hostname_trimmed_before_address() {
+        let mut session = sample_session();
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        session
+            .hop_mut(1)
+            .unwrap()
+            .responders
+            .get_mut(&ip)
+            .unwrap()
+            .hostname = Some("ae-12.cr02.ams7.backbone.example-transit-networks.net".into());
+
+        let out = generate_report_string(&session);
+        assert!(out.contains("ae-12.cr02.ams7.backbone… (192.168.1.1)"));
+
+        session.config.host_trim = HostTrim::Middle;
+        let out = generate_report_string(&session);
+        let row = out.lines().find(|l| l.starts_with("  1")).unwrap();
+        assert!(row.contains("ae-12.cr02.ams7.…"));
+        assert!(row.contains("ks.net (192.168.1.1)"));
+    }
+
+    #[test]
+    fn test_number_format() {
+        let mut session = sample_session();
+        session.config.number_format = NumberFormat {
+            auto_units: true,
+            ..NumberFormat::for_locale("de").unwrap()
+        };
+        let hop = session.hop_mut(1).unwrap();
+        for _ in 0..1233 {
+            hop.record_sent();
+        }
+        let out = generate_report_string(&session);
+        let row = out.lines().find(|l| l.starts_with("  1")).unwrap();
+        assert!(row.contains("1.234"), "{}", row);
+        assert!(row.contains("0,0%"), "{}", row);
+        assert!(row.contains("2,0ms"), "{}", row);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::state::{Hop, Session};
+
+/// Hop with the highest loss before the destination, ignoring hops that
+/// never answered (many routers simply don't reply to probes)
+fn worst_hop(session: &Session) -> Option<&Hop> {
+    let dest_ttl = session.dest_ttl.unwrap_or(session.config.max_ttl);
+    session
+        .hops
+        .iter()
+        .filter(|h| h.ttl < dest_ttl && h.received > 0 && h.loss_pct() > 0.0)
+        .max_by(|a, b| {
+            a.loss_pct()
+                .total_cmp(&b.loss_pct())
+                // Prefer the earliest hop on ties: loss there explains loss after it
+                .then(b.ttl.cmp(&a.ttl))
+        })
+}
+
+/// One compact line for a session, e.g.
+/// `example.com 0.0% 12.3ms worst #4 2.0%`
+///
+/// Intended for tmux `#()`, i3blocks/waybar custom modules and similar
+/// bars that show the last line a command printed.
+pub fn status_line(session: &Session) -> String {
+    let mut line = session.target.original.clone();
+
+    let dest = session.dest_ttl.and_then(|ttl| session.hop(ttl));
+    match dest {
+        Some(hop) if hop.received > 0 => {
+            line.push_str(&format!(" {:.1}%", hop.loss_pct()));
+            if let Some(stats) = hop.primary_stats() {
+                line.push_str(&format!(" {:.1}ms", stats.avg_rtt().as_secs_f64() * 1000.0));
+            }
+        }
+        _ if session.total_sent == 0 => line.push_str(" starting"),
+        _ => line.push_str(" unreachable"),
+    }
+
+    if let Some(hop) = worst_hop(session) {
+        line.push_str(&format!(" worst #{} {:.1}%", hop.ttl, hop.loss_pct()));
+    }
+
+    line
+}
+
+/// Status line covering several targets, separated by ` | `
+pub fn status_line_multi<'a>(sessions: impl IntoIterator<Item = &'a Session>) -> String {
+    sessions
+        .into_iter()
+        .map(status_line)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::Target;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    fn session() -> Session {
+        let target = Target::new(
+            "example.com".to_string(),
+            IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+        );
+        Session::new(target, Config::default())
+    }
+
+    fn probe(session: &mut Session, ttl: u8, ip: Ipv4Addr, replies: u64, timeouts: u64) {
+        let hop = session.hop_mut(ttl).unwrap();
+        for _ in 0..replies {
+            hop.record_sent();
+            hop.record_response(IpAddr::V4(ip), Duration::from_millis(10));
+        }
+        for _ in 0..timeouts {
+            hop.record_sent();
+            hop.record_timeout();
+        }
+        session.total_sent += replies + timeouts;
+    }
+
+    #[test]
+    fn test_status_line_reached() {
+        let mut s = session();
+        probe(&mut s, 1, Ipv4Addr::new(10, 0, 0, 1), 10, 0);
+        probe(&mut s, 2, Ipv4Addr::new(10, 0, 1, 1), 8, 2);
+        probe(&mut s, 3, Ipv4Addr::new(93, 184, 216, 34), 10, 0);
+        s.dest_ttl = Some(3);
+
+        assert_eq!(status_line(&s), "example.com 0.0% 10.0ms worst #2 20.0%");
+    }
+
+    #[test]
+    fn test_status_line_states() {
+        let mut s = session();
+        assert_eq!(status_line(&s), "example.com starting");
+
+        // Silent hops are not reported as the worst hop
+        probe(&mut s, 1, Ipv4Addr::new(10, 0, 0, 1), 0, 5);
+        assert_eq!(status_line(&s), "example.com unreachable");
+
+        let other = session();
+        assert_eq!(
+            status_line_multi([&s, &other]),
+            "example.com unreachable | example.com starting"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::Write;
+
+use crate::state::Session;
+
+/// Narrowest target column, so short names still line up with the header
+const MIN_TARGET_WIDTH: usize = 24;
+
+/// One summary row: reachability, hop count, destination loss and RTT,
+/// and the hop that introduced the most loss
+struct SummaryRow {
+    target: String,
+    reachable: bool,
+    hops: String,
+    loss: String,
+    avg: String,
+    worst: String,
+}
+
+impl SummaryRow {
+    fn new(session: &Session) -> Self {
+        let target = if session.target.original == session.target.resolved.to_string() {
+            session.target.original.clone()
+        } else {
+            format!("{} ({})", session.target.original, session.target.resolved)
+        };
+
+        let nf = &session.config.number_format;
+        let dest = session
+            .dest_ttl
+            .and_then(|ttl| session.hop(ttl))
+            .filter(|hop| hop.received > 0);
+        let (hops, loss, avg) = match dest {
+            Some(hop) => (
+                hop.ttl.to_string(),
+                nf.percent(hop.loss_pct(), 1),
+                hop.primary_stats()
+                    .map(|s| nf.rtt(s.avg_rtt(), 1))
+                    .unwrap_or_else(|| "-".into()),
+            ),
+            None => ("-".into(), "-".into(), "-".into()),
+        };
+
+        // Attributed rather than raw loss: a hop that only rate limits its
+        // own replies isn't where the path is losing packets
+        let worst = session
+            .attributable_loss()
+            .into_iter()
+            .filter(|&(_, pct)| pct > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(ttl, pct)| {
+                let ip = session
+                    .hop(ttl)
+                    .and_then(|h| h.primary)
+                    .map(|ip| format!(" {}", ip))
+                    .unwrap_or_default();
+                format!("#{}{} {}", ttl, ip, nf.percent(pct, 1))
+            })
+            .unwrap_or_else(|| "-".into());
+
+        Self {
+            target,
+            reachable: dest.is_some(),
+            hops,
+            loss,
+            avg,
+            worst,
+        }
+    }
+}
+
+/// Combined table for a multi-target batch run, one line per target, e.g.
+///
+/// ```text
+/// Target                      Reach  Hops   Loss%      Avg  Worst hop
+/// example.com (93.184.216.34)   yes    12    0.0%   12.3ms  #4 10.0.0.1 2.0%
+/// ```
+pub fn write_summary<'a, W: Write>(
+    sessions: impl IntoIterator<Item = &'a Session>,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let rows: Vec<SummaryRow> = sessions.into_iter().map(SummaryRow::new).collect();
+    let reachable = rows.iter().filter(|r| r.reachable).count();
+    let width = rows
+        .iter()
+        .map(|r| r.target.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(MIN_TARGET_WIDTH);
+
+    writeln!(
+        writer,
+        "ttl summary: {} targets, {} reachable, {} unreachable",
+        rows.len(),
+        reachable,
+        rows.len() - reachable
+    )?;
+    writeln!(writer)?;
+
+    let header = format!(
+        "{:<width$}  {:>5}  {:>4}  {:>6}  {:>8}  Worst hop",
+        "Target", "Reach", "Hops", "Loss%", "Avg"
+    );
+    writeln!(writer, "{}", header)?;
+    writeln!(writer, "{}", "-".repeat(header.chars().count()))?;
+
+    for row in &rows {
+        writeln!(
+            writer,
+            "{:<width$}  {:>5}  {:>4}  {:>6}  {:>8}  {}",
+            row.target,
+            if row.reachable { "yes" } else { "NO" },
+            row.hops,
+            row.loss,
+            row.avg,
+            row.worst
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::Target;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    fn probe(session: &mut Session, ttl: u8, ip: Ipv4Addr, replies: u64, timeouts: u64) {
+        let hop = session.hop_mut(ttl).unwrap();
+        for _ in 0..replies {
+            hop.record_sent();
+            hop.record_response(IpAddr::V4(ip), Duration::from_millis(10));
+        }
+        for _ in 0..timeouts {
+            hop.record_sent();
+            hop.record_timeout();
+        }
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let dest = Ipv4Addr::new(93, 184, 216, 34);
+        let mut reached = Session::new(
+            Target::new("example.com".to_string(), IpAddr::V4(dest)),
+            Config::default(),
+        );
+        probe(&mut reached, 1, Ipv4Addr::new(10, 0, 0, 1), 10, 0);
+        probe(&mut reached, 2, Ipv4Addr::new(10, 0, 1, 1), 8, 2);
+        probe(&mut reached, 3, dest, 8, 2);
+        reached.dest_ttl = Some(3);
+
+        let silent = Session::new(
+            Target::new("192.0.2.1".to_string(), "192.0.2.1".parse().unwrap()),
+            Config::default(),
+        );
+
+        let mut buf = Vec::new();
+        write_summary([&reached, &silent], &mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "ttl summary: 2 targets, 1 reachable, 1 unreachable"
+        );
+        assert!(lines[2].starts_with("Target "));
+        assert!(lines[4].starts_with("example.com (93.184.216.34)"));
+        assert!(lines[4].contains("yes"));
+        assert!(lines[4].contains("20.0%"));
+        assert!(lines[4].ends_with("#2 10.0.1.1 20.0%"));
+        assert!(lines[5].starts_with("192.0.2.1 "));
+        assert!(lines[5].contains("NO"));
+        assert!(lines[5].ends_with("  -"));
+    }
+}
@@ -32,6 +32,20 @@
 //! export_csv(&session, &mut csv_output).unwrap();
 //! ```
 //!
+//! Fallible functions return [`TtlError`], so callers can tell an export
+//! that couldn't be written from, say, a missing privilege:
+//!
+//! ```no_run
+//! use ttl::TtlError;
+//! # let session: ttl::state::Session = unimplemented!();
+//!
+//! match ttl::export::export_csv(&session, std::io::stdout()) {
+//!     Ok(()) => {}
+//!     Err(TtlError::Export { message, .. }) => eprintln!("export failed: {}", message),
+//!     Err(e) => eprintln!("{} error: {}", e.kind(), e),
+//! }
+//! ```
+//!
 //! ## CLI Usage
 //!
 //! ```bash
@@ -42,9 +56,12 @@
 
 // Public API - data types and export functions
 pub mod config;
+pub mod error;
 pub mod export;
 pub mod state;
 
+pub use error::TtlError;
+
 // Internal implementation - not part of public API
 // These modules are used by the binary but not exported from the lib
 #[allow(dead_code)]
@@ -52,8 +69,28 @@ pub(crate) mod cli;
 #[allow(dead_code)]
 pub(crate) mod lookup;
 #[allow(dead_code)]
+pub(crate) mod plugin;
+#[allow(dead_code)]
+pub(crate) mod prefs;
+#[allow(dead_code)]
 pub(crate) mod probe;
 #[allow(dead_code)]
+pub(crate) mod remote;
+#[allow(dead_code)]
+pub(crate) mod sinks;
+#[allow(dead_code)]
+pub(crate) mod systemd;
+#[allow(dead_code)]
 pub(crate) mod trace;
 #[allow(dead_code)]
 pub(crate) mod tui;
+
+/// Hot-path internals for the criterion benches in `benches/`; not a stable API
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::probe::corpus::{parse_corpus, replay_corpus};
+    pub use crate::probe::correlate::parse_icmp_response;
+    pub use crate::probe::icmp::build_echo_request;
+    pub use crate::probe::tcp::build_tcp_syn;
+    pub use crate::probe::udp::build_udp_payload;
+}
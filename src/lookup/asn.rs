@@ -1,6 +1,5 @@
-use anyhow::Result;
-use hickory_resolver::config::ResolverConfig;
-use hickory_resolver::{Resolver, TokioResolver};
+use anyhow::{Result, bail};
+use hickory_resolver::TokioResolver;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -8,45 +7,91 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use super::policy::NetPolicy;
+use super::rir::RirLookup;
 use super::sanitize_display;
+use super::whois::{CYMRU_WHOIS, WhoisClient};
+use crate::config::LookupLimit;
 use crate::state::AsnInfo;
 use crate::trace::receiver::SessionMap;
 
+/// Zone the Team Cymru IP-to-ASN records are published under
+pub const CYMRU_ZONE: &str = "asn.cymru.com";
+
 /// ASN cache entry
 struct CacheEntry {
     asn: Option<AsnInfo>,
     cached_at: Instant,
 }
 
-/// ASN lookup via Team Cymru DNS
+/// Fields from a Team Cymru AS description record
+#[derive(Debug, Default, PartialEq)]
+struct AsRecord {
+    name: Option<String>,
+    country: Option<String>,
+    registry: Option<String>,
+    allocated: Option<String>,
+}
+
+/// Parse "AS | CC | Registry | Allocated | AS Name"
+/// (e.g. "15169 | US | arin | 2000-03-30 | GOOGLE, US")
+fn parse_as_record(txt: &str) -> AsRecord {
+    let parts: Vec<&str> = txt.split('|').map(|s| s.trim()).collect();
+    // Empty fields are common for unallocated or reserved ASNs
+    let field = |i: usize| {
+        parts
+            .get(i)
+            .filter(|s| !s.is_empty())
+            .map(|s| sanitize_display(s))
+    };
+    AsRecord {
+        country: field(1),
+        registry: field(2),
+        allocated: field(3),
+        // AS name is at index 4, sanitize for safe display
+        name: field(4),
+    }
+}
+
+/// ASN lookup via Team Cymru DNS, falling back to whois
 pub struct AsnLookup {
-    resolver: TokioResolver,
+    /// None when DNS is unusable or asn.cymru.com isn't allowed
+    resolver: Option<TokioResolver>,
+    /// Port 43 fallback (None when whois.cymru.com isn't allowed)
+    whois: Option<WhoisClient>,
     cache: RwLock<HashMap<IpAddr, CacheEntry>>,
     cache_ttl: Duration,
+    /// RIR delegated stats for registry/country/allocation date
+    /// (None if the cache directory is unusable)
+    rir: Option<RirLookup>,
 }
 
 impl AsnLookup {
-    pub async fn new() -> Result<Self> {
-        // Try system DNS config first, fall back to Google DNS if unavailable
-        let resolver = match Resolver::builder_tokio() {
-            Ok(builder) => builder.build(),
-            Err(_) => {
-                eprintln!("Warning: System DNS config unavailable, using Google DNS (8.8.8.8)");
-                Resolver::builder_with_config(
-                    ResolverConfig::google(),
-                    hickory_resolver::name_server::TokioConnectionProvider::default(),
-                )
-                .build()
-            }
+    pub async fn new(policy: &NetPolicy, disk_cache: bool) -> Result<Self> {
+        let resolver = if policy.allows_host(CYMRU_ZONE) {
+            policy.resolver().ok()
+        } else {
+            None
         };
+        let whois = Some(WhoisClient::new(policy)).filter(|w| w.enabled());
+        if resolver.is_none() && whois.is_none() {
+            bail!("neither {} nor {} is allowed", CYMRU_ZONE, CYMRU_WHOIS);
+        }
 
         Ok(Self {
             resolver,
+            whois,
             cache: RwLock::new(HashMap::new()),
             cache_ttl: Duration::from_secs(3600), // 1 hour
+            rir: RirLookup::new(policy, disk_cache).ok(),
         })
     }
 
+    /// Drop the cached result for an IP so the next lookup asks again
+    pub fn forget(&self, ip: IpAddr) {
+        self.cache.write().remove(&ip);
+    }
+
     /// Lookup ASN info for an IP via Team Cymru DNS
     pub async fn lookup(&self, ip: IpAddr) -> Option<AsnInfo> {
         // Check cache first
@@ -77,13 +122,43 @@ impl AsnLookup {
         asn
     }
 
-    /// Perform the actual DNS lookup
+    /// Look up over DNS, then whois if that fails, and fill in registry
+    /// details
     async fn do_lookup(&self, ip: IpAddr) -> Option<AsnInfo> {
+        let mut info = match self.dns_lookup(ip).await {
+            Some(info) => info,
+            None => self.whois.as_ref()?.lookup_ip(ip).await?,
+        };
+
+        // Prefer the registries' own delegation files when available
+        if let Some(ref rir) = self.rir
+            && let Some(alloc) = rir.lookup(info.number).await
+        {
+            info.registry = Some(alloc.registry);
+            info.country = Some(alloc.country).filter(|c| !c.is_empty());
+            info.allocated = alloc.allocated.or(info.allocated);
+        }
+
+        // No AS record anywhere so far: ask the registry holding the ASN
+        if info.name == format!("AS{}", info.number)
+            && let Some(ref whois) = self.whois
+            && let Some(ref registry) = info.registry
+            && let Some(name) = whois.as_name(info.number, registry).await
+        {
+            info.name = name;
+        }
+
+        Some(info)
+    }
+
+    /// Origin and AS record over Team Cymru DNS
+    async fn dns_lookup(&self, ip: IpAddr) -> Option<AsnInfo> {
+        let resolver = self.resolver.as_ref()?;
         // Build the query name for origin lookup
         let query_name = self.build_origin_query(ip);
 
         // Query TXT record at <reversed_ip>.origin.asn.cymru.com
-        let txt_records = self.resolver.txt_lookup(&query_name).await.ok()?;
+        let txt_records = resolver.txt_lookup(&query_name).await.ok()?;
 
         // Parse the first TXT record
         // Format: "AS | IP | BGP Prefix | CC | Registry | Allocated"
@@ -112,13 +187,18 @@ impl AsnLookup {
         // Extract prefix if available (index 2)
         let prefix = parts.get(2).map(|s| s.to_string());
 
-        // Now lookup the AS name
-        let as_name = self.lookup_as_name(asn_number).await;
-
+        // Now lookup the AS name (the same record carries CC/registry/date)
+        let record = self
+            .lookup_as_record(resolver, asn_number)
+            .await
+            .unwrap_or_default();
         Some(AsnInfo {
             number: asn_number,
-            name: as_name.unwrap_or_else(|| format!("AS{}", asn_number)),
+            name: record.name.unwrap_or_else(|| format!("AS{}", asn_number)),
             prefix,
+            registry: record.registry,
+            country: record.country,
+            allocated: record.allocated,
         })
     }
 
@@ -165,11 +245,11 @@ impl AsnLookup {
         format!("{}.origin6.asn.cymru.com", nibble_str)
     }
 
-    /// Lookup AS name from AS number
-    async fn lookup_as_name(&self, asn: u32) -> Option<String> {
+    /// Lookup the AS description record (name, country, registry, allocation)
+    async fn lookup_as_record(&self, resolver: &TokioResolver, asn: u32) -> Option<AsRecord> {
         let query_name = format!("AS{}.asn.cymru.com", asn);
 
-        let txt_records = self.resolver.txt_lookup(&query_name).await.ok()?;
+        let txt_records = resolver.txt_lookup(&query_name).await.ok()?;
         let txt = txt_records.iter().next()?;
 
         // TXT records may be quoted or split - join and strip quotes
@@ -179,27 +259,19 @@ impl AsnLookup {
             .filter_map(|bytes| std::str::from_utf8(bytes).ok())
             .collect::<Vec<_>>()
             .join("");
-        let txt_str = txt_str.trim_matches('"');
 
-        // Format: "AS | CC | Registry | Allocated | AS Name"
-        // Example: "15169 | US | arin | 2000-03-30 | GOOGLE, US"
-        let parts: Vec<&str> = txt_str.split('|').map(|s| s.trim()).collect();
-
-        // AS name is at index 4, sanitize for safe display
-        parts.get(4).map(|s| sanitize_display(s))
+        Some(parse_as_record(txt_str.trim_matches('"')))
     }
 }
 
-/// Maximum concurrent ASN lookups
-const MAX_CONCURRENT_LOOKUPS: usize = 10;
-
 /// Background ASN lookup worker that updates session state (multi-target)
 pub async fn run_asn_worker(
     asn_lookup: Arc<AsnLookup>,
     sessions: SessionMap,
     cancel: CancellationToken,
+    limit: LookupLimit,
 ) {
-    let mut interval = tokio::time::interval(Duration::from_millis(500));
+    let mut interval = tokio::time::interval(limit.interval);
 
     loop {
         tokio::select! {
@@ -207,16 +279,20 @@ pub async fn run_asn_worker(
                 break;
             }
             _ = interval.tick() => {
-                // Collect IPs that need ASN lookup from all sessions
-                let ips_to_lookup: Vec<IpAddr> = {
+                // Collect IPs that need ASN lookup from all sessions, and
+                // whether each was asked to refresh
+                let ips_to_lookup: Vec<(IpAddr, bool)> = {
                     let sessions = sessions.read();
                     sessions.values()
                         .flat_map(|state| {
                             let session = state.read();
+                            if !session.config.asn_enabled {
+                                return Vec::new();
+                            }
                             session.hops.iter()
                                 .flat_map(|hop| hop.responders.values())
-                                .filter(|stats| stats.asn.is_none())
-                                .map(|stats| stats.ip)
+                                .filter(|stats| stats.asn.is_none() || stats.refresh.asn)
+                                .map(|stats| (stats.ip, stats.refresh.asn))
                                 .collect::<Vec<_>>()
                         })
                         .collect()
@@ -227,16 +303,19 @@ pub async fn run_asn_worker(
                 }
 
                 // Perform parallel ASN lookups (limited batch size)
-                let batch: Vec<IpAddr> = ips_to_lookup
+                let batch: Vec<(IpAddr, bool)> = ips_to_lookup
                     .into_iter()
-                    .take(MAX_CONCURRENT_LOOKUPS)
+                    .take(limit.concurrency)
                     .collect();
 
                 // Spawn concurrent lookups
                 let futures: Vec<_> = batch
                     .iter()
-                    .map(|&ip| {
+                    .map(|&(ip, refresh)| {
                         let asn = asn_lookup.clone();
+                        if refresh {
+                            asn.forget(ip);
+                        }
                         async move { (ip, asn.lookup(ip).await) }
                     })
                     .collect();
@@ -244,18 +323,21 @@ pub async fn run_asn_worker(
                 // Wait for all lookups to complete
                 let results = futures::future::join_all(futures).await;
 
-                // Update all sessions with results
+                // Update all sessions with results, then settle any path
+                // change waiting on this address (found or not)
                 let sessions = sessions.read();
                 for (ip, asn_info) in results {
-                    if let Some(asn_info) = asn_info {
-                        for state in sessions.values() {
-                            let mut session = state.write();
-                            for hop in &mut session.hops {
-                                if let Some(stats) = hop.responders.get_mut(&ip) {
+                    for state in sessions.values() {
+                        let mut session = state.write();
+                        for hop in &mut session.hops {
+                            if let Some(stats) = hop.responders.get_mut(&ip) {
+                                stats.refresh.asn = false;
+                                if let Some(ref asn_info) = asn_info {
                                     stats.asn = Some(asn_info.clone());
                                 }
                             }
                         }
+                        session.resolve_prefix(ip);
                     }
                 }
             }
@@ -319,10 +401,13 @@ mod tests {
 
     #[test]
     fn test_parse_cymru_name_response() {
-        let txt = "15169 | US | arin | 2000-03-30 | GOOGLE, US";
-        let parts: Vec<&str> = txt.split('|').map(|s| s.trim()).collect();
-
-        let name = parts.get(4).map(|s| s.to_string());
-        assert_eq!(name, Some("GOOGLE, US".to_string()));
+        let record = parse_as_record("15169 | US | arin | 2000-03-30 | GOOGLE, US");
+        assert_eq!(record.name.as_deref(), Some("GOOGLE, US"));
+        assert_eq!(record.country.as_deref(), Some("US"));
+        assert_eq!(record.registry.as_deref(), Some("arin"));
+        assert_eq!(record.allocated.as_deref(), Some("2000-03-30"));
+
+        let sparse = parse_as_record("64512 |  |  |  | ");
+        assert_eq!(sparse, AsRecord::default());
     }
 }
@@ -0,0 +1,205 @@
+//! On-disk lookup caches
+//!
+//! PeeringDB and RIR data are cached under `~/.cache/ttl` so later runs
+//! don't download them again. The directories are created owner-only
+//! (0700) and cache files are written 0600, also tightening files left by
+//! older versions. `--no-disk-cache` keeps everything in memory, and
+//! `ttl cache purge` deletes the whole directory.
+//!
+//! Several ttl instances may share the directory. Files are replaced by
+//! renaming a finished temporary file over them, so a reader never sees a
+//! half-written one, and a lock file next to a cache keeps two instances
+//! from refreshing it at the same time.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Lock files older than this were left behind by a run that died mid-refresh
+const STALE_LOCK: Duration = Duration::from_secs(10 * 60);
+
+/// `~/.cache/ttl` (or `./ttl` without a cache directory)
+pub fn root() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ttl")
+}
+
+#[cfg(unix)]
+fn restrict(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Cache subdirectory `name`, created owner-only if needed
+pub fn dir(name: &str) -> Result<PathBuf> {
+    let root = root();
+    let dir = root.join(name);
+    fs::create_dir_all(&dir)?;
+    restrict(&root, 0o700)?;
+    restrict(&dir, 0o700)?;
+    Ok(dir)
+}
+
+/// `path` with `suffix` appended to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Write a cache file readable only by its owner, atomically replacing
+/// any previous version
+pub fn write(path: &Path, data: &str) -> Result<()> {
+    let temp = sibling(path, &format!(".{}.tmp", std::process::id()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options
+        .open(&temp)
+        .and_then(|mut file| {
+            std::io::Write::write_all(&mut file, data.as_bytes())?;
+            file.sync_all()
+        })
+        .map_err(anyhow::Error::from)
+        // `mode` only applies to new files
+        .and_then(|()| restrict(&temp, 0o600))
+        .and_then(|()| Ok(fs::rename(&temp, path)?));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Claim on refreshing a cache file, released when dropped
+#[derive(Debug)]
+pub struct RefreshLock {
+    path: PathBuf,
+}
+
+impl Drop for RefreshLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Take the refresh lock for cache file `path`. Returns None while another
+/// instance holds it; a lock left by a crashed run is taken over once it
+/// is STALE_LOCK old.
+pub fn try_lock(path: &Path) -> Result<Option<RefreshLock>> {
+    let lock = sibling(path, ".lock");
+    for _ in 0..2 {
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock)
+        {
+            Ok(_) => return Ok(Some(RefreshLock { path: lock })),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let age = fs::metadata(&lock)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+                if age.is_none_or(|age| age < STALE_LOCK) {
+                    return Ok(None);
+                }
+                let _ = fs::remove_file(&lock);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// Move an unreadable cache file aside (to `<name>.corrupt`, replacing an
+/// older one) so the next load fetches fresh data
+pub fn quarantine(path: &Path) -> Result<()> {
+    fs::rename(path, sibling(path, ".corrupt"))
+        .with_context(|| format!("Failed to move aside {}", path.display()))
+}
+
+fn size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|e| size(&e.path())).sum())
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Delete the cache directory `root`, returning the bytes freed
+/// (None if there was nothing to delete)
+pub fn purge(root: &Path) -> Result<Option<u64>> {
+    if !root.exists() {
+        return Ok(None);
+    }
+    let bytes = size(root);
+    fs::remove_dir_all(root).with_context(|| format!("Failed to remove {}", root.display()))?;
+    Ok(Some(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_purge() {
+        let root = std::env::temp_dir().join(format!("ttl_cache_test_{}", std::process::id()));
+        fs::create_dir_all(root.join("peeringdb")).unwrap();
+        let file = root.join("peeringdb").join("ix_cache.json");
+        fs::write(&file, "old").unwrap();
+
+        write(&file, "{}").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{}");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Written through a temporary file that doesn't stay behind
+        let entries = fs::read_dir(root.join("peeringdb")).unwrap().count();
+        assert_eq!(entries, 1);
+
+        assert_eq!(purge(&root).unwrap(), Some(2));
+        assert!(!root.exists());
+        assert_eq!(purge(&root).unwrap(), None);
+    }
+
+    #[test]
+    fn test_refresh_lock() {
+        let dir = std::env::temp_dir().join(format!("ttl_lock_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("ix_cache.json");
+
+        let lock = try_lock(&file).unwrap().expect("free");
+        assert!(dir.join("ix_cache.json.lock").exists());
+        assert!(try_lock(&file).unwrap().is_none());
+        drop(lock);
+        assert!(!dir.join("ix_cache.json.lock").exists());
+        assert!(try_lock(&file).unwrap().is_some());
+
+        fs::write(&file, "{trunc").unwrap();
+        quarantine(&file).unwrap();
+        assert!(!file.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("ix_cache.json.corrupt")).unwrap(),
+            "{trunc"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+//! Custom enrichment endpoint (`--enrich-url`)
+//!
+//! Sites that keep device names and owners in their own inventory (a CMDB,
+//! NetBox, an IPAM) can have ttl ask it about every new responder. Each one
+//! is POSTed as the same JSON a `responder` plugin gets on stdin; the
+//! endpoint answers with a flat JSON object whose fields are kept as the
+//! responder's `extra` annotations, shown in hop detail and saved in JSON
+//! exports. An endpoint that fails leaves an `error` entry there instead.
+
+use anyhow::{Context, Result, anyhow};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::policy::NetPolicy;
+use super::sanitize_display;
+use crate::config::LookupLimit;
+use crate::plugin::{Pending, ResponderRequest, parse_fields, responder_requests};
+use crate::trace::receiver::SessionMap;
+
+/// Client for the configured endpoint
+pub struct EnrichLookup {
+    client: reqwest::Client,
+    url: String,
+    /// Sent as `Authorization: Bearer ...`
+    token: Option<String>,
+}
+
+impl EnrichLookup {
+    pub fn new(url: &str, token: Option<String>, policy: &NetPolicy) -> Result<Self> {
+        if !policy.allows_url(url) {
+            return Err(anyhow!("{} is not in allowed_hosts", url));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(format!(
+                "ttl/{} (https://github.com/lance0/ttl)",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            token,
+        })
+    }
+
+    /// Annotations the endpoint has for a responder
+    pub async fn lookup(&self, request: &ResponderRequest) -> Result<BTreeMap<String, String>> {
+        let mut post = self.client.post(&self.url).json(request);
+        if let Some(ref token) = self.token {
+            post = post.bearer_auth(token);
+        }
+        let response = post.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("endpoint returned {}", response.status()));
+        }
+        let body = response.bytes().await?;
+        parse_fields(&body).context("bad response")
+    }
+}
+
+/// Background worker asking the endpoint about new responders
+pub async fn run_enrich_worker(
+    lookup: Arc<EnrichLookup>,
+    sessions: SessionMap,
+    cancel: CancellationToken,
+    limit: LookupLimit,
+) {
+    let mut interval = tokio::time::interval(limit.interval);
+    let mut pending = Pending::default();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                break;
+            }
+            _ = interval.tick() => {
+                let candidates = responder_requests(&sessions);
+                let due = pending.take_due(candidates, limit.concurrency, Instant::now());
+                if due.is_empty() {
+                    continue;
+                }
+
+                let futures: Vec<_> = due
+                    .iter()
+                    .map(|request| {
+                        let lookup = lookup.clone();
+                        async move { (request.ip, lookup.lookup(request).await) }
+                    })
+                    .collect();
+                let results = futures::future::join_all(futures).await;
+
+                let sessions = sessions.read();
+                for (ip, result) in results {
+                    let fields = annotations(result);
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    for state in sessions.values() {
+                        let mut session = state.write();
+                        for hop in &mut session.hops {
+                            if let Some(stats) = hop.responders.get_mut(&ip) {
+                                stats.extra.extend(fields.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fields to store for a lookup result; a failure is kept as `error`, where
+/// the TUI can't be garbled
+fn annotations(result: Result<BTreeMap<String, String>>) -> BTreeMap<String, String> {
+    match result {
+        Ok(fields) => fields,
+        Err(e) => BTreeMap::from([("error".to_string(), sanitize_display(&format!("{:#}", e)))]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::{Session, Target};
+    use parking_lot::RwLock;
+    use std::collections::HashMap;
+    use std::net::IpAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Responders due to be sent to the endpoint now
+    fn asked(pending: &mut Pending, sessions: &SessionMap) -> Vec<IpAddr> {
+        pending
+            .take_due(responder_requests(sessions), 16, Instant::now())
+            .into_iter()
+            .map(|r| r.ip)
+            .collect()
+    }
+
+    #[test]
+    fn test_new_respects_policy() {
+        let url = "https://cmdb.example.com/ttl";
+        assert!(EnrichLookup::new(url, None, &NetPolicy::default()).is_ok());
+        let offline = NetPolicy {
+            offline: true,
+            ..NetPolicy::default()
+        };
+        assert!(EnrichLookup::new(url, None, &offline).is_err());
+        let allowed = NetPolicy {
+            allowed_hosts: vec!["example.com".to_string()],
+            ..NetPolicy::default()
+        };
+        assert!(EnrichLookup::new(url, None, &allowed).is_ok());
+    }
+
+    #[test]
+    fn test_annotations_keep_errors() {
+        let fields = annotations(Err(anyhow!("endpoint returned 503\u{1b}[2J")));
+        assert_eq!(fields["error"], "endpoint returned 503[2J");
+        let ok = BTreeMap::from([("owner".to_string(), "netops".to_string())]);
+        assert_eq!(annotations(Ok(ok.clone())), ok);
+    }
+
+    #[test]
+    fn test_each_responder_asked_once() {
+        let target = Target::new("t".into(), "192.0.2.1".parse().unwrap());
+        let config = Config {
+            dns_enabled: false,
+            asn_enabled: false,
+            ..Config::default()
+        };
+        let mut session = Session::new(target, config);
+        let router: IpAddr = "10.0.0.1".parse().unwrap();
+        session
+            .hop_mut(1)
+            .unwrap()
+            .record_response(router, Duration::from_millis(1));
+        let sessions: SessionMap = Arc::new(RwLock::new(HashMap::from([(
+            session.target.resolved,
+            Arc::new(RwLock::new(session)),
+        )])));
+
+        let mut pending = Pending::default();
+        assert_eq!(asked(&mut pending, &sessions), vec![router]);
+        assert!(asked(&mut pending, &sessions).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_posts_responder() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            // Headers and the small JSON body arrive well within a few reads
+            while !String::from_utf8_lossy(&request).contains("\"ttl\":3") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"device":"core-fra1","rack":12}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let lookup = EnrichLookup::new(
+            &format!("http://{}/enrich", addr),
+            Some("s3cret".to_string()),
+            &NetPolicy::default(),
+        )
+        .unwrap();
+        let request = ResponderRequest {
+            ip: "10.0.0.1".parse().unwrap(),
+            ttl: 3,
+            target: "example.com".to_string(),
+            target_ip: "192.0.2.1".parse().unwrap(),
+            hostname: None,
+            asn: None,
+            as_name: None,
+        };
+        let fields = lookup.lookup(&request).await.unwrap();
+        assert_eq!(fields["device"], "core-fra1");
+        assert_eq!(fields["rack"], "12");
+
+        let seen = server.await.unwrap();
+        assert!(seen.starts_with("POST /enrich "));
+        assert!(
+            seen.to_ascii_lowercase()
+                .contains("authorization: bearer s3cret")
+        );
+        assert!(seen.contains("\"ip\":\"10.0.0.1\""));
+    }
+}
@@ -7,6 +7,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::config::LookupLimit;
 use crate::state::GeoInfo;
 use crate::trace::receiver::SessionMap;
 
@@ -35,10 +36,9 @@ impl GeoLookup {
         })
     }
 
-    /// Try to create GeoLookup from common default paths
-    pub fn try_default() -> Option<Self> {
-        // Try common paths in order
-        let paths = [
+    /// Where a GeoLite2 database is looked for without --geoip-db, in order
+    pub fn default_paths() -> Vec<std::path::PathBuf> {
+        [
             // User data directory
             dirs::data_dir().map(|d| d.join("ttl").join("GeoLite2-City.mmdb")),
             // Config directory
@@ -52,9 +52,15 @@ impl GeoLookup {
             Some(std::path::PathBuf::from(
                 "/var/lib/GeoIP/GeoLite2-City.mmdb",
             )),
-        ];
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
 
-        for path in paths.into_iter().flatten() {
+    /// Try to create GeoLookup from common default paths
+    pub fn try_default() -> Option<Self> {
+        for path in Self::default_paths() {
             if path.exists()
                 && let Ok(lookup) = Self::new(&path)
             {
@@ -65,6 +71,12 @@ impl GeoLookup {
         None
     }
 
+    /// Drop the cached result for an IP so the next lookup reads the
+    /// database again
+    pub fn forget(&self, ip: IpAddr) {
+        self.cache.write().remove(&ip);
+    }
+
     /// Lookup GeoIP info for an IP address
     pub fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
         // Check cache first
@@ -128,16 +140,14 @@ impl GeoLookup {
     }
 }
 
-/// Maximum concurrent GeoIP lookups
-const MAX_CONCURRENT_LOOKUPS: usize = 20;
-
 /// Background GeoIP lookup worker that updates session state (multi-target)
 pub async fn run_geo_worker(
     geo_lookup: Arc<GeoLookup>,
     sessions: SessionMap,
     cancel: CancellationToken,
+    limit: LookupLimit,
 ) {
-    let mut interval = tokio::time::interval(Duration::from_millis(500));
+    let mut interval = tokio::time::interval(limit.interval);
 
     loop {
         tokio::select! {
@@ -145,16 +155,25 @@ pub async fn run_geo_worker(
                 break;
             }
             _ = interval.tick() => {
-                // Collect IPs that need geo lookup from all sessions
-                let ips_to_lookup: Vec<IpAddr> = {
+                // Collect IPs that need geo lookup from all sessions, and
+                // whether each was asked to refresh
+                let ips_to_lookup: Vec<(IpAddr, bool)> = {
                     let sessions = sessions.read();
                     sessions.values()
                         .flat_map(|state| {
                             let session = state.read();
+                            if !session.config.geo_enabled {
+                                return Vec::new();
+                            }
+                            // The source address too, as the origin for hop distances
+                            let source = session
+                                .source_ip
+                                .filter(|_| session.source_geo.is_none() && session.config.origin.is_none());
                             session.hops.iter()
                                 .flat_map(|hop| hop.responders.values())
-                                .filter(|stats| stats.geo.is_none())
-                                .map(|stats| stats.ip)
+                                .filter(|stats| stats.geo.is_none() || stats.refresh.geo)
+                                .map(|stats| (stats.ip, stats.refresh.geo))
+                                .chain(source.map(|ip| (ip, false)))
                                 .collect::<Vec<_>>()
                         })
                         .collect()
@@ -165,29 +184,40 @@ pub async fn run_geo_worker(
                 }
 
                 // GeoIP lookups are fast (local file), so we can do more at once
-                let batch: Vec<IpAddr> = ips_to_lookup
+                let batch: Vec<(IpAddr, bool)> = ips_to_lookup
                     .into_iter()
-                    .take(MAX_CONCURRENT_LOOKUPS)
+                    .take(limit.concurrency)
                     .collect();
 
                 // Lookups are sync and fast, just do them in a loop
                 let results: Vec<(IpAddr, Option<GeoInfo>)> = batch
                     .iter()
-                    .map(|&ip| (ip, geo_lookup.lookup(ip)))
+                    .map(|&(ip, refresh)| {
+                        if refresh {
+                            geo_lookup.forget(ip);
+                        }
+                        (ip, geo_lookup.lookup(ip))
+                    })
                     .collect();
 
                 // Update all sessions with results
                 let sessions = sessions.read();
                 for (ip, geo_info) in results {
-                    if let Some(geo_info) = geo_info {
-                        for state in sessions.values() {
-                            let mut session = state.write();
-                            for hop in &mut session.hops {
-                                if let Some(stats) = hop.responders.get_mut(&ip) {
+                    for state in sessions.values() {
+                        let mut session = state.write();
+                        for hop in &mut session.hops {
+                            if let Some(stats) = hop.responders.get_mut(&ip) {
+                                stats.refresh.geo = false;
+                                if let Some(ref geo_info) = geo_info {
                                     stats.geo = Some(geo_info.clone());
                                 }
                             }
                         }
+                        if let Some(ref geo_info) = geo_info
+                            && session.source_ip == Some(ip)
+                        {
+                            session.source_geo = Some(geo_info.clone());
+                        }
                     }
                 }
             }
@@ -17,7 +17,10 @@ use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::OnceCell;
 use tokio_util::sync::CancellationToken;
 
+use super::cache;
+use super::policy::NetPolicy;
 use super::sanitize_display;
+use crate::config::LookupLimit;
 use crate::state::IxInfo;
 use crate::trace::receiver::SessionMap;
 
@@ -67,6 +70,11 @@ struct PrefixCacheEntry {
     ix_country: Option<String>,
 }
 
+/// Cache file under `~/.cache/ttl`
+pub const CACHE_FILE: (&str, &str) = ("peeringdb", "ix_cache.json");
+/// Cache format version; files with another version are refetched
+pub const CACHE_VERSION: u32 = 1;
+
 /// Serializable cache format
 #[derive(Debug, Serialize, Deserialize)]
 struct IxCache {
@@ -76,15 +84,18 @@ struct IxCache {
 }
 
 impl IxCache {
-    const VERSION: u32 = 1;
+    const VERSION: u32 = CACHE_VERSION;
     const MAX_AGE_SECS: u64 = 24 * 60 * 60; // 24 hours
 
+    /// A timestamp in the future (clock change, damaged file) counts as
+    /// expired rather than fresh for days
     fn is_expired(&self) -> bool {
         let now = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
-        now - self.fetched_at > Self::MAX_AGE_SECS
+        now.checked_sub(self.fetched_at)
+            .is_none_or(|age| age > Self::MAX_AGE_SECS)
     }
 }
 
@@ -99,8 +110,8 @@ pub struct IxLookup {
     /// Parsed prefixes for lookup (populated from cache or API)
     /// Sorted by prefix length descending for longest-prefix-match
     prefixes: RwLock<Vec<PrefixEntry>>,
-    /// Cache file path
-    cache_path: PathBuf,
+    /// Cache file path (None with --no-disk-cache)
+    cache_path: Option<PathBuf>,
     /// OnceCell ensures successful load runs exactly once
     /// Uses get_or_try_init so failures don't fill the cell
     load_once: OnceCell<()>,
@@ -112,24 +123,24 @@ pub struct IxLookup {
     ip_cache_ttl: Duration,
     /// Timestamps for IP cache entries
     ip_cache_times: RwLock<HashMap<IpAddr, Instant>>,
+    /// Whether PeeringDB may be contacted; if not, only the cache is used
+    fetch: bool,
 }
 
+/// PeeringDB API base URL
+pub const PEERINGDB_API: &str = "https://www.peeringdb.com/api";
+
 /// Backoff period after load failure (5 minutes)
 const LOAD_FAILURE_BACKOFF_SECS: u64 = 300;
 
 impl IxLookup {
     /// Create a new IX lookup instance
-    pub fn new() -> Result<Self> {
-        // Use standard cache directory
-        let cache_dir = dirs::cache_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("ttl")
-            .join("peeringdb");
-
-        // Create cache directory if needed
-        fs::create_dir_all(&cache_dir)?;
-
-        let cache_path = cache_dir.join("ix_cache.json");
+    pub fn new(policy: &NetPolicy, disk_cache: bool) -> Result<Self> {
+        let cache_path = if disk_cache {
+            Some(cache::dir(CACHE_FILE.0)?.join(CACHE_FILE.1))
+        } else {
+            None
+        };
 
         Ok(Self {
             prefixes: RwLock::new(Vec::new()),
@@ -139,9 +150,17 @@ impl IxLookup {
             ip_cache: RwLock::new(HashMap::new()),
             ip_cache_ttl: Duration::from_secs(3600), // 1 hour for IP results
             ip_cache_times: RwLock::new(HashMap::new()),
+            fetch: policy.allows_url(PEERINGDB_API),
         })
     }
 
+    /// Drop the cached result for an IP so the next lookup matches it
+    /// against the prefixes again (the PeeringDB data itself is kept)
+    pub fn forget(&self, ip: IpAddr) {
+        self.ip_cache.write().remove(&ip);
+        self.ip_cache_times.write().remove(&ip);
+    }
+
     /// Lookup IX info for an IP address
     ///
     /// Lazily loads PeeringDB data on first lookup.
@@ -224,11 +243,35 @@ impl IxLookup {
             return Ok(());
         }
 
+        // Not allowed to fetch: an expired cache is all there is
+        if !self.fetch {
+            let cache = self.load_cache()?;
+            return self.populate_from_cache(&cache);
+        }
+
+        // Another ttl is already refreshing the file: make do with what it
+        // holds, or fetch without writing over the other instance's save
+        let lock = match self.cache_path {
+            Some(ref path) => match cache::try_lock(path) {
+                Ok(Some(lock)) => Some(lock),
+                Ok(None) => {
+                    if let Ok(cache) = self.load_cache() {
+                        return self.populate_from_cache(&cache);
+                    }
+                    None
+                }
+                Err(_) => None,
+            },
+            None => None,
+        };
+
         // Fetch from API
         match self.fetch_from_api().await {
             Ok(cache) => {
                 // Save to disk (ignore errors - cache is optional)
-                let _ = self.save_cache(&cache);
+                if lock.is_some() {
+                    let _ = self.save_cache(&cache);
+                }
                 self.populate_from_cache(&cache)?;
                 Ok(())
             }
@@ -246,8 +289,20 @@ impl IxLookup {
 
     /// Load cache from disk
     fn load_cache(&self) -> Result<IxCache> {
-        let data = fs::read_to_string(&self.cache_path)?;
-        let cache: IxCache = serde_json::from_str(&data)?;
+        let path = self
+            .cache_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("disk cache disabled"))?;
+        let data = fs::read_to_string(path)?;
+        // A damaged file (interrupted write, full disk) is moved aside so
+        // the data is fetched again instead of failing every load
+        let cache: IxCache = match serde_json::from_str(&data) {
+            Ok(cache) => cache,
+            Err(e) => {
+                let _ = cache::quarantine(path);
+                return Err(anyhow!("corrupt cache {}: {}", path.display(), e));
+            }
+        };
         if cache.version != IxCache::VERSION {
             return Err(anyhow!("cache version mismatch"));
         }
@@ -256,8 +311,9 @@ impl IxLookup {
 
     /// Save cache to disk
     fn save_cache(&self, cache: &IxCache) -> Result<()> {
-        let data = serde_json::to_string_pretty(cache)?;
-        fs::write(&self.cache_path, data)?;
+        if let Some(ref path) = self.cache_path {
+            cache::write(path, &serde_json::to_string_pretty(cache)?)?;
+        }
         Ok(())
     }
 
@@ -281,7 +337,7 @@ impl IxLookup {
 
         // Sort by prefix length descending for longest-prefix-match
         // This ensures more specific prefixes are checked first
-        entries.sort_by(|a, b| b.network.prefix().cmp(&a.network.prefix()));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.network.prefix()));
 
         *self.prefixes.write() = entries;
         Ok(())
@@ -370,21 +426,21 @@ impl IxLookup {
     /// Fetch IX data from API
     /// Note: limit=0 disables pagination to fetch all records
     async fn fetch_ix(&self, client: &reqwest::Client) -> Result<Vec<PdbIx>> {
-        let url = "https://www.peeringdb.com/api/ix?limit=0";
+        let url = format!("{}/ix?limit=0", PEERINGDB_API);
         let resp: PdbResponse<PdbIx> = client.get(url).send().await?.json().await?;
         Ok(resp.data)
     }
 
     /// Fetch IXLAN data from API
     async fn fetch_ixlan(&self, client: &reqwest::Client) -> Result<Vec<PdbIxlan>> {
-        let url = "https://www.peeringdb.com/api/ixlan?limit=0";
+        let url = format!("{}/ixlan?limit=0", PEERINGDB_API);
         let resp: PdbResponse<PdbIxlan> = client.get(url).send().await?.json().await?;
         Ok(resp.data)
     }
 
     /// Fetch IX prefix data from API
     async fn fetch_ixpfx(&self, client: &reqwest::Client) -> Result<Vec<PdbIxpfx>> {
-        let url = "https://www.peeringdb.com/api/ixpfx?limit=0";
+        let url = format!("{}/ixpfx?limit=0", PEERINGDB_API);
         let resp: PdbResponse<PdbIxpfx> = client.get(url).send().await?.json().await?;
         Ok(resp.data)
     }
@@ -396,16 +452,14 @@ impl IxLookup {
     }
 }
 
-/// Maximum concurrent IX lookups
-const MAX_CONCURRENT_LOOKUPS: usize = 10;
-
 /// Background IX lookup worker that updates session state
 pub async fn run_ix_worker(
     ix_lookup: Arc<IxLookup>,
     sessions: SessionMap,
     cancel: CancellationToken,
+    limit: LookupLimit,
 ) {
-    let mut interval = tokio::time::interval(Duration::from_millis(500));
+    let mut interval = tokio::time::interval(limit.interval);
 
     loop {
         tokio::select! {
@@ -413,16 +467,20 @@ pub async fn run_ix_worker(
                 break;
             }
             _ = interval.tick() => {
-                // Collect IPs that need IX lookup from all sessions
-                let ips_to_lookup: Vec<IpAddr> = {
+                // Collect IPs that need IX lookup from all sessions, and
+                // whether each was asked to refresh
+                let ips_to_lookup: Vec<(IpAddr, bool)> = {
                     let sessions = sessions.read();
                     sessions.values()
                         .flat_map(|state| {
                             let session = state.read();
+                            if !session.config.ix_enabled {
+                                return Vec::new();
+                            }
                             session.hops.iter()
                                 .flat_map(|hop| hop.responders.values())
-                                .filter(|stats| stats.ix.is_none())
-                                .map(|stats| stats.ip)
+                                .filter(|stats| stats.ix.is_none() || stats.refresh.ix)
+                                .map(|stats| (stats.ip, stats.refresh.ix))
                                 .collect::<Vec<_>>()
                         })
                         .collect()
@@ -433,16 +491,19 @@ pub async fn run_ix_worker(
                 }
 
                 // Perform parallel IX lookups (limited batch size)
-                let batch: Vec<IpAddr> = ips_to_lookup
+                let batch: Vec<(IpAddr, bool)> = ips_to_lookup
                     .into_iter()
-                    .take(MAX_CONCURRENT_LOOKUPS)
+                    .take(limit.concurrency)
                     .collect();
 
                 // Spawn concurrent lookups
                 let futures: Vec<_> = batch
                     .iter()
-                    .map(|&ip| {
+                    .map(|&(ip, refresh)| {
                         let ix = ix_lookup.clone();
+                        if refresh {
+                            ix.forget(ip);
+                        }
                         async move { (ip, ix.lookup(ip).await) }
                     })
                     .collect();
@@ -450,18 +511,21 @@ pub async fn run_ix_worker(
                 // Wait for all lookups to complete
                 let results = futures::future::join_all(futures).await;
 
-                // Update all sessions with results
+                // Update all sessions with results, then settle any path
+                // change waiting on this address (IX or not)
                 let sessions = sessions.read();
                 for (ip, ix_info) in results {
-                    if let Some(ix_info) = ix_info {
-                        for state in sessions.values() {
-                            let mut session = state.write();
-                            for hop in &mut session.hops {
-                                if let Some(stats) = hop.responders.get_mut(&ip) {
+                    for state in sessions.values() {
+                        let mut session = state.write();
+                        for hop in &mut session.hops {
+                            if let Some(stats) = hop.responders.get_mut(&ip) {
+                                stats.refresh.ix = false;
+                                if let Some(ref ix_info) = ix_info {
                                     stats.ix = Some(ix_info.clone());
                                 }
                             }
                         }
+                        session.resolve_ix(ip);
                     }
                 }
             }
@@ -507,12 +571,56 @@ mod tests {
             prefixes: vec![],
         };
         assert!(old.is_expired());
+
+        // Timestamp in the future
+        let future = IxCache {
+            version: IxCache::VERSION,
+            fetched_at: now + 60 * 60,
+            prefixes: vec![],
+        };
+        assert!(future.is_expired());
+    }
+
+    #[test]
+    fn test_corrupt_cache_moved_aside() {
+        let temp_dir = std::env::temp_dir().join(format!("ix_test_corrupt_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+        let cache_path = temp_dir.join("ix_cache.json");
+        fs::write(&cache_path, "{\"version\": 1, \"fetched_at\": 17").unwrap();
+
+        let lookup = IxLookup {
+            prefixes: RwLock::new(Vec::new()),
+            cache_path: Some(cache_path.clone()),
+            load_once: OnceCell::new(),
+            last_failure: AtomicU64::new(0),
+            ip_cache: RwLock::new(HashMap::new()),
+            ip_cache_ttl: Duration::from_secs(3600),
+            ip_cache_times: RwLock::new(HashMap::new()),
+            fetch: false,
+        };
+        assert!(lookup.load_cache().is_err());
+        assert!(!cache_path.exists());
+        assert!(temp_dir.join("ix_cache.json.corrupt").exists());
+
+        // The next save replaces it cleanly
+        let cache = IxCache {
+            version: IxCache::VERSION,
+            fetched_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            prefixes: vec![],
+        };
+        lookup.save_cache(&cache).unwrap();
+        assert!(lookup.load_cache().is_ok());
+
+        let _ = fs::remove_dir_all(&temp_dir);
     }
 
     #[test]
     fn test_longest_prefix_match_sorting() {
         // Verify that prefixes are sorted by length descending
-        let mut entries = vec![
+        let mut entries = [
             PrefixEntry {
                 network: "10.0.0.0/8".parse().unwrap(),
                 info: IxInfo {
@@ -540,7 +648,7 @@ mod tests {
         ];
 
         // Sort by prefix length descending (same as populate_from_cache)
-        entries.sort_by(|a, b| b.network.prefix().cmp(&a.network.prefix()));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.network.prefix()));
 
         // First entry should be /24 (most specific)
         assert_eq!(entries[0].network.prefix(), 24);
@@ -589,12 +697,13 @@ mod tests {
 
         let lookup = IxLookup {
             prefixes: RwLock::new(Vec::new()),
-            cache_path,
+            cache_path: Some(cache_path),
             load_once: OnceCell::new(),
             last_failure: AtomicU64::new(0),
             ip_cache: RwLock::new(HashMap::new()),
             ip_cache_ttl: Duration::from_secs(3600),
             ip_cache_times: RwLock::new(HashMap::new()),
+            fetch: true,
         };
 
         // Set last_failure to now (simulate recent failure)
@@ -625,12 +734,13 @@ mod tests {
 
         let lookup = IxLookup {
             prefixes: RwLock::new(Vec::new()),
-            cache_path: cache_path.clone(),
+            cache_path: Some(cache_path.clone()),
             load_once: OnceCell::new(),
             last_failure: AtomicU64::new(0),
             ip_cache: RwLock::new(HashMap::new()),
             ip_cache_ttl: Duration::from_secs(3600),
             ip_cache_times: RwLock::new(HashMap::new()),
+            fetch: true,
         };
 
         // No cache exists, API will timeout/fail - OnceCell should stay empty
@@ -666,12 +776,13 @@ mod tests {
                     country: Some("US".to_string()),
                 },
             }]),
-            cache_path,
+            cache_path: Some(cache_path),
             load_once: OnceCell::const_new_with(()), // Pre-filled = loaded
             last_failure: AtomicU64::new(0),
             ip_cache: RwLock::new(HashMap::new()),
             ip_cache_ttl: Duration::from_secs(3600),
             ip_cache_times: RwLock::new(HashMap::new()),
+            fetch: true,
         };
 
         // Lookup should find the pre-loaded prefix
@@ -706,12 +817,13 @@ mod tests {
                     country: None,
                 },
             }]),
-            cache_path,
+            cache_path: Some(cache_path),
             load_once: OnceCell::const_new_with(()),
             last_failure: AtomicU64::new(0),
             ip_cache: RwLock::new(HashMap::new()),
             ip_cache_ttl: Duration::from_secs(3600),
             ip_cache_times: RwLock::new(HashMap::new()),
+            fetch: true,
         };
 
         let ip = IpAddr::V4(Ipv4Addr::new(206, 223, 115, 50));
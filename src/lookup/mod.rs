@@ -1,7 +1,13 @@
 pub mod asn;
+pub mod cache;
+pub mod enrich;
 pub mod geo;
 pub mod ix;
+pub mod policy;
+pub mod prefix;
 pub mod rdns;
+pub mod rir;
+pub mod whois;
 
 /// Sanitize a string for safe terminal display by removing control characters.
 ///
@@ -0,0 +1,149 @@
+//! Outbound network policy for lookups
+//!
+//! Enrichment talks to several outside services: the DNS resolver (reverse
+//! DNS, Team Cymru ASN queries), the whois servers, PeeringDB, the RIR
+//! statistics mirrors and RIPEstat. Some environments only permit known destinations, so
+//! `allowed_hosts` in config.toml (or `--allow-host`) limits lookups to the
+//! listed hosts, and `--offline` turns off every network call except the
+//! probes themselves. Lookups whose host isn't allowed fall back to their
+//! on-disk cache where they have one, and are skipped otherwise.
+
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::name_server::TokioConnectionProvider;
+use hickory_resolver::system_conf::read_system_conf;
+use hickory_resolver::{Resolver, TokioResolver};
+use ipnetwork::IpNetwork;
+use std::net::IpAddr;
+
+use crate::error::{Result, TtlError};
+
+/// Which outside hosts lookups may contact
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetPolicy {
+    /// No network calls at all besides probes
+    pub offline: bool,
+    /// Hosts (with their subdomains), addresses or CIDR ranges lookups may
+    /// contact; empty means no restriction
+    pub allowed_hosts: Vec<String>,
+}
+
+impl NetPolicy {
+    /// Whether `host` (a name or an address) may be contacted
+    pub fn allows_host(&self, host: &str) -> bool {
+        if self.offline {
+            return false;
+        }
+        if self.allowed_hosts.is_empty() {
+            return true;
+        }
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        let ip = host.parse::<IpAddr>().ok();
+        self.allowed_hosts
+            .iter()
+            .any(|entry| match (ip, entry.parse::<IpNetwork>()) {
+                (Some(ip), Ok(net)) => net.contains(ip),
+                (None, Err(_)) => {
+                    let entry = entry
+                        .trim_start_matches("*.")
+                        .trim_end_matches('.')
+                        .to_ascii_lowercase();
+                    host == entry || host.ends_with(&format!(".{}", entry))
+                }
+                _ => false,
+            })
+    }
+
+    /// Whether the host of `url` may be contacted
+    pub fn allows_url(&self, url: &str) -> bool {
+        reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|host| self.allows_host(host)))
+            .unwrap_or(false)
+    }
+
+    /// DNS resolver using the system's name servers, minus any the policy
+    /// doesn't allow (Google DNS if the system config is unreadable)
+    pub fn resolver(&self) -> Result<TokioResolver> {
+        if self.offline {
+            return Err(TtlError::Lookup(
+                "DNS lookups are disabled by --offline".into(),
+            ));
+        }
+        let (config, opts) = match read_system_conf() {
+            Ok(conf) => conf,
+            Err(_) => {
+                eprintln!("Warning: System DNS config unavailable, using Google DNS (8.8.8.8)");
+                (ResolverConfig::google(), ResolverOpts::default())
+            }
+        };
+        let servers: Vec<_> = config
+            .name_servers()
+            .iter()
+            .filter(|ns| self.allows_host(&ns.socket_addr.ip().to_string()))
+            .cloned()
+            .collect();
+        if servers.is_empty() {
+            return Err(TtlError::Lookup("no DNS resolver in allowed_hosts".into()));
+        }
+        let config =
+            ResolverConfig::from_parts(config.domain().cloned(), config.search().to_vec(), servers);
+        Ok(
+            Resolver::builder_with_config(config, TokioConnectionProvider::default())
+                .with_options(opts)
+                .build(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(hosts: &[&str]) -> NetPolicy {
+        NetPolicy {
+            offline: false,
+            allowed_hosts: hosts.iter().map(|h| h.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_allows_host() {
+        assert!(NetPolicy::default().allows_host("www.peeringdb.com"));
+
+        let p = policy(&["peeringdb.com", "*.ripe.net", "10.0.0.0/8", "2001:db8::53"]);
+        assert!(p.allows_host("www.peeringdb.com"));
+        assert!(p.allows_host("PeeringDB.com."));
+        assert!(!p.allows_host("notpeeringdb.com"));
+        assert!(p.allows_host("stat.ripe.net"));
+        assert!(!p.allows_host("asn.cymru.com"));
+        assert!(p.allows_host("10.1.2.3"));
+        assert!(!p.allows_host("192.0.2.1"));
+        assert!(p.allows_host("[2001:db8::53]"));
+
+        assert!(p.allows_url("https://www.peeringdb.com/api/ix?limit=0"));
+        assert!(!p.allows_url("https://hooks.example.com/ttl"));
+        assert!(!p.allows_url("not a url"));
+    }
+
+    #[test]
+    fn test_offline_allows_nothing() {
+        let p = NetPolicy {
+            offline: true,
+            allowed_hosts: vec!["peeringdb.com".to_string()],
+        };
+        assert!(!p.allows_host("www.peeringdb.com"));
+        assert!(NetPolicy::default().allows_host("192.0.2.1"));
+        assert!(
+            NetPolicy {
+                offline: true,
+                ..Default::default()
+            }
+            .resolver()
+            .is_err()
+        );
+    }
+}
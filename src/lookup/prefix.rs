@@ -0,0 +1,245 @@
+//! BGP prefix and origin tracking for hop responders
+//!
+//! Team Cymru (see `asn`) answers with the covering prefix once and the
+//! answer is cached for the session. For `--prefix-watch` each responder is
+//! re-checked against RIPEstat's routing data every few minutes, so a
+//! more-specific announcement or a new origin AS showing up mid-session is
+//! recorded against the hop — the signature of a hijack or route leak,
+//! as opposed to an IGP reroute where the prefix stays put.
+
+use anyhow::{Result, anyhow};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::policy::NetPolicy;
+use super::sanitize_display;
+use crate::config::LookupLimit;
+use crate::trace::receiver::SessionMap;
+
+pub const NETWORK_INFO_URL: &str = "https://stat.ripe.net/data/network-info/data.json";
+
+/// How long a prefix answer is trusted before the address is checked again
+const RECHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Deserialize)]
+struct NetworkInfoResponse {
+    data: NetworkInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct NetworkInfo {
+    #[serde(default)]
+    asns: Vec<String>,
+    #[serde(default)]
+    prefix: Option<String>,
+}
+
+/// Extract the covering prefix and origin ASNs from a RIPEstat
+/// network-info response. Unannounced space has no prefix.
+fn parse_network_info(body: &str) -> Result<Option<(String, Vec<u32>)>> {
+    let info = serde_json::from_str::<NetworkInfoResponse>(body)?.data;
+    let Some(prefix) = info.prefix.filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let mut origins: Vec<u32> = info.asns.iter().filter_map(|a| a.parse().ok()).collect();
+    origins.sort_unstable();
+    origins.dedup();
+    Ok(Some((sanitize_display(&prefix), origins)))
+}
+
+/// Whether an address can appear in the global routing table.
+/// Private, shared (CGNAT), loopback, link-local and documentation
+/// ranges never will, so they aren't worth a query.
+fn is_globally_routed(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let o = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_documentation()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || (o[0] == 100 && (o[1] & 0xc0) == 64))
+        }
+        // Only 2000::/3 is allocated for global unicast
+        IpAddr::V6(v6) => (v6.segments()[0] & 0xe000) == 0x2000,
+    }
+}
+
+/// RIPEstat client that remembers when each address was last checked
+pub struct PrefixLookup {
+    client: reqwest::Client,
+    checked: Mutex<HashMap<IpAddr, Instant>>,
+}
+
+impl PrefixLookup {
+    pub fn new(policy: &NetPolicy) -> Result<Self> {
+        if !policy.allows_url(NETWORK_INFO_URL) {
+            return Err(anyhow!("stat.ripe.net is not in allowed_hosts"));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(format!(
+                "ttl/{} (https://github.com/lance0/ttl)",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()?;
+        Ok(Self {
+            client,
+            checked: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// From `ips`, the addresses due for a (re)check, marking them checked
+    fn take_due(&self, ips: impl IntoIterator<Item = IpAddr>, max: usize) -> Vec<IpAddr> {
+        let mut checked = self.checked.lock();
+        let now = Instant::now();
+        let due: Vec<IpAddr> = ips
+            .into_iter()
+            .filter(|ip| {
+                checked
+                    .get(ip)
+                    .is_none_or(|at| now.duration_since(*at) >= RECHECK_INTERVAL)
+            })
+            .take(max)
+            .collect();
+        for ip in &due {
+            checked.insert(*ip, now);
+        }
+        due
+    }
+
+    /// Current covering prefix and origins for an address
+    pub async fn lookup(&self, ip: IpAddr) -> Result<Option<(String, Vec<u32>)>> {
+        let response = self
+            .client
+            .get(format!("{}?resource={}", NETWORK_INFO_URL, ip))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("RIPEstat returned {}", response.status()));
+        }
+        let body = response.text().await?;
+        parse_network_info(&body)
+    }
+}
+
+/// Background worker that keeps responder prefixes current across sessions
+pub async fn run_prefix_worker(
+    prefix_lookup: Arc<PrefixLookup>,
+    sessions: SessionMap,
+    cancel: CancellationToken,
+    limit: LookupLimit,
+) {
+    let mut interval = tokio::time::interval(limit.interval);
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                break;
+            }
+            _ = interval.tick() => {
+                // The same router often shows up in several sessions; ask once
+                let candidates: HashSet<IpAddr> = {
+                    let sessions = sessions.read();
+                    sessions.values()
+                        .flat_map(|state| {
+                            let session = state.read();
+                            if !session.config.prefix_watch {
+                                return Vec::new();
+                            }
+                            session.hops.iter()
+                                .flat_map(|hop| hop.responders.keys().copied())
+                                .filter(|ip| is_globally_routed(*ip))
+                                .collect::<Vec<_>>()
+                        })
+                        .collect()
+                };
+
+                let batch = prefix_lookup.take_due(candidates, limit.concurrency);
+                if batch.is_empty() {
+                    continue;
+                }
+
+                let futures: Vec<_> = batch
+                    .iter()
+                    .map(|&ip| {
+                        let lookup = prefix_lookup.clone();
+                        async move { (ip, lookup.lookup(ip).await) }
+                    })
+                    .collect();
+                let results = futures::future::join_all(futures).await;
+
+                // Failed or empty answers keep the last known prefix
+                let sessions = sessions.read();
+                for (ip, result) in results {
+                    if let Ok(Some((prefix, origins))) = result {
+                        for state in sessions.values() {
+                            state.write().update_prefix(ip, &prefix, &origins);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network_info() {
+        let body =
+            r#"{"status":"ok","data":{"asns":["3333","3333","1101"],"prefix":"193.0.0.0/21"}}"#;
+        let (prefix, origins) = parse_network_info(body).unwrap().unwrap();
+        assert_eq!(prefix, "193.0.0.0/21");
+        assert_eq!(origins, vec![1101, 3333]);
+
+        let unannounced = r#"{"data":{"asns":[],"prefix":""}}"#;
+        assert!(parse_network_info(unannounced).unwrap().is_none());
+        assert!(parse_network_info("not json").is_err());
+    }
+
+    #[test]
+    fn test_is_globally_routed() {
+        for ip in ["193.0.6.139", "8.8.8.8", "2001:4860:4860::8888"] {
+            assert!(is_globally_routed(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "10.1.2.3",
+            "192.168.1.1",
+            "100.64.0.1",
+            "169.254.1.1",
+            "192.0.2.1",
+            "fe80::1",
+            "fd00::1",
+        ] {
+            assert!(!is_globally_routed(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_take_due_rechecks_later() {
+        let lookup = PrefixLookup::new(&NetPolicy::default()).unwrap();
+        let a: IpAddr = "8.8.8.8".parse().unwrap();
+        let b: IpAddr = "1.1.1.1".parse().unwrap();
+        assert_eq!(lookup.take_due([a, b], 1).len(), 1);
+        // The one left over is still due; the checked one isn't
+        assert_eq!(lookup.take_due([a, b], 4).len(), 1);
+        assert!(lookup.take_due([a, b], 4).is_empty());
+
+        lookup
+            .checked
+            .lock()
+            .insert(a, Instant::now() - RECHECK_INTERVAL);
+        assert_eq!(lookup.take_due([a, b], 4), vec![a]);
+    }
+}
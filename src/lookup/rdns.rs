@@ -1,6 +1,4 @@
-use anyhow::Result;
-use hickory_resolver::config::ResolverConfig;
-use hickory_resolver::{Resolver, TokioResolver};
+use hickory_resolver::TokioResolver;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::net::IpAddr;
@@ -8,7 +6,10 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use super::policy::NetPolicy;
 use super::sanitize_display;
+use crate::config::LookupLimit;
+use crate::error::Result;
 use crate::trace::receiver::SessionMap;
 
 /// DNS cache entry
@@ -25,19 +26,8 @@ pub struct DnsLookup {
 }
 
 impl DnsLookup {
-    pub async fn new() -> Result<Self> {
-        // Try system DNS config first, fall back to Google DNS if unavailable
-        let resolver = match Resolver::builder_tokio() {
-            Ok(builder) => builder.build(),
-            Err(_) => {
-                eprintln!("Warning: System DNS config unavailable, using Google DNS (8.8.8.8)");
-                Resolver::builder_with_config(
-                    ResolverConfig::google(),
-                    hickory_resolver::name_server::TokioConnectionProvider::default(),
-                )
-                .build()
-            }
-        };
+    pub async fn new(policy: &NetPolicy) -> Result<Self> {
+        let resolver = policy.resolver()?;
 
         Ok(Self {
             resolver,
@@ -46,6 +36,11 @@ impl DnsLookup {
         })
     }
 
+    /// Drop the cached name for an IP so the next lookup asks again
+    pub fn forget(&self, ip: IpAddr) {
+        self.cache.write().remove(&ip);
+    }
+
     /// Lookup reverse DNS for an IP, using cache
     pub async fn reverse_lookup(&self, ip: IpAddr) -> Option<String> {
         // Check cache first
@@ -85,12 +80,14 @@ impl DnsLookup {
     }
 }
 
-/// Maximum concurrent DNS lookups
-const MAX_CONCURRENT_LOOKUPS: usize = 10;
-
 /// Background DNS lookup worker that updates session state (multi-target)
-pub async fn run_dns_worker(dns: Arc<DnsLookup>, sessions: SessionMap, cancel: CancellationToken) {
-    let mut interval = tokio::time::interval(Duration::from_millis(500));
+pub async fn run_dns_worker(
+    dns: Arc<DnsLookup>,
+    sessions: SessionMap,
+    cancel: CancellationToken,
+    limit: LookupLimit,
+) {
+    let mut interval = tokio::time::interval(limit.interval);
 
     loop {
         tokio::select! {
@@ -98,16 +95,21 @@ pub async fn run_dns_worker(dns: Arc<DnsLookup>, sessions: SessionMap, cancel: C
                 break;
             }
             _ = interval.tick() => {
-                // Collect IPs that need lookup from all sessions
-                let ips_to_lookup: Vec<IpAddr> = {
+                // Collect IPs that need lookup from all sessions, and
+                // whether each was asked to refresh
+                let ips_to_lookup: Vec<(IpAddr, bool)> = {
                     let sessions = sessions.read();
                     sessions.values()
                         .flat_map(|state| {
                             let session = state.read();
+                            // Lookups can be switched off at runtime by a config reload
+                            if !session.config.dns_enabled {
+                                return Vec::new();
+                            }
                             session.hops.iter()
                                 .flat_map(|hop| hop.responders.values())
-                                .filter(|stats| stats.hostname.is_none())
-                                .map(|stats| stats.ip)
+                                .filter(|stats| stats.hostname.is_none() || stats.refresh.dns)
+                                .map(|stats| (stats.ip, stats.refresh.dns))
                                 .collect::<Vec<_>>()
                         })
                         .collect()
@@ -118,16 +120,19 @@ pub async fn run_dns_worker(dns: Arc<DnsLookup>, sessions: SessionMap, cancel: C
                 }
 
                 // Perform parallel DNS lookups (limited batch size)
-                let batch: Vec<IpAddr> = ips_to_lookup
+                let batch: Vec<(IpAddr, bool)> = ips_to_lookup
                     .into_iter()
-                    .take(MAX_CONCURRENT_LOOKUPS)
+                    .take(limit.concurrency)
                     .collect();
 
                 // Spawn concurrent lookups
                 let futures: Vec<_> = batch
                     .iter()
-                    .map(|&ip| {
+                    .map(|&(ip, refresh)| {
                         let dns = dns.clone();
+                        if refresh {
+                            dns.forget(ip);
+                        }
                         async move { (ip, dns.reverse_lookup(ip).await) }
                     })
                     .collect();
@@ -137,12 +142,14 @@ pub async fn run_dns_worker(dns: Arc<DnsLookup>, sessions: SessionMap, cancel: C
 
                 // Update all sessions with results
                 let sessions = sessions.read();
+                // (a failed refresh keeps the old name)
                 for (ip, hostname) in results {
-                    if let Some(hostname) = hostname {
-                        for state in sessions.values() {
-                            let mut session = state.write();
-                            for hop in &mut session.hops {
-                                if let Some(stats) = hop.responders.get_mut(&ip) {
+                    for state in sessions.values() {
+                        let mut session = state.write();
+                        for hop in &mut session.hops {
+                            if let Some(stats) = hop.responders.get_mut(&ip) {
+                                stats.refresh.dns = false;
+                                if let Some(ref hostname) = hostname {
                                     stats.hostname = Some(hostname.clone());
                                 }
                             }
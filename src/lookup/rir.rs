@@ -0,0 +1,328 @@
+//! ASN allocation metadata from RIR delegated statistics
+//!
+//! Every Regional Internet Registry publishes a daily "delegated-extended"
+//! file listing the ASN blocks it has handed out, with country code and
+//! allocation date. The five files are fetched once, reduced to ASN ranges,
+//! and cached on disk so later runs can answer offline.
+
+use anyhow::{Result, anyhow};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tokio::sync::OnceCell;
+
+use super::cache;
+use super::policy::NetPolicy;
+use super::sanitize_display;
+
+/// Delegated-extended statistics published by each RIR
+const DELEGATED_URLS: &[&str] = &[
+    "https://ftp.afrinic.net/pub/stats/afrinic/delegated-afrinic-extended-latest",
+    "https://ftp.apnic.net/stats/apnic/delegated-apnic-extended-latest",
+    "https://ftp.arin.net/pub/stats/arin/delegated-arin-extended-latest",
+    "https://ftp.lacnic.net/pub/stats/lacnic/delegated-lacnic-extended-latest",
+    "https://ftp.ripe.net/pub/stats/ripencc/delegated-ripencc-extended-latest",
+];
+
+/// Backoff period after a failed download (30 minutes)
+const LOAD_FAILURE_BACKOFF_SECS: u64 = 1800;
+
+/// Registry, country and allocation date for an ASN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsnAllocation {
+    pub registry: String,
+    pub country: String,
+    /// Allocation date as YYYY-MM-DD, when the registry records one
+    pub allocated: Option<String>,
+}
+
+/// A block of consecutive ASNs delegated together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AsnRange {
+    start: u32,
+    count: u32,
+    registry: String,
+    country: String,
+    allocated: Option<String>,
+}
+
+impl AsnRange {
+    fn contains(&self, asn: u32) -> bool {
+        asn >= self.start && asn - self.start < self.count
+    }
+}
+
+/// Cache file under `~/.cache/ttl`
+pub const CACHE_FILE: (&str, &str) = ("rir", "asn_delegations.json");
+/// Cache format version; files with another version are refetched
+pub const CACHE_VERSION: u32 = 1;
+
+/// Serializable cache format
+#[derive(Debug, Serialize, Deserialize)]
+struct RirCache {
+    version: u32,
+    fetched_at: u64, // Unix timestamp
+    ranges: Vec<AsnRange>,
+}
+
+impl RirCache {
+    const VERSION: u32 = CACHE_VERSION;
+    /// Allocations change slowly; refresh weekly
+    const MAX_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+    fn is_expired(&self) -> bool {
+        unix_now().saturating_sub(self.fetched_at) > Self::MAX_AGE_SECS
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Convert a delegated-stats date (YYYYMMDD) to YYYY-MM-DD.
+/// Registries use 00000000 or an empty field for unknown dates.
+fn format_date(raw: &str) -> Option<String> {
+    if raw.len() != 8 || !raw.bytes().all(|b| b.is_ascii_digit()) || raw == "00000000" {
+        return None;
+    }
+    Some(format!("{}-{}-{}", &raw[..4], &raw[4..6], &raw[6..]))
+}
+
+/// Parse ASN records out of a delegated-extended file.
+///
+/// Lines look like `ripencc|NL|asn|1101|1|19930901|allocated|...`; the
+/// version header, summary lines and non-ASN records are skipped.
+fn parse_delegated(text: &str) -> Vec<AsnRange> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 7 || fields[2] != "asn" {
+                return None;
+            }
+            if !matches!(fields[6], "allocated" | "assigned") {
+                return None;
+            }
+            let start: u32 = fields[3].parse().ok()?;
+            let count: u32 = fields[4].parse().ok()?;
+            Some(AsnRange {
+                start,
+                count,
+                registry: sanitize_display(fields[0]),
+                country: sanitize_display(fields[1]),
+                allocated: format_date(fields[5]),
+            })
+        })
+        .collect()
+}
+
+/// ASN allocation lookup backed by cached RIR delegated statistics
+pub struct RirLookup {
+    /// Ranges sorted by start ASN
+    ranges: RwLock<Vec<AsnRange>>,
+    /// Cache file path (None with --no-disk-cache)
+    cache_path: Option<PathBuf>,
+    /// Filled once data has loaded successfully
+    load_once: OnceCell<()>,
+    /// Timestamp of last load failure (for backoff)
+    last_failure: AtomicU64,
+    /// Whether the registries may be contacted; if not, only the cache is used
+    fetch: bool,
+}
+
+impl RirLookup {
+    pub fn new(policy: &NetPolicy, disk_cache: bool) -> Result<Self> {
+        let cache_path = if disk_cache {
+            Some(cache::dir(CACHE_FILE.0)?.join(CACHE_FILE.1))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            ranges: RwLock::new(Vec::new()),
+            cache_path,
+            load_once: OnceCell::new(),
+            last_failure: AtomicU64::new(0),
+            fetch: DELEGATED_URLS.iter().all(|url| policy.allows_url(url)),
+        })
+    }
+
+    /// Look up the allocation record for an ASN, loading data on first use
+    pub async fn lookup(&self, asn: u32) -> Option<AsnAllocation> {
+        if self.load_once.get().is_none() {
+            let last_fail = self.last_failure.load(Ordering::Relaxed);
+            if last_fail > 0 && unix_now().saturating_sub(last_fail) < LOAD_FAILURE_BACKOFF_SECS {
+                return None;
+            }
+            let result = self
+                .load_once
+                .get_or_try_init(|| async {
+                    // Allocation data is optional enrichment; fail quietly
+                    self.load_data()
+                        .await
+                        .inspect_err(|_| self.last_failure.store(unix_now(), Ordering::Relaxed))
+                })
+                .await;
+            if result.is_err() {
+                return None;
+            }
+        }
+
+        find_range(&self.ranges.read(), asn).map(|r| AsnAllocation {
+            registry: r.registry.clone(),
+            country: r.country.clone(),
+            allocated: r.allocated.clone(),
+        })
+    }
+
+    /// Load from cache, refreshing from the registries when stale
+    async fn load_data(&self) -> Result<()> {
+        let cached = self.load_cache().ok();
+        if let Some(cache) = cached.as_ref().filter(|c| !c.is_expired()) {
+            self.populate(&cache.ranges);
+            return Ok(());
+        }
+
+        if !self.fetch {
+            let cache = cached.ok_or_else(|| anyhow!("RIR mirrors not allowed and no cache"))?;
+            self.populate(&cache.ranges);
+            return Ok(());
+        }
+
+        match self.fetch_all().await {
+            Ok(cache) => {
+                let _ = self.save_cache(&cache);
+                self.populate(&cache.ranges);
+                Ok(())
+            }
+            // A stale cache beats no data at all
+            Err(e) => match cached {
+                Some(cache) => {
+                    self.populate(&cache.ranges);
+                    Ok(())
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    fn load_cache(&self) -> Result<RirCache> {
+        let path = self
+            .cache_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("disk cache disabled"))?;
+        let data = fs::read_to_string(path)?;
+        let cache: RirCache = serde_json::from_str(&data)?;
+        if cache.version != RirCache::VERSION {
+            return Err(anyhow!("cache version mismatch"));
+        }
+        Ok(cache)
+    }
+
+    fn save_cache(&self, cache: &RirCache) -> Result<()> {
+        if let Some(ref path) = self.cache_path {
+            cache::write(path, &serde_json::to_string(cache)?)?;
+        }
+        Ok(())
+    }
+
+    fn populate(&self, ranges: &[AsnRange]) {
+        let mut sorted = ranges.to_vec();
+        sorted.sort_by_key(|r| r.start);
+        *self.ranges.write() = sorted;
+    }
+
+    /// Download all five registries' files. Every registry must succeed so a
+    /// partial download is never cached for a week.
+    async fn fetch_all(&self) -> Result<RirCache> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .user_agent(format!(
+                "ttl/{} (https://github.com/lance0/ttl)",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()?;
+
+        let bodies = futures::future::join_all(DELEGATED_URLS.iter().map(|url| {
+            let client = client.clone();
+            async move {
+                client
+                    .get(*url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await
+            }
+        }))
+        .await;
+
+        let mut ranges = Vec::new();
+        for body in bodies {
+            ranges.extend(parse_delegated(&body?));
+        }
+        if ranges.is_empty() {
+            return Err(anyhow!("no ASN records in delegated statistics"));
+        }
+
+        Ok(RirCache {
+            version: RirCache::VERSION,
+            fetched_at: unix_now(),
+            ranges,
+        })
+    }
+}
+
+/// Binary search sorted ranges for the block containing `asn`
+fn find_range(ranges: &[AsnRange], asn: u32) -> Option<&AsnRange> {
+    let idx = ranges.partition_point(|r| r.start <= asn);
+    ranges[..idx].last().filter(|r| r.contains(asn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+2|ripencc|1700000000|123|19830705|20231114|+0100
+ripencc|*|asn|*|38000|summary
+ripencc|NL|asn|1101|3|19930901|allocated|abc
+ripencc|EU|ipv4|2.0.0.0|1048576|20100712|allocated|def
+ripencc||asn|1200|1||available
+ripencc|DE|asn|3320|1|00000000|assigned|ghi
+";
+
+    #[test]
+    fn test_parse_delegated_keeps_asn_records() {
+        let ranges = parse_delegated(SAMPLE);
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].start, 1101);
+        assert_eq!(ranges[0].count, 3);
+        assert_eq!(ranges[0].country, "NL");
+        assert_eq!(ranges[0].allocated.as_deref(), Some("1993-09-01"));
+        assert_eq!(ranges[1].allocated, None);
+    }
+
+    #[test]
+    fn test_find_range() {
+        let mut ranges = parse_delegated(SAMPLE);
+        ranges.sort_by_key(|r| r.start);
+        assert_eq!(find_range(&ranges, 1103).unwrap().start, 1101);
+        assert!(find_range(&ranges, 1104).is_none());
+        assert_eq!(find_range(&ranges, 3320).unwrap().country, "DE");
+        assert!(find_range(&ranges, 1).is_none());
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date("20000330").as_deref(), Some("2000-03-30"));
+        assert_eq!(format_date("00000000"), None);
+        assert_eq!(format_date(""), None);
+    }
+}
@@ -0,0 +1,272 @@
+//! Plain WHOIS (port 43) fallback for ASN lookups
+//!
+//! Some networks let whois out but block DNS to outside zones, or the Team
+//! Cymru DNS service is simply unreachable. When an origin or AS name
+//! lookup over DNS fails, the same data is asked from Team Cymru's whois
+//! server, and an AS name still missing is taken from the registry that
+//! holds the ASN. Queries to each server are spaced out, and a server that
+//! can't be reached is left alone for a while.
+
+use anyhow::{Context, Result, bail};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::policy::NetPolicy;
+use super::sanitize_display;
+use crate::state::AsnInfo;
+
+/// Team Cymru's IP-to-ASN whois service
+pub const CYMRU_WHOIS: &str = "whois.cymru.com";
+const WHOIS_PORT: u16 = 43;
+/// Connect, send and read must all finish within this
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Minimum spacing between queries to one server
+const MIN_QUERY_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a server is skipped after a failed query
+const FAILURE_BACKOFF: Duration = Duration::from_secs(300);
+/// Largest answer read; RIR objects are a few kilobytes
+const MAX_RESPONSE: u64 = 64 * 1024;
+
+/// Whois server of each registry, as named in Cymru and RIR records
+fn registry_server(registry: &str) -> Option<&'static str> {
+    match registry.to_ascii_lowercase().as_str() {
+        "arin" => Some("whois.arin.net"),
+        "ripencc" | "ripe" => Some("whois.ripe.net"),
+        "apnic" => Some("whois.apnic.net"),
+        "afrinic" => Some("whois.afrinic.net"),
+        "lacnic" => Some("whois.lacnic.net"),
+        _ => None,
+    }
+}
+
+/// Parse Cymru's verbose answer for one address:
+///
+/// ```text
+/// AS      | IP               | BGP Prefix          | CC | Registry | Allocated  | AS Name
+/// 15169   | 8.8.8.8          | 8.8.8.0/24          | US | arin     | 1992-12-01 | GOOGLE, US
+/// ```
+fn parse_cymru(text: &str) -> Option<AsnInfo> {
+    let line = text.lines().find(|line| {
+        line.split('|')
+            .next()
+            .is_some_and(|f| f.trim().parse::<u32>().is_ok())
+    })?;
+    let parts: Vec<&str> = line.split('|').map(|s| s.trim()).collect();
+    let field = |i: usize| {
+        parts
+            .get(i)
+            .filter(|s| !s.is_empty() && **s != "NA")
+            .map(|s| sanitize_display(s))
+    };
+    let number: u32 = parts[0].parse().ok()?;
+    Some(AsnInfo {
+        number,
+        name: field(6).unwrap_or_else(|| format!("AS{}", number)),
+        prefix: field(2),
+        country: field(3),
+        registry: field(4),
+        allocated: field(5),
+    })
+}
+
+/// AS name from a registry's aut-num answer. ARIN gives `ASName` and
+/// `OrgName`, the RPSL registries (RIPE, APNIC, AFRINIC) `as-name` and
+/// `descr`, LACNIC `owner`. The organisation is preferred, as Cymru's
+/// names are.
+fn parse_as_name(text: &str) -> Option<String> {
+    let mut fields: HashMap<String, &str> = HashMap::new();
+    for line in text.lines() {
+        if line.starts_with(['%', '#']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if !value.is_empty() {
+            // First occurrence wins; later objects are usually the org's
+            // contacts
+            fields
+                .entry(key.trim().to_ascii_lowercase())
+                .or_insert(value);
+        }
+    }
+    ["orgname", "owner", "descr", "asname", "as-name"]
+        .iter()
+        .find_map(|key| fields.get(*key))
+        .map(|name| sanitize_display(name))
+}
+
+/// Per-server pacing and failure state
+#[derive(Default)]
+struct ServerState {
+    next_slot: Option<Instant>,
+    down_until: Option<Instant>,
+}
+
+/// Rate-limited whois client
+pub struct WhoisClient {
+    policy: NetPolicy,
+    servers: Mutex<HashMap<&'static str, ServerState>>,
+}
+
+impl WhoisClient {
+    pub fn new(policy: &NetPolicy) -> Self {
+        Self {
+            policy: policy.clone(),
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the Cymru whois server may be contacted
+    pub fn enabled(&self) -> bool {
+        self.policy.allows_host(CYMRU_WHOIS)
+    }
+
+    /// Origin AS, prefix and AS name for an address
+    pub async fn lookup_ip(&self, ip: IpAddr) -> Option<AsnInfo> {
+        let text = self.query(CYMRU_WHOIS, &format!(" -v {}", ip)).await.ok()?;
+        parse_cymru(&text)
+    }
+
+    /// AS name from the registry holding the ASN
+    pub async fn as_name(&self, asn: u32, registry: &str) -> Option<String> {
+        let server = registry_server(registry)?;
+        // ARIN wants an explicit ASN query; the others take the handle
+        let query = if server == "whois.arin.net" {
+            format!("a {}", asn)
+        } else {
+            format!("AS{}", asn)
+        };
+        let text = self.query(server, &query).await.ok()?;
+        parse_as_name(&text)
+    }
+
+    /// Wait for a slot on `server`, or fail if it's backed off
+    async fn reserve(&self, server: &'static str) -> Result<()> {
+        let wait = {
+            let mut servers = self.servers.lock();
+            let state = servers.entry(server).or_default();
+            let now = Instant::now();
+            if state.down_until.is_some_and(|until| until > now) {
+                bail!("{} unreachable recently", server);
+            }
+            let slot = state.next_slot.map_or(now, |next| next.max(now));
+            state.next_slot = Some(slot + MIN_QUERY_INTERVAL);
+            slot - now
+        };
+        tokio::time::sleep(wait).await;
+        Ok(())
+    }
+
+    async fn query(&self, server: &'static str, query: &str) -> Result<String> {
+        if !self.policy.allows_host(server) {
+            bail!("{} is not in allowed_hosts", server);
+        }
+        self.reserve(server).await?;
+        let result = tokio::time::timeout(QUERY_TIMEOUT, async {
+            let mut stream = TcpStream::connect((server, WHOIS_PORT)).await?;
+            stream
+                .write_all(format!("{}\r\n", query).as_bytes())
+                .await?;
+            let mut buf = Vec::new();
+            stream.take(MAX_RESPONSE).read_to_end(&mut buf).await?;
+            Ok::<_, std::io::Error>(buf)
+        })
+        .await
+        .with_context(|| format!("{} timed out", server))
+        .and_then(|r| r.with_context(|| format!("querying {}", server)));
+
+        match result {
+            Ok(buf) => Ok(String::from_utf8_lossy(&buf).into_owned()),
+            Err(e) => {
+                self.servers.lock().entry(server).or_default().down_until =
+                    Some(Instant::now() + FAILURE_BACKOFF);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cymru() {
+        let text = "Bulk mode; whois.cymru.com [2026-01-01 00:00:00 +0000]\n\
+                    15169   | 8.8.8.8          | 8.8.8.0/24          | US | arin     | 1992-12-01 | GOOGLE, US\n";
+        let info = parse_cymru(text).unwrap();
+        assert_eq!(info.number, 15169);
+        assert_eq!(info.name, "GOOGLE, US");
+        assert_eq!(info.prefix.as_deref(), Some("8.8.8.0/24"));
+        assert_eq!(info.registry.as_deref(), Some("arin"));
+
+        // Unrouted addresses come back as "NA"
+        assert!(
+            parse_cymru(
+                "NA      | 10.0.0.1         | NA                  |    | other    |            | NA"
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parse_as_name() {
+        let arin = "#\n# ARIN WHOIS data\n#\n\nASNumber:       15169\nASName:         GOOGLE\n\
+                    ASHandle:       AS15169\n\nOrgName:        Google LLC\nOrgId:          GOGL\n";
+        assert_eq!(parse_as_name(arin).as_deref(), Some("Google LLC"));
+
+        let ripe = "% This is the RIPE Database query service.\n\naut-num:        AS3333\n\
+                    as-name:        RIPE-NCC-AS\norg:            ORG-RIEN1-RIPE\n";
+        assert_eq!(parse_as_name(ripe).as_deref(), Some("RIPE-NCC-AS"));
+
+        let apnic = "aut-num:        AS4608\nas-name:        APNIC-SERVICES\n\
+                     descr:          Asia Pacific Network Information Centre\n\
+                     descr:          Regional Internet Registry\n";
+        assert_eq!(
+            parse_as_name(apnic).as_deref(),
+            Some("Asia Pacific Network Information Centre")
+        );
+
+        let lacnic = "% LACNIC resource: whois.lacnic.net\n\naut-num:     AS28000\n\
+                      owner:       LACNIC - Latin American and Caribbean IP address\n";
+        assert_eq!(
+            parse_as_name(lacnic).as_deref(),
+            Some("LACNIC - Latin American and Caribbean IP address")
+        );
+
+        assert_eq!(parse_as_name("% No entries found\n"), None);
+    }
+
+    #[test]
+    fn test_registry_server() {
+        assert_eq!(registry_server("ripencc"), Some("whois.ripe.net"));
+        assert_eq!(registry_server("ARIN"), Some("whois.arin.net"));
+        assert_eq!(registry_server("other"), None);
+    }
+
+    #[tokio::test]
+    async fn test_failed_server_backs_off() {
+        let client = WhoisClient::new(&NetPolicy::default());
+        client
+            .servers
+            .lock()
+            .entry(CYMRU_WHOIS)
+            .or_default()
+            .down_until = Some(Instant::now() + FAILURE_BACKOFF);
+        assert!(client.reserve(CYMRU_WHOIS).await.is_err());
+
+        // Not allowed by the policy: no connection attempted
+        let offline = WhoisClient::new(&NetPolicy {
+            offline: true,
+            ..Default::default()
+        });
+        assert!(!offline.enabled());
+        assert!(offline.query(CYMRU_WHOIS, " -v 8.8.8.8").await.is_err());
+    }
+}
@@ -3,44 +3,51 @@ use clap::Parser;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
-use std::net::{IpAddr, ToSocketAddrs};
+use std::io::{BufReader, Write};
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 
+mod build_info;
 mod cli;
 mod config;
+mod error;
 mod export;
 mod lookup;
+mod plugin;
 mod prefs;
 mod probe;
+mod reload;
+mod remote;
+mod sinks;
 mod state;
+mod systemd;
 mod trace;
 mod tui;
 
-use cli::Args;
-use config::Config;
-use export::{export_csv, export_json, generate_report};
-use lookup::asn::{AsnLookup, run_asn_worker};
-use lookup::geo::{GeoLookup, run_geo_worker};
-use lookup::ix::{IxLookup, run_ix_worker};
-use lookup::rdns::{DnsLookup, run_dns_worker};
-use prefs::Prefs;
-use probe::{
-    InterfaceInfo, check_permissions, detect_default_gateway, get_local_addr_with_interface,
-    validate_interface,
+use cli::{Args, CacheAction, Command, ConfigAction};
+use config::{Config, PluginHook};
+use export::{
+    PROGRESS_PERIOD, analyze_history, export_csv, export_json, export_view, generate_report_with,
+    merge_sessions, status_line_multi, write_analysis_report, write_merged_report, write_progress,
+    write_summary,
 };
-use state::{Session, Target, run_ratelimit_worker};
-use trace::engine::ProbeEngine;
-use trace::pending::new_pending_map;
-use trace::receiver::{ReceiverConfig, SessionMap, spawn_receiver};
-use tui::app::run_tui;
+use plugin::run_export_plugins;
+use prefs::Prefs;
+use probe::{InterfaceInfo, check_permissions, validate_interface};
+use reload::spawn_config_watcher;
+use remote::{connect_agent, default_agent_listen, forwarded_args, query_health, run_agent};
+use state::Session;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use trace::receiver::SessionMap;
+use trace::runner::{ResolveOptions, build_sessions, check_address_families, start_tracing};
+use tui::app::{ConfigNotice, run_tui};
 use tui::theme::Theme;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
 
     // Handle shell completion generation (before validation, doesn't need targets)
     if let Some(ref shell) = args.completions {
@@ -48,17 +55,85 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.build_info {
+        return build_info::write_build_info(std::io::stdout());
+    }
+
+    // Hidden: time the parse/stats path against a captured response corpus
+    if let Some(ref corpus) = args.bench_parse {
+        return run_bench_parse(corpus);
+    }
+
+    // Subcommands skip the normal trace flow
+    match args.command {
+        Some(Command::Merge { ref files, json }) => return run_merge(files, json),
+        Some(Command::Analyze {
+            ref files,
+            ref target,
+            since,
+            json,
+        }) => return run_analyze(files, target.as_deref(), since, json),
+        Some(Command::Cache {
+            action: CacheAction::Purge,
+        }) => return run_cache_purge(),
+        Some(Command::Config {
+            action: ConfigAction::Show { json, ref flags },
+        }) => return run_config_show(flags, json),
+        Some(Command::Config {
+            action: ConfigAction::Init { force },
+        }) => return run_config_init(force),
+        Some(Command::Agent {
+            health: Some(ref addr),
+            ref token,
+            ..
+        }) => {
+            let health = query_health(addr, token).await?;
+            println!("{}", serde_json::to_string_pretty(&health)?);
+            return Ok(());
+        }
+        Some(Command::Agent {
+            listen,
+            allow_remote,
+            ref token,
+            ..
+        }) => {
+            check_permissions()?;
+            let cancel = CancellationToken::new();
+            let cancel_clone = cancel.clone();
+            tokio::spawn(async move {
+                tokio::signal::ctrl_c().await.ok();
+                cancel_clone.cancel();
+            });
+            let listen = listen.unwrap_or_else(default_agent_listen);
+            return run_agent(listen, allow_remote, token.clone(), cancel).await;
+        }
+        None => {}
+    }
+
+    // Fill in settings from config.toml that weren't given on the command line
+    let cli_args = args.clone();
+    let prefs = Prefs::load();
+    prefs.apply_to(&mut args);
+
     // Validate arguments
-    if let Err(e) = args.validate() {
+    if let Err(e) = args.load_targets_file().and_then(|_| args.validate()) {
         eprintln!("Error: {}", e);
         std::process::exit(1);
     }
+    for warning in args.src_port_warnings() {
+        eprintln!("Warning: {}", warning);
+    }
 
     // Handle replay mode (doesn't need permissions or target resolution)
     if let Some(ref replay_path) = args.replay {
         return run_replay_mode(&args, replay_path).await;
     }
 
+    // Remote mode: probes are sent by the agent, so no local privileges needed
+    if args.via.is_some() {
+        return run_remote_mode(args).await;
+    }
+
     // Check permissions early
     if let Err(e) = check_permissions() {
         eprintln!("{}", e);
@@ -79,60 +154,18 @@ async fn main() -> Result<()> {
     };
 
     // Resolve all targets
-    let mut targets: Vec<IpAddr> = Vec::new();
-    let mut sessions_map: HashMap<IpAddr, Arc<RwLock<Session>>> = HashMap::new();
     let config = Config::from(&args);
-
-    for target_str in &args.targets {
-        let resolved_ip = resolve_target(target_str, args.ipv4, args.ipv6)
-            .with_context(|| format!("Failed to resolve target: {}", target_str))?;
-
-        // Skip duplicate targets
-        if sessions_map.contains_key(&resolved_ip) {
-            eprintln!(
-                "Warning: Duplicate target {} ({}), skipping",
-                target_str, resolved_ip
-            );
-            continue;
-        }
-
-        let target = Target::new(target_str.clone(), resolved_ip);
-        let mut session = Session::new(target, config.clone());
-
-        // Set source IP and gateway for display in TUI
-        let ipv6 = resolved_ip.is_ipv6();
-        session.source_ip = config.source_ip.or_else(|| {
-            let addr = get_local_addr_with_interface(resolved_ip, interface_info.as_ref());
-            // Filter out unspecified addresses (0.0.0.0 or ::)
-            if addr.is_unspecified() {
-                None
-            } else {
-                Some(addr)
-            }
-        });
-        session.gateway = if let Some(ref info) = interface_info {
-            // Use interface-specific gateway
-            if ipv6 {
-                info.gateway_ipv6.map(IpAddr::V6)
-            } else {
-                info.gateway_ipv4.map(IpAddr::V4)
-            }
-        } else {
-            // Detect default gateway for auto-selected interface
-            detect_default_gateway(ipv6)
-        };
-
-        sessions_map.insert(resolved_ip, Arc::new(RwLock::new(session)));
-        targets.push(resolved_ip);
-    }
-
-    if targets.is_empty() {
-        anyhow::bail!("No valid targets specified");
+    let (sessions, targets) = build_sessions(
+        &args.targets,
+        &ResolveOptions::from_args(&args),
+        &config,
+        interface_info.as_ref(),
+    )
+    .await?;
+    if config.slo.is_none() {
+        apply_target_slos(&sessions, &prefs)?;
     }
 
-    // Create SessionMap (Arc<RwLock<HashMap>>)
-    let sessions: SessionMap = Arc::new(RwLock::new(sessions_map));
-
     // Cancellation token for graceful shutdown
     let cancel = CancellationToken::new();
 
@@ -143,53 +176,75 @@ async fn main() -> Result<()> {
         cancel_clone.cancel();
     });
 
-    // All targets must be same IP version for now (single receiver)
-    let ipv6 = targets[0].is_ipv6();
-    if targets.iter().any(|t| t.is_ipv6() != ipv6) {
-        anyhow::bail!("Mixed IPv4/IPv6 targets not supported. Use -4 or -6 to force one version.");
+    if let Err(e) = check_address_families(&targets, interface_info.as_ref(), config.source_ip) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 
-    // Validate interface has address matching target IP family
-    if let Some(ref info) = interface_info {
-        if ipv6 && info.ipv6.is_none() {
+    // Browser view runs alongside whichever mode is chosen below
+    if let Some(addr) = args.serve_tui {
+        let listener = tui::web::bind(addr, "--serve-tui").await?;
+        eprintln!("Serving TUI at http://{}/", addr);
+        if !addr.ip().is_loopback() {
             eprintln!(
-                "Error: Interface '{}' has no IPv6 address but targets require IPv6. \
-                 Use -4 to force IPv4.",
-                info.name
+                "Warning: the browser view has no authentication; anyone who can reach {} \
+                 sees the trace",
+                addr
             );
-            std::process::exit(1);
         }
-        if !ipv6 && info.ipv4.is_none() {
+        tokio::spawn(tui::web::run_web_tui(
+            listener,
+            sessions.clone(),
+            targets.clone(),
+            initial_theme(&args),
+            cancel.clone(),
+        ));
+    }
+
+    // Path signature metrics for Prometheus, without the browser view
+    if let Some(addr) = args.metrics {
+        let listener = tui::web::bind(addr, "--metrics").await?;
+        eprintln!("Serving metrics at http://{}/metrics", addr);
+        if !addr.ip().is_loopback() {
             eprintln!(
-                "Error: Interface '{}' has no IPv4 address but targets require IPv4. \
-                 Use -6 to force IPv6.",
-                info.name
+                "Warning: metrics have no authentication; anyone who can reach {} can read them",
+                addr
             );
-            std::process::exit(1);
         }
+        tokio::spawn(tui::web::run_metrics_server(
+            listener,
+            sessions.clone(),
+            targets.clone(),
+            cancel.clone(),
+        ));
     }
 
-    // Validate source IP matches target IP family
-    if let Some(source_ip) = config.source_ip
-        && source_ip.is_ipv6() != ipv6
-    {
-        eprintln!(
-            "Error: Source IP {} is {} but targets are {}. \
-             Use -4 or -6 to force matching IP version.",
-            source_ip,
-            if source_ip.is_ipv6() { "IPv6" } else { "IPv4" },
-            if ipv6 { "IPv6" } else { "IPv4" }
-        );
-        std::process::exit(1);
+    if args.perf {
+        trace::perf::PERF.enable();
+    }
+    if let Some(ref file) = args.dump_unmatched {
+        trace::dump::DUMPS.enable(file.as_deref())?;
     }
 
     // Run in appropriate mode
     if args.is_batch_mode() {
         run_batch_mode(args, sessions, targets, config, cancel, interface_info).await
+    } else if args.status_line {
+        run_status_line_mode(args, sessions, targets, config, cancel, interface_info).await
     } else if args.no_tui {
         run_streaming_mode(args, sessions, targets, config, cancel, interface_info).await
     } else {
-        run_interactive_mode(args, sessions, targets, config, cancel, interface_info).await
+        let notices = watch_config(cli_args, prefs, &sessions, &cancel);
+        run_interactive_mode(
+            args,
+            sessions,
+            targets,
+            config,
+            cancel,
+            interface_info,
+            notices,
+        )
+        .await
     }
 }
 
@@ -197,35 +252,144 @@ async fn main() -> Result<()> {
 fn load_session(path: &str) -> Result<Session> {
     const MAX_REPLAY_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 
-    let file = File::open(path).with_context(|| format!("Failed to open replay file: {}", path))?;
+    let file =
+        File::open(path).with_context(|| format!("Failed to open session file: {}", path))?;
 
     // Check file size to prevent DoS via huge JSON
     let metadata = file
         .metadata()
-        .with_context(|| format!("Failed to read replay file metadata: {}", path))?;
+        .with_context(|| format!("Failed to read session file metadata: {}", path))?;
     if metadata.len() > MAX_REPLAY_SIZE {
-        anyhow::bail!("Replay file too large (max 10MB): {}", path);
+        anyhow::bail!("Session file too large (max 10MB): {}", path);
     }
 
     let reader = BufReader::new(file);
     let session: Session = serde_json::from_reader(reader)
-        .with_context(|| format!("Failed to parse replay file: {}", path))?;
+        .with_context(|| format!("Failed to parse session file: {}", path))?;
     Ok(session)
 }
 
+/// Merge saved sessions from several vantage points and print the combined view
+fn run_merge(files: &[String], json: bool) -> Result<()> {
+    let mut sessions = Vec::with_capacity(files.len());
+    for path in files {
+        let label = std::path::Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone());
+        sessions.push((label, load_session(path)?));
+    }
+
+    let merged = merge_sessions(&sessions)?;
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &merged)?;
+        println!();
+    } else {
+        write_merged_report(&merged, std::io::stdout())?;
+    }
+    Ok(())
+}
+
+/// Load every session in a history file: one `--json` session, or JSONL
+/// with one session per line (e.g. appended by a cron job)
+fn load_history(path: &str) -> Result<Vec<Session>> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read history file: {}", path))?;
+    serde_json::Deserializer::from_str(&data)
+        .into_iter::<Session>()
+        .collect::<std::result::Result<_, _>>()
+        .with_context(|| format!("Failed to parse history file: {}", path))
+}
+
+/// `ttl analyze`: trends over saved sessions toward one target
+fn run_analyze(
+    files: &[String],
+    target: Option<&str>,
+    since: Option<Duration>,
+    json: bool,
+) -> Result<()> {
+    let mut sessions = Vec::new();
+    for path in files {
+        sessions.extend(load_history(path)?);
+    }
+    let since = since
+        .map(|d| chrono::Duration::from_std(d).map(|d| chrono::Utc::now() - d))
+        .transpose()
+        .context("--since is too long")?;
+
+    let analysis = analyze_history(&sessions, target, since)?;
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &analysis)?;
+        println!();
+    } else {
+        write_analysis_report(&analysis, std::io::stdout())?;
+    }
+    Ok(())
+}
+
+/// `ttl cache purge`: delete everything under ~/.cache/ttl
+fn run_cache_purge() -> Result<()> {
+    let root = lookup::cache::root();
+    match lookup::cache::purge(&root)? {
+        Some(bytes) => println!("Removed {} ({} KiB)", root.display(), bytes.div_ceil(1024)),
+        None => println!("No cache at {}", root.display()),
+    }
+    Ok(())
+}
+
+/// `ttl config show`: the settings a trace with `flags` would run with
+fn run_config_show(flags: &[String], json: bool) -> Result<()> {
+    let mut args = Args::parse_flags(flags).unwrap_or_else(|e| e.exit());
+    let path = Prefs::path();
+    let prefs = match path {
+        Some(ref path) => Prefs::read(path)?,
+        None => Prefs::default(),
+    };
+    prefs.apply_to(&mut args);
+    args.validate().map_err(anyhow::Error::msg)?;
+
+    let config = Config::from(&args);
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &config)?;
+        println!();
+    } else {
+        let source = path
+            .filter(|p| p.exists())
+            .map_or_else(|| "no config file".to_string(), |p| p.display().to_string());
+        println!(
+            "# Effective settings: defaults, then {}, then flags",
+            source
+        );
+        println!("# Settings that are off or unset are not listed");
+        print!("{}", toml::to_string_pretty(&config)?);
+    }
+    Ok(())
+}
+
+/// `ttl config init`: write a commented starter config.toml
+fn run_config_init(force: bool) -> Result<()> {
+    let path = Prefs::path().context("no config directory on this system")?;
+    Prefs::init(&path, force)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
 /// Run replay mode - load a saved session and display/export it
 async fn run_replay_mode(args: &Args, replay_path: &str) -> Result<()> {
     let session = load_session(replay_path)?;
     let target_ip = session.target.resolved;
 
     // Output based on flags
-    if args.json {
-        export_json(&session, std::io::stdout())?;
-    } else if args.csv {
-        export_csv(&session, std::io::stdout())?;
-    } else if args.report || args.no_tui {
-        // Default to report for replay without TUI
-        generate_report(&session, std::io::stdout())?;
+    if args.json || args.csv || args.report || args.no_tui {
+        let export = export_view(&session, args.redaction())?;
+        if args.json {
+            export_json(&export, std::io::stdout())?;
+        } else if args.csv {
+            export_csv(&export, std::io::stdout())?;
+        } else {
+            // Default to report for replay without TUI
+            generate_report_with(&export, &args.report_options(), std::io::stdout())?;
+        }
     } else {
         // Show in TUI (read-only)
         let state = Arc::new(RwLock::new(session));
@@ -237,16 +401,7 @@ async fn run_replay_mode(args: &Args, replay_path: &str) -> Result<()> {
         let sessions: SessionMap = Arc::new(RwLock::new(sessions_map));
         let targets = vec![target_ip];
 
-        // Load saved preferences
-        let prefs = Prefs::load();
-
-        // Determine theme: CLI override > saved preference > default
-        let theme_name = if args.theme != "default" {
-            &args.theme
-        } else {
-            prefs.theme.as_deref().unwrap_or("default")
-        };
-        let theme = Theme::by_name(theme_name);
+        let theme = initial_theme(args);
 
         // Setup Ctrl+C handler
         let cancel_clone = cancel.clone();
@@ -255,429 +410,286 @@ async fn run_replay_mode(args: &Args, replay_path: &str) -> Result<()> {
             cancel_clone.cancel();
         });
 
-        let final_theme = run_tui(sessions, targets, cancel, theme).await?;
-
-        // Save theme preference (best effort, don't fail on save error)
-        let mut prefs = Prefs::load();
-        prefs.theme = Some(final_theme);
-        let _ = prefs.save();
+        let final_theme = run_tui(sessions, targets, cancel, theme, None).await?;
+        save_theme(final_theme);
     }
 
     Ok(())
 }
 
-fn resolve_target(target: &str, force_ipv4: bool, force_ipv6: bool) -> Result<IpAddr> {
-    // Try parsing as IP address first
-    if let Ok(ip) = target.parse::<IpAddr>() {
-        return Ok(ip);
-    }
-
-    // Resolve hostname
-    let addrs: Vec<_> = format!("{}:0", target)
-        .to_socket_addrs()?
-        .map(|s| s.ip())
-        .collect();
+/// Drive a trace on a remote agent and show it locally
+async fn run_remote_mode(args: Args) -> Result<()> {
+    let via = args.via.clone().unwrap_or_default();
+    let token = args.agent_token.clone().unwrap_or_default();
+    let cancel = CancellationToken::new();
 
-    if addrs.is_empty() {
-        anyhow::bail!("No addresses found for hostname");
-    }
+    let cancel_clone = cancel.clone();
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c().await.ok();
+        cancel_clone.cancel();
+    });
 
-    // Filter by IP version if requested
-    let filtered: Vec<_> = addrs
-        .iter()
-        .filter(|ip| {
-            if force_ipv4 {
-                ip.is_ipv4()
-            } else if force_ipv6 {
-                ip.is_ipv6()
-            } else {
-                true
-            }
-        })
-        .cloned()
-        .collect();
+    let forwarded = forwarded_args(std::env::args().skip(1));
+    let remote = connect_agent(&via, &token, forwarded, cancel.clone()).await?;
 
-    if filtered.is_empty() {
-        anyhow::bail!(
-            "No {} addresses found",
-            if force_ipv4 { "IPv4" } else { "IPv6" }
-        );
+    if args.is_batch_mode() {
+        remote.updates.await??;
+        return print_batch_results(&args, &remote.sessions, &remote.targets);
     }
 
-    // Prefer IPv4 by default if no preference
-    if !force_ipv6 && let Some(ipv4) = filtered.iter().find(|ip| ip.is_ipv4()) {
-        return Ok(*ipv4);
-    }
+    let theme = initial_theme(&args);
+    let final_theme = run_tui(
+        remote.sessions.clone(),
+        remote.targets.clone(),
+        cancel.clone(),
+        theme,
+        None,
+    )
+    .await?;
+    save_theme(final_theme);
 
-    Ok(filtered[0])
+    cancel.cancel();
+    // The connection may already have ended; the TUI showed the last snapshot
+    let _ = remote.updates.await;
+    Ok(())
 }
 
-async fn run_interactive_mode(
-    args: Args,
-    sessions: SessionMap,
-    targets: Vec<IpAddr>,
-    config: Config,
-    cancel: CancellationToken,
-    interface: Option<InterfaceInfo>,
-) -> Result<()> {
-    // Shared pending map for probe correlation (engine writes, receiver reads)
-    let pending = new_pending_map();
-
-    // All targets must be same IP version (validated in main)
-    let ipv6 = targets[0].is_ipv6();
-
-    // Spawn receiver thread (handles all targets)
-    let receiver_config = ReceiverConfig {
-        timeout: config.timeout,
-        ipv6,
-        src_port_base: config.src_port_base,
-        num_flows: config.flows,
-        interface: interface.clone(),
-        recv_any: config.recv_any,
-    };
-    let receiver_handle = spawn_receiver(
-        sessions.clone(),
-        pending.clone(),
-        cancel.clone(),
-        receiver_config,
-    );
-
-    // Spawn probe engine for each target
-    let mut engine_handles = Vec::new();
-    {
-        let sessions_read = sessions.read();
-        for target_ip in &targets {
-            if let Some(state) = sessions_read.get(target_ip) {
-                let engine = ProbeEngine::new(
-                    config.clone(),
-                    *target_ip,
-                    state.clone(),
-                    pending.clone(),
-                    cancel.clone(),
-                    interface.clone(),
-                );
-                let handle = tokio::spawn(async move { engine.run().await });
-                engine_handles.push(handle);
-            }
+/// Give each session the SLO configured for its target in config.toml
+/// (`--slo` applies one objective to every target instead)
+fn apply_target_slos(sessions: &SessionMap, prefs: &Prefs) -> Result<()> {
+    for session in sessions.read().values() {
+        let mut session = session.write();
+        let slo = prefs.slo_for(&session.target.original, session.target.resolved)?;
+        if slo.is_some() {
+            session.set_slo(slo);
         }
     }
+    Ok(())
+}
 
-    // Spawn DNS worker (if enabled)
-    let dns_handle = if config.dns_enabled {
-        let dns = Arc::new(DnsLookup::new().await?);
-        Some(tokio::spawn(run_dns_worker(
-            dns,
-            sessions.clone(),
-            cancel.clone(),
-        )))
-    } else {
-        None
-    };
-
-    // Spawn ASN worker (if enabled)
-    let asn_handle = if config.asn_enabled {
-        let asn = Arc::new(AsnLookup::new().await?);
-        Some(tokio::spawn(run_asn_worker(
-            asn,
-            sessions.clone(),
-            cancel.clone(),
-        )))
-    } else {
-        None
-    };
-
-    // Spawn GeoIP worker (if enabled and database available)
-    let geo_handle = if config.geo_enabled {
-        let geo_lookup = if let Some(ref path) = args.geoip_db {
-            // Use explicit path from CLI
-            match GeoLookup::new(path) {
-                Ok(lookup) => Some(lookup),
-                Err(e) => {
-                    eprintln!("Warning: Failed to load GeoIP database '{}': {}", path, e);
-                    None
-                }
-            }
-        } else {
-            // Try default paths
-            GeoLookup::try_default()
-        };
-
-        geo_lookup.map(|geo| {
-            tokio::spawn(run_geo_worker(
-                Arc::new(geo),
-                sessions.clone(),
-                cancel.clone(),
-            ))
-        })
-    } else {
-        None
-    };
-
-    // Spawn IX worker (if enabled)
-    let ix_handle = if config.ix_enabled {
-        match IxLookup::new() {
-            Ok(ix) => Some(tokio::spawn(run_ix_worker(
-                Arc::new(ix),
-                sessions.clone(),
-                cancel.clone(),
-            ))),
-            Err(e) => {
-                eprintln!("Warning: Failed to initialize IX lookup: {}", e);
-                None
-            }
+/// Start applying config.toml edits to the running trace (best effort)
+fn watch_config(
+    cli: Args,
+    prefs: Prefs,
+    sessions: &SessionMap,
+    cancel: &CancellationToken,
+) -> Option<UnboundedReceiver<ConfigNotice>> {
+    let (tx, rx) = unbounded_channel();
+    match spawn_config_watcher(cli, prefs, sessions.clone(), tx, cancel.clone()) {
+        Ok(_) => Some(rx),
+        Err(e) => {
+            eprintln!("Warning: config reload disabled: {}", e);
+            None
         }
-    } else {
-        None
-    };
-
-    // Spawn rate limit detection worker (always enabled, lightweight analysis)
-    let ratelimit_handle = tokio::spawn(run_ratelimit_worker(sessions.clone(), cancel.clone()));
+    }
+}
 
-    // Load saved preferences
+/// Pick the TUI theme: CLI override > saved preference > default
+fn initial_theme(args: &Args) -> Theme {
     let prefs = Prefs::load();
-
-    // Determine theme: CLI override > saved preference > default
     let theme_name = if args.theme != "default" {
         &args.theme
     } else {
         prefs.theme.as_deref().unwrap_or("default")
     };
-    let theme = Theme::by_name(theme_name);
-
-    // Run TUI (with target list for cycling)
-    let final_theme = run_tui(sessions.clone(), targets.clone(), cancel.clone(), theme).await?;
+    Theme::by_name(theme_name)
+}
 
-    // Save theme preference (best effort, don't fail on save error)
+/// Save the theme chosen in the TUI (best effort, don't fail on save error)
+fn save_theme(final_theme: String) {
     let mut prefs = Prefs::load();
     prefs.theme = Some(final_theme);
     let _ = prefs.save();
-
-    // Cleanup
-    cancel.cancel();
-    for handle in engine_handles {
-        handle.await??;
-    }
-    receiver_handle.join().map_err(|e| {
-        // This branch shouldn't be reached since we use catch_unwind in the receiver,
-        // but handle it just in case something panics outside the protected region
-        let msg = e
-            .downcast_ref::<&str>()
-            .map(|s| s.to_string())
-            .or_else(|| e.downcast_ref::<String>().cloned())
-            .unwrap_or_else(|| "unknown panic".to_string());
-        anyhow::anyhow!("Receiver thread failed: {}", msg)
-    })??;
-    if let Some(handle) = dns_handle {
-        handle.await?;
-    }
-    if let Some(handle) = asn_handle {
-        handle.await?;
-    }
-    if let Some(handle) = geo_handle {
-        handle.await?;
-    }
-    if let Some(handle) = ix_handle {
-        handle.await?;
-    }
-    ratelimit_handle.await?;
-
-    Ok(())
 }
 
-async fn run_batch_mode(
+async fn run_interactive_mode(
     args: Args,
     sessions: SessionMap,
     targets: Vec<IpAddr>,
     config: Config,
     cancel: CancellationToken,
     interface: Option<InterfaceInfo>,
+    notices: Option<UnboundedReceiver<ConfigNotice>>,
 ) -> Result<()> {
-    // Shared pending map for probe correlation (engine writes, receiver reads)
-    let pending = new_pending_map();
-
-    // All targets must be same IP version (validated in main)
-    let ipv6 = targets[0].is_ipv6();
-
-    // Spawn receiver thread (handles all targets)
-    let receiver_config = ReceiverConfig {
-        timeout: config.timeout,
-        ipv6,
-        src_port_base: config.src_port_base,
-        num_flows: config.flows,
-        interface: interface.clone(),
-        recv_any: config.recv_any,
-    };
-    let receiver_handle = spawn_receiver(
+    let tasks = start_tracing(
+        &sessions,
+        &targets,
+        &config,
+        &cancel,
+        interface.as_ref(),
+        args.geoip_db.as_deref(),
+    )
+    .await?;
+
+    // Run TUI (with target list for cycling)
+    let theme = initial_theme(&args);
+    let final_theme = run_tui(
         sessions.clone(),
-        pending.clone(),
+        targets.clone(),
         cancel.clone(),
-        receiver_config,
-    );
-
-    // Spawn probe engine for each target
-    let mut engine_handles = Vec::new();
-    {
-        let sessions_read = sessions.read();
-        for target_ip in &targets {
-            if let Some(state) = sessions_read.get(target_ip) {
-                let engine = ProbeEngine::new(
-                    config.clone(),
-                    *target_ip,
-                    state.clone(),
-                    pending.clone(),
-                    cancel.clone(),
-                    interface.clone(),
-                );
-                let handle = tokio::spawn(async move { engine.run().await });
-                engine_handles.push(handle);
-            }
-        }
-    }
-
-    // Spawn DNS worker (if enabled)
-    let dns_handle = if config.dns_enabled {
-        let dns = Arc::new(DnsLookup::new().await?);
-        Some(tokio::spawn(run_dns_worker(
-            dns,
-            sessions.clone(),
-            cancel.clone(),
-        )))
-    } else {
-        None
-    };
-
-    // Spawn ASN worker (if enabled)
-    let asn_handle = if config.asn_enabled {
-        let asn = Arc::new(AsnLookup::new().await?);
-        Some(tokio::spawn(run_asn_worker(
-            asn,
-            sessions.clone(),
-            cancel.clone(),
-        )))
-    } else {
-        None
-    };
-
-    // Spawn GeoIP worker (if enabled and database available)
-    let geo_handle = if config.geo_enabled {
-        let geo_lookup = if let Some(ref path) = args.geoip_db {
-            match GeoLookup::new(path) {
-                Ok(lookup) => Some(lookup),
-                Err(e) => {
-                    eprintln!("Warning: Failed to load GeoIP database '{}': {}", path, e);
-                    None
-                }
-            }
-        } else {
-            GeoLookup::try_default()
-        };
+        theme,
+        notices,
+    )
+    .await?;
+    save_theme(final_theme);
 
-        geo_lookup.map(|geo| {
-            tokio::spawn(run_geo_worker(
-                Arc::new(geo),
-                sessions.clone(),
-                cancel.clone(),
-            ))
-        })
-    } else {
-        None
-    };
+    // Cleanup
+    cancel.cancel();
+    tasks.join().await
+}
 
-    // Spawn IX worker (if enabled)
-    let ix_handle = if config.ix_enabled {
-        match IxLookup::new() {
-            Ok(ix) => Some(tokio::spawn(run_ix_worker(
-                Arc::new(ix),
-                sessions.clone(),
-                cancel.clone(),
-            ))),
-            Err(e) => {
-                eprintln!("Warning: Failed to initialize IX lookup: {}", e);
-                None
+async fn run_batch_mode(
+    args: Args,
+    sessions: SessionMap,
+    targets: Vec<IpAddr>,
+    config: Config,
+    cancel: CancellationToken,
+    interface: Option<InterfaceInfo>,
+) -> Result<()> {
+    let mut tasks = start_tracing(
+        &sessions,
+        &targets,
+        &config,
+        &cancel,
+        interface.as_ref(),
+        args.geoip_db.as_deref(),
+    )
+    .await?;
+
+    // Wait for all engines to complete, reporting progress on stderr if asked
+    if args.progress.is_some() {
+        let mut ticker = tokio::time::interval(PROGRESS_PERIOD);
+        ticker.tick().await;
+        let engines = tasks.wait_engines();
+        tokio::pin!(engines);
+        loop {
+            tokio::select! {
+                result = &mut engines => break result?,
+                _ = ticker.tick() => print_progress(&sessions, &targets, false),
             }
         }
     } else {
-        None
-    };
-
-    // Spawn rate limit detection worker (always enabled, lightweight analysis)
-    let ratelimit_handle = tokio::spawn(run_ratelimit_worker(sessions.clone(), cancel.clone()));
-
-    // Wait for all engines to complete
-    for handle in engine_handles {
-        handle.await??;
+        tasks.wait_engines().await?;
     }
 
     // Wait for final responses and enrichment to settle
     tokio::time::sleep(config.timeout + Duration::from_millis(500)).await;
     cancel.cancel();
+    tasks.join().await?;
 
-    receiver_handle.join().map_err(|e| {
-        let msg = e
-            .downcast_ref::<&str>()
-            .map(|s| s.to_string())
-            .or_else(|| e.downcast_ref::<String>().cloned())
-            .unwrap_or_else(|| "unknown panic".to_string());
-        anyhow::anyhow!("Receiver thread failed: {}", msg)
-    })??;
-
-    // Wait for enrichment workers to finish
-    if let Some(handle) = dns_handle {
-        handle.await?;
-    }
-    if let Some(handle) = asn_handle {
-        handle.await?;
+    if args.progress.is_some() {
+        print_progress(&sessions, &targets, true);
     }
-    if let Some(handle) = geo_handle {
-        handle.await?;
+    print_batch_results(&args, &sessions, &targets)?;
+    run_export_hooks(&config, &sessions, &targets).await
+}
+
+/// One --progress json line per target on stderr (best effort)
+fn print_progress(sessions: &SessionMap, targets: &[IpAddr], done: bool) {
+    let sessions_read = sessions.read();
+    let guards: Vec<_> = targets
+        .iter()
+        .filter_map(|ip| sessions_read.get(ip).map(|s| s.read()))
+        .collect();
+    let _ = write_progress(guards.iter().map(|g| &**g), done, std::io::stderr().lock());
+}
+
+/// Pipe each finished session to the `export` plugins, their output to stdout
+async fn run_export_hooks(
+    config: &Config,
+    sessions: &SessionMap,
+    targets: &[IpAddr],
+) -> Result<()> {
+    let commands = config.plugin_commands(PluginHook::Export);
+    if commands.is_empty() {
+        return Ok(());
     }
-    if let Some(handle) = ix_handle {
-        handle.await?;
+    for target_ip in targets {
+        let json = match sessions.read().get(target_ip) {
+            Some(state) => serde_json::to_vec(&*state.read())?,
+            None => continue,
+        };
+        for (command, result) in run_export_plugins(&commands, json).await {
+            match result {
+                Ok(output) => std::io::stdout().write_all(&output)?,
+                Err(e) => eprintln!("Warning: export plugin '{}' failed: {:#}", command, e),
+            }
+        }
     }
-    ratelimit_handle.await?;
+    Ok(())
+}
 
-    // Output results for all targets
+/// Print finished sessions in the batch format selected by --json/--csv/--report
+fn print_batch_results(args: &Args, sessions: &SessionMap, targets: &[IpAddr]) -> Result<()> {
+    // Output results for all targets, redacted first if asked
     let sessions_read = sessions.read();
+    let guards: Vec<_> = targets
+        .iter()
+        .filter_map(|ip| sessions_read.get(ip))
+        .map(|state| state.read())
+        .collect();
+    let exports = guards
+        .iter()
+        .map(|session| export_view(session, args.redaction()))
+        .collect::<Result<Vec<_>, _>>()?;
 
     // Handle JSON output separately for proper array formatting
     if args.json {
         if targets.len() > 1 {
             // Multi-target: output as JSON array
             print!("[");
-            let mut first = true;
-            for target_ip in targets.iter() {
-                if let Some(state) = sessions_read.get(target_ip) {
-                    let session = state.read();
-                    if !first {
-                        print!(",");
-                    }
-                    first = false;
-                    serde_json::to_writer(std::io::stdout(), &*session)?;
+            for (i, session) in exports.iter().enumerate() {
+                if i > 0 {
+                    print!(",");
                 }
+                serde_json::to_writer(std::io::stdout(), &**session)?;
             }
             println!("]");
-        } else if let Some(state) = sessions_read.get(&targets[0]) {
+        } else if let Some(session) = exports.first() {
             // Single target: output as-is (backwards compatible)
-            export_json(&state.read(), std::io::stdout())?;
+            export_json(session, std::io::stdout())?;
         }
     } else {
+        // Several targets in a report: combined summary first. With
+        // --sources that is the paths side by side, one column per source.
+        if args.report && targets.len() > 1 {
+            if args.sources.is_empty() {
+                write_summary(exports.iter().map(|s| &**s), std::io::stdout())?;
+            } else {
+                let labelled: Vec<_> = targets
+                    .iter()
+                    .zip(&exports)
+                    .map(|(source, session)| (source.to_string(), (**session).clone()))
+                    .collect();
+                write_merged_report(&merge_sessions(&labelled)?, std::io::stdout())?;
+            }
+            if args.summary_only {
+                return Ok(());
+            }
+        }
+
         // Non-JSON output
-        for (i, target_ip) in targets.iter().enumerate() {
-            if let Some(state) = sessions_read.get(target_ip) {
-                let session = state.read();
-                if targets.len() > 1 {
-                    println!(
-                        "\n=== Target {}/{}: {} ===\n",
-                        i + 1,
-                        targets.len(),
-                        target_ip
-                    );
-                }
-                if args.report {
-                    generate_report(&session, std::io::stdout())?;
-                } else if args.csv {
-                    export_csv(&session, std::io::stdout())?;
-                }
+        for (i, session) in exports.iter().enumerate() {
+            if !args.sources.is_empty() {
+                println!(
+                    "\n=== Source {}/{}: {} -> {} ===\n",
+                    i + 1,
+                    targets.len(),
+                    targets[i],
+                    session.target.resolved
+                );
+            } else if targets.len() > 1 {
+                println!(
+                    "\n=== Target {}/{}: {} ===\n",
+                    i + 1,
+                    targets.len(),
+                    session.target.resolved
+                );
+            }
+            if args.report {
+                generate_report_with(session, &args.report_options(), std::io::stdout())?;
+            } else if args.csv {
+                export_csv(session, std::io::stdout())?;
             }
         }
     }
@@ -693,116 +705,15 @@ async fn run_streaming_mode(
     cancel: CancellationToken,
     interface: Option<InterfaceInfo>,
 ) -> Result<()> {
-    // Shared pending map for probe correlation (engine writes, receiver reads)
-    let pending = new_pending_map();
-
-    // All targets must be same IP version (validated in main)
-    let ipv6 = targets[0].is_ipv6();
-
-    // Spawn receiver thread (handles all targets)
-    let receiver_config = ReceiverConfig {
-        timeout: config.timeout,
-        ipv6,
-        src_port_base: config.src_port_base,
-        num_flows: config.flows,
-        interface: interface.clone(),
-        recv_any: config.recv_any,
-    };
-    let receiver_handle = spawn_receiver(
-        sessions.clone(),
-        pending.clone(),
-        cancel.clone(),
-        receiver_config,
-    );
-
-    // Spawn probe engine for each target
-    let mut engine_handles = Vec::new();
-    {
-        let sessions_read = sessions.read();
-        for target_ip in &targets {
-            if let Some(state) = sessions_read.get(target_ip) {
-                let engine = ProbeEngine::new(
-                    config.clone(),
-                    *target_ip,
-                    state.clone(),
-                    pending.clone(),
-                    cancel.clone(),
-                    interface.clone(),
-                );
-                let handle = tokio::spawn(async move { engine.run().await });
-                engine_handles.push(handle);
-            }
-        }
-    }
-
-    // Spawn DNS worker (if enabled)
-    let dns_handle = if config.dns_enabled {
-        let dns = Arc::new(DnsLookup::new().await?);
-        Some(tokio::spawn(run_dns_worker(
-            dns,
-            sessions.clone(),
-            cancel.clone(),
-        )))
-    } else {
-        None
-    };
-
-    // Spawn ASN worker (if enabled)
-    let asn_handle = if config.asn_enabled {
-        let asn = Arc::new(AsnLookup::new().await?);
-        Some(tokio::spawn(run_asn_worker(
-            asn,
-            sessions.clone(),
-            cancel.clone(),
-        )))
-    } else {
-        None
-    };
-
-    // Spawn GeoIP worker (if enabled and database available)
-    let geo_handle = if config.geo_enabled {
-        let geo_lookup = if let Some(ref path) = args.geoip_db {
-            match GeoLookup::new(path) {
-                Ok(lookup) => Some(lookup),
-                Err(e) => {
-                    eprintln!("Warning: Failed to load GeoIP database '{}': {}", path, e);
-                    None
-                }
-            }
-        } else {
-            GeoLookup::try_default()
-        };
-
-        geo_lookup.map(|geo| {
-            tokio::spawn(run_geo_worker(
-                Arc::new(geo),
-                sessions.clone(),
-                cancel.clone(),
-            ))
-        })
-    } else {
-        None
-    };
-
-    // Spawn IX worker (if enabled)
-    let ix_handle = if config.ix_enabled {
-        match IxLookup::new() {
-            Ok(ix) => Some(tokio::spawn(run_ix_worker(
-                Arc::new(ix),
-                sessions.clone(),
-                cancel.clone(),
-            ))),
-            Err(e) => {
-                eprintln!("Warning: Failed to initialize IX lookup: {}", e);
-                None
-            }
-        }
-    } else {
-        None
-    };
-
-    // Spawn rate limit detection worker (always enabled, lightweight analysis)
-    let ratelimit_handle = tokio::spawn(run_ratelimit_worker(sessions.clone(), cancel.clone()));
+    let tasks = start_tracing(
+        &sessions,
+        &targets,
+        &config,
+        &cancel,
+        interface.as_ref(),
+        args.geoip_db.as_deref(),
+    )
+    .await?;
 
     // Print results as they come in
     let mut last_total_received: HashMap<IpAddr, u64> = HashMap::new();
@@ -830,7 +741,9 @@ async fn run_streaming_mode(
                                 if hop.received > 0
                                     && let Some(stats) = hop.primary_stats()
                                 {
-                                    let host = stats.hostname.as_deref().unwrap_or("");
+                                    let host = stats
+                                        .display_name(session.config.label_mode)
+                                        .unwrap_or_default();
                                     println!(
                                         "TTL {:2}  {:15}  {:20}  {:>6.2}ms  {:>5.1}% loss",
                                         hop.ttl,
@@ -850,33 +763,69 @@ async fn run_streaming_mode(
         }
     }
 
-    for handle in engine_handles {
-        handle.await??;
-    }
-    receiver_handle.join().map_err(|e| {
-        let msg = e
-            .downcast_ref::<&str>()
-            .map(|s| s.to_string())
-            .or_else(|| e.downcast_ref::<String>().cloned())
-            .unwrap_or_else(|| "unknown panic".to_string());
-        anyhow::anyhow!("Receiver thread failed: {}", msg)
-    })??;
+    tasks.join().await
+}
 
-    // Wait for enrichment workers to finish
-    if let Some(handle) = dns_handle {
-        handle.await?;
-    }
-    if let Some(handle) = asn_handle {
-        handle.await?;
-    }
-    if let Some(handle) = geo_handle {
-        handle.await?;
-    }
-    if let Some(handle) = ix_handle {
-        handle.await?;
+/// Print a one-line summary of every target each probe interval
+async fn run_status_line_mode(
+    args: Args,
+    sessions: SessionMap,
+    targets: Vec<IpAddr>,
+    config: Config,
+    cancel: CancellationToken,
+    interface: Option<InterfaceInfo>,
+) -> Result<()> {
+    let tasks = start_tracing(
+        &sessions,
+        &targets,
+        &config,
+        &cancel,
+        interface.as_ref(),
+        args.geoip_db.as_deref(),
+    )
+    .await?;
+
+    let print_status = || {
+        let sessions_read = sessions.read();
+        let guards: Vec<_> = targets
+            .iter()
+            .filter_map(|ip| sessions_read.get(ip).map(|s| s.read()))
+            .collect();
+        // Bars read the last line printed; stdout flushes on newline
+        println!("{}", status_line_multi(guards.iter().map(|g| &**g)));
+    };
+
+    let mut interval = tokio::time::interval(config.interval);
+    // Skip the immediate first tick so the first line has data
+    interval.tick().await;
+    // Engines cancel the token themselves once -c rounds are done
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = interval.tick() => print_status(),
+        }
     }
-    ratelimit_handle.await?;
 
+    // Final line reflects the finished (or interrupted) trace
+    print_status();
+    tasks.join().await
+}
+
+/// Replay a response corpus through the parser and report the cost per packet
+fn run_bench_parse(path: &str) -> Result<()> {
+    let packets = probe::corpus::load_corpus(path)?;
+    // Warm up caches and the session's hop table before timing
+    probe::corpus::replay_corpus(&packets, 10_000);
+    let stats = probe::corpus::replay_corpus(&packets, 1_000_000);
+    println!(
+        "{} packets ({} in corpus, {} matched) in {:.3}s: {:.0} ns/packet, {:.2}M packets/s",
+        stats.packets,
+        packets.len(),
+        stats.matched,
+        stats.elapsed.as_secs_f64(),
+        stats.ns_per_packet(),
+        stats.packets_per_sec() / 1_000_000.0
+    );
     Ok(())
 }
 
@@ -0,0 +1,397 @@
+//! External plugin commands
+//!
+//! `--plugin HOOK=CMD` (or `[[plugin]]` tables in config.toml) runs a
+//! local executable with JSON on stdin, so site-specific enrichment and
+//! export formats don't need a fork of ttl. The hook name is also passed in
+//! `TTL_HOOK`, letting one script serve both hooks.
+//!
+//! - `responder`: run once per new responder address, after reverse DNS and
+//!   ASN have had a chance to fill in. The command prints a JSON object;
+//!   its values are shown in the hop detail view and saved as `plugin_data`
+//!   (a failing command leaves an `error` entry there instead).
+//! - `export`: run with the session JSON whenever a session is exported
+//!   (`e` in the TUI, or the end of a `-c` batch run). In batch mode its
+//!   output goes to stdout; in the TUI it is discarded.
+//!
+//! Commands are split on whitespace, not by a shell; wrap anything fancier
+//! in a script. A command that prints more than 4 MiB fails, and only the
+//! first line of its stderr is kept for the error.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{LookupLimit, PluginHook};
+use crate::lookup::sanitize_display;
+use crate::trace::receiver::SessionMap;
+
+/// How long a plugin may run before it is killed
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a responder waits for DNS/ASN before it goes to plugins anyway
+const ENRICHMENT_WAIT: Duration = Duration::from_secs(5);
+
+/// Limits on what a responder plugin may add
+const MAX_FIELDS: usize = 16;
+const MAX_FIELD_LEN: usize = 64;
+
+/// Most stdout read from a plugin; a command printing more fails
+const MAX_OUTPUT: u64 = 4 * 1024 * 1024;
+
+/// Most of a failing plugin's first stderr line kept for its error
+const MAX_ERROR_LINE: u64 = 1024;
+
+/// Run `command` with `input` on stdin, returning its stdout
+pub async fn run_command(command: &str, hook: PluginHook, input: Vec<u8>) -> Result<Vec<u8>> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().context("empty plugin command")?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .env("TTL_HOOK", hook.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to start '{}'", program))?;
+
+    // Written concurrently so a plugin that answers before reading
+    // everything can't deadlock on a full pipe
+    let mut stdin = child.stdin.take().context("no stdin")?;
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+    // Only the first stderr line is kept; the rest is drained so the
+    // plugin can't block on it
+    let stderr = child.stderr.take().context("no stderr")?;
+    let error_line = tokio::spawn(async move {
+        let mut reader = BufReader::new(stderr);
+        let mut line = Vec::new();
+        let _ = (&mut reader)
+            .take(MAX_ERROR_LINE)
+            .read_until(b'\n', &mut line)
+            .await;
+        let _ = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await;
+        line
+    });
+    let mut stdout = child.stdout.take().context("no stdout")?;
+
+    let run = async {
+        let mut output = Vec::new();
+        (&mut stdout)
+            .take(MAX_OUTPUT + 1)
+            .read_to_end(&mut output)
+            .await?;
+        if output.len() as u64 > MAX_OUTPUT {
+            bail!("output over {} bytes", MAX_OUTPUT);
+        }
+        Ok((child.wait().await?, output))
+    };
+    let result = tokio::time::timeout(PLUGIN_TIMEOUT, run)
+        .await
+        .map_err(|_| anyhow!("timed out after {}s", PLUGIN_TIMEOUT.as_secs()))
+        .and_then(|result| result);
+    writer.abort();
+    // Dropping the child kills it if it is still running, closing stderr
+    let (status, output) = match result {
+        Ok(done) => done,
+        Err(e) => {
+            error_line.abort();
+            return Err(e);
+        }
+    };
+
+    if !status.success() {
+        let line = error_line.await.unwrap_or_default();
+        let line = String::from_utf8_lossy(&line);
+        match line.lines().next().filter(|l| !l.trim().is_empty()) {
+            Some(line) => bail!("{}: {}", status, sanitize_display(line)),
+            None => bail!("{}", status),
+        }
+    }
+    error_line.abort();
+    Ok(output)
+}
+
+/// JSON sent to `responder` plugins
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponderRequest {
+    pub ip: IpAddr,
+    pub ttl: u8,
+    pub target: String,
+    pub target_ip: IpAddr,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_name: Option<String>,
+}
+
+/// Fields from a responder plugin's output: a JSON object whose scalar
+/// values become strings (nested values are skipped), with control
+/// characters stripped and sizes capped
+pub fn parse_fields(stdout: &[u8]) -> Result<BTreeMap<String, String>> {
+    let object: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(stdout).context("expected a JSON object")?;
+    let clip = |s: &str| sanitize_display(s).chars().take(MAX_FIELD_LEN).collect();
+    Ok(object
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => return None,
+            };
+            Some((clip(&key), clip(&value)))
+        })
+        .filter(|(key, _): &(String, String)| !key.is_empty())
+        .take(MAX_FIELDS)
+        .collect())
+}
+
+/// Responders waiting for, or done with, plugins (or the `--enrich-url`
+/// endpoint)
+#[derive(Default)]
+pub struct Pending {
+    /// First time each waiting address was seen
+    waiting: HashMap<IpAddr, Instant>,
+    done: HashSet<IpAddr>,
+}
+
+impl Pending {
+    /// From `candidates` (request, enrichment finished), the ones due now,
+    /// marking them done
+    pub fn take_due(
+        &mut self,
+        candidates: Vec<(ResponderRequest, bool)>,
+        max: usize,
+        now: Instant,
+    ) -> Vec<ResponderRequest> {
+        let mut due = Vec::new();
+        for (request, enriched) in candidates {
+            if self.done.contains(&request.ip) || due.len() >= max {
+                continue;
+            }
+            let since = *self.waiting.entry(request.ip).or_insert(now);
+            if enriched || now.duration_since(since) >= ENRICHMENT_WAIT {
+                self.waiting.remove(&request.ip);
+                self.done.insert(request.ip);
+                due.push(request);
+            }
+        }
+        due
+    }
+}
+
+/// A request for every responder in every session, and whether reverse DNS
+/// and ASN lookups (where enabled) have answered for it yet
+pub fn responder_requests(sessions: &SessionMap) -> Vec<(ResponderRequest, bool)> {
+    let sessions = sessions.read();
+    sessions
+        .values()
+        .flat_map(|state| {
+            let session = state.read();
+            let config = &session.config;
+            session
+                .hops
+                .iter()
+                .flat_map(|hop| hop.responders.values().map(move |s| (hop.ttl, s)))
+                .map(|(ttl, stats)| {
+                    let enriched = (stats.hostname.is_some() || !config.dns_enabled)
+                        && (stats.asn.is_some() || !config.asn_enabled);
+                    let request = ResponderRequest {
+                        ip: stats.ip,
+                        ttl,
+                        target: session.target.original.clone(),
+                        target_ip: session.target.resolved,
+                        hostname: stats.hostname.clone(),
+                        asn: stats.asn.as_ref().map(|a| a.number),
+                        as_name: stats.asn.as_ref().map(|a| a.name.clone()),
+                    };
+                    (request, enriched)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Background worker running `responder` plugins for new responders
+pub async fn run_plugin_worker(
+    commands: Vec<String>,
+    sessions: SessionMap,
+    cancel: CancellationToken,
+    limit: LookupLimit,
+) {
+    let mut interval = tokio::time::interval(limit.interval);
+    let mut pending = Pending::default();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                break;
+            }
+            _ = interval.tick() => {
+                let candidates = responder_requests(&sessions);
+                let due = pending.take_due(candidates, limit.concurrency, Instant::now());
+                if due.is_empty() {
+                    continue;
+                }
+
+                let futures: Vec<_> = due
+                    .iter()
+                    .map(|request| {
+                        let commands = commands.clone();
+                        let input = serde_json::to_vec(request).unwrap_or_default();
+                        async move {
+                            let mut fields = BTreeMap::new();
+                            let mut errors = Vec::new();
+                            for command in commands {
+                                let result = run_command(&command, PluginHook::Responder, input.clone())
+                                    .await
+                                    .and_then(|stdout| parse_fields(&stdout));
+                                match result {
+                                    Ok(more) => fields.extend(more),
+                                    Err(e) => errors.push((command, e)),
+                                }
+                            }
+                            (request.ip, fields, errors)
+                        }
+                    })
+                    .collect();
+                let results = futures::future::join_all(futures).await;
+
+                let sessions = sessions.read();
+                for (ip, mut fields, errors) in results {
+                    // Shown with the responder, where the TUI can't be garbled
+                    if let Some((command, e)) = errors.first() {
+                        fields
+                            .entry("error".to_string())
+                            .or_insert_with(|| sanitize_display(&format!("{}: {:#}", command, e)));
+                    }
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    for state in sessions.values() {
+                        let mut session = state.write();
+                        for hop in &mut session.hops {
+                            if let Some(stats) = hop.responders.get_mut(&ip) {
+                                stats.plugin_data.extend(fields.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Run each `export` plugin on a session's JSON, returning each command's
+/// result
+pub async fn run_export_plugins(
+    commands: &[String],
+    session_json: Vec<u8>,
+) -> Vec<(String, Result<Vec<u8>>)> {
+    let mut results = Vec::new();
+    for command in commands {
+        let result = run_command(command, PluginHook::Export, session_json.clone()).await;
+        results.push((command.clone(), result));
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(ip: &str) -> ResponderRequest {
+        ResponderRequest {
+            ip: ip.parse().unwrap(),
+            ttl: 3,
+            target: "example.com".to_string(),
+            target_ip: "192.0.2.1".parse().unwrap(),
+            hostname: None,
+            asn: None,
+            as_name: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let fields =
+            parse_fields(br#"{"site":"fra1","rack":12,"up":true,"tags":["a"],"x\u001b":"y"}"#)
+                .unwrap();
+        assert_eq!(fields["site"], "fra1");
+        assert_eq!(fields["rack"], "12");
+        assert_eq!(fields["up"], "true");
+        assert_eq!(fields["x"], "y");
+        assert!(!fields.contains_key("tags"));
+
+        assert!(parse_fields(b"[1,2]").is_err());
+        assert!(parse_fields(b"not json").is_err());
+        assert!(parse_fields(b"{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_take_due_waits_for_enrichment() {
+        let mut pending = Pending::default();
+        let start = Instant::now();
+
+        // Enriched: due at once, and only once
+        let due = pending.take_due(vec![(request("10.0.0.1"), true)], 10, start);
+        assert_eq!(due.len(), 1);
+        assert!(
+            pending
+                .take_due(vec![(request("10.0.0.1"), true)], 10, start)
+                .is_empty()
+        );
+
+        // Not enriched: waits, then goes anyway
+        assert!(
+            pending
+                .take_due(vec![(request("10.0.0.2"), false)], 10, start)
+                .is_empty()
+        );
+        let later = start + ENRICHMENT_WAIT;
+        let due = pending.take_due(vec![(request("10.0.0.2"), false)], 10, later);
+        assert_eq!(due[0].ip.to_string(), "10.0.0.2");
+
+        // Capped per tick
+        let many = (1..=5)
+            .map(|i| (request(&format!("10.0.1.{}", i)), true))
+            .collect();
+        assert_eq!(pending.take_due(many, 2, later).len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command() {
+        let out = run_command("cat", PluginHook::Export, b"{\"a\":1}".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(out, b"{\"a\":1}");
+        assert!(
+            run_command("false", PluginHook::Export, Vec::new())
+                .await
+                .is_err()
+        );
+        assert!(
+            run_command("/nonexistent/plugin", PluginHook::Export, Vec::new())
+                .await
+                .is_err()
+        );
+
+        // Endless output fails at the cap instead of filling memory
+        let err = run_command("yes", PluginHook::Export, Vec::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("output over"));
+    }
+}
@@ -1,16 +1,198 @@
 //! User preferences persistence.
 //!
-//! Saves user preferences (like theme) to ~/.config/ttl/config.toml
+//! Saves user preferences (like theme) to ~/.config/ttl/config.toml.
+//! Settings here fill in options not given on the command line.
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cli::Args;
+use crate::config::Plugin;
+use crate::error::TtlError;
+use crate::state::{GeoPoint, ResponderAlias, Slo};
 
 /// User preferences
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Prefs {
     /// Selected theme name
     pub theme: Option<String>,
+    /// Probe interval in seconds
+    pub interval: Option<f64>,
+    /// Destination loss alert threshold in percent
+    pub alert_loss: Option<f64>,
+    /// Enrichment toggles (`dns = false` behaves like `--no-dns`)
+    pub dns: Option<bool>,
+    pub asn: Option<bool>,
+    pub geo: Option<bool>,
+    pub ix: Option<bool>,
+    /// Show timestamps in local time (`--local-time`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_time: Option<bool>,
+    /// Number formatting locale (`--locale`), e.g. "de" or "auto"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// TUI language (`--lang`), e.g. "de" or "auto"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// RTTs in µs/ms/s as fits (`--auto-units`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_units: Option<bool>,
+    /// Socket buffer sizes in bytes (`--recv-buffer`, `--send-buffer`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_buffer: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_buffer: Option<usize>,
+    /// DSCP value probes are marked with (`--dscp`, 0-63)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dscp: Option<u8>,
+    /// Seconds, like --stats-window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats_window: Option<u64>,
+    /// Silent hops before higher TTLs are only re-checked (`--gap-limit`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_limit: Option<u8>,
+    /// Probe spacing factor across TTLs (`--ttl-decay`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_decay: Option<f64>,
+    /// Probe lossy hops less often (`--adaptive`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive: Option<bool>,
+    /// Clear per-hop statistics on a path change (`--reset-on-path-change`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_on_path_change: Option<bool>,
+    /// Merge responders sharing a reverse DNS name (`--merge-by-hostname`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_hostnames: Option<bool>,
+    /// This host's position (`--origin`), e.g. `origin = { lat = 52.52, lon = 13.4 }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<GeoPoint>,
+    /// No network calls besides probes (`--offline`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offline: Option<bool>,
+    /// Hosts lookups may contact (`--allow-host`), e.g. `["peeringdb.com", "10.0.0.53"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// Write lookup caches to disk (`disk_cache = false` is `--no-disk-cache`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_cache: Option<bool>,
+    /// Responder aliases (`[[alias]]` tables, added to any `--alias`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alias: Vec<ResponderAlias>,
+    /// Responder labels (`[labels]` table of address or prefix to name,
+    /// added to any `--label`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Session tags (`[tags]` table of key to value; `--tag` overrides a key)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// `"alongside"` or `"replace"` (`--label-mode`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_mode: Option<String>,
+    /// `"smart"`, `"end"` or `"middle"` (`--host-trim`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_trim: Option<String>,
+    /// Plugin commands (`[[plugin]]` tables, added to any `--plugin`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugin: Vec<Plugin>,
+    /// Endpoint asked about each new responder (`--enrich-url`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrich_url: Option<String>,
+    /// Per-target SLOs (`[[slo]]` tables)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slo: Vec<TargetSlo>,
+}
+
+/// Written by `ttl config init`. Every setting is commented out, so the
+/// file starts out changing nothing.
+pub const STARTER_CONFIG: &str = r#"# ttl configuration
+#
+# Settings here fill in options not given on the command line; flags always
+# win. Uncomment a line to use it. `ttl config show` prints the result.
+
+# Color theme ('t' cycles in the TUI and saves the choice here)
+# theme = "default"
+
+# Seconds between probe rounds (-i)
+# interval = 1.0
+
+# Alert when destination loss reaches this percent (--alert-loss)
+# alert_loss = 5.0
+
+# Lookups (false is like --no-dns, --no-asn, --no-geo, --no-ix)
+# dns = true
+# asn = true
+# geo = true
+# ix = true
+
+# No network calls besides probes (--offline), or only to these hosts
+# offline = false
+# allowed_hosts = ["peeringdb.com"]
+# disk_cache = true
+
+# Display
+# local_time = false
+# locale = "auto"
+# lang = "auto"
+# auto_units = false
+# label_mode = "alongside"
+# host_trim = "smart"
+
+# Probing
+# dscp = 0
+# gap_limit = 3
+# ttl_decay = 1.2
+# adaptive = false
+# recv_buffer = 4194304
+# send_buffer = 4194304
+
+# Statistics
+# stats_window = 300
+# reset_on_path_change = false
+# merge_hostnames = false
+
+# Where this host is, for distance estimates (--origin)
+# origin = { lat = 52.52, lon = 13.4 }
+
+# Ask an inventory service about each new responder (--enrich-url)
+# enrich_url = "https://cmdb.example.com/ttl/enrich"
+
+# Names for responders, by address or prefix (--label)
+# [labels]
+# "192.0.2.1" = "core-fra1"
+# "198.51.100.0/24" = "transit"
+
+# Session tags (--tag)
+# [tags]
+# site = "fra1"
+
+# Responders that are one router (--alias)
+# [[alias]]
+# name = "edge-1"
+# networks = ["192.0.2.1/32", "2001:db8::1/128"]
+
+# Commands run on events (--plugin)
+# [[plugin]]
+# hook = "responder"
+# command = "/opt/cmdb-lookup"
+
+# Per-target objectives (--slo)
+# [[slo]]
+# target = "example.com"
+# objective = "p95<80ms,loss<1%"
+"#;
+
+/// SLO for one target, matched against the name or address given on the
+/// command line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetSlo {
+    pub target: String,
+    /// Objective in `--slo` syntax, e.g. "p95<80ms,loss<1%"
+    pub objective: String,
 }
 
 impl Prefs {
@@ -22,13 +204,131 @@ impl Prefs {
     /// Load preferences from disk (returns default if missing/invalid)
     pub fn load() -> Self {
         Self::path()
-            .and_then(|p| fs::read_to_string(p).ok())
-            .and_then(|s| toml::from_str(&s).ok())
+            .and_then(|p| Self::read(&p).ok())
             .unwrap_or_default()
     }
 
+    /// Read preferences from `path`, reporting parse errors.
+    /// A missing file is the same as an empty one.
+    pub fn read(path: &Path) -> Result<Self, TtlError> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| TtlError::Config(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(TtlError::Config(format!("{}: {}", path.display(), e))),
+        }
+    }
+
+    /// Fill in options the command line left unset
+    pub fn apply_to(&self, args: &mut Args) {
+        if args.interval.is_none() {
+            args.interval = self.interval;
+        }
+        if args.alert_loss.is_none() {
+            args.alert_loss = self.alert_loss;
+        }
+        if args.recv_buffer.is_none() {
+            args.recv_buffer = self.recv_buffer;
+        }
+        if args.send_buffer.is_none() {
+            args.send_buffer = self.send_buffer;
+        }
+        if args.dscp.is_none() {
+            args.dscp = self.dscp.filter(|&d| d <= 63);
+        }
+        if args.stats_window.is_none() {
+            args.stats_window = self.stats_window.map(Duration::from_secs);
+        }
+        if args.gap_limit.is_none() {
+            args.gap_limit = self.gap_limit.filter(|&n| n > 0);
+        }
+        if args.ttl_decay.is_none() {
+            args.ttl_decay = self
+                .ttl_decay
+                .filter(|&d| d > 1.0 && d <= crate::config::MAX_TTL_DECAY);
+        }
+        if args.origin.is_none() {
+            args.origin = self.origin;
+        }
+        if args.locale.is_none() {
+            args.locale = self.locale.clone();
+        }
+        if args.lang.is_none() {
+            args.lang = self.lang.clone();
+        }
+        if args.label_mode.is_none() {
+            args.label_mode = self.label_mode.clone();
+        }
+        if args.host_trim.is_none() {
+            args.host_trim = self.host_trim.clone();
+        }
+        if args.enrich_url.is_none() {
+            args.enrich_url = self.enrich_url.clone();
+        }
+        if args.allow_host.is_empty() {
+            args.allow_host = self.allowed_hosts.clone();
+        }
+        args.offline |= self.offline == Some(true);
+        args.no_disk_cache |= self.disk_cache == Some(false);
+        args.local_time |= self.local_time == Some(true);
+        args.auto_units |= self.auto_units == Some(true);
+        args.merge_by_hostname |= self.merge_hostnames == Some(true);
+        args.reset_on_path_change |= self.reset_on_path_change == Some(true);
+        args.adaptive |= self.adaptive == Some(true);
+        // Config tags first so a repeated key from `--tag` wins
+        let cli_tags = std::mem::take(&mut args.tags);
+        args.tags = self.tags.clone().into_iter().chain(cli_tags).collect();
+        args.alias.extend(self.alias.iter().map(|alias| {
+            let networks: Vec<String> = alias.networks.iter().map(|n| n.to_string()).collect();
+            format!("{}={}", alias.name, networks.join(","))
+        }));
+        args.label.extend(
+            self.labels
+                .iter()
+                .map(|(addr, label)| format!("{}={}", addr, label)),
+        );
+        args.plugin.extend(
+            self.plugin
+                .iter()
+                .map(|p| format!("{}={}", p.hook.as_str(), p.command)),
+        );
+        args.no_dns |= self.dns == Some(false);
+        args.no_asn |= self.asn == Some(false);
+        args.no_geo |= self.geo == Some(false);
+        args.no_ix |= self.ix == Some(false);
+    }
+
+    /// Configured SLO for a target, by hostname as typed or by address
+    pub fn slo_for(&self, target: &str, ip: IpAddr) -> Result<Option<Slo>, TtlError> {
+        let Some(entry) = self
+            .slo
+            .iter()
+            .find(|e| e.target == target || e.target.parse() == Ok(ip))
+        else {
+            return Ok(None);
+        };
+        Slo::parse(&entry.objective).map(Some).map_err(|e| {
+            TtlError::Config(format!(
+                "Invalid SLO for {} in config.toml: {}",
+                entry.target, e
+            ))
+        })
+    }
+
+    /// Write the starter config to `path`, leaving an existing file alone
+    /// unless `force` is set
+    pub fn init(path: &Path, force: bool) -> Result<()> {
+        if path.exists() && !force {
+            anyhow::bail!("{} already exists (--force replaces it)", path.display());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, STARTER_CONFIG)?;
+        Ok(())
+    }
+
     /// Save preferences to disk
-    pub fn save(&self) -> anyhow::Result<()> {
+    pub fn save(&self) -> Result<()> {
         if let Some(path) = Self::path() {
             if let Some(parent) = path.parent() {
                 fs::create_dir_all(parent)?;
@@ -42,6 +342,7 @@ impl Prefs {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
 
     #[test]
     fn test_prefs_default() {
@@ -53,6 +354,7 @@ mod tests {
     fn test_prefs_serialization() {
         let prefs = Prefs {
             theme: Some("dracula".to_string()),
+            ..Default::default()
         };
         let toml_str = toml::to_string_pretty(&prefs).unwrap();
         assert!(toml_str.contains("theme = \"dracula\""));
@@ -60,4 +362,151 @@ mod tests {
         let loaded: Prefs = toml::from_str(&toml_str).unwrap();
         assert_eq!(loaded.theme, Some("dracula".to_string()));
     }
+
+    #[test]
+    fn test_prefs_apply_to_args() {
+        let prefs: Prefs = toml::from_str(
+            "interval = 0.5\nalert_loss = 5.0\ngeo = false\nrecv_buffer = 4194304\ndscp = 46\nstats_window = 300\ngap_limit = 3\nttl_decay = 1.2\nadaptive = true\nhost_trim = \"middle\"\nlang = \"de\"\n\
+             origin = { lat = 52.52, lon = 13.4 }\nallowed_hosts = [\"peeringdb.com\"]\n\
+             [tags]\nsite = \"fra1\"\n",
+        )
+        .unwrap();
+
+        let mut args = Args::try_parse_from(["ttl", "8.8.8.8"]).unwrap();
+        prefs.apply_to(&mut args);
+        assert_eq!(args.interval, Some(0.5));
+        assert_eq!(args.alert_loss, Some(5.0));
+        assert!(args.no_geo);
+        assert!(!args.no_dns);
+        assert_eq!(args.recv_buffer, Some(4 * 1024 * 1024));
+        assert_eq!(args.send_buffer, None);
+        assert_eq!(args.dscp, Some(46));
+        assert_eq!(args.stats_window, Some(Duration::from_secs(300)));
+        assert_eq!(args.gap_limit, Some(3));
+        assert_eq!(args.ttl_decay, Some(1.2));
+        assert!(args.adaptive);
+        assert_eq!(args.host_trim.as_deref(), Some("middle"));
+        assert_eq!(args.lang.as_deref(), Some("de"));
+        assert_eq!(
+            args.origin,
+            Some(GeoPoint {
+                lat: 52.52,
+                lon: 13.4
+            })
+        );
+        assert_eq!(args.allow_host, ["peeringdb.com"]);
+        assert!(!args.offline);
+        assert_eq!(args.tags, [("site".to_string(), "fra1".to_string())]);
+
+        // Command-line flags win
+        let mut args = Args::try_parse_from([
+            "ttl",
+            "-i",
+            "2",
+            "--alert-loss",
+            "9",
+            "--allow-host",
+            "10.0.0.53",
+            "--tag",
+            "site=ams2",
+            "a",
+        ])
+        .unwrap();
+        prefs.apply_to(&mut args);
+        assert_eq!(args.interval, Some(2.0));
+        assert_eq!(args.alert_loss, Some(9.0));
+        assert_eq!(args.allow_host, ["10.0.0.53"]);
+        let config = crate::config::Config::from(&args);
+        assert_eq!(config.tags_label(","), "site=ams2");
+    }
+
+    #[test]
+    fn test_prefs_plugins_added_to_args() {
+        let prefs: Prefs = toml::from_str(
+            "[[plugin]]\nhook = \"responder\"\ncommand = \"/opt/cmdb-lookup --site fra\"\n",
+        )
+        .unwrap();
+        let mut args =
+            Args::try_parse_from(["ttl", "--plugin", "export=./to-influx", "8.8.8.8"]).unwrap();
+        prefs.apply_to(&mut args);
+        assert_eq!(
+            args.plugin,
+            [
+                "export=./to-influx",
+                "responder=/opt/cmdb-lookup --site fra"
+            ]
+        );
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_starter_config_parses() {
+        let prefs: Prefs = toml::from_str(STARTER_CONFIG).unwrap();
+        assert_eq!(prefs, Prefs::default());
+
+        // Each example is valid once uncommented
+        let uncommented: String = STARTER_CONFIG
+            .lines()
+            .filter(|line| line.starts_with("# ") && !line.starts_with("# ttl"))
+            .map(|line| line.trim_start_matches("# "))
+            .filter(|line| line.contains(" = ") || line.starts_with('['))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let prefs: Prefs = toml::from_str(&uncommented).unwrap();
+        assert_eq!(prefs.interval, Some(1.0));
+        assert_eq!(prefs.labels.len(), 2);
+        assert_eq!(prefs.slo.len(), 1);
+        let mut args = Args::try_parse_from(["ttl", "8.8.8.8"]).unwrap();
+        prefs.apply_to(&mut args);
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_prefs_init_keeps_existing_file() {
+        let path = std::env::temp_dir().join(format!("ttl-init-{}.toml", std::process::id()));
+        Prefs::init(&path, false).unwrap();
+        assert!(Prefs::init(&path, false).is_err());
+        Prefs::init(&path, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), STARTER_CONFIG);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_prefs_read_missing_file() {
+        let prefs = Prefs::read(Path::new("/nonexistent/ttl/config.toml")).unwrap();
+        assert_eq!(prefs, Prefs::default());
+    }
+
+    #[test]
+    fn test_prefs_target_slo() {
+        let prefs: Prefs = toml::from_str(
+            r#"
+theme = "nord"
+
+[[slo]]
+target = "example.com"
+objective = "p95<80ms,loss<1%"
+
+[[slo]]
+target = "1.1.1.1"
+objective = "p99 < 20ms"
+"#,
+        )
+        .unwrap();
+        let ip: IpAddr = "1.1.1.1".parse().unwrap();
+
+        let slo = prefs.slo_for("example.com", ip).unwrap().unwrap();
+        assert_eq!(slo.latency_ms, Some(80.0));
+        let slo = prefs.slo_for("one.one.one.one", ip).unwrap().unwrap();
+        assert_eq!(slo.percentile, 99.0);
+        assert!(
+            prefs
+                .slo_for("other", "8.8.8.8".parse().unwrap())
+                .unwrap()
+                .is_none()
+        );
+
+        // Saving the theme keeps the SLO tables
+        assert!(toml::to_string_pretty(&prefs).unwrap().contains("[[slo]]"));
+    }
 }
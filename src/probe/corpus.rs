@@ -0,0 +1,173 @@
+//! Captured response corpus for `--bench-parse`
+//!
+//! A corpus is a text file with one received packet per line:
+//!
+//! ```text
+//! # responder  identifier  bytes as read from the raw socket (hex)
+//! 10.0.0.1 0xabcd 4500003800000000...
+//! ```
+//!
+//! Replaying it runs each packet through the same parse and stats path the
+//! receiver uses, without sockets or timers, so the cost per packet can be
+//! compared between builds.
+
+use anyhow::{Context, Result, anyhow, bail};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, UdpIdEncoding};
+use crate::probe::correlate::parse_icmp_response;
+use crate::state::{Session, Target};
+
+/// One captured packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusPacket {
+    pub responder: IpAddr,
+    pub identifier: u16,
+    pub data: Vec<u8>,
+}
+
+/// Outcome of a replay run
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayStats {
+    pub packets: u64,
+    /// Packets that parsed and matched their identifier
+    pub matched: u64,
+    pub elapsed: Duration,
+}
+
+impl ReplayStats {
+    pub fn ns_per_packet(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.packets.max(1) as f64
+    }
+
+    pub fn packets_per_sec(&self) -> f64 {
+        self.packets as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+fn parse_identifier(s: &str) -> Option<u16> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parse corpus text; blank lines and `#` comments are skipped
+pub fn parse_corpus(text: &str) -> Result<Vec<CorpusPacket>> {
+    let mut packets = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [responder, identifier, hex] = fields[..] else {
+            bail!("line {}: expected RESPONDER IDENTIFIER HEX", n + 1);
+        };
+        let responder = responder
+            .parse()
+            .map_err(|_| anyhow!("line {}: invalid responder '{}'", n + 1, responder))?;
+        let identifier = parse_identifier(identifier)
+            .ok_or_else(|| anyhow!("line {}: invalid identifier '{}'", n + 1, identifier))?;
+        let data = parse_hex(hex).ok_or_else(|| anyhow!("line {}: invalid hex bytes", n + 1))?;
+        packets.push(CorpusPacket {
+            responder,
+            identifier,
+            data,
+        });
+    }
+    if packets.is_empty() {
+        bail!("corpus has no packets");
+    }
+    Ok(packets)
+}
+
+/// Read a corpus file
+pub fn load_corpus(path: &str) -> Result<Vec<CorpusPacket>> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read corpus {}", path))?;
+    parse_corpus(&text).with_context(|| format!("Invalid corpus {}", path))
+}
+
+/// Parse and record the corpus repeatedly until at least `min_packets` have
+/// been processed. Matched responses are recorded into a scratch session,
+/// so stats updates are part of the measured cost.
+pub fn replay_corpus(packets: &[CorpusPacket], min_packets: u64) -> ReplayStats {
+    let target = Target::new("corpus".to_string(), packets[0].responder);
+    let mut session = Session::new(target, Config::default());
+    let rtt = Duration::from_millis(10);
+
+    let mut stats = ReplayStats {
+        packets: 0,
+        matched: 0,
+        elapsed: Duration::ZERO,
+    };
+    let start = Instant::now();
+    while stats.packets < min_packets {
+        for packet in packets {
+            stats.packets += 1;
+            let Some(parsed) = parse_icmp_response(
+                &packet.data,
+                packet.responder,
+                packet.identifier,
+                false,
+                UdpIdEncoding::Payload,
+            ) else {
+                continue;
+            };
+            stats.matched += 1;
+            if let Some(hop) = session.hop_mut(parsed.probe_id.ttl) {
+                hop.record_sent();
+                hop.record_response(parsed.responder, rtt);
+            }
+        }
+    }
+    stats.elapsed = start.elapsed();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_corpus() {
+        let text = "\
+# comment
+192.168.1.1 0xabcd 45000000000000000001000000000000000000000b00000000000000450000000000000000010000000000000000000008000000abcd0503
+
+8.8.8.8 4660 00
+";
+        let packets = parse_corpus(text).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].identifier, 0xabcd);
+        assert_eq!(packets[0].data.len(), 56);
+        assert_eq!(packets[1].identifier, 0x1234);
+        assert_eq!(packets[1].data, vec![0]);
+
+        assert!(parse_corpus("# nothing\n").is_err());
+        assert!(parse_corpus("10.0.0.1 0x1 abc").is_err());
+        assert!(parse_corpus("10.0.0.1 zz 00").is_err());
+        assert!(parse_corpus("10.0.0.1 00").is_err());
+    }
+
+    #[test]
+    fn test_replay_corpus() {
+        let text = "192.168.1.1 0xabcd 45000000000000000001000000000000000000000b00000000000000450000000000000000010000000000000000000008000000abcd0503";
+        let packets = parse_corpus(text).unwrap();
+        let stats = replay_corpus(&packets, 10);
+        assert_eq!(stats.packets, 10);
+        assert_eq!(stats.matched, 10);
+    }
+}
@@ -1,6 +1,8 @@
+use crate::config::UdpIdEncoding;
+use crate::probe::icmp::NONCE_OFFSET;
 use crate::probe::tcp::extract_probe_id_from_tcp;
-use crate::probe::udp::extract_probe_id_from_udp_payload;
-use crate::state::{IcmpResponseType, MplsLabel, ProbeId};
+use crate::probe::udp::extract_probe_id_from_udp;
+use crate::state::{EcnCodepoint, IcmpResponseType, IncomingInterface, MplsLabel, ProbeId};
 use pnet::packet::icmp::{IcmpPacket, IcmpTypes};
 use pnet::packet::ipv4::Ipv4Packet;
 use std::net::IpAddr;
@@ -28,6 +30,8 @@ pub struct ParsedResponse {
     pub response_type: IcmpResponseType,
     /// MPLS labels from ICMP extensions (RFC 4950), if present
     pub mpls_labels: Option<Vec<MplsLabel>>,
+    /// Interface the probe arrived on (RFC 5837), if the router said
+    pub interface: Option<IncomingInterface>,
     /// Source port from original UDP/TCP packet (for flow identification in Paris/Dublin traceroute)
     /// This allows the receiver to compute flow_id = src_port - base_src_port
     pub src_port: Option<u16>,
@@ -37,29 +41,55 @@ pub struct ParsedResponse {
     /// TTL from quoted IP header in ICMP error (for TTL manipulation detection)
     /// For Time Exceeded, this should be 0 or 1 per RFC; values > 1 suggest manipulation
     pub quoted_ttl: Option<u8>,
+    /// DSCP from the quoted IP header in an ICMP error: the marking the
+    /// probe carried when it reached the responder
+    pub quoted_dscp: Option<u8>,
+    /// ECN codepoint from the same quoted header, for bleaching detection
+    pub quoted_ecn: Option<EcnCodepoint>,
+    /// UDP/TCP checksum of the quoted probe (TCP only when the error quotes
+    /// 18 bytes of it). A NAT that rewrites the source address must fix it up,
+    /// so it differs from the one sent even when the port survives.
+    pub quoted_checksum: Option<u16>,
     /// Original destination IP from quoted packet in ICMP error
     /// Used to disambiguate multi-target responses
     pub original_dest: Option<IpAddr>,
 }
 
-// ICMP extension constants (RFC 4884, RFC 4950)
+// ICMP extension constants (RFC 4884, RFC 4950, RFC 5837)
 const ICMP_EXT_VERSION: u8 = 2;
 const MPLS_LABEL_STACK_CLASS: u8 = 1;
 const MPLS_LABEL_STACK_TYPE: u8 = 1;
+const INTERFACE_INFO_CLASS: u8 = 2;
 const MIN_ORIGINAL_DATAGRAM: usize = 128;
 
-/// Parse ICMP extensions from an error message payload (RFC 4884)
-/// Returns MPLS label stack if present (RFC 4950)
+// Interface Information C-Type: role in the top two bits, then flags for
+// the sub-objects present, in the order they follow
+const ROLE_INCOMING: u8 = 0;
+const HAS_IFINDEX: u8 = 0x08;
+const HAS_IPADDR: u8 = 0x04;
+const HAS_NAME: u8 = 0x02;
+const HAS_MTU: u8 = 0x01;
+const AFI_IPV4: u16 = 1;
+const AFI_IPV6: u16 = 2;
+
+/// What the extension structure of an ICMP error carried
+#[derive(Debug, Default)]
+struct IcmpExtensions {
+    mpls_labels: Option<Vec<MplsLabel>>,
+    interface: Option<IncomingInterface>,
+}
+
+/// Parse ICMP extensions from an error message payload (RFC 4884):
+/// the MPLS label stack (RFC 4950) and the incoming interface (RFC 5837)
 ///
 /// The icmp_payload parameter should be the ICMP error payload starting
 /// after the 8-byte ICMP header (i.e., starting with the original datagram).
 /// The icmp_length parameter is the "length" field from the ICMP header
 /// (byte 5 of the full ICMP message), which indicates the original datagram
 /// length in 32-bit words when non-zero.
-fn parse_icmp_extensions_with_length(
-    icmp_payload: &[u8],
-    icmp_length: u8,
-) -> Option<Vec<MplsLabel>> {
+fn parse_icmp_extensions_with_length(icmp_payload: &[u8], icmp_length: u8) -> IcmpExtensions {
+    let mut extensions = IcmpExtensions::default();
+
     // RFC 4884: The "length" field indicates original datagram length in 32-bit words
     // If non-zero, extensions start at (length * 4) bytes
     // If zero (legacy), extensions start at 128 bytes (if present)
@@ -70,7 +100,7 @@ fn parse_icmp_extensions_with_length(
     };
 
     if icmp_payload.len() < ext_start + 4 {
-        return None;
+        return extensions;
     }
 
     let ext_header = &icmp_payload[ext_start..];
@@ -78,7 +108,7 @@ fn parse_icmp_extensions_with_length(
     // Version (high nibble of first byte) must be 2
     let version = (ext_header[0] >> 4) & 0x0F;
     if version != ICMP_EXT_VERSION {
-        return None;
+        return extensions;
     }
 
     // Skip checksum validation for now (optional in compliant mode)
@@ -94,14 +124,17 @@ fn parse_icmp_extensions_with_length(
         if obj_length < 4 || offset + obj_length > ext_header.len() {
             break;
         }
+        let body = &ext_header[offset + 4..offset + obj_length];
 
         // Check for MPLS Label Stack (class=1, type=1)
-        if obj_class == MPLS_LABEL_STACK_CLASS && obj_type == MPLS_LABEL_STACK_TYPE {
-            let label_data = &ext_header[offset + 4..offset + obj_length];
+        if obj_class == MPLS_LABEL_STACK_CLASS
+            && obj_type == MPLS_LABEL_STACK_TYPE
+            && extensions.mpls_labels.is_none()
+        {
             let mut labels = Vec::new();
 
             // Each label entry is 4 bytes
-            for chunk in label_data.chunks_exact(4) {
+            for chunk in body.chunks_exact(4) {
                 // chunks_exact(4) guarantees chunk.len() == 4, so this is infallible
                 let bytes: [u8; 4] = [chunk[0], chunk[1], chunk[2], chunk[3]];
                 let label = MplsLabel::from_bytes(&bytes);
@@ -113,14 +146,72 @@ fn parse_icmp_extensions_with_length(
             }
 
             if !labels.is_empty() {
-                return Some(labels);
+                extensions.mpls_labels = Some(labels);
             }
         }
 
+        // Interface Information (class=2); only the incoming interface is kept
+        if obj_class == INTERFACE_INFO_CLASS && extensions.interface.is_none() {
+            extensions.interface = parse_interface_info(obj_type, body);
+        }
+
         offset += obj_length;
     }
 
-    None
+    extensions
+}
+
+/// Parse an Interface Information object body (RFC 5837) describing the
+/// incoming interface. Objects for other roles, or cut short, give None.
+fn parse_interface_info(c_type: u8, body: &[u8]) -> Option<IncomingInterface> {
+    if c_type >> 6 != ROLE_INCOMING {
+        return None;
+    }
+    let mut interface = IncomingInterface::default();
+    let mut rest = body;
+
+    if c_type & HAS_IFINDEX != 0 {
+        let (index, tail) = rest.split_first_chunk::<4>()?;
+        interface.index = Some(u32::from_be_bytes(*index));
+        rest = tail;
+    }
+    if c_type & HAS_IPADDR != 0 {
+        // AFI (16 bits), reserved (16 bits), then the address
+        let (head, tail) = rest.split_first_chunk::<4>()?;
+        rest = tail;
+        interface.address = match u16::from_be_bytes([head[0], head[1]]) {
+            AFI_IPV4 => {
+                let (addr, tail) = rest.split_first_chunk::<4>()?;
+                rest = tail;
+                Some(IpAddr::from(*addr))
+            }
+            AFI_IPV6 => {
+                let (addr, tail) = rest.split_first_chunk::<16>()?;
+                rest = tail;
+                Some(IpAddr::from(*addr))
+            }
+            _ => return None,
+        };
+    }
+    if c_type & HAS_NAME != 0 {
+        // Length octet (counting itself, a multiple of 4), then UTF-8 padded with NULs
+        let len = *rest.first()? as usize;
+        if len == 0 || len > rest.len() {
+            return None;
+        }
+        let name: String = String::from_utf8_lossy(&rest[1..len])
+            .trim_end_matches('\0')
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        interface.name = (!name.is_empty()).then_some(name);
+        rest = &rest[len..];
+    }
+    if c_type & HAS_MTU != 0 {
+        let (mtu, _) = rest.split_first_chunk::<4>()?;
+        interface.mtu = Some(u32::from_be_bytes(*mtu));
+    }
+    Some(interface)
 }
 
 /// Calculate ICMP checksum (RFC 1071)
@@ -167,6 +258,7 @@ pub fn parse_icmp_response(
     responder: IpAddr,
     our_identifier: u16,
     is_dgram: bool,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     if data.is_empty() {
         return None;
@@ -175,17 +267,17 @@ pub fn parse_icmp_response(
     if is_dgram {
         // DGRAM socket: no IP header, use responder address to determine version
         if responder.is_ipv4() {
-            parse_icmp_response_v4_dgram(data, responder, our_identifier)
+            parse_icmp_response_v4_dgram(data, responder, our_identifier, udp_id)
         } else {
-            parse_icmp_response_v6_dgram(data, responder, our_identifier)
+            parse_icmp_response_v6_dgram(data, responder, our_identifier, udp_id)
         }
     } else if responder.is_ipv6() {
         // RAW IPv6 socket: Linux kernel strips IPv6 header, delivers ICMPv6 directly
         // Use DGRAM parser which expects no IP header
-        parse_icmp_response_v6_dgram(data, responder, our_identifier)
+        parse_icmp_response_v6_dgram(data, responder, our_identifier, udp_id)
     } else {
         // RAW IPv4 socket: has IP header
-        parse_icmp_response_v4(data, responder, our_identifier)
+        parse_icmp_response_v4(data, responder, our_identifier, udp_id)
     }
 }
 
@@ -194,6 +286,7 @@ fn parse_icmp_response_v4(
     data: &[u8],
     responder: IpAddr,
     our_identifier: u16,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     let ip_packet = Ipv4Packet::new(data)?;
     let ip_header_len = (ip_packet.get_header_length() as usize) * 4;
@@ -228,9 +321,13 @@ fn parse_icmp_response_v4(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -247,9 +344,13 @@ fn parse_icmp_response_v4(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -264,6 +365,7 @@ fn parse_icmp_response_v4(
                 our_identifier,
                 IcmpResponseType::TimeExceeded(code),
                 None,
+                udp_id,
             )
         }
         IcmpTypes::DestinationUnreachable => {
@@ -281,35 +383,45 @@ fn parse_icmp_response_v4(
                 our_identifier,
                 IcmpResponseType::DestUnreachable(code),
                 mtu,
+                udp_id,
             )
         }
         _ => None,
     }
 }
 
-// IPv6 Next Header protocol numbers
-// Note: These are currently unused because Linux strips the IPv6 header from raw
-// ICMPv6 sockets before delivery. Kept for potential future use on other platforms.
-#[allow(dead_code)]
+// IPv6 Next Header protocol numbers used when walking extension header chains
 const IPV6_NH_HOP_BY_HOP: u8 = 0;
-#[allow(dead_code)]
 const IPV6_NH_ROUTING: u8 = 43;
-#[allow(dead_code)]
 const IPV6_NH_FRAGMENT: u8 = 44;
+const IPV6_NH_AUTH: u8 = 51;
 #[allow(dead_code)]
 const IPV6_NH_ICMPV6: u8 = 58;
-#[allow(dead_code)]
-const IPV6_NH_NO_NEXT: u8 = 59;
-#[allow(dead_code)]
 const IPV6_NH_DEST_OPTS: u8 = 60;
 
-/// Skip IPv6 extension headers and return offset to ICMPv6 payload
-/// Returns None if ICMPv6 is not the upper layer protocol
+/// Checksum of a quoted UDP header (always within the 8 quoted bytes)
+fn quoted_udp_checksum(udp: &[u8]) -> Option<u16> {
+    udp.get(6..8).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+/// Checksum of a quoted TCP header, if the error quoted that far
+fn quoted_tcp_checksum(tcp: &[u8]) -> Option<u16> {
+    tcp.get(16..18).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+/// Traffic Class of an IPv6 header (4 bits in each of the first two bytes)
+fn quoted_traffic_class(ipv6_header: &[u8]) -> u8 {
+    (ipv6_header[0] << 4) | (ipv6_header[1] >> 4)
+}
+
+/// Walk an IPv6 header's extension chain to the upper-layer header.
 ///
-/// Note: Currently unused because Linux strips IPv6 headers from raw ICMPv6 sockets.
-/// Kept for potential future use on platforms that include the IPv6 header.
-#[allow(dead_code)]
-fn skip_ipv6_extension_headers(data: &[u8]) -> Option<usize> {
+/// Returns the upper-layer protocol number and its offset within `data`.
+/// Hop-by-Hop, Routing, Destination Options and AH headers are skipped.
+/// Fragment headers are only accepted when `allow_first_fragment` is set and
+/// the fragment offset is zero, since later fragments carry no transport header.
+/// Anything else (ESP, No Next Header, unknown) ends the walk with that protocol.
+fn walk_ipv6_header_chain(data: &[u8], allow_first_fragment: bool) -> Option<(u8, usize)> {
     const IPV6_HEADER_LEN: usize = 40;
 
     if data.len() < IPV6_HEADER_LEN {
@@ -320,44 +432,51 @@ fn skip_ipv6_extension_headers(data: &[u8]) -> Option<usize> {
     let mut next_header = data[6];
     let mut offset = IPV6_HEADER_LEN;
 
-    // Walk through extension headers until we find ICMPv6 or something else
     loop {
-        match next_header {
-            IPV6_NH_ICMPV6 => {
-                // Found ICMPv6
-                return Some(offset);
-            }
+        let ext_len = match next_header {
             IPV6_NH_HOP_BY_HOP | IPV6_NH_ROUTING | IPV6_NH_DEST_OPTS => {
-                // Variable-length extension header
-                // Byte 0: Next Header, Byte 1: Length (in 8-octet units, excluding first 8)
-                if data.len() < offset + 2 {
+                // Byte 1: length in 8-octet units, excluding the first 8
+                (*data.get(offset + 1)? as usize + 1) * 8
+            }
+            IPV6_NH_AUTH => {
+                // AH length is in 4-octet units, minus 2 (RFC 4302)
+                (*data.get(offset + 1)? as usize + 2) * 4
+            }
+            IPV6_NH_FRAGMENT => {
+                if !allow_first_fragment {
+                    // We can't reassemble fragments, so reject them
                     return None;
                 }
-                next_header = data[offset];
-                let ext_len = (data[offset + 1] as usize + 1) * 8;
-                offset += ext_len;
-                if offset > data.len() {
+                // Fragment offset is the top 13 bits of bytes 2-3
+                let frag = u16::from_be_bytes([*data.get(offset + 2)?, *data.get(offset + 3)?]);
+                if frag >> 3 != 0 {
                     return None;
                 }
+                8
             }
-            IPV6_NH_FRAGMENT => {
-                // Fragment headers indicate fragmented packets
-                // We can't reassemble fragments, so reject them
-                return None;
-            }
-            IPV6_NH_NO_NEXT => {
-                // No upper layer payload
-                return None;
-            }
-            _ => {
-                // Unknown or unsupported protocol (ESP, AH, etc.)
-                // Can't safely skip, so reject
-                return None;
-            }
+            proto => return Some((proto, offset)),
+        };
+        next_header = *data.get(offset)?;
+        offset += ext_len;
+        if offset > data.len() {
+            return None;
         }
     }
 }
 
+/// Skip IPv6 extension headers and return offset to ICMPv6 payload
+/// Returns None if ICMPv6 is not the upper layer protocol
+///
+/// Note: Currently unused because Linux strips IPv6 headers from raw ICMPv6 sockets.
+/// Kept for potential future use on platforms that include the IPv6 header.
+#[allow(dead_code)]
+fn skip_ipv6_extension_headers(data: &[u8]) -> Option<usize> {
+    match walk_ipv6_header_chain(data, false)? {
+        (IPV6_NH_ICMPV6, offset) => Some(offset),
+        _ => None,
+    }
+}
+
 /// Parse IPv6 ICMPv6 response
 ///
 /// Note: ICMPv6 checksum validation is intentionally omitted. Unlike ICMPv4,
@@ -372,6 +491,7 @@ fn parse_icmp_response_v6(
     data: &[u8],
     responder: IpAddr,
     our_identifier: u16,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     // Skip any extension headers to find ICMPv6
     let icmp_offset = skip_ipv6_extension_headers(data)?;
@@ -398,9 +518,13 @@ fn parse_icmp_response_v6(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -416,9 +540,13 @@ fn parse_icmp_response_v6(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -445,6 +573,7 @@ fn parse_icmp_response_v6(
                 our_identifier,
                 IcmpResponseType::PacketTooBig,
                 mtu,
+                udp_id,
             )
         }
         ICMPV6_TIME_EXCEEDED => parse_icmp_error_payload_v6_with_mtu(
@@ -453,6 +582,7 @@ fn parse_icmp_response_v6(
             our_identifier,
             IcmpResponseType::TimeExceeded(icmp_code),
             None,
+            udp_id,
         ),
         ICMPV6_DEST_UNREACHABLE => parse_icmp_error_payload_v6_with_mtu(
             icmp_data,
@@ -460,6 +590,7 @@ fn parse_icmp_response_v6(
             our_identifier,
             IcmpResponseType::DestUnreachable(icmp_code),
             None,
+            udp_id,
         ),
         _ => None,
     }
@@ -472,6 +603,7 @@ fn parse_icmp_error_payload_v4_with_mtu(
     our_identifier: u16,
     response_type: IcmpResponseType,
     mtu: Option<u16>,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     // ICMP error format (RFC 4884):
     // [0]    Type
@@ -498,6 +630,9 @@ fn parse_icmp_error_payload_v4_with_mtu(
     let orig_protocol = original_ip.get_next_level_protocol().0;
     // Extract quoted TTL for TTL manipulation detection
     let quoted_ttl = original_ip.get_ttl();
+    // DSCP as the responder received it, for remark detection
+    let quoted_dscp = original_ip.get_dscp();
+    let quoted_ecn = EcnCodepoint::from_tos(original_ip.get_ecn());
     // Extract original destination for multi-target disambiguation
     let original_dest = Some(IpAddr::V4(original_ip.get_destination()));
 
@@ -508,7 +643,10 @@ fn parse_icmp_error_payload_v4_with_mtu(
     let original_payload = &original_ip_data[orig_ihl..];
 
     // Try to parse ICMP extensions using RFC 4884 length field
-    let mpls_labels = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
+    let IcmpExtensions {
+        mpls_labels,
+        interface,
+    } = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
 
     // Handle based on original protocol
     match orig_protocol {
@@ -534,9 +672,13 @@ fn parse_icmp_error_payload_v4_with_mtu(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -557,9 +699,13 @@ fn parse_icmp_error_payload_v4_with_mtu(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -584,9 +730,13 @@ fn parse_icmp_error_payload_v4_with_mtu(
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_tcp_checksum(original_payload),
                 original_dest,
             })
         }
@@ -595,24 +745,27 @@ fn parse_icmp_error_payload_v4_with_mtu(
             // UDP header: [0-1] src port, [2-3] dst port, [4-5] length, [6-7] checksum
             // Our payload starts at offset 8
 
-            if original_payload.len() < 8 + 6 {
-                // Need UDP header + at least 6 bytes of payload
+            if original_payload.len() < 8 {
+                // Need the UDP header; the decoder checks any quoted payload
                 return None;
             }
 
             // Extract source port for flow identification (Paris/Dublin traceroute)
             let src_port = u16::from_be_bytes([original_payload[0], original_payload[1]]);
-            let udp_payload = &original_payload[8..];
-            let probe_id = extract_probe_id_from_udp_payload(udp_payload)?;
+            let probe_id = extract_probe_id_from_udp(original_payload, udp_id)?;
 
             Some(ParsedResponse {
                 responder,
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_udp_checksum(original_payload),
                 original_dest,
             })
         }
@@ -635,6 +788,7 @@ fn parse_icmp_error_payload_v6_with_mtu(
     our_identifier: u16,
     response_type: IcmpResponseType,
     mtu: Option<u16>,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     // ICMPv6 error format (RFC 4884):
     // [0]    Type
@@ -663,10 +817,10 @@ fn parse_icmp_error_payload_v6_with_mtu(
     let icmp_length = icmp_data[5];
 
     let original_ipv6_data = &icmp_data[8..];
-    // Next header field is at byte 6 of IPv6 header
-    let next_header = original_ipv6_data[6];
     // Hop limit (IPv6 equivalent of TTL) is at byte 7
     let quoted_ttl = original_ipv6_data[7];
+    let quoted_dscp = quoted_traffic_class(original_ipv6_data) >> 2;
+    let quoted_ecn = EcnCodepoint::from_tos(quoted_traffic_class(original_ipv6_data));
     // Extract original destination for multi-target disambiguation (bytes 24-39)
     let original_dest = Some(IpAddr::V6(std::net::Ipv6Addr::new(
         u16::from_be_bytes([original_ipv6_data[24], original_ipv6_data[25]]),
@@ -678,10 +832,19 @@ fn parse_icmp_error_payload_v6_with_mtu(
         u16::from_be_bytes([original_ipv6_data[36], original_ipv6_data[37]]),
         u16::from_be_bytes([original_ipv6_data[38], original_ipv6_data[39]]),
     )));
-    let original_payload = &original_ipv6_data[IPV6_HEADER_LEN..];
+    // Routers quote the packet as sent, so any extension headers we (or the
+    // kernel) added sit between the IPv6 header and the transport header
+    let (next_header, payload_offset) = walk_ipv6_header_chain(original_ipv6_data, true)?;
+    let original_payload = &original_ipv6_data[payload_offset..];
+    if original_payload.len() < 8 {
+        return None;
+    }
 
     // Try to parse ICMP extensions using RFC 4884 length field
-    let mpls_labels = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
+    let IcmpExtensions {
+        mpls_labels,
+        interface,
+    } = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
 
     // Handle based on original protocol
     match next_header {
@@ -707,9 +870,13 @@ fn parse_icmp_error_payload_v6_with_mtu(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -729,9 +896,13 @@ fn parse_icmp_error_payload_v6_with_mtu(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -756,9 +927,13 @@ fn parse_icmp_error_payload_v6_with_mtu(
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_tcp_checksum(original_payload),
                 original_dest,
             })
         }
@@ -767,24 +942,27 @@ fn parse_icmp_error_payload_v6_with_mtu(
             // UDP header: [0-1] src port, [2-3] dst port, [4-5] length, [6-7] checksum
             // Our payload starts at offset 8
 
-            if original_payload.len() < 8 + 6 {
-                // Need UDP header + at least 6 bytes of payload
+            if original_payload.len() < 8 {
+                // Need the UDP header; the decoder checks any quoted payload
                 return None;
             }
 
             // Extract source port for flow identification (Paris/Dublin traceroute)
             let src_port = u16::from_be_bytes([original_payload[0], original_payload[1]]);
-            let udp_payload = &original_payload[8..];
-            let probe_id = extract_probe_id_from_udp_payload(udp_payload)?;
+            let probe_id = extract_probe_id_from_udp(original_payload, udp_id)?;
 
             Some(ParsedResponse {
                 responder,
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_udp_checksum(original_payload),
                 original_dest,
             })
         }
@@ -792,12 +970,119 @@ fn parse_icmp_error_payload_v6_with_mtu(
     }
 }
 
+// ICMP Redirect (RFC 792) / ICMPv6 Redirect (RFC 4861)
+const ICMP_REDIRECT: u8 = 5;
+const ICMPV6_REDIRECT: u8 = 137;
+
+/// An ICMP Redirect: `router` tells us to send traffic for `dest` to
+/// `gateway` instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Redirect {
+    pub router: IpAddr,
+    pub gateway: IpAddr,
+    pub dest: IpAddr,
+}
+
+/// Parse an ICMP Redirect. These aren't replies to a probe, so they are
+/// matched to a target by the redirected destination rather than a ProbeId.
+pub fn parse_redirect(data: &[u8], responder: IpAddr, is_dgram: bool) -> Option<Redirect> {
+    match responder {
+        IpAddr::V4(_) => {
+            // RAW IPv4 sockets include the IP header
+            let icmp = if is_dgram {
+                data
+            } else {
+                data.get((*data.first()? as usize & 0x0F) * 4..)?
+            };
+            if *icmp.first()? != ICMP_REDIRECT {
+                return None;
+            }
+            let gateway: [u8; 4] = icmp.get(4..8)?.try_into().ok()?;
+            // Destination of the quoted IP header
+            let dest: [u8; 4] = icmp.get(8 + 16..8 + 20)?.try_into().ok()?;
+            Some(Redirect {
+                router: responder,
+                gateway: IpAddr::from(gateway),
+                dest: IpAddr::from(dest),
+            })
+        }
+        IpAddr::V6(_) => {
+            // Target Address (the better first hop), then Destination Address
+            if *data.first()? != ICMPV6_REDIRECT {
+                return None;
+            }
+            let gateway: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+            let dest: [u8; 16] = data.get(24..40)?.try_into().ok()?;
+            Some(Redirect {
+                router: responder,
+                gateway: IpAddr::from(gateway),
+                dest: IpAddr::from(dest),
+            })
+        }
+    }
+}
+
 // ============================================================================
 // DGRAM socket parsing (no IP header - used on macOS)
 // ============================================================================
 
 /// Helper to extract identifier from payload (fallback for macOS DGRAM id override)
 /// Payload layout: [0-1] identifier, [2-3] sequence, [4-7] timestamp
+/// Session nonce carried in the echo payload of a reply, or quoted in an
+/// ICMP error for one of our echo requests. None when the reply isn't
+/// about an echo request or the quote stops before the nonce.
+pub fn echo_payload_nonce(data: &[u8], responder: IpAddr, is_dgram: bool) -> Option<u32> {
+    // RAW IPv4 sockets include the IP header; DGRAM and ICMPv6 sockets don't
+    let icmp = if responder.is_ipv4() && !is_dgram {
+        data.get((*data.first()? as usize & 0x0F) * 4..)?
+    } else {
+        data
+    };
+    let v6 = responder.is_ipv6();
+
+    let echo_payload = match (v6, *icmp.first()?) {
+        (false, 0) | (true, ICMPV6_ECHO_REPLY) => icmp.get(8..)?,
+        (false, 3 | 11) => {
+            // RFC 4884 length: the quote ends there, extensions follow
+            let quote = match icmp.get(5).copied()? {
+                0 => icmp.get(8..)?,
+                words => icmp.get(8..8 + words as usize * 4)?,
+            };
+            let ihl = (*quote.first()? as usize & 0x0F) * 4;
+            if *quote.get(9)? != IPPROTO_ICMP {
+                return None;
+            }
+            let original = quote.get(ihl..)?;
+            if *original.first()? != 8 {
+                return None;
+            }
+            original.get(8..)?
+        }
+        (true, ICMPV6_DEST_UNREACHABLE | ICMPV6_PACKET_TOO_BIG | ICMPV6_TIME_EXCEEDED) => {
+            let quote = match icmp.get(5).copied()? {
+                0 => icmp.get(8..)?,
+                words => icmp.get(8..8 + words as usize * 8)?,
+            };
+            let (IPPROTO_ICMPV6, offset) = walk_ipv6_header_chain(quote, true)? else {
+                return None;
+            };
+            let original = quote.get(offset..)?;
+            if *original.first()? != ICMPV6_ECHO_REQUEST {
+                return None;
+            }
+            original.get(8..)?
+        }
+        _ => return None,
+    };
+
+    let nonce = echo_payload.get(NONCE_OFFSET..NONCE_OFFSET + 4)?;
+    // Zeros are padding from a short quote (RFC 4884 pads to 128 bytes)
+    match u32::from_be_bytes(nonce.try_into().ok()?) {
+        0 => None,
+        nonce => Some(nonce),
+    }
+}
+
 fn extract_id_from_payload(payload: &[u8], our_identifier: u16) -> Option<(u16, u16)> {
     if payload.len() < 4 {
         return None;
@@ -816,6 +1101,7 @@ fn parse_icmp_response_v4_dgram(
     icmp_data: &[u8],
     responder: IpAddr,
     our_identifier: u16,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     if icmp_data.len() < 8 {
         return None;
@@ -842,9 +1128,13 @@ fn parse_icmp_response_v4_dgram(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -861,9 +1151,13 @@ fn parse_icmp_response_v4_dgram(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -878,6 +1172,7 @@ fn parse_icmp_response_v4_dgram(
                 our_identifier,
                 IcmpResponseType::TimeExceeded(icmp_code),
                 None,
+                udp_id,
             )
         }
         3 => {
@@ -894,6 +1189,7 @@ fn parse_icmp_response_v4_dgram(
                 our_identifier,
                 IcmpResponseType::DestUnreachable(icmp_code),
                 mtu,
+                udp_id,
             )
         }
         _ => None,
@@ -907,6 +1203,7 @@ fn parse_icmp_error_payload_v4_dgram(
     our_identifier: u16,
     response_type: IcmpResponseType,
     mtu: Option<u16>,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     // ICMP error: [0-7] ICMP header, [8..] original IP packet
     if icmp_data.len() < 8 + 20 + 8 {
@@ -919,6 +1216,8 @@ fn parse_icmp_error_payload_v4_dgram(
     let orig_ihl = (original_ip.get_header_length() as usize) * 4;
     let orig_protocol = original_ip.get_next_level_protocol().0;
     let quoted_ttl = original_ip.get_ttl();
+    let quoted_dscp = original_ip.get_dscp();
+    let quoted_ecn = EcnCodepoint::from_tos(original_ip.get_ecn());
     let original_dest = Some(IpAddr::V4(original_ip.get_destination()));
 
     if original_ip_data.len() < orig_ihl + 8 {
@@ -926,7 +1225,10 @@ fn parse_icmp_error_payload_v4_dgram(
     }
 
     let original_payload = &original_ip_data[orig_ihl..];
-    let mpls_labels = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
+    let IcmpExtensions {
+        mpls_labels,
+        interface,
+    } = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
 
     match orig_protocol {
         IPPROTO_ICMP => {
@@ -949,9 +1251,13 @@ fn parse_icmp_error_payload_v4_dgram(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -968,9 +1274,13 @@ fn parse_icmp_error_payload_v4_dgram(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -988,28 +1298,35 @@ fn parse_icmp_error_payload_v4_dgram(
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_tcp_checksum(original_payload),
                 original_dest,
             })
         }
         IPPROTO_UDP => {
-            if original_payload.len() < 8 + 6 {
+            if original_payload.len() < 8 {
                 return None;
             }
             let src_port = u16::from_be_bytes([original_payload[0], original_payload[1]]);
-            let udp_payload = &original_payload[8..];
-            let probe_id = extract_probe_id_from_udp_payload(udp_payload)?;
+            let probe_id = extract_probe_id_from_udp(original_payload, udp_id)?;
 
             Some(ParsedResponse {
                 responder,
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_udp_checksum(original_payload),
                 original_dest,
             })
         }
@@ -1022,6 +1339,7 @@ fn parse_icmp_response_v6_dgram(
     icmp_data: &[u8],
     responder: IpAddr,
     our_identifier: u16,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     if icmp_data.len() < 8 {
         return None;
@@ -1041,9 +1359,13 @@ fn parse_icmp_response_v6_dgram(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -1059,9 +1381,13 @@ fn parse_icmp_response_v6_dgram(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type: IcmpResponseType::EchoReply,
                     mpls_labels: None,
+                    interface: None,
                     src_port: None,
                     mtu: None,
                     quoted_ttl: None,
+                    quoted_dscp: None,
+                    quoted_ecn: None,
+                    quoted_checksum: None,
                     // For Echo Reply, responder IS the target
                     original_dest: Some(responder),
                 });
@@ -1086,6 +1412,7 @@ fn parse_icmp_response_v6_dgram(
                 our_identifier,
                 IcmpResponseType::PacketTooBig,
                 mtu,
+                udp_id,
             )
         }
         ICMPV6_TIME_EXCEEDED => parse_icmp_error_payload_v6_dgram(
@@ -1094,6 +1421,7 @@ fn parse_icmp_response_v6_dgram(
             our_identifier,
             IcmpResponseType::TimeExceeded(icmp_code),
             None,
+            udp_id,
         ),
         ICMPV6_DEST_UNREACHABLE => parse_icmp_error_payload_v6_dgram(
             icmp_data,
@@ -1101,6 +1429,7 @@ fn parse_icmp_response_v6_dgram(
             our_identifier,
             IcmpResponseType::DestUnreachable(icmp_code),
             None,
+            udp_id,
         ),
         _ => None,
     }
@@ -1113,6 +1442,7 @@ fn parse_icmp_error_payload_v6_dgram(
     our_identifier: u16,
     response_type: IcmpResponseType,
     mtu: Option<u16>,
+    udp_id: UdpIdEncoding,
 ) -> Option<ParsedResponse> {
     const IPV6_HEADER_LEN: usize = 40;
 
@@ -1130,8 +1460,9 @@ fn parse_icmp_error_payload_v6_dgram(
 
     let icmp_length = icmp_data[5];
     let original_ipv6_data = &icmp_data[8..];
-    let next_header = original_ipv6_data[6];
     let quoted_ttl = original_ipv6_data[7]; // Hop limit
+    let quoted_dscp = quoted_traffic_class(original_ipv6_data) >> 2;
+    let quoted_ecn = EcnCodepoint::from_tos(quoted_traffic_class(original_ipv6_data));
     // Extract original destination for multi-target disambiguation (bytes 24-39)
     let original_dest = Some(IpAddr::V6(std::net::Ipv6Addr::new(
         u16::from_be_bytes([original_ipv6_data[24], original_ipv6_data[25]]),
@@ -1143,9 +1474,18 @@ fn parse_icmp_error_payload_v6_dgram(
         u16::from_be_bytes([original_ipv6_data[36], original_ipv6_data[37]]),
         u16::from_be_bytes([original_ipv6_data[38], original_ipv6_data[39]]),
     )));
-    let original_payload = &original_ipv6_data[IPV6_HEADER_LEN..];
+    // Routers quote the packet as sent, so any extension headers we (or the
+    // kernel) added sit between the IPv6 header and the transport header
+    let (next_header, payload_offset) = walk_ipv6_header_chain(original_ipv6_data, true)?;
+    let original_payload = &original_ipv6_data[payload_offset..];
+    if original_payload.len() < 8 {
+        return None;
+    }
 
-    let mpls_labels = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
+    let IcmpExtensions {
+        mpls_labels,
+        interface,
+    } = parse_icmp_extensions_with_length(&icmp_data[8..], icmp_length);
 
     match next_header {
         IPPROTO_ICMPV6 => {
@@ -1162,9 +1502,13 @@ fn parse_icmp_error_payload_v6_dgram(
                     probe_id: ProbeId::from_sequence(sequence),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -1179,9 +1523,13 @@ fn parse_icmp_error_payload_v6_dgram(
                     probe_id: ProbeId::from_sequence(payload_seq),
                     response_type,
                     mpls_labels,
+                    interface,
                     src_port: None,
                     mtu,
                     quoted_ttl: Some(quoted_ttl),
+                    quoted_dscp: Some(quoted_dscp),
+                    quoted_ecn: Some(quoted_ecn),
+                    quoted_checksum: None,
                     original_dest,
                 });
             }
@@ -1199,28 +1547,35 @@ fn parse_icmp_error_payload_v6_dgram(
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_tcp_checksum(original_payload),
                 original_dest,
             })
         }
         IPPROTO_UDP => {
-            if original_payload.len() < 8 + 6 {
+            if original_payload.len() < 8 {
                 return None;
             }
             let src_port = u16::from_be_bytes([original_payload[0], original_payload[1]]);
-            let udp_payload = &original_payload[8..];
-            let probe_id = extract_probe_id_from_udp_payload(udp_payload)?;
+            let probe_id = extract_probe_id_from_udp(original_payload, udp_id)?;
 
             Some(ParsedResponse {
                 responder,
                 probe_id,
                 response_type,
                 mpls_labels,
+                interface,
                 src_port: Some(src_port),
                 mtu,
                 quoted_ttl: Some(quoted_ttl),
+                quoted_dscp: Some(quoted_dscp),
+                quoted_ecn: Some(quoted_ecn),
+                quoted_checksum: quoted_udp_checksum(original_payload),
                 original_dest,
             })
         }
@@ -1283,7 +1638,9 @@ mod tests {
     #[test]
     fn test_empty_packet_returns_none() {
         let responder = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
-        assert!(parse_icmp_response(&[], responder, 0x1234, false).is_none());
+        assert!(
+            parse_icmp_response(&[], responder, 0x1234, false, UdpIdEncoding::Payload).is_none()
+        );
     }
 
     #[test]
@@ -1291,7 +1648,10 @@ mod tests {
         let responder = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
         // Just an IP version nibble, nothing else
         let truncated = [0x45]; // IPv4, IHL=5
-        assert!(parse_icmp_response(&truncated, responder, 0x1234, false).is_none());
+        assert!(
+            parse_icmp_response(&truncated, responder, 0x1234, false, UdpIdEncoding::Payload)
+                .is_none()
+        );
     }
 
     #[test]
@@ -1299,7 +1659,10 @@ mod tests {
         let responder = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
         // IP version 3 doesn't exist
         let invalid = [0x30, 0x00, 0x00, 0x00];
-        assert!(parse_icmp_response(&invalid, responder, 0x1234, false).is_none());
+        assert!(
+            parse_icmp_response(&invalid, responder, 0x1234, false, UdpIdEncoding::Payload)
+                .is_none()
+        );
     }
 
     #[test]
@@ -1323,7 +1686,10 @@ mod tests {
         packet[26] = 0x00;
         packet[27] = 0x01;
 
-        assert!(parse_icmp_response(&packet, responder, 0x1234, false).is_none());
+        assert!(
+            parse_icmp_response(&packet, responder, 0x1234, false, UdpIdEncoding::Payload)
+                .is_none()
+        );
     }
 
     #[test]
@@ -1353,7 +1719,7 @@ mod tests {
         // Set valid ICMP checksum
         set_icmp_checksum(&mut packet[20..]);
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1382,6 +1748,7 @@ mod tests {
 
         // Original IP header (inside ICMP payload at offset 28)
         packet[28] = 0x45; // Version 4, IHL 5
+        packet[29] = 46 << 2 | 0b10; // TOS: DSCP EF, ECT(0)
         packet[37] = 1; // Protocol: ICMP
 
         // Original ICMP Echo Request (at offset 48)
@@ -1396,13 +1763,57 @@ mod tests {
         packet[54] = (seq >> 8) as u8;
         packet[55] = (seq & 0xFF) as u8;
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
         assert_eq!(parsed.probe_id.ttl, 5);
         assert_eq!(parsed.probe_id.seq, 3);
         assert_eq!(parsed.response_type, IcmpResponseType::TimeExceeded(0));
+        assert_eq!(parsed.quoted_dscp, Some(46));
+        assert_eq!(parsed.quoted_ecn, Some(EcnCodepoint::Ect0));
+    }
+
+    #[test]
+    fn test_parse_redirect() {
+        let router = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
+
+        // Outer IPv4 (20) + Redirect header (8) + quoted IPv4 (20) + 8 bytes
+        let mut packet = vec![0u8; 56];
+        packet[0] = 0x45;
+        packet[9] = 1;
+        packet[20] = 5; // Type: Redirect
+        packet[21] = 1; // Code: for host
+        packet[24..28].copy_from_slice(&[192, 168, 1, 254]);
+        packet[28] = 0x45;
+        packet[37] = 1;
+        packet[44..48].copy_from_slice(&[93, 184, 216, 34]);
+
+        let redirect = parse_redirect(&packet, router, false).unwrap();
+        assert_eq!(redirect.router, router);
+        assert_eq!(redirect.gateway.to_string(), "192.168.1.254");
+        assert_eq!(redirect.dest.to_string(), "93.184.216.34");
+        // Same message without the IP header (DGRAM socket)
+        assert_eq!(parse_redirect(&packet[20..], router, true), Some(redirect));
+        // Not a redirect, or cut short
+        assert_eq!(parse_redirect(&packet[20..40], router, true), None);
+        packet[20] = 11;
+        assert_eq!(parse_redirect(&packet, router, false), None);
+
+        // ICMPv6: type, code, checksum, reserved, target, destination
+        let router = "fe80::1".parse().unwrap();
+        let mut packet = vec![0u8; 40];
+        packet[0] = 137;
+        packet[8..24].copy_from_slice(&"fe80::2".parse::<std::net::Ipv6Addr>().unwrap().octets());
+        packet[24..40].copy_from_slice(
+            &"2001:db8::1"
+                .parse::<std::net::Ipv6Addr>()
+                .unwrap()
+                .octets(),
+        );
+        let redirect = parse_redirect(&packet, router, false).unwrap();
+        assert_eq!(redirect.gateway.to_string(), "fe80::2");
+        assert_eq!(redirect.dest.to_string(), "2001:db8::1");
     }
 
     #[test]
@@ -1432,7 +1843,7 @@ mod tests {
         // Set valid ICMP checksum (ICMP starts at offset 24)
         set_icmp_checksum(&mut packet[24..]);
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1464,7 +1875,7 @@ mod tests {
         packet[6] = (seq >> 8) as u8;
         packet[7] = (seq & 0xFF) as u8;
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1490,7 +1901,8 @@ mod tests {
         // Unused (bytes 4-7)
 
         // Original IPv6 header (inside ICMPv6 payload at offset 8)
-        packet[8] = 0x60; // Version 6
+        packet[8] = 0x62; // Version 6, Traffic Class high nibble
+        packet[9] = 0x80; // Traffic Class low nibble: 0x28 = DSCP AF11 (10)
         packet[14] = 58; // Next Header: ICMPv6
 
         // Original ICMPv6 Echo Request (at offset 48 = 8 + 40)
@@ -1505,19 +1917,100 @@ mod tests {
         packet[54] = (seq >> 8) as u8;
         packet[55] = (seq & 0xFF) as u8;
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
         assert_eq!(parsed.probe_id.ttl, 6);
         assert_eq!(parsed.probe_id.seq, 2);
         assert_eq!(parsed.response_type, IcmpResponseType::TimeExceeded(0));
+        assert_eq!(parsed.quoted_dscp, Some(10));
+    }
+
+    // Note: Linux strips the outer IPv6 header before delivering to raw ICMPv6
+    // sockets, so extension headers only matter inside the quoted packet.
+
+    /// ICMPv6 Time Exceeded quoting an IPv6 packet with `first_nh` in the
+    /// fixed header, then the extension headers and upper-layer bytes as given
+    fn time_exceeded_v6_with_chain(first_nh: u8, exts: &[&[u8]], upper: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0u8; 8 + 40];
+        packet[0] = 3; // Type: Time Exceeded
+        packet[8] = 0x60; // Version 6
+        packet[8 + 6] = first_nh;
+        packet[8 + 7] = 1; // Hop limit as quoted
+        for ext in exts {
+            packet.extend_from_slice(ext);
+        }
+        packet.extend_from_slice(upper);
+        packet
+    }
+
+    #[test]
+    fn test_quoted_ipv6_extension_headers_walked() {
+        let responder = IpAddr::V6(std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let probe_id = ProbeId::new(7, 3);
+        let seq = probe_id.to_sequence();
+
+        // Hop-by-Hop (8 bytes) -> Destination Options (16 bytes) -> ICMPv6
+        let hbh = [60u8, 0, 1, 4, 0, 0, 0, 0];
+        let mut dest_opts = [0u8; 16];
+        dest_opts[0] = 58;
+        dest_opts[1] = 1; // (1 + 1) * 8 = 16 bytes
+        let echo = [128u8, 0, 0, 0, 0xAB, 0xCD, (seq >> 8) as u8, seq as u8];
+        let packet = time_exceeded_v6_with_chain(0, &[&hbh, &dest_opts], &echo);
+
+        for is_dgram in [false, true] {
+            let parsed =
+                parse_icmp_response(&packet, responder, 0xABCD, is_dgram, UdpIdEncoding::Payload)
+                    .unwrap();
+            assert_eq!(parsed.probe_id, probe_id);
+            assert_eq!(parsed.quoted_ttl, Some(1));
+        }
+
+        // Routing header (8 bytes) -> UDP probe
+        let routing = [17u8, 0, 0, 0, 0, 0, 0, 0];
+        let mut udp = vec![0x82, 0x9A, 0x82, 0x9B, 0, 22, 0, 0];
+        udp.extend_from_slice(&crate::probe::udp::build_udp_payload(probe_id));
+        let packet = time_exceeded_v6_with_chain(43, &[&routing], &udp);
+
+        let parsed =
+            parse_icmp_response(&packet, responder, 0xABCD, true, UdpIdEncoding::Payload).unwrap();
+        assert_eq!(parsed.probe_id, probe_id);
+        assert_eq!(parsed.src_port, Some(0x829A));
+    }
+
+    #[test]
+    fn test_quoted_ipv6_fragments() {
+        let responder = IpAddr::V6(std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let probe_id = ProbeId::new(5, 1);
+        let seq = probe_id.to_sequence();
+        let echo = [128u8, 0, 0, 0, 0xAB, 0xCD, (seq >> 8) as u8, seq as u8];
+
+        // First fragment still carries the transport header
+        let first = [58u8, 0, 0, 1, 0, 0, 0, 9];
+        let packet = time_exceeded_v6_with_chain(44, &[&first], &echo);
+        let parsed =
+            parse_icmp_response(&packet, responder, 0xABCD, true, UdpIdEncoding::Payload).unwrap();
+        assert_eq!(parsed.probe_id, probe_id);
+
+        // Later fragments do not
+        let later = [58u8, 0, 0x05, 0x00, 0, 0, 0, 9];
+        let packet = time_exceeded_v6_with_chain(44, &[&later], &echo);
+        assert!(
+            parse_icmp_response(&packet, responder, 0xABCD, true, UdpIdEncoding::Payload).is_none()
+        );
     }
 
-    // Note: IPv6 extension header tests removed.
-    // Linux kernel strips outer IPv6 header before delivering to raw ICMPv6 sockets,
-    // so we never see extension headers in the outer packet. Extension header parsing
-    // code (parse_icmp_response_v6, skip_ipv6_extension_headers) is now unused.
+    #[test]
+    fn test_quoted_ipv6_truncated_extension_header() {
+        let responder = IpAddr::V6(std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        // Destination Options claims 64 bytes but the quote ends after 8
+        let dest_opts = [58u8, 7, 0, 0, 0, 0, 0, 0];
+        let packet = time_exceeded_v6_with_chain(60, &[&dest_opts], &[]);
+        assert!(
+            parse_icmp_response(&packet, responder, 0xABCD, true, UdpIdEncoding::Payload).is_none()
+        );
+    }
 
     #[test]
     fn test_ipv6_fragment_header_rejected() {
@@ -1543,7 +2036,7 @@ mod tests {
         packet[53] = 0x34;
 
         // Fragments are rejected (we don't handle reassembly)
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_none());
     }
 
@@ -1572,7 +2065,7 @@ mod tests {
         packet[27] = (seq & 0xFF) as u8;
 
         // Should be rejected due to invalid checksum
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_none());
     }
 
@@ -1605,7 +2098,7 @@ mod tests {
         packet[54] = (seq >> 8) as u8;
         packet[55] = (seq & 0xFF) as u8;
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1617,6 +2110,35 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_udp_checksum_id_from_header_only_quote() {
+        let responder = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
+
+        // Time Exceeded quoting the IP header and only the 8-byte UDP header
+        let mut packet = vec![0u8; 56];
+        packet[0] = 0x45;
+        packet[9] = 1;
+        packet[20] = 11;
+        packet[28] = 0x45;
+        packet[37] = 17;
+        packet[48..50].copy_from_slice(&50000u16.to_be_bytes());
+        packet[50..52].copy_from_slice(&33441u16.to_be_bytes());
+        let probe_id = ProbeId::new(7, 200);
+        packet[54..56].copy_from_slice(&probe_id.to_sequence().to_be_bytes());
+
+        let parsed =
+            parse_icmp_response(&packet, responder, 0xDEAD, false, UdpIdEncoding::Checksum)
+                .unwrap();
+        assert_eq!(parsed.probe_id, probe_id);
+        assert_eq!(parsed.src_port, Some(50000));
+        assert_eq!(parsed.quoted_checksum, Some(probe_id.to_sequence()));
+        // The payload encoding can't tell which probe it was
+        assert!(
+            parse_icmp_response(&packet, responder, 0xDEAD, false, UdpIdEncoding::Payload)
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_mpls_label_parsing() {
         // Test MplsLabel::from_bytes parsing
@@ -1732,7 +2254,7 @@ mod tests {
         let label_bytes = label_word.to_be_bytes();
         packet[164..168].copy_from_slice(&label_bytes);
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1800,7 +2322,7 @@ mod tests {
         let label_bytes = label_word.to_be_bytes();
         packet[84..88].copy_from_slice(&label_bytes);
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1815,6 +2337,70 @@ mod tests {
         assert_eq!(labels[0].ttl, 64);
     }
 
+    #[test]
+    fn test_time_exceeded_with_interface_info() {
+        let responder = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let mut packet = vec![0u8; 120];
+        packet[0] = 0x45;
+        packet[9] = 1; // ICMP
+        packet[20] = 11; // Time Exceeded
+        packet[25] = 12; // Length = 12 (48 bytes of original datagram)
+        packet[28] = 0x45;
+        packet[37] = 1;
+        packet[48] = 8; // Echo Request
+        packet[52] = 0x12;
+        packet[53] = 0x34;
+        let seq = ProbeId::new(4, 1).to_sequence();
+        packet[54..56].copy_from_slice(&seq.to_be_bytes());
+
+        packet[76] = 0x20; // Extension version 2
+        // MPLS object first, then the Interface Information object
+        packet[80..84].copy_from_slice(&[0x00, 0x08, 0x01, 0x01]);
+        packet[84..88].copy_from_slice(&((24000u32 << 12) | (1 << 8) | 1).to_be_bytes());
+        // Incoming interface with ifIndex, IPv4 address, name and MTU
+        packet[88..92].copy_from_slice(&[0x00, 32, INTERFACE_INFO_CLASS, 0x0F]);
+        packet[92..96].copy_from_slice(&512u32.to_be_bytes());
+        packet[96..100].copy_from_slice(&[0x00, 0x01, 0x00, 0x00]); // AFI IPv4
+        packet[100..104].copy_from_slice(&[192, 0, 2, 7]);
+        packet[104] = 12; // Name sub-object length, NUL padded
+        packet[105..113].copy_from_slice(b"xe-0/0/1");
+        packet[116..120].copy_from_slice(&9000u32.to_be_bytes());
+
+        let parsed =
+            parse_icmp_response(&packet, responder, 0x1234, false, UdpIdEncoding::Payload).unwrap();
+        assert_eq!(parsed.mpls_labels.map(|l| l[0].label), Some(24000));
+        let interface = parsed.interface.unwrap();
+        assert_eq!(
+            interface,
+            IncomingInterface {
+                index: Some(512),
+                address: Some("192.0.2.7".parse().unwrap()),
+                name: Some("xe-0/0/1".to_string()),
+                mtu: Some(9000),
+            }
+        );
+        assert_eq!(
+            interface.describe(),
+            "xe-0/0/1 (ifIndex 512, 192.0.2.7, MTU 9000)"
+        );
+    }
+
+    #[test]
+    fn test_interface_info_roles_and_truncation() {
+        // Name only
+        let mut body = vec![8];
+        body.extend_from_slice(b"et-1\0\0\0");
+        let interface = parse_interface_info(HAS_NAME, &body).unwrap();
+        assert_eq!(interface.name.as_deref(), Some("et-1"));
+        assert_eq!(interface.describe(), "et-1");
+
+        // The outgoing interface (role 2) isn't the one the probe came in on
+        assert!(parse_interface_info(0x80 | HAS_NAME, &body).is_none());
+        // Flags promising more than the object holds
+        assert!(parse_interface_info(HAS_IFINDEX | HAS_NAME, &body[..6]).is_none());
+        assert!(parse_interface_info(HAS_MTU, &[0, 0]).is_none());
+    }
+
     #[test]
     fn test_time_exceeded_without_extension() {
         // Same test as test_parse_time_exceeded_v4 but verify no MPLS labels
@@ -1838,7 +2424,7 @@ mod tests {
         packet[54] = (seq >> 8) as u8;
         packet[55] = (seq & 0xFF) as u8;
 
-        let result = parse_icmp_response(&packet, responder, our_id, false);
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1881,7 +2467,7 @@ mod tests {
         // Set valid ICMP checksum
         set_icmp_checksum(&mut packet);
 
-        let result = parse_icmp_response(&packet, responder, our_id, true); // is_dgram=true
+        let result = parse_icmp_response(&packet, responder, our_id, true, UdpIdEncoding::Payload); // is_dgram=true
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1930,7 +2516,7 @@ mod tests {
         packet[38] = (seq >> 8) as u8;
         packet[39] = (seq & 0xFF) as u8;
 
-        let result = parse_icmp_response(&packet, responder, our_id, true); // is_dgram=true
+        let result = parse_icmp_response(&packet, responder, our_id, true, UdpIdEncoding::Payload); // is_dgram=true
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -1969,7 +2555,7 @@ mod tests {
 
         set_icmp_checksum(&mut packet);
 
-        let result = parse_icmp_response(&packet, responder, our_id, true);
+        let result = parse_icmp_response(&packet, responder, our_id, true, UdpIdEncoding::Payload);
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -2011,7 +2597,7 @@ mod tests {
         // Set valid ICMP checksum (ICMP starts at offset 20)
         set_icmp_checksum(&mut packet[20..]);
 
-        let result = parse_icmp_response(&packet, responder, our_id, false); // is_dgram=false (RAW)
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload); // is_dgram=false (RAW)
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -2067,7 +2653,7 @@ mod tests {
         // Set ICMP checksum for outer Time Exceeded (offset 20, length 44)
         set_icmp_checksum(&mut packet[20..]);
 
-        let result = parse_icmp_response(&packet, responder, our_id, false); // is_dgram=false (RAW)
+        let result = parse_icmp_response(&packet, responder, our_id, false, UdpIdEncoding::Payload); // is_dgram=false (RAW)
         assert!(result.is_some());
 
         let parsed = result.unwrap();
@@ -2079,6 +2665,45 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_echo_payload_nonce() {
+        use crate::probe::build_echo_request;
+        let v4 = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
+        let request = build_echo_request(0x1234, 0x0503, 0xdead_beef, 32, false, None);
+
+        // Echo Reply on a DGRAM socket: no IP header
+        let mut reply = request.clone();
+        reply[0] = 0;
+        assert_eq!(echo_payload_nonce(&reply, v4, true), Some(0xdead_beef));
+
+        // Time Exceeded on a RAW socket quoting the request
+        let mut packet = vec![0u8; 48];
+        packet[0] = 0x45;
+        packet[9] = 1;
+        packet[20] = 11;
+        packet[28] = 0x45;
+        packet[37] = 1;
+        packet.extend_from_slice(&request);
+        assert_eq!(echo_payload_nonce(&packet, v4, false), Some(0xdead_beef));
+
+        // RFC 792 minimum quote stops before the nonce
+        assert_eq!(echo_payload_nonce(&packet[..56], v4, false), None);
+
+        // Quoted UDP probe
+        packet[37] = 17;
+        assert_eq!(echo_payload_nonce(&packet, v4, false), None);
+
+        // ICMPv6 Echo Reply
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        let addrs = Some((
+            "2001:db8::2".parse().unwrap(),
+            "2001:db8::1".parse().unwrap(),
+        ));
+        let mut reply = build_echo_request(0x1234, 0x0503, 0xfeed, 32, true, addrs);
+        reply[0] = ICMPV6_ECHO_REPLY;
+        assert_eq!(echo_payload_nonce(&reply, v6, false), Some(0xfeed));
+    }
+
     // ========================================================================
     // Property-based tests (proptest)
     // ========================================================================
@@ -2137,7 +2762,7 @@ mod tests {
         #[test]
         fn proptest_parse_icmp_no_panic(data in prop::collection::vec(0u8..=255, 0..1500)) {
             let responder = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
-            let _ = parse_icmp_response(&data, responder, 0x1234, false);
+            let _ = parse_icmp_response(&data, responder, 0x1234, false, UdpIdEncoding::Payload);
         }
 
         /// Packets with random IP version nibbles should not panic
@@ -2149,7 +2774,7 @@ mod tests {
             }
 
             let responder = IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8));
-            let _ = parse_icmp_response(&data, responder, 0x5678, false);
+            let _ = parse_icmp_response(&data, responder, 0x5678, false, UdpIdEncoding::Payload);
         }
 
         /// IPv4 packets with various IHL values should not panic
@@ -2173,7 +2798,7 @@ mod tests {
             }
 
             let responder = IpAddr::V4(std::net::Ipv4Addr::new(172, 16, 0, 1));
-            let _ = parse_icmp_response(&data, responder, 0x9999, false);
+            let _ = parse_icmp_response(&data, responder, 0x9999, false, UdpIdEncoding::Payload);
         }
 
         /// ICMP checksum validation should handle all byte patterns
@@ -2208,7 +2833,7 @@ mod tests {
         fn proptest_short_packets_return_none(size in 0usize..20) {
             let data = vec![0x45u8; size]; // IPv4 version nibble but too short
             let responder = IpAddr::V4(std::net::Ipv4Addr::new(1, 1, 1, 1));
-            prop_assert!(parse_icmp_response(&data, responder, 0x1234, false).is_none());
+            prop_assert!(parse_icmp_response(&data, responder, 0x1234, false, UdpIdEncoding::Payload).is_none());
         }
     }
 }
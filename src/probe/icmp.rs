@@ -9,6 +9,9 @@ pub const ICMP_HEADER_SIZE: usize = 8;
 pub const DEFAULT_PAYLOAD_SIZE: usize = 56;
 /// Minimum payload size (4 bytes ProbeId + 4 bytes timestamp)
 pub const MIN_PAYLOAD_SIZE: usize = 8;
+/// Offset of the session nonce in the echo payload (present when the
+/// payload has room for it)
+pub const NONCE_OFFSET: usize = 8;
 
 /// Calculate ICMPv6 checksum including IPv6 pseudo-header.
 ///
@@ -73,10 +76,13 @@ pub fn get_identifier() -> u16 {
 /// - Bytes 0-1: identifier (backup for kernel override on macOS DGRAM sockets)
 /// - Bytes 2-3: sequence (backup for kernel override)
 /// - Bytes 4-7: timestamp (lower 32 bits)
-/// - Bytes 8+: pattern fill
+/// - Bytes 8-11: session nonce, telling our replies from those of another
+///   process using the same identifier (payloads of 12+ bytes)
+/// - Bytes 12+: pattern fill
 pub fn build_echo_request(
     identifier: u16,
     sequence: u16,
+    nonce: u32,
     payload_size: usize,
     ipv6: bool,
     ipv6_addrs: Option<(Ipv6Addr, Ipv6Addr)>,
@@ -121,6 +127,9 @@ pub fn build_echo_request(
     for (i, byte) in payload[8..].iter_mut().enumerate() {
         *byte = (i & 0xFF) as u8;
     }
+    if let Some(slot) = payload.get_mut(NONCE_OFFSET..NONCE_OFFSET + 4) {
+        slot.copy_from_slice(&nonce.to_be_bytes());
+    }
 
     // Calculate checksum
     if ipv6 {
@@ -147,7 +156,7 @@ mod tests {
 
     #[test]
     fn test_build_echo_request() {
-        let packet = build_echo_request(1234, 5678, DEFAULT_PAYLOAD_SIZE, false, None);
+        let packet = build_echo_request(1234, 5678, 0xfeed, DEFAULT_PAYLOAD_SIZE, false, None);
         assert_eq!(packet.len(), ICMP_HEADER_SIZE + DEFAULT_PAYLOAD_SIZE);
         assert_eq!(packet[0], 8); // Echo Request type
         assert_eq!(packet[1], 0); // Code
@@ -158,7 +167,14 @@ mod tests {
         use std::str::FromStr;
         let src = Ipv6Addr::from_str("2001:db8::1").unwrap();
         let dest = Ipv6Addr::from_str("2001:db8::2").unwrap();
-        let packet = build_echo_request(1234, 5678, DEFAULT_PAYLOAD_SIZE, true, Some((src, dest)));
+        let packet = build_echo_request(
+            1234,
+            5678,
+            0xfeed,
+            DEFAULT_PAYLOAD_SIZE,
+            true,
+            Some((src, dest)),
+        );
         assert_eq!(packet.len(), ICMP_HEADER_SIZE + DEFAULT_PAYLOAD_SIZE);
         assert_eq!(packet[0], 128); // ICMPv6 Echo Request type
         assert_eq!(packet[1], 0); // Code
@@ -169,7 +185,14 @@ mod tests {
         use std::str::FromStr;
         let src = Ipv6Addr::from_str("2001:db8::1").unwrap();
         let dest = Ipv6Addr::from_str("2001:db8::2").unwrap();
-        let packet = build_echo_request(1234, 5678, DEFAULT_PAYLOAD_SIZE, true, Some((src, dest)));
+        let packet = build_echo_request(
+            1234,
+            5678,
+            0xfeed,
+            DEFAULT_PAYLOAD_SIZE,
+            true,
+            Some((src, dest)),
+        );
         assert_eq!(packet.len(), ICMP_HEADER_SIZE + DEFAULT_PAYLOAD_SIZE);
         assert_eq!(packet[0], 128); // ICMPv6 Echo Request type
         assert_eq!(packet[1], 0); // Code
@@ -194,11 +217,20 @@ mod tests {
     #[test]
     fn test_build_echo_request_custom_size() {
         // Test larger payload
-        let packet = build_echo_request(1234, 5678, 1400, false, None);
+        let packet = build_echo_request(1234, 5678, 0xfeed, 1400, false, None);
         assert_eq!(packet.len(), ICMP_HEADER_SIZE + 1400);
 
-        // Test minimum payload
-        let packet = build_echo_request(1234, 5678, 0, false, None);
+        // Test minimum payload: no room for the nonce
+        let packet = build_echo_request(1234, 5678, 0xfeed, 0, false, None);
         assert_eq!(packet.len(), ICMP_HEADER_SIZE + MIN_PAYLOAD_SIZE);
     }
+
+    #[test]
+    fn test_build_echo_request_nonce() {
+        let packet = build_echo_request(1234, 5678, 0xdead_beef, DEFAULT_PAYLOAD_SIZE, false, None);
+        let at = ICMP_HEADER_SIZE + NONCE_OFFSET;
+        assert_eq!(packet[at..at + 4], 0xdead_beef_u32.to_be_bytes());
+        // Pattern fill continues after it
+        assert_eq!(packet[at + 4], 4);
+    }
 }
@@ -1,3 +1,4 @@
+pub mod corpus;
 pub mod correlate;
 pub mod icmp;
 pub mod interface;
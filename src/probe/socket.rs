@@ -3,6 +3,8 @@ use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
+use crate::error::TtlError;
+
 /// Socket capability level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SocketCapability {
@@ -26,7 +28,7 @@ pub struct SocketInfo {
 /// On macOS, requires RAW socket for receiving ICMP Time Exceeded messages
 /// (DGRAM sockets only receive Echo Reply, not error messages from routers)
 #[cfg(target_os = "macos")]
-pub fn check_permissions() -> Result<SocketCapability> {
+pub fn check_permissions() -> Result<SocketCapability, TtlError> {
     // On macOS:
     // - Send socket uses DGRAM (supports IP_TTL for per-probe TTL control)
     // - Receive socket must use RAW (DGRAM can't receive Time Exceeded from routers)
@@ -35,11 +37,12 @@ pub fn check_permissions() -> Result<SocketCapability> {
 
     // Check if we can create RAW IPv4 socket (needed for receiving)
     if create_raw_icmp_socket(false).is_err() {
-        return Err(anyhow!(
+        return Err(TtlError::Permission(
             "Insufficient permissions for ICMP sockets.\n\n\
              On macOS, raw sockets are required to receive ICMP Time Exceeded\n\
              messages from intermediate routers.\n\n\
              Fix: Run with sudo: sudo ttl <target>"
+                .into(),
         ));
     }
 
@@ -50,9 +53,10 @@ pub fn check_permissions() -> Result<SocketCapability> {
 
     // Also verify DGRAM works for sending (should always work if RAW works)
     if create_dgram_icmp_socket().is_err() {
-        return Err(anyhow!(
+        return Err(TtlError::Permission(
             "Failed to create ICMP socket for sending.\n\n\
              Fix: Run with sudo: sudo ttl <target>"
+                .into(),
         ));
     }
 
@@ -68,7 +72,7 @@ pub fn check_permissions() -> Result<SocketCapability> {
 /// Check socket permissions and return capability level
 /// On Linux, requires RAW sockets for traceroute functionality
 #[cfg(not(target_os = "macos"))]
-pub fn check_permissions() -> Result<SocketCapability> {
+pub fn check_permissions() -> Result<SocketCapability, TtlError> {
     // RAW sockets required - DGRAM can't receive Time Exceeded from intermediate routers
     if create_raw_icmp_socket(false).is_ok() {
         // Also check IPv6 RAW - warn if unavailable
@@ -82,14 +86,14 @@ pub fn check_permissions() -> Result<SocketCapability> {
         .map(|p| p.display().to_string())
         .unwrap_or_else(|_| "ttl".to_string());
 
-    Err(anyhow!(
+    Err(TtlError::Permission(format!(
         "Insufficient permissions for raw sockets.\n\n\
          Fix (one-time):\n\
          \u{2022} sudo setcap cap_net_raw+ep {}\n\n\
          Or run with sudo:\n\
          \u{2022} sudo ttl <target>",
         binary_path
-    ))
+    )))
 }
 
 /// Create a raw ICMP socket
@@ -230,10 +234,10 @@ pub fn set_ttl(socket: &Socket, ttl: u8, ipv6: bool) -> Result<()> {
     Ok(())
 }
 
-/// Set DSCP/ToS value on socket for QoS testing
-/// DSCP occupies upper 6 bits of TOS byte, so shift left by 2
-pub fn set_dscp(socket: &Socket, dscp: u8, ipv6: bool) -> Result<()> {
-    let tos = (dscp as u32) << 2;
+/// Set the ToS / traffic class byte on socket for QoS and ECN testing
+/// DSCP occupies the upper 6 bits, ECN the lower 2 (see `Config::tos`)
+pub fn set_tos(socket: &Socket, tos: u8, ipv6: bool) -> Result<()> {
+    let tos = tos as u32;
     if ipv6 {
         socket.set_tclass_v6(tos)?;
     } else {
@@ -250,6 +254,29 @@ pub fn bind_to_source_ip(socket: &Socket, ip: IpAddr) -> Result<()> {
     Ok(())
 }
 
+/// Port range the OS hands out to sockets bound to port 0.
+///
+/// Linux publishes it in `ip_local_port_range`; elsewhere the IANA
+/// dynamic range (49152-65535) is assumed.
+pub fn ephemeral_port_range() -> (u16, u16) {
+    #[cfg(target_os = "linux")]
+    if let Ok(text) = std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range")
+        && let Some(range) = parse_port_pair(&text)
+    {
+        return range;
+    }
+    (49152, 65535)
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_port_pair(text: &str) -> Option<(u16, u16)> {
+    let mut ports = text.split_whitespace().map(|p| p.parse::<u16>());
+    match (ports.next(), ports.next()) {
+        (Some(Ok(first)), Some(Ok(last))) if first <= last => Some((first, last)),
+        _ => None,
+    }
+}
+
 /// Set Don't Fragment flag for Path MTU Discovery
 /// - IPv4: Sets IP_MTU_DISCOVER = IP_PMTUDISC_DO (always set DF bit)
 /// - IPv6: Sets IPV6_DONTFRAG = 1 (prevent source fragmentation)
@@ -356,6 +383,9 @@ pub struct RecvResult {
     pub source: IpAddr,
     /// TTL/hop-limit from the IP header of the response packet
     pub response_ttl: Option<u8>,
+    /// Packets the kernel has dropped on this socket so far because the
+    /// receive buffer was full (Linux, after `enable_drop_counter`)
+    pub dropped: Option<u32>,
 }
 
 /// Enable IP_RECVTTL/IPV6_RECVHOPLIMIT socket option
@@ -408,8 +438,8 @@ pub fn recv_icmp_with_ttl(socket: &Socket, buffer: &mut [u8], ipv6: bool) -> Res
         iov_len: buffer.len(),
     };
 
-    // Allocate control message buffer (for TTL)
-    let mut cmsg_buf = [0u8; 64];
+    // Allocate control message buffer (for TTL and the drop counter)
+    let mut cmsg_buf = [0u8; 128];
 
     // Source address storage
     let mut src_storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
@@ -435,17 +465,21 @@ pub fn recv_icmp_with_ttl(socket: &Socket, buffer: &mut [u8], ipv6: bool) -> Res
     let source = parse_sockaddr_storage(&src_storage)?;
 
     // Check for MSG_CTRUNC - control message truncated, TTL may be unreliable
-    let response_ttl = if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+    let (response_ttl, dropped) = if msg.msg_flags & libc::MSG_CTRUNC != 0 {
         // Control buffer was too small, TTL extraction may fail
-        None
+        (None, None)
     } else {
-        extract_ttl_from_cmsg(&msg, ipv6)
+        (
+            extract_ttl_from_cmsg(&msg, ipv6),
+            extract_drops_from_cmsg(&msg),
+        )
     };
 
     Ok(RecvResult {
         len: len as usize,
         source,
         response_ttl,
+        dropped,
     })
 }
 
@@ -492,6 +526,81 @@ fn extract_ttl_from_cmsg(msg: &libc::msghdr, ipv6: bool) -> Option<u8> {
     None
 }
 
+/// Extract the SO_RXQ_OVFL drop counter from control messages
+#[cfg(target_os = "linux")]
+fn extract_drops_from_cmsg(msg: &libc::msghdr) -> Option<u32> {
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+        while !cmsg.is_null() {
+            let hdr = &*cmsg;
+            if hdr.cmsg_level == libc::SOL_SOCKET && hdr.cmsg_type == libc::SO_RXQ_OVFL {
+                let data_ptr = libc::CMSG_DATA(cmsg);
+                return Some(std::ptr::read_unaligned(data_ptr as *const u32));
+            }
+            cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+        }
+    }
+    None
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn extract_drops_from_cmsg(_msg: &libc::msghdr) -> Option<u32> {
+    None
+}
+
+/// Ask the kernel to report receive-buffer overflow drops with each packet
+/// (SO_RXQ_OVFL). Only Linux has a per-socket counter.
+#[cfg(target_os = "linux")]
+pub fn enable_drop_counter(socket: &Socket) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let val: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RXQ_OVFL,
+            &val as *const _ as *const libc::c_void,
+            std::mem::size_of_val(&val) as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enable_drop_counter(_socket: &Socket) -> Result<()> {
+    Err(anyhow!("kernel drop counters are only available on Linux"))
+}
+
+/// Apply requested buffer sizes and return what the kernel actually granted
+/// (Linux doubles the request and caps it at net.core.rmem_max/wmem_max)
+pub fn apply_buffer_sizes(
+    socket: &Socket,
+    recv: Option<usize>,
+    send: Option<usize>,
+) -> Result<SocketBuffers> {
+    if let Some(size) = recv {
+        socket.set_recv_buffer_size(size)?;
+    }
+    if let Some(size) = send {
+        socket.set_send_buffer_size(size)?;
+    }
+    Ok(SocketBuffers {
+        recv: socket.recv_buffer_size()?,
+        send: socket.send_buffer_size()?,
+    })
+}
+
+/// Kernel socket buffer sizes in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketBuffers {
+    pub recv: usize,
+    pub send: usize,
+}
+
 /// Parse sockaddr_storage to IpAddr
 #[cfg(unix)]
 fn parse_sockaddr_storage(storage: &libc::sockaddr_storage) -> Result<IpAddr> {
@@ -564,4 +673,13 @@ mod tests {
         assert_eq!(decoded.ttl, 15);
         assert_eq!(decoded.seq, 42);
     }
+
+    #[test]
+    fn test_parse_port_pair() {
+        use super::parse_port_pair;
+
+        assert_eq!(parse_port_pair("32768\t60999\n"), Some((32768, 60999)));
+        assert_eq!(parse_port_pair("60999 32768"), None);
+        assert_eq!(parse_port_pair("32768"), None);
+    }
 }
@@ -19,6 +19,13 @@ const TCP_SRC_PORT: u16 = 50000;
 
 /// TCP flags
 const TCP_FLAG_SYN: u8 = 0x02;
+const TCP_FLAG_RST: u8 = 0x04;
+const TCP_FLAG_ACK: u8 = 0x10;
+
+/// TCP option kinds
+const TCP_OPT_END: u8 = 0;
+const TCP_OPT_NOP: u8 = 1;
+const TCP_OPT_MSS: u8 = 2;
 
 /// Minimum TCP header size
 pub const TCP_HEADER_SIZE: usize = 20;
@@ -196,6 +203,71 @@ pub fn extract_probe_id_from_tcp(tcp_header: &[u8]) -> Option<ProbeId> {
     Some(ProbeId::from_sequence(probe_seq))
 }
 
+/// A destination's SYN-ACK to one of our probes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SynAck {
+    /// Our probe's source port (the flow)
+    pub dst_port: u16,
+    pub probe_id: ProbeId,
+    /// Maximum segment size option, if present
+    pub mss: Option<u16>,
+}
+
+/// Parse a SYN-ACK answering one of our SYN probes, read from the raw TCP
+/// socket (IPv4 raw sockets include the IP header, IPv6 ones don't).
+/// Our sequence numbers have zero low bits, so the ack (sequence + 1)
+/// must end in 1.
+pub fn parse_syn_ack(packet: &[u8], ipv6: bool) -> Option<SynAck> {
+    let segment = if ipv6 {
+        packet
+    } else {
+        let ihl = ((*packet.first()? & 0x0F) as usize) * 4;
+        if packet.first()? >> 4 != 4 || ihl < 20 || *packet.get(9)? != IPPROTO_TCP {
+            return None;
+        }
+        packet.get(ihl..)?
+    };
+    if segment.len() < TCP_HEADER_SIZE {
+        return None;
+    }
+    let flags = segment[13];
+    if flags & (TCP_FLAG_SYN | TCP_FLAG_ACK | TCP_FLAG_RST) != TCP_FLAG_SYN | TCP_FLAG_ACK {
+        return None;
+    }
+    let ack = u32::from_be_bytes([segment[8], segment[9], segment[10], segment[11]]);
+    if ack & 0xFFFF != 1 {
+        return None;
+    }
+    let header_len = ((segment[12] >> 4) as usize) * 4;
+    let options = segment.get(TCP_HEADER_SIZE..header_len).unwrap_or_default();
+    Some(SynAck {
+        dst_port: u16::from_be_bytes([segment[2], segment[3]]),
+        probe_id: ProbeId::from_sequence((ack >> 16) as u16),
+        mss: parse_mss_option(options),
+    })
+}
+
+/// Find the MSS in a TCP options block
+fn parse_mss_option(mut options: &[u8]) -> Option<u16> {
+    while let Some(&kind) = options.first() {
+        match kind {
+            TCP_OPT_END => return None,
+            TCP_OPT_NOP => options = &options[1..],
+            _ => {
+                let len = *options.get(1)? as usize;
+                if len < 2 || len > options.len() {
+                    return None;
+                }
+                if kind == TCP_OPT_MSS && len == 4 {
+                    return Some(u16::from_be_bytes([options[2], options[3]]));
+                }
+                options = &options[len..];
+            }
+        }
+    }
+    None
+}
+
 /// Get the source IP address for checksum calculation
 /// Uses UDP connect trick to determine the local IP that routes to target
 pub fn get_local_addr(target: IpAddr) -> IpAddr {
@@ -305,4 +377,38 @@ mod tests {
         let checksum = u16::from_be_bytes([packet[16], packet[17]]);
         assert_ne!(checksum, 0);
     }
+
+    #[test]
+    fn test_parse_syn_ack() {
+        let probe_id = ProbeId::new(12, 7);
+        let ack = ((probe_id.to_sequence() as u32) << 16) + 1;
+        // SYN-ACK from port 443 to our flow port, options NOP, NOP, MSS 1380
+        let mut segment = vec![0u8; 28];
+        segment[0..2].copy_from_slice(&443u16.to_be_bytes());
+        segment[2..4].copy_from_slice(&50001u16.to_be_bytes());
+        segment[8..12].copy_from_slice(&ack.to_be_bytes());
+        segment[12] = 0x70;
+        segment[13] = TCP_FLAG_SYN | TCP_FLAG_ACK;
+        segment[20..28].copy_from_slice(&[1, 1, 2, 4, 0x05, 0x64, 0, 0]);
+
+        let syn_ack = parse_syn_ack(&segment, true).unwrap();
+        assert_eq!(syn_ack.dst_port, 50001);
+        assert_eq!(syn_ack.probe_id, probe_id);
+        assert_eq!(syn_ack.mss, Some(1380));
+
+        // IPv4 raw sockets hand over the IP header too
+        let mut packet = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, IPPROTO_TCP];
+        packet.resize(20, 0);
+        packet.extend_from_slice(&segment);
+        assert_eq!(parse_syn_ack(&packet, false), Some(syn_ack));
+
+        // No options, a RST, or someone else's connection
+        segment[12] = 0x50;
+        assert_eq!(parse_syn_ack(&segment, true).unwrap().mss, None);
+        segment[13] = TCP_FLAG_RST | TCP_FLAG_ACK;
+        assert!(parse_syn_ack(&segment, true).is_none());
+        segment[13] = TCP_FLAG_SYN | TCP_FLAG_ACK;
+        segment[8..12].copy_from_slice(&0x1234_5678u32.to_be_bytes());
+        assert!(parse_syn_ack(&segment, true).is_none());
+    }
 }
@@ -3,12 +3,18 @@ use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
+use crate::config::UdpIdEncoding;
 use crate::state::ProbeId;
 
 /// UDP protocol number for IPv4/IPv6
 #[allow(dead_code)]
 pub const IPPROTO_UDP: u8 = 17;
 
+/// Payload version byte: probe ID in the first two bytes, or in the
+/// UDP checksum
+const VERSION_PAYLOAD_ID: u8 = 0x00;
+const VERSION_CHECKSUM_ID: u8 = 0x01;
+
 /// Minimum UDP payload size (header fields)
 pub const MIN_UDP_PAYLOAD: usize = 8;
 /// Default UDP payload size
@@ -24,19 +30,66 @@ pub fn build_udp_payload(probe_id: ProbeId) -> Vec<u8> {
 /// Build a UDP probe payload with specific size
 /// Minimum size is 8 bytes (for probe header), larger payloads are filled with pattern
 pub fn build_udp_payload_sized(probe_id: ProbeId, size: usize) -> Vec<u8> {
+    let mut payload = payload_template(size, VERSION_PAYLOAD_ID);
+    // Encode probe_id in first 2 bytes as sequence number
+    payload[..2].copy_from_slice(&probe_id.to_sequence().to_be_bytes());
+    payload
+}
+
+/// Build a UDP probe payload whose UDP checksum is the probe's sequence
+/// number (the Paris traceroute technique). The first two bytes are chosen
+/// to steer the checksum, so the ID sits in the 8 header bytes every ICMP
+/// error quotes. `src` and `dst` must be what the kernel will send with:
+/// they are part of the checksum's pseudo-header.
+pub fn build_udp_payload_checksum(
+    probe_id: ProbeId,
+    size: usize,
+    src: SocketAddr,
+    dst: SocketAddr,
+) -> Vec<u8> {
+    let mut payload = payload_template(size, VERSION_CHECKSUM_ID);
+    // With the steering word zero, the checksum is !sum; adding w to the
+    // sum makes it !(sum + w), so w = !target - sum
+    let sum = udp_sum(src, dst, &payload);
+    let target = !probe_id.to_sequence();
+    let steer = ones_add(target, !sum);
+    payload[..2].copy_from_slice(&steer.to_be_bytes());
+    payload
+}
+
+/// Build a probe payload carrying `probe_id` as `encoding` says
+pub fn build_udp_probe(
+    encoding: UdpIdEncoding,
+    probe_id: ProbeId,
+    size: usize,
+    src: SocketAddr,
+    dst: SocketAddr,
+) -> Vec<u8> {
+    match encoding {
+        UdpIdEncoding::Payload => build_udp_payload_sized(probe_id, size),
+        UdpIdEncoding::Checksum => build_udp_payload_checksum(probe_id, size, src, dst),
+    }
+}
+
+/// UDP checksum the kernel sends `payload` from `src` to `dst` with
+/// (a computed zero goes out as all ones)
+pub fn udp_checksum(src: SocketAddr, dst: SocketAddr, payload: &[u8]) -> u16 {
+    match !udp_sum(src, dst, payload) {
+        0 => 0xFFFF,
+        checksum => checksum,
+    }
+}
+
+/// Payload with the ID bytes zeroed, the magic, `version` and fill pattern
+fn payload_template(size: usize, version: u8) -> Vec<u8> {
     let size = size.max(MIN_UDP_PAYLOAD);
-    let sequence = probe_id.to_sequence();
     let mut payload = vec![0u8; size];
 
-    // Encode probe_id in first 2 bytes as sequence number
-    payload[0] = (sequence >> 8) as u8;
-    payload[1] = (sequence & 0xFF) as u8;
-
     // Add a magic number for identification (helps distinguish our probes)
     payload[2] = 0x54; // 'T'
     payload[3] = 0x54; // 'T'
     payload[4] = 0x4C; // 'L'
-    payload[5] = 0x00; // Version
+    payload[5] = version;
 
     // Fill remaining bytes with pattern (useful for MTU testing)
     for (i, byte) in payload[6..].iter_mut().enumerate() {
@@ -46,6 +99,45 @@ pub fn build_udp_payload_sized(probe_id: ProbeId, size: usize) -> Vec<u8> {
     payload
 }
 
+/// One's complement addition (RFC 1071)
+fn ones_add(a: u16, b: u16) -> u16 {
+    let (sum, carry) = a.overflowing_add(b);
+    sum + carry as u16
+}
+
+/// One's complement sum of the UDP pseudo-header, a header with a zero
+/// checksum, and `payload`
+fn udp_sum(src: SocketAddr, dst: SocketAddr, payload: &[u8]) -> u16 {
+    let len = (8 + payload.len()) as u32;
+    let mut words: Vec<u16> = Vec::new();
+    match (src.ip(), dst.ip()) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            for octets in [s.octets(), d.octets()] {
+                words.extend(octets.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])));
+            }
+            words.extend([IPPROTO_UDP as u16, len as u16]);
+        }
+        (s, d) => {
+            let octets = |ip: IpAddr| match ip {
+                IpAddr::V4(v4) => v4.to_ipv6_mapped().octets(),
+                IpAddr::V6(v6) => v6.octets(),
+            };
+            for octets in [octets(s), octets(d)] {
+                words.extend(octets.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])));
+            }
+            words.extend([(len >> 16) as u16, len as u16, IPPROTO_UDP as u16]);
+        }
+    }
+    // Header: ports, length, zero checksum
+    words.extend([src.port(), dst.port(), len as u16]);
+    words.extend(
+        payload
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)])),
+    );
+    words.into_iter().fold(0, ones_add)
+}
+
 /// Create a raw UDP socket for sending probes
 #[allow(dead_code)]
 pub fn create_udp_send_socket(ipv6: bool) -> Result<Socket> {
@@ -157,6 +249,28 @@ pub fn extract_probe_id_from_udp_payload(udp_payload: &[u8]) -> Option<ProbeId>
     Some(ProbeId::from_sequence(sequence))
 }
 
+/// Extract the ProbeId of a UDP probe quoted in an ICMP error. `datagram`
+/// is the quoted UDP header and as much of the payload as the router
+/// included; with the checksum encoding the header alone is enough.
+pub fn extract_probe_id_from_udp(datagram: &[u8], encoding: UdpIdEncoding) -> Option<ProbeId> {
+    let payload = datagram.get(8..)?;
+    match encoding {
+        UdpIdEncoding::Payload => extract_probe_id_from_udp_payload(payload),
+        UdpIdEncoding::Checksum => {
+            // Whatever payload was quoted must be ours
+            if payload.len() >= 6 && payload[2..6] != [0x54, 0x54, 0x4C, VERSION_CHECKSUM_ID] {
+                return None;
+            }
+            let checksum = u16::from_be_bytes([datagram[6], datagram[7]]);
+            // Zero is "no checksum"; ours are never zero (TTL 0 isn't sent)
+            if checksum == 0 {
+                return None;
+            }
+            Some(ProbeId::from_sequence(checksum))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,4 +302,71 @@ mod tests {
         let extracted = extract_probe_id_from_udp_payload(&payload);
         assert!(extracted.is_none());
     }
+
+    fn datagram(src: SocketAddr, dst: SocketAddr, payload: &[u8]) -> Vec<u8> {
+        let mut udp = Vec::new();
+        udp.extend_from_slice(&src.port().to_be_bytes());
+        udp.extend_from_slice(&dst.port().to_be_bytes());
+        udp.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        udp.extend_from_slice(&udp_checksum(src, dst, payload).to_be_bytes());
+        udp.extend_from_slice(payload);
+        udp
+    }
+
+    #[test]
+    fn test_checksum_carries_probe_id() {
+        let v4 = (
+            "192.0.2.10:50000".parse().unwrap(),
+            "198.51.100.7:33440".parse().unwrap(),
+        );
+        let v6 = (
+            "[2001:db8::10]:50001".parse().unwrap(),
+            "[2001:db8:1::7]:33434".parse().unwrap(),
+        );
+        for (src, dst) in [v4, v6] {
+            for (ttl, seq, size) in [(1, 0, 32), (15, 42, 33), (64, 255, 8), (255, 255, 100)] {
+                let probe_id = ProbeId::new(ttl, seq);
+                let payload = build_udp_probe(UdpIdEncoding::Checksum, probe_id, size, src, dst);
+                assert_eq!(payload.len(), size);
+                assert_eq!(udp_checksum(src, dst, &payload), probe_id.to_sequence());
+
+                // Found from the header alone, or with the payload quoted
+                let udp = datagram(src, dst, &payload);
+                for quoted in [8, 14, udp.len()] {
+                    assert_eq!(
+                        extract_probe_id_from_udp(&udp[..quoted], UdpIdEncoding::Checksum),
+                        Some(probe_id)
+                    );
+                }
+                // The payload encoding needs the payload
+                assert_eq!(
+                    extract_probe_id_from_udp(&udp[..8], UdpIdEncoding::Payload),
+                    None
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_checksum_rejects_other_payloads() {
+        let src = "192.0.2.10:50000".parse().unwrap();
+        let dst = "198.51.100.7:33434".parse().unwrap();
+        let payload = build_udp_payload_sized(ProbeId::new(3, 1), 32);
+        let udp = datagram(src, dst, &payload);
+        assert_eq!(
+            extract_probe_id_from_udp(&udp, UdpIdEncoding::Checksum),
+            None
+        );
+        assert_eq!(
+            extract_probe_id_from_udp(&udp, UdpIdEncoding::Payload),
+            Some(ProbeId::new(3, 1))
+        );
+        // IPv4 without a checksum
+        let mut unchecked = udp[..8].to_vec();
+        unchecked[6..8].copy_from_slice(&[0, 0]);
+        assert_eq!(
+            extract_probe_id_from_udp(&unchecked, UdpIdEncoding::Checksum),
+            None
+        );
+    }
 }
@@ -0,0 +1,408 @@
+//! Hot reload of ~/.config/ttl/config.toml
+//!
+//! The config directory is watched while the TUI runs. When the file
+//! changes, settings that are safe to swap on a live trace (theme, probe
+//! interval, alert threshold, SLOs, turning lookups off) are applied to
+//! every session, and the TUI is told what changed and what only takes
+//! effect after a restart. Command-line flags keep priority over the file,
+//! as they do at startup.
+
+use anyhow::Result;
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::cli::Args;
+use crate::prefs::Prefs;
+use crate::state::EventKind;
+use crate::trace::receiver::SessionMap;
+use crate::tui::app::ConfigNotice;
+
+/// Editors often write a file in several steps; wait for them to finish
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Settings that can change on a running trace
+#[derive(Debug, Clone, PartialEq)]
+struct LiveSettings {
+    /// Theme from the file (None when --theme was given)
+    theme: Option<String>,
+    interval: Duration,
+    alert_loss: Option<f64>,
+    dns: bool,
+    asn: bool,
+    geo: bool,
+    ix: bool,
+}
+
+impl LiveSettings {
+    /// Effective settings: command-line flags first, then the file
+    fn resolve(cli: &Args, prefs: &Prefs) -> Self {
+        let mut args = cli.clone();
+        prefs.apply_to(&mut args);
+        Self {
+            theme: if cli.theme == "default" {
+                prefs.theme.clone()
+            } else {
+                None
+            },
+            interval: args.interval_duration(),
+            alert_loss: args.alert_loss,
+            dns: !args.no_dns,
+            asn: !args.no_asn,
+            geo: !args.no_geo,
+            ix: !args.no_ix,
+        }
+    }
+
+    fn lookups(&self) -> [(&'static str, bool); 4] {
+        [
+            ("dns", self.dns),
+            ("asn", self.asn),
+            ("geo", self.geo),
+            ("ix", self.ix),
+        ]
+    }
+}
+
+/// What a reload changes
+#[derive(Debug, Default, PartialEq)]
+struct ReloadPlan {
+    theme: Option<String>,
+    interval: Option<Duration>,
+    alert_loss: Option<Option<f64>>,
+    /// Lookups to switch on or off, by kind
+    lookups: Vec<(&'static str, bool)>,
+    /// Per-target SLO tables changed
+    slo: bool,
+    /// Human-readable changes that were applied
+    applied: Vec<String>,
+    /// Changes that need a restart
+    restart: Vec<String>,
+}
+
+impl ReloadPlan {
+    /// Status bar text, or None when nothing changed
+    fn message(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if !self.applied.is_empty() {
+            parts.push(format!("Config reloaded: {}", self.applied.join(", ")));
+        }
+        if !self.restart.is_empty() {
+            parts.push(format!("restart needed for {}", self.restart.join(", ")));
+        }
+        (!parts.is_empty()).then(|| parts.join("; "))
+    }
+}
+
+/// Compare two versions of the file and decide what to apply.
+///
+/// `running` is the startup state: lookups that were off then have no
+/// worker, so turning them on needs a restart.
+fn plan_reload(
+    cli: &Args,
+    running: &LiveSettings,
+    old: &Prefs,
+    new: &Prefs,
+) -> std::result::Result<ReloadPlan, String> {
+    let before = LiveSettings::resolve(cli, old);
+    let after = LiveSettings::resolve(cli, new);
+    let mut plan = ReloadPlan::default();
+
+    if after.interval != before.interval {
+        let secs = after.interval.as_secs_f64();
+        if secs <= 0.0 || cli.timeout > 256.0 * secs {
+            return Err(format!(
+                "interval {}s is too short for the {}s timeout",
+                secs, cli.timeout
+            ));
+        }
+        plan.interval = Some(after.interval);
+        plan.applied.push(format!("interval {}s", secs));
+    }
+
+    if after.alert_loss != before.alert_loss {
+        if let Some(pct) = after.alert_loss
+            && !(pct > 0.0 && pct <= 100.0)
+        {
+            return Err("alert_loss must be between 0 and 100".into());
+        }
+        plan.alert_loss = Some(after.alert_loss);
+        plan.applied.push(match after.alert_loss {
+            Some(pct) => format!("alert-loss {}%", pct),
+            None => "alert-loss off".to_string(),
+        });
+    }
+
+    let started = running.lookups();
+    for (((kind, was), (_, now)), (_, has_worker)) in before
+        .lookups()
+        .into_iter()
+        .zip(after.lookups())
+        .zip(started)
+    {
+        if was == now {
+            continue;
+        }
+        if now && !has_worker {
+            plan.restart.push(format!("{} lookups", kind));
+        } else {
+            plan.lookups.push((kind, now));
+            plan.applied
+                .push(format!("{} {}", kind, if now { "on" } else { "off" }));
+        }
+    }
+
+    if new.slo != old.slo && cli.slo.is_none() {
+        for entry in &new.slo {
+            crate::state::Slo::parse(&entry.objective)
+                .map_err(|e| format!("SLO for {}: {}", entry.target, e))?;
+        }
+        plan.slo = true;
+        plan.applied.push("SLOs".to_string());
+    }
+
+    if after.theme != before.theme
+        && let Some(ref theme) = after.theme
+    {
+        plan.theme = Some(theme.clone());
+        plan.applied.push(format!("theme {}", theme));
+    }
+
+    Ok(plan)
+}
+
+/// Apply a reload plan to every session
+fn apply_plan(plan: &ReloadPlan, prefs: &Prefs, sessions: &SessionMap) -> Result<()> {
+    // Theme is a display setting, not worth a timeline entry
+    let live_changes: Vec<&str> = plan
+        .applied
+        .iter()
+        .map(String::as_str)
+        .filter(|c| !c.starts_with("theme "))
+        .collect();
+
+    for state in sessions.read().values() {
+        let mut session = state.write();
+        if let Some(interval) = plan.interval {
+            session.config.interval = interval;
+        }
+        if let Some(alert_loss) = plan.alert_loss {
+            session.config.alert_loss = alert_loss;
+        }
+        for &(kind, enabled) in &plan.lookups {
+            let flag = match kind {
+                "dns" => &mut session.config.dns_enabled,
+                "asn" => &mut session.config.asn_enabled,
+                "geo" => &mut session.config.geo_enabled,
+                _ => &mut session.config.ix_enabled,
+            };
+            *flag = enabled;
+        }
+        if plan.slo {
+            let slo = prefs.slo_for(&session.target.original, session.target.resolved)?;
+            if slo != session.config.slo {
+                session.set_slo(slo);
+            }
+        }
+        if !live_changes.is_empty() {
+            let description = format!("config reload: {}", live_changes.join(", "));
+            session.record_event(EventKind::ConfigChange { description });
+        }
+    }
+    Ok(())
+}
+
+/// Watch config.toml and apply safe changes until `cancel` fires.
+///
+/// `cli` holds the arguments as given on the command line (before the
+/// file filled anything in) and `startup` the file as read at startup.
+pub fn spawn_config_watcher(
+    cli: Args,
+    startup: Prefs,
+    sessions: SessionMap,
+    notices: UnboundedSender<ConfigNotice>,
+    cancel: CancellationToken,
+) -> Result<JoinHandle<()>> {
+    let path = Prefs::path().ok_or_else(|| anyhow::anyhow!("No config directory"))?;
+    let dir = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    std::fs::create_dir_all(&dir)?;
+
+    // Watch the directory: editors replace the file rather than write in place
+    let (tx, mut rx) = unbounded_channel();
+    let file_name = path.file_name().map(|n| n.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res
+            && event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == file_name.as_deref())
+        {
+            let _ = tx.send(());
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    Ok(tokio::spawn(async move {
+        // Dropping the watcher stops it, so it lives as long as this task
+        let _watcher = watcher;
+        let running = LiveSettings::resolve(&cli, &startup);
+        let mut current = startup;
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                Some(()) = rx.recv() => {
+                    tokio::time::sleep(DEBOUNCE).await;
+                    while rx.try_recv().is_ok() {}
+
+                    let new = match Prefs::read(&path) {
+                        Ok(prefs) => prefs,
+                        Err(e) => {
+                            let _ = notices.send(ConfigNotice {
+                                theme: None,
+                                message: format!("config.toml not applied: {}", e),
+                            });
+                            continue;
+                        }
+                    };
+                    if new == current {
+                        continue;
+                    }
+
+                    let outcome = plan_reload(&cli, &running, &current, &new)
+                        .and_then(|plan| {
+                            apply_plan(&plan, &new, &sessions).map_err(|e| e.to_string())?;
+                            Ok(plan)
+                        });
+                    match outcome {
+                        Ok(plan) => {
+                            if let Some(message) = plan.message() {
+                                let _ = notices.send(ConfigNotice {
+                                    theme: plan.theme,
+                                    message,
+                                });
+                            }
+                            current = new;
+                        }
+                        Err(e) => {
+                            let _ = notices.send(ConfigNotice {
+                                theme: None,
+                                message: format!("config.toml not applied: {}", e),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn prefs(text: &str) -> Prefs {
+        toml::from_str(text).unwrap()
+    }
+
+    fn args(argv: &[&str]) -> Args {
+        Args::try_parse_from(["ttl"].iter().chain(argv).chain(&["8.8.8.8"])).unwrap()
+    }
+
+    #[test]
+    fn test_plan_reload_applies_safe_changes() {
+        let cli = args(&[]);
+        let old = prefs("theme = \"nord\"\n");
+        let new = prefs("theme = \"dracula\"\ninterval = 0.5\nalert_loss = 5.0\ndns = false\n");
+        let running = LiveSettings::resolve(&cli, &old);
+
+        let plan = plan_reload(&cli, &running, &old, &new).unwrap();
+        assert_eq!(plan.interval, Some(Duration::from_millis(500)));
+        assert_eq!(plan.alert_loss, Some(Some(5.0)));
+        assert_eq!(plan.lookups, vec![("dns", false)]);
+        assert_eq!(plan.theme.as_deref(), Some("dracula"));
+        assert_eq!(
+            plan.message().unwrap(),
+            "Config reloaded: interval 0.5s, alert-loss 5%, dns off, theme dracula"
+        );
+    }
+
+    #[test]
+    fn test_plan_reload_respects_cli_and_restart() {
+        // -i on the command line wins; geo had no worker at startup
+        let cli = args(&["-i", "2", "--no-geo"]);
+        let old = prefs("geo = false\n");
+        let new = prefs("interval = 0.5\ngeo = true\n");
+        let running = LiveSettings::resolve(&cli, &old);
+
+        let plan = plan_reload(&cli, &running, &old, &new).unwrap();
+        assert_eq!(plan.interval, None);
+        assert!(plan.lookups.is_empty());
+        assert!(plan.message().is_none());
+
+        let cli = args(&[]);
+        let running = LiveSettings::resolve(&cli, &old);
+        let plan = plan_reload(&cli, &running, &old, &new).unwrap();
+        assert_eq!(plan.restart, vec!["geo lookups"]);
+        assert_eq!(
+            plan.message().unwrap(),
+            "Config reloaded: interval 0.5s; restart needed for geo lookups"
+        );
+    }
+
+    #[test]
+    fn test_plan_reload_rejects_invalid_values() {
+        let cli = args(&["--timeout", "10"]);
+        let old = Prefs::default();
+        let running = LiveSettings::resolve(&cli, &old);
+
+        let new = prefs("interval = 0.01\n");
+        assert!(plan_reload(&cli, &running, &old, &new).is_err());
+        let new = prefs("alert_loss = 150.0\n");
+        assert!(plan_reload(&cli, &running, &old, &new).is_err());
+        let new = prefs("[[slo]]\ntarget = \"a\"\nobjective = \"fast\"\n");
+        assert!(plan_reload(&cli, &running, &old, &new).is_err());
+    }
+
+    #[test]
+    fn test_apply_plan_updates_sessions() {
+        use crate::config::Config;
+        use crate::state::{Session, Target};
+        use parking_lot::RwLock;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let ip = "8.8.8.8".parse().unwrap();
+        let session = Session::new(Target::new("dns.google".into(), ip), Config::default());
+        let sessions: SessionMap = Arc::new(RwLock::new(HashMap::from([(
+            ip,
+            Arc::new(RwLock::new(session)),
+        )])));
+
+        let cli = args(&[]);
+        let old = Prefs::default();
+        let new = prefs(
+            "interval = 0.25\nix = false\n\n[[slo]]\ntarget = \"dns.google\"\nobjective = \"p95<30ms\"\n",
+        );
+        let running = LiveSettings::resolve(&cli, &old);
+        let plan = plan_reload(&cli, &running, &old, &new).unwrap();
+        apply_plan(&plan, &new, &sessions).unwrap();
+
+        let map = sessions.read();
+        let session = map[&ip].read();
+        assert_eq!(session.config.interval, Duration::from_millis(250));
+        assert!(!session.config.ix_enabled);
+        assert_eq!(session.slo.as_ref().unwrap().slo.latency_ms, Some(30.0));
+        assert!(matches!(
+            session.events.last().unwrap().kind,
+            EventKind::ConfigChange { ref description } if description.starts_with("config reload: interval 0.25s")
+        ));
+    }
+}
@@ -0,0 +1,964 @@
+//! Remote agent protocol.
+//!
+//! `ttl agent --listen ADDR` runs traces on behalf of a client started with
+//! `ttl --via ADDR target`. The wire format is newline-delimited JSON over TCP:
+//! the client sends one [`Hello`] carrying a shared token and its CLI
+//! arguments, and the agent answers with a stream of [`Frame`]s holding full
+//! session snapshots. The channel is authenticated by the token but is not
+//! TLS: token and snapshots travel in plaintext. Encryption is left to the
+//! transport, so the agent only listens on loopback unless started with
+//! `--allow-remote`; reach it through an SSH tunnel, or a VPN when allowing
+//! remote clients.
+//!
+//! A [`Hello`] with `health` set asks for one [`AgentHealth`] frame instead of
+//! a trace, for `ttl agent --health` and service monitoring. Under systemd the
+//! agent also reports readiness and status, pings the watchdog, and re-reads
+//! config.toml (the defaults applied to client traces) on SIGHUP.
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use chrono::{DateTime, Utc};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use parking_lot::{Mutex, RwLock};
+
+use crate::cli::Args;
+use crate::config::Config;
+use crate::prefs::Prefs;
+use crate::probe::validate_interface;
+use crate::state::Session;
+use crate::systemd;
+use crate::trace::receiver::SessionMap;
+use crate::trace::runner::{ResolveOptions, build_sessions, check_address_families, start_tracing};
+
+/// Port used when `--via` or `--listen` omit one
+pub const DEFAULT_AGENT_PORT: u16 = 7447;
+
+/// `--listen` default: loopback only, since the channel is unencrypted
+pub fn default_agent_listen() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], DEFAULT_AGENT_PORT))
+}
+
+/// Refuse a non-loopback listener unless remote clients were allowed, and
+/// warn when they were
+fn check_listen(listen: SocketAddr, allow_remote: bool) -> Result<Option<String>> {
+    if listen.ip().is_loopback() {
+        return Ok(None);
+    }
+    if !allow_remote {
+        anyhow::bail!(
+            "Refusing to listen on {}: agent traffic (token included) is not encrypted. \
+             Listen on loopback and use an SSH tunnel, or pass --allow-remote",
+            listen
+        );
+    }
+    Ok(Some(format!(
+        "Warning: agent traffic on {} is not encrypted; the token and results can be read \
+         by anyone on the path",
+        listen
+    )))
+}
+
+/// Largest frame accepted in either direction (matches the replay file cap)
+const MAX_FRAME_SIZE: u64 = 10 * 1024 * 1024;
+
+/// How often the agent pushes session snapshots
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Time allowed for a client to send its handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client handshake
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hello {
+    pub token: String,
+    /// CLI arguments for the trace, without the program name
+    pub args: Vec<String>,
+    /// Ask for an [`AgentHealth`] frame instead of starting a trace
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub health: bool,
+}
+
+/// Agent state for `ttl agent --health` and monitoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentHealth {
+    pub version: String,
+    pub uptime_secs: u64,
+    /// Targets of the trace in progress, empty when idle
+    pub tracing: Vec<String>,
+    /// Traces started since the agent came up
+    pub traces_served: u64,
+    /// Why the last trace failed to start or ended with an error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// When config.toml was last read (startup or SIGHUP)
+    pub config_loaded: DateTime<Utc>,
+    /// Parse error from the last read; the previous settings stay in use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_error: Option<String>,
+}
+
+/// Agent-to-client message
+///
+/// Externally tagged on purpose: internally tagged enums buffer their content,
+/// which breaks the integer map keys inside `Session`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Frame {
+    /// Current state of every traced target; `done` marks the final snapshot
+    Snapshot { sessions: Vec<Session>, done: bool },
+    /// Fatal error; the agent closes the connection after sending it
+    Error { message: String },
+    /// Answer to a health request; the agent closes the connection after it
+    Health(AgentHealth),
+}
+
+/// State shared by the agent's connections
+struct Agent {
+    token: String,
+    /// Traces share the ICMP identifier, so concurrent traces would steal
+    /// each other's responses; serve one client at a time
+    busy: Semaphore,
+    started: Instant,
+    status: Mutex<AgentStatus>,
+}
+
+struct AgentStatus {
+    /// config.toml defaults for client traces
+    prefs: Prefs,
+    config_loaded: DateTime<Utc>,
+    config_error: Option<String>,
+    tracing: Vec<String>,
+    traces_served: u64,
+    last_error: Option<String>,
+}
+
+impl Agent {
+    fn new(token: String) -> Self {
+        let mut status = AgentStatus {
+            prefs: Prefs::default(),
+            config_loaded: Utc::now(),
+            config_error: None,
+            tracing: Vec::new(),
+            traces_served: 0,
+            last_error: None,
+        };
+        status.reload();
+        Self {
+            token,
+            busy: Semaphore::new(1),
+            started: Instant::now(),
+            status: Mutex::new(status),
+        }
+    }
+
+    fn health(&self) -> AgentHealth {
+        let status = self.status.lock();
+        AgentHealth {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            tracing: status.tracing.clone(),
+            traces_served: status.traces_served,
+            last_error: status.last_error.clone(),
+            config_loaded: status.config_loaded,
+            config_error: status.config_error.clone(),
+        }
+    }
+
+    fn trace_started(&self, targets: &[String]) {
+        let mut status = self.status.lock();
+        status.tracing = targets.to_vec();
+        status.traces_served += 1;
+        systemd::status(&format!("Tracing {}", targets.join(", ")));
+    }
+
+    fn trace_ended(&self, error: Option<String>) {
+        let mut status = self.status.lock();
+        status.tracing.clear();
+        if error.is_some() {
+            status.last_error = error;
+        }
+        systemd::status("Idle");
+    }
+}
+
+impl AgentStatus {
+    /// Re-read config.toml, keeping the previous settings if it doesn't parse
+    fn reload(&mut self) {
+        self.config_loaded = Utc::now();
+        let Some(path) = Prefs::path() else {
+            return;
+        };
+        match Prefs::read(&path) {
+            Ok(prefs) => {
+                self.prefs = prefs;
+                self.config_error = None;
+            }
+            Err(e) => {
+                eprintln!("agent: {}: {}", path.display(), e);
+                self.config_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+/// Compare tokens in time that depends on neither their contents nor their
+/// lengths: both are hashed to fixed-size digests first
+fn token_matches(given: &str, expected: &str) -> bool {
+    let (a, b) = (Sha256::digest(given), Sha256::digest(expected));
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+/// Parse "host", "host:port" or "[v6]:port", defaulting the port
+pub fn parse_agent_addr(addr: &str) -> String {
+    let has_port = match addr.rsplit_once(':') {
+        Some((host, port)) => {
+            port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+        }
+        None => false,
+    };
+    if has_port {
+        addr.to_string()
+    } else if addr.contains(':') && !addr.starts_with('[') {
+        format!("[{}]:{}", addr, DEFAULT_AGENT_PORT)
+    } else {
+        format!("{}:{}", addr, DEFAULT_AGENT_PORT)
+    }
+}
+
+/// Strip client-only options (`--via`, `--agent-token`) from a raw argv tail
+pub fn forwarded_args(argv: impl IntoIterator<Item = String>) -> Vec<String> {
+    const CLIENT_ONLY: [&str; 2] = ["--via", "--agent-token"];
+    let mut out = Vec::new();
+    let mut skip_value = false;
+    for arg in argv {
+        if skip_value {
+            skip_value = false;
+            continue;
+        }
+        if CLIENT_ONLY.contains(&arg.as_str()) {
+            skip_value = true;
+            continue;
+        }
+        if CLIENT_ONLY
+            .iter()
+            .any(|opt| arg.starts_with(&format!("{}=", opt)))
+        {
+            continue;
+        }
+        out.push(arg);
+    }
+    out
+}
+
+async fn read_frame<T, R>(reader: &mut R) -> Result<Option<T>>
+where
+    T: DeserializeOwned,
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = String::new();
+    let n = (&mut *reader)
+        .take(MAX_FRAME_SIZE)
+        .read_line(&mut line)
+        .await?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') && n as u64 >= MAX_FRAME_SIZE {
+        anyhow::bail!("Frame exceeds {} bytes", MAX_FRAME_SIZE);
+    }
+    Ok(Some(
+        serde_json::from_str(&line).context("Malformed frame")?,
+    ))
+}
+
+async fn write_frame<T, W>(writer: &mut W, frame: &T) -> Result<()>
+where
+    T: Serialize,
+    W: AsyncWrite + Unpin,
+{
+    let mut data = serde_json::to_vec(frame)?;
+    data.push(b'\n');
+    writer.write_all(&data).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn snapshot(sessions: &SessionMap, targets: &[IpAddr]) -> Vec<Session> {
+    let sessions_read = sessions.read();
+    targets
+        .iter()
+        .filter_map(|t| sessions_read.get(t).map(|s| s.read().clone()))
+        .collect()
+}
+
+/// Options a client may send: probing, lookups against the agent's own
+/// configured services, and display or output options the agent ignores.
+/// Anything that runs a command, touches files or contacts a host the client
+/// picks (`--plugin`, `--enrich-url`, `--dest-webhook`, `--geoip-db`,
+/// `--targets-file`, `--dns-server`, ...) is left out, as is every option
+/// added later until it is vetted here.
+const REMOTE_OPTIONS: &[&str] = &[
+    // Probing
+    "targets",
+    "count",
+    "duration",
+    "stats_window",
+    "interval",
+    "max_ttl",
+    "first_ttl",
+    "gap_limit",
+    "ttl_decay",
+    "adaptive",
+    "protocol",
+    "port",
+    "port_fixed",
+    "udp_id",
+    "flows",
+    "src_port",
+    "src_port_range",
+    "timeout",
+    "ipv4",
+    "ipv6",
+    "address_index",
+    "best_family",
+    "yes",
+    "interface",
+    "recv_any",
+    "redirects",
+    "dscp",
+    "ecn",
+    "size",
+    "pmtud",
+    "df_sweep",
+    "rate",
+    "source_ip",
+    "sources",
+    "schedule",
+    "batch",
+    "parallel",
+    "global_rate",
+    "global_burst",
+    "recv_buffer",
+    "send_buffer",
+    "perf",
+    "flap_hold",
+    "flap_cooldown",
+    "reset_on_path_change",
+    "stale_after",
+    "alert_loss",
+    "slo",
+    // Lookups and naming
+    "no_dns",
+    "no_asn",
+    "no_geo",
+    "no_ix",
+    "prefix_watch",
+    "offline",
+    "no_disk_cache",
+    "origin",
+    "alias",
+    "label",
+    "label_mode",
+    "host_trim",
+    "merge_by_hostname",
+    "lookup_concurrency",
+    "lookup_interval",
+    // Display and output, handled by the client
+    "json",
+    "csv",
+    "report",
+    "report_wide",
+    "order",
+    "no_header",
+    "summary_only",
+    "progress",
+    "tags",
+    "redact",
+    "no_tui",
+    "theme",
+    "local_time",
+    "locale",
+    "lang",
+    "auto_units",
+];
+
+/// Parse and vet arguments received from a client, filling in the agent's
+/// config.toml defaults
+fn parse_remote_args(args: &[String], prefs: &Prefs) -> Result<Args> {
+    let command = Args::command();
+    let matches = command
+        .clone()
+        .try_get_matches_from(std::iter::once("ttl".to_string()).chain(args.iter().cloned()))
+        .map_err(|e| anyhow::anyhow!("{}", e.to_string().trim()))?;
+    if matches.subcommand().is_some() {
+        anyhow::bail!("Only trace arguments can be sent to an agent");
+    }
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) == Some(ValueSource::CommandLine)
+            && !REMOTE_OPTIONS.contains(&id)
+        {
+            let name = arg.get_long().unwrap_or(id);
+            anyhow::bail!("--{} cannot be sent to an agent", name);
+        }
+    }
+    let mut args = Args::from_arg_matches(&matches).map_err(|e| anyhow::anyhow!("{}", e))?;
+    prefs.apply_to(&mut args);
+    args.validate().map_err(|e| anyhow::anyhow!(e))?;
+    Ok(args)
+}
+
+/// Run the agent until cancelled, serving one trace at a time
+pub async fn run_agent(
+    listen: SocketAddr,
+    allow_remote: bool,
+    token: String,
+    cancel: CancellationToken,
+) -> Result<()> {
+    if token.is_empty() {
+        anyhow::bail!("Agent token cannot be empty");
+    }
+    if let Some(warning) = check_listen(listen, allow_remote)? {
+        eprintln!("{}", warning);
+    }
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    let mut hangup = signal(SignalKind::hangup()).context("Failed to watch for SIGHUP")?;
+    eprintln!("ttl agent listening on {}", listen);
+
+    let agent = Arc::new(Agent::new(token));
+    systemd::notify(&format!("READY=1\nSTATUS=Listening on {}", listen));
+    let mut watchdog = systemd::watchdog_interval().map(tokio::time::interval);
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = async { watchdog.as_mut().unwrap().tick().await }, if watchdog.is_some() => {
+                systemd::notify("WATCHDOG=1");
+            }
+            _ = hangup.recv() => {
+                systemd::reloading();
+                agent.status.lock().reload();
+                eprintln!("agent: reloaded config.toml (applies to the next trace)");
+                systemd::notify("READY=1");
+            }
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        eprintln!("agent: accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let agent = agent.clone();
+                let cancel = cancel.child_token();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_client(stream, &agent, cancel).await {
+                        eprintln!("agent: {}: {:#}", peer, e);
+                    }
+                });
+            }
+        }
+    }
+
+    systemd::notify("STOPPING=1");
+    Ok(())
+}
+
+async fn serve_client(stream: TcpStream, agent: &Agent, cancel: CancellationToken) -> Result<()> {
+    let (rd, mut wr) = stream.into_split();
+    let mut rd = BufReader::new(rd);
+
+    let hello: Hello = tokio::time::timeout(HANDSHAKE_TIMEOUT, read_frame(&mut rd))
+        .await
+        .context("Handshake timed out")??
+        .context("Connection closed before handshake")?;
+
+    let reject = |message: String| Frame::Error { message };
+    if !token_matches(&hello.token, &agent.token) {
+        write_frame(&mut wr, &reject("Authentication failed".into())).await?;
+        anyhow::bail!("Authentication failed");
+    }
+    if hello.health {
+        return write_frame(&mut wr, &Frame::Health(agent.health())).await;
+    }
+    let Ok(_permit) = agent.busy.try_acquire() else {
+        write_frame(&mut wr, &reject("Agent is busy with another trace".into())).await?;
+        return Ok(());
+    };
+
+    let prefs = agent.status.lock().prefs.clone();
+    let args = match parse_remote_args(&hello.args, &prefs) {
+        Ok(args) => args,
+        Err(e) => {
+            write_frame(&mut wr, &reject(e.to_string())).await?;
+            return Err(e);
+        }
+    };
+
+    agent.trace_started(&args.targets);
+    let result = run_client_trace(&args, &mut wr, &mut rd, cancel).await;
+    agent.trace_ended(result.as_ref().err().map(|e| format!("{:#}", e)));
+    result
+}
+
+/// Run one client's trace, streaming snapshots until it ends or the client leaves
+async fn run_client_trace<R, W>(
+    args: &Args,
+    wr: &mut W,
+    rd: &mut R,
+    cancel: CancellationToken,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let reject = |message: String| Frame::Error { message };
+    let config = Config::from(args);
+    let setup = async {
+        let interface = args
+            .interface
+            .as_deref()
+            .map(validate_interface)
+            .transpose()?;
+        let (sessions, targets) = build_sessions(
+            &args.targets,
+            // Nobody is at the agent's terminal to answer a prompt
+            &ResolveOptions {
+                assume_yes: true,
+                ..ResolveOptions::from_args(args)
+            },
+            &config,
+            interface.as_ref(),
+        )
+        .await?;
+        check_address_families(&targets, interface.as_ref(), config.source_ip)?;
+        let tasks = start_tracing(
+            &sessions,
+            &targets,
+            &config,
+            &cancel,
+            interface.as_ref(),
+            args.geoip_db.as_deref(),
+        )
+        .await?;
+        anyhow::Ok((sessions, targets, tasks))
+    };
+    let (sessions, targets, tasks) = match setup.await {
+        Ok(v) => v,
+        Err(e) => {
+            write_frame(wr, &reject(format!("{:#}", e))).await?;
+            return Err(e);
+        }
+    };
+
+    let mut tick = tokio::time::interval(SNAPSHOT_INTERVAL);
+    let mut scratch = [0u8; 64];
+
+    loop {
+        tokio::select! {
+            // Engines cancel the token once -c rounds are done (or the agent is
+            // shutting down); whatever we have is the final snapshot
+            _ = cancel.cancelled() => {
+                let frame = Frame::Snapshot {
+                    sessions: snapshot(&sessions, &targets),
+                    done: true,
+                };
+                let _ = write_frame(wr, &frame).await;
+                break;
+            }
+            // Anything from the client after the handshake is ignored; EOF means it left
+            read = rd.read(&mut scratch) => {
+                if !matches!(read, Ok(n) if n > 0) {
+                    break;
+                }
+            }
+            _ = tick.tick() => {
+                let frame = Frame::Snapshot {
+                    sessions: snapshot(&sessions, &targets),
+                    done: false,
+                };
+                if write_frame(wr, &frame).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    cancel.cancel();
+    tasks.join().await
+}
+
+/// Ask a running agent for its health (`ttl agent --health`)
+pub async fn query_health(addr: &str, token: &str) -> Result<AgentHealth> {
+    let addr = parse_agent_addr(addr);
+    let exchange = async {
+        let stream = TcpStream::connect(&addr)
+            .await
+            .with_context(|| format!("Failed to connect to agent {}", addr))?;
+        let (rd, mut wr) = stream.into_split();
+        let mut rd = BufReader::new(rd);
+        write_frame(
+            &mut wr,
+            &Hello {
+                token: token.to_string(),
+                args: Vec::new(),
+                health: true,
+            },
+        )
+        .await?;
+        match read_frame::<Frame, _>(&mut rd).await? {
+            Some(Frame::Health(health)) => Ok(health),
+            Some(Frame::Error { message }) => anyhow::bail!("Agent refused: {}", message),
+            Some(Frame::Snapshot { .. }) => anyhow::bail!("Agent sent a snapshot, not health"),
+            None => anyhow::bail!("Agent closed the connection"),
+        }
+    };
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, exchange)
+        .await
+        .with_context(|| format!("Agent {} did not answer", addr))?
+}
+
+/// A trace running on a remote agent, mirrored into a local session map
+pub struct RemoteTrace {
+    pub sessions: SessionMap,
+    pub targets: Vec<IpAddr>,
+    /// Completes when the agent sends its final snapshot or disconnects
+    pub updates: JoinHandle<Result<()>>,
+}
+
+/// Connect to an agent, start the trace and mirror its snapshots locally
+pub async fn connect_agent(
+    via: &str,
+    token: &str,
+    args: Vec<String>,
+    cancel: CancellationToken,
+) -> Result<RemoteTrace> {
+    let addr = parse_agent_addr(via);
+    let stream = TcpStream::connect(&addr)
+        .await
+        .with_context(|| format!("Failed to connect to agent {}", addr))?;
+    let (rd, mut wr) = stream.into_split();
+    let mut rd = BufReader::new(rd);
+
+    write_frame(
+        &mut wr,
+        &Hello {
+            token: token.to_string(),
+            args,
+            health: false,
+        },
+    )
+    .await?;
+
+    let first = match read_frame::<Frame, _>(&mut rd).await? {
+        Some(Frame::Snapshot { sessions, .. }) => sessions,
+        Some(Frame::Error { message }) => anyhow::bail!("Agent refused trace: {}", message),
+        Some(Frame::Health(_)) => anyhow::bail!("Agent sent health instead of a trace"),
+        None => anyhow::bail!("Agent closed the connection"),
+    };
+
+    let targets: Vec<IpAddr> = first.iter().map(|s| s.target.resolved).collect();
+    if targets.is_empty() {
+        anyhow::bail!("Agent returned no sessions");
+    }
+    let map: HashMap<IpAddr, Arc<RwLock<Session>>> = first
+        .into_iter()
+        .map(|s| (s.target.resolved, Arc::new(RwLock::new(s))))
+        .collect();
+    let sessions: SessionMap = Arc::new(RwLock::new(map));
+
+    let mirror = sessions.clone();
+    let updates = tokio::spawn(async move {
+        // Keep the write half alive: dropping it would signal EOF to the agent
+        let _wr = wr;
+        loop {
+            let frame = tokio::select! {
+                _ = cancel.cancelled() => return Ok(()),
+                frame = read_frame::<Frame, _>(&mut rd) => frame?,
+            };
+            match frame {
+                Some(Frame::Snapshot { sessions, done }) => {
+                    apply_snapshot(&mirror, sessions);
+                    if done {
+                        return Ok(());
+                    }
+                }
+                Some(Frame::Error { message }) => anyhow::bail!("Agent error: {}", message),
+                Some(Frame::Health(_)) => {}
+                None => anyhow::bail!("Agent closed the connection"),
+            }
+        }
+    });
+
+    Ok(RemoteTrace {
+        sessions,
+        targets,
+        updates,
+    })
+}
+
+/// Replace mirrored sessions with the agent's copies, keeping local-only state
+fn apply_snapshot(sessions: &SessionMap, snapshot: Vec<Session>) {
+    let sessions_read = sessions.read();
+    for remote in snapshot {
+        if let Some(state) = sessions_read.get(&remote.target.resolved) {
+            let mut local = state.write();
+            let notes = std::mem::take(&mut local.notes);
+            let paused = local.paused;
+            *local = remote;
+            local.notes = notes;
+            local.paused = paused;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cret", "s3cre"));
+        assert!(!token_matches("s3creT", "s3cret"));
+        assert!(!token_matches("", "s3cret"));
+    }
+
+    #[test]
+    fn test_parse_agent_addr() {
+        assert_eq!(parse_agent_addr("probe1"), "probe1:7447");
+        assert_eq!(parse_agent_addr("probe1:9000"), "probe1:9000");
+        assert_eq!(parse_agent_addr("10.0.0.5"), "10.0.0.5:7447");
+        assert_eq!(parse_agent_addr("2001:db8::1"), "[2001:db8::1]:7447");
+        assert_eq!(parse_agent_addr("[2001:db8::1]:9000"), "[2001:db8::1]:9000");
+    }
+
+    #[test]
+    fn test_forwarded_args_strip_client_options() {
+        let argv = [
+            "--via",
+            "probe1",
+            "-p",
+            "udp",
+            "--agent-token=abc",
+            "8.8.8.8",
+            "--agent-token",
+            "abc",
+        ]
+        .map(String::from);
+        assert_eq!(forwarded_args(argv), vec!["-p", "udp", "8.8.8.8"]);
+    }
+
+    #[test]
+    fn test_parse_remote_args_rejects_nested_modes() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let prefs = Prefs::default();
+        assert!(parse_remote_args(&args(&["8.8.8.8"]), &prefs).is_ok());
+        assert!(parse_remote_args(&args(&["--replay", "x.json"]), &prefs).is_err());
+        assert!(parse_remote_args(&args(&["--via", "other", "8.8.8.8"]), &prefs).is_err());
+        assert!(parse_remote_args(&args(&["merge", "a.json", "b.json"]), &prefs).is_err());
+    }
+
+    #[test]
+    fn test_parse_remote_args_rejects_host_side_options() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let prefs = Prefs::default();
+        let refused = |v: &[&str]| parse_remote_args(&args(v), &prefs).unwrap_err().to_string();
+        assert_eq!(
+            refused(&["--plugin", "responder=/bin/sh", "8.8.8.8"]),
+            "--plugin cannot be sent to an agent"
+        );
+        assert_eq!(
+            refused(&["--targets-file", "/etc/hosts"]),
+            "--targets-file cannot be sent to an agent"
+        );
+        // Probe and output options still go through
+        assert!(
+            parse_remote_args(
+                &args(&[
+                    "-c", "5", "--report", "--flows", "4", "--dscp", "46", "8.8.8.8"
+                ]),
+                &prefs
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_parse_remote_args_rejects_outbound_options() {
+        // The agent would fetch these and echo the result back in snapshots
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let prefs = Prefs::default();
+        let refused = |v: &[&str]| parse_remote_args(&args(v), &prefs).unwrap_err().to_string();
+        assert_eq!(
+            refused(&["--enrich-url", "http://169.254.169.254/", "8.8.8.8"]),
+            "--enrich-url cannot be sent to an agent"
+        );
+        assert_eq!(
+            refused(&["--dest-webhook", "http://10.0.0.1/", "8.8.8.8"]),
+            "--dest-webhook cannot be sent to an agent"
+        );
+        assert_eq!(
+            refused(&["--geoip-db", "/etc/shadow", "8.8.8.8"]),
+            "--geoip-db cannot be sent to an agent"
+        );
+        assert_eq!(
+            refused(&["--dns-server", "10.0.0.53", "8.8.8.8"]),
+            "--dns-server cannot be sent to an agent"
+        );
+    }
+
+    #[test]
+    fn test_parse_remote_args_applies_agent_config() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let prefs = Prefs {
+            dscp: Some(46),
+            dns: Some(false),
+            ..Prefs::default()
+        };
+        let parsed = parse_remote_args(&args(&["8.8.8.8"]), &prefs).unwrap();
+        assert_eq!(parsed.dscp, Some(46));
+        assert!(parsed.no_dns);
+        // The client's own flags win
+        let parsed = parse_remote_args(&args(&["--dscp", "10", "8.8.8.8"]), &prefs).unwrap();
+        assert_eq!(parsed.dscp, Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_frames_roundtrip() {
+        let mut buf = Vec::new();
+        write_frame(
+            &mut buf,
+            &Frame::Error {
+                message: "nope".into(),
+            },
+        )
+        .await
+        .unwrap();
+        let mut reader = BufReader::new(&buf[..]);
+        match read_frame::<Frame, _>(&mut reader).await.unwrap() {
+            Some(Frame::Error { message }) => assert_eq!(message, "nope"),
+            other => panic!("unexpected frame: {:?}", other),
+        }
+        assert!(read_frame::<Frame, _>(&mut reader).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_frame_roundtrip() {
+        use crate::state::Target;
+        use std::net::Ipv4Addr;
+
+        let target = Target::new("t".into(), IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)));
+        let mut session = Session::new(target, Config::default());
+        let hop = session.hop_mut(1).unwrap();
+        hop.record_sent();
+        hop.record_flow_sent(0);
+        hop.record_flow_response(
+            0,
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            Duration::from_millis(1),
+        );
+
+        let mut buf = Vec::new();
+        let frame = Frame::Snapshot {
+            sessions: vec![session],
+            done: true,
+        };
+        write_frame(&mut buf, &frame).await.unwrap();
+        let mut reader = BufReader::new(&buf[..]);
+        match read_frame::<Frame, _>(&mut reader).await.unwrap() {
+            Some(Frame::Snapshot { sessions, done }) => {
+                assert!(done);
+                assert_eq!(sessions[0].hop(1).unwrap().flow_paths.len(), 1);
+            }
+            other => panic!("unexpected frame: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_listen() {
+        assert_eq!(default_agent_listen().to_string(), "127.0.0.1:7447");
+        assert!(
+            check_listen(default_agent_listen(), false)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            check_listen("[::1]:7447".parse().unwrap(), false)
+                .unwrap()
+                .is_none()
+        );
+
+        let public: SocketAddr = "0.0.0.0:7447".parse().unwrap();
+        let err = check_listen(public, false).unwrap_err().to_string();
+        assert!(err.contains("--allow-remote"), "{}", err);
+        let warning = check_listen(public, true).unwrap().unwrap();
+        assert!(warning.contains("not encrypted"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_rejects_bad_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let agent = Agent::new("right".into());
+            serve_client(stream, &agent, CancellationToken::new()).await
+        });
+
+        let err = connect_agent(
+            &addr.to_string(),
+            "wrong",
+            vec!["127.0.0.1".into()],
+            CancellationToken::new(),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("Authentication failed"));
+        assert!(server.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_agent_health() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let agent = Agent::new("right".into());
+            agent.trace_started(&["8.8.8.8".to_string()]);
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let _ = serve_client(stream, &agent, CancellationToken::new()).await;
+            }
+        });
+
+        let health = query_health(&addr.to_string(), "right").await.unwrap();
+        assert_eq!(health.tracing, ["8.8.8.8"]);
+        assert_eq!(health.traces_served, 1);
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+        let err = query_health(&addr.to_string(), "wrong").await.unwrap_err();
+        assert!(err.to_string().contains("Authentication failed"));
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+//! Desktop notifications for session alerts
+//!
+//! Watches each session's event timeline and raises a desktop notification
+//! for path changes, destination outages and loss alerts, so a trace left
+//! running in a background terminal can still get someone's attention.
+//! Requires the `desktop-notify` cargo feature (off by default).
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::EventCursor;
+use crate::state::EventKind;
+use crate::trace::receiver::SessionMap;
+
+/// How often the timeline is checked for new events
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Minimum time between path change notifications for one target,
+/// so a flapping hop doesn't flood the desktop
+const PATH_CHANGE_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Summary and body for events worth interrupting someone for
+pub fn notification_text(target: &str, kind: &EventKind) -> Option<(String, String)> {
+    if !kind.is_notable() {
+        return None;
+    }
+    let summary = match kind {
+        EventKind::PathChange { .. } => format!("ttl: path change to {}", target),
+        EventKind::OutageStart { .. } => format!("ttl: {} is down", target),
+        EventKind::OutageEnd { .. } => format!("ttl: {} is back up", target),
+        EventKind::PrefixChange { .. } => format!("ttl: BGP change on path to {}", target),
+        EventKind::ReturnTtlChange { .. } => format!("ttl: return path change from {}", target),
+        EventKind::IxChange { .. } => format!("ttl: IX change on path to {}", target),
+        EventKind::Redirect { .. } => format!("ttl: ICMP redirect for {}", target),
+        EventKind::RoutingLoop { .. } => format!("ttl: routing loop on path to {}", target),
+        EventKind::LatencySpike { .. } => format!("ttl: latency spike to {}", target),
+        _ => format!("ttl: alert for {}", target),
+    };
+    Some((summary, kind.describe()))
+}
+
+#[cfg(feature = "desktop-notify")]
+fn show(summary: &str, body: &str) {
+    // No notification daemon (e.g. over SSH) is not worth reporting
+    let _ = notify_rust::Notification::new()
+        .appname("ttl")
+        .summary(summary)
+        .body(body)
+        .show();
+}
+
+#[cfg(not(feature = "desktop-notify"))]
+fn show(_summary: &str, _body: &str) {}
+
+/// Background worker that turns new session events into desktop notifications
+pub async fn run_notify_worker(sessions: SessionMap, cancel: CancellationToken) {
+    let mut cursor = EventCursor::default();
+    let mut last_path_change: HashMap<IpAddr, Instant> = HashMap::new();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        // Poll once more after cancel so events from the last round are shown
+        let cancelled = tokio::select! {
+            _ = cancel.cancelled() => true,
+            _ = interval.tick() => false,
+        };
+        let mut pending = Vec::new();
+        for item in cursor.poll(&sessions) {
+            if matches!(item.event.kind, EventKind::PathChange { .. }) {
+                let now = Instant::now();
+                if last_path_change
+                    .get(&item.target_ip)
+                    .is_some_and(|t| now.duration_since(*t) < PATH_CHANGE_COOLDOWN)
+                {
+                    continue;
+                }
+                last_path_change.insert(item.target_ip, now);
+            }
+            pending.extend(notification_text(&item.target, &item.event.kind));
+        }
+
+        // D-Bus calls block; keep them off the async runtime
+        if !pending.is_empty() {
+            let shown = tokio::task::spawn_blocking(move || {
+                for (summary, body) in pending {
+                    show(&summary, &body);
+                }
+            });
+            if cancelled {
+                let _ = shown.await;
+            }
+        }
+        if cancelled {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_notification_text() {
+        let kind = EventKind::OutageStart { ttl: 9 };
+        let (summary, body) = notification_text("example.com", &kind).unwrap();
+        assert_eq!(summary, "ttl: example.com is down");
+        assert!(body.contains("hop 9"));
+
+        let change = EventKind::PathChange {
+            ttl: 3,
+            from: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            to: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        };
+        assert!(notification_text("example.com", &change).is_some());
+        assert!(
+            notification_text("example.com", &EventKind::DestinationReached { ttl: 9 }).is_none()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Destination up/down alerts
+//!
+//! A trace is often left running just to catch the moment the destination
+//! goes away or comes back. With `--dest-alert` that moment rings the
+//! terminal bell and/or flashes the TUI status bar, and `--dest-webhook`
+//! POSTs it as JSON. Only outage start and end count; path changes and
+//! other alerts stay with `--notify` and `--event-log`.
+
+use serde::Serialize;
+use std::io::Write;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::{EventCursor, TargetEvent};
+use crate::config::DestAlert;
+use crate::state::EventKind;
+use crate::trace::receiver::SessionMap;
+
+/// How often the timeline is checked for new events
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reachability of the destination after a transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DestState {
+    Down,
+    Up,
+}
+
+impl DestState {
+    /// The transition an event stands for, if it is one
+    pub fn from_event(kind: &EventKind) -> Option<Self> {
+        match kind {
+            EventKind::OutageStart { .. } => Some(DestState::Down),
+            EventKind::OutageEnd { .. } => Some(DestState::Up),
+            _ => None,
+        }
+    }
+}
+
+/// Short status line for a transition, e.g. "example.com is DOWN (hop 9)"
+pub fn transition_text(target: &str, kind: &EventKind) -> Option<String> {
+    match kind {
+        EventKind::OutageStart { ttl } => Some(format!("{} is DOWN (hop {})", target, ttl)),
+        EventKind::OutageEnd { lost, .. } => Some(format!(
+            "{} is back UP ({} probe{} lost)",
+            target,
+            lost,
+            if *lost == 1 { "" } else { "s" }
+        )),
+        _ => None,
+    }
+}
+
+/// JSON body POSTed to `--dest-webhook`
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    pub target: String,
+    pub target_ip: String,
+    pub state: DestState,
+    pub ttl: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lost: Option<u32>,
+    pub at: String,
+    pub message: String,
+}
+
+impl WebhookPayload {
+    pub fn new(item: &TargetEvent) -> Option<Self> {
+        let state = DestState::from_event(&item.event.kind)?;
+        let (ttl, lost) = match item.event.kind {
+            EventKind::OutageStart { ttl } => (ttl, None),
+            EventKind::OutageEnd { ttl, lost } => (ttl, Some(lost)),
+            _ => return None,
+        };
+        Some(Self {
+            target: item.target.clone(),
+            target_ip: item.target_ip.to_string(),
+            state,
+            ttl,
+            lost,
+            at: item.event.at.to_rfc3339(),
+            message: transition_text(&item.target, &item.event.kind)?,
+        })
+    }
+}
+
+/// Ring the terminal bell. Goes to stderr so `--json`/`--csv` output on
+/// stdout stays clean; in the TUI both end up on the same terminal.
+fn ring_bell() {
+    let mut stderr = std::io::stderr();
+    let _ = stderr.write_all(b"\x07");
+    let _ = stderr.flush();
+}
+
+/// Background worker for the bell and webhook actions (the flash is drawn
+/// by the TUI itself)
+pub async fn run_dest_alert_worker(
+    sessions: SessionMap,
+    alert: DestAlert,
+    cancel: CancellationToken,
+) {
+    let client = match alert.webhook {
+        Some(_) => reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(format!(
+                "ttl/{} (https://github.com/lance0/ttl)",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .ok(),
+        None => None,
+    };
+    let mut cursor = EventCursor::default();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        // Poll once more after cancel so a transition in the last round
+        // still alerts
+        let cancelled = tokio::select! {
+            _ = cancel.cancelled() => true,
+            _ = interval.tick() => false,
+        };
+        let transitions: Vec<_> = cursor
+            .poll(&sessions)
+            .into_iter()
+            .filter(|item| DestState::from_event(&item.event.kind).is_some())
+            .collect();
+        if !transitions.is_empty() && alert.bell {
+            ring_bell();
+        }
+        if let (Some(client), Some(url)) = (&client, &alert.webhook) {
+            let mut sends = Vec::new();
+            for payload in transitions.iter().filter_map(WebhookPayload::new) {
+                // A slow or failing endpoint must not hold up later alerts
+                let request = client.post(url).json(&payload);
+                sends.push(tokio::spawn(async move {
+                    let _ = request.send().await;
+                }));
+            }
+            // ...but the last ones are sent before the worker returns
+            if cancelled {
+                for send in sends {
+                    let _ = send.await;
+                }
+            }
+        }
+        if cancelled {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Event;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn test_transition_text() {
+        let down = EventKind::OutageStart { ttl: 9 };
+        assert_eq!(DestState::from_event(&down), Some(DestState::Down));
+        assert_eq!(
+            transition_text("example.com", &down).unwrap(),
+            "example.com is DOWN (hop 9)"
+        );
+        let up = EventKind::OutageEnd { ttl: 9, lost: 1 };
+        assert_eq!(
+            transition_text("example.com", &up).unwrap(),
+            "example.com is back UP (1 probe lost)"
+        );
+
+        let reached = EventKind::DestinationReached { ttl: 9 };
+        assert_eq!(DestState::from_event(&reached), None);
+        assert_eq!(transition_text("example.com", &reached), None);
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let item = TargetEvent {
+            target: "example.com".to_string(),
+            target_ip: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            event: Event::new(EventKind::OutageEnd { ttl: 9, lost: 12 }),
+        };
+        let json = serde_json::to_value(WebhookPayload::new(&item).unwrap()).unwrap();
+        assert_eq!(json["state"], "up");
+        assert_eq!(json["target_ip"], "192.0.2.1");
+        assert_eq!(json["ttl"], 9);
+        assert_eq!(json["lost"], 12);
+        assert_eq!(json["message"], "example.com is back UP (12 probes lost)");
+
+        let item = TargetEvent {
+            event: Event::new(EventKind::OutageStart { ttl: 9 }),
+            ..item
+        };
+        let json = serde_json::to_value(WebhookPayload::new(&item).unwrap()).unwrap();
+        assert_eq!(json["state"], "down");
+        assert!(json.get("lost").is_none());
+    }
+}
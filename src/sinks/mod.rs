@@ -0,0 +1,51 @@
+//! Event sinks: forward session events (path changes, outages, alerts)
+//! to places outside the TUI
+
+pub mod desktop;
+pub mod dest;
+pub mod syslog;
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::state::Event;
+use crate::trace::receiver::SessionMap;
+
+/// A new event together with the target it belongs to
+pub struct TargetEvent {
+    pub target: String,
+    pub target_ip: IpAddr,
+    pub event: Event,
+}
+
+/// Remembers how far each session's timeline has been forwarded
+#[derive(Default)]
+pub struct EventCursor {
+    cursors: HashMap<IpAddr, u64>,
+}
+
+impl EventCursor {
+    /// Collect events recorded since the last poll, across all sessions.
+    /// Only notable events (see `EventKind::is_notable`) are returned.
+    pub fn poll(&mut self, sessions: &SessionMap) -> Vec<TargetEvent> {
+        let mut out = Vec::new();
+        let sessions = sessions.read();
+        for (ip, state) in sessions.iter() {
+            let session = state.read();
+            let cursor = self.cursors.entry(*ip).or_default();
+            out.extend(
+                session
+                    .events_since(*cursor)
+                    .iter()
+                    .filter(|e| e.kind.is_notable())
+                    .map(|e| TargetEvent {
+                        target: session.target.original.clone(),
+                        target_ip: *ip,
+                        event: e.clone(),
+                    }),
+            );
+            *cursor = session.events_recorded();
+        }
+        out
+    }
+}
@@ -0,0 +1,226 @@
+//! Syslog and journald sink
+//!
+//! Writes notable session events to the local syslog socket (RFC 3164
+//! framing, logfmt body) or to journald's native socket (one field per
+//! attribute), so long-running traces feed existing log pipelines.
+
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tokio::net::UnixDatagram;
+use tokio_util::sync::CancellationToken;
+
+use super::{EventCursor, TargetEvent};
+use crate::config::EventLog;
+use crate::state::EventKind;
+use crate::trace::receiver::SessionMap;
+
+#[cfg(target_os = "macos")]
+const SYSLOG_SOCKET: &str = "/var/run/syslog";
+#[cfg(not(target_os = "macos"))]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// syslog facility "user"
+const FACILITY_USER: u8 = 1;
+
+/// How often the timeline is checked for new events
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Syslog severity for an event (warning for outages/alerts/BGP changes,
+/// routing loops and ICMP redirects, notice for forward and return path changes, IX moves and
+/// latency spikes, info for recoveries)
+fn severity(kind: &EventKind) -> u8 {
+    match kind {
+        EventKind::OutageStart { .. }
+        | EventKind::Alert { .. }
+        | EventKind::PrefixChange { .. }
+        | EventKind::RoutingLoop { .. }
+        | EventKind::DestinationExpiry { .. }
+        | EventKind::Redirect { .. } => 4,
+        EventKind::PathChange { .. }
+        | EventKind::ReturnTtlChange { .. }
+        | EventKind::IxChange { .. }
+        | EventKind::LatencySpike { .. } => 5,
+        _ => 6,
+    }
+}
+
+/// Event attributes as key/value pairs: event type, target, then the
+/// event's own fields (hop, from, to, lost, message, ...)
+fn event_fields(item: &TargetEvent) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let value = serde_json::to_value(&item.event.kind).unwrap_or_default();
+    if let Some(kind) = value.get("type").and_then(|v| v.as_str()) {
+        fields.push(("event".to_string(), kind.to_string()));
+    }
+    fields.push(("target".to_string(), item.target.clone()));
+    fields.push(("target_ip".to_string(), item.target_ip.to_string()));
+    if let Some(obj) = value.as_object() {
+        for (key, v) in obj.iter().filter(|(k, _)| *k != "type") {
+            let text = match v {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            // "ttl" alone is ambiguous next to the program name
+            let key = if key == "ttl" { "hop" } else { key.as_str() };
+            fields.push((key.to_string(), text));
+        }
+    }
+    fields
+}
+
+/// Quote a logfmt value when it contains spaces, quotes or '='
+fn logfmt_value(value: &str) -> String {
+    if value.is_empty() || value.contains([' ', '"', '=', '\\']) {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format an event as an RFC 3164 syslog line with a logfmt body
+pub fn format_syslog(item: &TargetEvent) -> String {
+    let pri = FACILITY_USER * 8 + severity(&item.event.kind);
+    let timestamp = item
+        .event
+        .at
+        .with_timezone(&chrono::Local)
+        .format("%b %e %H:%M:%S");
+    let body: Vec<String> = event_fields(item)
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", k, logfmt_value(&v)))
+        .chain(std::iter::once(format!(
+            "msg={}",
+            logfmt_value(&item.event.kind.describe())
+        )))
+        .collect();
+    format!(
+        "<{}>{} ttl[{}]: {}",
+        pri,
+        timestamp,
+        std::process::id(),
+        body.join(" ")
+    )
+}
+
+/// Format an event for journald's native protocol (newline-separated
+/// KEY=value pairs; custom fields are prefixed with TTL_)
+pub fn format_journald(item: &TargetEvent) -> String {
+    let clean = |s: &str| s.replace('\n', " ");
+    let mut out = format!(
+        "MESSAGE={}: {}\nPRIORITY={}\nSYSLOG_IDENTIFIER=ttl\n",
+        clean(&item.target),
+        clean(&item.event.kind.describe()),
+        severity(&item.event.kind)
+    );
+    for (key, value) in event_fields(item) {
+        out.push_str(&format!("TTL_{}={}\n", key.to_uppercase(), clean(&value)));
+    }
+    out
+}
+
+/// Connected log socket plus the format it expects
+pub struct EventLogger {
+    socket: UnixDatagram,
+    kind: EventLog,
+}
+
+impl EventLogger {
+    /// Connect to the local syslog or journald socket
+    pub fn connect(kind: EventLog) -> Result<Self> {
+        let path = match kind {
+            EventLog::Syslog => SYSLOG_SOCKET,
+            EventLog::Journald => JOURNALD_SOCKET,
+        };
+        let socket = UnixDatagram::unbound()?;
+        socket
+            .connect(path)
+            .with_context(|| format!("Failed to connect to {} for --event-log", path))?;
+        Ok(Self { socket, kind })
+    }
+
+    fn format(&self, item: &TargetEvent) -> String {
+        match self.kind {
+            EventLog::Syslog => format_syslog(item),
+            EventLog::Journald => format_journald(item),
+        }
+    }
+}
+
+/// Background worker that writes new notable events to syslog/journald
+pub async fn run_event_log_worker(
+    logger: EventLogger,
+    sessions: SessionMap,
+    cancel: CancellationToken,
+) {
+    let mut cursor = EventCursor::default();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        // Poll once more after cancel so events from the last round are logged
+        let cancelled = tokio::select! {
+            _ = cancel.cancelled() => true,
+            _ = interval.tick() => false,
+        };
+        for item in cursor.poll(&sessions) {
+            // A restarted log daemon shouldn't take the trace down
+            let _ = logger.socket.send(logger.format(&item).as_bytes()).await;
+        }
+        if cancelled {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Event;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn path_change() -> TargetEvent {
+        TargetEvent {
+            target: "example.com".to_string(),
+            target_ip: IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+            event: Event::new(EventKind::PathChange {
+                ttl: 4,
+                from: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                to: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_format_syslog() {
+        let line = format_syslog(&path_change());
+        assert!(line.starts_with("<13>"));
+        assert!(line.contains(" ttl["));
+        assert!(line.contains(
+            "event=path_change target=example.com target_ip=93.184.216.34 from=10.0.0.1 to=10.0.0.2 hop=4"
+        ));
+        assert!(line.ends_with("msg=\"Path change at hop 4: 10.0.0.1 -> 10.0.0.2\""));
+    }
+
+    #[test]
+    fn test_format_journald() {
+        let mut item = path_change();
+        item.event = Event::new(EventKind::Alert {
+            message: "loss\nhigh".to_string(),
+        });
+        let payload = format_journald(&item);
+        assert!(payload.contains("PRIORITY=4\n"));
+        assert!(payload.contains("SYSLOG_IDENTIFIER=ttl\n"));
+        assert!(payload.contains("TTL_EVENT=alert\n"));
+        assert!(payload.contains("TTL_MESSAGE=loss high\n"));
+        // Every line is a single KEY=value pair
+        assert!(payload.lines().all(|l| l.contains('=')));
+    }
+
+    #[test]
+    fn test_logfmt_value() {
+        assert_eq!(logfmt_value("plain"), "plain");
+        assert_eq!(logfmt_value("two words"), "\"two words\"");
+        assert_eq!(logfmt_value("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+}
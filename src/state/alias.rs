@@ -0,0 +1,104 @@
+//! Responder aliases
+//!
+//! A router can answer from a different interface address depending on
+//! which link the probe arrived on, and CGNAT or anycast front ends rotate
+//! through a pool. Each address would otherwise get its own stats at the
+//! hop, splitting the counts and making one router look like ECMP. An
+//! alias groups addresses (or whole prefixes) under one name so they are
+//! tracked as a single responder.
+
+use ipnetwork::IpNetwork;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::net::IpAddr;
+
+/// Named group of responder addresses that belong to one device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponderAlias {
+    pub name: String,
+    /// Addresses or prefixes, e.g. "10.0.0.1" or "100.64.0.0/10"
+    #[serde(serialize_with = "ser_networks", deserialize_with = "de_networks")]
+    pub networks: Vec<IpNetwork>,
+}
+
+impl ResponderAlias {
+    /// Parse `NAME=ADDR[,ADDR...]`, where each ADDR may be a prefix
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, addrs) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("alias '{}' should look like NAME=ADDR[,ADDR...]", spec))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("alias '{}' needs a name", spec));
+        }
+        let networks = parse_networks(addrs.split(','))?;
+        if networks.is_empty() {
+            return Err(format!("alias '{}' lists no addresses", name));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            networks,
+        })
+    }
+
+    pub fn matches(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|net| net.contains(ip))
+    }
+}
+
+/// First alias covering `ip`
+pub fn find_alias(aliases: &[ResponderAlias], ip: IpAddr) -> Option<&ResponderAlias> {
+    aliases.iter().find(|alias| alias.matches(ip))
+}
+
+fn parse_networks<'a>(addrs: impl IntoIterator<Item = &'a str>) -> Result<Vec<IpNetwork>, String> {
+    addrs
+        .into_iter()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            a.parse::<IpNetwork>()
+                .map_err(|_| format!("invalid alias address '{}'", a))
+        })
+        .collect()
+}
+
+fn ser_networks<S: Serializer>(networks: &[IpNetwork], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(networks.iter().map(|n| n.to_string()))
+}
+
+fn de_networks<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<IpNetwork>, D::Error> {
+    let addrs = Vec::<String>::deserialize(deserializer)?;
+    parse_networks(addrs.iter().map(String::as_str)).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_alias() {
+        let alias = ResponderAlias::parse("core-1 = 10.0.0.1, 192.0.2.0/30").unwrap();
+        assert_eq!(alias.name, "core-1");
+        assert!(alias.matches("10.0.0.1".parse().unwrap()));
+        assert!(alias.matches("192.0.2.3".parse().unwrap()));
+        assert!(!alias.matches("192.0.2.4".parse().unwrap()));
+
+        assert!(ResponderAlias::parse("10.0.0.1").is_err());
+        assert!(ResponderAlias::parse("=10.0.0.1").is_err());
+        assert!(ResponderAlias::parse("x=").is_err());
+        assert!(ResponderAlias::parse("x=not-an-ip").is_err());
+    }
+
+    #[test]
+    fn test_alias_serde_roundtrip() {
+        let alias = ResponderAlias::parse("edge=2001:db8::/64,198.51.100.7").unwrap();
+        let json = serde_json::to_string(&alias).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"edge","networks":["2001:db8::/64","198.51.100.7/32"]}"#
+        );
+        let back: ResponderAlias = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, alias);
+        assert!(find_alias(&[back], "198.51.100.7".parse().unwrap()).is_some());
+    }
+}
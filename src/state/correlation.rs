@@ -0,0 +1,216 @@
+//! Loss correlation between hops
+//!
+//! Loss at two hops can share one cause or have two. A link that drops
+//! packets makes every hop beyond it lose the same rounds; a router that
+//! rate limits its own replies loses rounds of its own. Each round's
+//! outcome is kept per TTL, keyed by probe sequence number and flow, and
+//! for each pair of lossy hops the phi coefficient of their losses is taken
+//! over the rounds both were probed in: near 1 means the same rounds
+//! dropped, near 0 unrelated loss.
+
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Rounds (per flow) kept. Well under the 256 sequence numbers, so a
+/// round is gone before its number comes around again.
+const LOSS_ROUNDS: usize = 120;
+/// Losses a hop needs in the window before it is correlated
+const MIN_LOSSES: usize = 3;
+/// Phi at or above which two hops count as losing the same rounds
+pub const SHARED_LOSS_PHI: f64 = 0.7;
+
+/// How the losses at two hops line up
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LossCorrelation {
+    pub ttl_a: u8,
+    pub ttl_b: u8,
+    /// Rounds both hops were probed in
+    pub rounds: usize,
+    pub lost_a: usize,
+    pub lost_b: usize,
+    /// Rounds lost at both
+    pub lost_both: usize,
+    /// Phi coefficient of the two loss series (-1 to 1)
+    pub phi: f64,
+}
+
+impl LossCorrelation {
+    /// Whether the two hops mostly drop the same rounds
+    pub fn shared(&self) -> bool {
+        self.phi >= SHARED_LOSS_PHI
+    }
+
+    /// e.g. "hops 7 and 12: 9 of 10/11 lost rounds shared (phi 0.86)"
+    pub fn describe(&self) -> String {
+        format!(
+            "hops {} and {}: {} of {}/{} lost rounds shared (phi {:.2})",
+            self.ttl_a, self.ttl_b, self.lost_both, self.lost_a, self.lost_b, self.phi
+        )
+    }
+}
+
+/// Outcome at each TTL (true = lost) of the latest rounds, keyed by probe
+/// sequence number and flow
+#[derive(Debug, Clone, Default)]
+pub struct RoundLoss {
+    rounds: VecDeque<((u8, u8), BTreeMap<u8, bool>)>,
+}
+
+impl RoundLoss {
+    /// Record the probe at `ttl` in round `seq` of `flow` as answered or lost
+    pub fn record(&mut self, seq: u8, flow: u8, ttl: u8, lost: bool) {
+        let key = (seq, flow);
+        let round = match self.rounds.iter().position(|(k, _)| *k == key) {
+            Some(idx) => &mut self.rounds[idx].1,
+            None => {
+                if self.rounds.len() == LOSS_ROUNDS {
+                    self.rounds.pop_front();
+                }
+                self.rounds.push_back((key, BTreeMap::new()));
+                &mut self.rounds.back_mut().expect("just pushed").1
+            }
+        };
+        round.insert(ttl, lost);
+    }
+
+    pub fn clear(&mut self) {
+        self.rounds.clear();
+    }
+
+    /// Correlation of every pair of hops with enough losses, in TTL order.
+    /// A hop that lost every round it was probed in has no variation to
+    /// correlate and is left out.
+    pub fn correlate(&self) -> Vec<LossCorrelation> {
+        let mut probed: BTreeMap<u8, (usize, usize)> = BTreeMap::new();
+        for (_, round) in &self.rounds {
+            for (&ttl, &lost) in round {
+                let (n, losses) = probed.entry(ttl).or_default();
+                *n += 1;
+                *losses += usize::from(lost);
+            }
+        }
+        let lossy: Vec<u8> = probed
+            .into_iter()
+            .filter(|&(_, (n, losses))| losses >= MIN_LOSSES && losses < n)
+            .map(|(ttl, _)| ttl)
+            .collect();
+
+        let mut pairs = Vec::new();
+        for (i, &a) in lossy.iter().enumerate() {
+            for &b in &lossy[i + 1..] {
+                if let Some(pair) = self.pair(a, b) {
+                    pairs.push(pair);
+                }
+            }
+        }
+        pairs
+    }
+
+    fn pair(&self, ttl_a: u8, ttl_b: u8) -> Option<LossCorrelation> {
+        let (mut rounds, mut lost_a, mut lost_b, mut lost_both) = (0, 0, 0, 0);
+        for (_, round) in &self.rounds {
+            let (Some(&a), Some(&b)) = (round.get(&ttl_a), round.get(&ttl_b)) else {
+                continue;
+            };
+            rounds += 1;
+            lost_a += usize::from(a);
+            lost_b += usize::from(b);
+            lost_both += usize::from(a && b);
+        }
+        let n = rounds as f64;
+        let (la, lb) = (lost_a as f64, lost_b as f64);
+        let spread = la * (n - la) * lb * (n - lb);
+        if spread <= 0.0 {
+            return None;
+        }
+        Some(LossCorrelation {
+            ttl_a,
+            ttl_b,
+            rounds,
+            lost_a,
+            lost_b,
+            lost_both,
+            phi: (n * lost_both as f64 - la * lb) / spread.sqrt(),
+        })
+    }
+
+    /// No pair to report
+    pub fn is_uncorrelated(&self) -> bool {
+        self.correlate().is_empty()
+    }
+}
+
+/// Session JSON carries the correlations rather than the raw rounds
+pub fn serialize_correlations<S: Serializer>(
+    rounds: &RoundLoss,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    rounds.correlate().serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rounds `0..n`, with each TTL lost in the rounds listed for it
+    fn rounds(n: u8, lost: &[(u8, &[u8])]) -> RoundLoss {
+        let mut loss = RoundLoss::default();
+        for seq in 0..n {
+            for &(ttl, dropped) in lost {
+                loss.record(seq, 0, ttl, dropped.contains(&seq));
+            }
+        }
+        loss
+    }
+
+    #[test]
+    fn test_shared_loss_correlates() {
+        // Hop 12 loses every round hop 7 does, plus one of its own
+        let loss = rounds(
+            40,
+            &[(7, &[3, 9, 15, 22, 30]), (12, &[3, 9, 15, 22, 30, 35])],
+        );
+        let pairs = loss.correlate();
+        assert_eq!(pairs.len(), 1);
+        let pair = &pairs[0];
+        assert_eq!((pair.ttl_a, pair.ttl_b), (7, 12));
+        assert_eq!(
+            (pair.rounds, pair.lost_a, pair.lost_b, pair.lost_both),
+            (40, 5, 6, 5)
+        );
+        assert!(pair.shared(), "{}", pair.describe());
+    }
+
+    #[test]
+    fn test_independent_loss_does_not() {
+        let loss = rounds(40, &[(4, &[1, 5, 9, 13]), (9, &[2, 6, 10, 14])]);
+        let pair = &loss.correlate()[0];
+        assert_eq!(pair.lost_both, 0);
+        assert!(pair.phi < 0.0);
+        assert!(!pair.shared());
+    }
+
+    #[test]
+    fn test_quiet_and_silent_hops_skipped() {
+        // Hop 2 lost too little, hop 5 never answers
+        let all: Vec<u8> = (0..20).collect();
+        let loss = rounds(20, &[(2, &[4, 8]), (3, &[1, 6, 11]), (5, &all)]);
+        assert!(loss.correlate().is_empty());
+        assert!(loss.is_uncorrelated());
+    }
+
+    #[test]
+    fn test_rounds_keyed_by_flow_and_capped() {
+        let mut loss = RoundLoss::default();
+        loss.record(1, 0, 3, true);
+        loss.record(1, 1, 3, false);
+        assert_eq!(loss.rounds.len(), 2);
+
+        for seq in 0..=255u8 {
+            loss.record(seq, 0, 4, false);
+        }
+        assert_eq!(loss.rounds.len(), LOSS_ROUNDS);
+        loss.clear();
+        assert!(loss.rounds.is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+//! Great-circle distance and the speed-of-light RTT floor
+//!
+//! Light in fiber covers about 200 km per millisecond, so a responder
+//! `d` km away can't answer in less than `2d / 200` ms however direct the
+//! path. A measured RTT several times that floor means the path detours
+//! (or the responder isn't where GeoIP puts it). Distances are measured
+//! from the origin: `--origin`, else the geolocated source address, else
+//! the first hop with a location.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+use super::GeoInfo;
+
+/// Mean Earth radius
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// One-way distance light covers in fiber per millisecond (about 2/3 c)
+const FIBER_KM_PER_MS: f64 = 200.0;
+
+/// A position on the globe, in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// Parse `LAT,LON` in decimal degrees, e.g. `52.52,13.40`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid position '{}', expected LAT,LON e.g. 52.52,13.40",
+                spec
+            )
+        };
+        let (lat, lon) = spec.split_once(',').ok_or_else(invalid)?;
+        let lat: f64 = lat.trim().parse().map_err(|_| invalid())?;
+        let lon: f64 = lon.trim().parse().map_err(|_| invalid())?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(format!(
+                "Position '{}' out of range (latitude -90..90, longitude -180..180)",
+                spec
+            ));
+        }
+        Ok(Self { lat, lon })
+    }
+
+    /// Location of a GeoIP record, when it has coordinates
+    pub fn from_geo(geo: &GeoInfo) -> Option<Self> {
+        Some(Self {
+            lat: geo.latitude?,
+            lon: geo.longitude?,
+        })
+    }
+
+    /// Great-circle (haversine) distance in kilometres
+    pub fn distance_km(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+}
+
+impl fmt::Display for GeoPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2},{:.2}", self.lat, self.lon)
+    }
+}
+
+/// Where the origin position came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginSource {
+    /// `--origin` or `origin` in config.toml
+    Configured,
+    /// GeoIP location of the probe source address
+    SourceAddress,
+    /// GeoIP location of the first hop that has one
+    FirstHop(u8),
+}
+
+impl fmt::Display for OriginSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OriginSource::Configured => f.write_str("configured"),
+            OriginSource::SourceAddress => f.write_str("source address"),
+            OriginSource::FirstHop(ttl) => write!(f, "hop {}", ttl),
+        }
+    }
+}
+
+/// Shortest possible RTT to something `km` away (there and back in fiber)
+pub fn min_rtt(km: f64) -> Duration {
+    Duration::from_secs_f64(2.0 * km / FIBER_KM_PER_MS / 1000.0)
+}
+
+/// Distance from the origin to a responder and the RTT floor it implies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HopDistance {
+    pub km: f64,
+    pub min_rtt: Duration,
+}
+
+impl HopDistance {
+    pub fn between(origin: &GeoPoint, to: &GeoPoint) -> Self {
+        let km = origin.distance_km(to);
+        Self {
+            km,
+            min_rtt: min_rtt(km),
+        }
+    }
+
+    /// Measured RTT as a multiple of the floor (None when too close to tell)
+    pub fn inflation(&self, measured: Duration) -> Option<f64> {
+        (self.min_rtt >= Duration::from_micros(100))
+            .then(|| measured.as_secs_f64() / self.min_rtt.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_geo_point() {
+        let p = GeoPoint::parse(" 52.52, 13.40 ").unwrap();
+        assert_eq!(
+            p,
+            GeoPoint {
+                lat: 52.52,
+                lon: 13.4
+            }
+        );
+        assert_eq!(p.to_string(), "52.52,13.40");
+        assert!(GeoPoint::parse("52.52").is_err());
+        assert!(GeoPoint::parse("north,east").is_err());
+        assert!(GeoPoint::parse("91,0").is_err());
+        assert!(GeoPoint::parse("0,181").is_err());
+    }
+
+    #[test]
+    fn test_distance_and_min_rtt() {
+        let berlin = GeoPoint {
+            lat: 52.52,
+            lon: 13.40,
+        };
+        let new_york = GeoPoint {
+            lat: 40.71,
+            lon: -74.01,
+        };
+        let km = berlin.distance_km(&new_york);
+        assert!((km - 6385.0).abs() < 10.0, "{}", km);
+        assert_eq!(berlin.distance_km(&berlin), 0.0);
+
+        let d = HopDistance::between(&berlin, &new_york);
+        assert_eq!(d.min_rtt.as_millis(), 63);
+        let x = d.inflation(Duration::from_millis(95)).unwrap();
+        assert!((x - 1.49).abs() < 0.01, "{}", x);
+
+        // Same city: the floor is too small to compare against
+        assert_eq!(
+            HopDistance::between(&berlin, &berlin).inflation(Duration::from_millis(1)),
+            None
+        );
+    }
+}
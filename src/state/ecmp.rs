@@ -0,0 +1,111 @@
+//! ECMP width estimation from multi-flow probing
+//!
+//! With `--flows N` each flow hashes onto one of a hop's equal-cost next
+//! hops and stays there. The distinct primary responders across flows are a
+//! lower bound on the hop's ECMP width; whether that is all of them depends
+//! on how many flows answered. Following the Multipath Detection Algorithm
+//! (Augustin et al.), if the hop really had K+1 evenly loaded next hops, n
+//! flows would miss one of them with probability at most (K+1)·(K/(K+1))^n.
+//! One minus that bound is the confidence that the K seen are all there are.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+/// Estimated number of next hops at one TTL
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EcmpEstimate {
+    /// Distinct primary responders across flows
+    pub width: usize,
+    /// Flows that have a primary responder at this hop
+    pub flows: usize,
+    /// Chance that no further next hop went unseen (0.0-1.0)
+    pub confidence: f64,
+}
+
+impl EcmpEstimate {
+    /// Below this the badge says the width may be higher
+    pub const CONFIDENT: f64 = 0.95;
+
+    /// Estimate from each answering flow's primary responder; None until
+    /// two flows have answered
+    pub fn from_primaries(primaries: impl IntoIterator<Item = IpAddr>) -> Option<Self> {
+        let mut flows = 0;
+        let mut seen = HashSet::new();
+        for ip in primaries {
+            flows += 1;
+            seen.insert(ip);
+        }
+        if flows < 2 {
+            return None;
+        }
+        Some(Self {
+            width: seen.len(),
+            flows,
+            confidence: confidence(seen.len(), flows),
+        })
+    }
+
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= Self::CONFIDENT
+    }
+
+    /// "ECMP ×N" for a hop that load-balances, with a "+" while more next
+    /// hops may be hiding
+    pub fn badge(&self) -> Option<String> {
+        (self.width > 1).then(|| {
+            let more = if self.is_confident() { "" } else { "+" };
+            format!("ECMP ×{}{}", self.width, more)
+        })
+    }
+}
+
+/// Confidence that `width` next hops seen over `flows` flows are all of them
+pub fn confidence(width: usize, flows: usize) -> f64 {
+    if width == 0 {
+        return 0.0;
+    }
+    let k = width as f64;
+    let miss = (k + 1.0) * (k / (k + 1.0)).powi(flows.min(i32::MAX as usize) as i32);
+    (1.0 - miss).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, last])
+    }
+
+    #[test]
+    fn test_confidence_grows_with_flows() {
+        // One next hop over 2 flows: a second would be missed half the time
+        assert!((confidence(1, 2) - 0.5).abs() < 1e-9);
+        assert!((confidence(1, 8) - 0.9921875).abs() < 1e-9);
+        assert!(confidence(2, 4) < confidence(2, 8));
+        // Seeing more next hops takes more flows to be sure of
+        assert!(confidence(4, 8) < confidence(2, 8));
+        assert_eq!(confidence(8, 8), 0.0);
+        assert_eq!(confidence(0, 8), 0.0);
+    }
+
+    #[test]
+    fn test_estimate_from_primaries() {
+        assert_eq!(EcmpEstimate::from_primaries([ip(1)]), None);
+
+        let single = EcmpEstimate::from_primaries([ip(1); 16]).unwrap();
+        assert_eq!((single.width, single.flows), (1, 16));
+        assert!(single.is_confident());
+        assert_eq!(single.badge(), None);
+
+        let split = EcmpEstimate::from_primaries([ip(1), ip(2), ip(1), ip(2)]).unwrap();
+        assert_eq!(split.width, 2);
+        assert_eq!(split.badge().as_deref(), Some("ECMP ×2+"));
+
+        let flows: Vec<_> = (0..16).map(|i| ip(i % 2)).collect();
+        let split = EcmpEstimate::from_primaries(flows).unwrap();
+        assert!(split.is_confident());
+        assert_eq!(split.badge().as_deref(), Some("ECMP ×2"));
+    }
+}
@@ -0,0 +1,173 @@
+//! ECN bleaching detection (`--ecn`)
+//!
+//! With `--ecn` probes carry an ECN-capable transport codepoint in the low
+//! two bits of the IP ToS / traffic class byte. An ICMP error quotes the
+//! probe's IP header as the responder received it, so the quoted codepoint
+//! shows whether the marking survived to each hop. A middlebox that zeroes
+//! the bits ("bleaching") breaks ECN for every flow through it; a hop
+//! quoting CE only means a router on the way marked congestion.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// ECN field of an IP header (RFC 3168)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EcnCodepoint {
+    NotEct,
+    Ect1,
+    Ect0,
+    Ce,
+}
+
+impl EcnCodepoint {
+    /// Codepoint in the low two bits of a ToS / traffic class byte
+    pub fn from_tos(tos: u8) -> Self {
+        match tos & 0b11 {
+            0b00 => EcnCodepoint::NotEct,
+            0b01 => EcnCodepoint::Ect1,
+            0b10 => EcnCodepoint::Ect0,
+            _ => EcnCodepoint::Ce,
+        }
+    }
+
+    pub fn bits(&self) -> u8 {
+        match self {
+            EcnCodepoint::NotEct => 0b00,
+            EcnCodepoint::Ect1 => 0b01,
+            EcnCodepoint::Ect0 => 0b10,
+            EcnCodepoint::Ce => 0b11,
+        }
+    }
+
+    /// Codepoint for `--ecn`: "ect0" or "ect1"
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "ect0" | "ect(0)" => Ok(EcnCodepoint::Ect0),
+            "ect1" | "ect(1)" => Ok(EcnCodepoint::Ect1),
+            _ => Err(format!("Invalid ECN codepoint '{}' (ect0, ect1)", name)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EcnCodepoint::NotEct => "Not-ECT",
+            EcnCodepoint::Ect1 => "ECT(1)",
+            EcnCodepoint::Ect0 => "ECT(0)",
+            EcnCodepoint::Ce => "CE",
+        }
+    }
+}
+
+/// ECN codepoints quoted back in a hop's ICMP errors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcnInfo {
+    /// Codepoint the probes were sent with
+    pub sent: EcnCodepoint,
+    /// Quoted codepoints and how often each was seen
+    pub seen: BTreeMap<EcnCodepoint, u64>,
+}
+
+impl EcnInfo {
+    /// Quotes needed before a hop's codepoint counts
+    pub const MIN_SAMPLES: u64 = 3;
+
+    pub fn new(sent: EcnCodepoint) -> Self {
+        Self {
+            sent,
+            seen: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, sent: EcnCodepoint, quoted: EcnCodepoint) {
+        self.sent = sent;
+        *self.seen.entry(quoted).or_default() += 1;
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.seen.values().sum()
+    }
+
+    /// Quotes that came back marked CE
+    pub fn congestion_marks(&self) -> u64 {
+        self.seen.get(&EcnCodepoint::Ce).copied().unwrap_or(0)
+    }
+
+    /// Codepoint most of the quotes carried, once there are enough of them.
+    /// CE counts as the ECT codepoint it was set over, so congestion marks
+    /// alone don't read as a change of marking.
+    pub fn observed(&self) -> Option<EcnCodepoint> {
+        if self.samples() < Self::MIN_SAMPLES {
+            return None;
+        }
+        let mut counts: BTreeMap<EcnCodepoint, u64> = BTreeMap::new();
+        for (&codepoint, &count) in &self.seen {
+            let codepoint = if codepoint == EcnCodepoint::Ce {
+                self.sent
+            } else {
+                codepoint
+            };
+            *counts.entry(codepoint).or_default() += count;
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(codepoint, count)| (count, std::cmp::Reverse(codepoint)))
+            .map(|(codepoint, _)| codepoint)
+    }
+}
+
+/// Point where the ECN marking changed: the responder at `ttl` received
+/// `to` where the hop before it (or the sender) had `from`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcnChange {
+    pub ttl: u8,
+    pub from: EcnCodepoint,
+    pub to: EcnCodepoint,
+}
+
+impl EcnChange {
+    /// The marking was cleared rather than rewritten
+    pub fn bleached(&self) -> bool {
+        self.to == EcnCodepoint::NotEct
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codepoints() {
+        for codepoint in [
+            EcnCodepoint::NotEct,
+            EcnCodepoint::Ect1,
+            EcnCodepoint::Ect0,
+            EcnCodepoint::Ce,
+        ] {
+            // DSCP bits above don't matter
+            assert_eq!(
+                EcnCodepoint::from_tos(46 << 2 | codepoint.bits()),
+                codepoint
+            );
+        }
+        assert_eq!(EcnCodepoint::parse("ECT1"), Ok(EcnCodepoint::Ect1));
+        assert!(EcnCodepoint::parse("ce").is_err());
+    }
+
+    #[test]
+    fn test_observed_ignores_congestion_marks() {
+        let mut info = EcnInfo::new(EcnCodepoint::Ect0);
+        info.record(EcnCodepoint::Ect0, EcnCodepoint::Ce);
+        info.record(EcnCodepoint::Ect0, EcnCodepoint::Ce);
+        assert_eq!(info.observed(), None);
+        info.record(EcnCodepoint::Ect0, EcnCodepoint::NotEct);
+        // Two CE marks still mean the marking arrived
+        assert_eq!(info.observed(), Some(EcnCodepoint::Ect0));
+        assert_eq!(info.congestion_marks(), 2);
+
+        for _ in 0..3 {
+            info.record(EcnCodepoint::Ect0, EcnCodepoint::NotEct);
+        }
+        assert_eq!(info.observed(), Some(EcnCodepoint::NotEct));
+    }
+}
@@ -0,0 +1,68 @@
+//! Per-hop statistics archived at a path change
+//!
+//! With `--reset-on-path-change`, a confirmed path change at hop N clears
+//! the statistics of hop N and every hop after it, so the numbers shown
+//! afterwards describe the new path only. What was cleared is kept here as
+//! one `PathEpoch` per change: a summary of each affected hop while the old
+//! path was in use.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use super::session::Hop;
+
+/// Epochs kept per session; the oldest are dropped first
+pub const MAX_PATH_EPOCHS: usize = 20;
+
+/// Statistics of one hop over an epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HopSnapshot {
+    pub ttl: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary: Option<IpAddr>,
+    pub sent: u64,
+    pub received: u64,
+    pub loss_pct: f64,
+    /// Primary responder's RTTs in milliseconds (None without replies)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter_ms: Option<f64>,
+}
+
+impl HopSnapshot {
+    pub fn of(hop: &Hop) -> Self {
+        let stats = hop.primary_stats().filter(|s| s.received > 0);
+        let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+        Self {
+            ttl: hop.ttl,
+            primary: hop.primary,
+            sent: hop.sent,
+            received: hop.received,
+            loss_pct: hop.loss_pct(),
+            avg_ms: stats.map(|s| ms(s.avg_rtt())),
+            min_ms: stats.map(|s| ms(s.min_rtt)),
+            max_ms: stats.map(|s| ms(s.max_rtt)),
+            jitter_ms: stats.map(|s| ms(s.jitter_avg())),
+        }
+    }
+}
+
+/// Statistics of the hops a path change reset, from the start of the
+/// session (or the previous reset) up to the change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathEpoch {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Hop where the path changed, and the old and new responder there
+    pub ttl: u8,
+    pub from: IpAddr,
+    pub to: IpAddr,
+    /// Hops that had been probed, from `ttl` on
+    pub hops: Vec<HopSnapshot>,
+}
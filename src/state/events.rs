@@ -0,0 +1,330 @@
+use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use super::spike::SpikeCause;
+
+/// Maximum number of events kept per session (oldest are dropped first)
+pub const MAX_EVENTS: usize = 500;
+
+/// Consecutive destination timeouts before an outage is declared
+pub const OUTAGE_THRESHOLD: u32 = 3;
+
+/// Probes in the recent window before a loss alert can fire
+pub const LOSS_ALERT_MIN_SAMPLES: usize = 10;
+
+/// How the BGP prefix covering a responder changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefixChangeKind {
+    /// A longer prefix inside the old one now covers the address
+    /// (typical of hijacks, leaks and traffic engineering)
+    MoreSpecific,
+    /// The more-specific was withdrawn; a covering prefix is left
+    LessSpecific,
+    /// Same prefix, different origin AS
+    OriginChange,
+    /// An unrelated prefix now covers the address
+    Moved,
+}
+
+impl PrefixChangeKind {
+    /// Classify a change between two prefixes in CIDR notation
+    pub fn classify(
+        old_prefix: &str,
+        old_origins: &[u32],
+        new_prefix: &str,
+        new_origins: &[u32],
+    ) -> Option<Self> {
+        if old_prefix == new_prefix {
+            return (old_origins != new_origins).then_some(Self::OriginChange);
+        }
+        let (Ok(old), Ok(new)) = (
+            old_prefix.parse::<IpNetwork>(),
+            new_prefix.parse::<IpNetwork>(),
+        ) else {
+            return Some(Self::Moved);
+        };
+        Some(
+            if new.prefix() > old.prefix() && old.contains(new.network()) {
+                Self::MoreSpecific
+            } else if new.prefix() < old.prefix() && new.contains(old.network()) {
+                Self::LessSpecific
+            } else {
+                Self::Moved
+            },
+        )
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::MoreSpecific => "more-specific",
+            Self::LessSpecific => "more-specific withdrawn",
+            Self::OriginChange => "origin change",
+            Self::Moved => "prefix change",
+        }
+    }
+}
+
+/// Something notable that happened during a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    /// Primary responder at a hop changed (route flap)
+    PathChange { ttl: u8, from: IpAddr, to: IpAddr },
+    /// Destination stopped answering
+    OutageStart { ttl: u8 },
+    /// Destination answered again after an outage
+    OutageEnd { ttl: u8, lost: u32 },
+    /// First reply from the destination
+    DestinationReached { ttl: u8 },
+    /// Probing parameters changed (pause, resume, reset, ...)
+    ConfigChange { description: String },
+    /// Detection or threshold alert
+    Alert { message: String },
+    /// BGP prefix or origin covering a hop's responder changed
+    /// (`from`/`to` read like "192.0.2.0/24 AS64500")
+    PrefixChange {
+        ttl: u8,
+        ip: IpAddr,
+        change: PrefixChangeKind,
+        from: String,
+        to: String,
+    },
+    /// IP TTL of a responder's replies settled on a new value: the reverse
+    /// path changed length
+    ReturnTtlChange {
+        ttl: u8,
+        ip: IpAddr,
+        from: u8,
+        to: u8,
+    },
+    /// A path change moved a hop onto or off an Internet Exchange
+    /// (`None` is a hop outside any known IX, e.g. private peering)
+    IxChange {
+        ttl: u8,
+        ip: IpAddr,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// The same routers answered at several increasing TTLs in one round:
+    /// probes are going round in a forwarding loop
+    RoutingLoop {
+        first_ttl: u8,
+        last_ttl: u8,
+        routers: Vec<IpAddr>,
+    },
+    /// The destination itself sent Time Exceeded: the probe reached its
+    /// address with TTL left to expire, then was forwarded on
+    DestinationExpiry { ttl: u8, ip: IpAddr },
+    /// An ICMP Redirect from the first-hop router, naming a better gateway
+    /// for the target (often a local routing misconfiguration)
+    Redirect { router: IpAddr, gateway: IpAddr },
+    /// The destination's RTT jumped well above its recent median, with
+    /// the probable cause (`cause_ttl` is the hop it points at)
+    LatencySpike {
+        ttl: u8,
+        baseline_ms: f64,
+        rtt_ms: f64,
+        cause: SpikeCause,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cause_ttl: Option<u8>,
+        /// Destination probes were also being lost
+        #[serde(default)]
+        loss_burst: bool,
+    },
+}
+
+impl EventKind {
+    /// Whether the event is worth forwarding to notification and log sinks
+    /// (path changes, outages and alerts; not routine state changes)
+    pub fn is_notable(&self) -> bool {
+        !matches!(
+            self,
+            EventKind::DestinationReached { .. } | EventKind::ConfigChange { .. }
+        )
+    }
+
+    /// One-line human readable description
+    pub fn describe(&self) -> String {
+        match self {
+            EventKind::PathChange { ttl, from, to } => {
+                format!("Path change at hop {}: {} -> {}", ttl, from, to)
+            }
+            EventKind::OutageStart { ttl } => {
+                format!("Outage started (destination at hop {} not answering)", ttl)
+            }
+            EventKind::OutageEnd { ttl, lost } => {
+                format!("Outage ended at hop {} ({} probes lost)", ttl, lost)
+            }
+            EventKind::DestinationReached { ttl } => {
+                format!("Destination reached at hop {}", ttl)
+            }
+            EventKind::ConfigChange { description } => format!("Config: {}", description),
+            EventKind::Alert { message } => format!("Alert: {}", message),
+            EventKind::PrefixChange {
+                ttl,
+                ip,
+                change,
+                from,
+                to,
+            } => format!(
+                "BGP {} at hop {} ({}): {} -> {}",
+                change.label(),
+                ttl,
+                ip,
+                from,
+                to
+            ),
+            EventKind::ReturnTtlChange { ttl, ip, from, to } => format!(
+                "Return path change at hop {} ({}): reply TTL {} -> {}",
+                ttl, ip, from, to
+            ),
+            EventKind::IxChange { ttl, ip, from, to } => match (from, to) {
+                (None, Some(to)) => format!("Path moved onto IX {} at hop {} ({})", to, ttl, ip),
+                (Some(from), None) => {
+                    format!("Path left IX {} at hop {} (now {})", from, ttl, ip)
+                }
+                (from, to) => format!(
+                    "IX change at hop {} ({}): {} -> {}",
+                    ttl,
+                    ip,
+                    from.as_deref().unwrap_or("none"),
+                    to.as_deref().unwrap_or("none")
+                ),
+            },
+            EventKind::Redirect { router, gateway } => {
+                format!("ICMP redirect from {}: use gateway {}", router, gateway)
+            }
+            EventKind::RoutingLoop {
+                first_ttl,
+                last_ttl,
+                routers,
+            } => {
+                let routers: Vec<_> = routers.iter().map(|ip| ip.to_string()).collect();
+                format!(
+                    "Routing loop at hops {}-{} between {}",
+                    first_ttl,
+                    last_ttl,
+                    routers.join(", ")
+                )
+            }
+            EventKind::LatencySpike {
+                ttl,
+                baseline_ms,
+                rtt_ms,
+                cause,
+                cause_ttl,
+                loss_burst,
+            } => format!(
+                "Latency spike at hop {}: {:.1} -> {:.1} ms, probable cause: {}{}",
+                ttl,
+                baseline_ms,
+                rtt_ms,
+                cause.label(*cause_ttl),
+                if *loss_burst { " (with loss)" } else { "" }
+            ),
+            EventKind::DestinationExpiry { ttl, ip } => format!(
+                "TTL expired at the destination {} (hop {}): it forwarded the probe on",
+                ip, ttl
+            ),
+        }
+    }
+}
+
+/// Timestamped session event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl Event {
+    pub fn new(kind: EventKind) -> Self {
+        Self {
+            at: Utc::now(),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_event_json_shape() {
+        let event = Event::new(EventKind::PathChange {
+            ttl: 4,
+            from: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            to: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "path_change");
+        assert_eq!(json["ttl"], 4);
+        assert!(json["at"].is_string());
+
+        let restored: Event = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.kind, event.kind);
+    }
+
+    #[test]
+    fn test_classify_prefix_change() {
+        use PrefixChangeKind::*;
+        let classify = PrefixChangeKind::classify;
+        assert_eq!(classify("10.0.0.0/16", &[1], "10.0.0.0/16", &[1]), None);
+        assert_eq!(
+            classify("10.0.0.0/16", &[1], "10.0.0.0/16", &[2]),
+            Some(OriginChange)
+        );
+        assert_eq!(
+            classify("10.0.0.0/16", &[1], "10.0.4.0/24", &[2]),
+            Some(MoreSpecific)
+        );
+        assert_eq!(
+            classify("10.0.4.0/24", &[2], "10.0.0.0/16", &[1]),
+            Some(LessSpecific)
+        );
+        assert_eq!(
+            classify("10.0.0.0/16", &[1], "192.0.2.0/24", &[1]),
+            Some(Moved)
+        );
+        assert_eq!(
+            classify("2001:db8::/32", &[1], "2001:db8:1::/48", &[1]),
+            Some(MoreSpecific)
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let kind = EventKind::OutageEnd { ttl: 9, lost: 5 };
+        assert_eq!(kind.describe(), "Outage ended at hop 9 (5 probes lost)");
+
+        let kind = EventKind::IxChange {
+            ttl: 6,
+            ip: IpAddr::V4(Ipv4Addr::new(80, 81, 192, 1)),
+            from: None,
+            to: Some("DE-CIX Frankfurt".into()),
+        };
+        assert_eq!(
+            kind.describe(),
+            "Path moved onto IX DE-CIX Frankfurt at hop 6 (80.81.192.1)"
+        );
+
+        let kind = EventKind::LatencySpike {
+            ttl: 9,
+            baseline_ms: 12.0,
+            rtt_ms: 80.5,
+            cause: SpikeCause::Congestion,
+            cause_ttl: Some(5),
+            loss_burst: true,
+        };
+        assert_eq!(
+            kind.describe(),
+            "Latency spike at hop 9: 12.0 -> 80.5 ms, probable cause: congestion at hop 5 (with loss)"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Responder labels
+//!
+//! Where PTR records are missing or say nothing useful ("ip-10-0-0-1"),
+//! a label such as "office-fw" or "ISP-CPE" names the device instead. A
+//! label only changes how a responder is shown; unlike an alias it never
+//! merges addresses.
+
+use ipnetwork::IpNetwork;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// How a label is shown next to the reverse DNS name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMode {
+    /// "office-fw [fw1.corp.example]"
+    #[default]
+    Alongside,
+    /// "office-fw"
+    Replace,
+}
+
+impl LabelMode {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Labels by address or prefix; the most specific prefix wins
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResponderLabels(Vec<(IpNetwork, String)>);
+
+impl ResponderLabels {
+    /// Parse `ADDR=LABEL` specs, where ADDR may be a prefix
+    pub fn parse<'a>(specs: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut labels = Vec::new();
+        for spec in specs {
+            let (addr, label) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("label '{}' should look like ADDR=LABEL", spec))?;
+            let network = addr
+                .trim()
+                .parse::<IpNetwork>()
+                .map_err(|_| format!("invalid label address '{}'", addr.trim()))?;
+            let label = label.trim();
+            if label.is_empty() {
+                return Err(format!("label for {} is empty", network));
+            }
+            labels.push((network, label.to_string()));
+        }
+        // Stable: among equal prefixes the first given wins
+        labels.sort_by_key(|(net, _)| std::cmp::Reverse(net.prefix()));
+        Ok(Self(labels))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Label of the most specific prefix covering `ip`
+    pub fn get(&self, ip: IpAddr) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(net, _)| net.contains(ip))
+            .map(|(_, label)| label.as_str())
+    }
+}
+
+/// Name shown for a responder: its label per `mode`, else the reverse DNS
+/// name, else the alias it matched
+pub fn display_name(
+    label: Option<&str>,
+    hostname: Option<&str>,
+    alias: Option<&str>,
+    mode: LabelMode,
+) -> Option<String> {
+    match (label, hostname) {
+        (Some(label), Some(host)) if mode == LabelMode::Alongside => {
+            Some(format!("{} [{}]", label, host))
+        }
+        (Some(label), _) => Some(label.to_string()),
+        (None, Some(host)) => Some(host.to_string()),
+        (None, None) => alias.map(str::to_string),
+    }
+}
+
+/// How a name too long for its column is shortened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HostTrim {
+    /// Keep the leading labels, drop the domain: "ae-1.cr2.fra1…"
+    #[default]
+    Smart,
+    /// Cut at the column edge: "ae-1.cr2.fra1.exa…"
+    End,
+    /// Keep both ends: "ae-1.cr2…example.net"
+    Middle,
+}
+
+impl HostTrim {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Shorten `name` to at most `max_len` characters
+pub fn trim_host(name: &str, max_len: usize, style: HostTrim) -> String {
+    let len = name.chars().count();
+    if len <= max_len {
+        return name.to_string();
+    }
+    if max_len <= 3 {
+        return name.chars().take(max_len).collect();
+    }
+    match style {
+        HostTrim::Smart => trim_smart(name, max_len).unwrap_or_else(|| trim_end(name, max_len)),
+        HostTrim::End => trim_end(name, max_len),
+        HostTrim::Middle => {
+            let head = max_len / 2;
+            let tail = max_len - 1 - head;
+            let start: String = name.chars().take(head).collect();
+            let end: String = name.chars().skip(len - tail).collect();
+            format!("{}…{}", start, end)
+        }
+    }
+}
+
+fn trim_end(name: &str, max_len: usize) -> String {
+    let kept: String = name.chars().take(max_len - 1).collect();
+    format!("{}…", kept)
+}
+
+/// "label [host]" keeps the label and shortens the host. A host name drops
+/// its domain, then labels from the right, until it fits; None when not
+/// even the first label does (or for an address).
+fn trim_smart(name: &str, max_len: usize) -> Option<String> {
+    if let Some(inner) = name.strip_suffix(']')
+        && let Some((label, host)) = inner.split_once(" [")
+    {
+        let room = max_len.checked_sub(label.chars().count() + 3)?;
+        if room <= 3 {
+            return None;
+        }
+        return Some(format!("{} [{}]", label, trim_smart(host, room)?));
+    }
+    if name.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+
+    let labels: Vec<&str> = name.split('.').collect();
+    let n = labels.len();
+    // "example.net", or "example.co.uk" under a two-letter country code
+    let domain = if n >= 3 && labels[n - 1].len() == 2 && labels[n - 2].len() <= 3 {
+        3
+    } else {
+        2
+    };
+    if n <= domain {
+        return None;
+    }
+
+    let mut kept = labels[0].to_string();
+    if kept.chars().count() + 1 > max_len {
+        return None;
+    }
+    for label in &labels[1..n - domain] {
+        if kept.chars().count() + label.chars().count() + 2 > max_len {
+            break;
+        }
+        kept.push('.');
+        kept.push_str(label);
+    }
+    Some(format!("{}…", kept))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_label() {
+        let labels =
+            ResponderLabels::parse(["100.64.0.0/10=ISP-CGNAT", "100.64.1.1 = ISP-CPE"]).unwrap();
+        assert_eq!(labels.get("100.64.1.1".parse().unwrap()), Some("ISP-CPE"));
+        assert_eq!(labels.get("100.64.9.9".parse().unwrap()), Some("ISP-CGNAT"));
+        assert_eq!(labels.get("192.0.2.1".parse().unwrap()), None);
+
+        assert!(ResponderLabels::parse(["10.0.0.1"]).is_err());
+        assert!(ResponderLabels::parse(["10.0.0.1="]).is_err());
+        assert!(ResponderLabels::parse(["fw=10.0.0.1"]).is_err());
+    }
+
+    #[test]
+    fn test_display_name() {
+        let name = |label, host, mode| display_name(label, host, Some("core"), mode);
+        assert_eq!(
+            name(Some("fw"), Some("ip-10-0-0-1"), LabelMode::Alongside).as_deref(),
+            Some("fw [ip-10-0-0-1]")
+        );
+        assert_eq!(
+            name(Some("fw"), Some("ip-10-0-0-1"), LabelMode::Replace).as_deref(),
+            Some("fw")
+        );
+        assert_eq!(
+            name(None, Some("ip-10-0-0-1"), LabelMode::Replace).as_deref(),
+            Some("ip-10-0-0-1")
+        );
+        assert_eq!(
+            name(None, None, LabelMode::Alongside).as_deref(),
+            Some("core")
+        );
+    }
+
+    #[test]
+    fn test_trim_host_styles() {
+        let name = "ae-1.cr2.fra1.example.net";
+        assert_eq!(trim_host(name, 40, HostTrim::Smart), name);
+        assert_eq!(trim_host(name, 16, HostTrim::Smart), "ae-1.cr2.fra1…");
+        assert_eq!(trim_host(name, 12, HostTrim::Smart), "ae-1.cr2…");
+        assert_eq!(trim_host(name, 16, HostTrim::End), "ae-1.cr2.fra1.e…");
+        assert_eq!(trim_host(name, 16, HostTrim::Middle), "ae-1.cr2…ple.net");
+        assert_eq!(
+            trim_host("xe-0.gw.lon.example.co.uk", 14, HostTrim::Smart),
+            "xe-0.gw.lon…"
+        );
+    }
+
+    #[test]
+    fn test_trim_host_smart_falls_back() {
+        // A first label too long for the column is cut like End
+        let long = "customer-203-0-113-9.dsl.example.net";
+        assert_eq!(
+            trim_host(long, 12, HostTrim::Smart),
+            trim_host(long, 12, HostTrim::End)
+        );
+        // Addresses and bare domains have no host part to keep
+        let v6 = "2001:db8:1234:5678::1";
+        assert_eq!(trim_host(v6, 10, HostTrim::Smart), "2001:db8:…");
+        assert_eq!(trim_host("example.net", 8, HostTrim::Smart), "example…");
+        // A label keeps its place while its host is shortened
+        assert_eq!(
+            trim_host("fw [ae-1.cr2.fra1.example.net]", 19, HostTrim::Smart),
+            "fw [ae-1.cr2.fra1…]"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Language of the TUI's own text
+//!
+//! `--lang` (or `TTL_LANG`, or `lang` in config.toml) picks the language of
+//! the help, the key hints, status messages and column headings. Numbers
+//! follow `--locale` independently, and the text report and exports stay in
+//! English so scripts reading them don't depend on who ran ttl.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+}
+
+impl Lang {
+    /// Language for a name such as `de`, `de_AT.UTF-8` or `en-GB`; `auto`
+    /// reads the environment
+    pub fn for_locale(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("auto") {
+            return Ok(Self::from_env());
+        }
+        let base = name.split(['.', '@', '_', '-']).next().unwrap_or_default();
+        match base.to_ascii_lowercase().as_str() {
+            "" | "c" | "posix" | "en" => Ok(Lang::En),
+            "de" => Ok(Lang::De),
+            _ => Err(format!("Unsupported language '{}' (en, de, auto)", name)),
+        }
+    }
+
+    /// Language of the environment's messages locale, English when it is
+    /// unset or not translated
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::for_locale(&value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lang_for_locale() {
+        assert_eq!(Lang::for_locale("de"), Ok(Lang::De));
+        assert_eq!(Lang::for_locale("de_CH.UTF-8"), Ok(Lang::De));
+        assert_eq!(Lang::for_locale("en-GB"), Ok(Lang::En));
+        assert_eq!(Lang::for_locale("C"), Ok(Lang::En));
+        assert!(Lang::for_locale("fr").unwrap_err().contains("fr"));
+    }
+}
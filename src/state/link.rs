@@ -0,0 +1,135 @@
+//! Point-to-point link inference between adjacent hops
+//!
+//! Routers are usually joined by a /31 or /30 (/127 for IPv6), so the two
+//! ends of a link share all but the last bit or two of their address. When
+//! the responders at TTL n and n+1 fall in the same such subnet, the probe
+//! most likely crossed that one link between them, and a latency jump at
+//! n+1 belongs to it. This is a heuristic: a router answering from a
+//! loopback or from the far side of its ingress link breaks the pattern.
+
+use std::net::IpAddr;
+
+use super::Hop;
+
+/// Link inferred between a responder and the responder one TTL further on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InferredLink {
+    /// TTL of the near end; the far end answered at `near_ttl + 1`
+    pub near_ttl: u8,
+    pub near: IpAddr,
+    pub far: IpAddr,
+    /// Subnet both ends share: 31, 30 or 127
+    pub prefix_len: u8,
+}
+
+/// Prefix length of the point-to-point subnet holding both addresses, if any.
+///
+/// A /30 only counts when both addresses are usable hosts (.1 and .2 of the
+/// block); the network and broadcast addresses would not be interfaces.
+pub fn p2p_prefix(a: IpAddr, b: IpAddr) -> Option<u8> {
+    match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) if a != b => {
+            let (a, b) = (u32::from(a), u32::from(b));
+            if a >> 1 == b >> 1 {
+                Some(31)
+            } else if a >> 2 == b >> 2 && [a & 3, b & 3].iter().all(|h| (1..=2).contains(h)) {
+                Some(30)
+            } else {
+                None
+            }
+        }
+        (IpAddr::V6(a), IpAddr::V6(b)) if a != b => {
+            (u128::from(a) >> 1 == u128::from(b) >> 1).then_some(127)
+        }
+        _ => None,
+    }
+}
+
+/// Links between responders at consecutive TTLs, in TTL order.
+///
+/// `hops` must be in TTL order. Primary responders are paired first; under
+/// ECMP any responder pair at the two hops can match instead.
+pub fn infer_links<'a>(hops: impl IntoIterator<Item = &'a Hop>) -> Vec<InferredLink> {
+    let hops: Vec<&Hop> = hops.into_iter().filter(|h| h.received > 0).collect();
+    hops.windows(2)
+        .filter(|pair| pair[0].ttl.checked_add(1) == Some(pair[1].ttl))
+        .filter_map(|pair| {
+            let (near, far) = (pair[0], pair[1]);
+            let primaries = near.primary.zip(far.primary);
+            let others = near
+                .responders
+                .keys()
+                .flat_map(|&a| far.responders.keys().map(move |&b| (a, b)));
+            primaries.into_iter().chain(others).find_map(|(a, b)| {
+                p2p_prefix(a, b).map(|prefix_len| InferredLink {
+                    near_ttl: near.ttl,
+                    near: a,
+                    far: b,
+                    prefix_len,
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn hop(ttl: u8, responders: &[&str]) -> Hop {
+        let mut hop = Hop::new(ttl);
+        for r in responders {
+            hop.record_sent();
+            hop.record_response(ip(r), Duration::from_millis(5));
+        }
+        hop
+    }
+
+    #[test]
+    fn test_p2p_prefix() {
+        assert_eq!(p2p_prefix(ip("10.0.0.0"), ip("10.0.0.1")), Some(31));
+        assert_eq!(p2p_prefix(ip("10.0.0.2"), ip("10.0.0.3")), Some(31));
+        assert_eq!(p2p_prefix(ip("10.0.0.1"), ip("10.0.0.2")), Some(30));
+        // .0 and .3 are the network and broadcast addresses of the /30
+        assert_eq!(p2p_prefix(ip("10.0.0.0"), ip("10.0.0.2")), None);
+        assert_eq!(p2p_prefix(ip("10.0.0.1"), ip("10.0.0.3")), None);
+        assert_eq!(p2p_prefix(ip("10.0.0.1"), ip("10.0.0.5")), None);
+        assert_eq!(p2p_prefix(ip("10.0.0.1"), ip("10.0.0.1")), None);
+        assert_eq!(p2p_prefix(ip("2001:db8::"), ip("2001:db8::1")), Some(127));
+        assert_eq!(p2p_prefix(ip("2001:db8::1"), ip("2001:db8::2")), None);
+        assert_eq!(p2p_prefix(ip("10.0.0.1"), ip("::ffff:10.0.0.0")), None);
+    }
+
+    #[test]
+    fn test_infer_links() {
+        let hops = [
+            hop(1, &["192.168.1.1"]),
+            hop(2, &["10.0.0.1"]),
+            hop(3, &["10.0.0.2"]),
+            // No reply at TTL 4, so 3 and 5 aren't known to be adjacent
+            hop(5, &["10.0.0.3"]),
+        ];
+        let links = infer_links(&hops);
+        assert_eq!(
+            links,
+            vec![InferredLink {
+                near_ttl: 2,
+                near: ip("10.0.0.1"),
+                far: ip("10.0.0.2"),
+                prefix_len: 30,
+            }]
+        );
+
+        // Under ECMP a non-primary responder can complete the pair
+        let hops = [hop(5, &["10.0.0.3"]), hop(6, &["172.16.0.9", "10.0.0.2"])];
+        let links = infer_links(&hops);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].far, ip("10.0.0.2"));
+        assert_eq!(links[0].prefix_len, 31);
+    }
+}
@@ -0,0 +1,273 @@
+//! Latency under load (bufferbloat) test
+//!
+//! With `--load RATE --load-target HOST:PORT` a UDP stream is sent to a
+//! host the user picked (never the traced target) while tracing. The first
+//! `IDLE_PHASE` of the session is traced without it to get a baseline; then
+//! the stream runs for `LOADED_PHASE` and replies are counted as loaded.
+//! After that the stream stops for good.
+//! A queue that fills up under load (bufferbloat) shows as a jump in RTT
+//! from the hop that owns the queue onward, usually the access link.
+//!
+//! The stream goes out from this host, so it loads the upload direction.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+/// Baseline period traced before the load stream starts
+pub const IDLE_PHASE: Duration = Duration::from_secs(10);
+
+/// How long the load stream runs
+pub const LOADED_PHASE: Duration = Duration::from_secs(20);
+
+/// Load stream settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadSpec {
+    /// Target bandwidth in bits per second
+    pub rate_bps: u64,
+    /// Host the stream is sent to (`--load-target`)
+    #[serde(default = "unspecified")]
+    pub target: IpAddr,
+    /// Destination UDP port
+    pub port: u16,
+}
+
+/// Sessions saved before `--load-target` existed don't name the host
+fn unspecified() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+}
+
+/// Which part of the test replies currently count toward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadPhase {
+    /// Baseline, load stream not started yet
+    #[default]
+    Idle,
+    /// Load stream running
+    Loaded,
+    /// Load stream finished; later replies count toward neither phase
+    Done,
+}
+
+/// Running RTT mean
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RttMean {
+    pub count: u64,
+    /// Sum of RTTs in microseconds
+    pub sum_us: f64,
+}
+
+impl RttMean {
+    fn add(&mut self, rtt: Duration) {
+        self.count += 1;
+        self.sum_us += rtt.as_micros() as f64;
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros((self.sum_us / self.count as f64) as u64))
+    }
+}
+
+/// Idle and loaded RTT at one hop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoadHop {
+    pub idle: RttMean,
+    pub loaded: RttMean,
+}
+
+impl LoadHop {
+    /// Loaded minus idle mean RTT in milliseconds (negative if loaded was faster)
+    pub fn delta_ms(&self) -> Option<f64> {
+        let (idle, loaded) = (self.idle.mean()?, self.loaded.mean()?);
+        Some((loaded.as_micros() as f64 - idle.as_micros() as f64) / 1000.0)
+    }
+}
+
+/// Latency-under-load grade, from the RTT increase at the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LoadGrade {
+    #[serde(rename = "A+")]
+    APlus,
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl LoadGrade {
+    /// Grade for an RTT increase in milliseconds
+    pub fn from_delta_ms(delta: f64) -> Self {
+        match delta {
+            d if d < 5.0 => LoadGrade::APlus,
+            d if d < 30.0 => LoadGrade::A,
+            d if d < 60.0 => LoadGrade::B,
+            d if d < 200.0 => LoadGrade::C,
+            d if d < 400.0 => LoadGrade::D,
+            _ => LoadGrade::F,
+        }
+    }
+}
+
+impl fmt::Display for LoadGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LoadGrade::APlus => "A+",
+            LoadGrade::A => "A",
+            LoadGrade::B => "B",
+            LoadGrade::C => "C",
+            LoadGrade::D => "D",
+            LoadGrade::F => "F",
+        })
+    }
+}
+
+/// Overall result: the hop graded and its RTT increase
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadResult {
+    pub ttl: u8,
+    pub delta_ms: f64,
+    pub grade: LoadGrade,
+}
+
+/// Idle vs loaded RTT per hop for one target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadTest {
+    pub spec: LoadSpec,
+    #[serde(default)]
+    pub phase: LoadPhase,
+    #[serde(default)]
+    pub hops: BTreeMap<u8, LoadHop>,
+}
+
+impl LoadTest {
+    pub fn new(spec: LoadSpec) -> Self {
+        Self {
+            spec,
+            phase: LoadPhase::Idle,
+            hops: BTreeMap::new(),
+        }
+    }
+
+    /// Count a reply toward the current phase
+    pub fn record(&mut self, ttl: u8, rtt: Duration) {
+        let hop = self.hops.entry(ttl).or_default();
+        match self.phase {
+            LoadPhase::Idle => hop.idle.add(rtt),
+            LoadPhase::Loaded => hop.loaded.add(rtt),
+            LoadPhase::Done => {}
+        }
+    }
+
+    /// Drop samples; the phase follows the load stream, so it is kept
+    pub fn reset(&mut self) {
+        self.hops.clear();
+    }
+
+    /// Grade at `dest_ttl`, or at the furthest hop with both idle and loaded
+    /// replies if the destination has none
+    pub fn result(&self, dest_ttl: Option<u8>) -> Option<LoadResult> {
+        let at = |ttl: u8| {
+            self.hops.get(&ttl)?.delta_ms().map(|delta_ms| LoadResult {
+                ttl,
+                delta_ms,
+                grade: LoadGrade::from_delta_ms(delta_ms),
+            })
+        };
+        dest_ttl
+            .and_then(at)
+            .or_else(|| self.hops.keys().rev().find_map(|&ttl| at(ttl)))
+    }
+}
+
+/// Human-readable bit rate, e.g. "50 Mbit/s"
+pub fn format_bit_rate(bps: u64) -> String {
+    match bps {
+        b if b >= 1_000_000_000 && b % 1_000_000_000 == 0 => {
+            format!("{} Gbit/s", b / 1_000_000_000)
+        }
+        b if b >= 1_000_000 => format!("{} Mbit/s", trim_float(b as f64 / 1e6)),
+        b if b >= 1_000 => format!("{} kbit/s", trim_float(b as f64 / 1e3)),
+        b => format!("{} bit/s", b),
+    }
+}
+
+fn trim_float(n: f64) -> String {
+    let s = format!("{:.1}", n);
+    s.strip_suffix(".0").map(str::to_string).unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn spec() -> LoadSpec {
+        LoadSpec {
+            rate_bps: 10_000_000,
+            target: "192.0.2.9".parse().unwrap(),
+            port: 5001,
+        }
+    }
+
+    #[test]
+    fn test_load_grade() {
+        assert_eq!(LoadGrade::from_delta_ms(-2.0), LoadGrade::APlus);
+        assert_eq!(LoadGrade::from_delta_ms(4.9), LoadGrade::APlus);
+        assert_eq!(LoadGrade::from_delta_ms(29.0), LoadGrade::A);
+        assert_eq!(LoadGrade::from_delta_ms(59.0), LoadGrade::B);
+        assert_eq!(LoadGrade::from_delta_ms(150.0), LoadGrade::C);
+        assert_eq!(LoadGrade::from_delta_ms(399.0), LoadGrade::D);
+        assert_eq!(LoadGrade::from_delta_ms(400.0), LoadGrade::F);
+        assert_eq!(LoadGrade::APlus.to_string(), "A+");
+        assert_eq!(serde_json::to_string(&LoadGrade::APlus).unwrap(), "\"A+\"");
+    }
+
+    #[test]
+    fn test_load_test_phases() {
+        let mut test = LoadTest::new(spec());
+        test.record(1, ms(1));
+        test.record(1, ms(3));
+        test.record(5, ms(20));
+        assert_eq!(test.result(Some(5)), None);
+
+        test.phase = LoadPhase::Loaded;
+        test.record(1, ms(2));
+        test.record(5, ms(120));
+
+        assert_eq!(test.hops[&1].idle.mean(), Some(ms(2)));
+        assert_eq!(test.hops[&1].delta_ms(), Some(0.0));
+        let result = test.result(Some(5)).unwrap();
+        assert_eq!(result.ttl, 5);
+        assert_eq!(result.delta_ms, 100.0);
+        assert_eq!(result.grade, LoadGrade::C);
+
+        // Destination unknown or silent: furthest hop with both phases
+        assert_eq!(test.result(None).unwrap().ttl, 5);
+        assert_eq!(test.result(Some(9)).unwrap().ttl, 5);
+
+        // Once the stream stops, replies no longer count
+        test.phase = LoadPhase::Done;
+        test.record(5, ms(500));
+        assert_eq!(test.hops[&5].loaded.count, 1);
+
+        test.reset();
+        assert!(test.hops.is_empty());
+        assert_eq!(test.phase, LoadPhase::Done);
+    }
+
+    #[test]
+    fn test_format_bit_rate() {
+        assert_eq!(format_bit_rate(50_000_000), "50 Mbit/s");
+        assert_eq!(format_bit_rate(2_500_000), "2.5 Mbit/s");
+        assert_eq!(format_bit_rate(1_000_000_000), "1 Gbit/s");
+        assert_eq!(format_bit_rate(512_000), "512 kbit/s");
+        assert_eq!(format_bit_rate(800), "800 bit/s");
+    }
+}
@@ -0,0 +1,173 @@
+//! Routing loop detection
+//!
+//! A packet caught in a forwarding loop passes the same routers again and
+//! again, so as the probe TTL grows it expires at each of them in turn:
+//! within one round the same responder answers at two TTLs with the rest of
+//! the loop in between (A B A B ...). Replies are grouped by the round's
+//! sequence number. A router repeated at neighbouring TTLs is left alone;
+//! that is usually a router that doesn't decrement TTL, not a loop.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::net::IpAddr;
+
+/// Rounds kept; a round is judged clean once it is this old, so its late
+/// replies are in
+const LOOP_ROUNDS: usize = 3;
+/// Smallest TTL distance between two answers from one router that counts
+/// as a loop
+const MIN_LOOP_GAP: u8 = 2;
+
+/// Forwarding loop seen in a recent round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingLoop {
+    /// First and last hop answered by a router in the loop
+    pub first_ttl: u8,
+    pub last_ttl: u8,
+    /// Routers in the loop, in path order
+    pub routers: Vec<IpAddr>,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl RoutingLoop {
+    pub fn contains(&self, ttl: u8) -> bool {
+        (self.first_ttl..=self.last_ttl).contains(&ttl)
+    }
+
+    /// One-line description for the report
+    pub fn describe(&self) -> String {
+        let routers: Vec<_> = self.routers.iter().map(|ip| ip.to_string()).collect();
+        format!(
+            "hops {}-{} between {}",
+            self.first_ttl,
+            self.last_ttl,
+            routers.join(", ")
+        )
+    }
+}
+
+/// Find a loop in one round's responders (TTL to address)
+pub fn find_loop(path: &BTreeMap<u8, IpAddr>) -> Option<RoutingLoop> {
+    let (first_ttl, repeat_ttl) = path.iter().find_map(|(&ttl, ip)| {
+        path.range(ttl.checked_add(MIN_LOOP_GAP)?..)
+            .find(|&(_, other)| other == ip)
+            .map(|(&later, _)| (ttl, later))
+    })?;
+
+    let mut routers: Vec<IpAddr> = Vec::new();
+    for (_, &ip) in path.range(first_ttl..repeat_ttl) {
+        if !routers.contains(&ip) {
+            routers.push(ip);
+        }
+    }
+    // The loop carries on for as long as its routers keep answering
+    let last_ttl = path
+        .range(repeat_ttl..)
+        .take_while(|&(_, ip)| routers.contains(ip))
+        .map(|(&ttl, _)| ttl)
+        .last()
+        .unwrap_or(repeat_ttl);
+
+    Some(RoutingLoop {
+        first_ttl,
+        last_ttl,
+        routers,
+        detected_at: Utc::now(),
+    })
+}
+
+/// Responders of the latest rounds, keyed by probe sequence number
+#[derive(Debug, Clone, Default)]
+pub struct RoundPaths {
+    rounds: VecDeque<(u8, BTreeMap<u8, IpAddr>)>,
+}
+
+impl RoundPaths {
+    /// Record a reply at `ttl` in round `seq`. Returns the loop the round
+    /// now shows, if any, and the oldest round if it was dropped to make
+    /// room.
+    pub fn record(
+        &mut self,
+        seq: u8,
+        ttl: u8,
+        ip: IpAddr,
+    ) -> (Option<RoutingLoop>, Option<BTreeMap<u8, IpAddr>>) {
+        let mut evicted = None;
+        let path = match self.rounds.iter().position(|(s, _)| *s == seq) {
+            Some(idx) => &mut self.rounds[idx].1,
+            None => {
+                if self.rounds.len() == LOOP_ROUNDS {
+                    evicted = self.rounds.pop_front().map(|(_, path)| path);
+                }
+                self.rounds.push_back((seq, BTreeMap::new()));
+                &mut self.rounds.back_mut().expect("just pushed").1
+            }
+        };
+        path.insert(ttl, ip);
+        (find_loop(path), evicted)
+    }
+
+    pub fn clear(&mut self) {
+        self.rounds.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn ip(n: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, n))
+    }
+
+    fn path(hops: &[(u8, u8)]) -> BTreeMap<u8, IpAddr> {
+        hops.iter().map(|&(ttl, n)| (ttl, ip(n))).collect()
+    }
+
+    #[test]
+    fn test_find_loop() {
+        // 1 2 3 4 3 4 3: loop between 3 and 4 from hop 3
+        let found = find_loop(&path(&[
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 3),
+            (6, 4),
+            (7, 3),
+        ]))
+        .unwrap();
+        assert_eq!((found.first_ttl, found.last_ttl), (3, 7));
+        assert_eq!(found.routers, vec![ip(3), ip(4)]);
+        assert!(found.contains(5));
+        assert!(!found.contains(2));
+        assert_eq!(found.describe(), "hops 3-7 between 10.0.0.3, 10.0.0.4");
+
+        // Gaps from lost replies don't hide it
+        let found = find_loop(&path(&[(2, 2), (3, 3), (5, 3), (6, 4)])).unwrap();
+        assert_eq!((found.first_ttl, found.last_ttl), (3, 5));
+    }
+
+    #[test]
+    fn test_find_loop_ignores_repeats_at_neighbouring_ttls() {
+        assert_eq!(find_loop(&path(&[(1, 1), (2, 2), (3, 2), (4, 3)])), None);
+        assert_eq!(find_loop(&path(&[(1, 1), (2, 2), (3, 3)])), None);
+    }
+
+    #[test]
+    fn test_round_paths() {
+        let mut rounds = RoundPaths::default();
+        assert_eq!(rounds.record(0, 3, ip(3)), (None, None));
+        assert_eq!(rounds.record(1, 3, ip(3)), (None, None));
+        // Rounds are kept apart
+        assert_eq!(rounds.record(0, 4, ip(4)), (None, None));
+        assert!(rounds.record(0, 5, ip(3)).0.is_some());
+        assert!(rounds.record(1, 5, ip(5)).0.is_none());
+
+        rounds.record(2, 1, ip(1));
+        let (_, evicted) = rounds.record(3, 1, ip(1));
+        assert_eq!(evicted, Some(path(&[(3, 3), (4, 4), (5, 3)])));
+    }
+}
@@ -0,0 +1,95 @@
+//! Loss attribution along a path
+//!
+//! Loss on a forwarding link shows up at the hop after it and at every hop
+//! beyond, because probes to later hops cross the same link. Loss that a
+//! router adds only to probes addressed to itself (ICMP rate limiting,
+//! control-plane policing) shows up at that hop alone. So the loss a hop
+//! *causes* is the part of its loss that persists all the way downstream,
+//! minus whatever was already persisting from the hop before it.
+
+use std::collections::BTreeMap;
+
+use super::Hop;
+
+/// Loss percentage introduced at each responding hop.
+///
+/// `hops` must be in TTL order. Hops that never answered are skipped: a
+/// silent router says nothing about the links around it. The values sum
+/// to the loss at the last responding hop.
+pub fn attribute_loss<'a>(hops: impl IntoIterator<Item = &'a Hop>) -> BTreeMap<u8, f64> {
+    let responding: Vec<(u8, f64)> = hops
+        .into_iter()
+        .filter(|h| h.received > 0)
+        .map(|h| (h.ttl, h.loss_pct()))
+        .collect();
+
+    // Loss that persists from each hop to the end of the path
+    let mut persistent = vec![0.0; responding.len()];
+    let mut floor = f64::INFINITY;
+    for (i, &(_, loss)) in responding.iter().enumerate().rev() {
+        floor = floor.min(loss);
+        persistent[i] = floor;
+    }
+
+    let mut inherited = 0.0;
+    responding
+        .iter()
+        .zip(persistent)
+        .map(|(&(ttl, _), persists)| {
+            let caused = (persists - inherited).max(0.0);
+            inherited = persists;
+            (ttl, caused)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(ttl: u8, received: u64, timeouts: u64) -> Hop {
+        let mut hop = Hop::new(ttl);
+        hop.sent = received + timeouts;
+        hop.received = received;
+        hop.timeouts = timeouts;
+        hop
+    }
+
+    #[test]
+    fn test_rate_limited_hop_causes_nothing() {
+        // Hop 2 drops half of its own replies, but nothing downstream does
+        let hops = [hop(1, 10, 0), hop(2, 5, 5), hop(3, 10, 0)];
+        let caused = attribute_loss(&hops);
+        assert_eq!(caused.values().copied().collect::<Vec<_>>(), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_loss_attributed_where_it_starts() {
+        // 20% starts at hop 2 and carries through; another 10% starts at
+        // hop 5. Hop 3 also rate limits its own replies.
+        let hops = [
+            hop(1, 10, 0),
+            hop(2, 8, 2),
+            hop(3, 4, 6),
+            hop(4, 8, 2),
+            hop(5, 7, 3),
+            hop(6, 7, 3),
+        ];
+        let caused = attribute_loss(&hops);
+        assert_eq!(caused[&1], 0.0);
+        assert!((caused[&2] - 20.0).abs() < 1e-9);
+        assert_eq!(caused[&3], 0.0);
+        assert_eq!(caused[&4], 0.0);
+        assert!((caused[&5] - 10.0).abs() < 1e-9);
+        assert_eq!(caused[&6], 0.0);
+        assert!((caused.values().sum::<f64>() - hops[5].loss_pct()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_silent_hops_skipped() {
+        let hops = [hop(1, 10, 0), hop(2, 0, 10), hop(3, 9, 1)];
+        let caused = attribute_loss(&hops);
+        assert!(!caused.contains_key(&2));
+        assert!((caused[&3] - 10.0).abs() < 1e-9);
+    }
+}
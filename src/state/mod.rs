@@ -1,5 +1,40 @@
+pub mod alias;
+pub mod correlation;
+pub mod distance;
+pub mod ecmp;
+pub mod ecn;
+pub mod epoch;
+pub mod events;
+pub mod label;
+pub mod lang;
+pub mod link;
+pub mod load;
+pub mod loops;
+pub mod loss;
+pub mod mtu;
+pub mod number;
+pub mod pacing;
+pub mod paths;
 pub mod ratelimit;
+pub mod return_ttl;
 pub mod session;
+pub mod signature;
+pub mod slo;
+pub mod spike;
+pub mod sweep;
+pub mod tunnel;
+pub mod window;
 
+pub use alias::*;
+pub use distance::*;
+pub use ecn::*;
+pub use epoch::*;
+pub use events::*;
+pub use label::*;
+pub use lang::*;
+pub use load::*;
+pub use mtu::*;
+pub use number::*;
 pub use ratelimit::*;
 pub use session::*;
+pub use slo::*;
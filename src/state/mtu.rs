@@ -0,0 +1,153 @@
+//! Path MTU hints gathered without PMTUD probing
+//!
+//! Two things already on the wire bound the path MTU:
+//!
+//! - **Frag Needed / Packet Too Big**: a router that can't forward a probe
+//!   reports its next-hop MTU (RFC 1191, RFC 8201).
+//! - **TCP MSS**: the destination's SYN-ACK announces the largest segment
+//!   it accepts, and MSS-clamping middleboxes rewrite it on the way. The
+//!   MTU it implies is the MSS plus IP and TCP headers.
+//!
+//! The estimate is the smallest of these and the `--pmtud` result.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// IPv4 + TCP header bytes (no options) on top of an MSS
+const MSS_OVERHEAD_V4: u16 = 40;
+/// IPv6 + TCP header bytes on top of an MSS
+const MSS_OVERHEAD_V6: u16 = 60;
+
+/// A Frag Needed / Packet Too Big report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragNeeded {
+    /// Hop that sent it
+    pub ttl: u8,
+    /// Next-hop MTU it reported
+    pub mtu: u16,
+}
+
+/// Passive MTU evidence for a session (smallest value seen of each kind)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MtuHints {
+    /// MSS announced in the destination's SYN-ACKs (TCP probing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub syn_ack_mss: Option<u16>,
+    /// Lowest MTU reported by Frag Needed / Packet Too Big
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frag_needed: Option<FragNeeded>,
+}
+
+impl MtuHints {
+    pub fn is_empty(&self) -> bool {
+        self.syn_ack_mss.is_none() && self.frag_needed.is_none()
+    }
+
+    pub fn record_mss(&mut self, mss: u16) {
+        if mss > 0 && self.syn_ack_mss.is_none_or(|m| mss < m) {
+            self.syn_ack_mss = Some(mss);
+        }
+    }
+
+    pub fn record_frag_needed(&mut self, ttl: u8, mtu: u16) {
+        if mtu > 0 && self.frag_needed.is_none_or(|f| mtu < f.mtu) {
+            self.frag_needed = Some(FragNeeded { ttl, mtu });
+        }
+    }
+
+    /// Smallest MTU implied by the hints and the `--pmtud` result
+    pub fn estimate(&self, ipv6: bool, pmtud: Option<u16>) -> Option<MtuEstimate> {
+        let mss = self.syn_ack_mss.map(|mss| MtuEstimate {
+            mtu: mss_to_mtu(mss, ipv6),
+            source: MtuSource::TcpMss(mss),
+        });
+        let frag = self.frag_needed.map(|f| MtuEstimate {
+            mtu: f.mtu,
+            source: MtuSource::FragNeeded(f.ttl),
+        });
+        let pmtud = pmtud.map(|mtu| MtuEstimate {
+            mtu,
+            source: MtuSource::Pmtud,
+        });
+        [frag, pmtud, mss]
+            .into_iter()
+            .flatten()
+            .min_by_key(|e| e.mtu)
+    }
+}
+
+/// MTU implied by a TCP MSS
+pub fn mss_to_mtu(mss: u16, ipv6: bool) -> u16 {
+    let overhead = if ipv6 {
+        MSS_OVERHEAD_V6
+    } else {
+        MSS_OVERHEAD_V4
+    };
+    mss.saturating_add(overhead)
+}
+
+/// Where an MTU estimate came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtuSource {
+    /// `--pmtud` search result
+    Pmtud,
+    /// Frag Needed / Packet Too Big from this hop
+    FragNeeded(u8),
+    /// SYN-ACK MSS
+    TcpMss(u16),
+}
+
+/// Consolidated path MTU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MtuEstimate {
+    pub mtu: u16,
+    pub source: MtuSource,
+}
+
+impl fmt::Display for MtuSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MtuSource::Pmtud => write!(f, "PMTUD"),
+            MtuSource::FragNeeded(ttl) => write!(f, "frag needed at hop {}", ttl),
+            MtuSource::TcpMss(mss) => write!(f, "TCP MSS {}", mss),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mtu_estimate() {
+        let mut hints = MtuHints::default();
+        assert!(hints.is_empty());
+        assert_eq!(hints.estimate(false, None), None);
+
+        hints.record_mss(1460);
+        hints.record_mss(1500);
+        assert_eq!(hints.syn_ack_mss, Some(1460));
+        assert_eq!(
+            hints.estimate(false, None),
+            Some(MtuEstimate {
+                mtu: 1500,
+                source: MtuSource::TcpMss(1460)
+            })
+        );
+        assert_eq!(hints.estimate(true, None).unwrap().mtu, 1520);
+
+        // A tunnel along the way is narrower than the server's MSS suggests
+        hints.record_frag_needed(5, 1480);
+        hints.record_frag_needed(7, 1492);
+        let estimate = hints.estimate(false, Some(1500)).unwrap();
+        assert_eq!(estimate.mtu, 1480);
+        assert_eq!(estimate.source.to_string(), "frag needed at hop 5");
+
+        // Clamped MSS wins when it is the tighter bound
+        hints.record_mss(1360);
+        assert_eq!(
+            hints.estimate(false, Some(1480)).unwrap().source,
+            MtuSource::TcpMss(1360)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Locale-aware number formatting for display
+//!
+//! `--locale` picks the decimal and thousands separators used in the TUI
+//! and the text report; `--auto-units` shows small RTTs in µs and large
+//! ones in seconds instead of always milliseconds. The default matches the
+//! historical output ("12.3", "1234"), and machine-readable exports (JSON,
+//! CSV) never use it.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Separators and RTT units for displayed numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumberFormat {
+    /// Decimal separator
+    pub decimal: char,
+    /// Thousands separator (None = no grouping)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<char>,
+    /// Pick µs/ms/s per value instead of always ms
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_units: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            group: None,
+            auto_units: false,
+        }
+    }
+}
+
+/// Languages writing 1,234.5
+const DOT_DECIMAL: &[&str] = &["en", "ja", "zh", "ko", "he", "th", "hi", "ga", "ms"];
+/// Languages writing 1.234,5
+const COMMA_DECIMAL_DOT_GROUP: &[&str] = &[
+    "de", "nl", "da", "it", "es", "pt", "tr", "el", "id", "ro", "sl", "hr", "sr", "vi",
+];
+/// Languages writing 1 234,5
+const COMMA_DECIMAL_SPACE_GROUP: &[&str] = &[
+    "fr", "ru", "pl", "cs", "sk", "sv", "fi", "nb", "nn", "no", "uk", "hu", "bg", "et", "lv", "lt",
+    "kk",
+];
+
+impl NumberFormat {
+    /// Separators for a locale name such as `de`, `de_DE.UTF-8`, `en-US`,
+    /// `C`, or `auto` (taken from LC_ALL, LC_NUMERIC or LANG)
+    pub fn for_locale(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("auto") {
+            return Ok(Self::from_env());
+        }
+        let base = name.split(['.', '@']).next().unwrap_or_default();
+        if base.is_empty() || base.eq_ignore_ascii_case("c") || base.eq_ignore_ascii_case("posix") {
+            return Ok(Self::default());
+        }
+        let mut parts = base.split(['_', '-']);
+        let lang = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+
+        let (decimal, group) = match lang.as_str() {
+            // Swiss German and Italian group with an apostrophe
+            "de" | "it" if region == "CH" => ('.', '\''),
+            l if DOT_DECIMAL.contains(&l) => ('.', ','),
+            l if COMMA_DECIMAL_DOT_GROUP.contains(&l) => (',', '.'),
+            l if COMMA_DECIMAL_SPACE_GROUP.contains(&l) => (',', ' '),
+            _ => return Err(format!("Unknown locale '{}'", name)),
+        };
+        Ok(Self {
+            decimal,
+            group: Some(group),
+            auto_units: false,
+        })
+    }
+
+    /// Separators for the environment's numeric locale, or the default when
+    /// it is unset or unknown
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::for_locale(&value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `x` with `places` decimals
+    pub fn number(&self, x: f64, places: usize) -> String {
+        let s = format!("{:.*}", places, x.abs());
+        let (int, frac) = s.split_once('.').unwrap_or((&s, ""));
+        let mut out = String::with_capacity(s.len() + 4);
+        // Rounding can turn -0.04 into "0.0"; don't print "-0.0"
+        if x.is_sign_negative() && s.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+            out.push('-');
+        }
+        out.push_str(&self.group_digits(int));
+        if !frac.is_empty() {
+            out.push(self.decimal);
+            out.push_str(frac);
+        }
+        out
+    }
+
+    /// A count, with thousands separators
+    pub fn count(&self, n: u64) -> String {
+        self.group_digits(&n.to_string())
+    }
+
+    /// A percentage, e.g. "12.5%"
+    pub fn percent(&self, x: f64, places: usize) -> String {
+        format!("{}%", self.number(x, places))
+    }
+
+    /// An RTT with its unit: milliseconds with `places` decimals, or with
+    /// `auto_units` whole µs below 1ms and seconds from 1s
+    pub fn rtt(&self, d: Duration, places: usize) -> String {
+        let ms = d.as_secs_f64() * 1000.0;
+        match ms {
+            _ if !self.auto_units => format!("{}ms", self.number(ms, places)),
+            // Cut just below 1ms so 999.7µs doesn't print as "1000µs"
+            ms if ms < 0.9995 => format!("{}µs", self.number(ms * 1000.0, 0)),
+            ms if ms < 1000.0 => format!("{}ms", self.number(ms, places)),
+            ms => format!("{}s", self.number(ms / 1000.0, 2)),
+        }
+    }
+
+    /// An RTT for a column headed in milliseconds: the bare number, unless
+    /// `auto_units` is on and the unit has to be spelled out
+    pub fn rtt_bare(&self, d: Duration, places: usize) -> String {
+        if self.auto_units {
+            self.rtt(d, places)
+        } else {
+            self.number(d.as_secs_f64() * 1000.0, places)
+        }
+    }
+
+    fn group_digits(&self, digits: &str) -> String {
+        let Some(sep) = self.group else {
+            return digits.to_string();
+        };
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                out.push(sep);
+            }
+            out.push(c);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: f64) -> Duration {
+        Duration::from_secs_f64(n / 1000.0)
+    }
+
+    #[test]
+    fn test_default_matches_plain_formatting() {
+        let f = NumberFormat::default();
+        assert_eq!(f.number(1234.56, 1), "1234.6");
+        assert_eq!(f.count(1234567), "1234567");
+        assert_eq!(f.percent(12.5, 1), "12.5%");
+        assert_eq!(f.rtt(ms(12.34), 1), "12.3ms");
+        assert_eq!(f.rtt_bare(ms(0.25), 1), "0.2");
+        assert_eq!(f.number(-0.04, 1), "0.0");
+        assert_eq!(f.number(-3.26, 1), "-3.3");
+    }
+
+    #[test]
+    fn test_for_locale() {
+        let de = NumberFormat::for_locale("de_DE.UTF-8").unwrap();
+        assert_eq!(de.number(1234.56, 1), "1.234,6");
+        assert_eq!(de.count(1234567), "1.234.567");
+
+        let en = NumberFormat::for_locale("en-US").unwrap();
+        assert_eq!(en.number(1234.56, 2), "1,234.56");
+        assert_eq!(en.count(999), "999");
+
+        let fr = NumberFormat::for_locale("fr").unwrap();
+        assert_eq!(fr.percent(1234.5, 1), "1 234,5%");
+
+        let ch = NumberFormat::for_locale("de_CH").unwrap();
+        assert_eq!(ch.number(12345.0, 0), "12'345");
+
+        assert!(NumberFormat::for_locale("C").unwrap().is_default());
+        assert!(NumberFormat::for_locale("POSIX").unwrap().is_default());
+        assert!(NumberFormat::for_locale("xx_YY").is_err());
+    }
+
+    #[test]
+    fn test_auto_units() {
+        let f = NumberFormat {
+            auto_units: true,
+            ..NumberFormat::for_locale("de").unwrap()
+        };
+        assert_eq!(f.rtt(ms(0.85), 1), "850µs");
+        assert_eq!(f.rtt(ms(12.34), 1), "12,3ms");
+        assert_eq!(f.rtt(ms(1234.0), 1), "1,23s");
+        assert_eq!(f.rtt_bare(ms(0.85), 1), "850µs");
+    }
+}
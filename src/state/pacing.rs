@@ -0,0 +1,129 @@
+//! Adaptive probe pacing (`--adaptive`)
+//!
+//! A router that rate limits ICMP drops replies once probes come faster
+//! than its budget, so probing it every round shows loss that isn't there.
+//! With adaptive pacing a hop whose latest results show heavy loss, or
+//! loss together with a rate limit suspicion, is probed every other round,
+//! then every fourth, up to every eighth. Once its replies recover the
+//! rate steps back up the same way. Each step waits for a fresh window of
+//! results, so the hop has a chance to show the effect of the last one.
+
+use std::collections::VecDeque;
+
+/// Results a hop must gather between steps, and the window judged
+const PACE_WINDOW: usize = 10;
+/// Loss in the window (percent) that slows a hop down
+const BACKOFF_LOSS: f64 = 30.0;
+/// Loss that also slows it down when rate limiting is suspected
+const BACKOFF_LOSS_LIMITED: f64 = 10.0;
+/// Loss under which a slowed hop speeds back up
+const RECOVER_LOSS: f64 = 10.0;
+/// Slowest pace: every this many rounds
+pub const MAX_PACE_PERIOD: u64 = 8;
+
+/// A hop's probing pace under --adaptive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HopPace {
+    /// Probe every this many rounds (1 = every round)
+    pub period: u64,
+    /// Completed probes when the pace last changed (or was last judged)
+    mark: u64,
+}
+
+impl Default for HopPace {
+    fn default() -> Self {
+        Self { period: 1, mark: 0 }
+    }
+}
+
+impl HopPace {
+    pub fn backed_off(&self) -> bool {
+        self.period > 1
+    }
+
+    /// Judge the hop once a window of results has come in since the last
+    /// step. `completed` counts its replies and timeouts, `recent` holds
+    /// its latest results (true = reply). Returns the new period if it
+    /// changed.
+    pub fn update(
+        &mut self,
+        completed: u64,
+        recent: &VecDeque<bool>,
+        rate_limited: bool,
+    ) -> Option<u64> {
+        if completed < self.mark + PACE_WINDOW as u64 || recent.len() < PACE_WINDOW {
+            return None;
+        }
+        self.mark = completed;
+        let lost = recent
+            .iter()
+            .rev()
+            .take(PACE_WINDOW)
+            .filter(|ok| !**ok)
+            .count();
+        let loss = lost as f64 * 100.0 / PACE_WINDOW as f64;
+
+        let heavy = loss >= BACKOFF_LOSS || (rate_limited && loss >= BACKOFF_LOSS_LIMITED);
+        let period = if heavy {
+            (self.period * 2).min(MAX_PACE_PERIOD)
+        } else if loss < RECOVER_LOSS {
+            (self.period / 2).max(1)
+        } else {
+            self.period
+        };
+        (period != self.period).then(|| {
+            self.period = period;
+            period
+        })
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` results, the first `lost` of them timeouts
+    fn window(n: usize, lost: usize) -> VecDeque<bool> {
+        (0..n).map(|i| i >= lost).collect()
+    }
+
+    #[test]
+    fn test_backs_off_and_recovers() {
+        let mut pace = HopPace::default();
+        let lossy = window(PACE_WINDOW, 5);
+        let clean = window(PACE_WINDOW, 0);
+
+        assert_eq!(pace.update(10, &lossy, false), Some(2));
+        // Waits for a fresh window before the next step
+        assert_eq!(pace.update(15, &lossy, false), None);
+        assert_eq!(pace.update(20, &lossy, false), Some(4));
+        assert_eq!(pace.update(30, &lossy, false), Some(8));
+        assert_eq!(pace.update(40, &lossy, false), None);
+        assert_eq!(pace.period, MAX_PACE_PERIOD);
+
+        assert_eq!(pace.update(50, &clean, false), Some(4));
+        assert_eq!(pace.update(60, &clean, false), Some(2));
+        assert_eq!(pace.update(70, &clean, false), Some(1));
+        assert!(!pace.backed_off());
+    }
+
+    #[test]
+    fn test_rate_limit_lowers_the_bar() {
+        let some_loss = window(PACE_WINDOW, 2);
+        let mut pace = HopPace::default();
+        assert_eq!(pace.update(10, &some_loss, false), None);
+        assert_eq!(pace.update(20, &some_loss, true), Some(2));
+        // 20% loss is neither heavy nor recovered without the suspicion
+        assert_eq!(pace.update(30, &some_loss, false), None);
+        assert_eq!(pace.period, 2);
+
+        pace.reset();
+        assert_eq!(pace, HopPace::default());
+        // Too few results to judge
+        assert_eq!(pace.update(10, &window(4, 4), true), None);
+    }
+}
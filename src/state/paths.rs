@@ -0,0 +1,259 @@
+//! ECMP path graph from multi-flow probing
+//!
+//! With `--flows N` every flow keeps its own source port at every TTL, so a
+//! load balancer hashes it the same way each time and the flow follows one
+//! branch end to end. Chaining each flow's primary responder hop by hop
+//! gives its path; merging the paths gives a directed acyclic graph whose
+//! nodes are (TTL, responder) pairs. A node with several successors is where
+//! the paths diverge, one with several predecessors where they meet again.
+//! Silent hops are skipped, so an edge may span more than one TTL.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use super::Hop;
+
+/// A responder at one TTL and the flows that reached it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathNode {
+    pub ttl: u8,
+    pub ip: IpAddr,
+    pub flows: Vec<u8>,
+}
+
+/// Consecutive responders on the same flows (indices into `nodes`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathEdge {
+    pub from: usize,
+    pub to: usize,
+    pub flows: Vec<u8>,
+}
+
+/// One flow's route and figures
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowRoute {
+    pub flow_id: u8,
+    pub src_port: Option<u16>,
+    /// Primary responder of the flow at each TTL that answered it
+    pub hops: Vec<(u8, IpAddr)>,
+    /// Probes sent and loss on this flow at its last responding hop
+    pub sent: u64,
+    pub loss_pct: f64,
+}
+
+/// Paths that share every responder, and the flows taking them
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistinctPath<'a> {
+    pub flows: Vec<u8>,
+    pub hops: &'a [(u8, IpAddr)],
+}
+
+/// A responder whose flows continue to different next hops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    pub ip: IpAddr,
+    /// Next responder (TTL, address) and the flows going there
+    pub next: Vec<(u8, IpAddr, Vec<u8>)>,
+}
+
+impl Branch {
+    /// e.g. "hop 5 10.0.0.2 (flows 0,2), hop 5 10.0.0.3 (flow 1)"
+    pub fn describe(&self) -> String {
+        let next: Vec<_> = self
+            .next
+            .iter()
+            .map(|(ttl, ip, flows)| {
+                let noun = if flows.len() == 1 { "flow" } else { "flows" };
+                format!("hop {} {} ({} {})", ttl, ip, noun, flow_list(flows))
+            })
+            .collect();
+        next.join(", ")
+    }
+}
+
+/// Graph of the paths taken by each flow
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathTree {
+    pub nodes: Vec<PathNode>,
+    pub edges: Vec<PathEdge>,
+    pub flows: Vec<FlowRoute>,
+}
+
+impl PathTree {
+    /// Build from the per-flow stats of hops up to `last_ttl`
+    pub fn build(hops: &[Hop], last_ttl: u8) -> Self {
+        let hops: Vec<&Hop> = hops.iter().filter(|h| h.ttl <= last_ttl).collect();
+        let mut flow_ids: Vec<u8> = hops
+            .iter()
+            .flat_map(|h| h.flow_paths.keys().copied())
+            .collect();
+        flow_ids.sort_unstable();
+        flow_ids.dedup();
+
+        let mut tree = PathTree::default();
+        let mut index: BTreeMap<(u8, IpAddr), usize> = BTreeMap::new();
+        let mut edges: BTreeMap<(usize, usize), Vec<u8>> = BTreeMap::new();
+
+        for flow_id in flow_ids {
+            let mut route = FlowRoute {
+                flow_id,
+                src_port: None,
+                hops: Vec::new(),
+                sent: 0,
+                loss_pct: 0.0,
+            };
+            let mut previous: Option<usize> = None;
+            for hop in &hops {
+                let Some(stats) = hop.flow_paths.get(&flow_id) else {
+                    continue;
+                };
+                route.src_port = route.src_port.or(stats.src_port);
+                let Some(ip) = stats.primary_responder else {
+                    continue;
+                };
+                route.hops.push((hop.ttl, ip));
+                route.sent = stats.sent;
+                route.loss_pct = stats.loss_pct();
+
+                let node = *index.entry((hop.ttl, ip)).or_insert_with(|| {
+                    tree.nodes.push(PathNode {
+                        ttl: hop.ttl,
+                        ip,
+                        flows: Vec::new(),
+                    });
+                    tree.nodes.len() - 1
+                });
+                tree.nodes[node].flows.push(flow_id);
+                if let Some(from) = previous {
+                    edges.entry((from, node)).or_default().push(flow_id);
+                }
+                previous = Some(node);
+            }
+            tree.flows.push(route);
+        }
+
+        tree.edges = edges
+            .into_iter()
+            .map(|((from, to), flows)| PathEdge { from, to, flows })
+            .collect();
+        tree
+    }
+
+    /// Edges leaving `node`
+    pub fn successors(&self, node: usize) -> impl Iterator<Item = &PathEdge> {
+        self.edges.iter().filter(move |e| e.from == node)
+    }
+
+    /// Responders at `ttl` where the paths diverge
+    pub fn branches_at(&self, ttl: u8) -> Vec<Branch> {
+        (0..self.nodes.len())
+            .filter(|&n| self.nodes[n].ttl == ttl)
+            .filter_map(|n| {
+                let next: Vec<_> = self
+                    .successors(n)
+                    .map(|e| (self.nodes[e.to].ttl, self.nodes[e.to].ip, e.flows.clone()))
+                    .collect();
+                (next.len() > 1).then(|| Branch {
+                    ip: self.nodes[n].ip,
+                    next,
+                })
+            })
+            .collect()
+    }
+
+    /// Flows grouped by identical routes, in flow order
+    pub fn distinct_paths(&self) -> Vec<DistinctPath<'_>> {
+        let mut paths: Vec<DistinctPath> = Vec::new();
+        for route in self.flows.iter().filter(|r| !r.hops.is_empty()) {
+            match paths.iter_mut().find(|p| p.hops == route.hops.as_slice()) {
+                Some(path) => path.flows.push(route.flow_id),
+                None => paths.push(DistinctPath {
+                    flows: vec![route.flow_id],
+                    hops: &route.hops,
+                }),
+            }
+        }
+        paths
+    }
+}
+
+/// "0,2,3" for a list of flow ids
+pub fn flow_list(flows: &[u8]) -> String {
+    flows
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn ip(n: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, n))
+    }
+
+    /// Hops where `routes[flow][ttl - 1]` answered flow `flow` (0 = silent)
+    fn hops(routes: &[&[u8]]) -> Vec<Hop> {
+        let depth = routes.iter().map(|r| r.len()).max().unwrap_or(0);
+        (1..=depth as u8)
+            .map(|ttl| {
+                let mut hop = Hop::new(ttl);
+                for (flow, route) in routes.iter().enumerate() {
+                    hop.record_flow_sent(flow as u8);
+                    match route.get(ttl as usize - 1) {
+                        Some(&n) if n > 0 => {
+                            hop.record_flow_response(flow as u8, ip(n), Default::default())
+                        }
+                        _ => hop.record_flow_timeout(flow as u8),
+                    }
+                }
+                hop
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_path_tree_diamond() {
+        // 1 -> {2, 3} -> 4 across three flows
+        let tree = PathTree::build(&hops(&[&[1, 2, 4], &[1, 3, 4], &[1, 2, 4]]), 30);
+        assert_eq!(tree.nodes.len(), 4);
+        assert_eq!(tree.edges.len(), 4);
+
+        let branches = tree.branches_at(1);
+        assert_eq!(
+            branches,
+            vec![Branch {
+                ip: ip(1),
+                next: vec![(2, ip(2), vec![0, 2]), (2, ip(3), vec![1])],
+            }]
+        );
+        assert_eq!(
+            branches[0].describe(),
+            "hop 2 10.0.0.2 (flows 0,2), hop 2 10.0.0.3 (flow 1)"
+        );
+        assert!(tree.branches_at(2).is_empty());
+        let end = tree.nodes.iter().find(|n| n.ttl == 3).unwrap();
+        assert_eq!(end.flows, vec![0, 1, 2]);
+
+        let paths = tree.distinct_paths();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(flow_list(&paths[0].flows), "0,2");
+        assert_eq!(paths[1].hops, &[(1, ip(1)), (2, ip(3)), (3, ip(4))]);
+    }
+
+    #[test]
+    fn test_path_tree_skips_silent_hops() {
+        let tree = PathTree::build(&hops(&[&[1, 0, 3]]), 30);
+        assert_eq!(tree.edges.len(), 1);
+        let edge = &tree.edges[0];
+        assert_eq!((tree.nodes[edge.from].ttl, tree.nodes[edge.to].ttl), (1, 3));
+        assert_eq!(tree.flows[0].loss_pct, 0.0);
+        assert!(tree.branches_at(1).is_empty());
+
+        // Hops past the last TTL are left out
+        assert_eq!(PathTree::build(&hops(&[&[1, 0, 3]]), 2).edges.len(), 0);
+    }
+}
@@ -0,0 +1,107 @@
+//! Return TTL tracking per responder
+//!
+//! The IP TTL left on a reply says how many routers it crossed on the way
+//! back. When it shifts while the forward path stays put, the reverse path
+//! moved: often the first sign of a routing change on the other side. A
+//! new value only counts once it has held for several replies in a row, so
+//! return paths that alternate between two lengths (ECMP) don't produce a
+//! stream of changes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Consecutive replies a new return TTL needs before it replaces the old one
+pub const RETURN_TTL_SETTLE: u32 = 5;
+
+/// Return TTL seen from one responder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReturnTtl {
+    /// Settled return TTL
+    pub current: u8,
+    /// Most recent reply's TTL (may not have settled yet)
+    pub last: u8,
+    /// Settled value before the last change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<u8>,
+    /// When the settled value last changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<DateTime<Utc>>,
+    /// Number of settled changes
+    #[serde(default)]
+    pub changes: u32,
+    /// Replies in a row with `last` while it differs from `current`
+    #[serde(skip)]
+    streak: u32,
+}
+
+impl ReturnTtl {
+    pub fn new(ttl: u8) -> Self {
+        Self {
+            current: ttl,
+            last: ttl,
+            previous: None,
+            changed_at: None,
+            changes: 0,
+            streak: 0,
+        }
+    }
+
+    /// Record a reply's TTL, returning (old, new) when the settled value changes
+    pub fn record(&mut self, ttl: u8) -> Option<(u8, u8)> {
+        if ttl == self.current {
+            self.last = ttl;
+            self.streak = 0;
+            return None;
+        }
+        self.streak = if ttl == self.last { self.streak + 1 } else { 1 };
+        self.last = ttl;
+        if self.streak < RETURN_TTL_SETTLE {
+            return None;
+        }
+        let from = self.current;
+        self.previous = Some(from);
+        self.current = ttl;
+        self.changed_at = Some(Utc::now());
+        self.changes += 1;
+        self.streak = 0;
+        Some((from, ttl))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_ttl_settles() {
+        let mut rt = ReturnTtl::new(54);
+        for _ in 0..RETURN_TTL_SETTLE - 1 {
+            assert_eq!(rt.record(52), None);
+        }
+        assert_eq!(rt.current, 54);
+        assert_eq!(rt.last, 52);
+        assert_eq!(rt.record(52), Some((54, 52)));
+        assert_eq!(rt.current, 52);
+        assert_eq!(rt.previous, Some(54));
+        assert_eq!(rt.changes, 1);
+        assert!(rt.changed_at.is_some());
+        assert_eq!(rt.record(52), None);
+    }
+
+    #[test]
+    fn test_return_ttl_alternating_never_settles() {
+        let mut rt = ReturnTtl::new(54);
+        for i in 0..50 {
+            let ttl = if i % 2 == 0 { 53 } else { 55 };
+            assert_eq!(rt.record(ttl), None);
+        }
+        // A reply at the settled value resets the streak
+        for _ in 0..RETURN_TTL_SETTLE - 1 {
+            rt.record(53);
+        }
+        rt.record(54);
+        assert_eq!(rt.record(53), None);
+        assert_eq!(rt.current, 54);
+        assert_eq!(rt.changes, 0);
+    }
+}
@@ -1,14 +1,55 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::net::IpAddr;
 use std::time::Duration;
 
-use crate::config::Config;
+use crate::config::{Config, RedirectPolicy};
+use crate::state::alias::find_alias;
+use crate::state::correlation::{LossCorrelation, RoundLoss, serialize_correlations};
+use crate::state::distance::{GeoPoint, HopDistance, OriginSource};
+use crate::state::ecmp::EcmpEstimate;
+use crate::state::ecn::{EcnChange, EcnCodepoint, EcnInfo};
+use crate::state::epoch::{HopSnapshot, MAX_PATH_EPOCHS, PathEpoch};
+use crate::state::events::{
+    Event, EventKind, LOSS_ALERT_MIN_SAMPLES, MAX_EVENTS, OUTAGE_THRESHOLD, PrefixChangeKind,
+};
+use crate::state::label::{LabelMode, display_name};
+use crate::state::link::{InferredLink, infer_links};
+use crate::state::load::{LoadResult, LoadTest};
+use crate::state::loops::{RoundPaths, RoutingLoop, find_loop};
+use crate::state::loss::attribute_loss;
+use crate::state::mtu::{MtuEstimate, MtuHints};
+use crate::state::pacing::HopPace;
+use crate::state::paths::PathTree;
+use crate::state::return_ttl::ReturnTtl;
+use crate::state::signature::{SignatureHistory, as_path_signature, path_signature};
+use crate::state::slo::{Slo, SloStatus};
+use crate::state::spike::{self, RttShift};
+use crate::state::sweep::DfSweep;
+use crate::state::tunnel::{TunnelSpan, infer_tunnels};
+use crate::state::window::{SlidingWindow, WindowSummary};
+
+/// Replies a new primary responder must persist for by default
+pub const DEFAULT_FLAP_HOLD: u32 = 3;
+
+/// Rounds without a reply after which a responder is shown as stale
+pub const DEFAULT_STALE_AFTER: u64 = 10;
 
 /// Window size for recent RTT/result tracking (used for sparklines, jitter calculation)
 const RECENT_WINDOW_SIZE: usize = 60;
 
+/// Rounds between full-depth probes while `--gap-limit` holds probing back
+pub const GAP_RECHECK_ROUNDS: u64 = 10;
+/// Probes a hop must have gone unanswered before it counts as silent
+const GAP_MIN_SENT: u64 = 3;
+
+/// Probe outcomes kept per hop for the response code strip
+const OUTCOME_HISTORY: usize = 40;
+
+/// Timestamped probe results kept per hop (10 minutes at the default interval)
+pub const SAMPLE_HISTORY: usize = 600;
+
 /// Identifies a specific probe for correlation
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub struct ProbeId {
@@ -46,6 +87,49 @@ pub enum IcmpResponseType {
     PacketTooBig,
 }
 
+impl IcmpResponseType {
+    /// IPv4 Fragmentation Needed or ICMPv6 Packet Too Big
+    pub fn is_frag_needed(self) -> bool {
+        matches!(self, Self::DestUnreachable(4) | Self::PacketTooBig)
+    }
+
+    /// One-character code for the hop detail's response strip: `o` echo
+    /// reply, `.` time exceeded, and mtr's unreachable letters (`N` net,
+    /// `H` host, `P` protocol, `U` port, `F` fragmentation needed, `X`
+    /// administratively prohibited, `!` other). ICMPv6 numbers its
+    /// unreachable codes differently, hence `ipv6`.
+    pub fn symbol(self, ipv6: bool) -> char {
+        match self {
+            Self::EchoReply => 'o',
+            Self::TimeExceeded(_) => '.',
+            Self::PacketTooBig => 'F',
+            Self::DestUnreachable(code) if ipv6 => match code {
+                0 => 'N',
+                1 | 5 | 6 => 'X',
+                3 => 'H',
+                4 => 'U',
+                _ => '!',
+            },
+            Self::DestUnreachable(code) => match code {
+                0 => 'N',
+                1 => 'H',
+                2 => 'P',
+                3 => 'U',
+                4 => 'F',
+                9 | 10 | 13 => 'X',
+                _ => '!',
+            },
+        }
+    }
+}
+
+/// How a probe to a hop ended, for the response code strip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Reply(IpAddr, IcmpResponseType),
+    Timeout,
+}
+
 /// MPLS label from ICMP extension (RFC 4950)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MplsLabel {
@@ -74,6 +158,43 @@ impl MplsLabel {
     }
 }
 
+/// Interface a router says the probe arrived on, from an ICMP Interface
+/// Information extension (RFC 5837). Routers fill in any subset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncomingInterface {
+    /// ifIndex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<IpAddr>,
+    /// e.g. "xe-0/0/1" or "Bundle-Ether12"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+}
+
+impl IncomingInterface {
+    /// e.g. "xe-0/0/1 (ifIndex 512, 10.0.0.1, MTU 9000)"
+    pub fn describe(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(index) = self.index {
+            details.push(format!("ifIndex {}", index));
+        }
+        if let Some(address) = self.address {
+            details.push(address.to_string());
+        }
+        if let Some(mtu) = self.mtu {
+            details.push(format!("MTU {}", mtu));
+        }
+        match (&self.name, details.is_empty()) {
+            (Some(name), true) => name.clone(),
+            (Some(name), false) => format!("{} ({})", name, details.join(", ")),
+            (None, _) => details.join(", "),
+        }
+    }
+}
+
 /// Result of a single probe
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -85,11 +206,66 @@ pub struct ProbeResult {
 }
 
 /// ASN information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AsnInfo {
     pub number: u32,
     pub name: String,
     pub prefix: Option<String>,
+    /// Regional Internet Registry that allocated the ASN (e.g. "arin", "ripencc")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Country code the ASN is registered in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Allocation date (YYYY-MM-DD)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated: Option<String>,
+}
+
+impl AsnInfo {
+    /// Display name for the registry ("RIPE NCC" rather than "ripencc")
+    pub fn registry_label(&self) -> Option<String> {
+        self.registry.as_deref().map(|r| match r {
+            "ripencc" => "RIPE NCC".to_string(),
+            other => other.to_uppercase(),
+        })
+    }
+}
+
+/// Announced BGP prefix covering a responder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefixOrigin {
+    /// Most specific announced prefix covering the address
+    pub prefix: String,
+    /// Origin AS numbers (more than one = MOAS)
+    pub origins: Vec<u32>,
+    /// A more-specific prefix appeared while the session was running
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub more_specific: bool,
+    /// Prefix and origin before the last change ("192.0.2.0/24 AS64500")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+impl PrefixOrigin {
+    pub fn new(prefix: String, origins: Vec<u32>) -> Self {
+        Self {
+            prefix,
+            origins,
+            more_specific: false,
+            previous: None,
+        }
+    }
+
+    /// "192.0.2.0/24 AS64500" (origins joined with "/")
+    pub fn label(&self) -> String {
+        let origins: Vec<String> = self.origins.iter().map(|a| format!("AS{}", a)).collect();
+        if origins.is_empty() {
+            self.prefix.clone()
+        } else {
+            format!("{} {}", self.prefix, origins.join("/"))
+        }
+    }
 }
 
 /// Geolocation information
@@ -113,6 +289,16 @@ pub struct IxInfo {
     pub country: Option<String>,
 }
 
+/// Lookups asked to run again for a responder, past their caches
+/// (`r` in hop detail). Each worker clears its flag once it has asked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LookupRefresh {
+    pub dns: bool,
+    pub asn: bool,
+    pub geo: bool,
+    pub ix: bool,
+}
+
 /// Statistics for a single responder IP at a given TTL hop.
 ///
 /// Each hop in the traceroute path may have multiple responders (ECMP load balancing),
@@ -127,16 +313,72 @@ pub struct ResponderStats {
     pub geo: Option<GeoInfo>,
     /// Internet Exchange info (from PeeringDB)
     pub ix: Option<IxInfo>,
+    /// Lookups to redo, bypassing their caches
+    #[serde(skip)]
+    pub refresh: LookupRefresh,
 
     /// MPLS labels from ICMP extensions (RFC 4950)
     pub mpls_labels: Option<Vec<MplsLabel>>,
 
+    /// Interface the probe arrived on, as the router reports it (RFC 5837)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface: Option<IncomingInterface>,
+
+    /// BGP prefix and origin (from --prefix-watch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix_origin: Option<PrefixOrigin>,
+
+    /// Name of the alias rule this responder matched (--alias)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Friendly name from a --label rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Other addresses merged into this responder (alias rules or a
+    /// shared hostname)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<IpAddr>,
+
+    /// IP TTL of this responder's replies (reverse path length)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_ttl: Option<ReturnTtl>,
+
+    /// Fields returned by `--plugin responder=...` commands
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugin_data: BTreeMap<String, String>,
+
+    /// Annotations from the `--enrich-url` endpoint
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+
+    /// When this responder first and last answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Hop probe count (`Hop.sent`) at its last reply, for staleness
+    #[serde(default)]
+    pub last_seen_probe: u64,
+    /// Minutes in which this responder answered at least once
+    #[serde(default)]
+    pub answered_minutes: u64,
+    /// Minutes the hop had been probed before this responder first answered
+    #[serde(default)]
+    pub minutes_before_seen: u64,
+    /// Minute (since the epoch) of the latest reply
+    #[serde(skip)]
+    last_answered_minute: Option<i64>,
+
     // Counters
-    // Note: sent is kept for JSON schema compatibility but not used in TUI
-    // (we can't attribute probes to responders before receiving a reply;
-    // use Hop.sent for hop-level probe counts)
+    // A probe is attributed to a responder when it answers, or when it
+    // times out on a flow only this responder has answered (ECMP-pinned).
+    // Other timeouts can't be attributed; Hop::responder_loss spreads them
+    // by reply share. Hop.sent is the hop-level probe count.
     pub sent: u64,
     pub received: u64,
+    /// Timeouts on flows pinned to this responder
+    #[serde(default)]
+    pub timeouts: u64,
 
     // Latency stats (Welford's online algorithm)
     #[serde(with = "duration_serde")]
@@ -145,6 +387,10 @@ pub struct ResponderStats {
     pub max_rtt: Duration,
     pub mean_rtt: f64, // microseconds
     pub m2: f64,       // for stddev calculation
+    /// Half-width of the 95% confidence interval for `mean_rtt` in
+    /// microseconds (None below 2 replies)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean_ci95: Option<f64>,
 
     // Jitter (RFC 3550)
     pub jitter: f64,     // microseconds (smoothed)
@@ -157,9 +403,36 @@ pub struct ResponderStats {
     #[serde(skip)]
     pub recent: VecDeque<Option<Duration>>,
 
+    /// RTT change between consecutive replies, for the jitter sparkline
+    #[serde(skip)]
+    pub recent_jitter: VecDeque<Duration>,
+
     // Sample history for percentile calculations
     #[serde(skip)]
     pub samples: VecDeque<Duration>,
+
+    /// Recent-results window (--stats-window)
+    #[serde(skip)]
+    pub window: Option<SlidingWindow>,
+}
+
+/// Two-sided 95% critical value of Student's t with `df` degrees of freedom
+fn t_critical_95(df: u64) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::INFINITY,
+        1..=30 => TABLE[df as usize - 1],
+        // Between table rows use the smaller df's value, erring wide
+        31..=40 => 2.042,
+        41..=60 => 2.021,
+        61..=120 => 2.000,
+        121..=1000 => 1.980,
+        _ => 1.962,
+    }
 }
 
 impl ResponderStats {
@@ -170,19 +443,38 @@ impl ResponderStats {
             asn: None,
             geo: None,
             ix: None,
+            refresh: LookupRefresh::default(),
             mpls_labels: None,
+            interface: None,
+            prefix_origin: None,
+            alias: None,
+            label: None,
+            aliases: Vec::new(),
+            return_ttl: None,
+            plugin_data: BTreeMap::new(),
+            extra: BTreeMap::new(),
+            first_seen: None,
+            last_seen: None,
+            last_seen_probe: 0,
+            answered_minutes: 0,
+            minutes_before_seen: 0,
+            last_answered_minute: None,
             sent: 0,
             received: 0,
+            timeouts: 0,
             min_rtt: Duration::MAX,
             max_rtt: Duration::ZERO,
             mean_rtt: 0.0,
             m2: 0.0,
+            mean_ci95: None,
             jitter: 0.0,
             jitter_avg: 0.0,
             jitter_max: 0.0,
             last_rtt: None,
             recent: VecDeque::with_capacity(RECENT_WINDOW_SIZE),
+            recent_jitter: VecDeque::with_capacity(RECENT_WINDOW_SIZE),
             samples: VecDeque::with_capacity(256),
+            window: None,
         }
     }
 
@@ -191,8 +483,13 @@ impl ResponderStats {
 
     /// Update stats with a new RTT sample
     pub fn record_response(&mut self, rtt: Duration) {
+        self.sent += 1;
         self.received += 1;
 
+        let now = Utc::now();
+        self.first_seen.get_or_insert(now);
+        self.last_seen = Some(now);
+
         let rtt_micros = rtt.as_micros() as f64;
 
         // Update min/max
@@ -208,6 +505,7 @@ impl ResponderStats {
         self.mean_rtt += delta / self.received as f64;
         let delta2 = rtt_micros - self.mean_rtt;
         self.m2 += delta * delta2;
+        self.mean_ci95 = self.compute_ci95();
 
         // Jitter calculation uses RFC 3550-inspired smoothed variance:
         //
@@ -241,6 +539,12 @@ impl ResponderStats {
             // Note: jitter samples start at received=2, so use (received-1) for count
             let jitter_count = (self.received - 1) as f64;
             self.jitter_avg += (diff - self.jitter_avg) / jitter_count;
+
+            self.recent_jitter
+                .push_back(Duration::from_micros(diff as u64));
+            if self.recent_jitter.len() > RECENT_WINDOW_SIZE {
+                self.recent_jitter.pop_front();
+            }
         }
         self.last_rtt = Some(rtt);
 
@@ -255,6 +559,67 @@ impl ResponderStats {
         if self.samples.len() > Self::MAX_SAMPLES {
             self.samples.pop_front();
         }
+
+        if let Some(ref mut window) = self.window {
+            window.record_response(rtt);
+        }
+    }
+
+    /// Fold another address's stats into this one (responder aliasing).
+    /// Counts and RTT moments combine exactly; jitter, the sparkline and
+    /// the sliding window keep this responder's sequence.
+    pub fn absorb(&mut self, other: ResponderStats) {
+        let (n1, n2) = (self.received as f64, other.received as f64);
+        if n2 > 0.0 {
+            // Chan et al. parallel variance
+            let n = n1 + n2;
+            let delta = other.mean_rtt - self.mean_rtt;
+            self.mean_rtt += delta * n2 / n;
+            self.m2 += other.m2 + delta * delta * n1 * n2 / n;
+            self.min_rtt = self.min_rtt.min(other.min_rtt);
+            self.max_rtt = self.max_rtt.max(other.max_rtt);
+            self.jitter_max = self.jitter_max.max(other.jitter_max);
+        }
+        self.sent += other.sent;
+        self.received += other.received;
+        self.timeouts += other.timeouts;
+        self.mean_ci95 = self.compute_ci95();
+        self.last_rtt = self.last_rtt.or(other.last_rtt);
+        self.first_seen = match (self.first_seen, other.first_seen) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.last_seen = self.last_seen.max(other.last_seen);
+        self.last_seen_probe = self.last_seen_probe.max(other.last_seen_probe);
+        // Which minutes overlapped isn't kept; the busier address is a lower bound
+        self.answered_minutes = self.answered_minutes.max(other.answered_minutes);
+        self.minutes_before_seen = self.minutes_before_seen.min(other.minutes_before_seen);
+        self.last_answered_minute = self.last_answered_minute.max(other.last_answered_minute);
+
+        self.samples.extend(other.samples);
+        while self.samples.len() > Self::MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.hostname = self.hostname.take().or(other.hostname);
+        self.asn = self.asn.take().or(other.asn);
+        self.geo = self.geo.take().or(other.geo);
+        self.ix = self.ix.take().or(other.ix);
+        self.mpls_labels = self.mpls_labels.take().or(other.mpls_labels);
+        self.interface = self.interface.take().or(other.interface);
+        self.prefix_origin = self.prefix_origin.take().or(other.prefix_origin);
+        self.alias = self.alias.take().or(other.alias);
+        self.label = self.label.take().or(other.label);
+        self.return_ttl = self.return_ttl.take().or(other.return_ttl);
+        for (key, value) in other.plugin_data {
+            self.plugin_data.entry(key).or_insert(value);
+        }
+        for (key, value) in other.extra {
+            self.extra.entry(key).or_insert(value);
+        }
+
+        self.aliases.push(other.ip);
+        self.aliases.extend(other.aliases);
     }
 
     /// Record a timeout (no response) - updates sparkline only
@@ -266,17 +631,27 @@ impl ResponderStats {
         }
     }
 
-    /// Loss percentage (per-responder, not accurate since sent is always 0)
-    /// Use Hop::loss_pct() for accurate hop-level loss calculation.
+    /// Loss among the probes attributed to this responder. Timeouts that
+    /// couldn't be attributed are left out; see `Hop::responder_loss`.
     #[allow(dead_code)]
     pub fn loss_pct(&self) -> f64 {
         if self.sent == 0 {
             0.0
         } else {
-            (1.0 - (self.received as f64 / self.sent as f64)) * 100.0
+            (self.timeouts as f64 / self.sent as f64) * 100.0
         }
     }
 
+    /// Label, reverse DNS name or alias to show instead of the address
+    pub fn display_name(&self, mode: LabelMode) -> Option<String> {
+        display_name(
+            self.label.as_deref(),
+            self.hostname.as_deref(),
+            self.alias.as_deref(),
+            mode,
+        )
+    }
+
     /// Average RTT
     pub fn avg_rtt(&self) -> Duration {
         Duration::from_micros(self.mean_rtt as u64)
@@ -291,6 +666,25 @@ impl ResponderStats {
         Duration::from_micros(variance.sqrt() as u64)
     }
 
+    /// Student's t interval from the sample variance. It treats replies as
+    /// independent, which queueing bursts are not, so read it as a lower
+    /// bound on the uncertainty.
+    fn compute_ci95(&self) -> Option<f64> {
+        if self.received < 2 {
+            return None;
+        }
+        let n = self.received as f64;
+        let sample_sd = (self.m2 / (n - 1.0)).max(0.0).sqrt();
+        Some(t_critical_95(self.received - 1) * sample_sd / n.sqrt())
+    }
+
+    /// 95% confidence interval for the mean RTT, as (low, high)
+    pub fn mean_ci(&self) -> Option<(Duration, Duration)> {
+        let half = self.mean_ci95?;
+        let micros = |us: f64| Duration::from_micros(us.max(0.0) as u64);
+        Some((micros(self.mean_rtt - half), micros(self.mean_rtt + half)))
+    }
+
     /// Smoothed jitter (RFC 3550)
     pub fn jitter(&self) -> Duration {
         Duration::from_micros(self.jitter as u64)
@@ -354,6 +748,9 @@ pub struct FlowPathStats {
     /// Count of responses per responder IP on this flow
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub responder_counts: HashMap<IpAddr, u64>,
+    /// Source port this flow's probes are sent from (UDP/TCP)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src_port: Option<u16>,
 }
 
 impl FlowPathStats {
@@ -386,6 +783,18 @@ impl FlowPathStats {
         self.timeouts += 1;
     }
 
+    /// Count `from`'s responses under `into` (responder aliasing)
+    fn merge_responder(&mut self, into: IpAddr, from: IpAddr) {
+        if let Some(count) = self.responder_counts.remove(&from) {
+            *self.responder_counts.entry(into).or_insert(0) += count;
+            self.primary_responder = self
+                .responder_counts
+                .iter()
+                .max_by_key(|(_, c)| *c)
+                .map(|(ip, _)| *ip);
+        }
+    }
+
     /// Loss percentage for this flow (based on completed probes only)
     #[allow(dead_code)]
     pub fn loss_pct(&self) -> f64 {
@@ -402,12 +811,17 @@ impl FlowPathStats {
 ///
 /// Tracks source port matches and rewrites to detect NAT devices.
 /// When NAT rewrites source ports, multi-flow ECMP detection may be affected.
+/// A quoted checksum that changed while the port survived means the source
+/// address was rewritten (a port-preserving NAT, which must fix the checksum up).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NatInfo {
     /// Number of probes where source port matched (no NAT)
     pub port_matched: u64,
     /// Number of probes where source port was rewritten (NAT detected)
     pub port_rewritten: u64,
+    /// Of the port matches, probes whose quoted checksum differed from the one sent
+    #[serde(default)]
+    pub checksum_rewritten: u64,
     /// Sample of rewritten ports: (original, returned) - limited to MAX_SAMPLES
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub rewrite_samples: Vec<(u16, u16)>,
@@ -430,18 +844,25 @@ impl NatInfo {
         }
     }
 
-    /// True if NAT is detected (any port rewrite observed)
+    /// Record a port match whose quoted checksum was rewritten
+    pub fn record_checksum_rewrite(&mut self) {
+        self.port_matched += 1;
+        self.checksum_rewritten += 1;
+    }
+
+    /// True if NAT is detected (any port or checksum rewrite observed)
     pub fn has_nat(&self) -> bool {
-        self.port_rewritten > 0
+        self.port_rewritten > 0 || self.checksum_rewritten > 0
     }
 
-    /// NAT detection confidence (percentage of probes with rewritten ports)
+    /// NAT detection confidence (percentage of probes with a rewritten port
+    /// or checksum)
     pub fn nat_percentage(&self) -> f64 {
         let total = self.port_matched + self.port_rewritten;
         if total == 0 {
             0.0
         } else {
-            (self.port_rewritten as f64 / total as f64) * 100.0
+            ((self.port_rewritten + self.checksum_rewritten) as f64 / total as f64) * 100.0
         }
     }
 
@@ -460,6 +881,38 @@ pub struct RouteChange {
     pub to_ip: IpAddr,
     /// Response count when change was detected
     pub at_seq: u64,
+    /// Recorded during the cooldown after an earlier change, so no
+    /// path change event was logged for it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub damped: bool,
+}
+
+/// Hold-down and damping settings for path change detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlapDamping {
+    /// Consecutive replies the new primary needs before a change counts
+    pub hold: u32,
+    /// Replies after a change during which further changes are recorded
+    /// but not logged as events
+    pub cooldown: u64,
+}
+
+impl Default for FlapDamping {
+    fn default() -> Self {
+        Self {
+            hold: DEFAULT_FLAP_HOLD,
+            cooldown: 0,
+        }
+    }
+}
+
+impl FlapDamping {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            hold: config.flap_hold.max(1),
+            cooldown: config.flap_cooldown,
+        }
+    }
 }
 
 /// Asymmetric routing detection information for a hop
@@ -537,7 +990,7 @@ impl AsymmetryInfo {
 ///
 /// Uses common initial TTL defaults to estimate how many hops the response
 /// traveled. Picks the smallest default >= observed TTL.
-fn estimate_return_hops(response_ttl: u8, _ipv6: bool) -> u8 {
+pub(crate) fn estimate_return_hops(response_ttl: u8, _ipv6: bool) -> u8 {
     // Common initial TTL/hop-limit defaults by OS/device type
     // Must be sorted ascending for find() to work correctly
     // IPv4 and IPv6 share the same common defaults:
@@ -656,6 +1109,71 @@ impl TtlManipInfo {
     }
 }
 
+/// DSCP values quoted back in a hop's ICMP errors
+///
+/// An ICMP error quotes the probe's IP header as the responder received
+/// it, so comparing the quoted DSCP with the one sent shows where along the
+/// path QoS markings get rewritten.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DscpInfo {
+    /// DSCP the probes were sent with
+    pub sent: u8,
+    /// Quoted DSCP values and how often each was seen
+    pub seen: BTreeMap<u8, u64>,
+}
+
+impl DscpInfo {
+    /// Quotes needed before a hop's DSCP counts
+    pub const MIN_SAMPLES: u64 = 3;
+
+    pub fn record(&mut self, sent: u8, quoted: u8) {
+        self.sent = sent;
+        *self.seen.entry(quoted).or_default() += 1;
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.seen.values().sum()
+    }
+
+    /// DSCP most of the quotes carried, once there are enough of them
+    pub fn observed(&self) -> Option<u8> {
+        if self.samples() < Self::MIN_SAMPLES {
+            return None;
+        }
+        self.seen
+            .iter()
+            .max_by_key(|&(value, count)| (*count, std::cmp::Reverse(*value)))
+            .map(|(&value, _)| value)
+    }
+}
+
+/// DSCP value with its class name where it has one, e.g. "46/EF", "18/AF21"
+pub fn dscp_label(value: u8) -> String {
+    let name = match value {
+        0 => Some("BE".to_string()),
+        46 => Some("EF".to_string()),
+        44 => Some("VA".to_string()),
+        v if v % 8 == 0 && v <= 56 => Some(format!("CS{}", v / 8)),
+        v if (1..=4).contains(&(v / 8)) && [2, 4, 6].contains(&(v % 8)) => {
+            Some(format!("AF{}{}", v / 8, v % 8 / 2))
+        }
+        _ => None,
+    };
+    match name {
+        Some(name) => format!("{}/{}", value, name),
+        None => value.to_string(),
+    }
+}
+
+/// Point where the DSCP marking changed: the responder at `ttl` received
+/// `to` where the hop before it (or the sender) had `from`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DscpRemark {
+    pub ttl: u8,
+    pub from: u8,
+    pub to: u8,
+}
+
 /// ICMP rate limit detection info for a hop.
 ///
 /// Many routers rate-limit ICMP responses (Time Exceeded, Destination Unreachable),
@@ -808,6 +1326,30 @@ impl PmtudState {
     }
 }
 
+/// One probe result, stamped with the wall-clock time it completed (the
+/// reply arrived or the probe timed out) so exported histories line up
+/// with other monitoring systems
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub at: DateTime<Utc>,
+    /// Responder (None = timeout)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpAddr>,
+    /// Round-trip time in milliseconds (None = timeout)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f64>,
+}
+
+/// A responder's probes and loss at a hop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResponderLoss {
+    /// Probes sent its way, including its share of unattributed timeouts
+    pub sent: f64,
+    pub loss_pct: f64,
+    /// Whether unattributed timeouts went into the figures
+    pub estimated: bool,
+}
+
 /// A single hop (TTL level) in the path
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hop {
@@ -817,16 +1359,29 @@ pub struct Hop {
     /// Number of timed-out probes (used for accurate loss calculation)
     #[serde(default)]
     pub timeouts: u64,
+    /// Replies that arrived after their probe had timed out. They still
+    /// count in `timeouts`; a high share means queueing delay rather than loss.
+    #[serde(default)]
+    pub late: u64,
+    /// RTTs of the most recent late replies (up to LATE_SAMPLES)
+    #[serde(skip)]
+    pub late_samples: VecDeque<Duration>,
     pub responders: HashMap<IpAddr, ResponderStats>,
     pub primary: Option<IpAddr>, // most frequently seen responder
     /// Rolling window of recent probe results for hop-level loss sparkline
     /// true = response received, false = timeout
     #[serde(skip)]
     pub recent_results: VecDeque<bool>,
+    /// Latest probe results with timestamps, oldest first (capped at SAMPLE_HISTORY)
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    pub history: VecDeque<Sample>,
     /// Per-flow path statistics for ECMP detection (Paris/Dublin traceroute)
     /// Maps flow_id (0-255) to per-flow stats
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub flow_paths: HashMap<u8, FlowPathStats>,
+    /// Estimated ECMP width from `flow_paths` (two or more flows answering)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecmp: Option<EcmpEstimate>,
     /// NAT detection information for this hop
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub nat_info: Option<NatInfo>,
@@ -836,16 +1391,59 @@ pub struct Hop {
     /// Route changes (flaps) detected at this hop (single-flow mode only)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub route_changes: Vec<RouteChange>,
+    /// Total route changes (`route_changes` only keeps the latest)
+    #[serde(default)]
+    pub flap_count: u64,
+    /// Primary switches that reverted before the hold-down elapsed
+    /// (typically per-packet ECMP noise)
+    #[serde(default)]
+    pub suppressed_flaps: u64,
     /// Asymmetric routing detection information for this hop
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub asymmetry: Option<AsymmetryInfo>,
     /// TTL manipulation detection information for this hop
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ttl_manip: Option<TtlManipInfo>,
+    /// DSCP quoted back in this hop's ICMP errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dscp: Option<DscpInfo>,
+    /// ECN codepoints quoted back in this hop's ICMP errors (`--ecn`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecn: Option<EcnInfo>,
+    /// Probing pace under --adaptive
+    #[serde(skip)]
+    pub pace: HopPace,
     /// Internal: tracks primary with hysteresis for flap detection only
     /// (separate from `primary` which always reflects true most-frequent)
     #[serde(skip)]
     flap_tracking_primary: Option<IpAddr>,
+    /// Internal: switch of `flap_tracking_primary` awaiting hold-down (from, to)
+    #[serde(skip)]
+    pending_flap: Option<(IpAddr, IpAddr)>,
+    /// Internal: last responder and how many replies in a row it sent
+    #[serde(skip)]
+    reply_streak: Option<(IpAddr, u32)>,
+    /// Internal: merged-away address -> responder it is counted under
+    #[serde(skip)]
+    alias_of: HashMap<IpAddr, IpAddr>,
+    /// Recent-results window for loss (--stats-window); responders keep
+    /// their own for RTT
+    #[serde(skip)]
+    pub window: Option<SlidingWindow>,
+    /// Minutes in which this hop was probed (availability denominator)
+    #[serde(default)]
+    pub probed_minutes: u64,
+    /// Minute (since the epoch) of the latest probe
+    #[serde(skip)]
+    last_probed_minute: Option<i64>,
+    /// Latest replies (with their ICMP type) and timeouts, oldest first
+    #[serde(skip)]
+    pub outcomes: VecDeque<ProbeOutcome>,
+}
+
+/// Whole minutes since the Unix epoch, the availability bucket
+fn current_minute() -> i64 {
+    Utc::now().timestamp().div_euclid(60)
 }
 
 impl Hop {
@@ -855,6 +1453,8 @@ impl Hop {
     const MIN_RESPONSES_FOR_FLAP: u64 = 5;
     /// Maximum route changes to store
     const MAX_ROUTE_CHANGES: usize = 50;
+    /// Maximum late-reply RTTs to keep
+    const LATE_SAMPLES: usize = 256;
 
     pub fn new(ttl: u8) -> Self {
         Self {
@@ -862,22 +1462,108 @@ impl Hop {
             sent: 0,
             received: 0,
             timeouts: 0,
+            late: 0,
+            late_samples: VecDeque::new(),
             responders: HashMap::new(),
             primary: None,
             recent_results: VecDeque::with_capacity(RECENT_WINDOW_SIZE),
+            history: VecDeque::new(),
             flow_paths: HashMap::new(),
+            ecmp: None,
             nat_info: None,
             rate_limit: None,
             route_changes: Vec::new(),
+            flap_count: 0,
+            suppressed_flaps: 0,
             asymmetry: None,
             ttl_manip: None,
+            dscp: None,
+            ecn: None,
+            pace: HopPace::default(),
             flap_tracking_primary: None,
+            pending_flap: None,
+            reply_streak: None,
+            alias_of: HashMap::new(),
+            window: None,
+            probed_minutes: 0,
+            last_probed_minute: None,
+            outcomes: VecDeque::with_capacity(OUTCOME_HISTORY),
+        }
+    }
+
+    /// Keep sliding-window stats over the last `length` alongside the
+    /// cumulative ones
+    pub fn enable_window(&mut self, length: Duration) {
+        self.window = Some(SlidingWindow::new(length));
+        for stats in self.responders.values_mut() {
+            stats.window = Some(SlidingWindow::new(length));
+        }
+    }
+
+    /// Loss and RTT over the sliding window, if enabled. RTT figures are
+    /// the primary responder's.
+    pub fn window_summary(&self) -> Option<WindowSummary> {
+        let mut summary = self.window.as_ref()?.summary();
+        let rtt = self
+            .primary_stats()
+            .and_then(|s| s.window.as_ref())
+            .map(SlidingWindow::summary);
+        if let Some(rtt) = rtt {
+            summary.min_rtt = rtt.min_rtt;
+            summary.max_rtt = rtt.max_rtt;
+            summary.avg_rtt = rtt.avg_rtt;
+            summary.stddev = rtt.stddev;
+            summary.jitter_avg = rtt.jitter_avg;
         }
+        Some(summary)
     }
 
     /// Record a probe was sent for this TTL
     pub fn record_sent(&mut self) {
         self.sent += 1;
+        self.note_probed_minute(current_minute());
+    }
+
+    /// Count `minute` as probed (once per minute)
+    fn note_probed_minute(&mut self, minute: i64) {
+        if self.last_probed_minute != Some(minute) {
+            self.last_probed_minute = Some(minute);
+            self.probed_minutes += 1;
+        }
+    }
+
+    /// Count `minute` as answered by `ip` (once per minute)
+    fn note_answered_minute(&mut self, ip: IpAddr, minute: i64) {
+        self.note_probed_minute(minute);
+        let probed = self.probed_minutes;
+        if let Some(stats) = self.responders.get_mut(&ip)
+            && stats.last_answered_minute != Some(minute)
+        {
+            if stats.answered_minutes == 0 {
+                stats.minutes_before_seen = probed - 1;
+            }
+            stats.last_answered_minute = Some(minute);
+            stats.answered_minutes += 1;
+        }
+    }
+
+    /// Share of complete minutes since `stats` first answered in which it
+    /// answered at least once, in percent, with the number of minutes.
+    /// The current minute only counts once it has answered. None until a
+    /// full minute has passed.
+    pub fn availability(&self, stats: &ResponderStats) -> Option<(f64, u64)> {
+        let answered_now = stats.last_answered_minute.is_some()
+            && stats.last_answered_minute == self.last_probed_minute;
+        let current = u64::from(!answered_now && self.last_probed_minute.is_some());
+        let minutes = self
+            .probed_minutes
+            .saturating_sub(stats.minutes_before_seen)
+            .saturating_sub(current);
+        if minutes == 0 || stats.answered_minutes == 0 {
+            return None;
+        }
+        let pct = (stats.answered_minutes as f64 / minutes as f64 * 100.0).min(100.0);
+        Some((pct, minutes))
     }
 
     /// Record a response from a responder
@@ -893,30 +1579,51 @@ impl Hop {
         rtt: Duration,
         mpls_labels: Option<Vec<MplsLabel>>,
     ) {
+        let ip = self.canonical(ip);
         self.received += 1;
 
-        let stats = self
-            .responders
-            .entry(ip)
-            .or_insert_with(|| ResponderStats::new(ip));
+        let window_len = self.window.as_mut().map(|w| {
+            w.record_response(rtt);
+            w.length()
+        });
+        let stats = self.responders.entry(ip).or_insert_with(|| {
+            let mut stats = ResponderStats::new(ip);
+            stats.window = window_len.map(SlidingWindow::new);
+            stats
+        });
         // Note: We use hop-level loss calculation (Hop::loss_pct), not per-responder.
         // ResponderStats tracks response count for display purposes only.
         stats.record_response(rtt);
+        stats.last_seen_probe = self.sent;
 
         // Store MPLS labels if present (only update if we got labels)
         if mpls_labels.is_some() {
             stats.mpls_labels = mpls_labels;
         }
+        self.note_answered_minute(ip, current_minute());
 
         // Track in hop-level sparkline
         self.recent_results.push_back(true);
         if self.recent_results.len() > RECENT_WINDOW_SIZE {
             self.recent_results.pop_front();
         }
+        self.push_sample(Some(ip), Some(rtt));
 
         self.update_primary();
     }
 
+    /// Append to the timestamped history, dropping the oldest past SAMPLE_HISTORY
+    fn push_sample(&mut self, ip: Option<IpAddr>, rtt: Option<Duration>) {
+        self.history.push_back(Sample {
+            at: Utc::now(),
+            ip,
+            rtt_ms: rtt.map(|d| d.as_secs_f64() * 1000.0),
+        });
+        if self.history.len() > SAMPLE_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
     /// Record a response and detect route changes (single-flow mode only)
     ///
     /// This is the same as `record_response_with_mpls` but also tracks when
@@ -925,15 +1632,27 @@ impl Hop {
     /// Uses a separate `flap_tracking_primary` with hysteresis (margin of 2)
     /// to avoid false flaps from per-packet load balancing noise, while keeping
     /// `self.primary` as the true most-frequent responder for UI/export.
+    /// A switch is then held down until the new primary has answered
+    /// `damping.hold` times in a row; switches that revert first are only
+    /// counted in `suppressed_flaps`.
+    ///
+    /// Returns the route change if this response should be logged as one.
     pub fn record_response_detecting_flaps(
         &mut self,
         ip: IpAddr,
         rtt: Duration,
         mpls_labels: Option<Vec<MplsLabel>>,
-    ) {
+        damping: FlapDamping,
+    ) -> Option<RouteChange> {
+        let ip = self.canonical(ip);
         let old_flap_primary = self.flap_tracking_primary;
         self.record_response_with_mpls(ip, rtt, mpls_labels);
 
+        self.reply_streak = match self.reply_streak {
+            Some((last, n)) if last == ip => Some((ip, n + 1)),
+            _ => Some((ip, 1)),
+        };
+
         // Update flap_tracking_primary with hysteresis
         let current_count = self
             .flap_tracking_primary
@@ -950,21 +1669,50 @@ impl Hop {
             }
         }
 
-        // Check for route change (only after minimum responses)
-        if self.received >= Self::MIN_RESPONSES_FOR_FLAP
-            && let (Some(old), Some(new)) = (old_flap_primary, self.flap_tracking_primary)
+        if let (Some(old), Some(new)) = (old_flap_primary, self.flap_tracking_primary)
             && old != new
         {
-            self.route_changes.push(RouteChange {
-                from_ip: old,
-                to_ip: new,
-                at_seq: self.received,
-            });
-            // Cap history size
-            if self.route_changes.len() > Self::MAX_ROUTE_CHANGES {
-                self.route_changes.remove(0);
-            }
+            self.pending_flap = match self.pending_flap {
+                // Switched back before the hold-down elapsed
+                Some((from, _)) if from == new => {
+                    self.suppressed_flaps += 1;
+                    None
+                }
+                Some((from, _)) => Some((from, new)),
+                None => Some((old, new)),
+            };
+        }
+
+        let (from, to) = self.pending_flap?;
+        if self
+            .reply_streak
+            .is_none_or(|(last, n)| last != to || n < damping.hold)
+        {
+            return None;
+        }
+        self.pending_flap = None;
+
+        // Check for route change (only after minimum responses)
+        if self.received < Self::MIN_RESPONSES_FOR_FLAP {
+            return None;
+        }
+        let damped = self
+            .route_changes
+            .last()
+            .is_some_and(|last| self.received - last.at_seq < damping.cooldown);
+        let change = RouteChange {
+            from_ip: from,
+            to_ip: to,
+            at_seq: self.received,
+            damped,
+        };
+        self.flap_count += 1;
+        self.route_changes.push(change.clone());
+        // Cap history size
+        if self.route_changes.len() > Self::MAX_ROUTE_CHANGES {
+            self.route_changes.remove(0);
         }
+        (!damped).then_some(change)
     }
 
     /// Record a timeout - updates hop-level stats only
@@ -975,12 +1723,76 @@ impl Hop {
     /// Hop-level loss percentage (`loss_pct()`) uses completed probes only.
     pub fn record_timeout(&mut self) {
         self.timeouts += 1;
+        if let Some(ref mut window) = self.window {
+            window.record_timeout();
+        }
 
         // Track in hop-level sparkline (false = timeout/loss)
         self.recent_results.push_back(false);
         if self.recent_results.len() > RECENT_WINDOW_SIZE {
             self.recent_results.pop_front();
         }
+        self.push_sample(None, None);
+        self.push_outcome(ProbeOutcome::Timeout);
+    }
+
+    /// Note the ICMP type of a reply from `ip`, for the response code strip
+    pub fn record_response_type(&mut self, ip: IpAddr, kind: IcmpResponseType) {
+        let ip = self.canonical(ip);
+        self.push_outcome(ProbeOutcome::Reply(ip, kind));
+    }
+
+    /// Keep the latest incoming interface `ip` reported (RFC 5837)
+    pub fn record_interface(&mut self, ip: IpAddr, interface: IncomingInterface) {
+        let ip = self.canonical(ip);
+        if let Some(stats) = self.responders.get_mut(&ip) {
+            stats.interface = Some(interface);
+        }
+    }
+
+    fn push_outcome(&mut self, outcome: ProbeOutcome) {
+        self.outcomes.push_back(outcome);
+        if self.outcomes.len() > OUTCOME_HISTORY {
+            self.outcomes.pop_front();
+        }
+    }
+
+    /// Response codes of `ip`'s replies, oldest first, with the hop's
+    /// timeouts in between as `×` (they can't be pinned to a responder)
+    pub fn outcome_strip(&self, ip: IpAddr) -> String {
+        self.outcomes
+            .iter()
+            .filter_map(|o| match *o {
+                ProbeOutcome::Reply(from, kind) if from == ip => Some(kind.symbol(ip.is_ipv6())),
+                ProbeOutcome::Reply(..) => None,
+                ProbeOutcome::Timeout => Some('×'),
+            })
+            .collect()
+    }
+
+    /// Record a reply to a probe that was already counted as timed out
+    pub fn record_late(&mut self, rtt: Duration) {
+        self.late += 1;
+        self.late_samples.push_back(rtt);
+        if self.late_samples.len() > Self::LATE_SAMPLES {
+            self.late_samples.pop_front();
+        }
+    }
+
+    /// Share of timeouts whose reply turned up late, as a percentage
+    pub fn late_pct(&self) -> f64 {
+        if self.timeouts == 0 {
+            0.0
+        } else {
+            (self.late as f64 / self.timeouts as f64 * 100.0).min(100.0)
+        }
+    }
+
+    /// Mean and max RTT of the kept late replies
+    pub fn late_rtt(&self) -> Option<(Duration, Duration)> {
+        let max = *self.late_samples.iter().max()?;
+        let avg = self.late_samples.iter().sum::<Duration>() / self.late_samples.len() as u32;
+        Some((avg, max))
     }
 
     /// Update primary responder based on response count
@@ -995,11 +1807,94 @@ impl Hop {
             .map(|(ip, _)| *ip);
     }
 
+    /// Address a response from `ip` is counted under (itself unless merged)
+    fn canonical(&self, ip: IpAddr) -> IpAddr {
+        self.alias_of.get(&ip).copied().unwrap_or(ip)
+    }
+
+    /// Merge responder `from` into `into`, so both addresses count as one
+    /// responder from now on
+    pub fn merge_responder(&mut self, into: IpAddr, from: IpAddr) {
+        if into == from {
+            return;
+        }
+        let Some(stats) = self.responders.remove(&from) else {
+            return;
+        };
+        self.responders
+            .entry(into)
+            .or_insert_with(|| ResponderStats::new(into))
+            .absorb(stats);
+
+        for target in self.alias_of.values_mut() {
+            if *target == from {
+                *target = into;
+            }
+        }
+        self.alias_of.insert(from, into);
+        for flow in self.flow_paths.values_mut() {
+            flow.merge_responder(into, from);
+        }
+        self.update_ecmp();
+
+        // Flap tracking must not see the merge as a path change
+        let rename = |ip: IpAddr| if ip == from { into } else { ip };
+        self.flap_tracking_primary = self.flap_tracking_primary.map(rename);
+        self.pending_flap = self
+            .pending_flap
+            .map(|(a, b)| (rename(a), rename(b)))
+            .filter(|(a, b)| a != b);
+        self.reply_streak = self.reply_streak.map(|(ip, n)| (rename(ip), n));
+        self.update_primary();
+    }
+
+    /// Clear counters, responders and detection results, keeping route
+    /// change tracking
+    fn clear_stats(&mut self, window: Option<Duration>) {
+        self.sent = 0;
+        self.received = 0;
+        self.timeouts = 0;
+        self.late = 0;
+        self.late_samples.clear();
+        self.responders.clear();
+        self.primary = None;
+        self.recent_results.clear();
+        self.history.clear();
+        self.flow_paths.clear();
+        self.ecmp = None;
+        self.nat_info = None;
+        self.rate_limit = None;
+        self.asymmetry = None;
+        self.ttl_manip = None;
+        self.dscp = None;
+        self.ecn = None;
+        self.pace.reset();
+        self.probed_minutes = 0;
+        self.last_probed_minute = None;
+        self.outcomes.clear();
+        self.alias_of.clear();
+        if let Some(length) = window {
+            self.enable_window(length);
+        }
+    }
+
     /// Get primary responder stats
     pub fn primary_stats(&self) -> Option<&ResponderStats> {
         self.primary.and_then(|ip| self.responders.get(&ip))
     }
 
+    /// Rounds probed at this hop since `stats` last answered (0 while
+    /// its reply to the latest round is the newest)
+    pub fn rounds_since_seen(&self, stats: &ResponderStats, flows: u8) -> u64 {
+        self.sent.saturating_sub(stats.last_seen_probe) / flows.max(1) as u64
+    }
+
+    /// Whether `stats` has been silent for more than `stale_after` rounds
+    /// (0 = never stale), e.g. an ECMP alternate no longer in use
+    pub fn is_stale(&self, stats: &ResponderStats, stale_after: u64, flows: u8) -> bool {
+        stale_after > 0 && self.rounds_since_seen(stats, flows) > stale_after
+    }
+
     /// Loss percentage for this hop (based on completed probes only)
     ///
     /// Uses `timeouts / (received + timeouts)` to avoid counting in-flight
@@ -1018,13 +1913,22 @@ impl Hop {
         self.flow_paths.entry(flow_id).or_default().record_sent();
     }
 
+    /// Record a UDP/TCP probe sent on a flow from `src_port`
+    pub fn record_flow_sent_from(&mut self, flow_id: u8, src_port: u16) {
+        let flow = self.flow_paths.entry(flow_id).or_default();
+        flow.record_sent();
+        flow.src_port = Some(src_port);
+    }
+
     /// Record a response from a responder on a specific flow
     pub fn record_flow_response(&mut self, flow_id: u8, responder: IpAddr, rtt: Duration) {
+        let responder = self.canonical(responder);
         // Update flow-specific stats
         self.flow_paths
             .entry(flow_id)
             .or_default()
             .record_response(responder);
+        self.update_ecmp();
 
         // Also update aggregate stats (existing behavior)
         // Note: record_response already handles all hop-level tracking
@@ -1032,9 +1936,52 @@ impl Hop {
         let _ = rtt; // RTT is recorded in aggregate stats via record_response
     }
 
-    /// Record a timeout on a specific flow
-    pub fn record_flow_timeout(&mut self, flow_id: u8) {
-        self.flow_paths.entry(flow_id).or_default().record_timeout();
+    fn update_ecmp(&mut self) {
+        self.ecmp = EcmpEstimate::from_primaries(
+            self.flow_paths
+                .values()
+                .filter_map(|fp| fp.primary_responder),
+        );
+    }
+
+    /// Record a timeout on a specific flow. If only one responder has
+    /// ever answered on the flow, the timeout is attributed to it.
+    pub fn record_flow_timeout(&mut self, flow_id: u8) {
+        let flow = self.flow_paths.entry(flow_id).or_default();
+        flow.record_timeout();
+        let pinned = match flow.responder_counts.len() {
+            1 => flow.primary_responder,
+            _ => None,
+        };
+        if let Some(stats) = pinned.and_then(|ip| self.responders.get_mut(&ip)) {
+            stats.sent += 1;
+            stats.timeouts += 1;
+        }
+    }
+
+    /// Probes and loss for one responder: its replies, the timeouts on
+    /// flows pinned to it, and a share of the remaining timeouts in
+    /// proportion to its share of the hop's replies
+    pub fn responder_loss(&self, ip: IpAddr) -> Option<ResponderLoss> {
+        let stats = self.responders.get(&ip)?;
+        let attributed: u64 = self.responders.values().map(|s| s.timeouts).sum();
+        let unattributed = self.timeouts.saturating_sub(attributed);
+        let share = if self.received == 0 {
+            0.0
+        } else {
+            stats.received as f64 / self.received as f64
+        };
+        let timeouts = stats.timeouts as f64 + share * unattributed as f64;
+        let sent = stats.received as f64 + timeouts;
+        Some(ResponderLoss {
+            sent,
+            loss_pct: if sent == 0.0 {
+                0.0
+            } else {
+                timeouts / sent * 100.0
+            },
+            estimated: unattributed > 0 && share > 0.0,
+        })
     }
 
     /// Check if ECMP is detected (multiple unique primary responders across flows)
@@ -1079,17 +2026,24 @@ impl Hop {
 
     /// Record a NAT detection result for this hop
     ///
-    /// Compares the original source port (from the sent probe) with the
-    /// returned source port (from the ICMP error payload). A mismatch
-    /// indicates NAT is rewriting ports.
-    pub fn record_nat_check(&mut self, original: Option<u16>, returned: Option<u16>) {
-        match (original, returned) {
+    /// Compares the (source port, checksum) of the sent probe with those
+    /// quoted in the ICMP error payload. A port mismatch indicates NAT is
+    /// rewriting ports; a checksum mismatch alone, that it rewrote the
+    /// source address. The checksum is skipped when either side is unknown.
+    pub fn record_nat_check(
+        &mut self,
+        original: (Option<u16>, Option<u16>),
+        returned: (Option<u16>, Option<u16>),
+    ) {
+        match (original.0, returned.0) {
             (Some(orig), Some(ret)) => {
                 let nat_info = self.nat_info.get_or_insert_with(NatInfo::default);
-                if orig == ret {
-                    nat_info.record_match();
-                } else {
+                if orig != ret {
                     nat_info.record_rewrite(orig, ret);
+                } else if matches!((original.1, returned.1), (Some(a), Some(b)) if a != b) {
+                    nat_info.record_checksum_rewrite();
+                } else {
+                    nat_info.record_match();
                 }
             }
             _ => {
@@ -1112,6 +2066,19 @@ impl Hop {
         asymmetry.record_response(self.ttl, response_ttl, ipv6);
     }
 
+    /// Record the IP TTL of a reply from `ip`, returning (old, new) when
+    /// that responder's settled return TTL changes
+    pub fn record_return_ttl(&mut self, ip: IpAddr, ttl: u8) -> Option<(u8, u8)> {
+        let stats = self.responders.get_mut(&ip)?;
+        match stats.return_ttl {
+            Some(ref mut return_ttl) => return_ttl.record(ttl),
+            None => {
+                stats.return_ttl = Some(ReturnTtl::new(ttl));
+                None
+            }
+        }
+    }
+
     /// Check if asymmetric routing is suspected at this hop
     pub fn has_asymmetry(&self) -> bool {
         self.asymmetry.as_ref().is_some_and(|a| a.suspected)
@@ -1130,6 +2097,21 @@ impl Hop {
     pub fn has_ttl_manip(&self) -> bool {
         self.ttl_manip.as_ref().is_some_and(|t| t.suspected)
     }
+
+    /// Record the DSCP quoted in an ICMP error for a probe sent with `sent`
+    pub fn record_quoted_dscp(&mut self, sent: u8, quoted: u8) {
+        self.dscp
+            .get_or_insert_with(DscpInfo::default)
+            .record(sent, quoted);
+    }
+
+    /// Record the ECN codepoint quoted in an ICMP error for a probe sent
+    /// with `sent`
+    pub fn record_quoted_ecn(&mut self, sent: EcnCodepoint, quoted: EcnCodepoint) {
+        self.ecn
+            .get_or_insert_with(|| EcnInfo::new(sent))
+            .record(sent, quoted);
+    }
 }
 
 /// Target being traced
@@ -1138,6 +2120,13 @@ pub struct Target {
     pub original: String,
     pub resolved: IpAddr,
     pub hostname: Option<String>,
+    /// Every address the name resolved to, in the order offered for
+    /// `--address-index` (empty when the target was an IP literal)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<IpAddr>,
+    /// Why `resolved` was picked over the other addresses (`--best-family`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<String>,
 }
 
 impl Target {
@@ -1146,10 +2135,76 @@ impl Target {
             original,
             resolved,
             hostname: None,
+            addresses: Vec::new(),
+            selection: None,
         }
     }
 }
 
+/// Free-text annotation attached to a session or a single hop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub at: DateTime<Utc>,
+    /// Hop the note refers to (None = whole session)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u8>,
+    pub text: String,
+}
+
+/// Kernel socket buffers and overruns behind a session's probes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SocketStats {
+    /// Receive buffer the kernel granted, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_buffer: Option<usize>,
+    /// Send buffer of this target's probe socket, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_buffer: Option<usize>,
+    /// Responses the kernel dropped because the receive buffer was full
+    /// (None where the OS doesn't count them)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_drops: Option<u64>,
+    /// Targets sharing the receive socket, this one included
+    #[serde(default)]
+    pub receiver_targets: usize,
+    /// Responses the shared receiver handed to this target's probes
+    #[serde(default)]
+    pub matched: u64,
+    /// Responses about this target that no probe was waiting for
+    /// (duplicates, or later than the late-reply window)
+    #[serde(default)]
+    pub unmatched: u64,
+    /// Packets on the receive socket that answered none of our probes, for
+    /// any target (other tools' traffic, unrelated ICMP)
+    #[serde(default)]
+    pub stray: u64,
+}
+
+/// How long after the last held-back probe the limiter still counts as
+/// throttling
+const THROTTLE_DISPLAY: Duration = Duration::from_secs(2);
+
+/// State of the --global-rate token bucket as seen by this target's engine
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    /// Probes per second shared by all targets
+    pub rate: u32,
+    /// Probes that may be sent back to back
+    pub burst: u32,
+    /// This target's probes the limiter held back
+    pub delayed: u64,
+    /// How long the most recent held-back probe waited
+    pub last_wait: Duration,
+    last_at: std::time::Instant,
+}
+
+impl Throttle {
+    /// Whether the limiter held back a probe in the last couple of seconds
+    pub fn active(&self) -> bool {
+        self.last_at.elapsed() < THROTTLE_DISPLAY
+    }
+}
+
 /// A complete tracing session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -1165,12 +2220,119 @@ pub struct Session {
     /// PMTUD state (only present when --pmtud is enabled)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pmtud: Option<PmtudState>,
+    /// DF size sweep results (only present with --df-sweep)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub df_sweep: Option<DfSweep>,
     /// Source IP used for probes (for display in TUI)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub source_ip: Option<IpAddr>,
+    /// GeoIP location of `source_ip`, when it has one (public addresses)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_geo: Option<GeoInfo>,
     /// Default gateway IP (for display in TUI)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gateway: Option<IpAddr>,
+    /// User annotations, in the order they were added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
+    /// Chronological timeline of path changes, outages and alerts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<Event>,
+    /// SLO compliance at the destination (only present with an SLO configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slo: Option<SloStatus>,
+    /// Idle vs loaded RTT per hop (only present with --load)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<LoadTest>,
+    /// Socket buffer sizes and kernel drop counts (diagnostics panel)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_stats: Option<SocketStats>,
+    /// What `--redact` removed before this session was exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted: Option<String>,
+    /// Per-hop statistics cleared by `--reset-on-path-change`, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_epochs: Vec<PathEpoch>,
+    /// Path MTU evidence from SYN-ACK MSS and Frag Needed replies
+    #[serde(default, skip_serializing_if = "MtuHints::is_empty")]
+    pub mtu_hints: MtuHints,
+    /// ICMP Redirects received for this target, one per router/gateway pair
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<RedirectSeen>,
+    /// Forwarding loop seen in the latest rounds (single-flow mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_loop: Option<RoutingLoop>,
+    /// Hashes of the paths taken, one per path, oldest first
+    #[serde(default, skip_serializing_if = "SignatureHistory::is_empty")]
+    pub path_signatures: SignatureHistory,
+    /// Global rate limiter state (only set once it has held back a probe)
+    #[serde(skip)]
+    pub throttle: Option<Throttle>,
+    /// Consecutive destination timeouts (outage tracking)
+    #[serde(skip)]
+    dest_timeouts: u32,
+    /// Whether an outage is currently in progress
+    #[serde(skip)]
+    in_outage: bool,
+    /// Whether destination loss is above the --alert-loss threshold
+    #[serde(skip)]
+    loss_alert: bool,
+    /// Whether the destination's RTT is in a logged spike
+    #[serde(skip)]
+    in_spike: bool,
+    /// Events recorded since start, including ones dropped from `events`
+    #[serde(skip)]
+    events_recorded: u64,
+    /// Path changes waiting on the new responder's IX lookup
+    #[serde(skip)]
+    ix_watch: Vec<IxWatch>,
+    /// Path changes waiting on the new responder's ASN lookup
+    #[serde(skip)]
+    prefix_moves: Vec<PrefixMove>,
+    /// Responders of the latest rounds, for loop detection
+    #[serde(skip)]
+    round_paths: RoundPaths,
+    /// Outcome at each hop of the latest rounds; exported as the loss
+    /// correlation between hops
+    #[serde(
+        rename = "loss_correlation",
+        serialize_with = "serialize_correlations",
+        skip_deserializing,
+        skip_serializing_if = "RoundLoss::is_uncorrelated"
+    )]
+    round_loss: RoundLoss,
+    /// Whether the destination has answered with Time Exceeded
+    #[serde(skip)]
+    dest_expiry_seen: bool,
+}
+
+/// An ICMP Redirect: `router` asked us to send traffic for the target via
+/// `gateway`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectSeen {
+    pub router: IpAddr,
+    pub gateway: IpAddr,
+    /// Redirects received from this router for this gateway
+    pub count: u64,
+    pub last_at: DateTime<Utc>,
+}
+
+/// A path change at `ttl` from a responder announced in `from` (prefix and
+/// origin AS, from its ASN lookup) to `to`, whose ASN is not known yet
+#[derive(Debug, Clone)]
+struct PrefixMove {
+    ttl: u8,
+    from: (String, u32),
+    to: IpAddr,
+}
+
+/// A path change at `ttl` from a responder at IX `from_ix` (None: no IX)
+/// to `to`, whose IX is not known yet
+#[derive(Debug, Clone)]
+struct IxWatch {
+    ttl: u8,
+    from_ix: Option<String>,
+    to: IpAddr,
 }
 
 impl Session {
@@ -1187,6 +2349,17 @@ impl Session {
         } else {
             None
         };
+        let df_sweep = config
+            .df_sweep
+            .then(|| DfSweep::new(target.resolved.is_ipv6()));
+
+        let slo = config.slo.map(SloStatus::new);
+        let load = config.load.map(LoadTest::new);
+        if let Some(length) = config.stats_window {
+            for hop in &mut hops {
+                hop.enable_window(length);
+            }
+        }
 
         Self {
             target,
@@ -1198,9 +2371,646 @@ impl Session {
             total_sent: 0,
             paused: false,
             pmtud,
+            df_sweep,
             source_ip: None,
+            source_geo: None,
             gateway: None,
+            notes: Vec::new(),
+            events: Vec::new(),
+            slo,
+            load,
+            socket_stats: None,
+            redacted: None,
+            path_epochs: Vec::new(),
+            mtu_hints: MtuHints::default(),
+            redirects: Vec::new(),
+            routing_loop: None,
+            path_signatures: SignatureHistory::default(),
+            throttle: None,
+            dest_timeouts: 0,
+            in_outage: false,
+            loss_alert: false,
+            in_spike: false,
+            events_recorded: 0,
+            ix_watch: Vec::new(),
+            prefix_moves: Vec::new(),
+            round_paths: RoundPaths::default(),
+            round_loss: RoundLoss::default(),
+            dest_expiry_seen: false,
+        }
+    }
+
+    /// Append an event to the timeline, dropping the oldest past MAX_EVENTS
+    pub fn record_event(&mut self, kind: EventKind) {
+        self.events.push(Event::new(kind));
+        self.events_recorded += 1;
+        if self.events.len() > MAX_EVENTS {
+            self.events.remove(0);
+        }
+    }
+
+    /// Note a probe the global rate limiter held back for `wait`
+    pub fn record_throttle(&mut self, rate: u32, burst: u32, wait: Duration) {
+        let throttle = self.throttle.get_or_insert(Throttle {
+            rate,
+            burst,
+            delayed: 0,
+            last_wait: wait,
+            last_at: std::time::Instant::now(),
+        });
+        throttle.delayed += 1;
+        throttle.last_wait = wait;
+        throttle.last_at = std::time::Instant::now();
+    }
+
+    /// Note an ICMP Redirect for this target (per `--redirects`). Each new
+    /// router/gateway pair is recorded as an event; repeats are counted.
+    pub fn record_redirect(&mut self, router: IpAddr, gateway: IpAddr) {
+        if self.config.redirects == RedirectPolicy::Ignore {
+            return;
+        }
+        let now = Utc::now();
+        match self
+            .redirects
+            .iter_mut()
+            .find(|r| r.router == router && r.gateway == gateway)
+        {
+            Some(seen) => {
+                seen.count += 1;
+                seen.last_at = now;
+            }
+            None => {
+                self.redirects.push(RedirectSeen {
+                    router,
+                    gateway,
+                    count: 1,
+                    last_at: now,
+                });
+                self.record_event(EventKind::Redirect { router, gateway });
+            }
+        }
+    }
+
+    /// Track a reply to round `seq` for loop detection. A loop, or a new
+    /// span for one, is logged; it is cleared once a round old enough to be
+    /// complete crosses its span without repeating a router. Time Exceeded
+    /// from the destination itself is logged once.
+    pub fn record_round_reply(
+        &mut self,
+        seq: u8,
+        ttl: u8,
+        ip: IpAddr,
+        response_type: IcmpResponseType,
+    ) {
+        if matches!(response_type, IcmpResponseType::TimeExceeded(0))
+            && ip == self.target.resolved
+            && !self.dest_expiry_seen
+        {
+            self.dest_expiry_seen = true;
+            self.record_event(EventKind::DestinationExpiry { ttl, ip });
+        }
+
+        // Probes past the destination are answered by the destination
+        // itself at every TTL; that's the end of the path, not a loop
+        if ip == self.target.resolved {
+            return;
+        }
+        let (found, evicted) = self.round_paths.record(seq, ttl, ip);
+        if let Some(found) = found {
+            let same = self.routing_loop.as_ref().is_some_and(|current| {
+                (current.first_ttl, current.last_ttl, &current.routers)
+                    == (found.first_ttl, found.last_ttl, &found.routers)
+            });
+            if !same {
+                self.record_event(EventKind::RoutingLoop {
+                    first_ttl: found.first_ttl,
+                    last_ttl: found.last_ttl,
+                    routers: found.routers.clone(),
+                });
+                self.routing_loop = Some(found);
+            }
+        } else if let (Some(current), Some(old)) = (&self.routing_loop, evicted) {
+            let answered = old.keys().filter(|&&t| current.contains(t)).count();
+            if answered >= 2 && find_loop(&old).is_none() {
+                self.routing_loop = None;
+            }
+        }
+    }
+
+    /// Most recent redirect, when `--redirects warn` asks for it to be
+    /// flagged: our packets may be leaving through a different first hop
+    /// than the one the trace shows
+    pub fn redirect_warning(&self) -> Option<&RedirectSeen> {
+        if self.config.redirects != RedirectPolicy::Warn {
+            return None;
+        }
+        self.redirects.iter().max_by_key(|r| r.last_at)
+    }
+
+    /// Update the receive socket's drop count. The first overrun raises an
+    /// alert: responses lost in the kernel look exactly like path loss.
+    pub fn record_kernel_drops(&mut self, total: u64) {
+        let stats = self.socket_stats.get_or_insert_with(SocketStats::default);
+        let before = stats.recv_drops.unwrap_or(0);
+        stats.recv_drops = Some(total);
+        if before == 0 && total > 0 {
+            self.record_event(EventKind::Alert {
+                message: format!(
+                    "kernel dropped {} responses (receive buffer full); loss may be overstated, raise --recv-buffer",
+                    total
+                ),
+            });
+        }
+    }
+
+    /// Total events recorded so far (a cursor for `events_since`)
+    pub fn events_recorded(&self) -> u64 {
+        self.events_recorded
+    }
+
+    /// Events recorded after `cursor`, for sinks that forward new events.
+    /// Events already dropped from the capped timeline are skipped.
+    pub fn events_since(&self, cursor: u64) -> &[Event] {
+        let new = self.events_recorded.saturating_sub(cursor) as usize;
+        &self.events[self.events.len().saturating_sub(new)..]
+    }
+
+    /// Raise or clear the loss alert from the destination's recent loss
+    fn check_loss_alert(&mut self, ttl: u8) {
+        let Some(threshold) = self.config.alert_loss else {
+            return;
+        };
+        let Some(window) = self.hop(ttl).map(|h| &h.recent_results) else {
+            return;
+        };
+        let samples = window.len();
+        if samples < LOSS_ALERT_MIN_SAMPLES {
+            return;
+        }
+        let lost = window.iter().filter(|ok| !**ok).count();
+        let loss = lost as f64 * 100.0 / samples as f64;
+
+        if !self.loss_alert && loss >= threshold {
+            self.loss_alert = true;
+            self.record_event(EventKind::Alert {
+                message: format!(
+                    "Destination loss {:.1}% over last {} probes (threshold {}%)",
+                    loss, samples, threshold
+                ),
+            });
+        } else if self.loss_alert && loss < threshold {
+            self.loss_alert = false;
+            self.record_event(EventKind::Alert {
+                message: format!(
+                    "Destination loss back to {:.1}% (threshold {}%)",
+                    loss, threshold
+                ),
+            });
+        }
+    }
+
+    /// Replace the SLO for this session, restarting compliance tracking
+    pub fn set_slo(&mut self, slo: Option<Slo>) {
+        self.config.slo = slo;
+        self.slo = slo.map(SloStatus::new);
+    }
+
+    /// Mark the destination as reached at `ttl` with a reply after `rtt`,
+    /// logging the first arrival
+    pub fn record_dest_reached(&mut self, ttl: u8, rtt: Duration) {
+        if !self.complete {
+            self.record_event(EventKind::DestinationReached { ttl });
+        }
+        self.complete = true;
+        if self.dest_ttl.is_none_or(|d| ttl < d) {
+            self.dest_ttl = Some(ttl);
+        }
+        if self.in_outage {
+            self.record_event(EventKind::OutageEnd {
+                ttl,
+                lost: self.dest_timeouts,
+            });
+            self.in_outage = false;
+        }
+        self.dest_timeouts = 0;
+        // Probes past the destination also get echo replies; count each round once
+        if self.dest_ttl == Some(ttl) {
+            if let Some(ref mut slo) = self.slo {
+                slo.record(Some(rtt));
+            }
+            self.check_latency_spike(ttl);
+        }
+        self.check_loss_alert(ttl);
+    }
+
+    /// Log a spike in the destination's RTT once, with its probable cause
+    fn check_latency_spike(&mut self, ttl: u8) {
+        let Some(dest) = self.hop(ttl) else {
+            return;
+        };
+        let Some(shift) = RttShift::from_history(&dest.history) else {
+            return;
+        };
+        if !shift.is_spike() {
+            self.in_spike = false;
+            return;
+        }
+        if self.in_spike {
+            return;
+        }
+        let loss_burst = spike::loss_burst(&dest.recent_results);
+        self.in_spike = true;
+
+        let hops: Vec<_> = self
+            .hops
+            .iter()
+            .filter(|h| h.ttl < ttl)
+            .filter_map(|h| RttShift::from_history(&h.history).map(|s| (h.ttl, s)))
+            .collect();
+        let since = Utc::now() - chrono::Duration::seconds(spike::REROUTE_WINDOW_SECS);
+        let reroute = self
+            .events
+            .iter()
+            .rev()
+            .take_while(|e| e.at >= since)
+            .find_map(|e| match e.kind {
+                EventKind::PathChange { ttl, .. }
+                | EventKind::PrefixChange { ttl, .. }
+                | EventKind::IxChange { ttl, .. } => Some(ttl),
+                EventKind::RoutingLoop { first_ttl, .. } => Some(first_ttl),
+                _ => None,
+            });
+        let (cause, cause_ttl) = spike::classify(ttl, shift.rise(), &hops, reroute);
+        self.record_event(EventKind::LatencySpike {
+            ttl,
+            baseline_ms: shift.baseline,
+            rtt_ms: shift.recent,
+            cause,
+            cause_ttl,
+            loss_burst,
+        });
+    }
+
+    /// With `--gap-limit N`, where probing stops: the end of the first run
+    /// of N silent hops past the last hop that ever answered. None once the
+    /// destination is known, or while no such run exists.
+    pub fn gap_stop(&self) -> Option<u8> {
+        let limit = self.config.gap_limit?;
+        if self.dest_ttl.is_some() {
+            return None;
+        }
+        let last_answered = self
+            .hops
+            .iter()
+            .filter(|h| h.received > 0)
+            .map(|h| h.ttl)
+            .max()
+            .unwrap_or(self.config.first_ttl - 1);
+        let stop = last_answered.checked_add(limit)?;
+        if stop >= self.config.max_ttl {
+            return None;
+        }
+        (last_answered + 1..=stop)
+            .all(|ttl| {
+                self.hop(ttl)
+                    .is_some_and(|h| h.received == 0 && h.sent >= GAP_MIN_SENT)
+            })
+            .then_some(stop)
+    }
+
+    /// Step each probed hop's --adaptive pace on its latest results. The
+    /// destination keeps the full rate: its loss is the one that matters.
+    pub fn update_pacing(&mut self) {
+        let dest_ttl = self.dest_ttl;
+        for hop in &mut self.hops {
+            if Some(hop.ttl) == dest_ttl || hop.sent == 0 {
+                continue;
+            }
+            let rate_limited = hop.rate_limit.as_ref().is_some_and(|r| r.suspected);
+            hop.pace.update(
+                hop.received + hop.timeouts,
+                &hop.recent_results,
+                rate_limited,
+            );
+        }
+    }
+
+    /// Rounds between probes at each TTL under --adaptive, indexed by TTL - 1
+    pub fn pace_periods(&self) -> Vec<u64> {
+        self.hops
+            .iter()
+            .map(|hop| {
+                if Some(hop.ttl) == self.dest_ttl {
+                    1
+                } else {
+                    hop.pace.period
+                }
+            })
+            .collect()
+    }
+
+    /// Highest TTL to probe in round `round`: the destination once found,
+    /// else the gap stop, with every GAP_RECHECK_ROUNDS-th round going all
+    /// the way to max_ttl in case the path came back
+    pub fn probe_ceiling(&self, round: u64) -> u8 {
+        if let Some(dest) = self.dest_ttl {
+            return dest;
+        }
+        match self.gap_stop() {
+            Some(stop) if round % GAP_RECHECK_ROUNDS != 0 => stop,
+            _ => self.config.max_ttl,
+        }
+    }
+
+    /// Whether the destination has stopped answering (outage in progress)
+    pub fn in_outage(&self) -> bool {
+        self.in_outage
+    }
+
+    /// Record a timed-out probe; declares an outage after OUTAGE_THRESHOLD
+    /// consecutive misses at the destination hop
+    pub fn record_dest_timeout(&mut self, ttl: u8) {
+        if self.dest_ttl != Some(ttl) {
+            return;
+        }
+        self.dest_timeouts += 1;
+        if !self.in_outage && self.dest_timeouts >= OUTAGE_THRESHOLD {
+            self.in_outage = true;
+            self.record_event(EventKind::OutageStart { ttl });
+        }
+        if let Some(ref mut slo) = self.slo {
+            slo.record(None);
+        }
+        self.check_loss_alert(ttl);
+    }
+
+    /// Attach --label names to new responders at hop `ttl`
+    pub fn label_responders(&mut self, ttl: u8) {
+        let labels = &self.config.labels;
+        if labels.is_empty() {
+            return;
+        }
+        let Some(hop) = ttl
+            .checked_sub(1)
+            .and_then(|i| self.hops.get_mut(i as usize))
+        else {
+            return;
+        };
+        for (ip, stats) in hop.responders.iter_mut() {
+            if stats.label.is_none() {
+                stats.label = labels.get(*ip).map(str::to_string);
+            }
+        }
+    }
+
+    /// Merge responders at hop `ttl` that are one device: addresses under
+    /// the same --alias rule and, with --merge-by-hostname, addresses with
+    /// the same reverse DNS name. The busiest address keeps the stats.
+    pub fn apply_aliases(&mut self, ttl: u8) {
+        let aliases = &self.config.aliases;
+        let by_hostname = self.config.merge_hostnames;
+        if aliases.is_empty() && !by_hostname {
+            return;
+        }
+        let Some(hop) = ttl
+            .checked_sub(1)
+            .and_then(|i| self.hops.get_mut(i as usize))
+        else {
+            return;
+        };
+
+        let mut groups: HashMap<String, Vec<(IpAddr, u64)>> = HashMap::new();
+        for (ip, stats) in hop.responders.iter_mut() {
+            if stats.alias.is_none() {
+                stats.alias = find_alias(aliases, *ip).map(|a| a.name.clone());
+            }
+            let key = match (&stats.alias, &stats.hostname) {
+                (Some(name), _) => format!("alias:{}", name),
+                (None, Some(host)) if by_hostname => format!("host:{}", host.to_lowercase()),
+                _ => continue,
+            };
+            groups.entry(key).or_default().push((*ip, stats.received));
+        }
+
+        for mut members in groups.into_values().filter(|m| m.len() > 1) {
+            members.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            let into = members[0].0;
+            for &(from, _) in &members[1..] {
+                hop.merge_responder(into, from);
+            }
+        }
+    }
+
+    /// Record the BGP prefix now covering `ip`, logging a PrefixChange event
+    /// at every hop where it differs from the last check
+    pub fn update_prefix(&mut self, ip: IpAddr, prefix: &str, origins: &[u32]) {
+        let mut changes = Vec::new();
+        for hop in &mut self.hops {
+            let Some(stats) = hop.responders.get_mut(&ip) else {
+                continue;
+            };
+            let Some(ref mut current) = stats.prefix_origin else {
+                stats.prefix_origin = Some(PrefixOrigin::new(prefix.to_string(), origins.to_vec()));
+                continue;
+            };
+            let Some(change) =
+                PrefixChangeKind::classify(&current.prefix, &current.origins, prefix, origins)
+            else {
+                continue;
+            };
+            let from = current.label();
+            current.previous = Some(from.clone());
+            current.prefix = prefix.to_string();
+            current.origins = origins.to_vec();
+            current.more_specific |= change == PrefixChangeKind::MoreSpecific;
+            changes.push(EventKind::PrefixChange {
+                ttl: hop.ttl,
+                ip,
+                change,
+                from,
+                to: current.label(),
+            });
+        }
+        for change in changes {
+            self.record_event(change);
+        }
+    }
+
+    /// Start watching a path change at `ttl` for traffic moving onto or off
+    /// an IX. The event is logged by `resolve_ix` once `to` has been looked
+    /// up; another change at the same hop first keeps the original side.
+    pub fn watch_ix_change(&mut self, ttl: u8, from: IpAddr, to: IpAddr) {
+        if !self.config.ix_enabled {
+            return;
+        }
+        let Some(hop) = self.hop(ttl) else {
+            return;
+        };
+        let from_ix = hop
+            .responders
+            .get(&from)
+            .and_then(|s| s.ix.as_ref())
+            .map(|ix| ix.name.clone());
+        let to_known = hop.responders.get(&to).is_some_and(|s| s.ix.is_some());
+        match self.ix_watch.iter_mut().find(|w| w.ttl == ttl) {
+            Some(watch) => watch.to = to,
+            None => self.ix_watch.push(IxWatch { ttl, from_ix, to }),
+        }
+        if to_known {
+            self.resolve_ix(to);
+        }
+    }
+
+    /// `ip`'s IX lookup finished: log an IxChange for each path change to it
+    /// that moved the hop onto, off or between IXes. An IX still crossed at
+    /// another hop (the path just got longer or shorter) doesn't count.
+    pub fn resolve_ix(&mut self, ip: IpAddr) {
+        if !self.ix_watch.iter().any(|w| w.to == ip) {
+            return;
+        }
+        let last = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        let (resolved, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.ix_watch)
+            .into_iter()
+            .partition(|w| w.to == ip);
+        self.ix_watch = waiting;
+        let mut changes = Vec::new();
+        for watch in resolved {
+            let to_ix = self
+                .hop(watch.ttl)
+                .and_then(|h| h.responders.get(&ip))
+                .and_then(|s| s.ix.as_ref())
+                .map(|ix| ix.name.clone());
+            if watch.from_ix == to_ix {
+                continue;
+            }
+            let elsewhere = |name: &Option<String>| {
+                name.as_ref().is_some_and(|name| {
+                    self.hops
+                        .iter()
+                        .filter(|h| h.ttl != watch.ttl && h.ttl <= last)
+                        .filter_map(|h| h.primary_stats())
+                        .any(|s| s.ix.as_ref().is_some_and(|ix| &ix.name == name))
+                })
+            };
+            let from = watch.from_ix.clone().filter(|_| !elsewhere(&watch.from_ix));
+            let to = to_ix.clone().filter(|_| !elsewhere(&to_ix));
+            if from.is_some() || to.is_some() {
+                changes.push(EventKind::IxChange {
+                    ttl: watch.ttl,
+                    ip,
+                    from,
+                    to,
+                });
+            }
+        }
+        for change in changes {
+            self.record_event(change);
+        }
+    }
+
+    /// Start watching a path change at `ttl` for a move to another BGP
+    /// prefix or origin AS. Logged by `resolve_prefix` once `to`'s ASN
+    /// lookup has finished; responders without a known prefix are skipped.
+    pub fn watch_prefix_change(&mut self, ttl: u8, from: IpAddr, to: IpAddr) {
+        if !self.config.asn_enabled {
+            return;
+        }
+        let Some(hop) = self.hop(ttl) else {
+            return;
+        };
+        let asn_of = |ip: IpAddr| hop.responders.get(&ip).and_then(|s| s.asn.as_ref());
+        let from_prefix = asn_of(from).and_then(|a| Some((a.prefix.clone()?, a.number)));
+        let to_known = asn_of(to).is_some();
+        match self.prefix_moves.iter_mut().find(|m| m.ttl == ttl) {
+            Some(watch) => watch.to = to,
+            None => match from_prefix {
+                Some(from) => self.prefix_moves.push(PrefixMove { ttl, from, to }),
+                None => return,
+            },
+        }
+        if to_known {
+            self.resolve_prefix(to);
+        }
+    }
+
+    /// `ip`'s ASN lookup finished: log a PrefixChange for each path change
+    /// to it that left the covering prefix or origin AS of the old responder.
+    /// A change within the same prefix is an ordinary path change.
+    pub fn resolve_prefix(&mut self, ip: IpAddr) {
+        if !self.prefix_moves.iter().any(|m| m.to == ip) {
+            return;
+        }
+        let (resolved, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.prefix_moves)
+            .into_iter()
+            .partition(|m| m.to == ip);
+        self.prefix_moves = waiting;
+        let mut changes = Vec::new();
+        for watch in resolved {
+            let Some((to_prefix, to_asn)) = self
+                .hop(watch.ttl)
+                .and_then(|h| h.responders.get(&ip))
+                .and_then(|s| s.asn.as_ref())
+                .and_then(|a| Some((a.prefix.clone()?, a.number)))
+            else {
+                continue;
+            };
+            let (from_prefix, from_asn) = &watch.from;
+            let Some(change) =
+                PrefixChangeKind::classify(from_prefix, &[*from_asn], &to_prefix, &[to_asn])
+            else {
+                continue;
+            };
+            changes.push(EventKind::PrefixChange {
+                ttl: watch.ttl,
+                ip,
+                change,
+                from: format!("{} AS{}", from_prefix, from_asn),
+                to: format!("{} AS{}", to_prefix, to_asn),
+            });
+        }
+        for change in changes {
+            self.record_event(change);
+        }
+    }
+
+    /// Attach a note to the session, or to a hop when `ttl` is given.
+    /// Blank text is ignored.
+    pub fn add_note(&mut self, ttl: Option<u8>, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        self.notes.push(Note {
+            at: Utc::now(),
+            ttl,
+            text: text.to_string(),
+        });
+    }
+
+    /// Redo every enabled lookup for the responders at `ttl`, bypassing
+    /// the lookup caches. Returns how many responders were flagged.
+    pub fn request_lookup_refresh(&mut self, ttl: u8) -> usize {
+        let refresh = LookupRefresh {
+            dns: self.config.dns_enabled,
+            asn: self.config.asn_enabled,
+            geo: self.config.geo_enabled,
+            ix: self.config.ix_enabled,
+        };
+        if refresh == LookupRefresh::default() {
+            return 0;
+        }
+        let Some(hop) = self.hop_mut(ttl) else {
+            return 0;
+        };
+        for stats in hop.responders.values_mut() {
+            stats.refresh = refresh;
         }
+        hop.responders.len()
+    }
+
+    /// Notes attached to a specific hop
+    pub fn hop_notes(&self, ttl: u8) -> impl Iterator<Item = &Note> {
+        self.notes.iter().filter(move |n| n.ttl == Some(ttl))
     }
 
     /// Get hop by TTL (1-indexed)
@@ -1212,6 +3022,113 @@ impl Session {
         }
     }
 
+    /// Record the probe at `ttl` in round `seq` of `flow` as answered or
+    /// lost, for loss correlation
+    pub fn record_round_outcome(&mut self, seq: u8, flow: u8, ttl: u8, lost: bool) {
+        self.round_loss.record(seq, flow, ttl, lost);
+    }
+
+    /// How losses line up between hops up to the destination
+    /// (see [`RoundLoss::correlate`])
+    pub fn loss_correlation(&self) -> Vec<LossCorrelation> {
+        let max_ttl = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        self.round_loss
+            .correlate()
+            .into_iter()
+            .filter(|pair| pair.ttl_b <= max_ttl)
+            .collect()
+    }
+
+    /// Loss caused at each responding hop up to the destination
+    /// (see [`attribute_loss`](crate::state::loss::attribute_loss))
+    pub fn attributable_loss(&self) -> BTreeMap<u8, f64> {
+        let max_ttl = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        attribute_loss(self.hops.iter().filter(|h| h.ttl <= max_ttl))
+    }
+
+    /// Point-to-point links inferred between adjacent hops up to the
+    /// destination (see [`infer_links`](crate::state::link::infer_links))
+    pub fn inferred_links(&self) -> Vec<InferredLink> {
+        let max_ttl = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        infer_links(self.hops.iter().filter(|h| h.ttl <= max_ttl))
+    }
+
+    /// MPLS tunnels suspected from TTL and RTT patterns up to the
+    /// destination (see [`infer_tunnels`](crate::state::tunnel::infer_tunnels))
+    pub fn inferred_tunnels(&self) -> Vec<TunnelSpan> {
+        let max_ttl = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        infer_tunnels(self.hops.iter().filter(|h| h.ttl <= max_ttl))
+    }
+
+    /// Graph of the routes taken by each flow up to the destination
+    /// (`--flows`; empty in single-flow mode)
+    pub fn path_tree(&self) -> PathTree {
+        if self.config.flows < 2 {
+            return PathTree::default();
+        }
+        PathTree::build(&self.hops, self.dest_ttl.unwrap_or(self.config.max_ttl))
+    }
+
+    /// Path MTU: the smallest of the SYN-ACK MSS, Frag Needed reports and
+    /// the `--pmtud` result
+    pub fn path_mtu(&self) -> Option<MtuEstimate> {
+        let pmtud = self.pmtud.as_ref().and_then(|p| p.discovered_mtu);
+        self.mtu_hints
+            .estimate(self.target.resolved.is_ipv6(), pmtud)
+    }
+
+    /// Hops where the quoted DSCP differs from the one before it, up to
+    /// the destination. The first hop is compared with what was sent.
+    pub fn dscp_remarks(&self) -> Vec<DscpRemark> {
+        let max_ttl = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        let mut remarks = Vec::new();
+        let mut previous = None;
+        for hop in self.hops.iter().filter(|h| h.ttl <= max_ttl) {
+            let Some(info) = hop.dscp.as_ref() else {
+                continue;
+            };
+            let Some(observed) = info.observed() else {
+                continue;
+            };
+            let from = previous.unwrap_or(info.sent);
+            if observed != from {
+                remarks.push(DscpRemark {
+                    ttl: hop.ttl,
+                    from,
+                    to: observed,
+                });
+            }
+            previous = Some(observed);
+        }
+        remarks
+    }
+
+    /// Hops where the quoted ECN codepoint differs from the one before it,
+    /// up to the destination. The first hop is compared with what was sent.
+    pub fn ecn_changes(&self) -> Vec<EcnChange> {
+        let max_ttl = self.dest_ttl.unwrap_or(self.config.max_ttl);
+        let mut changes = Vec::new();
+        let mut previous = None;
+        for hop in self.hops.iter().filter(|h| h.ttl <= max_ttl) {
+            let Some(info) = hop.ecn.as_ref() else {
+                continue;
+            };
+            let Some(observed) = info.observed() else {
+                continue;
+            };
+            let from = previous.unwrap_or(info.sent);
+            if observed != from {
+                changes.push(EcnChange {
+                    ttl: hop.ttl,
+                    from,
+                    to: observed,
+                });
+            }
+            previous = Some(observed);
+        }
+        changes
+    }
+
     /// Get mutable hop by TTL (1-indexed)
     pub fn hop_mut(&mut self, ttl: u8) -> Option<&mut Hop> {
         if ttl == 0 || ttl as usize > self.hops.len() {
@@ -1233,42 +3150,158 @@ impl Session {
         self.hops.iter().rev().find(|h| h.received > 0)
     }
 
+    /// Position distances are measured from: `--origin`, else the located
+    /// source address, else the first hop with a location
+    pub fn origin(&self) -> Option<(GeoPoint, OriginSource)> {
+        if let Some(point) = self.config.origin {
+            return Some((point, OriginSource::Configured));
+        }
+        if let Some(point) = self.source_geo.as_ref().and_then(GeoPoint::from_geo) {
+            return Some((point, OriginSource::SourceAddress));
+        }
+        self.hops.iter().find_map(|hop| {
+            let geo = hop.primary_stats()?.geo.as_ref()?;
+            Some((GeoPoint::from_geo(geo)?, OriginSource::FirstHop(hop.ttl)))
+        })
+    }
+
+    /// Distance from the origin to the hop's primary responder
+    pub fn hop_distance(&self, hop: &Hop) -> Option<HopDistance> {
+        let (origin, _) = self.origin()?;
+        let to = GeoPoint::from_geo(hop.primary_stats()?.geo.as_ref()?)?;
+        Some(HopDistance::between(&origin, &to))
+    }
+
+    /// Count a reply toward the idle or loaded RTT at its hop (--load)
+    pub fn record_load_rtt(&mut self, ttl: u8, rtt: Duration) {
+        if let Some(ref mut load) = self.load {
+            load.record(ttl, rtt);
+        }
+    }
+
+    /// Latency-under-load grade, at the destination when it has replied
+    pub fn load_result(&self) -> Option<LoadResult> {
+        self.load.as_ref()?.result(self.dest_ttl)
+    }
+
     /// Reset all statistics while keeping the session structure
     pub fn reset_stats(&mut self) {
         self.total_sent = 0;
         self.complete = false;
         self.dest_ttl = None;
         self.started_at = Utc::now();
+        self.dest_timeouts = 0;
+        self.in_outage = false;
+        self.loss_alert = false;
+        self.in_spike = false;
+        if let Some(ref mut slo) = self.slo {
+            slo.reset();
+        }
+        if let Some(ref mut load) = self.load {
+            load.reset();
+        }
+        self.record_event(EventKind::ConfigChange {
+            description: "statistics reset".to_string(),
+        });
 
         // Reset PMTUD state if enabled
         if self.pmtud.is_some() {
             self.pmtud = Some(PmtudState::new(self.target.resolved.is_ipv6()));
         }
+        if self.df_sweep.is_some() {
+            self.df_sweep = Some(DfSweep::new(self.target.resolved.is_ipv6()));
+        }
 
+        self.path_epochs.clear();
+        self.routing_loop = None;
+        self.round_paths.clear();
+        self.round_loss.clear();
         for hop in &mut self.hops {
-            hop.sent = 0;
-            hop.received = 0;
-            hop.timeouts = 0;
-            hop.responders.clear();
-            hop.primary = None;
-            hop.recent_results.clear();
-            hop.flow_paths.clear();
-            hop.nat_info = None;
-            hop.rate_limit = None;
+            hop.clear_stats(self.config.stats_window);
             hop.route_changes.clear();
-            hop.asymmetry = None;
-            hop.ttl_manip = None;
+            hop.flap_count = 0;
+            hop.suppressed_flaps = 0;
             hop.flap_tracking_primary = None;
+            hop.pending_flap = None;
+            hop.reply_streak = None;
         }
     }
 
+    /// Archive and clear the statistics of hop `ttl` and every hop after it
+    /// after a confirmed path change there (`--reset-on-path-change`).
+    /// Route change history is kept, so flap detection carries on.
+    pub fn reset_for_path_change(&mut self, ttl: u8, from: IpAddr, to: IpAddr) {
+        let now = Utc::now();
+        let started_at = self
+            .path_epochs
+            .last()
+            .map_or(self.started_at, |epoch| epoch.ended_at);
+        let hops = self
+            .hops
+            .iter()
+            .filter(|hop| hop.ttl >= ttl && hop.sent > 0)
+            .map(HopSnapshot::of)
+            .collect();
+        self.path_epochs.push(PathEpoch {
+            started_at,
+            ended_at: now,
+            ttl,
+            from,
+            to,
+            hops,
+        });
+        if self.path_epochs.len() > MAX_PATH_EPOCHS {
+            self.path_epochs.remove(0);
+        }
+
+        for hop in self.hops.iter_mut().filter(|hop| hop.ttl >= ttl) {
+            hop.clear_stats(self.config.stats_window);
+        }
+    }
+
+    /// Hash the current path into `path_signatures` at the end of a round:
+    /// each hop's primary responder up to the destination, or up to the
+    /// furthest hop that answered. Returns true when the path changed.
+    pub fn record_path_signature(&mut self) -> bool {
+        let last = self.dest_ttl.or_else(|| {
+            self.hops
+                .iter()
+                .rev()
+                .find(|hop| hop.primary.is_some())
+                .map(|hop| hop.ttl)
+        });
+        let Some(last) = last else {
+            return false;
+        };
+        let path: Vec<&Hop> = self.hops.iter().filter(|hop| hop.ttl <= last).collect();
+        let primaries: Vec<Option<IpAddr>> = path.iter().map(|hop| hop.primary).collect();
+        let asns: Vec<u32> = path
+            .iter()
+            .filter_map(|hop| hop.primary_stats()?.asn.as_ref().map(|asn| asn.number))
+            .collect();
+        self.path_signatures.record(
+            path_signature(&primaries),
+            as_path_signature(&asns),
+            Utc::now(),
+        )
+    }
+
+    /// Hop `ttl`'s statistics before the latest reset that cleared it
+    pub fn previous_path(&self, ttl: u8) -> Option<(&PathEpoch, &HopSnapshot)> {
+        self.path_epochs.iter().rev().find_map(|epoch| {
+            let hop = epoch.hops.iter().find(|hop| hop.ttl == ttl)?;
+            Some((epoch, hop))
+        })
+    }
+
     /// Check if NAT is detected at any hop
+    #[allow(dead_code)]
     pub fn has_nat(&self) -> bool {
         self.hops.iter().any(|h| h.has_nat())
     }
 
-    /// Get the first hop where NAT is detected (likely the local NAT device)
-    #[allow(dead_code)]
+    /// Get the first hop where NAT is detected (likely the local NAT device).
+    /// Quoted packets from this hop on were seen after the rewrite.
     pub fn first_nat_hop(&self) -> Option<u8> {
         self.hops.iter().find(|h| h.has_nat()).map(|h| h.ttl)
     }
@@ -1354,6 +3387,39 @@ mod tests {
         assert!(stddev_ms > 8000 && stddev_ms < 8500);
     }
 
+    #[test]
+    fn test_responder_stats_mean_ci() {
+        let ip = IpAddr::V4(std::net::Ipv4Addr::new(1, 1, 1, 1));
+        let mut stats = ResponderStats::new(ip);
+        stats.record_response(Duration::from_millis(10));
+        assert_eq!(stats.mean_ci95, None);
+
+        // 10, 20, 30 ms: sample sd 10ms, t(2) = 4.303, half-width 24.84ms
+        stats.record_response(Duration::from_millis(20));
+        stats.record_response(Duration::from_millis(30));
+        let half = stats.mean_ci95.unwrap();
+        assert!((half - 24_843.0).abs() < 5.0, "{}", half);
+        // The low end is clamped at zero
+        let (low, high) = stats.mean_ci().unwrap();
+        assert_eq!(low, Duration::ZERO);
+        assert_eq!(high.as_millis(), 44);
+
+        // More replies with the same spread narrow it
+        for _ in 0..30 {
+            for ms in [10, 20, 30] {
+                stats.record_response(Duration::from_millis(ms));
+            }
+        }
+        assert!(stats.mean_ci95.unwrap() < 2_500.0);
+
+        // Absorbing another responder recomputes it from the combined moments
+        let mut other = ResponderStats::new(ip);
+        other.record_response(Duration::from_millis(20));
+        let before = stats.mean_ci95;
+        stats.absorb(other);
+        assert_ne!(stats.mean_ci95, before);
+    }
+
     #[test]
     fn test_responder_stats_jitter() {
         let ip = IpAddr::V4(std::net::Ipv4Addr::new(1, 1, 1, 1));
@@ -1507,32 +3573,538 @@ mod tests {
     }
 
     #[test]
-    fn test_session_serialization_roundtrip() {
+    fn test_session_serialization_roundtrip() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let config = Config::default();
+        let mut session = Session::new(target, config);
+
+        // Add some data
+        if let Some(hop) = session.hop_mut(1) {
+            hop.record_sent();
+            hop.record_response(
+                IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1)),
+                Duration::from_millis(5),
+            );
+        }
+
+        // Serialize
+        let json = serde_json::to_string(&session).unwrap();
+
+        // Deserialize
+        let restored: Session = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.target.original, "test.com");
+        assert_eq!(restored.hop(1).unwrap().sent, 1);
+        assert_eq!(restored.hop(1).unwrap().received, 1);
+    }
+
+    #[test]
+    fn test_loss_correlation_exported() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let mut session = Session::new(target, Config::default());
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(!json.contains("loss_correlation"));
+
+        // Hop 4 loses rounds 0, 5, 10..; hop 6 those and a few more
+        for seq in 0..40u8 {
+            session.record_round_outcome(seq, 0, 4, seq % 5 == 0);
+            session.record_round_outcome(seq, 0, 6, seq % 5 == 0 || seq % 13 == 1);
+        }
+        let pairs = session.loss_correlation();
+        assert_eq!(pairs.len(), 1);
+        assert!(pairs[0].shared());
+
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(json.contains("\"loss_correlation\":[{\"ttl_a\":4,\"ttl_b\":6"));
+        // Saved sessions load without the rounds behind it
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert!(restored.loss_correlation().is_empty());
+
+        session.reset_stats();
+        assert!(session.loss_correlation().is_empty());
+    }
+
+    #[test]
+    fn test_asn_registry_label_and_legacy_json() {
+        let info: AsnInfo =
+            serde_json::from_str(r#"{"number":1103,"name":"SURFNET-NL","prefix":null}"#).unwrap();
+        assert_eq!(info.registry_label(), None);
+
+        let info = AsnInfo {
+            registry: Some("ripencc".to_string()),
+            ..info
+        };
+        assert_eq!(info.registry_label().as_deref(), Some("RIPE NCC"));
+        let json = serde_json::to_value(&info).unwrap();
+        assert!(json.get("country").is_none());
+    }
+
+    #[test]
+    fn test_session_notes() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let mut session = Session::new(target, Config::default());
+
+        session.add_note(None, "ISP maintenance started");
+        session.add_note(Some(3), "  flapping uplink ");
+        session.add_note(Some(3), "   ");
+
+        assert_eq!(session.notes.len(), 2);
+        let hop3: Vec<_> = session.hop_notes(3).collect();
+        assert_eq!(hop3.len(), 1);
+        assert_eq!(hop3[0].text, "flapping uplink");
+        assert_eq!(session.hop_notes(1).count(), 0);
+
+        // Notes survive a JSON round trip and are omitted when empty
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.notes.len(), 2);
+        session.notes.clear();
+        assert!(
+            !serde_json::to_string(&session)
+                .unwrap()
+                .contains("\"notes\"")
+        );
+    }
+
+    #[test]
+    fn test_session_outage_events() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let mut session = Session::new(target, Config::default());
+
+        // Timeouts before the destination is known are ignored
+        session.record_dest_timeout(5);
+        session.record_dest_reached(5, Duration::from_millis(10));
+        session.record_dest_reached(5, Duration::from_millis(10));
+        assert_eq!(session.events.len(), 1);
+        assert_eq!(
+            session.events[0].kind,
+            EventKind::DestinationReached { ttl: 5 }
+        );
+
+        // Intermediate hop timeouts don't count toward an outage
+        for _ in 0..OUTAGE_THRESHOLD {
+            session.record_dest_timeout(3);
+        }
+        assert_eq!(session.events.len(), 1);
+
+        for _ in 0..OUTAGE_THRESHOLD + 1 {
+            session.record_dest_timeout(5);
+        }
+        assert_eq!(session.events[1].kind, EventKind::OutageStart { ttl: 5 });
+
+        session.record_dest_reached(5, Duration::from_millis(10));
+        assert_eq!(
+            session.events[2].kind,
+            EventKind::OutageEnd {
+                ttl: 5,
+                lost: OUTAGE_THRESHOLD + 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_session_events_capped() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let mut session = Session::new(target, Config::default());
+        for i in 0..MAX_EVENTS + 10 {
+            session.record_event(EventKind::Alert {
+                message: i.to_string(),
+            });
+        }
+        assert_eq!(session.events.len(), MAX_EVENTS);
+        assert_eq!(
+            session.events[0].kind,
+            EventKind::Alert {
+                message: "10".to_string()
+            }
+        );
+
+        // Cursors keep working after the oldest events were dropped
+        let cursor = session.events_recorded();
+        assert!(session.events_since(cursor).is_empty());
+        session.record_event(EventKind::Alert {
+            message: "new".to_string(),
+        });
+        assert_eq!(session.events_since(cursor).len(), 1);
+        assert_eq!(session.events_since(0).len(), MAX_EVENTS);
+    }
+
+    #[test]
+    fn test_session_slo_tracking() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let config = Config {
+            slo: Some(Slo::parse("p95<50ms").unwrap()),
+            ..Config::default()
+        };
+        let mut session = Session::new(target, config);
+
+        // Probes past the destination hop also get replies; they don't count
+        session.record_dest_reached(5, Duration::from_millis(20));
+        session.record_dest_reached(6, Duration::from_millis(20));
+        session.record_dest_reached(7, Duration::from_millis(20));
+        session.record_dest_reached(5, Duration::from_millis(90));
+        session.record_dest_timeout(6);
+        session.record_dest_timeout(5);
+
+        let slo = session.slo.as_ref().unwrap();
+        assert_eq!((slo.probes, slo.slow, slo.lost), (3, 1, 1));
+
+        session.reset_stats();
+        assert_eq!(session.slo.as_ref().unwrap().probes, 0);
+
+        session.set_slo(None);
+        assert!(session.slo.is_none());
+        assert!(session.config.slo.is_none());
+    }
+
+    #[test]
+    fn test_responder_stats_absorb() {
+        let a_ip = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let b_ip = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 1, 1));
+        let mut a = ResponderStats::new(a_ip);
+        let mut b = ResponderStats::new(b_ip);
+        let mut all = ResponderStats::new(a_ip);
+        for ms in [10, 12, 14] {
+            a.record_response(Duration::from_millis(ms));
+            all.record_response(Duration::from_millis(ms));
+        }
+        for ms in [20, 30] {
+            b.record_response(Duration::from_millis(ms));
+            all.record_response(Duration::from_millis(ms));
+        }
+        b.hostname = Some("core1.example.net".to_string());
+
+        a.absorb(b);
+        assert_eq!(a.received, 5);
+        assert_eq!(a.min_rtt, Duration::from_millis(10));
+        assert_eq!(a.max_rtt, Duration::from_millis(30));
+        assert!((a.mean_rtt - all.mean_rtt).abs() < 1e-6);
+        assert!((a.m2 - all.m2).abs() < 1e-3);
+        assert_eq!(a.hostname.as_deref(), Some("core1.example.net"));
+        assert_eq!(a.aliases, vec![b_ip]);
+    }
+
+    #[test]
+    fn test_session_alias_rules() {
+        let a = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+        let b = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 2));
+        let other = IpAddr::V4(std::net::Ipv4Addr::new(198, 51, 100, 1));
+        let config = Config {
+            aliases: vec![crate::state::ResponderAlias::parse("edge=192.0.2.0/30").unwrap()],
+            ..Config::default()
+        };
+        let target = Target::new("test".to_string(), other);
+        let mut session = Session::new(target, config);
+
+        let hop = session.hop_mut(3).unwrap();
+        for _ in 0..4 {
+            hop.record_response(a, Duration::from_millis(5));
+        }
+        hop.record_response(b, Duration::from_millis(6));
+        hop.record_flow_response(0, b, Duration::from_millis(6));
+        hop.record_response(other, Duration::from_millis(7));
+        session.apply_aliases(3);
+
+        let hop = session.hop(3).unwrap();
+        assert_eq!(hop.responders.len(), 2);
+        let merged = &hop.responders[&a];
+        assert_eq!(merged.received, 5);
+        assert_eq!(merged.alias.as_deref(), Some("edge"));
+        assert_eq!(merged.aliases, vec![b]);
+        assert_eq!(hop.flow_paths[&0].primary_responder, Some(a));
+
+        // Later replies from the merged address count under the survivor
+        let hop = session.hop_mut(3).unwrap();
+        hop.record_response(b, Duration::from_millis(6));
+        assert_eq!(hop.responders[&a].received, 6);
+        assert!(!hop.responders.contains_key(&b));
+    }
+
+    #[test]
+    fn test_session_merge_by_hostname() {
+        let a = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 1, 1));
+        let config = Config {
+            merge_hostnames: true,
+            ..Config::default()
+        };
+        let target = Target::new("test".to_string(), a);
+        let mut session = Session::new(target, config);
+        let hop = session.hop_mut(2).unwrap();
+        hop.record_response(a, Duration::from_millis(5));
+        hop.record_response(b, Duration::from_millis(5));
+        hop.record_response(b, Duration::from_millis(5));
+
+        // Nothing to merge until both names are known
+        session.apply_aliases(2);
+        assert_eq!(session.hop(2).unwrap().responders.len(), 2);
+
+        let hop = session.hop_mut(2).unwrap();
+        for ip in [a, b] {
+            hop.responders.get_mut(&ip).unwrap().hostname = Some("Core1.example.net".into());
+        }
+        session.apply_aliases(2);
+        let hop = session.hop(2).unwrap();
+        assert_eq!(hop.responders.len(), 1);
+        assert_eq!(hop.primary, Some(b));
+        assert_eq!(hop.responders[&b].received, 3);
+    }
+
+    #[test]
+    fn test_session_stats_window() {
+        let ip = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let target = Target::new("test".to_string(), ip);
+        let mut session = Session::new(target.clone(), Config::default());
+        assert!(session.hop(1).unwrap().window_summary().is_none());
+
+        let config = Config {
+            stats_window: Some(Duration::from_secs(300)),
+            ..Config::default()
+        };
+        let mut session2 = Session::new(target, config);
+        for session in [&mut session, &mut session2] {
+            let hop = session.hop_mut(1).unwrap();
+            hop.record_response(ip, Duration::from_millis(10));
+            hop.record_response(ip, Duration::from_millis(30));
+            hop.record_timeout();
+        }
+
+        let summary = session2.hop(1).unwrap().window_summary().unwrap();
+        assert_eq!(summary.received, 2);
+        assert_eq!(summary.timeouts, 1);
+        assert_eq!(summary.avg_rtt, Duration::from_millis(20));
+        assert_eq!(summary.max_rtt, Duration::from_millis(30));
+
+        // Reset starts a fresh window
+        session2.reset_stats();
+        let summary = session2.hop(1).unwrap().window_summary().unwrap();
+        assert_eq!(summary.received, 0);
+    }
+
+    #[test]
+    fn test_hop_late_replies() {
+        let mut hop = Hop::new(4);
+        assert_eq!(hop.late_pct(), 0.0);
+        assert!(hop.late_rtt().is_none());
+
+        for _ in 0..4 {
+            hop.record_sent();
+            hop.record_timeout();
+        }
+        hop.record_late(Duration::from_millis(1200));
+        hop.record_late(Duration::from_millis(1800));
+
+        // Late replies don't undo the timeout
+        assert_eq!(hop.loss_pct(), 100.0);
+        assert_eq!(hop.late, 2);
+        assert_eq!(hop.late_pct(), 50.0);
+        assert_eq!(
+            hop.late_rtt(),
+            Some((Duration::from_millis(1500), Duration::from_millis(1800)))
+        );
+    }
+
+    #[test]
+    fn test_session_kernel_drops() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let mut session = Session::new(target, Config::default());
+        session.record_kernel_drops(0);
+        assert!(session.events.is_empty());
+        assert_eq!(session.socket_stats.as_ref().unwrap().recv_drops, Some(0));
+
+        // Only the first overrun raises an alert
+        session.record_kernel_drops(12);
+        session.record_kernel_drops(40);
+        assert_eq!(session.events.len(), 1);
+        assert!(session.events[0].kind.describe().contains("12 responses"));
+        assert_eq!(session.socket_stats.as_ref().unwrap().recv_drops, Some(40));
+    }
+
+    #[test]
+    fn test_session_prefix_tracking() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let mut session = Session::new(target, Config::default());
+        let ip = IpAddr::V4(std::net::Ipv4Addr::new(203, 0, 113, 9));
+        session
+            .hop_mut(4)
+            .unwrap()
+            .record_response(ip, Duration::from_millis(10));
+
+        // First answer is the baseline, repeats are quiet
+        session.update_prefix(ip, "203.0.112.0/22", &[64500]);
+        session.update_prefix(ip, "203.0.112.0/22", &[64500]);
+        assert!(session.events.is_empty());
+
+        session.update_prefix(ip, "203.0.113.0/24", &[64511]);
+        let origin = session.hop(4).unwrap().responders[&ip]
+            .prefix_origin
+            .clone()
+            .unwrap();
+        assert!(origin.more_specific);
+        assert_eq!(origin.label(), "203.0.113.0/24 AS64511");
+        assert_eq!(origin.previous.as_deref(), Some("203.0.112.0/22 AS64500"));
+        assert!(matches!(
+            session.events.last().unwrap().kind,
+            EventKind::PrefixChange {
+                ttl: 4,
+                change: PrefixChangeKind::MoreSpecific,
+                ..
+            }
+        ));
+
+        // Withdrawal is logged but the hop stays flagged
+        session.update_prefix(ip, "203.0.112.0/22", &[64500]);
+        assert_eq!(session.events.len(), 2);
+        assert!(
+            session.hop(4).unwrap().responders[&ip]
+                .prefix_origin
+                .as_ref()
+                .unwrap()
+                .more_specific
+        );
+    }
+
+    #[test]
+    fn test_request_lookup_refresh() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let config = Config {
+            geo_enabled: false,
+            ..Config::default()
+        };
+        let mut session = Session::new(target, config);
+        let hop = session.hop_mut(2).unwrap();
+        hop.record_sent();
+        hop.record_response(
+            IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1)),
+            Duration::from_millis(3),
+        );
+        assert_eq!(session.request_lookup_refresh(1), 0);
+        assert_eq!(session.request_lookup_refresh(2), 1);
+        let stats = session.hop(2).unwrap().responders.values().next().unwrap();
+        assert!(stats.refresh.dns && stats.refresh.asn && stats.refresh.ix);
+        assert!(!stats.refresh.geo);
+
+        session.config.dns_enabled = false;
+        session.config.asn_enabled = false;
+        session.config.ix_enabled = false;
+        assert_eq!(session.request_lookup_refresh(2), 0);
+    }
+
+    #[test]
+    fn test_session_latency_spike() {
+        let target = Target::new(
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+        );
+        let mut session = Session::new(target.clone(), Config::default());
+        let router = |n| IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, n));
+        let spikes = |s: &Session| {
+            s.events
+                .iter()
+                .filter_map(|e| match e.kind {
+                    EventKind::LatencySpike {
+                        cause, cause_ttl, ..
+                    } => Some((cause, cause_ttl)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // 12 quiet rounds, then delay added from hop 2 onwards
+        for round in 0..15 {
+            let extra = if round >= 12 { 50 } else { 0 };
+            for ttl in 1..=3u8 {
+                let ip = if ttl == 3 {
+                    target.resolved
+                } else {
+                    router(ttl)
+                };
+                let rtt = Duration::from_millis(5 * ttl as u64 + if ttl > 1 { extra } else { 0 });
+                let hop = session.hop_mut(ttl).unwrap();
+                hop.record_sent();
+                hop.record_response(ip, rtt);
+            }
+            session.record_dest_reached(3, Duration::from_millis(15 + extra));
+        }
+        // Logged once, on the second slow round
+        assert_eq!(
+            spikes(&session),
+            vec![(crate::state::spike::SpikeCause::Congestion, Some(2))]
+        );
+    }
+
+    #[test]
+    fn test_session_loss_alert() {
         let target = Target::new(
-            "test.com".to_string(),
-            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+            "test".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8)),
         );
-        let config = Config::default();
-        let mut session = Session::new(target, config);
+        let config = Config {
+            alert_loss: Some(20.0),
+            ..Config::default()
+        };
+        let mut session = Session::new(target.clone(), config);
+        session.record_dest_reached(5, Duration::from_millis(10));
+        let alerts = |s: &Session| {
+            s.events
+                .iter()
+                .filter(|e| matches!(e.kind, EventKind::Alert { .. }))
+                .count()
+        };
 
-        // Add some data
-        if let Some(hop) = session.hop_mut(1) {
+        // 8 replies then 2 losses: 20% over 10 probes raises the alert
+        for i in 0..10 {
+            let hop = session.hop_mut(5).unwrap();
             hop.record_sent();
-            hop.record_response(
-                IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1)),
-                Duration::from_millis(5),
-            );
+            if i < 8 {
+                hop.record_response(target.resolved, Duration::from_millis(5));
+                session.record_dest_reached(5, Duration::from_millis(10));
+            } else {
+                hop.record_timeout();
+                session.record_dest_timeout(5);
+            }
         }
+        assert_eq!(alerts(&session), 1);
 
-        // Serialize
-        let json = serde_json::to_string(&session).unwrap();
-
-        // Deserialize
-        let restored: Session = serde_json::from_str(&json).unwrap();
-
-        assert_eq!(restored.target.original, "test.com");
-        assert_eq!(restored.hop(1).unwrap().sent, 1);
-        assert_eq!(restored.hop(1).unwrap().received, 1);
+        // Enough good probes push the window below the threshold again
+        for _ in 0..10 {
+            let hop = session.hop_mut(5).unwrap();
+            hop.record_sent();
+            hop.record_response(target.resolved, Duration::from_millis(5));
+            session.record_dest_reached(5, Duration::from_millis(10));
+        }
+        assert_eq!(alerts(&session), 2);
+        assert!(matches!(
+            &session.events.last().unwrap().kind,
+            EventKind::Alert { message } if message.contains("back to")
+        ));
     }
 
     #[test]
@@ -1601,6 +4173,30 @@ mod tests {
         assert!(avg_micros > 400_000 && avg_micros < 600_000);
     }
 
+    #[test]
+    fn test_hop_sample_history() {
+        let mut hop = Hop::new(1);
+        let ip = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let before = Utc::now();
+        hop.record_response(ip, Duration::from_millis(12));
+        hop.record_timeout();
+
+        let samples: Vec<_> = hop.history.iter().collect();
+        assert_eq!(samples[0].ip, Some(ip));
+        assert_eq!(samples[0].rtt_ms, Some(12.0));
+        assert_eq!(samples[1].rtt_ms, None);
+        assert!(samples[0].at >= before && samples[1].at >= samples[0].at);
+
+        let json = serde_json::to_string(&hop).unwrap();
+        assert!(json.contains("\"history\":[{\"at\":"));
+
+        for _ in 0..SAMPLE_HISTORY {
+            hop.record_timeout();
+        }
+        assert_eq!(hop.history.len(), SAMPLE_HISTORY);
+        assert!(hop.history.iter().all(|s| s.ip.is_none()));
+    }
+
     #[test]
     fn test_hop_recent_results_tracking() {
         let mut hop = Hop::new(3);
@@ -1643,6 +4239,26 @@ mod tests {
         assert!(first_entry >= Duration::from_millis(10));
     }
 
+    #[test]
+    fn test_responder_stats_recent_jitter() {
+        let ip = IpAddr::V4(std::net::Ipv4Addr::new(1, 1, 1, 1));
+        let mut stats = ResponderStats::new(ip);
+
+        stats.record_response(Duration::from_millis(10));
+        assert!(stats.recent_jitter.is_empty());
+        stats.record_response(Duration::from_millis(14));
+        stats.record_response(Duration::from_millis(11));
+        assert_eq!(
+            stats.recent_jitter,
+            [Duration::from_millis(4), Duration::from_millis(3)]
+        );
+
+        for i in 0..100 {
+            stats.record_response(Duration::from_millis(i));
+        }
+        assert_eq!(stats.recent_jitter.len(), 60);
+    }
+
     #[test]
     fn test_responder_stats_percentiles() {
         let ip = IpAddr::V4(std::net::Ipv4Addr::new(1, 1, 1, 1));
@@ -1748,6 +4364,7 @@ mod tests {
         // Single flow doesn't count as ECMP
         assert!(!hop.has_ecmp());
         assert_eq!(hop.path_count(), 1);
+        assert_eq!(hop.ecmp, None);
         assert_eq!(hop.ecmp_paths(), vec![(0, ip1)]);
 
         // Record flow 1 probes - all to ip2 (different path!)
@@ -1763,6 +4380,8 @@ mod tests {
         assert_eq!(paths.len(), 2);
         assert!(paths.contains(&(0, ip1)));
         assert!(paths.contains(&(1, ip2)));
+        let ecmp = hop.ecmp.unwrap();
+        assert_eq!((ecmp.width, ecmp.flows), (2, 2));
     }
 
     #[test]
@@ -1784,6 +4403,42 @@ mod tests {
         assert_eq!(hop.ecmp_paths().len(), 4); // 4 flows, but all same responder
     }
 
+    #[test]
+    fn test_responder_loss_ecmp() {
+        let mut hop = Hop::new(3);
+        let a = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        let ms = Duration::from_millis(10);
+
+        // Flow 0 lands on a, flow 1 on b; b's path drops half its probes
+        for _ in 0..4 {
+            hop.record_response(a, ms);
+            hop.record_flow_response(0, a, ms);
+            hop.record_response(b, ms);
+            hop.record_flow_response(1, b, ms);
+            hop.record_timeout();
+            hop.record_flow_timeout(1);
+        }
+
+        assert_eq!(hop.responders[&a].sent, 4);
+        assert_eq!(hop.responders[&a].loss_pct(), 0.0);
+        assert_eq!(hop.responders[&b].sent, 8);
+        assert_eq!(hop.responders[&b].loss_pct(), 50.0);
+        let loss = hop.responder_loss(b).unwrap();
+        assert_eq!(
+            (loss.sent, loss.loss_pct, loss.estimated),
+            (8.0, 50.0, false)
+        );
+
+        // A timeout on a flow that hasn't answered yet is split by reply share
+        hop.record_timeout();
+        hop.record_flow_timeout(2);
+        let loss = hop.responder_loss(a).unwrap();
+        assert_eq!(loss.sent, 4.5);
+        assert!(loss.estimated);
+        assert_eq!(hop.responder_loss(b).unwrap().sent, 8.5);
+    }
+
     #[test]
     fn test_session_reset_clears_flow_paths() {
         let target = Target::new(
@@ -1815,14 +4470,15 @@ mod tests {
         // Test that no false flaps occur when counts are equal (sticky tie-breaker)
         // The internal flap_tracking_primary uses hysteresis to prevent oscillation
         let mut hop = Hop::new(5);
+        let damping = FlapDamping::default();
         let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
         let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
 
         // Alternate responses to keep counts equal, past the min threshold
         // ip1: 3, ip2: 3 after 6 responses (past threshold of 5)
         for _ in 0..3 {
-            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None);
-            hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None);
+            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None, damping);
+            hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None, damping);
         }
 
         // No flaps should be recorded because:
@@ -1838,17 +4494,18 @@ mod tests {
     fn test_route_flap_margin_threshold() {
         // Test that flaps are only recorded when new IP exceeds by margin of 2
         let mut hop = Hop::new(5);
+        let damping = FlapDamping::default();
         let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
         let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
 
         // ip1 gets 3 responses, becomes initial flap_tracking_primary
         for _ in 0..3 {
-            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None);
+            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None, damping);
         }
 
         // ip2 gets 4 responses (only +1 over ip1's 3) - not enough margin
         for _ in 0..4 {
-            hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None);
+            hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None, damping);
         }
         // ip1: 3, ip2: 4 - only +1, below margin of 2
         // No flap recorded (flap_tracking_primary still ip1)
@@ -1858,7 +4515,7 @@ mod tests {
         );
 
         // ip2 gets one more (now +2 margin: 5 vs 3)
-        hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None);
+        hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None, damping);
         // ip1: 3, ip2: 5 - now +2, should record flap
         assert_eq!(
             hop.route_changes.len(),
@@ -1873,16 +4530,17 @@ mod tests {
     fn test_route_flap_min_response_threshold() {
         // Test that flaps are only recorded after MIN_RESPONSES_FOR_FLAP (5)
         let mut hop = Hop::new(5);
+        let damping = FlapDamping::default();
         let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
         let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
 
         // Record 1 from ip1 (becomes flap_tracking_primary)
-        hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None);
+        hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None, damping);
 
         // Record 3 from ip2 (margin +2 at received=4, switches flap_tracking_primary)
         // But below min threshold, so no flap recorded
         for _ in 0..3 {
-            hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None);
+            hop.record_response_detecting_flaps(ip2, Duration::from_millis(10), None, damping);
         }
         // Total: 4, ip1=1, ip2=3 - switch happened but below threshold
         assert!(
@@ -1893,13 +4551,13 @@ mod tests {
 
         // Now add one more from ip1 - brings total to 5, ip1=2, ip2=3
         // ip2 still leads by only 1, not enough margin for another switch
-        hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None);
+        hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None, damping);
         assert!(hop.route_changes.is_empty());
         assert_eq!(hop.received, 5);
 
         // Add 2 more from ip1 - now ip1=4, ip2=3, ip1 leads by 1
         for _ in 0..2 {
-            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None);
+            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None, damping);
         }
         // Not enough margin to switch back, no flap
         assert!(hop.route_changes.is_empty());
@@ -1909,7 +4567,7 @@ mod tests {
         // - We're past min threshold (received=10)
         // - ip1 exceeds ip2 by more than margin of 2
         for _ in 0..3 {
-            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None);
+            hop.record_response_detecting_flaps(ip1, Duration::from_millis(10), None, damping);
         }
         assert_eq!(
             hop.route_changes.len(),
@@ -1920,6 +4578,72 @@ mod tests {
         assert_eq!(hop.route_changes[0].to_ip, ip1);
     }
 
+    #[test]
+    fn test_route_flap_hold_down() {
+        let mut hop = Hop::new(5);
+        let damping = FlapDamping {
+            hold: 3,
+            cooldown: 0,
+        };
+        let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        let reply = |hop: &mut Hop, ip| {
+            hop.record_response_detecting_flaps(ip, Duration::from_millis(10), None, damping)
+        };
+
+        reply(&mut hop, ip1);
+        reply(&mut hop, ip1);
+        // ip2 takes the lead (8 vs 6) but never answers 3 times in a row
+        for _ in 0..4 {
+            for ip in [ip2, ip2, ip1] {
+                assert!(reply(&mut hop, ip).is_none());
+            }
+        }
+        assert_eq!(hop.pending_flap, Some((ip1, ip2)));
+
+        // ip1 wins back the lead (10 vs 8) before the hold-down ends: noise
+        for _ in 0..4 {
+            reply(&mut hop, ip1);
+        }
+        assert!(hop.route_changes.is_empty());
+        assert_eq!(hop.suppressed_flaps, 1);
+        assert_eq!(hop.pending_flap, None);
+
+        // A real reroute: ip2 persists and the change is logged once
+        let changes: Vec<_> = (0..6).filter_map(|_| reply(&mut hop, ip2)).collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].from_ip, changes[0].to_ip), (ip1, ip2));
+        assert_eq!(hop.flap_count, 1);
+    }
+
+    #[test]
+    fn test_route_flap_cooldown_damps_events() {
+        let mut hop = Hop::new(5);
+        let damping = FlapDamping {
+            hold: 1,
+            cooldown: 100,
+        };
+        let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        let mut events = 0;
+        let rounds = [(ip1, 5), (ip2, 8), (ip1, 5), (ip2, 5)];
+        for ip in rounds
+            .iter()
+            .flat_map(|&(ip, n)| std::iter::repeat_n(ip, n))
+        {
+            if hop
+                .record_response_detecting_flaps(ip, Duration::from_millis(10), None, damping)
+                .is_some()
+            {
+                events += 1;
+            }
+        }
+        // Every change is counted, only the first is logged
+        assert_eq!(hop.flap_count, 3);
+        assert_eq!(events, 1);
+        assert!(hop.route_changes.iter().skip(1).all(|c| c.damped));
+    }
+
     #[test]
     fn test_route_flap_capped_history() {
         // Test that route_changes is capped at MAX_ROUTE_CHANGES (50)
@@ -1933,6 +4657,7 @@ mod tests {
                 from_ip: if i % 2 == 0 { ip1 } else { ip2 },
                 to_ip: if i % 2 == 0 { ip2 } else { ip1 },
                 at_seq: i as u64,
+                damped: false,
             });
 
             // Simulate cap behavior
@@ -1966,6 +4691,7 @@ mod tests {
                 from_ip: ip1,
                 to_ip: ip2,
                 at_seq: 10,
+                damped: false,
             });
         }
 
@@ -1976,6 +4702,88 @@ mod tests {
         assert!(session.hop(1).unwrap().route_changes.is_empty());
     }
 
+    #[test]
+    fn test_stale_responder() {
+        let mut hop = Hop::new(3);
+        let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        // Two flows per round; ip2 answers only in the first round
+        for round in 0..12 {
+            hop.record_sent();
+            hop.record_sent();
+            hop.record_response(ip1, Duration::from_millis(5));
+            if round == 0 {
+                hop.record_response(ip2, Duration::from_millis(7));
+            }
+        }
+
+        let (s1, s2) = (&hop.responders[&ip1], &hop.responders[&ip2]);
+        assert!(s1.first_seen <= s1.last_seen);
+        assert!(s2.last_seen <= s1.last_seen);
+        assert_eq!(hop.rounds_since_seen(s1, 2), 0);
+        assert_eq!(hop.rounds_since_seen(s2, 2), 11);
+        assert!(!hop.is_stale(s1, 10, 2));
+        assert!(hop.is_stale(s2, 10, 2));
+        assert!(!hop.is_stale(s2, 11, 2));
+        assert!(!hop.is_stale(s2, 0, 2));
+
+        // Merging keeps the earliest first and latest last sighting
+        let mut merged = s2.clone();
+        merged.absorb(s1.clone());
+        assert_eq!(merged.first_seen, s1.first_seen.min(s2.first_seen));
+        assert_eq!(merged.last_seen, s1.last_seen);
+        assert_eq!(merged.last_seen_probe, s1.last_seen_probe);
+    }
+
+    #[test]
+    fn test_reset_for_path_change() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let mut session = Session::new(target, Config::default());
+        let ip1 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let ip2 = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        for ttl in 1..=3 {
+            let hop = session.hop_mut(ttl).unwrap();
+            for _ in 0..4 {
+                hop.record_sent();
+                hop.record_response(ip1, Duration::from_millis(10 * ttl as u64));
+            }
+            hop.route_changes.push(RouteChange {
+                from_ip: ip1,
+                to_ip: ip2,
+                at_seq: 4,
+                damped: false,
+            });
+        }
+
+        session.reset_for_path_change(2, ip1, ip2);
+
+        // Hop 1 is before the change and keeps its numbers
+        assert_eq!(session.hop(1).unwrap().received, 4);
+        for ttl in 2..=3 {
+            let hop = session.hop(ttl).unwrap();
+            assert_eq!((hop.sent, hop.received), (0, 0));
+            assert!(hop.responders.is_empty());
+            assert_eq!(hop.route_changes.len(), 1);
+        }
+
+        let [epoch] = session.path_epochs.as_slice() else {
+            panic!("expected one epoch");
+        };
+        assert_eq!((epoch.ttl, epoch.from, epoch.to), (2, ip1, ip2));
+        let ttls: Vec<u8> = epoch.hops.iter().map(|h| h.ttl).collect();
+        assert_eq!(ttls, vec![2, 3]);
+        let (_, before) = session.previous_path(3).unwrap();
+        assert_eq!(before.received, 4);
+        assert_eq!(before.avg_ms, Some(30.0));
+        assert!(session.previous_path(1).is_none());
+
+        session.reset_stats();
+        assert!(session.path_epochs.is_empty());
+    }
+
     #[test]
     fn test_multi_flow_no_flap_detection() {
         // Test that record_response_with_mpls (used in multi-flow mode)
@@ -2109,6 +4917,28 @@ mod tests {
         assert!(hop.has_asymmetry());
     }
 
+    #[test]
+    fn test_hop_record_return_ttl_per_responder() {
+        let mut hop = Hop::new(5);
+        let a = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(hop.record_return_ttl(a, 59), None); // no such responder yet
+
+        hop.record_response(a, Duration::from_millis(10));
+        hop.record_response(b, Duration::from_millis(10));
+        assert_eq!(hop.record_return_ttl(a, 59), None);
+        assert_eq!(hop.record_return_ttl(b, 250), None);
+
+        let mut changes = Vec::new();
+        for _ in 0..crate::state::return_ttl::RETURN_TTL_SETTLE {
+            changes.extend(hop.record_return_ttl(a, 57));
+            assert_eq!(hop.record_return_ttl(b, 250), None);
+        }
+        assert_eq!(changes, [(59, 57)]);
+        assert_eq!(hop.responders[&a].return_ttl.as_ref().unwrap().current, 57);
+        assert_eq!(hop.responders[&b].return_ttl.as_ref().unwrap().changes, 0);
+    }
+
     #[test]
     fn test_asymmetry_reset_clears() {
         let target = Target::new(
@@ -2241,4 +5071,489 @@ mod tests {
         session.reset_stats();
         assert!(session.hop(1).unwrap().ttl_manip.is_none());
     }
+
+    #[test]
+    fn test_dscp_remarks() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let config = Config {
+            dscp: Some(46),
+            ..Config::default()
+        };
+        let mut session = Session::new(target, config);
+        // Hops 1-2 keep EF, 3 sees it zeroed, 4 agrees with 3, 5 too few quotes
+        for (ttl, quoted, samples) in [(1, 46, 5), (2, 46, 5), (3, 0, 4), (4, 0, 5), (5, 8, 2)] {
+            let hop = session.hop_mut(ttl).unwrap();
+            for _ in 0..samples {
+                hop.record_quoted_dscp(46, quoted);
+            }
+        }
+        // A stray quote doesn't outvote the rest
+        session.hop_mut(4).unwrap().record_quoted_dscp(46, 46);
+
+        assert_eq!(
+            session.dscp_remarks(),
+            vec![DscpRemark {
+                ttl: 3,
+                from: 46,
+                to: 0
+            }]
+        );
+        assert_eq!(
+            session.hop(5).unwrap().dscp.as_ref().unwrap().observed(),
+            None
+        );
+
+        assert_eq!(dscp_label(46), "46/EF");
+        assert_eq!(dscp_label(0), "0/BE");
+        assert_eq!(dscp_label(18), "18/AF21");
+        assert_eq!(dscp_label(24), "24/CS3");
+        assert_eq!(dscp_label(5), "5");
+
+        session.reset_stats();
+        assert!(session.dscp_remarks().is_empty());
+    }
+
+    #[test]
+    fn test_ecn_changes() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let config = Config {
+            ecn: Some(EcnCodepoint::Ect0),
+            ..Config::default()
+        };
+        let mut session = Session::new(target, config);
+        // Hop 2 sees congestion marks, 3 onwards the marking cleared
+        let (ect0, ce, not_ect) = (EcnCodepoint::Ect0, EcnCodepoint::Ce, EcnCodepoint::NotEct);
+        for (ttl, quoted) in [(1, ect0), (2, ce), (3, not_ect), (4, not_ect)] {
+            let hop = session.hop_mut(ttl).unwrap();
+            for _ in 0..4 {
+                hop.record_quoted_ecn(ect0, quoted);
+            }
+        }
+
+        let changes = session.ecn_changes();
+        assert_eq!(
+            changes,
+            vec![EcnChange {
+                ttl: 3,
+                from: ect0,
+                to: not_ect
+            }]
+        );
+        assert!(changes[0].bleached());
+
+        session.reset_stats();
+        assert!(session.ecn_changes().is_empty());
+    }
+
+    #[test]
+    fn test_nat_check_port_and_checksum() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let mut session = Session::new(target, Config::default());
+        // Hop 1 is the NAT itself and quotes the packet as sent
+        session
+            .hop_mut(1)
+            .unwrap()
+            .record_nat_check((Some(50000), Some(0x1234)), (Some(50000), Some(0x1234)));
+        // Beyond it the port survives but the address, so the checksum, changed
+        let hop = session.hop_mut(2).unwrap();
+        hop.record_nat_check((Some(50000), Some(0x1234)), (Some(50000), Some(0x9abc)));
+        // A checksum the error didn't quote isn't a rewrite
+        hop.record_nat_check((Some(50000), Some(0x1234)), (Some(50000), None));
+        let nat = hop.nat_info.as_ref().unwrap();
+        assert_eq!(nat.port_matched, 2);
+        assert_eq!(nat.checksum_rewritten, 1);
+        assert_eq!(nat.nat_percentage(), 50.0);
+        session
+            .hop_mut(3)
+            .unwrap()
+            .record_nat_check((Some(50000), Some(0x1234)), (Some(61000), Some(0x5678)));
+
+        assert!(!session.hop(1).unwrap().has_nat());
+        assert!(session.hop(3).unwrap().has_nat());
+        assert_eq!(session.first_nat_hop(), Some(2));
+    }
+
+    #[test]
+    fn test_prefix_move_events() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let mut session = Session::new(target, Config::default());
+        let ip = |c, d| IpAddr::V4(std::net::Ipv4Addr::new(192, 0, c, d));
+        let set_asn = |session: &mut Session, ttl, addr, prefix: &str, number| {
+            let hop = session.hop_mut(ttl).unwrap();
+            hop.record_response(addr, Duration::from_millis(5));
+            hop.responders.get_mut(&addr).unwrap().asn = Some(AsnInfo {
+                number,
+                name: String::new(),
+                prefix: Some(prefix.to_string()),
+                ..Default::default()
+            });
+        };
+        let prefix_events = |session: &Session| {
+            session
+                .events
+                .iter()
+                .filter_map(|e| match &e.kind {
+                    EventKind::PrefixChange {
+                        ttl, change, to, ..
+                    } => Some((*ttl, *change, to.clone())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        set_asn(&mut session, 3, ip(2, 1), "192.0.2.0/24", 64500);
+
+        // Another address in the same prefix: an ordinary path change
+        set_asn(&mut session, 3, ip(2, 9), "192.0.2.0/24", 64500);
+        session.watch_prefix_change(3, ip(2, 1), ip(2, 9));
+        assert!(prefix_events(&session).is_empty());
+        assert!(session.prefix_moves.is_empty());
+
+        // Onto a more-specific, logged once the ASN lookup lands
+        let new = ip(2, 200);
+        session
+            .hop_mut(3)
+            .unwrap()
+            .record_response(new, Duration::from_millis(5));
+        session.watch_prefix_change(3, ip(2, 9), new);
+        assert!(prefix_events(&session).is_empty());
+        set_asn(&mut session, 3, new, "192.0.2.128/25", 64511);
+        session.resolve_prefix(new);
+        assert_eq!(
+            prefix_events(&session),
+            vec![(
+                3,
+                PrefixChangeKind::MoreSpecific,
+                "192.0.2.128/25 AS64511".to_string()
+            )]
+        );
+
+        // Old responder without a known prefix: nothing to compare
+        session
+            .hop_mut(4)
+            .unwrap()
+            .record_response(ip(9, 1), Duration::from_millis(5));
+        session.watch_prefix_change(4, ip(9, 1), ip(9, 2));
+        assert!(session.prefix_moves.is_empty());
+    }
+
+    #[test]
+    fn test_ix_change_events() {
+        let target = Target::new(
+            "test.com".to_string(),
+            IpAddr::V4(std::net::Ipv4Addr::new(1, 2, 3, 4)),
+        );
+        let mut session = Session::new(target, Config::default());
+        let ip = |last| IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, last));
+        let ix = |name: &str| IxInfo {
+            name: name.to_string(),
+            city: None,
+            country: None,
+        };
+        let ix_names = |session: &Session| {
+            session
+                .events
+                .iter()
+                .filter_map(|e| match &e.kind {
+                    EventKind::IxChange { ttl, from, to, .. } => {
+                        Some((*ttl, from.clone(), to.clone()))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        for ttl in 1..=4 {
+            let hop = session.hop_mut(ttl).unwrap();
+            hop.record_response(ip(ttl), Duration::from_millis(5));
+        }
+        session
+            .hop_mut(3)
+            .unwrap()
+            .responders
+            .get_mut(&ip(3))
+            .unwrap()
+            .ix = Some(ix("AMS-IX"));
+
+        // Hop 3 moves from the exchange to a private interconnect: nothing
+        // until the new address has been looked up
+        session
+            .hop_mut(3)
+            .unwrap()
+            .record_response(ip(33), Duration::from_millis(5));
+        session.watch_ix_change(3, ip(3), ip(33));
+        assert!(ix_names(&session).is_empty());
+        session.resolve_ix(ip(33));
+        assert_eq!(ix_names(&session), vec![(3, Some("AMS-IX".into()), None)]);
+
+        // Flapping at hop 2 and back before the lookup: no change
+        session.watch_ix_change(2, ip(2), ip(22));
+        session.watch_ix_change(2, ip(22), ip(2));
+        session.resolve_ix(ip(2));
+        assert_eq!(ix_names(&session).len(), 1);
+
+        // Hop 4 gains an IX, already known at the time of the change
+        let hop = session.hop_mut(4).unwrap();
+        hop.record_response(ip(44), Duration::from_millis(5));
+        hop.responders.get_mut(&ip(44)).unwrap().ix = Some(ix("DE-CIX"));
+        session.watch_ix_change(4, ip(4), ip(44));
+        assert_eq!(ix_names(&session)[1], (4, None, Some("DE-CIX".into())));
+
+        // No IX lookups: no watching
+        session.config.ix_enabled = false;
+        session.watch_ix_change(1, ip(1), ip(11));
+        assert!(session.ix_watch.is_empty());
+    }
+
+    #[test]
+    fn test_responder_availability() {
+        let mut hop = Hop::new(3);
+        let a = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        hop.record_response(a, Duration::from_millis(5));
+        hop.record_response(b, Duration::from_millis(5));
+        // Rebase the bookkeeping onto explicit minutes
+        hop.probed_minutes = 0;
+        hop.last_probed_minute = None;
+        for stats in hop.responders.values_mut() {
+            stats.answered_minutes = 0;
+            stats.last_answered_minute = None;
+        }
+
+        // a answers in minutes 0, 1 and 3; b first shows up in minute 2
+        // and answers every minute after
+        hop.note_answered_minute(a, 0);
+        hop.note_answered_minute(a, 0);
+        hop.note_answered_minute(a, 1);
+        hop.note_probed_minute(2);
+        hop.note_answered_minute(b, 2);
+        hop.note_answered_minute(a, 3);
+        hop.note_answered_minute(b, 3);
+        assert_eq!(hop.probed_minutes, 4);
+
+        let (pct, minutes) = hop.availability(&hop.responders[&a]).unwrap();
+        assert_eq!(minutes, 4);
+        assert!((pct - 75.0).abs() < 0.01);
+        let (pct, minutes) = hop.availability(&hop.responders[&b]).unwrap();
+        assert_eq!(minutes, 2);
+        assert!((pct - 100.0).abs() < 0.01);
+
+        // Minute 4 in progress: not held against a until it ends
+        hop.note_probed_minute(4);
+        hop.note_answered_minute(b, 4);
+        assert_eq!(hop.availability(&hop.responders[&a]).unwrap().1, 4);
+        hop.note_probed_minute(5);
+        let (pct, minutes) = hop.availability(&hop.responders[&a]).unwrap();
+        assert_eq!(minutes, 5);
+        assert!((pct - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_routing_loop_events() {
+        let ip = |n| IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, n));
+        let target = IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8));
+        let mut session = Session::new(Target::new("t".into(), target), Config::default());
+        let te = IcmpResponseType::TimeExceeded(0);
+
+        // Round 0: 1 2 3 2 3 -- a loop between 2 and 3
+        for (ttl, n) in [(1, 1), (2, 2), (3, 3), (4, 2), (5, 3)] {
+            session.record_round_reply(0, ttl, ip(n), te);
+        }
+        let found = session.routing_loop.clone().unwrap();
+        assert_eq!((found.first_ttl, found.last_ttl), (2, 5));
+        assert_eq!(found.routers, vec![ip(2), ip(3)]);
+        // Logged when found and when the span grows, not on every reply
+        assert_eq!(session.events.len(), 2);
+        assert!(matches!(
+            session.events[0].kind,
+            EventKind::RoutingLoop {
+                first_ttl: 2,
+                last_ttl: 4,
+                ..
+            }
+        ));
+
+        // Loop-free rounds clear it once the looped round ages out
+        for seq in 1..=3 {
+            for (ttl, n) in [(1, 1), (2, 2), (3, 3), (4, 4)] {
+                session.record_round_reply(seq, ttl, ip(n), te);
+            }
+        }
+        assert!(session.routing_loop.is_some());
+        session.record_round_reply(4, 1, ip(1), te);
+        assert!(session.routing_loop.is_none());
+
+        // Time Exceeded from the destination itself, logged once
+        session.record_round_reply(4, 5, target, te);
+        session.record_round_reply(5, 5, target, te);
+        assert_eq!(session.events.len(), 3);
+        assert!(matches!(
+            session.events[2].kind,
+            EventKind::DestinationExpiry { ttl: 5, .. }
+        ));
+
+        // The destination answering probes at every TTL past it isn't a loop
+        for ttl in 2..=8 {
+            session.record_round_reply(6, ttl, target, IcmpResponseType::EchoReply);
+        }
+        assert!(session.routing_loop.is_none());
+        assert_eq!(session.events.len(), 3);
+    }
+
+    #[test]
+    fn test_redirects() {
+        let router = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 1));
+        let gateway = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 254));
+        let mut session = Session::new(
+            Target::new("t".into(), IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8))),
+            Config::default(),
+        );
+        session.record_redirect(router, gateway);
+        session.record_redirect(router, gateway);
+        assert_eq!(session.redirects.len(), 1);
+        assert_eq!(session.redirects[0].count, 2);
+        assert_eq!(session.events.len(), 1);
+        assert!(matches!(
+            session.events[0].kind,
+            EventKind::Redirect { gateway: g, .. } if g == gateway
+        ));
+        // Only flagged with --redirects warn
+        assert!(session.redirect_warning().is_none());
+        session.config.redirects = RedirectPolicy::Warn;
+        assert_eq!(session.redirect_warning().unwrap().gateway, gateway);
+
+        session.config.redirects = RedirectPolicy::Ignore;
+        session.record_redirect(router, IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 2)));
+        assert_eq!(session.redirects.len(), 1);
+    }
+
+    #[test]
+    fn test_outcome_strip() {
+        let a = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+        let mut hop = Hop::new(3);
+        hop.record_response_type(a, IcmpResponseType::TimeExceeded(0));
+        hop.record_response_type(a, IcmpResponseType::DestUnreachable(13));
+        hop.record_response_type(b, IcmpResponseType::TimeExceeded(0));
+        hop.record_timeout();
+        hop.record_response_type(a, IcmpResponseType::TimeExceeded(0));
+        assert_eq!(hop.outcome_strip(a), ".X×.");
+        assert_eq!(hop.outcome_strip(b), ".×");
+
+        for _ in 0..OUTCOME_HISTORY {
+            hop.record_timeout();
+        }
+        assert_eq!(hop.outcomes.len(), OUTCOME_HISTORY);
+        assert!(hop.outcome_strip(a).chars().all(|c| c == '×'));
+
+        // ICMPv6 admin prohibited is code 1, port unreachable code 4
+        assert_eq!(IcmpResponseType::DestUnreachable(1).symbol(true), 'X');
+        assert_eq!(IcmpResponseType::DestUnreachable(4).symbol(true), 'U');
+        assert_eq!(IcmpResponseType::DestUnreachable(4).symbol(false), 'F');
+    }
+
+    #[test]
+    fn test_gap_limit() {
+        let config = Config {
+            gap_limit: Some(3),
+            ..Config::default()
+        };
+        let mut session = Session::new(
+            Target::new("t".into(), IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8))),
+            config,
+        );
+        let probe = |session: &mut Session, ttl: u8, reply: bool| {
+            let hop = session.hop_mut(ttl).unwrap();
+            hop.record_sent();
+            if reply {
+                hop.record_response(
+                    IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, ttl)),
+                    Duration::from_millis(5),
+                );
+            }
+        };
+        // Hops 1-2 answer, 3 and up are silent
+        for _ in 0..GAP_MIN_SENT {
+            for ttl in 1..=30 {
+                probe(&mut session, ttl, ttl <= 2);
+            }
+        }
+        assert_eq!(session.gap_stop(), Some(5));
+        assert_eq!(session.probe_ceiling(1), 5);
+        assert_eq!(session.probe_ceiling(GAP_RECHECK_ROUNDS), 30);
+
+        // A re-check finds the path again further out; the gap moves past it
+        probe(&mut session, 9, true);
+        assert_eq!(session.gap_stop(), Some(12));
+        // A gap that would end at max_ttl saves nothing
+        probe(&mut session, 27, true);
+        assert_eq!(session.gap_stop(), None);
+        assert_eq!(session.probe_ceiling(1), 30);
+
+        // The destination always wins
+        session.dest_ttl = Some(9);
+        assert_eq!(session.probe_ceiling(1), 9);
+        assert_eq!(session.gap_stop(), None);
+    }
+
+    #[test]
+    fn test_update_pacing_spares_destination() {
+        let mut session = Session::new(
+            Target::new("t".into(), IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8))),
+            Config::default(),
+        );
+        // Hops 2 and 3 (the destination) both lose half their probes
+        for i in 0..10 {
+            for ttl in 2..=3 {
+                let hop = session.hop_mut(ttl).unwrap();
+                hop.record_sent();
+                if i % 2 == 0 {
+                    hop.record_response(
+                        IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, ttl)),
+                        Duration::from_millis(5),
+                    );
+                } else {
+                    hop.record_timeout();
+                }
+            }
+        }
+        session.dest_ttl = Some(3);
+        session.update_pacing();
+        assert_eq!(session.hop(2).unwrap().pace.period, 2);
+        assert_eq!(session.hop(3).unwrap().pace.period, 1);
+        assert_eq!(&session.pace_periods()[..3], [1, 2, 1]);
+
+        session.reset_stats();
+        assert!(!session.hop(2).unwrap().pace.backed_off());
+    }
+
+    #[test]
+    fn test_gap_limit_counts_from_first_ttl() {
+        let config = Config {
+            gap_limit: Some(3),
+            first_ttl: 6,
+            ..Config::default()
+        };
+        let mut session = Session::new(
+            Target::new("t".into(), IpAddr::V4(std::net::Ipv4Addr::new(8, 8, 8, 8))),
+            config,
+        );
+        // Nothing answers from the first TTL on; hops 1-5 are never probed
+        for _ in 0..GAP_MIN_SENT {
+            for ttl in 6..=30 {
+                session.hop_mut(ttl).unwrap().record_sent();
+            }
+        }
+        assert_eq!(session.gap_stop(), Some(8));
+    }
 }
@@ -0,0 +1,180 @@
+//! Path signatures
+//!
+//! Every round the current path (the primary responder at each hop up to
+//! the destination) is hashed into a short signature. Two rounds with the
+//! same signature took the same path, so a monitoring system can alert on
+//! a change by comparing one value instead of diffing hop lists. A second
+//! signature over the AS path tells a change inside a network from one
+//! between networks. FNV-1a keeps both stable across runs, platforms and
+//! ttl versions.
+//!
+//! A new signature has to hold for a few rounds before it replaces the
+//! current one, so a round that is still missing replies isn't a change.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::net::IpAddr;
+
+/// Signatures kept per session; the oldest are dropped first
+pub const MAX_SIGNATURES: usize = 100;
+
+/// Rounds in a row a new signature must be seen before it counts
+pub const SIGNATURE_HOLD: u32 = 3;
+
+/// A path and how long it was in use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathSignature {
+    /// Hash of the primary responders, 16 hex digits
+    pub signature: String,
+    /// Hash of the AS path (refreshed while ASN lookups fill in)
+    pub as_signature: String,
+    /// Round the path was first seen in
+    pub since: DateTime<Utc>,
+    /// Rounds it has been seen in
+    pub rounds: u64,
+}
+
+/// Signatures a session has seen, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureHistory {
+    pub entries: VecDeque<PathSignature>,
+    /// Changes from one signature to another (`entries` only keeps the latest)
+    pub changes: u64,
+    /// Signature waiting out the hold: when it was first seen and in how
+    /// many rounds in a row
+    #[serde(skip)]
+    candidate: Option<(String, DateTime<Utc>, u32)>,
+}
+
+impl SignatureHistory {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Signature of the path in use
+    pub fn current(&self) -> Option<&PathSignature> {
+        self.entries.back()
+    }
+
+    /// Record one round's signatures. Returns true when a new path takes
+    /// over from a previous one.
+    pub fn record(&mut self, signature: String, as_signature: String, at: DateTime<Utc>) -> bool {
+        if let Some(current) = self.entries.back_mut()
+            && current.signature == signature
+        {
+            current.rounds += 1;
+            current.as_signature = as_signature;
+            self.candidate = None;
+            return false;
+        }
+
+        let (since, seen) = match self.candidate.take() {
+            Some((pending, since, seen)) if pending == signature => (since, seen + 1),
+            _ => (at, 1),
+        };
+        if seen < SIGNATURE_HOLD {
+            self.candidate = Some((signature, since, seen));
+            return false;
+        }
+
+        let changed = !self.entries.is_empty();
+        if changed {
+            self.changes += 1;
+        }
+        self.entries.push_back(PathSignature {
+            signature,
+            as_signature,
+            since,
+            rounds: seen as u64,
+        });
+        if self.entries.len() > MAX_SIGNATURES {
+            self.entries.pop_front();
+        }
+        changed
+    }
+}
+
+/// Signature of a path given each hop's primary responder in TTL order
+/// (None for a hop that didn't answer)
+pub fn path_signature(primaries: &[Option<IpAddr>]) -> String {
+    let path: Vec<String> = primaries
+        .iter()
+        .map(|ip| ip.map_or_else(|| "*".to_string(), |ip| ip.to_string()))
+        .collect();
+    format!("{:016x}", fnv1a(path.join(",").as_bytes()))
+}
+
+/// Signature of an AS path; repeats of the same AS count once
+pub fn as_path_signature(asns: &[u32]) -> String {
+    let mut path: Vec<u32> = asns.to_vec();
+    path.dedup();
+    let path: Vec<String> = path.iter().map(|asn| asn.to_string()).collect();
+    format!("{:016x}", fnv1a(path.join(" ").as_bytes()))
+}
+
+/// 64-bit FNV-1a
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[test]
+    fn test_signatures_stable() {
+        // Known FNV-1a vectors keep signatures comparable across versions
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+
+        let path = path_signature(&[ip(1), None, ip(3)]);
+        assert_eq!(path.len(), 16);
+        assert_eq!(path, path_signature(&[ip(1), None, ip(3)]));
+        assert_ne!(path, path_signature(&[ip(1), ip(2), ip(3)]));
+        assert_ne!(path, path_signature(&[ip(3), None, ip(1)]));
+
+        assert_eq!(
+            as_path_signature(&[64500, 64500, 174]),
+            as_path_signature(&[64500, 174])
+        );
+        assert_ne!(as_path_signature(&[64500, 174]), as_path_signature(&[174]));
+    }
+
+    #[test]
+    fn test_history_holds_new_signatures() {
+        let at = Utc::now();
+        let mut history = SignatureHistory::default();
+        let (a, b) = ("a".to_string(), "b".to_string());
+
+        // The first path also has to hold before it counts
+        assert!(!history.record(a.clone(), "x".into(), at));
+        assert!(history.is_empty());
+        for _ in 1..SIGNATURE_HOLD {
+            assert!(!history.record(a.clone(), "x".into(), at));
+        }
+        assert_eq!(history.current().unwrap().signature, "a");
+        assert_eq!(history.changes, 0);
+
+        // A one-round blip doesn't count
+        assert!(!history.record(b.clone(), "x".into(), at));
+        assert!(!history.record(a.clone(), "y".into(), at));
+        assert_eq!(history.current().unwrap().rounds, 4);
+        assert_eq!(history.current().unwrap().as_signature, "y");
+
+        let later = at + chrono::Duration::seconds(5);
+        for round in 1..=SIGNATURE_HOLD {
+            let took_over = history.record(b.clone(), "y".into(), later);
+            assert_eq!(took_over, round == SIGNATURE_HOLD);
+        }
+        assert_eq!(history.changes, 1);
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(history.current().unwrap().since, later);
+    }
+}
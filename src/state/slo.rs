@@ -0,0 +1,275 @@
+//! Latency and loss objectives for a target
+//!
+//! An SLO such as `p95<80ms,loss<1%` is checked against every probe that
+//! reaches (or should have reached) the destination hop. Each objective
+//! allows a fraction of "bad" probes — replies slower than the threshold,
+//! or lost probes — and the share of that allowance already used up is the
+//! error budget shown in the TUI and in reports.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Percentile used when a latency objective doesn't name one
+const DEFAULT_PERCENTILE: f64 = 95.0;
+
+/// Service level objective: a latency percentile bound and/or a loss bound
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Slo {
+    /// Percentile the latency bound applies to (e.g. 95.0 for p95)
+    pub percentile: f64,
+    /// Latency bound in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    /// Maximum destination loss in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loss_pct: Option<f64>,
+}
+
+impl Slo {
+    /// Parse a comma-separated objective list, e.g. `p95<80ms,loss<1%`.
+    ///
+    /// `latency<80ms` is shorthand for p95.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut slo = Slo {
+            percentile: DEFAULT_PERCENTILE,
+            latency_ms: None,
+            loss_pct: None,
+        };
+
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (metric, value) = part
+                .split_once('<')
+                .ok_or_else(|| format!("SLO objective '{}' should look like p95<80ms", part))?;
+            let metric = metric.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            if metric == "loss" {
+                let pct: f64 = value
+                    .trim_end_matches('%')
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid loss bound '{}'", value))?;
+                if !(0.0..100.0).contains(&pct) {
+                    return Err(format!("loss bound must be 0-100%, got {}", value));
+                }
+                slo.loss_pct = Some(pct);
+            } else {
+                let percentile = match metric.as_str() {
+                    "latency" | "rtt" => DEFAULT_PERCENTILE,
+                    m => m
+                        .strip_prefix('p')
+                        .and_then(|p| p.parse::<f64>().ok())
+                        .filter(|p| *p > 0.0 && *p < 100.0)
+                        .ok_or_else(|| format!("unknown SLO metric '{}'", metric))?,
+                };
+                let ms: f64 = value
+                    .trim_end_matches("ms")
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid latency bound '{}'", value))?;
+                if ms <= 0.0 {
+                    return Err(format!("latency bound must be positive, got {}", value));
+                }
+                slo.percentile = percentile;
+                slo.latency_ms = Some(ms);
+            }
+        }
+
+        if slo.latency_ms.is_none() && slo.loss_pct.is_none() {
+            return Err("SLO needs at least one objective (e.g. p95<80ms)".into());
+        }
+        Ok(slo)
+    }
+
+    /// Fraction of replies allowed above the latency bound
+    fn allowed_slow(&self) -> f64 {
+        1.0 - self.percentile / 100.0
+    }
+}
+
+impl fmt::Display for Slo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(ms) = self.latency_ms {
+            parts.push(format!("p{}<{}ms", self.percentile, ms));
+        }
+        if let Some(pct) = self.loss_pct {
+            parts.push(format!("loss<{}%", pct));
+        }
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// Compliance counters for an SLO over the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloStatus {
+    pub slo: Slo,
+    /// Probes evaluated at the destination hop
+    pub probes: u64,
+    /// Of those, probes that timed out
+    pub lost: u64,
+    /// Replies slower than the latency bound
+    pub slow: u64,
+}
+
+impl SloStatus {
+    pub fn new(slo: Slo) -> Self {
+        Self {
+            slo,
+            probes: 0,
+            lost: 0,
+            slow: 0,
+        }
+    }
+
+    /// Count a destination probe: `Some(rtt)` for a reply, `None` for a loss
+    pub fn record(&mut self, rtt: Option<Duration>) {
+        self.probes += 1;
+        match rtt {
+            Some(rtt) => {
+                if self
+                    .slo
+                    .latency_ms
+                    .is_some_and(|ms| rtt.as_secs_f64() * 1000.0 > ms)
+                {
+                    self.slow += 1;
+                }
+            }
+            None => self.lost += 1,
+        }
+    }
+
+    /// Clear counters, keeping the objective
+    pub fn reset(&mut self) {
+        *self = Self::new(self.slo);
+    }
+
+    fn replies(&self) -> u64 {
+        self.probes - self.lost
+    }
+
+    /// Percentage of replies above the latency bound
+    pub fn slow_pct(&self) -> f64 {
+        match self.replies() {
+            0 => 0.0,
+            n => self.slow as f64 * 100.0 / n as f64,
+        }
+    }
+
+    /// Percentage of destination probes lost
+    pub fn loss_pct(&self) -> f64 {
+        match self.probes {
+            0 => 0.0,
+            n => self.lost as f64 * 100.0 / n as f64,
+        }
+    }
+
+    /// Remaining latency error budget (0.0-1.0), None without a latency objective
+    pub fn latency_budget(&self) -> Option<f64> {
+        self.slo.latency_ms?;
+        Some(budget_left(
+            self.slow_pct() / 100.0,
+            self.slo.allowed_slow(),
+        ))
+    }
+
+    /// Remaining loss error budget (0.0-1.0), None without a loss objective
+    pub fn loss_budget(&self) -> Option<f64> {
+        let allowed = self.slo.loss_pct? / 100.0;
+        Some(budget_left(self.loss_pct() / 100.0, allowed))
+    }
+
+    /// Tightest remaining budget across objectives
+    pub fn budget(&self) -> f64 {
+        [self.latency_budget(), self.loss_budget()]
+            .into_iter()
+            .flatten()
+            .fold(1.0, f64::min)
+    }
+
+    /// Whether every objective is currently met
+    pub fn met(&self) -> bool {
+        let latency_ok = self
+            .slo
+            .latency_ms
+            .is_none_or(|_| self.slow_pct() <= self.slo.allowed_slow() * 100.0);
+        let loss_ok = self.slo.loss_pct.is_none_or(|pct| self.loss_pct() <= pct);
+        latency_ok && loss_ok
+    }
+}
+
+/// Share of an allowance not yet used, clamped to 0.0-1.0
+fn budget_left(bad: f64, allowed: f64) -> f64 {
+    if allowed <= 0.0 {
+        return if bad > 0.0 { 0.0 } else { 1.0 };
+    }
+    (1.0 - bad / allowed).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slo() {
+        let slo = Slo::parse("p95<80ms, loss<1%").unwrap();
+        assert_eq!(slo.percentile, 95.0);
+        assert_eq!(slo.latency_ms, Some(80.0));
+        assert_eq!(slo.loss_pct, Some(1.0));
+        assert_eq!(slo.to_string(), "p95<80ms,loss<1%");
+
+        let slo = Slo::parse("p99.9<150").unwrap();
+        assert_eq!(slo.percentile, 99.9);
+        assert_eq!(slo.loss_pct, None);
+
+        assert_eq!(Slo::parse("latency<50ms").unwrap().percentile, 95.0);
+        assert!(Slo::parse("").is_err());
+        assert!(Slo::parse("p100<10ms").is_err());
+        assert!(Slo::parse("jitter<5ms").is_err());
+        assert!(Slo::parse("loss<150%").is_err());
+        assert!(Slo::parse("p95=80ms").is_err());
+    }
+
+    #[test]
+    fn test_slo_budget() {
+        let mut status = SloStatus::new(Slo::parse("p90<50ms,loss<10%").unwrap());
+        assert!(status.met());
+        assert_eq!(status.budget(), 1.0);
+
+        // 18 fast replies, 1 slow, 1 lost
+        for _ in 0..18 {
+            status.record(Some(Duration::from_millis(20)));
+        }
+        status.record(Some(Duration::from_millis(80)));
+        status.record(None);
+
+        assert_eq!(status.probes, 20);
+        assert!(status.met());
+        // Loss: 5% of an allowed 10% used
+        assert!((status.loss_budget().unwrap() - 0.5).abs() < 1e-9);
+        // Latency: 1/19 slow of an allowed 10%
+        assert!((status.latency_budget().unwrap() - (1.0 - 10.0 / 19.0)).abs() < 1e-9);
+        assert!((status.budget() - (1.0 - 10.0 / 19.0)).abs() < 1e-9);
+
+        for _ in 0..5 {
+            status.record(None);
+        }
+        assert!(!status.met());
+        assert_eq!(status.loss_budget(), Some(0.0));
+
+        status.reset();
+        assert_eq!(status.probes, 0);
+        assert!(status.met());
+    }
+
+    #[test]
+    fn test_zero_loss_objective() {
+        let mut status = SloStatus::new(Slo::parse("loss<0%").unwrap());
+        status.record(Some(Duration::from_millis(1)));
+        assert_eq!(status.budget(), 1.0);
+        status.record(None);
+        assert_eq!(status.budget(), 0.0);
+        assert_eq!(status.latency_budget(), None);
+    }
+}
@@ -0,0 +1,209 @@
+//! Latency spike classification
+//!
+//! A jump in the destination's RTT is more useful with a guess at why.
+//! When the destination's latest replies run well above its recent median,
+//! the signals from the same moment are weighed: a path change just before
+//! points to a reroute. Otherwise the first hop from which every later hop
+//! shows the rise is where the delay is added; a rise already at hop 1
+//! means the local link or host. A slow hop followed by hops without the
+//! rise is ignored, since ICMP deprioritisation makes single hops noisy.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::Sample;
+
+/// How far above the baseline, as a multiple and in milliseconds, the
+/// latest replies must be to count as a spike
+const SPIKE_FACTOR: f64 = 2.0;
+const SPIKE_MIN_RISE_MS: f64 = 20.0;
+/// Latest replies that must all be high (one slow reply isn't a spike)
+const SPIKE_RECENT: usize = 2;
+/// Replies before those that make up the baseline
+const SPIKE_BASELINE: usize = 30;
+const SPIKE_MIN_BASELINE: usize = 10;
+/// Share of the destination's rise a hop must show to count as shifted
+const SHIFT_SHARE: f64 = 0.5;
+/// A path change this recent (seconds) makes the spike a reroute
+pub const REROUTE_WINDOW_SECS: i64 = 60;
+/// Timeouts among the destination's latest results that make a loss burst
+const LOSS_BURST: usize = 2;
+const LOSS_BURST_WINDOW: usize = 5;
+
+/// Probable cause of a latency spike
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpikeCause {
+    /// The path changed just before
+    Reroute,
+    /// Delay added from one hop onwards
+    Congestion,
+    /// Every hop shifted, from the first: local link or host
+    Local,
+    /// Too few hops answering to tell
+    Unknown,
+}
+
+impl SpikeCause {
+    /// e.g. "congestion at hop 5"
+    pub fn label(self, ttl: Option<u8>) -> String {
+        match (self, ttl) {
+            (Self::Reroute, Some(ttl)) => format!("reroute at hop {}", ttl),
+            (Self::Reroute, None) => "reroute".to_string(),
+            (Self::Congestion, Some(ttl)) => format!("congestion at hop {}", ttl),
+            (Self::Congestion, None) => "congestion".to_string(),
+            (Self::Local, _) => "local issue".to_string(),
+            (Self::Unknown, _) => "unknown".to_string(),
+        }
+    }
+}
+
+/// RTT shift at one hop, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttShift {
+    /// Median of the replies before the latest
+    pub baseline: f64,
+    /// Lowest of the latest replies
+    pub recent: f64,
+}
+
+impl RttShift {
+    /// From a hop's reply history; None without enough replies
+    pub fn from_history(history: &VecDeque<Sample>) -> Option<Self> {
+        let rtts: Vec<f64> = history.iter().filter_map(|s| s.rtt_ms).collect();
+        if rtts.len() < SPIKE_RECENT + SPIKE_MIN_BASELINE {
+            return None;
+        }
+        let (before, recent) = rtts.split_at(rtts.len() - SPIKE_RECENT);
+        let mut baseline = before[before.len().saturating_sub(SPIKE_BASELINE)..].to_vec();
+        baseline.sort_by(f64::total_cmp);
+        Some(Self {
+            baseline: baseline[baseline.len() / 2],
+            recent: recent.iter().copied().fold(f64::INFINITY, f64::min),
+        })
+    }
+
+    pub fn rise(&self) -> f64 {
+        self.recent - self.baseline
+    }
+
+    pub fn is_spike(&self) -> bool {
+        self.recent >= self.baseline * SPIKE_FACTOR && self.rise() >= SPIKE_MIN_RISE_MS
+    }
+}
+
+/// Classify a spike at the destination (`dest_ttl`, rising by
+/// `rise` ms) from the shifts of the hops before it, in TTL order, and the
+/// hop of a recent path change
+pub fn classify(
+    dest_ttl: u8,
+    rise: f64,
+    hops: &[(u8, RttShift)],
+    reroute: Option<u8>,
+) -> (SpikeCause, Option<u8>) {
+    if let Some(ttl) = reroute {
+        return (SpikeCause::Reroute, Some(ttl));
+    }
+    if hops.is_empty() {
+        return (SpikeCause::Unknown, None);
+    }
+    let shifted = |shift: &RttShift| shift.rise() >= rise * SHIFT_SHARE;
+    // Earliest hop of the run of shifted hops that reaches the destination
+    let start = hops
+        .iter()
+        .rev()
+        .take_while(|(_, shift)| shifted(shift))
+        .last()
+        .map(|&(ttl, _)| ttl);
+    match start {
+        Some(1) => (SpikeCause::Local, None),
+        Some(ttl) => (SpikeCause::Congestion, Some(ttl)),
+        // Only the destination itself shows it
+        None => (SpikeCause::Congestion, Some(dest_ttl)),
+    }
+}
+
+/// Whether the latest results (true = reply) hold a loss burst
+pub fn loss_burst(results: &VecDeque<bool>) -> bool {
+    results
+        .iter()
+        .rev()
+        .take(LOSS_BURST_WINDOW)
+        .filter(|ok| !**ok)
+        .count()
+        >= LOSS_BURST
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn history(rtts: &[f64]) -> VecDeque<Sample> {
+        rtts.iter()
+            .map(|&rtt| Sample {
+                at: Utc::now(),
+                ip: None,
+                rtt_ms: Some(rtt),
+            })
+            .collect()
+    }
+
+    fn shift(baseline: f64, recent: f64) -> RttShift {
+        RttShift { baseline, recent }
+    }
+
+    #[test]
+    fn test_rtt_shift() {
+        let mut rtts = vec![10.0; 12];
+        assert!(!RttShift::from_history(&history(&rtts)).unwrap().is_spike());
+
+        rtts.extend([60.0, 45.0]);
+        let found = RttShift::from_history(&history(&rtts)).unwrap();
+        assert_eq!(found, shift(10.0, 45.0));
+        assert!(found.is_spike());
+
+        // One slow reply isn't enough
+        rtts.push(10.0);
+        assert!(!RttShift::from_history(&history(&rtts)).unwrap().is_spike());
+
+        // Doubling a 5ms path is under the minimum rise
+        assert!(!shift(5.0, 12.0).is_spike());
+        assert_eq!(RttShift::from_history(&history(&[10.0; 5])), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let quiet = shift(10.0, 11.0);
+        let up = shift(10.0, 50.0);
+
+        assert_eq!(
+            classify(6, 40.0, &[(1, up), (2, up), (3, up)], None),
+            (SpikeCause::Local, None)
+        );
+        // Hop 2 doesn't show it but hops 3 on do
+        assert_eq!(
+            classify(6, 40.0, &[(1, quiet), (2, quiet), (3, up), (5, up)], None),
+            (SpikeCause::Congestion, Some(3))
+        );
+        // A hop that happens to be slow with quiet hops after it isn't it
+        assert_eq!(
+            classify(6, 40.0, &[(1, quiet), (2, up), (3, quiet)], None),
+            (SpikeCause::Congestion, Some(6))
+        );
+        assert_eq!(
+            classify(6, 40.0, &[(1, up)], Some(4)),
+            (SpikeCause::Reroute, Some(4))
+        );
+        assert_eq!(classify(6, 40.0, &[], None), (SpikeCause::Unknown, None));
+        assert_eq!(SpikeCause::Congestion.label(Some(3)), "congestion at hop 3");
+    }
+
+    #[test]
+    fn test_loss_burst() {
+        let results: VecDeque<bool> = [false, false, true, true, true, true, true].into();
+        assert!(!loss_burst(&results));
+        let results: VecDeque<bool> = [true, false, true, false, true].into();
+        assert!(loss_burst(&results));
+    }
+}
@@ -0,0 +1,175 @@
+//! DF-bit size sweep (`--df-sweep`)
+//!
+//! `--pmtud` finds one number for the whole path; the sweep shows where
+//! along the path it comes from. Each round sends one Don't Fragment probe
+//! of the next size in a ladder of common MTUs to every hop up to the
+//! destination. A hop that answers at a size was reached unfragmented at
+//! that size, so its largest answered size is its maximum unfragmented
+//! size. A Frag Needed / Packet Too Big instead names the router that could
+//! not forward the probe. Unanswered probes say nothing (ICMP rate limits
+//! look the same), so they are not counted against a size.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// Sizes swept over IPv4, in bytes including headers: the IPv4 minimum
+/// reassembly size, the IPv6 minimum MTU, and common tunnel MTUs (WireGuard,
+/// VXLAN, GRE, PPPoE) up to Ethernet
+pub const SWEEP_SIZES_V4: &[u16] = &[576, 1280, 1400, 1420, 1450, 1476, 1480, 1492, 1500];
+
+/// IPv6 links carry at least 1280 bytes, so the ladder starts there
+pub const SWEEP_SIZES_V6: &[u16] = &[1280, 1400, 1420, 1450, 1476, 1480, 1492, 1500];
+
+/// A size refused on the way to a hop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SweepBlock {
+    /// Probe size that was refused
+    pub size: u16,
+    /// Router that sent Frag Needed / Packet Too Big
+    pub from: IpAddr,
+    /// Next-hop MTU it reported, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u16>,
+}
+
+/// Sweep results for one TTL
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SweepHop {
+    /// Largest probe this hop answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unfragmented: Option<u16>,
+    /// Smallest probe refused before reaching this hop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<SweepBlock>,
+}
+
+/// State of a `--df-sweep` run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DfSweep {
+    /// Sizes still being swept, smallest first
+    pub sizes: Vec<u16>,
+    /// Smallest size the local interface refused to send; it and anything
+    /// larger are dropped from `sizes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_limit: Option<u16>,
+    /// Results by TTL
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hops: BTreeMap<u8, SweepHop>,
+    /// Index into `sizes` of the next round's probes
+    #[serde(skip)]
+    next: usize,
+}
+
+impl DfSweep {
+    pub fn new(ipv6: bool) -> Self {
+        let sizes = if ipv6 { SWEEP_SIZES_V6 } else { SWEEP_SIZES_V4 };
+        Self {
+            sizes: sizes.to_vec(),
+            local_limit: None,
+            hops: BTreeMap::new(),
+            next: 0,
+        }
+    }
+
+    /// Size for this round's probes, cycling through the ladder
+    pub fn next_size(&mut self) -> Option<u16> {
+        if self.sizes.is_empty() {
+            return None;
+        }
+        let size = self.sizes[self.next % self.sizes.len()];
+        self.next = (self.next + 1) % self.sizes.len();
+        Some(size)
+    }
+
+    /// A hop answered a probe of `size` (anything but Frag Needed)
+    pub fn record_answer(&mut self, ttl: u8, size: u16) {
+        let hop = self.hops.entry(ttl).or_default();
+        if hop.max_unfragmented.is_none_or(|max| size > max) {
+            hop.max_unfragmented = Some(size);
+        }
+    }
+
+    /// A probe of `size` sent to `ttl` drew Frag Needed / Packet Too Big
+    pub fn record_frag_needed(&mut self, ttl: u8, size: u16, from: IpAddr, mtu: Option<u16>) {
+        let hop = self.hops.entry(ttl).or_default();
+        if hop.blocked.is_none_or(|b| size < b.size) {
+            hop.blocked = Some(SweepBlock { size, from, mtu });
+        }
+    }
+
+    /// The local interface refused to send `size` (EMSGSIZE)
+    pub fn record_local_reject(&mut self, size: u16) {
+        self.sizes.retain(|&s| s < size);
+        self.local_limit = Some(self.local_limit.map_or(size, |l| l.min(size)));
+        self.next = 0;
+    }
+
+    /// Largest size that reached `ttl` unfragmented
+    pub fn max_unfragmented(&self, ttl: u8) -> Option<u16> {
+        self.hops.get(&ttl).and_then(|h| h.max_unfragmented)
+    }
+
+    /// First TTL a size was refused on the way to, and by whom
+    pub fn first_block(&self) -> Option<(u8, SweepBlock)> {
+        self.hops
+            .iter()
+            .find_map(|(&ttl, hop)| hop.blocked.map(|b| (ttl, b)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizes_cycle() {
+        let mut sweep = DfSweep::new(true);
+        let sizes: Vec<_> = (0..SWEEP_SIZES_V6.len() + 1)
+            .map(|_| sweep.next_size().unwrap())
+            .collect();
+        assert_eq!(&sizes[..SWEEP_SIZES_V6.len()], SWEEP_SIZES_V6);
+        assert_eq!(sizes[SWEEP_SIZES_V6.len()], 1280);
+    }
+
+    #[test]
+    fn test_local_reject_drops_larger_sizes() {
+        let mut sweep = DfSweep::new(false);
+        sweep.next_size();
+        sweep.record_local_reject(1480);
+        assert_eq!(sweep.local_limit, Some(1480));
+        assert_eq!(sweep.sizes, [576, 1280, 1400, 1420, 1450, 1476]);
+        assert_eq!(sweep.next_size(), Some(576));
+
+        sweep.record_local_reject(576);
+        assert_eq!(sweep.next_size(), None);
+    }
+
+    #[test]
+    fn test_per_hop_results() {
+        let router: IpAddr = "10.0.0.2".parse().unwrap();
+        let mut sweep = DfSweep::new(false);
+        for size in [1400, 1500, 1450] {
+            sweep.record_answer(2, size);
+        }
+        sweep.record_answer(3, 1400);
+        sweep.record_frag_needed(3, 1500, router, Some(1420));
+        sweep.record_frag_needed(3, 1450, router, Some(1420));
+        sweep.record_frag_needed(4, 1450, router, None);
+
+        assert_eq!(sweep.max_unfragmented(2), Some(1500));
+        assert_eq!(sweep.max_unfragmented(3), Some(1400));
+        assert_eq!(sweep.max_unfragmented(1), None);
+        assert_eq!(
+            sweep.first_block(),
+            Some((
+                3,
+                SweepBlock {
+                    size: 1450,
+                    from: router,
+                    mtu: Some(1420)
+                }
+            ))
+        );
+    }
+}
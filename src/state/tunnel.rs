@@ -0,0 +1,339 @@
+//! MPLS tunnel inference without ICMP extensions
+//!
+//! Routers that don't attach RFC 4950 label stacks still give tunnels away
+//! (Donnet et al., "Revealing MPLS Tunnels Obscured from Traceroute"):
+//!
+//! - **qTTL**: with ttl-propagate the label TTL expires inside the LSP, but
+//!   the IP TTL underneath is never decremented, so successive LSRs quote an
+//!   IP TTL of 2, 3, 4... instead of 1.
+//! - **uTurn**: an LSR's Time Exceeded reply follows the LSP to its end
+//!   before turning back, so its return path is longer than the forward
+//!   path by twice the remaining tunnel length, shrinking by two per hop.
+//! - **RTT plateau**: for the same reason every LSR's RTT sits at the
+//!   egress's latency. Flat RTTs alone are common, so this only backs up
+//!   the other two.
+//! - **FRPLA**: without ttl-propagate the LSRs vanish from the trace; the
+//!   hop after the tunnel shows a return path several hops longer than its
+//!   predecessor's, as the reply crosses the hidden routers in the clear.
+//!
+//! Only primary responders are considered, and hops carrying label stacks
+//! are skipped: their tunnels are already visible.
+
+use std::time::Duration;
+
+use super::Hop;
+use super::session::estimate_return_hops;
+
+/// Minimum hops in a qTTL run
+const MIN_QTTL_HOPS: usize = 2;
+/// Minimum hops in a uTurn run (a single step of two is common by chance)
+const MIN_UTURN_HOPS: usize = 3;
+/// Return path growth between neighbours that suggests hidden routers
+const MIN_HIDDEN_JUMP: i16 = 3;
+/// RTT spread allowed across a plateau, as a fraction of its highest RTT
+const PLATEAU_SPREAD: f64 = 0.1;
+/// ...but never tighter than this
+const PLATEAU_FLOOR: Duration = Duration::from_millis(1);
+
+/// Signal behind a suspected tunnel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TunnelEvidence {
+    QuotedTtl,
+    UTurn,
+    RttPlateau,
+    ReturnLength,
+}
+
+impl TunnelEvidence {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::QuotedTtl => "qTTL",
+            Self::UTurn => "uTurn",
+            Self::RttPlateau => "RTT plateau",
+            Self::ReturnLength => "FRPLA",
+        }
+    }
+}
+
+/// Suspected MPLS tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelSpan {
+    /// First and last hop inside the tunnel. A hidden tunnel has no hops of
+    /// its own and is reported at the hop just after it.
+    pub first_ttl: u8,
+    pub last_ttl: u8,
+    /// Routers a hidden tunnel is thought to contain
+    pub hidden: Option<u8>,
+    pub evidence: Vec<TunnelEvidence>,
+}
+
+impl TunnelSpan {
+    pub fn contains(&self, ttl: u8) -> bool {
+        (self.first_ttl..=self.last_ttl).contains(&ttl)
+    }
+
+    /// One-line description for the hop detail and the report
+    pub fn describe(&self) -> String {
+        let evidence: Vec<_> = self.evidence.iter().map(|e| e.label()).collect();
+        let evidence = evidence.join(", ");
+        match self.hidden {
+            Some(n) => format!(
+                "~{} hidden hop{} before hop {} ({})",
+                n,
+                if n == 1 { "" } else { "s" },
+                self.last_ttl,
+                evidence
+            ),
+            None if self.first_ttl == self.last_ttl => {
+                format!("hop {} ({})", self.first_ttl, evidence)
+            }
+            None => format!("hops {}-{} ({})", self.first_ttl, self.last_ttl, evidence),
+        }
+    }
+}
+
+/// What the heuristics need from one responding hop
+struct Point {
+    ttl: u8,
+    /// Latest quoted IP TTL from a Time Exceeded reply
+    quoted: Option<u8>,
+    /// Return path length minus forward path length
+    delta: Option<i16>,
+    rtt: Option<Duration>,
+    labelled: bool,
+}
+
+impl Point {
+    fn from_hop(hop: &Hop) -> Option<Self> {
+        let stats = hop.primary_stats()?;
+        Some(Self {
+            ttl: hop.ttl,
+            quoted: hop.ttl_manip.as_ref().and_then(|t| t.last_quoted_ttl),
+            delta: stats
+                .return_ttl
+                .as_ref()
+                .map(|r| estimate_return_hops(r.current, false) as i16 - hop.ttl as i16),
+            rtt: (stats.received > 0).then(|| stats.avg_rtt()),
+            labelled: stats.mpls_labels.as_ref().is_some_and(|l| !l.is_empty()),
+        })
+    }
+
+    fn precedes(&self, next: &Point) -> bool {
+        self.ttl.checked_add(1) == Some(next.ttl)
+    }
+}
+
+/// Maximal runs of adjacent points where every consecutive pair satisfies
+/// `step`, as inclusive index ranges at least `min_len` long
+fn runs(
+    points: &[Point],
+    min_len: usize,
+    step: impl Fn(&Point, &Point) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=points.len() {
+        let linked = i < points.len()
+            && points[i - 1].precedes(&points[i])
+            && step(&points[i - 1], &points[i]);
+        if !linked {
+            if i - start >= min_len {
+                runs.push((start, i - 1));
+            }
+            start = i;
+        }
+    }
+    runs
+}
+
+/// Whether the RTTs from `points` sit within the plateau tolerance
+fn is_plateau(points: &[Point]) -> bool {
+    let rtts: Option<Vec<Duration>> = points.iter().map(|p| p.rtt).collect();
+    let Some(rtts) = rtts else {
+        return false;
+    };
+    let (Some(&lo), Some(&hi)) = (rtts.iter().min(), rtts.iter().max()) else {
+        return false;
+    };
+    hi - lo <= hi.mul_f64(PLATEAU_SPREAD).max(PLATEAU_FLOOR)
+}
+
+/// Suspected tunnels along the path, in TTL order.
+///
+/// `hops` must be in TTL order.
+pub fn infer_tunnels<'a>(hops: impl IntoIterator<Item = &'a Hop>) -> Vec<TunnelSpan> {
+    let points: Vec<Point> = hops
+        .into_iter()
+        .filter(|h| h.received > 0)
+        .filter_map(Point::from_hop)
+        .collect();
+
+    let qttl = runs(&points, MIN_QTTL_HOPS, |a, b| {
+        !a.labelled
+            && !b.labelled
+            && matches!((a.quoted, b.quoted), (Some(qa), Some(qb)) if qa >= 2 && qb == qa + 1)
+    });
+    let uturn = runs(&points, MIN_UTURN_HOPS, |a, b| {
+        !a.labelled
+            && !b.labelled
+            && matches!((a.delta, b.delta), (Some(da), Some(db)) if db >= 2 && da - db == 2)
+    });
+
+    // Merge overlapping runs, keeping every signal that found them
+    let mut found: Vec<(usize, usize, Vec<TunnelEvidence>)> = qttl
+        .into_iter()
+        .map(|(s, e)| (s, e, TunnelEvidence::QuotedTtl))
+        .chain(
+            uturn
+                .into_iter()
+                .map(|(s, e)| (s, e, TunnelEvidence::UTurn)),
+        )
+        .map(|(s, e, ev)| (s, e, vec![ev]))
+        .collect();
+    found.sort_by_key(|&(s, e, _)| (s, e));
+    let mut merged: Vec<(usize, usize, Vec<TunnelEvidence>)> = Vec::new();
+    for (s, e, ev) in found {
+        match merged.last_mut() {
+            Some(last) if s <= last.1 => {
+                last.1 = last.1.max(e);
+                last.2.extend(ev);
+            }
+            _ => merged.push((s, e, ev)),
+        }
+    }
+
+    let mut spans: Vec<TunnelSpan> = merged
+        .into_iter()
+        .map(|(s, e, mut evidence)| {
+            // The egress answers at the same latency as the LSRs before it
+            let end = (e + 1).min(points.len() - 1);
+            let with_egress = if points[e].precedes(&points[end]) {
+                end
+            } else {
+                e
+            };
+            if is_plateau(&points[s..=with_egress]) {
+                evidence.push(TunnelEvidence::RttPlateau);
+            }
+            evidence.sort();
+            evidence.dedup();
+            TunnelSpan {
+                first_ttl: points[s].ttl,
+                last_ttl: points[e].ttl,
+                hidden: None,
+                evidence,
+            }
+        })
+        .collect();
+
+    // Hidden tunnels: the return path grows by several hops in one step
+    // while the RTT goes up too
+    for pair in points.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if !a.precedes(b) || spans.iter().any(|s| s.contains(b.ttl)) {
+            continue;
+        }
+        let (Some(da), Some(db)) = (a.delta, b.delta) else {
+            continue;
+        };
+        let slower = matches!((a.rtt, b.rtt), (Some(ra), Some(rb)) if rb > ra);
+        if db - da >= MIN_HIDDEN_JUMP && slower {
+            spans.push(TunnelSpan {
+                first_ttl: b.ttl,
+                last_ttl: b.ttl,
+                hidden: Some((db - da).min(u8::MAX as i16) as u8),
+                evidence: vec![TunnelEvidence::ReturnLength],
+            });
+        }
+    }
+
+    spans.sort_by_key(|s| s.first_ttl);
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MplsLabel;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    /// Hop answered by 10.0.0.<ttl> with the given RTT, quoted TTL and
+    /// return path length (in hops)
+    fn hop(ttl: u8, rtt_ms: u64, quoted: u8, return_hops: u8) -> Hop {
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, ttl));
+        let mut hop = Hop::new(ttl);
+        hop.record_sent();
+        hop.record_response(ip, Duration::from_millis(rtt_ms));
+        hop.record_ttl_manip_check(quoted);
+        hop.record_return_ttl(ip, 255 - return_hops);
+        hop
+    }
+
+    #[test]
+    fn test_plain_path_has_no_tunnels() {
+        let hops: Vec<_> = (1..=6).map(|t| hop(t, 5 * t as u64, 1, t - 1)).collect();
+        assert!(infer_tunnels(&hops).is_empty());
+    }
+
+    #[test]
+    fn test_qttl_and_uturn() {
+        // LSRs at 3-5 quote 2, 3, 4; their replies turn at the egress (6),
+        // so the return paths are 6, 4 and 2 hops longer than forward
+        let hops = [
+            hop(1, 1, 1, 0),
+            hop(2, 5, 1, 1),
+            hop(3, 30, 2, 9),
+            hop(4, 30, 3, 8),
+            hop(5, 31, 4, 7),
+            hop(6, 31, 1, 5),
+            hop(7, 32, 1, 6),
+        ];
+        let tunnels = infer_tunnels(&hops);
+        assert_eq!(
+            tunnels,
+            vec![TunnelSpan {
+                first_ttl: 3,
+                last_ttl: 5,
+                hidden: None,
+                evidence: vec![
+                    TunnelEvidence::QuotedTtl,
+                    TunnelEvidence::UTurn,
+                    TunnelEvidence::RttPlateau
+                ],
+            }]
+        );
+        assert_eq!(tunnels[0].describe(), "hops 3-5 (qTTL, uTurn, RTT plateau)");
+        assert!(tunnels[0].contains(4));
+        assert!(!tunnels[0].contains(6));
+    }
+
+    #[test]
+    fn test_labelled_hops_are_skipped() {
+        let mut hops = [hop(3, 30, 2, 2), hop(4, 30, 3, 3)];
+        for hop in &mut hops {
+            let ip = hop.primary.unwrap();
+            let label = MplsLabel::from_bytes(&[0x00, 0x01, 0x01, 0x01]);
+            hop.responders.get_mut(&ip).unwrap().mpls_labels = Some(vec![label]);
+        }
+        assert!(infer_tunnels(&hops).is_empty());
+    }
+
+    #[test]
+    fn test_hidden_tunnel() {
+        // Hop 5's reply comes back over 4 routers the forward trace never saw
+        let hops = [
+            hop(3, 10, 1, 2),
+            hop(4, 12, 1, 3),
+            hop(5, 40, 1, 8),
+            hop(6, 41, 1, 9),
+        ];
+        let tunnels = infer_tunnels(&hops);
+        assert_eq!(tunnels.len(), 1);
+        assert_eq!(tunnels[0].hidden, Some(4));
+        assert_eq!(tunnels[0].describe(), "~4 hidden hops before hop 5 (FRPLA)");
+
+        // A longer return path without extra latency is just asymmetry
+        let hops = [hop(3, 10, 1, 2), hop(4, 9, 1, 8)];
+        assert!(infer_tunnels(&hops).is_empty());
+    }
+}
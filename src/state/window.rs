@@ -0,0 +1,267 @@
+//! Sliding-window statistics
+//!
+//! The cumulative stats on `Hop` and `ResponderStats` cover the whole
+//! session, so one spike early on inflates max and stddev forever. With
+//! `--stats-window` each hop and responder also keeps a sliding window of
+//! recent results in fixed time buckets; buckets that age out of the
+//! window are dropped, so memory stays bounded however fast probes go.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Buckets per window; the window edge moves in steps of window/BUCKETS
+const BUCKETS: u32 = 30;
+
+/// Results that landed in one bucket
+#[derive(Debug, Clone)]
+struct Bucket {
+    start: Instant,
+    received: u64,
+    timeouts: u64,
+    /// RTT sum and sum of squares, microseconds
+    sum: f64,
+    sum_sq: f64,
+    min: Duration,
+    max: Duration,
+    /// Sum and count of |RTT change| between consecutive replies, microseconds
+    jitter_sum: f64,
+    jitter_count: u64,
+}
+
+impl Bucket {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            received: 0,
+            timeouts: 0,
+            sum: 0.0,
+            sum_sq: 0.0,
+            min: Duration::MAX,
+            max: Duration::ZERO,
+            jitter_sum: 0.0,
+            jitter_count: 0,
+        }
+    }
+}
+
+/// Stats over the results of the last `length` of wall time
+#[derive(Debug, Clone)]
+pub struct SlidingWindow {
+    length: Duration,
+    bucket_len: Duration,
+    buckets: VecDeque<Bucket>,
+    last_rtt: Option<Duration>,
+}
+
+/// Snapshot of a sliding window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowSummary {
+    pub received: u64,
+    pub timeouts: u64,
+    pub min_rtt: Duration,
+    pub max_rtt: Duration,
+    pub avg_rtt: Duration,
+    pub stddev: Duration,
+    /// Mean |RTT change| between consecutive replies
+    pub jitter_avg: Duration,
+}
+
+impl WindowSummary {
+    /// Loss percentage over the window (completed probes only, like
+    /// `Hop::loss_pct`)
+    pub fn loss_pct(&self) -> f64 {
+        let completed = self.received + self.timeouts;
+        if completed == 0 {
+            0.0
+        } else {
+            (self.timeouts as f64 / completed as f64) * 100.0
+        }
+    }
+}
+
+impl SlidingWindow {
+    pub fn new(length: Duration) -> Self {
+        Self {
+            length,
+            bucket_len: (length / BUCKETS).max(Duration::from_millis(100)),
+            buckets: VecDeque::new(),
+            last_rtt: None,
+        }
+    }
+
+    pub fn length(&self) -> Duration {
+        self.length
+    }
+
+    pub fn record_response(&mut self, rtt: Duration) {
+        self.record_response_at(Instant::now(), rtt);
+    }
+
+    pub fn record_timeout(&mut self) {
+        self.record_timeout_at(Instant::now());
+    }
+
+    pub fn summary(&self) -> WindowSummary {
+        self.summary_at(Instant::now())
+    }
+
+    fn record_response_at(&mut self, now: Instant, rtt: Duration) {
+        let jitter = self
+            .last_rtt
+            .map(|last| (rtt.as_micros() as f64 - last.as_micros() as f64).abs());
+        self.last_rtt = Some(rtt);
+
+        let micros = rtt.as_micros() as f64;
+        let bucket = self.bucket_at(now);
+        bucket.received += 1;
+        bucket.sum += micros;
+        bucket.sum_sq += micros * micros;
+        bucket.min = bucket.min.min(rtt);
+        bucket.max = bucket.max.max(rtt);
+        if let Some(diff) = jitter {
+            bucket.jitter_sum += diff;
+            bucket.jitter_count += 1;
+        }
+    }
+
+    fn record_timeout_at(&mut self, now: Instant) {
+        self.bucket_at(now).timeouts += 1;
+    }
+
+    /// Current bucket, starting a new one and dropping expired ones as needed
+    fn bucket_at(&mut self, now: Instant) -> &mut Bucket {
+        while let Some(front) = self.buckets.front() {
+            if now.saturating_duration_since(front.start) < self.length + self.bucket_len {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+        let current = self
+            .buckets
+            .back()
+            .is_some_and(|b| now.saturating_duration_since(b.start) < self.bucket_len);
+        if !current {
+            self.buckets.push_back(Bucket::new(now));
+        }
+        self.buckets.back_mut().expect("bucket just pushed")
+    }
+
+    fn summary_at(&self, now: Instant) -> WindowSummary {
+        let live = self
+            .buckets
+            .iter()
+            .filter(|b| now.saturating_duration_since(b.start) < self.length);
+
+        let mut received = 0;
+        let mut timeouts = 0;
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        let (mut min, mut max) = (Duration::MAX, Duration::ZERO);
+        let (mut jitter_sum, mut jitter_count) = (0.0, 0);
+        for b in live {
+            received += b.received;
+            timeouts += b.timeouts;
+            sum += b.sum;
+            sum_sq += b.sum_sq;
+            min = min.min(b.min);
+            max = max.max(b.max);
+            jitter_sum += b.jitter_sum;
+            jitter_count += b.jitter_count;
+        }
+
+        let (avg, stddev) = if received > 0 {
+            let n = received as f64;
+            let mean = sum / n;
+            let variance = (sum_sq / n - mean * mean).max(0.0);
+            let stddev = if received < 2 { 0.0 } else { variance.sqrt() };
+            (mean, stddev)
+        } else {
+            (0.0, 0.0)
+        };
+        let jitter_avg = if jitter_count > 0 {
+            jitter_sum / jitter_count as f64
+        } else {
+            0.0
+        };
+
+        WindowSummary {
+            received,
+            timeouts,
+            min_rtt: if received > 0 { min } else { Duration::ZERO },
+            max_rtt: max,
+            avg_rtt: Duration::from_micros(avg as u64),
+            stddev: Duration::from_micros(stddev as u64),
+            jitter_avg: Duration::from_micros(jitter_avg as u64),
+        }
+    }
+}
+
+/// Compact label for a window length, e.g. "5m", "1h30m", "45s"
+pub fn window_label(length: Duration) -> String {
+    let secs = length.as_secs().max(1);
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    [(h, "h"), (m, "m"), (s, "s")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn test_window_summary() {
+        let start = Instant::now();
+        let mut window = SlidingWindow::new(Duration::from_secs(60));
+        window.record_response_at(start, ms(10));
+        window.record_response_at(start + ms(500), ms(20));
+        window.record_timeout_at(start + Duration::from_secs(5));
+        window.record_response_at(start + Duration::from_secs(10), ms(30));
+
+        let s = window.summary_at(start + Duration::from_secs(11));
+        assert_eq!(s.received, 3);
+        assert_eq!(s.timeouts, 1);
+        assert_eq!(s.loss_pct(), 25.0);
+        assert_eq!(s.min_rtt, ms(10));
+        assert_eq!(s.max_rtt, ms(30));
+        assert_eq!(s.avg_rtt, ms(20));
+        // Population stddev of 10, 20, 30
+        assert_eq!(s.stddev.as_micros(), 8164);
+        assert_eq!(s.jitter_avg, ms(10));
+    }
+
+    #[test]
+    fn test_window_label() {
+        assert_eq!(window_label(Duration::from_secs(300)), "5m");
+        assert_eq!(window_label(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(window_label(Duration::from_secs(90)), "1m30s");
+        assert_eq!(window_label(Duration::from_millis(500)), "1s");
+    }
+
+    #[test]
+    fn test_old_spike_ages_out() {
+        let start = Instant::now();
+        let mut window = SlidingWindow::new(Duration::from_secs(60));
+        window.record_response_at(start, ms(500));
+        for i in 1..=100 {
+            window.record_response_at(start + Duration::from_secs(i), ms(10));
+        }
+
+        let s = window.summary_at(start + Duration::from_secs(100));
+        assert_eq!(s.max_rtt, ms(10));
+        assert_eq!(s.stddev, Duration::ZERO);
+        assert!(s.received <= 62, "{}", s.received);
+        // Expired buckets are dropped, not just skipped
+        assert!(window.buckets.len() <= BUCKETS as usize + 2);
+
+        let empty = window.summary_at(start + Duration::from_secs(300));
+        assert_eq!(empty.received, 0);
+        assert_eq!(empty.min_rtt, Duration::ZERO);
+        assert_eq!(empty.loss_pct(), 0.0);
+    }
+}
@@ -0,0 +1,119 @@
+//! systemd service integration for `ttl agent`
+//!
+//! Under a `Type=notify` (or `notify-reload`) unit the agent reports
+//! readiness, reloads and status lines on `$NOTIFY_SOCKET`, and pings the
+//! watchdog when `WatchdogSec=` is set. Outside systemd the variables are
+//! unset and every call here does nothing.
+
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+/// Send a notification such as `READY=1` to the service manager, if any.
+/// Supervision is best-effort: a failed send never stops the agent.
+pub fn notify(state: &str) {
+    if let Some(path) = std::env::var_os("NOTIFY_SOCKET") {
+        let _ = notify_to(&path.to_string_lossy(), state);
+    }
+}
+
+/// Free-form status line shown by `systemctl status`
+pub fn status(text: &str) {
+    notify(&format!("STATUS={}", text.replace('\n', " ")));
+}
+
+/// Announce a config reload; follow with `READY=1` once it's done
+pub fn reloading() {
+    notify(&format!("RELOADING=1\nMONOTONIC_USEC={}", monotonic_usec()));
+}
+
+fn notify_to(path: &str, state: &str) -> io::Result<()> {
+    let socket = UnixDatagram::unbound()?;
+    match path.strip_prefix('@') {
+        Some(name) => send_abstract(&socket, name, state),
+        None => socket.send_to(state.as_bytes(), path).map(|_| ()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send_abstract(socket: &UnixDatagram, name: &str, state: &str) -> io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+    socket.send_to_addr(state.as_bytes(), &addr).map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_abstract(_socket: &UnixDatagram, _name: &str, _state: &str) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// CLOCK_MONOTONIC in microseconds, as systemd expects alongside RELOADING=1
+fn monotonic_usec() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: ts is a valid timespec for clock_gettime to fill in
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000 + ts.tv_nsec as u64 / 1_000
+}
+
+/// How often to ping the watchdog, if systemd asked for pings
+pub fn watchdog_interval() -> Option<Duration> {
+    watchdog_from(
+        std::env::var("WATCHDOG_USEC").ok().as_deref(),
+        std::env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+/// Half the watchdog timeout, as sd_watchdog_enabled(3) recommends.
+/// `WATCHDOG_PID` names the process the timeout is meant for; a child that
+/// inherited the environment must not ping on its parent's behalf.
+fn watchdog_from(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if let Some(pid) = pid
+        && pid.trim().parse::<u32>().ok() != Some(own_pid)
+    {
+        return None;
+    }
+    let usec: u64 = usec?.trim().parse().ok().filter(|&u| u > 0)?;
+    Some(Duration::from_micros(usec / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_interval() {
+        assert_eq!(
+            watchdog_from(Some("30000000"), None, 42),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            watchdog_from(Some("30000000"), Some("42"), 42),
+            Some(Duration::from_secs(15))
+        );
+        // Meant for another process
+        assert_eq!(watchdog_from(Some("30000000"), Some("7"), 42), None);
+        assert_eq!(watchdog_from(None, None, 42), None);
+        assert_eq!(watchdog_from(Some("0"), None, 42), None);
+        assert_eq!(watchdog_from(Some("soon"), None, 42), None);
+    }
+
+    #[test]
+    fn test_notify_to_socket() {
+        let dir = std::env::temp_dir().join(format!("ttl-notify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notify.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixDatagram::bind(&path).unwrap();
+
+        notify_to(path.to_str().unwrap(), "READY=1\nSTATUS=Listening").unwrap();
+        let mut buf = [0u8; 64];
+        let n = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1\nSTATUS=Listening");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
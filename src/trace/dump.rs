@@ -0,0 +1,225 @@
+//! Raw copies of replies ttl could not use (`--dump-unmatched`)
+//!
+//! When ttl shows loss that tcpdump says isn't there, the packets it threw
+//! away are the evidence. With dumping enabled the receiver keeps the raw
+//! bytes of every packet it could not correlate with a probe: stray packets
+//! that don't parse as a reply to ours, and replies to no probe in flight.
+//! The latest ones are kept in a small ring shown in the diagnostics panel,
+//! and with a file given each one is also appended there as a hex dump.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, const_mutex};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Process-wide dump, shared by the receiver thread and the TUI
+pub static DUMPS: PacketDumps = PacketDumps::new();
+
+/// Packets kept in memory
+const DUMP_CAPACITY: usize = 32;
+/// Bytes per hex dump line
+const BYTES_PER_LINE: usize = 16;
+
+/// Why the receiver set a packet aside
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpReason {
+    /// Not recognised as a reply to one of our probes
+    Stray,
+    /// A reply, but to no probe in flight (duplicate or very late)
+    Unmatched,
+}
+
+impl DumpReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DumpReason::Stray => "stray",
+            DumpReason::Unmatched => "unmatched",
+        }
+    }
+}
+
+/// One packet set aside by the receiver
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketDump {
+    pub at: DateTime<Utc>,
+    pub source: IpAddr,
+    pub reason: DumpReason,
+    pub data: Vec<u8>,
+}
+
+impl PacketDump {
+    /// e.g. "2026-10-15T09:12:03.120Z 192.0.2.1 stray, 56 bytes"
+    pub fn header(&self) -> String {
+        format!(
+            "{} {} {}, {} bytes",
+            self.at.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            self.source,
+            self.reason.as_str(),
+            self.data.len()
+        )
+    }
+
+    /// Header followed by the hex dump, as written to the dump file
+    pub fn to_text(&self) -> String {
+        let mut text = self.header();
+        text.push('\n');
+        for line in hex_lines(&self.data) {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// `offset  hex bytes  |ascii|`, 16 bytes a line
+pub fn hex_lines(data: &[u8]) -> Vec<String> {
+    data.chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!(
+                "{:04x}  {:<width$}  |{}|",
+                i * BYTES_PER_LINE,
+                hex.join(" "),
+                ascii,
+                width = BYTES_PER_LINE * 3 - 1
+            )
+        })
+        .collect()
+}
+
+pub struct PacketDumps {
+    enabled: AtomicBool,
+    /// Packets set aside since start, including those no longer kept
+    seen: AtomicU64,
+    ring: Mutex<VecDeque<PacketDump>>,
+    file: Mutex<Option<File>>,
+}
+
+impl PacketDumps {
+    const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            seen: AtomicU64::new(0),
+            ring: const_mutex(VecDeque::new()),
+            file: const_mutex(None),
+        }
+    }
+
+    /// Start keeping packets, also appending them to `path` if given
+    pub fn enable(&self, path: Option<&Path>) -> Result<()> {
+        if let Some(path) = path {
+            let file = File::options()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("opening {}", path.display()))?;
+            *self.file.lock() = Some(file);
+        }
+        self.enabled.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, source: IpAddr, reason: DumpReason, data: &[u8]) {
+        if !self.enabled() {
+            return;
+        }
+        let dump = PacketDump {
+            at: Utc::now(),
+            source,
+            reason,
+            data: data.to_vec(),
+        };
+        self.seen.fetch_add(1, Ordering::Relaxed);
+        if let Some(file) = self.file.lock().as_mut() {
+            // Best effort: a full disk must not stop the receiver
+            let _ = file.write_all(dump.to_text().as_bytes());
+        }
+        let mut ring = self.ring.lock();
+        if ring.len() == DUMP_CAPACITY {
+            ring.pop_front();
+        }
+        ring.push_back(dump);
+    }
+
+    /// Packets set aside so far, and the ones still kept (oldest first)
+    pub fn snapshot(&self) -> (u64, Vec<PacketDump>) {
+        let ring = self.ring.lock();
+        (
+            self.seen.load(Ordering::Relaxed),
+            ring.iter().cloned().collect(),
+        )
+    }
+
+    /// Write the kept packets to `ttl-dump-<time>.txt` in the current
+    /// directory and return the file name
+    pub fn save(&self) -> Result<String> {
+        let filename = format!("ttl-dump-{}.txt", Utc::now().format("%Y%m%d-%H%M%S"));
+        let (_, dumps) = self.snapshot();
+        let text: String = dumps.iter().map(PacketDump::to_text).collect();
+        std::fs::write(&filename, text).with_context(|| format!("writing {}", filename))?;
+        Ok(filename)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_lines() {
+        let data: Vec<u8> = (0x40..0x40 + 20).collect();
+        let lines = hex_lines(&data);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "0000  40 41 42 43 44 45 46 47 48 49 4a 4b 4c 4d 4e 4f  |@ABCDEFGHIJKLMNO|"
+        );
+        // A short last line is padded so the ASCII column lines up
+        assert_eq!(
+            lines[1],
+            "0010  50 51 52 53                                      |PQRS|"
+        );
+        assert_eq!(
+            hex_lines(&[0x45, 0x00, 0x0a])[0].split('|').nth(1),
+            Some("E..")
+        );
+    }
+
+    #[test]
+    fn test_ring_bounded_and_disabled_by_default() {
+        let dumps = PacketDumps::new();
+        let source: IpAddr = "192.0.2.1".parse().unwrap();
+        dumps.record(source, DumpReason::Stray, &[1, 2, 3]);
+        assert_eq!(dumps.snapshot().0, 0);
+
+        dumps.enable(None).unwrap();
+        for i in 0..DUMP_CAPACITY + 5 {
+            dumps.record(source, DumpReason::Unmatched, &[i as u8]);
+        }
+        let (seen, kept) = dumps.snapshot();
+        assert_eq!(seen, DUMP_CAPACITY as u64 + 5);
+        assert_eq!(kept.len(), DUMP_CAPACITY);
+        assert_eq!(kept[0].data, [5]);
+        assert!(kept[0].header().ends_with("192.0.2.1 unmatched, 1 bytes"));
+    }
+}
@@ -1,54 +1,102 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use parking_lot::RwLock;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
-use crate::config::{Config, ProbeProtocol};
+use crate::config::{Config, FlowPorts, ProbeProtocol};
 use crate::probe::{
     DEFAULT_PAYLOAD_SIZE, DEFAULT_UDP_PAYLOAD, ICMP_HEADER_SIZE, InterfaceInfo, TCP_HEADER_SIZE,
-    bind_to_source_ip, build_echo_request, build_tcp_syn_sized, build_udp_payload_sized,
-    create_send_socket_with_interface, create_tcp_socket_with_interface, create_udp_dgram_socket,
-    create_udp_dgram_socket_bound_full, create_udp_dgram_socket_bound_with_interface,
-    enable_recv_ttl, get_identifier, get_local_addr_with_interface, parse_icmp_response,
-    recv_icmp_with_ttl, send_icmp, send_tcp_probe, send_udp_probe, set_dont_fragment, set_dscp,
-    set_ttl,
+    apply_buffer_sizes, bind_to_source_ip, build_echo_request, build_tcp_syn_sized,
+    build_udp_probe, create_send_socket_with_interface, create_tcp_socket_with_interface,
+    create_udp_dgram_socket, create_udp_dgram_socket_bound_full,
+    create_udp_dgram_socket_bound_with_interface, echo_payload_nonce, enable_recv_ttl,
+    get_local_addr_with_interface, parse_icmp_response, parse_syn_ack, recv_icmp_with_ttl,
+    send_icmp, send_tcp_probe, send_udp_probe, set_dont_fragment, set_tos, set_ttl, udp_checksum,
 };
-use crate::state::{IcmpResponseType, PmtudPhase, ProbeId, Session};
+use crate::state::{EventKind, FlapDamping, IcmpResponseType, PmtudPhase, ProbeId, Session};
+use crate::trace::identity::{ProbeIdentity, identifier_rotated};
 use crate::trace::pending::{PendingMap, PendingProbe};
+use crate::trace::scheduler::{ProbeBudget, RoundSlot};
 
 /// The probe engine sends ICMP probes at configured intervals
 pub struct ProbeEngine {
     config: Config,
     target: IpAddr,
-    identifier: u16,
+    identity: Arc<ProbeIdentity>,
     state: Arc<RwLock<Session>>,
     pending: PendingMap,
     cancel: CancellationToken,
     interface: Option<InterfaceInfo>,
+    /// When this engine's rounds run relative to other targets
+    slot: RoundSlot,
+    /// Global probes-per-second budget shared with other engines
+    budget: Option<Arc<ProbeBudget>>,
+    /// When --duration runs out
+    deadline: Option<Instant>,
 }
 
 impl ProbeEngine {
     pub fn new(
         config: Config,
         target: IpAddr,
+        identity: Arc<ProbeIdentity>,
         state: Arc<RwLock<Session>>,
         pending: PendingMap,
         cancel: CancellationToken,
         interface: Option<InterfaceInfo>,
     ) -> Self {
+        let slot = RoundSlot::immediate(config.interval);
+        let deadline = config.duration.map(|d| Instant::now() + d);
         Self {
             config,
             target,
-            identifier: get_identifier(),
+            identity,
             state,
             pending,
             cancel,
             interface,
+            slot,
+            budget: None,
+            deadline,
         }
     }
 
+    /// Run rounds in `slot` and draw every probe from the shared `budget`
+    pub fn with_schedule(mut self, slot: RoundSlot, budget: Option<Arc<ProbeBudget>>) -> Self {
+        self.slot = slot;
+        self.budget = budget;
+        self
+    }
+
+    /// Highest TTL to probe in round `round`, and with --adaptive each
+    /// TTL's pace (rounds between probes, indexed by TTL - 1)
+    fn round_plan(&self, round: u64) -> (u8, Vec<u64>) {
+        if !self.config.adaptive {
+            return (self.state.read().probe_ceiling(round), Vec::new());
+        }
+        let mut state = self.state.write();
+        state.update_pacing();
+        (state.probe_ceiling(round), state.pace_periods())
+    }
+
+    /// Whether `ttl` sits out round `round` (--ttl-decay, --adaptive)
+    fn skips(&self, ttl: u8, round: u64, pace: &[u64]) -> bool {
+        let paced = pace.get(ttl as usize - 1).copied().unwrap_or(1);
+        round % (self.config.ttl_period(ttl) * paced) != 0
+    }
+
+    /// Whether -c rounds are done or the --duration deadline has passed
+    fn finished(&self, rounds_completed: u64) -> bool {
+        self.config
+            .count
+            .is_some_and(|count| rounds_completed >= count)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
     /// Get rate limit delay between probes (if rate is configured)
     fn rate_delay(&self) -> Option<Duration> {
         self.config.rate.and_then(|rate| {
@@ -60,6 +108,29 @@ impl ProbeEngine {
         })
     }
 
+    /// Follow an interval changed at runtime (config reload) by rescaling
+    /// this engine's round slot and restarting its ticker
+    fn refresh_interval(&mut self, ticker: &mut tokio::time::Interval) {
+        let current = self.state.read().config.interval;
+        if current != self.config.interval {
+            self.slot = self.slot.rescaled(self.config.interval, current);
+            self.config.interval = current;
+            *ticker = self.slot.resumed();
+        }
+    }
+
+    /// Wait for a send slot from the global probe budget, if one is set
+    async fn wait_for_budget(&self) {
+        if let Some(ref budget) = self.budget {
+            let wait = budget.acquire().await;
+            if !wait.is_zero() {
+                self.state
+                    .write()
+                    .record_throttle(budget.rate(), budget.burst(), wait);
+            }
+        }
+    }
+
     /// Apply rate limiting delay if configured
     async fn apply_rate_limit(&self) {
         if let Some(delay) = self.rate_delay() {
@@ -93,7 +164,7 @@ impl ProbeEngine {
             // Test that we can create a bound socket with interface binding
             create_udp_dgram_socket_bound_with_interface(
                 ipv6,
-                self.config.src_port_base,
+                self.config.flow_ports().port(0),
                 self.interface.as_ref(),
             )
             .is_ok()
@@ -116,12 +187,27 @@ impl ProbeEngine {
         self.run_tcp().await
     }
 
+    /// Apply --send-buffer to a probe socket and record the size granted
+    fn size_send_buffer(&self, socket: &socket2::Socket) {
+        match apply_buffer_sizes(socket, None, self.config.send_buffer) {
+            Ok(buffers) => {
+                let mut state = self.state.write();
+                state
+                    .socket_stats
+                    .get_or_insert_with(Default::default)
+                    .send_buffer = Some(buffers.send);
+            }
+            Err(e) => eprintln!("Failed to set send buffer size: {}", e),
+        }
+    }
+
     /// Run ICMP probing mode
-    async fn run_icmp(self) -> Result<()> {
+    async fn run_icmp(mut self) -> Result<()> {
         let ipv6 = self.target.is_ipv6();
         let socket_info = create_send_socket_with_interface(ipv6, self.interface.as_ref())?;
         let socket = socket_info.socket;
         let is_dgram = socket_info.is_dgram;
+        self.size_send_buffer(&socket);
 
         // Linux-only: Enable hop limit reception on send socket for Echo Reply polling
         // This allows asymmetry detection to work for the destination hop
@@ -158,8 +244,7 @@ impl ProbeEngine {
         // PMTUD uses separate seq counter; collision prevented by is_pmtud flag in pending key
         let mut pmtud_seq: u8 = 0;
         let mut rounds_completed: u64 = 0;
-        let mut interval = tokio::time::interval(self.config.interval);
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut interval = self.slot.interval();
 
         loop {
             tokio::select! {
@@ -167,6 +252,14 @@ impl ProbeEngine {
                     break;
                 }
                 _ = interval.tick() => {
+                    // Stop after -c rounds or once --duration has elapsed
+                    // (wall clock, so a paused session still ends on time)
+                    if self.finished(rounds_completed) {
+                        // Signal completion
+                        self.cancel.cancel();
+                        break;
+                    }
+
                     // Check if paused
                     {
                         let state = self.state.read();
@@ -174,28 +267,23 @@ impl ProbeEngine {
                             continue;
                         }
                     }
+                    self.refresh_interval(&mut interval);
 
-                    // Check probe round limit (-c flag means number of probe rounds)
-                    if let Some(count) = self.config.count
-                        && rounds_completed >= count
-                    {
-                        // Signal completion
-                        self.cancel.cancel();
-                        break;
-                    }
-
-                    // Determine max TTL to probe (stop at destination if known)
-                    let max_probe_ttl = {
-                        let state = self.state.read();
-                        state.dest_ttl.unwrap_or(self.config.max_ttl)
-                    };
+                    // Determine max TTL to probe (stop at destination if known, or
+                    // short of a run of silent hops with --gap-limit)
+                    let (max_probe_ttl, pace) = self.round_plan(rounds_completed);
 
                     // Send probes for TTLs up to the destination
-                    for ttl in 1..=max_probe_ttl {
+                    for ttl in self.config.first_ttl..=max_probe_ttl {
                         // Always probe all TTLs up to destination (max_probe_ttl already limits range)
                         // Previously we skipped non-responding hops after destination was found,
                         // but this prevented detecting hops that recover from rate limiting
                         // and caused sent counters to freeze on non-responding hops.
+                        // With --ttl-decay, distant TTLs sit out most rounds; with
+                        // --adaptive, lossy hops are probed less often
+                        if self.skips(ttl, rounds_completed, &pace) {
+                            continue;
+                        }
 
                         let probe_id = ProbeId::new(ttl, seq);
 
@@ -213,8 +301,9 @@ impl ProbeEngine {
                         };
 
                         let packet = build_echo_request(
-                            self.identifier,
+                            self.identity.identifier(),
                             probe_id.to_sequence(),
+                            self.identity.nonce(),
                             payload_size,
                             self.target.is_ipv6(),
                             ipv6_addrs,
@@ -226,13 +315,14 @@ impl ProbeEngine {
                             continue;
                         }
 
-                        // Set DSCP if configured
-                        if let Some(dscp) = self.config.dscp
-                            && let Err(e) = set_dscp(&socket, dscp, self.target.is_ipv6())
+                        // Set DSCP/ECN marking if configured
+                        if let Some(tos) = self.config.tos()
+                            && let Err(e) = set_tos(&socket, tos, self.target.is_ipv6())
                         {
-                            eprintln!("Failed to set DSCP {}: {}", dscp, e);
+                            eprintln!("Failed to set ToS {:#04x}: {}", tos, e);
                         }
 
+                        self.wait_for_budget().await;
                         let sent_at = Instant::now();
 
                         // Register pending BEFORE sending to prevent race with fast responses
@@ -245,6 +335,7 @@ impl ProbeEngine {
                                 target: self.target,
                                 flow_id,
                                 original_src_port: None, // ICMP has no source port
+                                original_checksum: None,
                                 packet_size: None,
                             });
                         }
@@ -270,6 +361,14 @@ impl ProbeEngine {
                         self.apply_rate_limit().await;
                     }
 
+                    // DF sweep: one probe of the next ladder size to every hop
+                    // probed this round, numbered like PMTUD probes
+                    if self.config.df_sweep
+                        && self.send_df_sweep_icmp(&socket, max_probe_ttl, pmtud_seq, src_ip).await
+                    {
+                        pmtud_seq = pmtud_seq.wrapping_add(1);
+                    }
+
                     // PMTUD: Send additional probe at destination TTL with current test size
                     // Uses separate pmtud_seq counter to avoid ProbeId collision with normal probes
                     if let Some(dest_ttl) = self.check_pmtud_ready()
@@ -290,6 +389,7 @@ impl ProbeEngine {
 
                     seq = seq.wrapping_add(1);
                     rounds_completed += 1;
+                    self.state.write().record_path_signature();
                 }
             }
         }
@@ -298,15 +398,16 @@ impl ProbeEngine {
     }
 
     /// Run UDP probing mode
-    async fn run_udp(self) -> Result<()> {
+    async fn run_udp(mut self) -> Result<()> {
         let ipv6 = self.target.is_ipv6();
         let num_flows = self.config.flows;
+        let flow_ports = self.config.flow_ports();
 
         // Create sockets for each flow (Paris/Dublin traceroute multi-flow support)
         // Each socket is bound to a different source port for flow identification
         let mut sockets = Vec::with_capacity(num_flows as usize);
         for flow_id in 0..num_flows {
-            let src_port = self.config.src_port_base + (flow_id as u16);
+            let src_port = flow_ports.port(flow_id);
             let socket = create_udp_dgram_socket_bound_full(
                 ipv6,
                 src_port,
@@ -314,23 +415,29 @@ impl ProbeEngine {
                 self.config.source_ip,
             )?;
 
-            // Set DSCP if configured (set once per socket)
-            if let Some(dscp) = self.config.dscp
-                && let Err(e) = set_dscp(&socket, dscp, ipv6)
+            // Set DSCP/ECN marking if configured (set once per socket)
+            if let Some(tos) = self.config.tos()
+                && let Err(e) = set_tos(&socket, tos, ipv6)
             {
-                eprintln!("Failed to set DSCP {} on flow {}: {}", dscp, flow_id, e);
+                eprintln!("Failed to set ToS {:#04x} on flow {}: {}", tos, flow_id, e);
             }
+            self.size_send_buffer(&socket);
 
             sockets.push(socket);
         }
 
         // Base port for UDP probes (classic traceroute)
         let base_port = self.config.port.unwrap_or(33434);
+        // Source address the kernel will use: the checksum encoding steers by
+        // it, and NAT detection compares quoted checksums with what we sent
+        let src_ip = self
+            .config
+            .source_ip
+            .unwrap_or_else(|| get_local_addr_with_interface(self.target, self.interface.as_ref()));
 
         let mut seq: u8 = 0;
         let mut rounds_completed: u64 = 0;
-        let mut interval = tokio::time::interval(self.config.interval);
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut interval = self.slot.interval();
 
         loop {
             tokio::select! {
@@ -338,6 +445,12 @@ impl ProbeEngine {
                     break;
                 }
                 _ = interval.tick() => {
+                    // Stop after -c rounds or once --duration has elapsed
+                    if self.finished(rounds_completed) {
+                        self.cancel.cancel();
+                        break;
+                    }
+
                     // Check if paused
                     {
                         let state = self.state.read();
@@ -345,28 +458,21 @@ impl ProbeEngine {
                             continue;
                         }
                     }
-
-                    // Check probe round limit (-c flag means number of probe rounds)
-                    if let Some(count) = self.config.count
-                        && rounds_completed >= count
-                    {
-                        self.cancel.cancel();
-                        break;
-                    }
+                    self.refresh_interval(&mut interval);
 
                     // Determine max TTL to probe
-                    let max_probe_ttl = {
-                        let state = self.state.read();
-                        state.dest_ttl.unwrap_or(self.config.max_ttl)
-                    };
+                    let (max_probe_ttl, pace) = self.round_plan(rounds_completed);
 
                     // Send probes for each flow and each TTL (Paris/Dublin traceroute)
                     for flow_id in 0..num_flows {
                         let socket = &sockets[flow_id as usize];
-                        let src_port = self.config.src_port_base + (flow_id as u16);
+                        let src_port = flow_ports.port(flow_id);
 
-                        for ttl in 1..=max_probe_ttl {
+                        for ttl in self.config.first_ttl..=max_probe_ttl {
                             // Always probe all TTLs up to destination (see ICMP loop comment)
+                            if self.skips(ttl, rounds_completed, &pace) {
+                                continue;
+                            }
 
                             let probe_id = ProbeId::new(ttl, seq);
 
@@ -377,8 +483,6 @@ impl ProbeEngine {
                             let payload_size = self.config.packet_size
                                 .map(|s| (s as usize).saturating_sub(ip_header_size + UDP_HEADER_SIZE))
                                 .unwrap_or(DEFAULT_UDP_PAYLOAD);
-                            let payload = build_udp_payload_sized(probe_id, payload_size);
-
                             // Set TTL before sending
                             if let Err(e) = set_ttl(socket, ttl, ipv6) {
                                 eprintln!("Failed to set TTL {}: {}", ttl, e);
@@ -391,7 +495,11 @@ impl ProbeEngine {
                             } else {
                                 base_port + (ttl as u16)
                             };
+                            let (src, dst) = (SocketAddr::new(src_ip, src_port), SocketAddr::new(self.target, dst_port));
+                            let payload = build_udp_probe(self.config.udp_id, probe_id, payload_size, src, dst);
+                            let checksum = (!src_ip.is_unspecified()).then(|| udp_checksum(src, dst, &payload));
 
+                            self.wait_for_budget().await;
                             let sent_at = Instant::now();
 
                             // Register pending BEFORE sending (key includes flow_id and target for multi-flow/multi-target)
@@ -402,6 +510,7 @@ impl ProbeEngine {
                                     target: self.target,
                                     flow_id,
                                     original_src_port: Some(src_port), // For NAT detection
+                                    original_checksum: checksum,
                                     packet_size: None,
                                 });
                             }
@@ -417,7 +526,7 @@ impl ProbeEngine {
                                 let mut state = self.state.write();
                                 if let Some(hop) = state.hop_mut(ttl) {
                                     hop.record_sent();
-                                    hop.record_flow_sent(flow_id);
+                                    hop.record_flow_sent_from(flow_id, src_port);
                                 }
                                 state.total_sent += 1;
                             }
@@ -429,6 +538,7 @@ impl ProbeEngine {
 
                     seq = seq.wrapping_add(1);
                     rounds_completed += 1;
+                    self.state.write().record_path_signature();
                 }
             }
         }
@@ -437,23 +547,25 @@ impl ProbeEngine {
     }
 
     /// Run TCP SYN probing mode
-    async fn run_tcp(self) -> Result<()> {
+    async fn run_tcp(mut self) -> Result<()> {
         let ipv6 = self.target.is_ipv6();
         let socket = create_tcp_socket_with_interface(ipv6, self.interface.as_ref())?;
+        self.size_send_buffer(&socket);
 
         // Bind to specific source IP if configured
         if let Some(source_ip) = self.config.source_ip {
             bind_to_source_ip(&socket, source_ip)?;
         }
 
-        // Set DSCP if configured
-        if let Some(dscp) = self.config.dscp
-            && let Err(e) = set_dscp(&socket, dscp, ipv6)
+        // Set DSCP/ECN marking if configured
+        if let Some(tos) = self.config.tos()
+            && let Err(e) = set_tos(&socket, tos, ipv6)
         {
-            eprintln!("Failed to set DSCP {}: {}", dscp, e);
+            eprintln!("Failed to set ToS {:#04x}: {}", tos, e);
         }
 
         let num_flows = self.config.flows;
+        let flow_ports = self.config.flow_ports();
 
         // Base port for TCP probes (default: 80)
         let base_port = self.config.port.unwrap_or(80);
@@ -466,8 +578,7 @@ impl ProbeEngine {
 
         let mut seq: u8 = 0;
         let mut rounds_completed: u64 = 0;
-        let mut interval = tokio::time::interval(self.config.interval);
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut interval = self.slot.interval();
 
         loop {
             tokio::select! {
@@ -475,6 +586,12 @@ impl ProbeEngine {
                     break;
                 }
                 _ = interval.tick() => {
+                    // Stop after -c rounds or once --duration has elapsed
+                    if self.finished(rounds_completed) {
+                        self.cancel.cancel();
+                        break;
+                    }
+
                     // Check if paused
                     {
                         let state = self.state.read();
@@ -482,28 +599,21 @@ impl ProbeEngine {
                             continue;
                         }
                     }
-
-                    // Check probe round limit (-c flag means number of probe rounds)
-                    if let Some(count) = self.config.count
-                        && rounds_completed >= count
-                    {
-                        self.cancel.cancel();
-                        break;
-                    }
+                    self.refresh_interval(&mut interval);
 
                     // Determine max TTL to probe
-                    let max_probe_ttl = {
-                        let state = self.state.read();
-                        state.dest_ttl.unwrap_or(self.config.max_ttl)
-                    };
+                    let (max_probe_ttl, pace) = self.round_plan(rounds_completed);
 
                     // Send probes for each flow and each TTL (Paris/Dublin traceroute)
                     for flow_id in 0..num_flows {
                         // Source port varies per flow for flow identification
-                        let src_port = self.config.src_port_base + (flow_id as u16);
+                        let src_port = flow_ports.port(flow_id);
 
-                        for ttl in 1..=max_probe_ttl {
+                        for ttl in self.config.first_ttl..=max_probe_ttl {
                             // Always probe all TTLs up to destination (see ICMP loop comment)
+                            if self.skips(ttl, rounds_completed, &pace) {
+                                continue;
+                            }
 
                             let probe_id = ProbeId::new(ttl, seq);
 
@@ -523,6 +633,7 @@ impl ProbeEngine {
 
                             // Build TCP SYN packet with flow-specific source port
                             let packet = build_tcp_syn_sized(probe_id, src_port, dst_port, src_ip, self.target, payload_size);
+                            let checksum = (!src_ip.is_unspecified()).then(|| u16::from_be_bytes([packet[16], packet[17]]));
 
                             // Set TTL before sending
                             if let Err(e) = set_ttl(&socket, ttl, self.target.is_ipv6()) {
@@ -530,6 +641,7 @@ impl ProbeEngine {
                                 continue;
                             }
 
+                            self.wait_for_budget().await;
                             let sent_at = Instant::now();
 
                             // Register pending BEFORE sending (key includes flow_id and target for multi-flow/multi-target)
@@ -540,6 +652,7 @@ impl ProbeEngine {
                                     target: self.target,
                                     flow_id,
                                     original_src_port: Some(src_port), // For NAT detection
+                                    original_checksum: checksum,
                                     packet_size: None,
                                 });
                             }
@@ -555,7 +668,7 @@ impl ProbeEngine {
                                 let mut state = self.state.write();
                                 if let Some(hop) = state.hop_mut(ttl) {
                                     hop.record_sent();
-                                    hop.record_flow_sent(flow_id);
+                                    hop.record_flow_sent_from(flow_id, src_port);
                                 }
                                 state.total_sent += 1;
                             }
@@ -567,6 +680,8 @@ impl ProbeEngine {
 
                     seq = seq.wrapping_add(1);
                     rounds_completed += 1;
+                    self.state.write().record_path_signature();
+                    self.poll_syn_ack(&socket, &flow_ports, ipv6);
                 }
             }
         }
@@ -574,6 +689,28 @@ impl ProbeEngine {
         Ok(())
     }
 
+    /// Drain the raw TCP socket for the destination's SYN-ACKs and record
+    /// the MSS they announce (a path MTU hint). Linux hands raw TCP sockets
+    /// a copy of every incoming segment; elsewhere there is nothing to read.
+    fn poll_syn_ack(&self, socket: &socket2::Socket, flow_ports: &FlowPorts, ipv6: bool) {
+        let _ = socket.set_nonblocking(true);
+        let mut buffer = [0u8; 1500];
+        while let Ok(recv_result) = recv_icmp_with_ttl(socket, &mut buffer, ipv6) {
+            if recv_result.source != self.target {
+                continue;
+            }
+            let Some(syn_ack) = parse_syn_ack(&buffer[..recv_result.len], ipv6) else {
+                continue;
+            };
+            if flow_ports.flow(syn_ack.dst_port).is_none() {
+                continue;
+            }
+            if let Some(mss) = syn_ack.mss {
+                self.state.write().mtu_hints.record_mss(mss);
+            }
+        }
+    }
+
     // =========================================================================
     // PMTUD (Path MTU Discovery) support
     // =========================================================================
@@ -626,10 +763,102 @@ impl ProbeEngine {
         seq: u8,
         src_ip: IpAddr,
     ) -> bool {
-        let probe_id = ProbeId::new(dest_ttl, seq);
+        match self
+            .send_df_probe_icmp(socket, dest_ttl, packet_size, seq, src_ip)
+            .await
+        {
+            Ok(()) => {
+                // Record probe sent
+                let mut state = self.state.write();
+                if let Some(hop) = state.hop_mut(dest_ttl) {
+                    hop.record_sent();
+                }
+                state.total_sent += 1;
+                true
+            }
+            Err(e) if is_emsgsize(&e) => {
+                // Packet too large for local interface: clamp PMTUD max to current size - 1
+                let mut state = self.state.write();
+                if let Some(ref mut pmtud) = state.pmtud {
+                    pmtud.max_size = packet_size.saturating_sub(1);
+                    pmtud.successes = 0;
+                    pmtud.failures = 0;
+                    // Recalculate current size
+                    if pmtud.is_converged() {
+                        pmtud.discovered_mtu = Some(pmtud.min_size);
+                        pmtud.phase = PmtudPhase::Complete;
+                    } else {
+                        pmtud.current_size = pmtud.next_probe_size();
+                    }
+                }
+                false
+            }
+            Err(e) => {
+                eprintln!("PMTUD: {:#}", e);
+                false
+            }
+        }
+    }
+
+    /// Send this round's `--df-sweep` probes: one of the next ladder size to
+    /// each TTL up to `max_ttl`. They only feed the sweep, so hop counters
+    /// and loss are left alone. Returns true if any probe was sent.
+    async fn send_df_sweep_icmp(
+        &self,
+        socket: &socket2::Socket,
+        max_ttl: u8,
+        seq: u8,
+        src_ip: IpAddr,
+    ) -> bool {
+        let Some(size) = self
+            .state
+            .write()
+            .df_sweep
+            .as_mut()
+            .and_then(|s| s.next_size())
+        else {
+            return false;
+        };
+        let mut sent = false;
+        for ttl in self.config.first_ttl..=max_ttl {
+            match self
+                .send_df_probe_icmp(socket, ttl, size, seq, src_ip)
+                .await
+            {
+                Ok(()) => {
+                    self.state.write().total_sent += 1;
+                    sent = true;
+                    self.apply_rate_limit().await;
+                }
+                Err(e) => {
+                    if is_emsgsize(&e) {
+                        if let Some(ref mut sweep) = self.state.write().df_sweep {
+                            sweep.record_local_reject(size);
+                        }
+                    } else {
+                        eprintln!("DF sweep: {:#}", e);
+                    }
+                    break;
+                }
+            }
+        }
+        sent
+    }
+
+    /// Send an ICMP echo of `packet_size` bytes (IP + ICMP headers
+    /// included) with Don't Fragment set. It is registered as a sized probe
+    /// so the receiver hands its answer to PMTUD or the DF sweep.
+    async fn send_df_probe_icmp(
+        &self,
+        socket: &socket2::Socket,
+        ttl: u8,
+        packet_size: u16,
+        seq: u8,
+        src_ip: IpAddr,
+    ) -> Result<()> {
+        let probe_id = ProbeId::new(ttl, seq);
 
         // Calculate payload size from total packet size
-        // packet_size includes IP + ICMP headers
         let ip_header_size: usize = if self.target.is_ipv6() { 40 } else { 20 };
         let payload_size = (packet_size as usize).saturating_sub(ip_header_size + ICMP_HEADER_SIZE);
 
@@ -640,93 +869,50 @@ impl ProbeEngine {
         };
 
         let packet = build_echo_request(
-            self.identifier,
+            self.identity.identifier(),
             probe_id.to_sequence(),
+            self.identity.nonce(),
             payload_size,
             self.target.is_ipv6(),
             ipv6_addrs,
         );
 
-        // Set TTL
-        if let Err(e) = set_ttl(socket, dest_ttl, self.target.is_ipv6()) {
-            eprintln!("PMTUD: Failed to set TTL {}: {}", dest_ttl, e);
-            return false;
-        }
-
-        // Set Don't Fragment flag (critical for PMTUD)
-        if let Err(e) = set_dont_fragment(socket, self.target.is_ipv6()) {
-            eprintln!("PMTUD: Failed to set DF flag: {}", e);
-            return false;
-        }
+        set_ttl(socket, ttl, self.target.is_ipv6())
+            .with_context(|| format!("Failed to set TTL {}", ttl))?;
+        // Don't Fragment is the whole point of a sized probe
+        set_dont_fragment(socket, self.target.is_ipv6()).context("Failed to set DF flag")?;
 
-        // Set DSCP if configured
-        if let Some(dscp) = self.config.dscp
-            && let Err(e) = set_dscp(socket, dscp, self.target.is_ipv6())
+        // Set DSCP/ECN marking if configured
+        if let Some(tos) = self.config.tos()
+            && let Err(e) = set_tos(socket, tos, self.target.is_ipv6())
         {
-            eprintln!("PMTUD: Failed to set DSCP: {}", e);
+            eprintln!("Failed to set ToS {:#04x}: {}", tos, e);
         }
 
+        self.wait_for_budget().await;
         let sent_at = Instant::now();
         let flow_id = 0u8;
 
         // Register pending probe with packet_size for correlation
         // Use is_pmtud=true to distinguish from normal probes with same ProbeId
-        {
-            let mut pending = self.pending.write();
-            pending.insert(
-                (probe_id, flow_id, self.target, true),
-                PendingProbe {
-                    sent_at,
-                    target: self.target,
-                    flow_id,
-                    original_src_port: None,
-                    packet_size: Some(packet_size),
-                },
-            );
-        }
-
-        // Send the probe
-        match send_icmp(socket, &packet, self.target) {
-            Ok(_) => {
-                // Record probe sent
-                let mut state = self.state.write();
-                if let Some(hop) = state.hop_mut(dest_ttl) {
-                    hop.record_sent();
-                }
-                state.total_sent += 1;
-                true
-            }
-            Err(e) => {
-                // Remove pending entry
-                self.pending
-                    .write()
-                    .remove(&(probe_id, flow_id, self.target, true));
-
-                // Check for EMSGSIZE - packet too large for local interface
-                if let Some(io_err) = e.downcast_ref::<std::io::Error>()
-                    && io_err.raw_os_error() == Some(libc::EMSGSIZE)
-                {
-                    // Clamp PMTUD max to current size - 1
-                    let mut state = self.state.write();
-                    if let Some(ref mut pmtud) = state.pmtud {
-                        pmtud.max_size = packet_size.saturating_sub(1);
-                        pmtud.successes = 0;
-                        pmtud.failures = 0;
-                        // Recalculate current size
-                        if pmtud.is_converged() {
-                            pmtud.discovered_mtu = Some(pmtud.min_size);
-                            pmtud.phase = PmtudPhase::Complete;
-                        } else {
-                            pmtud.current_size = pmtud.next_probe_size();
-                        }
-                    }
-                    return false;
-                }
+        let key = (probe_id, flow_id, self.target, true);
+        self.pending.write().insert(
+            key,
+            PendingProbe {
+                sent_at,
+                target: self.target,
+                flow_id,
+                original_src_port: None,
+                original_checksum: None,
+                packet_size: Some(packet_size),
+            },
+        );
 
-                eprintln!("PMTUD: Failed to send probe size {}: {}", packet_size, e);
-                false
-            }
+        if let Err(e) = send_icmp(socket, &packet, self.target) {
+            self.pending.write().remove(&key);
+            return Err(e.context(format!("Failed to send probe size {}", packet_size)));
         }
+        Ok(())
     }
 
     /// Poll the send socket for IPv6 Echo Reply responses (Linux-only)
@@ -752,14 +938,34 @@ impl ProbeEngine {
                 Ok(recv_result) => {
                     // Parse the ICMP response
                     // For IPv6 raw sockets, kernel strips the IPv6 header
+                    let data = &buffer[..recv_result.len];
+                    let udp_id = self.config.udp_id;
                     let Some(parsed) = parse_icmp_response(
-                        &buffer[..recv_result.len],
+                        data,
                         recv_result.source,
-                        self.identifier,
+                        self.identity.identifier(),
                         is_dgram,
-                    ) else {
+                        udp_id,
+                    )
+                    .or_else(|| {
+                        let previous = self.identity.previous()?;
+                        parse_icmp_response(data, recv_result.source, previous, is_dgram, udp_id)
+                    }) else {
                         continue;
                     };
+                    if self.identity.is_foreign(echo_payload_nonce(
+                        data,
+                        recv_result.source,
+                        is_dgram,
+                    )) {
+                        if let Some((old, new)) = self.identity.record_foreign(self.config.timeout)
+                        {
+                            self.state.write().record_event(EventKind::Alert {
+                                message: identifier_rotated(old, new),
+                            });
+                        }
+                        continue;
+                    }
 
                     // Only handle Echo Reply here (type 129)
                     // Time Exceeded is handled by the receiver
@@ -785,15 +991,45 @@ impl ProbeEngine {
 
                         // Update state with parity to receiver behavior
                         let mut state = self.state.write();
+                        if let Some(size) = probe.packet_size
+                            && let Some(ref mut sweep) = state.df_sweep
+                        {
+                            sweep.record_answer(parsed.probe_id.ttl, size);
+                            continue;
+                        }
+                        let damping = FlapDamping::from_config(&state.config);
+                        let mut return_change = None;
                         if let Some(hop) = state.hop_mut(parsed.probe_id.ttl) {
                             // Use flap-detecting record for single-flow mode (ICMP is always single-flow)
-                            hop.record_response_detecting_flaps(parsed.responder, rtt, None);
+                            hop.record_response_detecting_flaps(
+                                parsed.responder,
+                                rtt,
+                                None,
+                                damping,
+                            );
                             hop.record_flow_response(flow_id, parsed.responder, rtt);
                             // Record response TTL for asymmetry detection
                             if let Some(response_ttl) = recv_result.response_ttl {
                                 hop.record_response_ttl(response_ttl, true);
+                                return_change =
+                                    hop.record_return_ttl(parsed.responder, response_ttl);
+                            }
+                            if let Some(quoted) = parsed.quoted_dscp {
+                                hop.record_quoted_dscp(self.config.dscp.unwrap_or(0), quoted);
+                            }
+                            if let (Some(sent), Some(quoted)) = (self.config.ecn, parsed.quoted_ecn)
+                            {
+                                hop.record_quoted_ecn(sent, quoted);
                             }
                         }
+                        if let Some((from, to)) = return_change {
+                            state.record_event(EventKind::ReturnTtlChange {
+                                ttl: parsed.probe_id.ttl,
+                                ip: parsed.responder,
+                                from,
+                                to,
+                            });
+                        }
 
                         // Mark trace as complete if this is the destination
                         if parsed.responder == self.target {
@@ -836,6 +1072,14 @@ impl ProbeEngine {
     }
 }
 
+/// Whether a send failed because the packet is larger than the local
+/// interface allows
+fn is_emsgsize(e: &anyhow::Error) -> bool {
+    e.chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|io| io.raw_os_error() == Some(libc::EMSGSIZE))
+}
+
 /// Create interval from config
 #[allow(dead_code)]
 pub fn create_probe_interval(config: &Config) -> tokio::time::Interval {
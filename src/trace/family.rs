@@ -0,0 +1,219 @@
+//! Best address family selection for `--best-family`
+//!
+//! A dual-stack hostname is traced over IPv4 by default, but the IPv6 path
+//! is often shorter or faster (or the other way round, through a tunnel).
+//! Like happy eyeballs, both families get a short trial trace at the same
+//! time; the one that reaches the destination in fewer hops, then with the
+//! lower RTT, is traced for the rest of the session.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::Config;
+use crate::probe::InterfaceInfo;
+use crate::state::{Session, Target};
+use crate::trace::runner::start_tracing;
+
+/// Probe rounds sent to each family before choosing
+const TRIAL_ROUNDS: u64 = 3;
+
+/// Interval between trial rounds
+const TRIAL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Longest a trial waits for the last replies
+const TRIAL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Outcome of one family's trial trace
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FamilyTrial {
+    pub addr: IpAddr,
+    /// Hop count and mean destination RTT, if the destination replied
+    pub reached: Option<(u8, Duration)>,
+}
+
+/// Address picked by the trials and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyChoice {
+    pub chosen: IpAddr,
+    pub reason: String,
+}
+
+fn family(addr: IpAddr) -> &'static str {
+    if addr.is_ipv6() { "IPv6" } else { "IPv4" }
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// Pick between two trials: fewer hops to the destination, then lower RTT.
+/// When neither reached it, `v4` is kept, as without `--best-family`.
+pub fn decide(v4: &FamilyTrial, v6: &FamilyTrial) -> FamilyChoice {
+    let (chosen, other, reason) = match (v4.reached, v6.reached) {
+        (None, None) => {
+            return FamilyChoice {
+                chosen: v4.addr,
+                reason: "neither IPv4 nor IPv6 reached the destination in the trial; \
+                         using IPv4"
+                    .to_string(),
+            };
+        }
+        (Some(_), None) => (v4, v6, "only it reached the destination".to_string()),
+        (None, Some(_)) => (v6, v4, "only it reached the destination".to_string()),
+        (Some((h4, rtt4)), Some((h6, rtt6))) => {
+            let v6_wins = h6 < h4 || (h6 == h4 && rtt6 < rtt4);
+            let (win, lose) = if v6_wins { (v6, v4) } else { (v4, v6) };
+            let reason = if h4 == h6 {
+                "same hop count, lower RTT".to_string()
+            } else {
+                "fewer hops".to_string()
+            };
+            (win, lose, reason)
+        }
+    };
+
+    let describe = |trial: &FamilyTrial| match trial.reached {
+        Some((hops, rtt)) => format!(
+            "{} hop{}, {:.1}ms",
+            hops,
+            if hops == 1 { "" } else { "s" },
+            ms(rtt)
+        ),
+        None => "no reply".to_string(),
+    };
+    FamilyChoice {
+        chosen: chosen.addr,
+        reason: format!(
+            "{} chosen, {}: {} ({}) vs {} ({})",
+            family(chosen.addr),
+            reason,
+            family(chosen.addr),
+            describe(chosen),
+            family(other.addr),
+            describe(other)
+        ),
+    }
+}
+
+/// Short trace toward `addr` with enrichment and side effects turned off
+async fn trial(
+    target_str: &str,
+    addr: IpAddr,
+    config: &Config,
+    interface: Option<&InterfaceInfo>,
+) -> FamilyTrial {
+    let mut config = config.clone();
+    config.count = Some(TRIAL_ROUNDS);
+    config.duration = None;
+    config.interval = TRIAL_INTERVAL;
+    config.timeout = config.timeout.min(TRIAL_TIMEOUT);
+    config.dns_enabled = false;
+    config.asn_enabled = false;
+    config.geo_enabled = false;
+    config.ix_enabled = false;
+    config.prefix_watch = false;
+    config.pmtud = false;
+    config.notify = false;
+    config.dest_alert = Default::default();
+    config.plugins.clear();
+    config.event_log = None;
+    config.load = None;
+    config.source_ip = None;
+
+    let session = Session::new(Target::new(target_str.to_string(), addr), config.clone());
+    let state = Arc::new(RwLock::new(session));
+    let sessions = Arc::new(RwLock::new(HashMap::from([(addr, state.clone())])));
+    let cancel = CancellationToken::new();
+
+    // A family that can't be probed (no route, no IPv6 socket) just loses
+    let ran = async {
+        let mut tasks =
+            start_tracing(&sessions, &[addr], &config, &cancel, interface, None).await?;
+        let engines = tasks.wait_engines().await;
+        tokio::time::sleep(config.timeout).await;
+        cancel.cancel();
+        tasks.join().await?;
+        engines
+    }
+    .await;
+    cancel.cancel();
+
+    let session = state.read();
+    let reached = ran.ok().and(session.dest_ttl).and_then(|ttl| {
+        let stats = session.hop(ttl)?.responders.get(&addr)?;
+        Some((ttl, stats.avg_rtt()))
+    });
+    FamilyTrial { addr, reached }
+}
+
+/// Trial-trace both addresses at once and pick one
+pub async fn choose_family(
+    target_str: &str,
+    v4: IpAddr,
+    v6: IpAddr,
+    config: &Config,
+    interface: Option<&InterfaceInfo>,
+) -> FamilyChoice {
+    let (t4, t6) = tokio::join!(
+        trial(target_str, v4, config, interface),
+        trial(target_str, v6, config, interface)
+    );
+    decide(&t4, &t6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trial(addr: &str, reached: Option<(u8, u64)>) -> FamilyTrial {
+        FamilyTrial {
+            addr: addr.parse().unwrap(),
+            reached: reached.map(|(hops, rtt)| (hops, Duration::from_millis(rtt))),
+        }
+    }
+
+    #[test]
+    fn test_decide_fewer_hops() {
+        let v4 = trial("192.0.2.1", Some((11, 14)));
+        let v6 = trial("2001:db8::1", Some((9, 20)));
+        let choice = decide(&v4, &v6);
+        assert_eq!(choice.chosen, v6.addr);
+        assert_eq!(
+            choice.reason,
+            "IPv6 chosen, fewer hops: IPv6 (9 hops, 20.0ms) vs IPv4 (11 hops, 14.0ms)"
+        );
+    }
+
+    #[test]
+    fn test_decide_same_hops() {
+        let v4 = trial("192.0.2.1", Some((9, 12)));
+        let v6 = trial("2001:db8::1", Some((9, 15)));
+        let choice = decide(&v4, &v6);
+        assert_eq!(choice.chosen, v4.addr);
+        assert!(
+            choice
+                .reason
+                .starts_with("IPv4 chosen, same hop count, lower RTT")
+        );
+    }
+
+    #[test]
+    fn test_decide_unreached() {
+        let v4 = trial("192.0.2.1", None);
+        let v6 = trial("2001:db8::1", Some((12, 30)));
+        let choice = decide(&v4, &v6);
+        assert_eq!(choice.chosen, v6.addr);
+        assert_eq!(
+            choice.reason,
+            "IPv6 chosen, only it reached the destination: IPv6 (12 hops, 30.0ms) vs IPv4 (no reply)"
+        );
+
+        let choice = decide(&v4, &trial("2001:db8::1", None));
+        assert_eq!(choice.chosen, v4.addr);
+        assert!(choice.reason.starts_with("neither"));
+    }
+}
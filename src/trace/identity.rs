@@ -0,0 +1,153 @@
+//! ICMP identifier ownership
+//!
+//! Echo probes are matched to replies by identifier and sequence, and the
+//! identifier is derived from the PID. When another ping or traceroute
+//! happens to use the same identifier (PID reuse, a second instance in
+//! another container), its replies land in our sequence space and would be
+//! counted as ours. Each session therefore also writes a random nonce into
+//! the echo payload; replies quoting a different nonce are foreign.
+//!
+//! After a few foreign replies the identifier is rotated. Pending probes are
+//! keyed by TTL and sequence, not identifier, so they carry over; replies to
+//! probes sent under the old identifier are still accepted for one probe
+//! timeout.
+
+use parking_lot::Mutex;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// Foreign replies tolerated before the identifier is rotated
+pub const ROTATE_AFTER: u32 = 3;
+
+/// Identifier and payload nonce shared by the engines and the receiver
+#[derive(Debug)]
+pub struct ProbeIdentity {
+    nonce: u32,
+    state: Mutex<IdState>,
+}
+
+#[derive(Debug)]
+struct IdState {
+    current: u16,
+    /// Identifier before the last rotation, and until when it is accepted
+    previous: Option<(u16, Instant)>,
+    /// Foreign replies seen since the last rotation
+    foreign: u32,
+}
+
+fn random_u64() -> u64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+impl ProbeIdentity {
+    pub fn new(identifier: u16) -> Self {
+        // Zero reads as "no nonce" (padding in short quotes), so never use it
+        let nonce = (random_u64() as u32).max(1);
+        Self {
+            nonce,
+            state: Mutex::new(IdState {
+                current: identifier,
+                previous: None,
+                foreign: 0,
+            }),
+        }
+    }
+
+    /// Identifier for new probes
+    pub fn identifier(&self) -> u16 {
+        self.state.lock().current
+    }
+
+    pub fn nonce(&self) -> u32 {
+        self.nonce
+    }
+
+    /// Identifier replaced by the last rotation, while replies to it are
+    /// still expected
+    pub fn previous(&self) -> Option<u16> {
+        let mut state = self.state.lock();
+        match state.previous {
+            Some((id, until)) if Instant::now() < until => Some(id),
+            Some(_) => {
+                state.previous = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Whether a reply carrying `nonce` was sent by someone else. Replies
+    /// without a readable nonce get the benefit of the doubt.
+    pub fn is_foreign(&self, nonce: Option<u32>) -> bool {
+        nonce.is_some_and(|n| n != self.nonce)
+    }
+
+    /// Count a foreign reply; after `ROTATE_AFTER` of them switch to a new
+    /// identifier, accepting the old one for `grace`. Returns the old and
+    /// new identifier when it rotated.
+    pub fn record_foreign(&self, grace: Duration) -> Option<(u16, u16)> {
+        let mut state = self.state.lock();
+        state.foreign += 1;
+        if state.foreign < ROTATE_AFTER {
+            return None;
+        }
+        let old = state.current;
+        let mut new = old;
+        while new == old || new == 0 {
+            new = random_u64() as u16;
+        }
+        state.current = new;
+        state.previous = Some((old, Instant::now() + grace));
+        state.foreign = 0;
+        Some((old, new))
+    }
+}
+
+/// Alert text for a rotation
+pub fn identifier_rotated(old: u16, new: u16) -> String {
+    format!(
+        "ICMP identifier {:#06x} also used by another process; switched to {:#06x}",
+        old, new
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotates_after_foreign_replies() {
+        let identity = ProbeIdentity::new(0x1234);
+        assert_ne!(identity.nonce(), 0);
+        assert!(!identity.is_foreign(None));
+        assert!(!identity.is_foreign(Some(identity.nonce())));
+        assert!(identity.is_foreign(Some(identity.nonce().wrapping_add(1))));
+
+        let grace = Duration::from_secs(60);
+        for _ in 1..ROTATE_AFTER {
+            assert_eq!(identity.record_foreign(grace), None);
+        }
+        let (old, new) = identity.record_foreign(grace).unwrap();
+        assert_eq!(old, 0x1234);
+        assert_ne!(new, old);
+        assert_eq!(identity.identifier(), new);
+        assert_eq!(identity.previous(), Some(0x1234));
+    }
+
+    #[test]
+    fn test_previous_expires() {
+        let identity = ProbeIdentity::new(7);
+        for _ in 0..ROTATE_AFTER {
+            identity.record_foreign(Duration::ZERO);
+        }
+        assert_ne!(identity.identifier(), 7);
+        assert_eq!(identity.previous(), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Load stream for `--load`
+//!
+//! Waits out the idle baseline, switches every session's load test to the
+//! loaded phase, then sends fixed-size UDP packets to `--load-target` at
+//! the configured rate for `LOADED_PHASE` and stops. Packets are paced in
+//! small ticks rather than sent in per-second bursts, so the queue under
+//! test sees a steady stream like a bulk upload would produce.
+
+use anyhow::{Context, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use crate::state::{IDLE_PHASE, LOADED_PHASE, LoadPhase, LoadSpec};
+use crate::trace::receiver::SessionMap;
+
+/// UDP payload per load packet (fits any path MTU of 1280 or more)
+const PACKET_BYTES: usize = 1200;
+
+/// Pacing granularity
+const TICK: Duration = Duration::from_millis(5);
+
+/// Send credit that may build up across a stalled tick, in ticks
+const MAX_BURST_TICKS: f64 = 4.0;
+
+/// Open the load socket, connected to the stream's target and port
+pub fn open_load_socket(spec: &LoadSpec, source_ip: Option<IpAddr>) -> Result<UdpSocket> {
+    let target = spec.target;
+    let bind = source_ip.unwrap_or(if target.is_ipv6() {
+        Ipv6Addr::UNSPECIFIED.into()
+    } else {
+        Ipv4Addr::UNSPECIFIED.into()
+    });
+    let socket = std::net::UdpSocket::bind((bind, 0)).context("Failed to bind load socket")?;
+    socket
+        .connect((target, spec.port))
+        .with_context(|| format!("Failed to connect load socket to {}:{}", target, spec.port))?;
+    socket.set_nonblocking(true)?;
+    Ok(UdpSocket::from_std(socket)?)
+}
+
+/// Add `elapsed` worth of send credit and return how many packets it pays for
+fn packets_due(credit: &mut f64, elapsed: Duration, bytes_per_sec: f64) -> usize {
+    let max_credit =
+        (bytes_per_sec * TICK.as_secs_f64() * MAX_BURST_TICKS).max(PACKET_BYTES as f64);
+    *credit = (*credit + elapsed.as_secs_f64() * bytes_per_sec).min(max_credit);
+    let due = (*credit / PACKET_BYTES as f64) as usize;
+    *credit -= (due * PACKET_BYTES) as f64;
+    due
+}
+
+/// Set every session's load test to `phase`
+fn set_phase(sessions: &SessionMap, phase: LoadPhase) {
+    for session in sessions.read().values() {
+        if let Some(ref mut load) = session.write().load {
+            load.phase = phase;
+        }
+    }
+}
+
+/// Run the idle baseline, then the load stream for `LOADED_PHASE`
+pub async fn run_load_worker(
+    socket: UdpSocket,
+    spec: LoadSpec,
+    sessions: SessionMap,
+    cancel: CancellationToken,
+) {
+    tokio::select! {
+        _ = cancel.cancelled() => return,
+        _ = tokio::time::sleep(IDLE_PHASE) => {}
+    }
+    set_phase(&sessions, LoadPhase::Loaded);
+    let stop = tokio::time::sleep(LOADED_PHASE);
+    tokio::pin!(stop);
+
+    let bytes_per_sec = spec.rate_bps as f64 / 8.0;
+    let payload = vec![0u8; PACKET_BYTES];
+    let mut ticker = tokio::time::interval(TICK);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut credit = 0.0;
+    let mut last = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = &mut stop => {
+                set_phase(&sessions, LoadPhase::Done);
+                break;
+            }
+            _ = ticker.tick() => {
+                let now = Instant::now();
+                let due = packets_due(&mut credit, now.duration_since(last), bytes_per_sec);
+                last = now;
+                for _ in 0..due {
+                    // A full send buffer or a port unreachable reported on
+                    // the connected socket only costs this one packet
+                    let _ = socket.try_send(&payload);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packets_due() {
+        // 9.6 Mbit/s = 1000 packets/s, 5 per tick
+        let rate = 9_600_000.0 / 8.0;
+        let mut credit = 0.0;
+        assert_eq!(packets_due(&mut credit, TICK, rate), 5);
+        assert!(credit < 1.0);
+
+        // A long stall is capped instead of sent as one burst
+        assert_eq!(packets_due(&mut credit, Duration::from_secs(1), rate), 20);
+
+        // Slow rates accumulate across ticks until a packet is paid for
+        let slow = 96_000.0 / 8.0;
+        let mut credit = 0.0;
+        let sent: usize = (0..25).map(|_| packets_due(&mut credit, TICK, slow)).sum();
+        assert_eq!(sent, 1);
+    }
+}
@@ -1,3 +1,11 @@
+pub mod dump;
 pub mod engine;
+pub mod family;
+pub mod identity;
+pub mod load;
 pub mod pending;
+pub mod perf;
+pub mod priority;
 pub mod receiver;
+pub mod runner;
+pub mod scheduler;
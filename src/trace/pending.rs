@@ -21,6 +21,9 @@ pub struct PendingProbe {
     pub flow_id: u8,
     /// Original source port for NAT detection (UDP/TCP only, None for ICMP)
     pub original_src_port: Option<u16>,
+    /// UDP/TCP checksum as sent, for NAT detection (None for ICMP, or when
+    /// the source address isn't known)
+    pub original_checksum: Option<u16>,
     /// Packet size for PMTUD correlation (only set during PMTUD phase)
     pub packet_size: Option<u16>,
 }
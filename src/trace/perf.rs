@@ -0,0 +1,238 @@
+//! Self-monitoring for `--perf`
+//!
+//! Timings of ttl's own work, for telling a slow network from a slow ttl
+//! on a loaded machine: how long the TUI takes to draw and how often it
+//! falls behind its tick, how long the TUI and the receiver wait for
+//! session locks, and how much work queues up between receiver passes.
+//! Nothing leaves the machine; the numbers are shown in the diagnostics
+//! panel and can be saved from there. Recording is a few relaxed atomic
+//! operations and is skipped entirely unless enabled.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Process-wide monitor, shared by the receiver thread and the TUI
+pub static PERF: PerfMonitor = PerfMonitor::new();
+
+/// Running count, mean and maximum of a timing, in microseconds
+pub struct DurationStat {
+    count: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+    last_us: AtomicU64,
+}
+
+impl DurationStat {
+    const fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            total_us: AtomicU64::new(0),
+            max_us: AtomicU64::new(0),
+            last_us: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+        self.last_us.store(us, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> TimingSummary {
+        let count = self.count.load(Ordering::Relaxed);
+        let total = self.total_us.load(Ordering::Relaxed);
+        TimingSummary {
+            count,
+            last_ms: self.last_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            mean_ms: if count == 0 {
+                0.0
+            } else {
+                total as f64 / count as f64 / 1000.0
+            },
+            max_ms: self.max_us.load(Ordering::Relaxed) as f64 / 1000.0,
+        }
+    }
+}
+
+/// Latest and highest value of a queue depth
+pub struct DepthStat {
+    last: AtomicU64,
+    max: AtomicU64,
+}
+
+impl DepthStat {
+    const fn new() -> Self {
+        Self {
+            last: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, depth: usize) {
+        let depth = depth as u64;
+        self.last.store(depth, Ordering::Relaxed);
+        self.max.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> DepthSummary {
+        DepthSummary {
+            last: self.last.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub struct PerfMonitor {
+    enabled: AtomicBool,
+    /// TUI frame render time
+    pub draw: DurationStat,
+    /// TUI wait for the session locks before drawing
+    pub ui_lock_wait: DurationStat,
+    /// Receiver wait for a session's write lock
+    pub receiver_lock_wait: DurationStat,
+    /// TUI loop iterations that took well over the tick
+    tick_overruns: AtomicU64,
+    /// Packets read from the socket in one receiver pass
+    pub receiver_batch: DepthStat,
+    /// Probes in flight, waiting for a reply or timeout
+    pub pending_probes: DepthStat,
+}
+
+impl PerfMonitor {
+    const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            draw: DurationStat::new(),
+            ui_lock_wait: DurationStat::new(),
+            receiver_lock_wait: DurationStat::new(),
+            tick_overruns: AtomicU64::new(0),
+            receiver_batch: DepthStat::new(),
+            pending_probes: DepthStat::new(),
+        }
+    }
+
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, stat: &DurationStat, elapsed: Duration) {
+        if self.enabled() {
+            stat.record(elapsed);
+        }
+    }
+
+    pub fn record_depth(&self, stat: &DepthStat, depth: usize) {
+        if self.enabled() {
+            stat.record(depth);
+        }
+    }
+
+    /// Note one TUI loop iteration; more than half a tick late counts as
+    /// an overrun
+    pub fn record_tick(&self, elapsed: Duration, tick: Duration) {
+        if self.enabled() && elapsed > tick + tick / 2 {
+            self.tick_overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> PerfSnapshot {
+        PerfSnapshot {
+            draw: self.draw.snapshot(),
+            tick_overruns: self.tick_overruns.load(Ordering::Relaxed),
+            ui_lock_wait: self.ui_lock_wait.snapshot(),
+            receiver_lock_wait: self.receiver_lock_wait.snapshot(),
+            receiver_batch: self.receiver_batch.snapshot(),
+            pending_probes: self.pending_probes.snapshot(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimingSummary {
+    pub count: u64,
+    pub last_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+impl TimingSummary {
+    /// e.g. "1.2ms (mean 0.8, max 14.0)"
+    pub fn describe(&self) -> String {
+        if self.count == 0 {
+            return "-".to_string();
+        }
+        format!(
+            "{:.1}ms (mean {:.1}, max {:.1})",
+            self.last_ms, self.mean_ms, self.max_ms
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DepthSummary {
+    pub last: u64,
+    pub max: u64,
+}
+
+/// Point-in-time copy of the monitor, as shown and exported
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PerfSnapshot {
+    pub draw: TimingSummary,
+    pub tick_overruns: u64,
+    pub ui_lock_wait: TimingSummary,
+    pub receiver_lock_wait: TimingSummary,
+    pub receiver_batch: DepthSummary,
+    pub pending_probes: DepthSummary,
+}
+
+impl PerfSnapshot {
+    /// Write to `ttl-perf-<time>.json` in the current directory and return
+    /// the file name
+    pub fn save(&self) -> Result<String> {
+        let filename = format!("ttl-perf-{}.json", Utc::now().format("%Y%m%d-%H%M%S"));
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&filename, json).with_context(|| format!("writing {}", filename))?;
+        Ok(filename)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perf_monitor() {
+        let perf = PerfMonitor::new();
+        // Nothing is recorded until enabled
+        perf.record(&perf.draw, Duration::from_millis(5));
+        perf.record_tick(Duration::from_secs(1), Duration::from_millis(100));
+        assert_eq!(perf.snapshot().draw.count, 0);
+        assert_eq!(perf.snapshot().tick_overruns, 0);
+
+        perf.enable();
+        perf.record(&perf.draw, Duration::from_millis(2));
+        perf.record(&perf.draw, Duration::from_millis(6));
+        perf.record_depth(&perf.pending_probes, 40);
+        perf.record_depth(&perf.pending_probes, 12);
+        perf.record_tick(Duration::from_millis(120), Duration::from_millis(100));
+        perf.record_tick(Duration::from_millis(400), Duration::from_millis(100));
+
+        let snapshot = perf.snapshot();
+        assert_eq!(snapshot.draw.count, 2);
+        assert_eq!(snapshot.draw.last_ms, 6.0);
+        assert_eq!(snapshot.draw.mean_ms, 4.0);
+        assert_eq!(snapshot.draw.describe(), "6.0ms (mean 4.0, max 6.0)");
+        assert_eq!(snapshot.ui_lock_wait.describe(), "-");
+        assert_eq!(snapshot.pending_probes, DepthSummary { last: 12, max: 40 });
+        assert_eq!(snapshot.tick_overruns, 1);
+    }
+}
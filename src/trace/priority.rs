@@ -0,0 +1,161 @@
+//! Dedicated probe thread for `--probe-thread`
+//!
+//! Probe engines normally share the tokio runtime with the TUI, the
+//! enrichment lookups and the event sinks, so a slow redraw or a burst of
+//! DNS replies can hold a probe back and the delay shows up as RTT jitter.
+//! With `--probe-thread` the engines get an OS thread and a single-threaded
+//! runtime of their own. That thread and the receiver's try to raise their
+//! scheduling priority (which needs root or CAP_SYS_NICE) and, with
+//! `--probe-cpu`, are pinned to one core. Neither is essential: when the
+//! OS refuses, probing carries on with a warning. The warning comes from a
+//! trial run on a throwaway thread before tracing starts, since the real
+//! threads only start once the TUI owns the terminal.
+
+use anyhow::{Context, Result};
+use tokio::task::JoinHandle;
+
+use super::engine::ProbeEngine;
+
+/// Nice value asked for on the probe and receiver threads
+const PROBE_NICE: i32 = -10;
+
+/// Priority and CPU placement for the probe and receiver threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadTuning {
+    /// Core to pin to
+    pub cpu: Option<usize>,
+}
+
+impl ThreadTuning {
+    /// Try the tuning on a throwaway thread and describe anything the OS
+    /// refused (e.g. a raised priority without root or CAP_SYS_NICE)
+    pub fn check(&self) -> Vec<String> {
+        let tuning = *self;
+        std::thread::spawn(move || tuning.try_apply())
+            .join()
+            .unwrap_or_else(|_| vec!["thread tuning check panicked".to_string()])
+    }
+
+    /// Tune the calling thread. Failures were reported by `check` before
+    /// the TUI started, so they are not repeated here.
+    pub fn apply(&self) {
+        let _ = self.try_apply();
+    }
+
+    fn try_apply(&self) -> Vec<String> {
+        let mut refused = Vec::new();
+        if let Err(e) = raise_priority() {
+            refused.push(format!(
+                "probe and receiver threads keep normal priority: {:#} (needs root or CAP_SYS_NICE)",
+                e
+            ));
+        }
+        if let Some(cpu) = self.cpu
+            && let Err(e) = pin_to_cpu(cpu)
+        {
+            refused.push(format!(
+                "probe and receiver threads not pinned to CPU {}: {:#}",
+                cpu, e
+            ));
+        }
+        refused
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn raise_priority() -> Result<()> {
+    // Nice values are per thread on Linux; address this one by its tid
+    // SAFETY: gettid and setpriority take no pointers
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, PROBE_NICE) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("setpriority");
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn raise_priority() -> Result<()> {
+    anyhow::bail!("per-thread priority is only supported on Linux")
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_cpu(cpu: usize) -> Result<()> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        anyhow::bail!("CPU index out of range");
+    }
+    // SAFETY: the set is zeroed, then filled and read through libc's own
+    // helpers; pid 0 is the calling thread
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error()).context("sched_setaffinity");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to_cpu(_cpu: usize) -> Result<()> {
+    anyhow::bail!("CPU pinning is only supported on Linux")
+}
+
+/// Run `engines` on a thread of their own. The returned task finishes
+/// with the first engine error, once all of them have stopped.
+pub fn spawn_probe_thread(
+    engines: Vec<ProbeEngine>,
+    tuning: ThreadTuning,
+) -> Result<JoinHandle<Result<()>>> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("creating probe runtime")?;
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    std::thread::Builder::new()
+        .name("ttl-probe".to_string())
+        .spawn(move || {
+            tuning.apply();
+            let result = runtime.block_on(async {
+                let handles: Vec<_> = engines
+                    .into_iter()
+                    .map(|engine| tokio::spawn(async move { engine.run().await }))
+                    .collect();
+                let mut result = Ok(());
+                for handle in handles {
+                    let outcome = handle
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Probe engine failed: {}", e))
+                        .and_then(|r| r);
+                    if result.is_ok() {
+                        result = outcome;
+                    }
+                }
+                result
+            });
+            let _ = done_tx.send(result);
+        })
+        .context("spawning probe thread")?;
+
+    Ok(tokio::spawn(async move {
+        done_rx
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Probe thread exited without a result")))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reports_refused_pinning() {
+        let warnings = ThreadTuning { cpu: Some(100_000) }.check();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.starts_with("probe and receiver threads not pinned to CPU 100000")),
+            "{:?}",
+            warnings
+        );
+    }
+}
@@ -6,12 +6,21 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::config::{FlowPorts, UdpIdEncoding};
 use crate::probe::{
-    InterfaceInfo, create_recv_socket_with_interface, get_identifier, parse_icmp_response,
-    recv_icmp_with_ttl,
+    InterfaceInfo, ParsedResponse, apply_buffer_sizes, bind_to_source_ip,
+    create_recv_socket_with_interface, echo_payload_nonce, enable_drop_counter,
+    parse_icmp_response, parse_redirect, recv_icmp_with_ttl,
 };
-use crate::state::{IcmpResponseType, MplsLabel, PmtudPhase, ProbeId, Session};
-use crate::trace::pending::PendingMap;
+use crate::state::{
+    EcnCodepoint, EventKind, FlapDamping, IcmpResponseType, IncomingInterface, MplsLabel,
+    PmtudPhase, ProbeId, Session,
+};
+use crate::trace::dump::{DUMPS, DumpReason};
+use crate::trace::identity::{ProbeIdentity, identifier_rotated};
+use crate::trace::pending::{PendingKey, PendingMap, PendingProbe};
+use crate::trace::perf::PERF;
+use crate::trace::priority::ThreadTuning;
 
 /// Map of target IP to session, shared across multiple engines and the receiver
 pub type SessionMap = Arc<RwLock<HashMap<IpAddr, Arc<RwLock<Session>>>>>;
@@ -23,14 +32,22 @@ pub struct ReceiverConfig {
     pub timeout: Duration,
     /// Whether targets are IPv6
     pub ipv6: bool,
-    /// Base source port for flow identification (Paris/Dublin traceroute)
-    pub src_port_base: u16,
+    /// Source port of each flow (Paris/Dublin traceroute)
+    pub flow_ports: FlowPorts,
     /// Number of flows for multi-path ECMP detection
     pub num_flows: u8,
     /// Network interface to bind receiver socket to
     pub interface: Option<InterfaceInfo>,
     /// Don't bind receiver to interface (for asymmetric routing)
     pub recv_any: bool,
+    /// Requested SO_RCVBUF in bytes (None = OS default)
+    pub recv_buffer: Option<usize>,
+    /// Only take replies addressed to this local address (--sources)
+    pub bind: Option<IpAddr>,
+    /// Priority and CPU pinning for the receiver thread (--probe-thread)
+    pub tuning: Option<ThreadTuning>,
+    /// Where UDP probes carry their ID
+    pub udp_id: UdpIdEncoding,
 }
 
 /// Maximum consecutive errors before stopping the receiver
@@ -40,12 +57,18 @@ const MAX_CONSECUTIVE_ERRORS: u32 = 50;
 /// Prevents starvation at high packet rates
 const MAX_DRAIN_BATCH: usize = 100;
 
+/// How long a timed-out probe is remembered so a reply to it can still be
+/// counted as late. Well under the 256-round wrap of per-TTL sequence numbers
+/// at typical intervals.
+const LATE_WINDOW: Duration = Duration::from_secs(30);
+
 /// Collected response data for batched state updates
 struct BatchedResponse {
     probe_id: ProbeId,
     responder: IpAddr,
     rtt: Duration,
     mpls_labels: Option<Vec<MplsLabel>>,
+    interface: Option<IncomingInterface>,
     response_type: IcmpResponseType,
     target: IpAddr,
     /// Flow ID for Paris/Dublin traceroute ECMP detection
@@ -54,6 +77,9 @@ struct BatchedResponse {
     original_src_port: Option<u16>,
     /// Returned source port from ICMP error payload (for NAT detection)
     returned_src_port: Option<u16>,
+    /// Checksum sent and checksum quoted back (for NAT detection)
+    original_checksum: Option<u16>,
+    returned_checksum: Option<u16>,
     /// Packet size for PMTUD correlation (if this was a PMTUD probe)
     packet_size: Option<u16>,
     /// MTU from ICMP Frag Needed / Packet Too Big (for PMTUD)
@@ -62,6 +88,64 @@ struct BatchedResponse {
     response_ttl: Option<u8>,
     /// Quoted TTL from ICMP error payload (for TTL manipulation detection)
     quoted_ttl: Option<u8>,
+    /// Quoted DSCP from ICMP error payload (for remark detection)
+    quoted_dscp: Option<u8>,
+    /// Quoted ECN codepoint from ICMP error payload (for bleaching detection)
+    quoted_ecn: Option<EcnCodepoint>,
+}
+
+/// Reply to a probe that had already been counted as timed out
+struct LateResponse {
+    target: IpAddr,
+    ttl: u8,
+    rtt: Duration,
+}
+
+/// Remove and return the probe a response answers. Pending probes are keyed
+/// by target, so only a probe sent to `original_dest` can match: the
+/// destination quoted in an ICMP error, or the sender of an Echo Reply or
+/// TCP reply. Engines share the identifier and sequence space, so this is
+/// what keeps one target's responses off another's probes. Without a
+/// destination each target is tried.
+fn take_probe(
+    probes: &mut HashMap<PendingKey, PendingProbe>,
+    probe_id: ProbeId,
+    flow_id: u8,
+    original_dest: Option<IpAddr>,
+    targets: &[IpAddr],
+) -> Option<PendingProbe> {
+    let dests: &[IpAddr] = match original_dest {
+        Some(ref dest) => std::slice::from_ref(dest),
+        None => targets,
+    };
+    // Normal probe first, then PMTUD
+    dests.iter().find_map(|&dest| {
+        probes
+            .remove(&(probe_id, flow_id, dest, false))
+            .or_else(|| probes.remove(&(probe_id, flow_id, dest, true)))
+    })
+}
+
+/// Where the receiver routed a response
+enum Routed {
+    /// Answers an outstanding probe
+    Probe(PendingProbe),
+    /// Answers a probe that had already timed out
+    Late(PendingProbe),
+    /// Parsed as ours, but no probe was waiting for it (a duplicate, or
+    /// later than LATE_WINDOW); carries the target when the response names one
+    Unmatched(Option<IpAddr>),
+}
+
+/// Targets in the order a pass applies their responses: rotated by one
+/// each pass, so no session always waits for every other one's updates
+fn fair_order(targets: &[IpAddr], pass: usize) -> impl Iterator<Item = IpAddr> + '_ {
+    let start = if targets.is_empty() {
+        0
+    } else {
+        pass % targets.len()
+    };
+    targets[start..].iter().chain(&targets[..start]).copied()
 }
 
 /// The receiver listens for ICMP responses and correlates them to probes
@@ -70,9 +154,15 @@ pub struct Receiver {
     pending: PendingMap,
     cancel: CancellationToken,
     config: ReceiverConfig,
+    /// ICMP identifier and payload nonce shared with the engines
+    identity: Arc<ProbeIdentity>,
     consecutive_errors: u32,
     /// List of target IPs for probe lookup (cached from sessions keys)
     targets: Vec<IpAddr>,
+    /// Timed-out probes still within LATE_WINDOW, for late-reply tracking
+    expired: HashMap<PendingKey, PendingProbe>,
+    /// Passes over the socket so far, for `fair_order`
+    passes: usize,
 }
 
 impl Receiver {
@@ -81,6 +171,7 @@ impl Receiver {
         pending: PendingMap,
         cancel: CancellationToken,
         config: ReceiverConfig,
+        identity: Arc<ProbeIdentity>,
     ) -> Self {
         // Cache target list for probe lookup
         let targets: Vec<IpAddr> = sessions.read().keys().cloned().collect();
@@ -89,14 +180,215 @@ impl Receiver {
             pending,
             cancel,
             config,
+            identity,
             consecutive_errors: 0,
             targets,
+            expired: HashMap::new(),
+            passes: 0,
+        }
+    }
+
+    /// Hand a response to the probe it answers, whichever target's engine
+    /// sent it. The pending map is keyed by target, so one receive socket
+    /// serves every engine.
+    fn route(&mut self, parsed: &ParsedResponse, flow_id: u8) -> Routed {
+        let found = take_probe(
+            &mut self.pending.write(),
+            parsed.probe_id,
+            flow_id,
+            parsed.original_dest,
+            &self.targets,
+        );
+        if let Some(probe) = found {
+            return Routed::Probe(probe);
+        }
+        if let Some(probe) = take_probe(
+            &mut self.expired,
+            parsed.probe_id,
+            flow_id,
+            parsed.original_dest,
+            &self.targets,
+        ) {
+            return Routed::Late(probe);
+        }
+        // Errors quote the destination; echo replies come from it
+        let target = parsed
+            .original_dest
+            .or(Some(parsed.responder))
+            .filter(|ip| self.targets.contains(ip));
+        Routed::Unmatched(target)
+    }
+
+    /// Count a reply carrying another process's nonce, and tell every
+    /// session when that rotates the identifier
+    fn identifier_collision(&self) {
+        if let Some((old, new)) = self.identity.record_foreign(self.config.timeout) {
+            for session in self.sessions.read().values() {
+                session.write().record_event(EventKind::Alert {
+                    message: identifier_rotated(old, new),
+                });
+            }
+        }
+    }
+
+    /// Fold one response into its target's session
+    fn apply_response(&self, state: &mut Session, resp: BatchedResponse) {
+        // Sized probes belong to the DF sweep when it runs (it excludes
+        // PMTUD); they stay out of hop statistics, so oversized probes
+        // don't show up as loss or as extra responders
+        if let Some(size) = resp.packet_size
+            && let Some(ref mut sweep) = state.df_sweep
+        {
+            let ttl = resp.probe_id.ttl;
+            if resp.response_type.is_frag_needed() {
+                sweep.record_frag_needed(ttl, size, resp.responder, resp.reported_mtu);
+                if let Some(mtu) = resp.reported_mtu {
+                    state.mtu_hints.record_frag_needed(ttl, mtu);
+                }
+            } else {
+                sweep.record_answer(ttl, size);
+            }
+            return;
+        }
+
+        let mut route_change = None;
+        let mut return_change = None;
+        let damping = FlapDamping::from_config(&state.config);
+        let sent_dscp = state.config.dscp.unwrap_or(0);
+        let sent_ecn = state.config.ecn;
+        if let Some(hop) = state.hop_mut(resp.probe_id.ttl) {
+            // Record aggregate stats with optional flap detection
+            // Only detect flaps in single-flow mode (multi-flow expects path changes)
+            if self.config.num_flows == 1 {
+                route_change = hop.record_response_detecting_flaps(
+                    resp.responder,
+                    resp.rtt,
+                    resp.mpls_labels,
+                    damping,
+                );
+            } else {
+                hop.record_response_with_mpls(resp.responder, resp.rtt, resp.mpls_labels);
+            }
+            hop.record_response_type(resp.responder, resp.response_type);
+            if let Some(interface) = resp.interface {
+                hop.record_interface(resp.responder, interface);
+            }
+            // Record per-flow stats for Paris/Dublin traceroute ECMP detection
+            hop.record_flow_response(resp.flow_id, resp.responder, resp.rtt);
+            // Record NAT detection result (compare sent vs returned source
+            // port and checksum)
+            hop.record_nat_check(
+                (resp.original_src_port, resp.original_checksum),
+                (resp.returned_src_port, resp.returned_checksum),
+            );
+            // Asymmetric routing detection (single-flow mode only, like flap detection)
+            if self.config.num_flows == 1
+                && let Some(ttl) = resp.response_ttl
+            {
+                hop.record_response_ttl(ttl, self.config.ipv6);
+            }
+            // Reverse path changes, per responder (all flow modes)
+            if let Some(ttl) = resp.response_ttl {
+                return_change = hop.record_return_ttl(resp.responder, ttl);
+            }
+
+            // TTL manipulation detection (TimeExceeded code 0 only, all flow modes)
+            // Code 0 = TTL exceeded in transit, Code 1 = fragment reassembly exceeded
+            // Only code 0 is relevant for TTL manipulation - code 1 can have quoted TTL > 1
+            if matches!(resp.response_type, IcmpResponseType::TimeExceeded(0))
+                && let Some(quoted) = resp.quoted_ttl
+            {
+                hop.record_ttl_manip_check(quoted);
+            }
+            // Where QoS markings get rewritten (any ICMP error quoting our header)
+            if let Some(quoted) = resp.quoted_dscp {
+                hop.record_quoted_dscp(sent_dscp, quoted);
+            }
+            if let (Some(sent), Some(quoted)) = (sent_ecn, resp.quoted_ecn) {
+                hop.record_quoted_ecn(sent, quoted);
+            }
+        }
+
+        // Fold aliased addresses into one responder while a
+        // switch to them is still inside the hold-down
+        state.apply_aliases(resp.probe_id.ttl);
+        state.label_responders(resp.probe_id.ttl);
+        state.record_load_rtt(resp.probe_id.ttl, resp.rtt);
+        // Which rounds each hop answered, for loss correlation
+        if resp.packet_size.is_none() {
+            state.record_round_outcome(resp.probe_id.seq, resp.flow_id, resp.probe_id.ttl, false);
+        }
+        // Loops show up as repeats within one round; flows take different
+        // paths, so single-flow mode only
+        if self.config.num_flows == 1 {
+            state.record_round_reply(
+                resp.probe_id.seq,
+                resp.probe_id.ttl,
+                resp.responder,
+                resp.response_type,
+            );
+        }
+
+        if let Some(change) = route_change {
+            state.record_event(EventKind::PathChange {
+                ttl: resp.probe_id.ttl,
+                from: change.from_ip,
+                to: change.to_ip,
+            });
+            state.watch_ix_change(resp.probe_id.ttl, change.from_ip, change.to_ip);
+            state.watch_prefix_change(resp.probe_id.ttl, change.from_ip, change.to_ip);
+            if state.config.reset_on_path_change {
+                state.reset_for_path_change(resp.probe_id.ttl, change.from_ip, change.to_ip);
+            }
+        }
+        if let Some((from, to)) = return_change {
+            state.record_event(EventKind::ReturnTtlChange {
+                ttl: resp.probe_id.ttl,
+                ip: resp.responder,
+                from,
+                to,
+            });
+        }
+
+        // Check if we reached the destination
+        if matches!(resp.response_type, IcmpResponseType::EchoReply)
+            && resp.responder == resp.target
+        {
+            state.record_dest_reached(resp.probe_id.ttl, resp.rtt);
+        }
+
+        // Any Frag Needed / Packet Too Big bounds the path MTU, PMTUD or not
+        if let Some(mtu) = resp.reported_mtu
+            && resp.response_type.is_frag_needed()
+        {
+            state.mtu_hints.record_frag_needed(resp.probe_id.ttl, mtu);
+        }
+
+        // PMTUD: Update state if this was a PMTUD probe
+        // Verify packet_size matches current_size to ignore late responses from old sizes
+        if let Some(probe_size) = resp.packet_size
+            && let Some(ref mut pmtud) = state.pmtud
+            && pmtud.phase == PmtudPhase::Searching
+            && probe_size == pmtud.current_size
+        {
+            if resp.response_type.is_frag_needed() {
+                // ICMP Frag Needed - use reported MTU if available
+                if let Some(mtu) = resp.reported_mtu {
+                    pmtud.record_frag_needed(mtu);
+                } else {
+                    // No MTU in response - treat as failure
+                    pmtud.record_failure();
+                }
+            } else {
+                // Any other response = success at this size
+                // (EchoReply, TimeExceeded, PortUnreachable, etc.)
+                pmtud.record_success();
+            }
         }
     }
 
     /// Run the receiver on a dedicated thread (blocking I/O)
     pub fn run_blocking(mut self) -> Result<()> {
-        let identifier = get_identifier();
         // Skip interface binding if recv_any is set (allows asymmetric routing)
         let effective_interface = if self.config.recv_any {
             None
@@ -106,10 +398,34 @@ impl Receiver {
         let socket_info = create_recv_socket_with_interface(self.config.ipv6, effective_interface)?;
         let is_dgram = socket_info.is_dgram;
         let socket = socket_info.socket;
+        // A raw socket bound to an address only receives packets sent to it
+        if let Some(source) = self.config.bind {
+            bind_to_source_ip(&socket, source)?;
+        }
 
         // Set non-blocking with short timeout for polling
         socket.set_read_timeout(Some(Duration::from_millis(100)))?;
 
+        // At high probe rates a full receive buffer drops responses silently;
+        // size it as asked and count overruns so they aren't read as path loss
+        let buffers = apply_buffer_sizes(&socket, self.config.recv_buffer, None);
+        if let Err(ref e) = buffers {
+            eprintln!("Failed to set receive buffer size: {}", e);
+        }
+        let counting_drops = enable_drop_counter(&socket).is_ok();
+        for session in self.sessions.read().values() {
+            let mut state = session.write();
+            let stats = state.socket_stats.get_or_insert_with(Default::default);
+            stats.recv_buffer = buffers.as_ref().ok().map(|b| b.recv);
+            stats.receiver_targets = self.targets.len();
+            if counting_drops {
+                stats.recv_drops = Some(0);
+            }
+        }
+        // The kernel only attaches the counter once it is non-zero
+        let mut kernel_drops: u32 = 0;
+        let mut reported_drops: u32 = 0;
+
         let mut buffer = [0u8; 1500];
 
         loop {
@@ -121,6 +437,10 @@ impl Receiver {
             // FIRST: Drain packets from socket into batch (limited to prevent starvation)
             // This prevents dropping responses that are already queued in the buffer
             let mut batch: Vec<BatchedResponse> = Vec::with_capacity(MAX_DRAIN_BATCH);
+            let mut late: Vec<LateResponse> = Vec::new();
+            let mut unmatched: HashMap<IpAddr, u64> = HashMap::new();
+            let mut stray: u64 = 0;
+            let mut redirects = Vec::new();
             let mut batch_count = 0;
 
             loop {
@@ -134,79 +454,68 @@ impl Receiver {
                         // Reset consecutive error count on successful receive
                         self.consecutive_errors = 0;
                         batch_count += 1;
+                        if let Some(dropped) = recv_result.dropped {
+                            kernel_drops = kernel_drops.max(dropped);
+                        }
 
-                        if let Some(parsed) = parse_icmp_response(
-                            &buffer[..recv_result.len],
+                        let data = &buffer[..recv_result.len];
+                        let udp_id = self.config.udp_id;
+                        let parsed = parse_icmp_response(
+                            data,
                             recv_result.source,
-                            identifier,
+                            self.identity.identifier(),
                             is_dgram,
-                        ) {
-                            // Derive flow_id from source port in ICMP error payload
-                            // For UDP/TCP: src_port = src_port_base + flow_id
-                            // For ICMP: src_port is None, flow_id = 0
-                            // Validate range to avoid mis-attribution from NAT rewrites or unrelated errors
-                            let flow_id = parsed
-                                .src_port
-                                .and_then(|p| {
-                                    if p >= self.config.src_port_base
-                                        && p < self.config.src_port_base
-                                            + self.config.num_flows as u16
-                                    {
-                                        Some((p - self.config.src_port_base) as u8)
-                                    } else {
-                                        // Port outside expected range - treat as ICMP (flow 0)
-                                        None
-                                    }
-                                })
-                                .unwrap_or(0);
-
-                            // Find matching pending probe (key includes flow_id, target, is_pmtud)
-                            let mut found_probe = None;
-                            {
-                                let mut pending = self.pending.write();
-
-                                // If we have original_dest from ICMP error, use direct lookup
-                                if let Some(dest) = parsed.original_dest {
-                                    // Try normal probe first
-                                    if let Some(probe) =
-                                        pending.remove(&(parsed.probe_id, flow_id, dest, false))
-                                    {
-                                        found_probe = Some(probe);
-                                    } else if let Some(probe) =
-                                        pending.remove(&(parsed.probe_id, flow_id, dest, true))
-                                    {
-                                        // Try PMTUD probe
-                                        found_probe = Some(probe);
-                                    }
+                            udp_id,
+                        )
+                        .or_else(|| {
+                            let previous = self.identity.previous()?;
+                            parse_icmp_response(
+                                data,
+                                recv_result.source,
+                                previous,
+                                is_dgram,
+                                udp_id,
+                            )
+                        });
+                        // Echo traffic of another process sharing our identifier
+                        let foreign = parsed.as_ref().is_some_and(|p| {
+                            p.src_port.is_none()
+                                && self.identity.is_foreign(echo_payload_nonce(
+                                    data,
+                                    recv_result.source,
+                                    is_dgram,
+                                ))
+                        });
+                        if foreign {
+                            self.identifier_collision();
+                        }
+                        let Some(parsed) = parsed.filter(|_| !foreign) else {
+                            // A redirect for one of our targets, or someone
+                            // else's traffic on the shared raw socket
+                            match parse_redirect(data, recv_result.source, is_dgram) {
+                                Some(redirect) if self.targets.contains(&redirect.dest) => {
+                                    redirects.push(redirect);
                                 }
-
-                                // Fallback: iterate targets (for Echo Reply which has no quoted dest)
-                                if found_probe.is_none() {
-                                    for target in &self.targets {
-                                        // Try normal probe first
-                                        if let Some(probe) = pending.remove(&(
-                                            parsed.probe_id,
-                                            flow_id,
-                                            *target,
-                                            false,
-                                        )) {
-                                            found_probe = Some(probe);
-                                            break;
-                                        }
-                                        // Try PMTUD probe
-                                        if let Some(probe) = pending.remove(&(
-                                            parsed.probe_id,
-                                            flow_id,
-                                            *target,
-                                            true,
-                                        )) {
-                                            found_probe = Some(probe);
-                                            break;
-                                        }
-                                    }
+                                _ => {
+                                    DUMPS.record(recv_result.source, DumpReason::Stray, data);
+                                    stray += 1;
                                 }
                             }
-                            if let Some(probe) = found_probe {
+                            continue;
+                        };
+                        // Derive flow_id from source port in ICMP error payload
+                        // For UDP/TCP: src_port is one of the configured flow ports
+                        // For ICMP: src_port is None, flow_id = 0
+                        // Ports we didn't send from (NAT rewrites, unrelated errors)
+                        // are treated as flow 0 rather than mis-attributed
+                        let flow_id = parsed
+                            .src_port
+                            .and_then(|p| self.config.flow_ports.flow(p))
+                            .unwrap_or(0);
+
+                        // Find matching pending probe (key includes flow_id, target, is_pmtud)
+                        match self.route(&parsed, flow_id) {
+                            Routed::Probe(probe) => {
                                 let rtt = Instant::now().duration_since(probe.sent_at);
 
                                 // Collect for batched state update
@@ -215,23 +524,36 @@ impl Receiver {
                                     responder: parsed.responder,
                                     rtt,
                                     mpls_labels: parsed.mpls_labels,
+                                    interface: parsed.interface,
                                     response_type: parsed.response_type,
                                     target: probe.target,
                                     flow_id: probe.flow_id,
                                     original_src_port: probe.original_src_port,
                                     returned_src_port: parsed.src_port,
+                                    original_checksum: probe.original_checksum,
+                                    returned_checksum: parsed.quoted_checksum,
                                     packet_size: probe.packet_size,
                                     reported_mtu: parsed.mtu,
                                     response_ttl: recv_result.response_ttl,
                                     quoted_ttl: parsed.quoted_ttl,
+                                    quoted_dscp: parsed.quoted_dscp,
+                                    quoted_ecn: parsed.quoted_ecn,
                                 });
-                            } else {
+                            }
+                            Routed::Late(probe) => {
                                 // Late packet arrival - response came after timeout
-                                #[cfg(debug_assertions)]
-                                eprintln!(
-                                    "Late response: TTL {} seq {} from {} (already timed out)",
-                                    parsed.probe_id.ttl, parsed.probe_id.seq, parsed.responder
-                                );
+                                late.push(LateResponse {
+                                    target: probe.target,
+                                    ttl: parsed.probe_id.ttl,
+                                    rtt: Instant::now().duration_since(probe.sent_at),
+                                });
+                            }
+                            Routed::Unmatched(target) => {
+                                DUMPS.record(recv_result.source, DumpReason::Unmatched, data);
+                                match target {
+                                    Some(target) => *unmatched.entry(target).or_default() += 1,
+                                    None => stray += 1,
+                                }
                             }
                         }
                     }
@@ -266,89 +588,63 @@ impl Receiver {
                 }
             }
 
-            // SECOND: Apply all batched state updates
-            if !batch.is_empty() {
-                let sessions = self.sessions.read();
-                for resp in batch {
-                    // Look up the session for this target
-                    if let Some(session) = sessions.get(&resp.target) {
-                        let mut state = session.write();
-                        if let Some(hop) = state.hop_mut(resp.probe_id.ttl) {
-                            // Record aggregate stats with optional flap detection
-                            // Only detect flaps in single-flow mode (multi-flow expects path changes)
-                            if self.config.num_flows == 1 {
-                                hop.record_response_detecting_flaps(
-                                    resp.responder,
-                                    resp.rtt,
-                                    resp.mpls_labels,
-                                );
-                            } else {
-                                hop.record_response_with_mpls(
-                                    resp.responder,
-                                    resp.rtt,
-                                    resp.mpls_labels,
-                                );
-                            }
-                            // Record per-flow stats for Paris/Dublin traceroute ECMP detection
-                            hop.record_flow_response(resp.flow_id, resp.responder, resp.rtt);
-                            // Record NAT detection result (compare sent vs returned source port)
-                            hop.record_nat_check(resp.original_src_port, resp.returned_src_port);
-                            // Asymmetric routing detection (single-flow mode only, like flap detection)
-                            if self.config.num_flows == 1
-                                && let Some(ttl) = resp.response_ttl
-                            {
-                                hop.record_response_ttl(ttl, self.config.ipv6);
-                            }
-
-                            // TTL manipulation detection (TimeExceeded code 0 only, all flow modes)
-                            // Code 0 = TTL exceeded in transit, Code 1 = fragment reassembly exceeded
-                            // Only code 0 is relevant for TTL manipulation - code 1 can have quoted TTL > 1
-                            if matches!(resp.response_type, IcmpResponseType::TimeExceeded(0))
-                                && let Some(quoted) = resp.quoted_ttl
-                            {
-                                hop.record_ttl_manip_check(quoted);
-                            }
-                        }
+            if kernel_drops != reported_drops {
+                reported_drops = kernel_drops;
+                for session in self.sessions.read().values() {
+                    session.write().record_kernel_drops(kernel_drops as u64);
+                }
+            }
 
-                        // Check if we reached the destination
-                        if matches!(resp.response_type, IcmpResponseType::EchoReply)
-                            && resp.responder == resp.target
-                        {
-                            state.complete = true;
-                            let ttl = resp.probe_id.ttl;
-                            if state.dest_ttl.is_none() || ttl < state.dest_ttl.unwrap() {
-                                state.dest_ttl = Some(ttl);
-                            }
-                        }
+            // SECOND: Apply batched state updates, one lock per target
+            self.passes += 1;
+            PERF.record_depth(&PERF.receiver_batch, batch_count);
+            if !batch.is_empty() || !unmatched.is_empty() || stray > 0 {
+                let mut by_target: HashMap<IpAddr, Vec<BatchedResponse>> = HashMap::new();
+                for resp in batch {
+                    by_target.entry(resp.target).or_default().push(resp);
+                }
+                let sessions = self.sessions.read();
+                for target in fair_order(&self.targets, self.passes) {
+                    let Some(session) = sessions.get(&target) else {
+                        continue;
+                    };
+                    let responses = by_target.remove(&target).unwrap_or_default();
+                    let missed = unmatched.get(&target).copied().unwrap_or(0);
+                    if responses.is_empty() && missed == 0 && stray == 0 {
+                        continue;
+                    }
+                    let waited = Instant::now();
+                    let mut state = session.write();
+                    PERF.record(&PERF.receiver_lock_wait, waited.elapsed());
+                    let stats = state.socket_stats.get_or_insert_with(Default::default);
+                    stats.matched += responses.len() as u64;
+                    stats.unmatched += missed;
+                    stats.stray += stray;
+                    for resp in responses {
+                        self.apply_response(&mut state, resp);
+                    }
+                }
+            }
 
-                        // PMTUD: Update state if this was a PMTUD probe
-                        // Verify packet_size matches current_size to ignore late responses from old sizes
-                        if let Some(probe_size) = resp.packet_size
-                            && let Some(ref mut pmtud) = state.pmtud
-                            && pmtud.phase == PmtudPhase::Searching
-                            && probe_size == pmtud.current_size
-                        {
-                            // Check if this is Fragmentation Needed / Packet Too Big
-                            let is_frag_needed = matches!(
-                                resp.response_type,
-                                IcmpResponseType::DestUnreachable(4)  // IPv4 Frag Needed
-                                    | IcmpResponseType::PacketTooBig // ICMPv6 Packet Too Big
-                            );
+            if !redirects.is_empty() {
+                let sessions = self.sessions.read();
+                for redirect in redirects {
+                    if let Some(session) = sessions.get(&redirect.dest) {
+                        session
+                            .write()
+                            .record_redirect(redirect.router, redirect.gateway);
+                    }
+                }
+            }
 
-                            if is_frag_needed {
-                                // ICMP Frag Needed - use reported MTU if available
-                                if let Some(mtu) = resp.reported_mtu {
-                                    pmtud.record_frag_needed(mtu);
-                                } else {
-                                    // No MTU in response - treat as failure
-                                    pmtud.record_failure();
-                                }
-                            } else {
-                                // Any other response = success at this size
-                                // (EchoReply, TimeExceeded, PortUnreachable, etc.)
-                                pmtud.record_success();
-                            }
-                        }
+            // Late replies only add to the late count; the timeout stands
+            if !late.is_empty() {
+                let sessions = self.sessions.read();
+                for resp in late {
+                    if let Some(session) = sessions.get(&resp.target)
+                        && let Some(hop) = session.write().hop_mut(resp.ttl)
+                    {
+                        hop.record_late(resp.rtt);
                     }
                 }
             }
@@ -361,15 +657,35 @@ impl Receiver {
                 let sessions = self.sessions.read();
                 let timeout = self.config.timeout;
                 // Key is (ProbeId, flow_id, target, is_pmtud) tuple
-                pending.retain(|(probe_id, _flow_id, target, _is_pmtud), probe| {
+                let expired = &mut self.expired;
+                expired.retain(|_, probe| now.duration_since(probe.sent_at) < LATE_WINDOW);
+                pending.retain(|key, probe| {
+                    let (probe_id, _flow_id, target, is_pmtud) = key;
                     if now.duration_since(probe.sent_at) > timeout {
+                        // PMTUD probes that time out are expected (too big)
+                        if !*is_pmtud {
+                            expired.insert(*key, probe.clone());
+                        }
                         // Record timeout (both hop-level and flow-level)
                         if let Some(session) = sessions.get(target) {
                             let mut state = session.write();
+                            // Unanswered sweep probes say nothing about a hop
+                            if probe.packet_size.is_some() && state.df_sweep.is_some() {
+                                return false;
+                            }
                             if let Some(hop) = state.hop_mut(probe_id.ttl) {
                                 hop.record_timeout();
                                 hop.record_flow_timeout(probe.flow_id);
                             }
+                            state.record_dest_timeout(probe_id.ttl);
+                            if !*is_pmtud {
+                                state.record_round_outcome(
+                                    probe_id.seq,
+                                    probe.flow_id,
+                                    probe_id.ttl,
+                                    true,
+                                );
+                            }
 
                             // PMTUD: Record failure for timed out PMTUD probes
                             // Verify packet_size matches current_size to ignore late timeouts from old sizes
@@ -386,6 +702,7 @@ impl Receiver {
                         true
                     }
                 });
+                PERF.record_depth(&PERF.pending_probes, pending.len());
             }
         }
 
@@ -399,9 +716,13 @@ pub fn spawn_receiver(
     pending: PendingMap,
     cancel: CancellationToken,
     config: ReceiverConfig,
+    identity: Arc<ProbeIdentity>,
 ) -> std::thread::JoinHandle<Result<()>> {
     std::thread::spawn(move || {
-        let receiver = Receiver::new(sessions, pending, cancel, config);
+        if let Some(tuning) = config.tuning {
+            tuning.apply();
+        }
+        let receiver = Receiver::new(sessions, pending, cancel, config, identity);
 
         // Catch panics and convert to error with details
         match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| receiver.run_blocking())) {
@@ -419,3 +740,60 @@ pub fn spawn_receiver(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fair_order_rotates() {
+        let targets: Vec<IpAddr> = ["192.0.2.1", "192.0.2.2", "192.0.2.3"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let first = |pass| fair_order(&targets, pass).next().unwrap();
+        assert_eq!(first(0), targets[0]);
+        assert_eq!(first(1), targets[1]);
+        assert_eq!(first(3), targets[0]);
+        // Every target is still visited once per pass
+        let mut order: Vec<_> = fair_order(&targets, 2).collect();
+        assert_eq!(order[0], targets[2]);
+        order.sort();
+        assert_eq!(order, targets);
+        assert_eq!(fair_order(&[], 5).count(), 0);
+    }
+
+    #[test]
+    fn test_take_probe_by_target() {
+        let a: IpAddr = "192.0.2.1".parse().unwrap();
+        let b: IpAddr = "192.0.2.2".parse().unwrap();
+        let id = ProbeId::new(4, 1);
+        let probe = |target| PendingProbe {
+            sent_at: Instant::now(),
+            flow_id: 0,
+            target,
+            original_src_port: None,
+            original_checksum: None,
+            packet_size: None,
+        };
+        let mut probes = HashMap::new();
+        probes.insert((id, 0, a, false), probe(a));
+        probes.insert((id, 0, b, false), probe(b));
+
+        // An error quoting its destination only takes that target's probe
+        let taken = take_probe(&mut probes, id, 0, Some(b), &[a, b]).unwrap();
+        assert_eq!(taken.target, b);
+        assert!(take_probe(&mut probes, id, 0, Some(b), &[a, b]).is_none());
+        // Same probe id, but quoting a target with nothing pending: no match
+        let c: IpAddr = "192.0.2.3".parse().unwrap();
+        assert!(take_probe(&mut probes, id, 0, Some(c), &[a, b, c]).is_none());
+        assert!(probes.contains_key(&(id, 0, a, false)));
+        // Without a destination, each target is tried
+        assert_eq!(
+            take_probe(&mut probes, id, 0, None, &[a, b])
+                .unwrap()
+                .target,
+            a
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Multi-target probe scheduling
+//!
+//! With many targets every engine would otherwise fire its first round at
+//! the same instant, and every round after that in lockstep — a burst of
+//! `targets × hops` packets through the same first-hop router. The schedule
+//! decides when each engine's rounds start, and an optional global budget
+//! (a token bucket) caps individual probes across all engines.
+
+use parking_lot::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::{Config, Schedule};
+
+/// When one engine runs its probe rounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundSlot {
+    /// Delay before the first round
+    pub offset: Duration,
+    /// Time between this engine's rounds
+    pub period: Duration,
+}
+
+impl RoundSlot {
+    /// Every round at the configured interval, starting immediately
+    pub fn immediate(interval: Duration) -> Self {
+        Self {
+            offset: Duration::ZERO,
+            period: interval,
+        }
+    }
+
+    /// Ticker for this engine's rounds
+    pub fn interval(&self) -> tokio::time::Interval {
+        self.ticker(self.offset)
+    }
+
+    /// Ticker continuing an engine that is already running: the next round
+    /// comes one period from now
+    pub fn resumed(&self) -> tokio::time::Interval {
+        self.ticker(self.period)
+    }
+
+    fn ticker(&self, delay: Duration) -> tokio::time::Interval {
+        let mut interval = tokio::time::interval_at(Instant::now() + delay, self.period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        interval
+    }
+
+    /// The same slot after the probe interval changes from `from` to `to`
+    pub fn rescaled(&self, from: Duration, to: Duration) -> Self {
+        let factor = to.as_secs_f64() / from.as_secs_f64();
+        Self {
+            offset: self.offset.mul_f64(factor),
+            period: self.period.mul_f64(factor),
+        }
+    }
+}
+
+/// Compute the round slot for engine `index` of `targets`
+pub fn round_slot(config: &Config, index: usize, targets: usize) -> RoundSlot {
+    let interval = config.interval;
+    let targets = targets.max(1);
+    match config.schedule {
+        Schedule::Parallel => RoundSlot::immediate(interval),
+        // Spread first rounds evenly over one interval
+        Schedule::Staggered => RoundSlot {
+            offset: interval.mul_f64(index as f64 / targets as f64),
+            period: interval,
+        },
+        // Batches take turns: each interval belongs to one batch
+        Schedule::RoundRobin => {
+            let batch = config.schedule_batch.unwrap_or(1).max(1);
+            let batches = targets.div_ceil(batch);
+            RoundSlot {
+                offset: interval * (index / batch) as u32,
+                period: interval * batches as u32,
+            }
+        }
+    }
+}
+
+/// Probes-per-second budget shared by every engine.
+///
+/// A token bucket holding `burst` probes and refilled at `rate` per second,
+/// kept as the time the bucket would next be full (GCRA). Each probe
+/// reserves the next free send slot, so once the bucket is empty slots are
+/// spaced `1 / rate` apart no matter how many engines are asking. PMTUD
+/// probes draw from the same bucket.
+pub struct ProbeBudget {
+    rate: u32,
+    burst: u32,
+    spacing: Duration,
+    /// Time the bucket refills completely ("theoretical arrival time")
+    full_at: Mutex<Instant>,
+}
+
+impl ProbeBudget {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            rate: rate.max(1),
+            burst: burst.max(1),
+            spacing: Duration::from_secs_f64(1.0 / rate.max(1) as f64),
+            full_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Reserve the next send slot and return its start time
+    fn reserve(&self) -> Instant {
+        let now = Instant::now();
+        let mut full_at = self.full_at.lock();
+        let base = (*full_at).max(now);
+        // Up to `burst - 1` probes may go ahead of the steady spacing
+        let slot = base
+            .checked_sub(self.spacing * (self.burst - 1))
+            .map_or(now, |t| t.max(now));
+        *full_at = base + self.spacing;
+        slot
+    }
+
+    /// Wait until this probe may be sent; returns how long it was held back
+    pub async fn acquire(&self) -> Duration {
+        let slot = self.reserve();
+        let wait = slot.saturating_duration_since(Instant::now());
+        tokio::time::sleep_until(slot).await;
+        wait
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(schedule: Schedule, batch: Option<usize>) -> Config {
+        Config {
+            interval: Duration::from_secs(1),
+            schedule,
+            schedule_batch: batch,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_round_slot_parallel() {
+        let cfg = config(Schedule::Parallel, None);
+        assert_eq!(round_slot(&cfg, 3, 4), RoundSlot::immediate(cfg.interval));
+    }
+
+    #[test]
+    fn test_round_slot_staggered() {
+        let cfg = config(Schedule::Staggered, None);
+        let slots: Vec<_> = (0..4).map(|i| round_slot(&cfg, i, 4)).collect();
+        assert_eq!(slots[0].offset, Duration::ZERO);
+        assert_eq!(slots[2].offset, Duration::from_millis(500));
+        assert_eq!(slots[3].offset, Duration::from_millis(750));
+        assert!(slots.iter().all(|s| s.period == cfg.interval));
+    }
+
+    #[test]
+    fn test_round_slot_round_robin() {
+        // 5 targets in batches of 2: three batches take turns
+        let cfg = config(Schedule::RoundRobin, Some(2));
+        let offsets: Vec<_> = (0..5)
+            .map(|i| round_slot(&cfg, i, 5).offset.as_secs())
+            .collect();
+        assert_eq!(offsets, vec![0, 0, 1, 1, 2]);
+        assert_eq!(round_slot(&cfg, 4, 5).period, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_round_slot_rescaled() {
+        let cfg = config(Schedule::RoundRobin, Some(2));
+        let slot = round_slot(&cfg, 2, 5).rescaled(cfg.interval, Duration::from_millis(500));
+        assert_eq!(slot.offset, Duration::from_millis(500));
+        assert_eq!(slot.period, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_probe_budget_spacing() {
+        let budget = ProbeBudget::new(10, 1);
+        let first = budget.reserve();
+        let slots: Vec<_> = (0..2).map(|_| budget.reserve() - first).collect();
+        assert_eq!(
+            slots,
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+
+        // An idle budget doesn't bank slots for a later burst
+        let budget = ProbeBudget::new(1000, 1);
+        budget.reserve();
+        std::thread::sleep(Duration::from_millis(20));
+        let before = Instant::now();
+        let slot = budget.reserve();
+        assert!(slot >= before);
+        assert_eq!(budget.reserve() - slot, Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_probe_budget_burst() {
+        // A full bucket of 3 sends at once, then refills at 10 per second
+        let budget = ProbeBudget::new(10, 3);
+        let before = Instant::now();
+        let burst: Vec<_> = (0..3).map(|_| budget.reserve()).collect();
+        let after = Instant::now();
+        assert!(burst.iter().all(|&slot| slot >= before && slot <= after));
+        let next = budget.reserve();
+        assert!(next >= before + Duration::from_millis(100));
+        assert!(next <= after + Duration::from_millis(100));
+        assert_eq!(budget.reserve() - next, Duration::from_millis(100));
+    }
+}
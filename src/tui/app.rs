@@ -7,42 +7,107 @@ use crossterm::terminal::{
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout};
-use ratatui::style::Style;
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::widgets::Paragraph;
 use scopeguard::defer;
 use std::io::stdout;
 use std::net::IpAddr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
 use tokio_util::sync::CancellationToken;
 
-use crate::export::export_json_file;
+use crate::config::PluginHook;
+use crate::export::{export_json_file, export_view};
+use crate::plugin::run_export_plugins;
+use crate::sinks::EventCursor;
+use crate::sinks::dest::{DestState, transition_text};
+use crate::state::EventKind;
+use crate::state::Lang;
 use crate::state::Session;
+use crate::state::window::window_label;
+use crate::trace::dump::DUMPS;
+use crate::trace::perf::PERF;
 use crate::trace::receiver::SessionMap;
+use crate::tui::i18n::strings;
+use crate::tui::layout::scroll_offset;
+use crate::tui::saved::SavedUi;
 use crate::tui::theme::Theme;
-use crate::tui::views::{HelpView, HopDetailView, MainView};
+use crate::tui::views::{
+    CompareView, DiagnosticsView, HelpView, HopDetailView, MainView, OverviewView, TimelineView,
+};
+use crate::tui::widgets::SparklineSource;
+
+/// Maximum length of a note typed in the TUI
+const MAX_NOTE_LEN: usize = 200;
+
+/// Settings change pushed to the TUI when config.toml is reloaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigNotice {
+    /// Theme to switch to
+    pub theme: Option<String>,
+    /// What changed, shown in the status bar
+    pub message: String,
+}
 
 /// UI state
 #[derive(Default)]
 pub struct UiState {
     /// Currently selected hop index (0-indexed into displayed hops)
     pub selected: Option<usize>,
+    /// First hop row shown (kept so the selection stays in view)
+    pub scroll: usize,
     /// Whether probing is paused
     pub paused: bool,
     /// Show help overlay
     pub show_help: bool,
     /// Show expanded hop view
     pub show_hop_detail: bool,
+    /// Show event timeline overlay
+    pub show_timeline: bool,
+    /// Show socket diagnostics overlay
+    pub show_diagnostics: bool,
+    /// Show the side-by-side path comparison (multi-target)
+    pub show_compare: bool,
+    /// Show the per-target dashboard (multi-target)
+    pub show_overview: bool,
+    /// Target highlighted in the dashboard
+    pub overview_selected: usize,
+    /// Show sliding-window stats instead of cumulative (--stats-window)
+    pub windowed: bool,
+    /// What the main table's sparkline plots
+    pub sparkline: SparklineSource,
     /// Status message to display
     pub status_message: Option<(String, std::time::Instant)>,
     /// Current theme index
     pub theme_index: usize,
     /// Currently selected target index (for multi-target mode)
     pub selected_target: usize,
+    /// Note being typed (Some while the note prompt is open)
+    pub note_input: Option<String>,
+    /// Hop the note being typed will attach to (None = whole session)
+    pub note_ttl: Option<u8>,
+    /// Destination transition the status bar is flashing for (--dest-alert flash)
+    pub flash: Option<DestState>,
+    /// Language of the TUI's text (--lang)
+    pub lang: Lang,
 }
 
 impl UiState {
     pub fn set_status(&mut self, msg: impl Into<String>) {
         self.status_message = Some((msg.into(), std::time::Instant::now()));
+        self.flash = None;
+    }
+
+    /// Show a status message with the status bar flashing
+    pub fn flash_status(&mut self, msg: impl Into<String>, state: DestState) {
+        self.set_status(msg);
+        self.flash = Some(state);
+    }
+
+    /// Whether the flash is in its highlighted half-second
+    fn flash_on(&self) -> Option<DestState> {
+        let (_, time) = self.status_message.as_ref()?;
+        (time.elapsed().as_millis() / 500 % 2 == 0).then_some(self.flash?)
     }
 
     pub fn clear_old_status(&mut self) {
@@ -50,6 +115,7 @@ impl UiState {
             && time.elapsed() > Duration::from_secs(3)
         {
             self.status_message = None;
+            self.flash = None;
         }
     }
 }
@@ -60,6 +126,7 @@ pub async fn run_tui(
     targets: Vec<IpAddr>,
     cancel: CancellationToken,
     initial_theme: Theme,
+    notices: Option<UnboundedReceiver<ConfigNotice>>,
 ) -> Result<String> {
     // Setup terminal
     enable_raw_mode()?;
@@ -81,10 +148,24 @@ pub async fn run_tui(
         .position(|&name| Theme::by_name(name).name() == initial_theme.name())
         .unwrap_or(0);
 
+    let lang = sessions
+        .read()
+        .values()
+        .next()
+        .map(|s| s.read().config.lang)
+        .unwrap_or_default();
     let mut ui_state = UiState {
         theme_index: initial_index,
+        lang,
         ..Default::default()
     };
+    // View toggles from the last run
+    let has_window = sessions
+        .read()
+        .values()
+        .any(|s| s.read().config.stats_window.is_some());
+    let mut saved = SavedUi::load();
+    saved.restore(&mut ui_state, has_window);
     let tick_rate = Duration::from_millis(100);
 
     run_app(
@@ -94,9 +175,14 @@ pub async fn run_tui(
         &mut ui_state,
         cancel.clone(),
         tick_rate,
+        notices,
     )
     .await?;
 
+    // Best effort, like the theme
+    saved.remember(&ui_state, has_window);
+    let _ = saved.save();
+
     // Return final theme name for persistence
     Ok(theme_names[ui_state.theme_index].to_string())
 }
@@ -108,6 +194,7 @@ async fn run_app<B>(
     ui_state: &mut UiState,
     cancel: CancellationToken,
     tick_rate: Duration,
+    mut notices: Option<UnboundedReceiver<ConfigNotice>>,
 ) -> Result<()>
 where
     B: ratatui::backend::Backend,
@@ -116,15 +203,58 @@ where
     let theme_names = Theme::list();
     let num_targets = targets.len();
 
+    // Destination transitions to flash for; events already recorded (e.g.
+    // in a replay) are skipped
+    let flash = sessions
+        .read()
+        .values()
+        .any(|s| s.read().config.dest_alert.flash);
+    let mut transitions = EventCursor::default();
+    transitions.poll(&sessions);
+
+    // Results of export plugins, which run in the background
+    let (plugin_tx, mut plugin_rx) = unbounded_channel::<String>();
+    let mut last_tick: Option<Instant> = None;
+
     loop {
         // Check cancellation
         if cancel.is_cancelled() {
             break;
         }
+        if let Some(previous) = last_tick.replace(Instant::now()) {
+            PERF.record_tick(previous.elapsed(), tick_rate);
+        }
 
         // Clear old status messages
         ui_state.clear_old_status();
 
+        // Apply config reloads
+        while let Some(notice) = notices.as_mut().and_then(|rx| rx.try_recv().ok()) {
+            if let Some(index) = notice.theme.as_deref().and_then(|name| {
+                let theme = Theme::by_name(name);
+                theme_names
+                    .iter()
+                    .position(|&n| Theme::by_name(n).name() == theme.name())
+            }) {
+                ui_state.theme_index = index;
+            }
+            ui_state.set_status(notice.message);
+        }
+        while let Ok(message) = plugin_rx.try_recv() {
+            ui_state.set_status(message);
+        }
+
+        if flash {
+            for item in transitions.poll(&sessions) {
+                if let (Some(state), Some(text)) = (
+                    DestState::from_event(&item.event.kind),
+                    transition_text(&item.target, &item.event.kind),
+                ) {
+                    ui_state.flash_status(text, state);
+                }
+            }
+        }
+
         // Get current theme
         let theme = Theme::by_name(theme_names[ui_state.theme_index]);
 
@@ -132,15 +262,43 @@ where
         let current_target = targets[ui_state.selected_target];
 
         // Draw
+        let draw_started = Instant::now();
         terminal.draw(|f| {
+            let waited = Instant::now();
             let sessions_read = sessions.read();
             if let Some(state) = sessions_read.get(&current_target) {
                 let session = state.read();
-                draw_ui(f, &session, ui_state, &theme, num_targets);
+                PERF.record(&PERF.ui_lock_wait, waited.elapsed());
+                draw_ui(f, &session, &mut *ui_state, &theme, num_targets);
+            }
+            if ui_state.show_compare {
+                // Locked one at a time, in target order
+                let guards: Vec<_> = targets
+                    .iter()
+                    .filter_map(|t| sessions_read.get(t))
+                    .map(|s| s.read())
+                    .collect();
+                let all = guards.iter().map(|g| &**g).collect();
+                f.render_widget(CompareView::new(all, &theme), f.area());
+            }
+            if ui_state.show_overview {
+                let guards: Vec<_> = targets
+                    .iter()
+                    .filter_map(|t| sessions_read.get(t))
+                    .map(|s| s.read())
+                    .collect();
+                let all = guards.iter().map(|g| &**g).collect();
+                f.render_widget(
+                    OverviewView::new(all, ui_state.overview_selected, &theme),
+                    f.area(),
+                );
             }
         })?;
+        PERF.record(&PERF.draw, draw_started.elapsed());
 
-        // Handle input with timeout
+        // Handle input with timeout. Resize events need nothing here: the
+        // next draw lays the table out for the new size and re-clamps the
+        // scroll offset around the selection.
         if event::poll(tick_rate)?
             && let Event::Key(key) = event::read()?
         {
@@ -148,17 +306,130 @@ where
                 continue;
             }
 
+            // Note prompt captures all keys until submitted or cancelled
+            if let Some(ref mut input) = ui_state.note_input {
+                match key.code {
+                    KeyCode::Enter => {
+                        let text = ui_state.note_input.take().unwrap_or_default();
+                        let sessions_read = sessions.read();
+                        if let Some(state) = sessions_read.get(&current_target) {
+                            state.write().add_note(ui_state.note_ttl, &text);
+                        }
+                        if !text.trim().is_empty() {
+                            ui_state.set_status(strings(ui_state.lang).note_added);
+                        }
+                    }
+                    KeyCode::Esc => {
+                        ui_state.note_input = None;
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) if input.chars().count() < MAX_NOTE_LEN => {
+                        input.push(c);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
             // Handle overlays first
             if ui_state.show_help {
                 ui_state.show_help = false;
                 continue;
             }
 
+            if ui_state.show_timeline {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('l') | KeyCode::Char('q') => {
+                        ui_state.show_timeline = false;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            if ui_state.show_compare {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => {
+                        ui_state.show_compare = false;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            if ui_state.show_overview {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('o') | KeyCode::Char('q') => {
+                        ui_state.show_overview = false;
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        ui_state.overview_selected = ui_state.overview_selected.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        ui_state.overview_selected =
+                            (ui_state.overview_selected + 1).min(num_targets - 1);
+                    }
+                    KeyCode::Enter => {
+                        ui_state.show_overview = false;
+                        switch_target(ui_state, ui_state.overview_selected, &targets, &sessions);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            if ui_state.show_diagnostics {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('d') | KeyCode::Char('q') => {
+                        ui_state.show_diagnostics = false;
+                    }
+                    KeyCode::Char('e') if PERF.enabled() => {
+                        let text = strings(ui_state.lang);
+                        ui_state.set_status(match PERF.snapshot().save() {
+                            Ok(filename) => (text.saved_timings)(&filename),
+                            Err(e) => (text.saving_timings_failed)(&format!("{:#}", e)),
+                        });
+                    }
+                    KeyCode::Char('x') if DUMPS.enabled() => {
+                        let text = strings(ui_state.lang);
+                        ui_state.set_status(match DUMPS.save() {
+                            Ok(filename) => (text.saved_packets)(&filename),
+                            Err(e) => (text.saving_packets_failed)(&format!("{:#}", e)),
+                        });
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
             if ui_state.show_hop_detail {
                 match key.code {
                     KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
                         ui_state.show_hop_detail = false;
                     }
+                    // Redo the selected hop's lookups past their caches
+                    KeyCode::Char('r') => {
+                        let state = sessions.read().get(&current_target).cloned();
+                        if let (Some(state), Some(idx)) = (state, ui_state.selected) {
+                            let mut session = state.write();
+                            let ttl = session
+                                .hops
+                                .iter()
+                                .filter(|h| h.sent > 0)
+                                .nth(idx)
+                                .map(|h| h.ttl);
+                            let flagged = ttl.map_or(0, |ttl| session.request_lookup_refresh(ttl));
+                            drop(session);
+                            let text = strings(ui_state.lang);
+                            ui_state.set_status(if flagged > 0 {
+                                (text.refreshing_lookups)(flagged)
+                            } else {
+                                text.no_lookups.to_string()
+                            });
+                        }
+                    }
                     _ => {}
                 }
                 continue;
@@ -178,49 +449,44 @@ where
                     cancel.cancel();
                     break;
                 }
+                KeyCode::Char('c') if num_targets > 1 => {
+                    ui_state.show_compare = true;
+                }
+                KeyCode::Char('o') if num_targets > 1 => {
+                    ui_state.overview_selected = ui_state.selected_target;
+                    ui_state.show_overview = true;
+                }
                 KeyCode::Char('?') | KeyCode::Char('h') => {
                     ui_state.show_help = true;
                 }
+                KeyCode::Char('n') => {
+                    // Attach to the selected hop, or the whole session if none
+                    let sessions_read = sessions.read();
+                    ui_state.note_ttl = ui_state.selected.and_then(|idx| {
+                        sessions_read.get(&current_target).and_then(|state| {
+                            state
+                                .read()
+                                .hops
+                                .iter()
+                                .filter(|h| h.sent > 0)
+                                .nth(idx)
+                                .map(|h| h.ttl)
+                        })
+                    });
+                    ui_state.note_input = Some(String::new());
+                }
                 // Target switching
-                KeyCode::Tab | KeyCode::Char('n') => {
-                    if num_targets > 1 {
-                        ui_state.selected_target = (ui_state.selected_target + 1) % num_targets;
-                        ui_state.selected = None; // Reset hop selection when switching targets
-                        let target = targets[ui_state.selected_target];
-                        ui_state.set_status(format!(
-                            "Target {}/{}: {}",
-                            ui_state.selected_target + 1,
-                            num_targets,
-                            target
-                        ));
-                        // Sync pause state with new target's session
-                        let sessions_read = sessions.read();
-                        if let Some(state) = sessions_read.get(&target) {
-                            ui_state.paused = state.read().paused;
-                        }
-                    }
+                KeyCode::Tab if num_targets > 1 => {
+                    let next = (ui_state.selected_target + 1) % num_targets;
+                    switch_target(ui_state, next, &targets, &sessions);
                 }
-                KeyCode::BackTab | KeyCode::Char('N') => {
-                    if num_targets > 1 {
-                        ui_state.selected_target = if ui_state.selected_target == 0 {
-                            num_targets - 1
-                        } else {
-                            ui_state.selected_target - 1
-                        };
-                        ui_state.selected = None; // Reset hop selection when switching targets
-                        let target = targets[ui_state.selected_target];
-                        ui_state.set_status(format!(
-                            "Target {}/{}: {}",
-                            ui_state.selected_target + 1,
-                            num_targets,
-                            target
-                        ));
-                        // Sync pause state with new target's session
-                        let sessions_read = sessions.read();
-                        if let Some(state) = sessions_read.get(&target) {
-                            ui_state.paused = state.read().paused;
-                        }
-                    }
+                KeyCode::BackTab | KeyCode::Char('N') if num_targets > 1 => {
+                    let prev = if ui_state.selected_target == 0 {
+                        num_targets - 1
+                    } else {
+                        ui_state.selected_target - 1
+                    };
+                    switch_target(ui_state, prev, &targets, &sessions);
                 }
                 KeyCode::Char('p') => {
                     ui_state.paused = !ui_state.paused;
@@ -229,8 +495,21 @@ where
                     if let Some(state) = sessions_read.get(&current_target) {
                         let mut session = state.write();
                         session.paused = ui_state.paused;
+                        let description = if ui_state.paused {
+                            "probing paused"
+                        } else {
+                            "probing resumed"
+                        };
+                        session.record_event(EventKind::ConfigChange {
+                            description: description.to_string(),
+                        });
                     }
-                    ui_state.set_status(if ui_state.paused { "Paused" } else { "Resumed" });
+                    let text = strings(ui_state.lang);
+                    ui_state.set_status(if ui_state.paused {
+                        text.paused
+                    } else {
+                        text.resumed
+                    });
                 }
                 KeyCode::Char('r') => {
                     // Reset current target's statistics
@@ -239,24 +518,83 @@ where
                         let mut session = state.write();
                         session.reset_stats();
                     }
-                    ui_state.set_status("Stats reset");
+                    ui_state.set_status(strings(ui_state.lang).stats_reset);
+                }
+                KeyCode::Char('l') => {
+                    ui_state.show_timeline = true;
+                }
+                KeyCode::Char('d') => {
+                    ui_state.show_diagnostics = true;
+                }
+                KeyCode::Char('w') => {
+                    let sessions_read = sessions.read();
+                    let window = sessions_read
+                        .get(&current_target)
+                        .and_then(|state| state.read().config.stats_window);
+                    match window {
+                        Some(length) => {
+                            ui_state.windowed = !ui_state.windowed;
+                            let text = strings(ui_state.lang);
+                            ui_state.set_status(if ui_state.windowed {
+                                (text.stats_last)(&window_label(length))
+                            } else {
+                                text.stats_since_start.to_string()
+                            });
+                        }
+                        None => ui_state.set_status(strings(ui_state.lang).no_stats_window),
+                    }
+                }
+                KeyCode::Char('s') => {
+                    ui_state.sparkline = ui_state.sparkline.next();
+                    ui_state.set_status((strings(ui_state.lang).sparkline)(
+                        ui_state.sparkline.title(),
+                    ));
                 }
                 KeyCode::Char('t') => {
                     // Cycle through themes
                     ui_state.theme_index = (ui_state.theme_index + 1) % theme_names.len();
                     let new_theme = theme_names[ui_state.theme_index];
-                    ui_state.set_status(format!("Theme: {}", new_theme));
+                    ui_state.set_status((strings(ui_state.lang).theme)(new_theme));
                 }
                 KeyCode::Char('e') => {
                     let sessions_read = sessions.read();
                     if let Some(state) = sessions_read.get(&current_target) {
                         let session = state.read();
-                        match export_json_file(&session) {
+                        let exported = export_view(&session, session.config.redact)
+                            .and_then(|export| export_json_file(&export));
+                        match exported {
                             Ok(filename) => {
-                                ui_state.set_status(format!("Exported to {}", filename));
+                                ui_state.set_status((strings(ui_state.lang).exported)(&filename));
+                                let commands = session.config.plugin_commands(PluginHook::Export);
+                                if !commands.is_empty()
+                                    && let Ok(json) = serde_json::to_vec(&*session)
+                                {
+                                    let tx = plugin_tx.clone();
+                                    tokio::spawn(async move {
+                                        let results = run_export_plugins(&commands, json).await;
+                                        let failed: Vec<_> =
+                                            results.iter().filter(|(_, r)| r.is_err()).collect();
+                                        let _ = tx.send(match failed.first() {
+                                            Some((command, Err(e))) => {
+                                                format!(
+                                                    "Export plugin '{}' failed: {:#}",
+                                                    command, e
+                                                )
+                                            }
+                                            _ => format!(
+                                                "Exported to {} and {} plugin{}",
+                                                filename,
+                                                results.len(),
+                                                if results.len() == 1 { "" } else { "s" }
+                                            ),
+                                        });
+                                    });
+                                }
                             }
                             Err(e) => {
-                                ui_state.set_status(format!("Export failed: {}", e));
+                                ui_state.set_status((strings(ui_state.lang).export_failed)(
+                                    &e.to_string(),
+                                ));
                             }
                         }
                     }
@@ -265,7 +603,7 @@ where
                     let sessions_read = sessions.read();
                     if let Some(state) = sessions_read.get(&current_target) {
                         let session = state.read();
-                        let hop_count = session.hops.iter().filter(|h| h.sent > 0).count();
+                        let hop_count = MainView::row_count(&session);
                         if hop_count > 0 {
                             ui_state.selected = Some(match ui_state.selected {
                                 Some(i) if i > 0 => i - 1,
@@ -279,7 +617,7 @@ where
                     let sessions_read = sessions.read();
                     if let Some(state) = sessions_read.get(&current_target) {
                         let session = state.read();
-                        let hop_count = session.hops.iter().filter(|h| h.sent > 0).count();
+                        let hop_count = MainView::row_count(&session);
                         if hop_count > 0 {
                             ui_state.selected = Some(match ui_state.selected {
                                 Some(i) if i < hop_count - 1 => i + 1,
@@ -289,10 +627,8 @@ where
                         }
                     }
                 }
-                KeyCode::Enter => {
-                    if ui_state.selected.is_some() {
-                        ui_state.show_hop_detail = true;
-                    }
+                KeyCode::Enter if ui_state.selected.is_some() => {
+                    ui_state.show_hop_detail = true;
                 }
                 KeyCode::Esc => {
                     ui_state.selected = None;
@@ -305,10 +641,28 @@ where
     Ok(())
 }
 
+/// Show target `index` in the main view (Tab/BackTab/N, dashboard Enter)
+fn switch_target(ui_state: &mut UiState, index: usize, targets: &[IpAddr], sessions: &SessionMap) {
+    ui_state.selected_target = index;
+    ui_state.selected = None; // Reset hop selection when switching targets
+    ui_state.scroll = 0;
+    let target = targets[index];
+    ui_state.set_status((strings(ui_state.lang).target)(
+        index + 1,
+        targets.len(),
+        &target.to_string(),
+    ));
+    // Sync pause state with new target's session
+    let sessions_read = sessions.read();
+    if let Some(state) = sessions_read.get(&target) {
+        ui_state.paused = state.read().paused;
+    }
+}
+
 fn draw_ui(
     f: &mut ratatui::Frame,
     session: &Session,
-    ui_state: &UiState,
+    ui_state: &mut UiState,
     theme: &Theme,
     num_targets: usize,
 ) {
@@ -320,27 +674,66 @@ fn draw_ui(
         .constraints([Constraint::Min(0), Constraint::Length(1)])
         .split(area);
 
+    // Scroll for the current height, so a resize keeps the selection in view
+    ui_state.scroll = scroll_offset(
+        ui_state.selected,
+        ui_state.scroll,
+        MainView::visible_rows(chunks[0].height),
+        MainView::row_count(session),
+    );
+
     // Main view (with target indicator)
     let main_view = MainView::new(session, ui_state.selected, ui_state.paused, theme)
-        .with_target_info(ui_state.selected_target + 1, num_targets);
+        .with_target_info(ui_state.selected_target + 1, num_targets)
+        .with_windowed(ui_state.windowed)
+        .with_scroll(ui_state.scroll)
+        .with_sparkline(ui_state.sparkline);
     f.render_widget(main_view, chunks[0]);
 
-    // Status bar
-    let status_text = if let Some((ref msg, _)) = ui_state.status_message {
+    // Status bar (doubles as the note prompt)
+    let status_text = if let Some(ref input) = ui_state.note_input {
+        let scope = ui_state
+            .note_ttl
+            .map(|t| format!("hop {}", t))
+            .unwrap_or_else(|| "session".to_string());
+        format!("Note ({}): {}_  [Enter save, Esc cancel]", scope, input)
+    } else if let Some((ref msg, _)) = ui_state.status_message {
         msg.clone()
     } else if num_targets > 1 {
-        "q quit | Tab next target | p pause | r reset | t theme | e export | ? help".to_string()
+        strings(ui_state.lang).hints_multi.to_string()
     } else {
-        "q quit | p pause | r reset | t theme | e export | ? help | \u{2191}\u{2193} select"
-            .to_string()
+        strings(ui_state.lang).hints.to_string()
     };
 
-    let status_bar = Paragraph::new(status_text).style(Style::default().fg(theme.text_dim));
+    let status_style = match ui_state.flash_on() {
+        Some(state) => Style::default()
+            .fg(Color::Black)
+            .bg(match state {
+                DestState::Down => theme.error,
+                DestState::Up => theme.success,
+            })
+            .add_modifier(Modifier::BOLD),
+        None => Style::default().fg(theme.text_dim),
+    };
+    let status_bar = Paragraph::new(status_text).style(status_style);
     f.render_widget(status_bar, chunks[1]);
 
     // Overlays
     if ui_state.show_help {
-        f.render_widget(HelpView::new(theme), area);
+        f.render_widget(HelpView::new(theme, ui_state.lang), area);
+    }
+
+    if ui_state.show_timeline {
+        f.render_widget(TimelineView::new(session, theme), area);
+    }
+
+    if ui_state.show_diagnostics {
+        f.render_widget(
+            DiagnosticsView::new(session, theme)
+                .with_perf(PERF.enabled().then(|| PERF.snapshot()))
+                .with_dumps(DUMPS.enabled().then(|| DUMPS.snapshot())),
+            area,
+        );
     }
 
     if ui_state.show_hop_detail
@@ -348,7 +741,53 @@ fn draw_ui(
     {
         let hops: Vec<_> = session.hops.iter().filter(|h| h.sent > 0).collect();
         if let Some(hop) = hops.get(selected) {
-            f.render_widget(HopDetailView::new(hop, theme), area);
+            let notes = session.hop_notes(hop.ttl).collect();
+            let links = session
+                .inferred_links()
+                .into_iter()
+                .filter(|l| l.near_ttl == hop.ttl || l.near_ttl + 1 == hop.ttl)
+                .collect();
+            let tunnels = session
+                .inferred_tunnels()
+                .into_iter()
+                .filter(|t| t.contains(hop.ttl))
+                .collect();
+            let remark = session
+                .dscp_remarks()
+                .into_iter()
+                .find(|r| r.ttl == hop.ttl);
+            let ecn_change = session.ecn_changes().into_iter().find(|c| c.ttl == hop.ttl);
+            f.render_widget(
+                HopDetailView::new(hop, theme)
+                    .with_notes(notes, &session.config)
+                    .with_links(links)
+                    .with_tunnels(tunnels)
+                    .with_branches(session.path_tree().branches_at(hop.ttl))
+                    .with_shared_loss(
+                        session
+                            .loss_correlation()
+                            .into_iter()
+                            .filter(|p| p.shared() && (p.ttl_a == hop.ttl || p.ttl_b == hop.ttl))
+                            .collect(),
+                    )
+                    .with_loop(
+                        session
+                            .routing_loop
+                            .as_ref()
+                            .filter(|l| l.contains(hop.ttl)),
+                    )
+                    .with_dscp_remark(remark)
+                    .with_ecn_change(ecn_change)
+                    .with_load(
+                        session
+                            .load
+                            .as_ref()
+                            .and_then(|load| load.hops.get(&hop.ttl).copied()),
+                    )
+                    .with_distance(session.hop_distance(hop))
+                    .with_previous_path(session.previous_path(hop.ttl)),
+                area,
+            );
         }
     }
 }
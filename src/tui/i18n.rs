@@ -0,0 +1,205 @@
+//! Translated TUI text (`--lang`)
+//!
+//! Each language is one `Strings` table, so a missing translation is a
+//! compile error rather than a blank in the UI. Messages with values in
+//! them are plain functions. Column headings only list the ones that
+//! differ from English, and must fit the column's width.
+
+use crate::state::Lang;
+use crate::tui::layout::Column;
+
+pub struct Strings {
+    pub help_title: &'static str,
+    /// Key and what it does, general then navigation
+    pub help_keys: &'static [(&'static str, &'static str)],
+    pub help_nav: &'static [(&'static str, &'static str)],
+    pub help_close: &'static str,
+    /// Key hints in the status bar
+    pub hints: &'static str,
+    pub hints_multi: &'static str,
+    /// Main view title
+    pub paused_badge: &'static str,
+    pub probes: fn(u64) -> String,
+    pub interval: fn(u64) -> String,
+    /// Column headings that differ from `Column::title`
+    columns: &'static [(Column, &'static str)],
+    // Status messages
+    pub paused: &'static str,
+    pub resumed: &'static str,
+    pub stats_reset: &'static str,
+    pub note_added: &'static str,
+    pub stats_since_start: &'static str,
+    pub stats_last: fn(&str) -> String,
+    pub no_stats_window: &'static str,
+    pub theme: fn(&str) -> String,
+    pub sparkline: fn(&str) -> String,
+    pub exported: fn(&str) -> String,
+    pub export_failed: fn(&str) -> String,
+    pub saved_timings: fn(&str) -> String,
+    pub saving_timings_failed: fn(&str) -> String,
+    pub saved_packets: fn(&str) -> String,
+    pub saving_packets_failed: fn(&str) -> String,
+    pub target: fn(usize, usize, &str) -> String,
+    pub refreshing_lookups: fn(usize) -> String,
+    pub no_lookups: &'static str,
+}
+
+impl Strings {
+    /// Header text of a main table column
+    pub fn column(&self, column: Column) -> &'static str {
+        self.columns
+            .iter()
+            .find(|(c, _)| *c == column)
+            .map_or_else(|| column.title(), |(_, title)| title)
+    }
+}
+
+pub fn strings(lang: Lang) -> &'static Strings {
+    match lang {
+        Lang::En => &EN,
+        Lang::De => &DE,
+    }
+}
+
+static EN: Strings = Strings {
+    help_title: " Help ",
+    help_keys: &[
+        ("q", "Quit"),
+        ("p", "Pause/Resume probing"),
+        ("r", "Reset statistics"),
+        ("t", "Cycle theme"),
+        ("e", "Export to JSON"),
+        ("n", "Add note (to selected hop)"),
+        ("l", "Event timeline"),
+        ("d", "Socket diagnostics"),
+        ("w", "Toggle windowed/cumulative stats"),
+        ("s", "Cycle sparkline (RTT, jitter, loss)"),
+        ("?/h", "Show this help"),
+    ],
+    help_nav: &[
+        ("Tab", "Next target (multi-target)"),
+        ("S-Tab/N", "Previous target"),
+        ("c", "Compare paths side by side"),
+        ("o", "Overview of all targets"),
+        ("Up/k", "Move selection up"),
+        ("Down/j", "Move selection down"),
+        ("Enter", "Expand selected hop (r there: redo lookups)"),
+        ("Esc", "Close popup / Deselect"),
+    ],
+    help_close: "Press any key to close",
+    hints: "q quit | p pause | r reset | t theme | n note | e export | ? help | \u{2191}\u{2193} select",
+    hints_multi: "q quit | Tab next target | o overview | c compare | p pause | r reset | n note | e export | ? help",
+    paused_badge: " [PAUSED]",
+    probes: |n| format!("{} probes", n),
+    interval: |ms| format!("{}ms interval", ms),
+    columns: &[],
+    paused: "Paused",
+    resumed: "Resumed",
+    stats_reset: "Stats reset",
+    note_added: "Note added",
+    stats_since_start: "Stats: since start",
+    stats_last: |window| format!("Stats: last {}", window),
+    no_stats_window: "Start with --stats-window to enable windowed stats",
+    theme: |name| format!("Theme: {}", name),
+    sparkline: |source| format!("Sparkline: {}", source),
+    exported: |file| format!("Exported to {}", file),
+    export_failed: |e| format!("Export failed: {}", e),
+    saved_timings: |file| format!("Saved timings to {}", file),
+    saving_timings_failed: |e| format!("Saving timings failed: {}", e),
+    saved_packets: |file| format!("Saved packets to {}", file),
+    saving_packets_failed: |e| format!("Saving packets failed: {}", e),
+    target: |n, of, target| format!("Target {}/{}: {}", n, of, target),
+    refreshing_lookups: |n| format!("Refreshing lookups for {} responder(s)", n),
+    no_lookups: "No lookups to refresh",
+};
+
+static DE: Strings = Strings {
+    help_title: " Hilfe ",
+    help_keys: &[
+        ("q", "Beenden"),
+        ("p", "Messung pausieren/fortsetzen"),
+        ("r", "Statistik zurücksetzen"),
+        ("t", "Farbschema wechseln"),
+        ("e", "Als JSON exportieren"),
+        ("n", "Notiz hinzufügen (zum gewählten Hop)"),
+        ("l", "Ereignis-Zeitleiste"),
+        ("d", "Socket-Diagnose"),
+        ("w", "Fenster-/Gesamtstatistik umschalten"),
+        ("s", "Sparkline: RTT, Jitter, Verlust"),
+        ("?/h", "Diese Hilfe anzeigen"),
+    ],
+    help_nav: &[
+        ("Tab", "Nächstes Ziel (mehrere Ziele)"),
+        ("S-Tab/N", "Vorheriges Ziel"),
+        ("c", "Pfade nebeneinander vergleichen"),
+        ("o", "Übersicht aller Ziele"),
+        ("Up/k", "Auswahl nach oben"),
+        ("Down/j", "Auswahl nach unten"),
+        ("Enter", "Hop-Details (dort r: Lookups neu)"),
+        ("Esc", "Popup schließen / Auswahl aufheben"),
+    ],
+    help_close: "Beliebige Taste schließt",
+    hints: "q Ende | p Pause | r Reset | t Farben | n Notiz | e Export | ? Hilfe | \u{2191}\u{2193} Auswahl",
+    hints_multi: "q Ende | Tab nächstes Ziel | o Übersicht | c Vergleich | p Pause | r Reset | n Notiz | e Export | ? Hilfe",
+    paused_badge: " [PAUSIERT]",
+    probes: |n| format!("{} Proben", n),
+    interval: |ms| format!("{}ms Intervall", ms),
+    columns: &[
+        (Column::Loss, "Verl.%"),
+        (Column::Attr, "Zuord%"),
+        (Column::Sent, "Gesend."),
+        (Column::Avg, "Mittel"),
+        (Column::StdDev, "StdAbw"),
+        (Column::Paths, "Pfade"),
+    ],
+    paused: "Pausiert",
+    resumed: "Fortgesetzt",
+    stats_reset: "Statistik zurückgesetzt",
+    note_added: "Notiz hinzugefügt",
+    stats_since_start: "Statistik: seit Start",
+    stats_last: |window| format!("Statistik: letzte {}", window),
+    no_stats_window: "Fensterstatistik braucht --stats-window beim Start",
+    theme: |name| format!("Farbschema: {}", name),
+    sparkline: |source| format!("Sparkline: {}", source),
+    exported: |file| format!("Exportiert nach {}", file),
+    export_failed: |e| format!("Export fehlgeschlagen: {}", e),
+    saved_timings: |file| format!("Zeitmessungen gespeichert in {}", file),
+    saving_timings_failed: |e| format!("Speichern der Zeitmessungen fehlgeschlagen: {}", e),
+    saved_packets: |file| format!("Pakete gespeichert in {}", file),
+    saving_packets_failed: |e| format!("Speichern der Pakete fehlgeschlagen: {}", e),
+    target: |n, of, target| format!("Ziel {}/{}: {}", n, of, target),
+    refreshing_lookups: |n| format!("Lookups für {} Responder werden erneuert", n),
+    no_lookups: "Keine Lookups zu erneuern",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_cover_the_same_keys() {
+        let keys = |s: &Strings| -> Vec<&str> {
+            s.help_keys
+                .iter()
+                .chain(s.help_nav)
+                .map(|(k, _)| *k)
+                .collect()
+        };
+        assert_eq!(keys(&EN), keys(&DE));
+        // Translated headings fit their columns
+        for (column, title) in DE.columns {
+            assert!(
+                title.chars().count() <= column.width() as usize,
+                "{}",
+                title
+            );
+        }
+        assert_eq!(strings(Lang::De).column(Column::Loss), "Verl.%");
+        assert_eq!(strings(Lang::De).column(Column::Max), "Max");
+        assert_eq!((strings(Lang::En).probes)(3), "3 probes");
+        assert_eq!(
+            (strings(Lang::De).saved_packets)("dump.txt"),
+            "Pakete gespeichert in dump.txt"
+        );
+    }
+}
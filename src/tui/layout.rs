@@ -0,0 +1,260 @@
+//! Main table layout for the current terminal size
+//!
+//! Recomputed on every frame, so a resize reflows the table instead of
+//! squeezing it: columns are dropped in priority order when the terminal
+//! is narrow, spare width goes to the host name first and then to the loss
+//! sparkline, and the scroll offset keeps the selected hop in view.
+
+/// Columns of the main hop table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Ttl,
+    Host,
+    Asn,
+    Loss,
+    Attr,
+    Sent,
+    Avg,
+    Min,
+    Max,
+    StdDev,
+    Jitter,
+    Nat,
+    Paths,
+    /// Largest DF probe the hop answered (`--df-sweep`)
+    MaxSize,
+    Sparkline,
+}
+
+impl Column {
+    /// Header text
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Ttl => "#",
+            Column::Host => "Host",
+            Column::Asn => "ASN",
+            Column::Loss => "Loss%",
+            Column::Attr => "Attr%",
+            Column::Sent => "Sent",
+            Column::Avg => "Avg",
+            Column::Min => "Min",
+            Column::Max => "Max",
+            Column::StdDev => "StdDev",
+            Column::Jitter => "Jitter",
+            Column::Nat => "NAT",
+            Column::Paths => "Paths",
+            Column::MaxSize => "MaxPkt",
+            Column::Sparkline => "",
+        }
+    }
+
+    /// Fixed width (Host and Sparkline are sized by `TableLayout::fit`)
+    pub(crate) fn width(self) -> u16 {
+        match self {
+            Column::Ttl => 3,
+            Column::Asn => 13,
+            // Sent fits seven digits, or a million with a thousands separator
+            Column::Loss | Column::Avg | Column::Min | Column::Max | Column::Sent => 7,
+            Column::StdDev | Column::Jitter => 7,
+            Column::Attr | Column::Paths | Column::MaxSize => 6,
+            Column::Nat => 4,
+            Column::Host => HOST_MIN,
+            Column::Sparkline => SPARKLINE_MIN,
+        }
+    }
+}
+
+/// Columns dropped first when the terminal is too narrow, in order
+const DROP_ORDER: [Column; 9] = [
+    Column::StdDev,
+    Column::Min,
+    Column::Max,
+    Column::Attr,
+    Column::Jitter,
+    Column::Sent,
+    Column::Nat,
+    Column::MaxSize,
+    Column::Asn,
+];
+
+/// Host column bounds: it never gets narrower than this, and stops
+/// growing at the longest name worth showing (an IPv6 address plus flags)
+const HOST_MIN: u16 = 16;
+const HOST_MAX: u16 = 42;
+
+/// Loss sparkline bounds (one cell per probe result)
+const SPARKLINE_MIN: u16 = 10;
+const SPARKLINE_MAX: u16 = 40;
+
+/// Space ratatui leaves between table columns
+const COLUMN_SPACING: u16 = 1;
+
+/// Which columns fit and how wide the flexible ones are
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLayout {
+    pub columns: Vec<Column>,
+    pub host_width: u16,
+    pub sparkline_width: u16,
+}
+
+impl TableLayout {
+    /// Lay out the table for `width` cells
+    pub fn fit(width: u16, multi_flow: bool, df_sweep: bool) -> Self {
+        let mut columns = vec![
+            Column::Ttl,
+            Column::Host,
+            Column::Asn,
+            Column::Loss,
+            Column::Attr,
+            Column::Sent,
+            Column::Avg,
+            Column::Min,
+            Column::Max,
+            Column::StdDev,
+            Column::Jitter,
+        ];
+        if multi_flow {
+            columns.extend([Column::Nat, Column::Paths]);
+        }
+        if df_sweep {
+            columns.push(Column::MaxSize);
+        }
+        columns.push(Column::Sparkline);
+
+        let needed = |columns: &[Column]| -> u16 {
+            columns.iter().map(|c| c.width()).sum::<u16>()
+                + COLUMN_SPACING * (columns.len() as u16).saturating_sub(1)
+        };
+        for drop in DROP_ORDER {
+            if needed(&columns) <= width {
+                break;
+            }
+            columns.retain(|&c| c != drop);
+        }
+
+        let spare = width.saturating_sub(needed(&columns));
+        let host_extra = spare.min(HOST_MAX - HOST_MIN);
+        let sparkline_extra = (spare - host_extra).min(SPARKLINE_MAX - SPARKLINE_MIN);
+        Self {
+            columns,
+            host_width: HOST_MIN + host_extra,
+            sparkline_width: SPARKLINE_MIN + sparkline_extra,
+        }
+    }
+
+    pub fn shows(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// Width of each visible column, in order
+    pub fn widths(&self) -> Vec<u16> {
+        self.columns
+            .iter()
+            .map(|&c| match c {
+                Column::Host => self.host_width,
+                Column::Sparkline => self.sparkline_width,
+                c => c.width(),
+            })
+            .collect()
+    }
+}
+
+/// First row to show so `selected` stays in view. The previous offset is
+/// kept when it still works, and clamped so a taller terminal doesn't
+/// leave blank rows below the last hop.
+pub fn scroll_offset(
+    selected: Option<usize>,
+    offset: usize,
+    visible: usize,
+    total: usize,
+) -> usize {
+    let offset = offset.min(total.saturating_sub(visible));
+    match selected {
+        Some(i) if visible > 0 && i < offset => i,
+        Some(i) if visible > 0 && i >= offset + visible => i + 1 - visible,
+        _ => offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_wide_terminal() {
+        let layout = TableLayout::fit(200, false, false);
+        assert_eq!(layout.columns.len(), 12);
+        assert_eq!(layout.host_width, HOST_MAX);
+        assert_eq!(layout.sparkline_width, SPARKLINE_MAX);
+        assert!(layout.widths().iter().sum::<u16>() + 11 <= 200);
+    }
+
+    #[test]
+    fn test_fit_drops_columns_in_order() {
+        // 108 cells: everything fits at minimum width
+        let layout = TableLayout::fit(108, false, false);
+        assert_eq!(layout.columns.len(), 12);
+        assert_eq!(layout.host_width, HOST_MIN);
+
+        let layout = TableLayout::fit(107, false, false);
+        assert!(!layout.shows(Column::StdDev));
+        assert!(layout.shows(Column::Min));
+        assert_eq!(layout.host_width, HOST_MIN + 7);
+
+        let layout = TableLayout::fit(92, false, false);
+        assert!(!layout.shows(Column::Min));
+        assert!(layout.shows(Column::Max));
+
+        // Very narrow: only the essentials remain
+        let layout = TableLayout::fit(30, true, false);
+        assert_eq!(
+            layout.columns,
+            [
+                Column::Ttl,
+                Column::Host,
+                Column::Loss,
+                Column::Avg,
+                Column::Paths,
+                Column::Sparkline
+            ]
+        );
+        assert_eq!(layout.host_width, HOST_MIN);
+    }
+
+    #[test]
+    fn test_fit_df_sweep_column() {
+        assert!(!TableLayout::fit(200, false, false).shows(Column::MaxSize));
+        let layout = TableLayout::fit(200, false, true);
+        assert_eq!(layout.columns[layout.columns.len() - 2], Column::MaxSize);
+        // Dropped before the ASN when space runs out
+        let layout = TableLayout::fit(64, false, true);
+        assert!(!layout.shows(Column::MaxSize));
+        assert!(layout.shows(Column::Asn));
+    }
+
+    #[test]
+    fn test_fit_host_grows_before_sparkline() {
+        let layout = TableLayout::fit(112, false, false);
+        assert_eq!(layout.host_width, HOST_MIN + 4);
+        assert_eq!(layout.sparkline_width, SPARKLINE_MIN);
+
+        let layout = TableLayout::fit(142, false, false);
+        assert_eq!(layout.host_width, HOST_MAX);
+        assert_eq!(layout.sparkline_width, SPARKLINE_MIN + 8);
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        // Selection below the window scrolls down just enough
+        assert_eq!(scroll_offset(Some(12), 0, 10, 20), 3);
+        // Selection above the window scrolls up to it
+        assert_eq!(scroll_offset(Some(2), 5, 10, 20), 2);
+        // Selection in view keeps the offset
+        assert_eq!(scroll_offset(Some(6), 5, 10, 20), 5);
+        // Terminal grew: no blank rows at the bottom
+        assert_eq!(scroll_offset(None, 8, 15, 20), 5);
+        assert_eq!(scroll_offset(Some(19), 10, 30, 20), 0);
+        // Terminal shrank: selection still visible
+        assert_eq!(scroll_offset(Some(9), 0, 4, 20), 6);
+    }
+}
@@ -1,4 +1,8 @@
 pub mod app;
+pub mod i18n;
+pub mod layout;
+pub mod saved;
 pub mod theme;
 pub mod views;
+pub mod web;
 pub mod widgets;
@@ -0,0 +1,122 @@
+//! TUI state kept between runs.
+//!
+//! View toggles changed with a key (sparkline source, windowed stats) are
+//! written to ~/.local/state/ttl/ui.toml on exit and restored on the next
+//! start. The theme stays in config.toml, where `t` has always saved it.
+//! Unlike config.toml this file is owned by ttl and rewritten freely.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::tui::app::UiState;
+use crate::tui::widgets::SparklineSource;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedUi {
+    /// What the main table's sparkline plots (`s`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sparkline: Option<SparklineSource>,
+    /// Windowed instead of cumulative stats (`w`, with --stats-window)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub windowed: Option<bool>,
+}
+
+impl SavedUi {
+    /// State file path: ~/.local/state/ttl/ui.toml (data dir where the OS
+    /// has no state dir)
+    pub fn path() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_dir)
+            .map(|p| p.join("ttl").join("ui.toml"))
+    }
+
+    /// Load the saved state (default if missing or unreadable)
+    pub fn load() -> Self {
+        Self::path().map(|p| Self::read(&p)).unwrap_or_default()
+    }
+
+    /// Read from `path`; a missing or corrupt file is an empty state
+    pub fn read(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Apply to a fresh UI. Windowed stats are only restored when the
+    /// trace keeps a window.
+    pub fn restore(&self, ui_state: &mut UiState, has_window: bool) {
+        if let Some(sparkline) = self.sparkline {
+            ui_state.sparkline = sparkline;
+        }
+        ui_state.windowed = has_window && self.windowed == Some(true);
+    }
+
+    /// Take the current toggles. `windowed` is left alone when the trace
+    /// had no window, so a run without --stats-window doesn't forget it.
+    pub fn remember(&mut self, ui_state: &UiState, has_window: bool) {
+        self.sparkline = Some(ui_state.sparkline);
+        if has_window {
+            self.windowed = Some(ui_state.windowed);
+        }
+    }
+
+    /// Save to the state file
+    pub fn save(&self) -> Result<()> {
+        match Self::path() {
+            Some(path) => self.write(&path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("ttl-ui-{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(SavedUi::read(&path), SavedUi::default());
+
+        let mut ui_state = UiState {
+            sparkline: SparklineSource::Jitter,
+            windowed: true,
+            ..Default::default()
+        };
+        let mut saved = SavedUi::default();
+        saved.remember(&ui_state, true);
+        saved.write(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("sparkline = \"jitter\""), "{}", text);
+
+        let restored = SavedUi::read(&path);
+        assert_eq!(restored, saved);
+        ui_state = UiState::default();
+        restored.restore(&mut ui_state, false);
+        assert_eq!(ui_state.sparkline, SparklineSource::Jitter);
+        assert!(!ui_state.windowed);
+        restored.restore(&mut ui_state, true);
+        assert!(ui_state.windowed);
+
+        // No window this run: the saved choice survives
+        let mut saved = restored.clone();
+        saved.remember(&UiState::default(), false);
+        assert_eq!(saved.windowed, Some(true));
+
+        fs::write(&path, "not toml [").unwrap();
+        assert_eq!(SavedUi::read(&path), SavedUi::default());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,157 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::state::Session;
+use crate::tui::theme::Theme;
+
+/// Width of the TTL column
+const TTL_WIDTH: usize = 4;
+
+/// Side-by-side path comparison overlay (`--sources`, multi-target)
+///
+/// One column per session, one row per TTL. Rows where the sessions'
+/// primary responders differ are highlighted.
+pub struct CompareView<'a> {
+    sessions: Vec<&'a Session>,
+    theme: &'a Theme,
+}
+
+impl<'a> CompareView<'a> {
+    pub fn new(sessions: Vec<&'a Session>, theme: &'a Theme) -> Self {
+        Self { sessions, theme }
+    }
+
+    /// Column heading: the source address when the sessions share a
+    /// target, the target otherwise
+    fn heading(&self, session: &Session) -> String {
+        let same_target = self
+            .sessions
+            .iter()
+            .all(|s| s.target.resolved == session.target.resolved);
+        match session.config.source_ip {
+            Some(source) if same_target => format!("from {}", source),
+            _ => session.target.original.clone(),
+        }
+    }
+}
+
+/// Pad or truncate to exactly `width` characters
+fn fit(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        format!("{:<width$}", s)
+    } else {
+        let mut out: String = s.chars().take(width.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    }
+}
+
+impl Widget for CompareView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width = area.width.saturating_sub(6);
+        let popup_height = area.height.saturating_sub(4);
+        let popup_x = (area.width - popup_width) / 2 + area.x;
+        let popup_y = (area.height - popup_height) / 2 + area.y;
+        let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+        Clear.render(popup_area, buf);
+
+        let block = Block::default()
+            .title(" Compare paths ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border));
+
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        let columns = self.sessions.len().max(1);
+        let col_width = (inner.width as usize)
+            .saturating_sub(TTL_WIDTH + 2)
+            .checked_div(columns)
+            .unwrap_or(0)
+            .saturating_sub(1)
+            .max(8);
+
+        let mut header = vec![Span::raw(format!("  {:<TTL_WIDTH$}", "TTL"))];
+        for session in &self.sessions {
+            header.push(Span::styled(
+                format!("{} ", fit(&self.heading(session), col_width)),
+                Style::default()
+                    .fg(self.theme.header)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        let mut lines = vec![Line::from(header)];
+
+        let last_ttl = self
+            .sessions
+            .iter()
+            .map(|s| {
+                s.dest_ttl.unwrap_or_else(|| {
+                    s.hops
+                        .iter()
+                        .filter(|h| h.sent > 0)
+                        .map(|h| h.ttl)
+                        .max()
+                        .unwrap_or(0)
+                })
+            })
+            .max()
+            .unwrap_or(0);
+
+        let visible = (inner.height as usize).saturating_sub(3);
+        for ttl in (1..=last_ttl).take(visible) {
+            let primaries: Vec<_> = self
+                .sessions
+                .iter()
+                .map(|s| s.hop(ttl).and_then(|h| h.primary))
+                .collect();
+            let differs = primaries.windows(2).any(|w| w[0] != w[1]);
+
+            let mut spans = vec![Span::styled(
+                format!("  {:<TTL_WIDTH$}", ttl),
+                Style::default().fg(self.theme.text_dim),
+            )];
+            for session in &self.sessions {
+                let cell = match session.hop(ttl) {
+                    Some(_) if session.dest_ttl.is_some_and(|d| ttl > d) => String::new(),
+                    Some(hop) => match hop.primary_stats() {
+                        Some(stats) => {
+                            let nf = &session.config.number_format;
+                            let name = stats
+                                .display_name(session.config.label_mode)
+                                .unwrap_or_else(|| stats.ip.to_string());
+                            format!(
+                                "{} {} {}",
+                                name,
+                                nf.percent(hop.loss_pct(), 0),
+                                nf.rtt(stats.avg_rtt(), 1)
+                            )
+                        }
+                        None if hop.sent > 0 => "???".to_string(),
+                        None => String::new(),
+                    },
+                    None => String::new(),
+                };
+                let style = if differs {
+                    Style::default().fg(self.theme.warning)
+                } else {
+                    Style::default().fg(self.theme.text)
+                };
+                spans.push(Span::styled(format!("{} ", fit(&cell, col_width)), style));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            "  [Esc/c/q] back",
+            Style::default().fg(self.theme.text_dim),
+        )]));
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
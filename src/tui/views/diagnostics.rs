@@ -0,0 +1,258 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::state::Session;
+use crate::trace::dump::{PacketDump, hex_lines};
+use crate::trace::perf::PerfSnapshot;
+use crate::tui::theme::Theme;
+
+/// Socket diagnostics overlay (buffer sizes, kernel receive drops, receiver counters)
+pub struct DiagnosticsView<'a> {
+    session: &'a Session,
+    theme: &'a Theme,
+    /// ttl's own timings (--perf)
+    perf: Option<PerfSnapshot>,
+    /// Packets set aside and those kept (--dump-unmatched)
+    dumps: Option<(u64, Vec<PacketDump>)>,
+}
+
+/// Packets shown in the panel, newest first
+const SHOWN_DUMPS: usize = 2;
+/// Hex lines shown per packet (the IP header and the start of the ICMP message)
+const SHOWN_HEX_LINES: usize = 3;
+
+impl<'a> DiagnosticsView<'a> {
+    pub fn new(session: &'a Session, theme: &'a Theme) -> Self {
+        Self {
+            session,
+            theme,
+            perf: None,
+            dumps: None,
+        }
+    }
+
+    /// Add a section with ttl's own timings
+    pub fn with_perf(mut self, perf: Option<PerfSnapshot>) -> Self {
+        self.perf = perf;
+        self
+    }
+
+    /// Add a section with the latest packets that matched no probe
+    pub fn with_dumps(mut self, dumps: Option<(u64, Vec<PacketDump>)>) -> Self {
+        self.dumps = dumps;
+        self
+    }
+
+    fn row(&self, label: &str, value: String, color: ratatui::style::Color) -> Line<'static> {
+        Line::from(vec![
+            Span::styled(
+                format!("  {:<16}", label),
+                Style::default().fg(self.theme.text_dim),
+            ),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    }
+}
+
+/// Byte count in the units --recv-buffer accepts
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 && bytes % (1024 * 1024) == 0 {
+        format!("{}M", bytes / (1024 * 1024))
+    } else if bytes >= 1024 && bytes % 1024 == 0 {
+        format!("{}K", bytes / 1024)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// "granted (requested X)" for a buffer the user sized, else "granted (OS default)"
+fn buffer_value(granted: Option<usize>, requested: Option<usize>) -> String {
+    let granted = granted.map_or_else(|| "unknown".to_string(), format_bytes);
+    match requested {
+        Some(req) => format!("{} (requested {})", granted, format_bytes(req)),
+        None => format!("{} (OS default)", granted),
+    }
+}
+
+impl Widget for DiagnosticsView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width = area.width.saturating_sub(10).min(72);
+        let mut wanted = if self.perf.is_some() { 26 } else { 18 };
+        if self.dumps.is_some() {
+            wanted += 3 + (SHOWN_DUMPS * (SHOWN_HEX_LINES + 1)) as u16;
+        }
+        let popup_height = area.height.saturating_sub(6).min(wanted);
+        let popup_x = (area.width - popup_width) / 2 + area.x;
+        let popup_y = (area.height - popup_height) / 2 + area.y;
+        let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+        Clear.render(popup_area, buf);
+
+        let block = Block::default()
+            .title(" Diagnostics ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border));
+
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        let stats = self.session.socket_stats.clone().unwrap_or_default();
+        let config = &self.session.config;
+        let text = self.theme.text;
+
+        let mut lines = vec![
+            Line::from(""),
+            self.row(
+                "Receive buffer",
+                buffer_value(stats.recv_buffer, config.recv_buffer),
+                text,
+            ),
+            self.row(
+                "Send buffer",
+                buffer_value(stats.send_buffer, config.send_buffer),
+                text,
+            ),
+        ];
+
+        let (drops, color) = match stats.recv_drops {
+            None => ("not counted on this OS".to_string(), self.theme.text_dim),
+            Some(0) => ("none".to_string(), self.theme.success),
+            Some(n) => (format!("{} responses", n), self.theme.error),
+        };
+        lines.push(self.row("Kernel drops", drops, color));
+        lines.push(self.row("Probes sent", self.session.total_sent.to_string(), text));
+
+        // The receive socket is shared by every target's engine
+        if stats.receiver_targets > 1 {
+            lines.push(self.row(
+                "Receiver",
+                format!("shared by {} targets", stats.receiver_targets),
+                text,
+            ));
+        }
+        lines.push(self.row("Matched", format!("{} responses", stats.matched), text));
+        let color = if stats.unmatched > 0 {
+            self.theme.warning
+        } else {
+            text
+        };
+        lines.push(self.row(
+            "Unmatched",
+            format!("{} (duplicate or very late)", stats.unmatched),
+            color,
+        ));
+        lines.push(self.row(
+            "Stray packets",
+            format!("{} (not replies to our probes)", stats.stray),
+            self.theme.text_dim,
+        ));
+
+        if let Some(rate) = config.global_rate {
+            let burst = config.global_burst.unwrap_or(1);
+            let (value, color) = match self.session.throttle.as_ref() {
+                Some(t) => (
+                    format!(
+                        "{} pps, burst {}: {} delayed, last {:.0}ms{}",
+                        t.rate,
+                        t.burst,
+                        t.delayed,
+                        t.last_wait.as_secs_f64() * 1000.0,
+                        if t.active() { " (throttling)" } else { "" }
+                    ),
+                    if t.active() { self.theme.warning } else { text },
+                ),
+                None => (format!("{} pps, burst {}: idle", rate, burst), text),
+            };
+            lines.push(self.row("Global limit", value, color));
+        }
+
+        if stats.recv_drops.is_some_and(|n| n > 0) {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "  Dropped responses count as loss. Raise --recv-buffer",
+                Style::default().fg(self.theme.warning),
+            )));
+            lines.push(Line::from(Span::styled(
+                "  (Linux caps it at net.core.rmem_max).",
+                Style::default().fg(self.theme.warning),
+            )));
+        }
+
+        if let Some(ref perf) = self.perf {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "  ttl itself",
+                Style::default().fg(self.theme.header),
+            )));
+            lines.push(self.row("Draw", perf.draw.describe(), text));
+            let color = if perf.tick_overruns > 0 {
+                self.theme.warning
+            } else {
+                text
+            };
+            lines.push(self.row("Tick overruns", perf.tick_overruns.to_string(), color));
+            lines.push(self.row("UI lock wait", perf.ui_lock_wait.describe(), text));
+            lines.push(self.row("Recv lock wait", perf.receiver_lock_wait.describe(), text));
+            lines.push(self.row(
+                "Recv batch",
+                format!(
+                    "{} packets (max {})",
+                    perf.receiver_batch.last, perf.receiver_batch.max
+                ),
+                text,
+            ));
+            lines.push(self.row(
+                "In flight",
+                format!(
+                    "{} probes (max {})",
+                    perf.pending_probes.last, perf.pending_probes.max
+                ),
+                text,
+            ));
+        }
+
+        if let Some((seen, ref kept)) = self.dumps {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "  Packets matching no probe",
+                Style::default().fg(self.theme.header),
+            )));
+            lines.push(self.row(
+                "Captured",
+                format!("{} (latest {} kept)", seen, kept.len()),
+                text,
+            ));
+            for dump in kept.iter().rev().take(SHOWN_DUMPS) {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", dump.header()),
+                    Style::default().fg(self.theme.text),
+                )));
+                for hex in hex_lines(&dump.data).into_iter().take(SHOWN_HEX_LINES) {
+                    lines.push(Line::from(Span::styled(
+                        format!("  {}", hex),
+                        Style::default().fg(self.theme.text_dim),
+                    )));
+                }
+            }
+        }
+
+        lines.push(Line::from(""));
+        let mut keys = String::from(" ");
+        if self.perf.is_some() {
+            keys.push_str(" [e] save timings");
+        }
+        if self.dumps.is_some() {
+            keys.push_str(" [x] save packets");
+        }
+        keys.push_str("  [Esc/d/q] back");
+        lines.push(Line::from(vec![Span::styled(
+            keys,
+            Style::default().fg(self.theme.text_dim),
+        )]));
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
@@ -4,24 +4,178 @@ use ratatui::style::Style;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
 
-use crate::state::Hop;
+use crate::config::Config;
+use crate::state::correlation::LossCorrelation;
+use crate::state::link::InferredLink;
+use crate::state::loops::RoutingLoop;
+use crate::state::paths::Branch;
+use crate::state::tunnel::TunnelSpan;
+use crate::state::{
+    DEFAULT_STALE_AFTER, DscpRemark, EcnChange, Hop, HopDistance, HopSnapshot, LoadHop, Note,
+    PathEpoch, dscp_label,
+};
 use crate::tui::theme::Theme;
 use crate::tui::widgets::sparkline_string;
 
+/// Time since a responder was seen, e.g. "4s", "3m 12s", "2h 5m"
+fn format_age(age: chrono::Duration) -> String {
+    let secs = age.num_seconds().max(0);
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Response code strip for `ip`, colored by what each code means:
+/// routine replies dim, unreachables red, timeouts yellow. Only the last
+/// `width` codes are shown.
+fn outcome_spans(
+    hop: &Hop,
+    ip: std::net::IpAddr,
+    width: usize,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let strip: Vec<char> = hop.outcome_strip(ip).chars().collect();
+    let skip = strip.len().saturating_sub(width);
+    strip[skip..]
+        .iter()
+        .map(|&c| {
+            let color = match c {
+                '.' | 'o' => theme.text_dim,
+                '×' => theme.warning,
+                _ => theme.error,
+            };
+            Span::styled(c.to_string(), Style::default().fg(color))
+        })
+        .collect()
+}
+
 /// Expanded hop detail view (modal overlay)
 pub struct HopDetailView<'a> {
     hop: &'a Hop,
     theme: &'a Theme,
+    notes: Vec<&'a Note>,
+    /// Session config, for note timestamps (UTC or local) and number format
+    config: Option<&'a Config>,
+    /// Point-to-point links to the hops either side of this one
+    links: Vec<InferredLink>,
+    /// Suspected MPLS tunnels covering this hop
+    tunnels: Vec<TunnelSpan>,
+    /// Where the ECMP paths of --flows split at this hop
+    branches: Vec<Branch>,
+    /// Other hops that lose the same rounds as this one
+    shared_loss: Vec<LossCorrelation>,
+    /// Routing loop covering this hop
+    routing_loop: Option<&'a RoutingLoop>,
+    /// DSCP rewrite between the previous hop and this one
+    dscp_remark: Option<DscpRemark>,
+    /// ECN marking change between the previous hop and this one
+    ecn_change: Option<EcnChange>,
+    /// Idle vs loaded RTT at this hop (--load)
+    load: Option<LoadHop>,
+    /// Distance from the origin and the speed-of-light RTT floor
+    distance: Option<HopDistance>,
+    /// Statistics cleared by the latest path change (--reset-on-path-change)
+    previous: Option<(&'a PathEpoch, &'a HopSnapshot)>,
 }
 
 impl<'a> HopDetailView<'a> {
     pub fn new(hop: &'a Hop, theme: &'a Theme) -> Self {
-        Self { hop, theme }
+        Self {
+            hop,
+            theme,
+            notes: Vec::new(),
+            config: None,
+            links: Vec::new(),
+            tunnels: Vec::new(),
+            branches: Vec::new(),
+            shared_loss: Vec::new(),
+            routing_loop: None,
+            dscp_remark: None,
+            ecn_change: None,
+            load: None,
+            distance: None,
+            previous: None,
+        }
+    }
+
+    /// Show user notes attached to this hop, timestamped per `config`
+    pub fn with_notes(mut self, notes: Vec<&'a Note>, config: &'a Config) -> Self {
+        self.notes = notes;
+        self.config = Some(config);
+        self
+    }
+
+    /// Show inferred links that start or end at this hop
+    pub fn with_links(mut self, links: Vec<InferredLink>) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Show suspected MPLS tunnels this hop belongs to
+    pub fn with_tunnels(mut self, tunnels: Vec<TunnelSpan>) -> Self {
+        self.tunnels = tunnels;
+        self
+    }
+
+    /// Show where flows leave this hop for different next hops
+    pub fn with_branches(mut self, branches: Vec<Branch>) -> Self {
+        self.branches = branches;
+        self
+    }
+
+    /// Show the hops whose losses line up with this one's
+    pub fn with_shared_loss(mut self, pairs: Vec<LossCorrelation>) -> Self {
+        self.shared_loss = pairs;
+        self
+    }
+
+    /// Show the routing loop this hop is part of
+    pub fn with_loop(mut self, routing_loop: Option<&'a RoutingLoop>) -> Self {
+        self.routing_loop = routing_loop;
+        self
+    }
+
+    /// Flag a DSCP rewrite just before this hop
+    pub fn with_dscp_remark(mut self, remark: Option<DscpRemark>) -> Self {
+        self.dscp_remark = remark;
+        self
+    }
+
+    /// Flag an ECN marking change just before this hop
+    pub fn with_ecn_change(mut self, change: Option<EcnChange>) -> Self {
+        self.ecn_change = change;
+        self
+    }
+
+    /// Show the primary responder's distance and minimum possible RTT
+    pub fn with_distance(mut self, distance: Option<HopDistance>) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Show the statistics this hop had before the latest path change reset
+    pub fn with_previous_path(
+        mut self,
+        previous: Option<(&'a PathEpoch, &'a HopSnapshot)>,
+    ) -> Self {
+        self.previous = previous;
+        self
+    }
+
+    /// Show the idle and loaded RTT from the latency-under-load test
+    pub fn with_load(mut self, load: Option<LoadHop>) -> Self {
+        self.load = load;
+        self
     }
 }
 
 impl Widget for HopDetailView<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        let nf = self.config.map(|c| c.number_format).unwrap_or_default();
+
         // Calculate centered popup area
         let popup_width = area.width.saturating_sub(10).min(80);
         let popup_height = area.height.saturating_sub(6).min(25);
@@ -33,6 +187,11 @@ impl Widget for HopDetailView<'_> {
         Clear.render(popup_area, buf);
 
         let stats = self.hop.primary_stats();
+        let (stale_after, flows) = self
+            .config
+            .map(|c| (c.stale_after, c.flows))
+            .unwrap_or((DEFAULT_STALE_AFTER, 1));
+        let now = chrono::Utc::now();
         let ip = stats
             .map(|s| s.ip.to_string())
             .unwrap_or_else(|| "* * *".to_string());
@@ -49,6 +208,14 @@ impl Widget for HopDetailView<'_> {
         let mut lines = Vec::new();
 
         if let Some(stats) = stats {
+            // Configured name (--label)
+            if let Some(ref label) = stats.label {
+                lines.push(Line::from(vec![
+                    Span::styled("  Label:     ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(label.clone()),
+                ]));
+            }
+
             // Hostname
             if let Some(ref hostname) = stats.hostname {
                 lines.push(Line::from(vec![
@@ -63,6 +230,160 @@ impl Widget for HopDetailView<'_> {
                 Span::raw(stats.ip.to_string()),
             ]));
 
+            // Interface the probe arrived on, as the router reports it (RFC 5837)
+            if let Some(ref interface) = stats.interface {
+                lines.push(Line::from(vec![
+                    Span::styled("  Interface: ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(interface.describe()),
+                ]));
+            }
+
+            // Addresses merged into this responder (--alias, --merge-by-hostname)
+            if stats.alias.is_some() || !stats.aliases.is_empty() {
+                let mut label = stats.alias.clone().unwrap_or_default();
+                if !stats.aliases.is_empty() {
+                    let also: Vec<String> = stats.aliases.iter().map(|ip| ip.to_string()).collect();
+                    if !label.is_empty() {
+                        label.push(' ');
+                    }
+                    label.push_str(&format!("(also {})", also.join(", ")));
+                }
+                lines.push(Line::from(vec![
+                    Span::styled("  Alias:     ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(label),
+                ]));
+            }
+
+            // First and last reply
+            if let (Some(first), Some(last)) = (stats.first_seen, stats.last_seen) {
+                let first = self
+                    .config
+                    .map(|c| c.format_time(&first, "%H:%M:%S"))
+                    .unwrap_or_else(|| first.format("%H:%M:%S").to_string());
+                let mut spans = vec![
+                    Span::styled("  Seen:      ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!(
+                        "first {}, last {} ago",
+                        first,
+                        format_age(now - last)
+                    )),
+                ];
+                if self.hop.is_stale(stats, stale_after, flows) {
+                    spans.push(Span::styled(
+                        format!(
+                            " (silent {} rounds)",
+                            self.hop.rounds_since_seen(stats, flows)
+                        ),
+                        Style::default().fg(self.theme.warning),
+                    ));
+                }
+                lines.push(Line::from(spans));
+            }
+
+            // Inferred /31, /30 or /127 links to the neighbouring hops
+            for link in &self.links {
+                let (other_ttl, other_ip) = if link.near_ttl == self.hop.ttl {
+                    (link.near_ttl + 1, link.far)
+                } else {
+                    (link.near_ttl, link.near)
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("  Link:      ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!(
+                        "/{} with hop {} ({})",
+                        link.prefix_len, other_ttl, other_ip
+                    )),
+                ]));
+            }
+
+            // MPLS tunnels inferred without label stacks
+            for tunnel in &self.tunnels {
+                lines.push(Line::from(vec![
+                    Span::styled("  Tunnel:    ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled(
+                        format!("MPLS suspected, {}", tunnel.describe()),
+                        Style::default().fg(self.theme.warning),
+                    ),
+                ]));
+            }
+
+            for branch in &self.branches {
+                lines.push(Line::from(vec![
+                    Span::styled("  Branches:  ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!("{} -> {}", branch.ip, branch.describe())),
+                ]));
+            }
+
+            if !self.shared_loss.is_empty() {
+                let peers: Vec<String> = self
+                    .shared_loss
+                    .iter()
+                    .map(|pair| {
+                        let other = if pair.ttl_a == self.hop.ttl {
+                            pair.ttl_b
+                        } else {
+                            pair.ttl_a
+                        };
+                        format!("hop {} (phi {:.2})", other, pair.phi)
+                    })
+                    .collect();
+                lines.push(Line::from(vec![
+                    Span::styled("  Loss with: ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled(peers.join(", "), Style::default().fg(self.theme.warning)),
+                ]));
+            }
+
+            if let Some(routing_loop) = self.routing_loop {
+                lines.push(Line::from(vec![
+                    Span::styled("  Loop:      ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled(
+                        routing_loop.describe(),
+                        Style::default().fg(self.theme.error),
+                    ),
+                ]));
+            }
+
+            // DSCP the probes carried when they got here
+            if let Some(observed) = self.hop.dscp.as_ref().and_then(|d| d.observed()) {
+                let mut spans = vec![
+                    Span::styled("  DSCP:      ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(dscp_label(observed)),
+                ];
+                if let Some(remark) = self.dscp_remark {
+                    spans.push(Span::styled(
+                        format!(
+                            " (remarked from {} before this hop)",
+                            dscp_label(remark.from)
+                        ),
+                        Style::default().fg(self.theme.warning),
+                    ));
+                }
+                lines.push(Line::from(spans));
+            }
+
+            // ECN codepoint the probes carried when they got here (--ecn)
+            if let Some(ecn) = self.hop.ecn.as_ref()
+                && let Some(observed) = ecn.observed()
+            {
+                let mut spans = vec![
+                    Span::styled("  ECN:       ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(observed.as_str()),
+                ];
+                let marks = ecn.congestion_marks();
+                if marks > 0 {
+                    spans.push(Span::raw(format!(" ({} of {} CE)", marks, ecn.samples())));
+                }
+                if let Some(change) = self.ecn_change {
+                    let note = if change.bleached() {
+                        " (cleared before this hop)".to_string()
+                    } else {
+                        format!(" (rewritten from {} before this hop)", change.from.as_str())
+                    };
+                    spans.push(Span::styled(note, Style::default().fg(self.theme.warning)));
+                }
+                lines.push(Line::from(spans));
+            }
+
             // ASN (if available)
             if let Some(ref asn) = stats.asn {
                 lines.push(Line::from(vec![
@@ -75,6 +396,72 @@ impl Widget for HopDetailView<'_> {
                         Span::raw(prefix.clone()),
                     ]));
                 }
+                let mut registry = [asn.registry_label(), asn.country.clone()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if let Some(ref date) = asn.allocated {
+                    if !registry.is_empty() {
+                        registry.push_str(", ");
+                    }
+                    registry.push_str(&format!("allocated {}", date));
+                }
+                if !registry.is_empty() {
+                    lines.push(Line::from(vec![
+                        Span::styled("  Registry:  ", Style::default().fg(self.theme.text_dim)),
+                        Span::raw(registry),
+                    ]));
+                }
+            }
+
+            // Live BGP prefix (--prefix-watch)
+            if let Some(ref origin) = stats.prefix_origin {
+                let mut spans = vec![
+                    Span::styled("  BGP:       ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(origin.label()),
+                ];
+                if origin.more_specific {
+                    spans.push(Span::styled(
+                        " more-specific",
+                        Style::default().fg(self.theme.warning),
+                    ));
+                }
+                if let Some(ref previous) = origin.previous {
+                    spans.push(Span::styled(
+                        format!(" (was {})", previous),
+                        Style::default().fg(self.theme.text_dim),
+                    ));
+                }
+                lines.push(Line::from(spans));
+            }
+
+            // Fields from --plugin responder=... commands
+            if !stats.plugin_data.is_empty() {
+                let fields = stats
+                    .plugin_data
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                lines.push(Line::from(vec![
+                    Span::styled("  Plugin:    ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(fields),
+                ]));
+            }
+
+            // Annotations from --enrich-url
+            if !stats.extra.is_empty() {
+                let fields = stats
+                    .extra
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                lines.push(Line::from(vec![
+                    Span::styled("  Extra:     ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(fields),
+                ]));
             }
 
             // Geo (if available)
@@ -95,6 +482,23 @@ impl Widget for HopDetailView<'_> {
                 ]));
             }
 
+            // Speed-of-light floor vs what was measured
+            if let Some(distance) = self.distance {
+                let mut text = format!(
+                    "{} km, min RTT {}",
+                    nf.number(distance.km, 0),
+                    nf.rtt(distance.min_rtt, 1)
+                );
+                let avg = stats.avg_rtt();
+                if let Some(x) = distance.inflation(avg) {
+                    text.push_str(&format!(" (avg {}, {}x)", nf.rtt(avg, 1), nf.number(x, 1)));
+                }
+                lines.push(Line::from(vec![
+                    Span::styled("  Distance:  ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(text),
+                ]));
+            }
+
             // IX (if available - PeeringDB)
             if let Some(ref ix) = stats.ix {
                 let ix_location = [ix.city.as_deref(), ix.country.as_deref()]
@@ -127,9 +531,26 @@ impl Widget for HopDetailView<'_> {
                 ]));
             }
 
+            // Latest response codes, e.g. "....X..×.X" for intermittent
+            // admin-prohibited filtering
+            let codes = outcome_spans(
+                self.hop,
+                stats.ip,
+                (inner.width as usize).saturating_sub(15),
+                self.theme,
+            );
+            if !codes.is_empty() {
+                let mut spans = vec![Span::styled(
+                    "  Codes:     ",
+                    Style::default().fg(self.theme.text_dim),
+                )];
+                spans.extend(codes);
+                lines.push(Line::from(spans));
+            }
+
             lines.push(Line::from(""));
 
-            // Stats (use hop-level sent/loss since we can't attribute probes to responders before reply)
+            // Stats (hop-level; per-responder figures follow when there are several)
             let hop_loss = self.hop.loss_pct();
             // Label as "Hop totals" when multiple responders exist (ECMP) to avoid confusion
             let stats_label = if self.hop.responders.len() > 1 {
@@ -140,12 +561,12 @@ impl Widget for HopDetailView<'_> {
             lines.push(Line::from(vec![
                 Span::styled(stats_label, Style::default().fg(self.theme.text_dim)),
                 Span::styled("Sent: ", Style::default().fg(self.theme.text_dim)),
-                Span::raw(format!("{:<6}", self.hop.sent)),
+                Span::raw(format!("{:<6}", nf.count(self.hop.sent))),
                 Span::styled("Recv: ", Style::default().fg(self.theme.text_dim)),
-                Span::raw(format!("{:<6}", self.hop.received)),
+                Span::raw(format!("{:<6}", nf.count(self.hop.received))),
                 Span::styled("Loss: ", Style::default().fg(self.theme.text_dim)),
                 Span::styled(
-                    format!("{:.1}%", hop_loss),
+                    nf.percent(hop_loss, 1),
                     if hop_loss > 10.0 {
                         Style::default().fg(self.theme.error)
                     } else {
@@ -154,6 +575,120 @@ impl Widget for HopDetailView<'_> {
                 ),
             ]));
 
+            // With ECMP, this responder's own share ("~" when part of it is
+            // an estimate from timeouts no flow could be pinned to)
+            if self.hop.responders.len() > 1
+                && let Some(loss) = self.hop.responder_loss(stats.ip)
+            {
+                let approx = if loss.estimated { "~" } else { "" };
+                lines.push(Line::from(vec![
+                    Span::styled("  Responder:  ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled("Sent: ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!(
+                        "{:<6}",
+                        format!("{}{}", approx, nf.number(loss.sent, 0))
+                    )),
+                    Span::styled("Recv: ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!("{:<6}", nf.count(stats.received))),
+                    Span::styled("Loss: ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled(
+                        format!("{}{}", approx, nf.percent(loss.loss_pct, 1)),
+                        if loss.loss_pct > 10.0 {
+                            Style::default().fg(self.theme.error)
+                        } else {
+                            Style::default().fg(self.theme.success)
+                        },
+                    ),
+                ]));
+            }
+
+            // IP TTL left on this responder's replies; a settled change
+            // means the reverse path moved
+            if let Some(ref return_ttl) = stats.return_ttl {
+                let mut spans = vec![
+                    Span::styled("  Return TTL: ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(return_ttl.current.to_string()),
+                ];
+                if return_ttl.last != return_ttl.current {
+                    spans.push(Span::styled(
+                        format!(" (last {})", return_ttl.last),
+                        Style::default().fg(self.theme.text_dim),
+                    ));
+                }
+                if let (Some(previous), Some(at)) = (return_ttl.previous, return_ttl.changed_at) {
+                    let at = self
+                        .config
+                        .map(|c| c.format_time(&at, "%H:%M:%S"))
+                        .unwrap_or_else(|| at.format("%H:%M:%S").to_string());
+                    spans.push(Span::styled(
+                        format!(
+                            " was {} until {}, {} change{}",
+                            previous,
+                            at,
+                            return_ttl.changes,
+                            if return_ttl.changes == 1 { "" } else { "s" }
+                        ),
+                        Style::default().fg(self.theme.warning),
+                    ));
+                }
+                lines.push(Line::from(spans));
+            }
+
+            // Minutes this responder answered in at all, since it first did
+            if let Some((pct, minutes)) = self.hop.availability(stats) {
+                let style = if pct < 100.0 {
+                    Style::default().fg(self.theme.warning)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("  Available: ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled(
+                        format!("{} of {} min", nf.percent(pct, 0), nf.count(minutes)),
+                        style,
+                    ),
+                ]));
+            }
+
+            // Replies that came back after the timeout: delay, not loss
+            if let Some((avg, max)) = self.hop.late_rtt() {
+                lines.push(Line::from(vec![
+                    Span::styled("  Late: ", Style::default().fg(self.theme.text_dim)),
+                    Span::styled(
+                        format!(
+                            "{} ({} of timeouts), avg {}, max {}",
+                            nf.count(self.hop.late),
+                            nf.percent(self.hop.late_pct(), 0),
+                            nf.rtt(avg, 1),
+                            nf.rtt(max, 1)
+                        ),
+                        Style::default().fg(self.theme.warning),
+                    ),
+                ]));
+            }
+
+            // Latency under load: baseline vs with the --load stream running
+            if let Some(load) = self.load
+                && let Some(idle) = load.idle.mean()
+            {
+                let loaded = match (load.loaded.mean(), load.delta_ms()) {
+                    (Some(loaded), Some(delta)) => {
+                        let sign = if delta >= 0.0 { "+" } else { "" };
+                        format!(
+                            "loaded {} ({}{}ms)",
+                            nf.rtt(loaded, 1),
+                            sign,
+                            nf.number(delta, 1)
+                        )
+                    }
+                    _ => "loaded -".to_string(),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("  Load: ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!("idle {}, {}", nf.rtt(idle, 1), loaded)),
+                ]));
+            }
+
             lines.push(Line::from(""));
 
             // RTT stats
@@ -161,37 +696,56 @@ impl Widget for HopDetailView<'_> {
                 // Basic latency stats
                 lines.push(Line::from(vec![
                     Span::styled("  Min: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!("{:.2}ms    ", stats.min_rtt.as_secs_f64() * 1000.0)),
+                    Span::raw(format!("{}    ", nf.rtt(stats.min_rtt, 2))),
                     Span::styled("Avg: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!(
-                        "{:.2}ms    ",
-                        stats.avg_rtt().as_secs_f64() * 1000.0
-                    )),
+                    Span::raw(format!("{}    ", nf.rtt(stats.avg_rtt(), 2))),
                     Span::styled("Max: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!("{:.2}ms", stats.max_rtt.as_secs_f64() * 1000.0)),
+                    Span::raw(nf.rtt(stats.max_rtt, 2)),
                 ]));
 
                 // Last RTT and StdDev
                 let last_rtt = stats
                     .last_rtt()
-                    .map(|d| format!("{:.2}ms", d.as_secs_f64() * 1000.0))
+                    .map(|d| nf.rtt(d, 2))
                     .unwrap_or_else(|| "-".to_string());
                 lines.push(Line::from(vec![
                     Span::styled("  Last: ", Style::default().fg(self.theme.text_dim)),
                     Span::raw(format!("{:<9}", last_rtt)),
                     Span::styled("StdDev: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!("{:.2}ms", stats.stddev().as_secs_f64() * 1000.0)),
+                    Span::raw(nf.rtt(stats.stddev(), 2)),
                 ]));
 
+                // How far the average can be trusted; wide with few replies
+                if let (Some(half), Some((low, high))) = (stats.mean_ci95, stats.mean_ci()) {
+                    let half = std::time::Duration::from_micros(half as u64);
+                    lines.push(Line::from(vec![
+                        Span::styled("  Avg 95% CI: ", Style::default().fg(self.theme.text_dim)),
+                        Span::raw(format!(
+                            "±{} ({} - {})",
+                            nf.rtt(half, 2),
+                            nf.rtt(low, 2),
+                            nf.rtt(high, 2)
+                        )),
+                        Span::styled(
+                            format!("  n={}", nf.count(stats.received)),
+                            Style::default().fg(if stats.received < 10 {
+                                self.theme.warning
+                            } else {
+                                self.theme.text_dim
+                            }),
+                        ),
+                    ]));
+                }
+
                 // Percentiles (if we have enough samples)
                 if let (Some(p50), Some(p95), Some(p99)) = (stats.p50(), stats.p95(), stats.p99()) {
                     lines.push(Line::from(vec![
                         Span::styled("  p50: ", Style::default().fg(self.theme.text_dim)),
-                        Span::raw(format!("{:.2}ms    ", p50.as_secs_f64() * 1000.0)),
+                        Span::raw(format!("{}    ", nf.rtt(p50, 2))),
                         Span::styled("p95: ", Style::default().fg(self.theme.text_dim)),
-                        Span::raw(format!("{:.2}ms    ", p95.as_secs_f64() * 1000.0)),
+                        Span::raw(format!("{}    ", nf.rtt(p95, 2))),
                         Span::styled("p99: ", Style::default().fg(self.theme.text_dim)),
-                        Span::raw(format!("{:.2}ms", p99.as_secs_f64() * 1000.0)),
+                        Span::raw(nf.rtt(p99, 2)),
                     ]));
                 }
 
@@ -204,17 +758,11 @@ impl Widget for HopDetailView<'_> {
                         "  Jitter (smoothed): ",
                         Style::default().fg(self.theme.text_dim),
                     ),
-                    Span::raw(format!("{:.2}ms  ", stats.jitter().as_secs_f64() * 1000.0)),
+                    Span::raw(format!("{}  ", nf.rtt(stats.jitter(), 2))),
                     Span::styled("Avg: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!(
-                        "{:.2}ms  ",
-                        stats.jitter_avg().as_secs_f64() * 1000.0
-                    )),
+                    Span::raw(format!("{}  ", nf.rtt(stats.jitter_avg(), 2))),
                     Span::styled("Max: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!(
-                        "{:.2}ms",
-                        stats.jitter_max().as_secs_f64() * 1000.0
-                    )),
+                    Span::raw(nf.rtt(stats.jitter_max(), 2)),
                 ]));
             }
 
@@ -237,7 +785,10 @@ impl Widget for HopDetailView<'_> {
                 lines.push(Line::from(""));
                 if nat_info.has_nat() {
                     lines.push(Line::from(vec![Span::styled(
-                        "  NAT Detected!",
+                        format!(
+                            "  NAT Detected! ({:.0}% of probes)",
+                            nat_info.nat_percentage()
+                        ),
                         Style::default().fg(self.theme.warning),
                     )]));
                     lines.push(Line::from(vec![
@@ -245,15 +796,28 @@ impl Widget for HopDetailView<'_> {
                         Span::raw(format!("{}", nat_info.port_matched)),
                         Span::styled("  Rewrites: ", Style::default().fg(self.theme.text_dim)),
                         Span::styled(
-                            format!(
-                                "{} ({:.0}%)",
-                                nat_info.port_rewritten,
-                                nat_info.nat_percentage()
-                            ),
+                            format!("{}", nat_info.port_rewritten),
                             Style::default().fg(self.theme.warning),
                         ),
                     ]));
 
+                    // Port kept but checksum fixed up: the source address changed
+                    if nat_info.checksum_rewritten > 0 {
+                        lines.push(Line::from(vec![
+                            Span::styled(
+                                "  Address rewrites: ",
+                                Style::default().fg(self.theme.text_dim),
+                            ),
+                            Span::styled(
+                                format!(
+                                    "{} (quoted checksum changed)",
+                                    nat_info.checksum_rewritten
+                                ),
+                                Style::default().fg(self.theme.warning),
+                            ),
+                        ]));
+                    }
+
                     // Show rewrite samples (original → returned)
                     if !nat_info.rewrite_samples.is_empty() {
                         let samples: Vec<String> = nat_info
@@ -309,17 +873,34 @@ impl Widget for HopDetailView<'_> {
                 ]));
                 lines.push(Line::from(vec![
                     Span::styled("  Tip: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw("Try slower probing with -i 1.0 or -i 2.0"),
+                    Span::raw("Try --adaptive, or slower probing with -i 1.0 or -i 2.0"),
+                ]));
+            }
+
+            if self.hop.pace.backed_off() {
+                lines.push(Line::from(vec![
+                    Span::styled("  Pacing: ", Style::default().fg(self.theme.text_dim)),
+                    Span::raw(format!(
+                        "every {} rounds while loss stays high (--adaptive)",
+                        self.hop.pace.period
+                    )),
                 ]));
             }
 
             // Route changes (flaps) detected at this hop
-            if !self.hop.route_changes.is_empty() {
+            if !self.hop.route_changes.is_empty() || self.hop.suppressed_flaps > 0 {
                 lines.push(Line::from(""));
-                lines.push(Line::from(vec![Span::styled(
-                    format!("  Route Changes: {} detected", self.hop.route_changes.len()),
+                let mut summary = vec![Span::styled(
+                    format!("  Route Changes: {} detected", self.hop.flap_count),
                     Style::default().fg(self.theme.warning),
-                )]));
+                )];
+                if self.hop.suppressed_flaps > 0 {
+                    summary.push(Span::styled(
+                        format!(" ({} suppressed by hold-down)", self.hop.suppressed_flaps),
+                        Style::default().fg(self.theme.text_dim),
+                    ));
+                }
+                lines.push(Line::from(summary));
 
                 // Show last few route changes
                 for change in self.hop.route_changes.iter().rev().take(5) {
@@ -329,20 +910,49 @@ impl Widget for HopDetailView<'_> {
                         Span::styled(" → ", Style::default().fg(self.theme.text_dim)),
                         Span::raw(format!("{}", change.to_ip)),
                         Span::styled(
-                            format!(" (after {} responses)", change.at_seq),
+                            format!(
+                                " (after {} responses{})",
+                                change.at_seq,
+                                if change.damped { ", damped" } else { "" }
+                            ),
                             Style::default().fg(self.theme.text_dim),
                         ),
                     ]));
                 }
 
-                if self.hop.route_changes.len() > 5 {
+                let older = self.hop.flap_count.saturating_sub(5);
+                if older > 0 {
                     lines.push(Line::from(vec![Span::styled(
-                        format!("    ... and {} more", self.hop.route_changes.len() - 5),
+                        format!("    ... and {} more", older),
                         Style::default().fg(self.theme.text_dim),
                     )]));
                 }
             }
 
+            // Statistics from before the last reset (--reset-on-path-change)
+            if let Some((epoch, before)) = self.previous {
+                lines.push(Line::from(""));
+                lines.push(Line::from(vec![Span::styled(
+                    format!(
+                        "  Before path change at hop {} ({} → {}):",
+                        epoch.ttl, epoch.from, epoch.to
+                    ),
+                    Style::default().fg(self.theme.text_dim),
+                )]));
+                let mut summary = format!(
+                    "    {} sent, {} loss",
+                    nf.count(before.sent),
+                    nf.percent(before.loss_pct, 1)
+                );
+                if let Some(avg) = before.avg_ms {
+                    summary.push_str(&format!(", avg {}ms", nf.number(avg, 1)));
+                }
+                if let Some(ip) = before.primary {
+                    summary.push_str(&format!(" via {}", ip));
+                }
+                lines.push(Line::from(summary));
+            }
+
             // Asymmetric routing detection
             if let Some(ref asym) = self.hop.asymmetry {
                 let total = asym.symmetric_samples + asym.asymmetric_samples;
@@ -472,6 +1082,19 @@ impl Widget for HopDetailView<'_> {
                     "  Per-Flow Paths (ECMP detected):",
                     Style::default().fg(self.theme.warning),
                 )]));
+                if let Some(ecmp) = self.hop.ecmp {
+                    let more = if ecmp.is_confident() { "" } else { " or more" };
+                    lines.push(Line::from(vec![
+                        Span::styled("    Width: ", Style::default().fg(self.theme.text_dim)),
+                        Span::raw(format!(
+                            "{} next hops{} ({:.0}% confidence over {} flows)",
+                            ecmp.width,
+                            more,
+                            ecmp.confidence * 100.0,
+                            ecmp.flows
+                        )),
+                    ]));
+                }
 
                 let ecmp_paths = self.hop.ecmp_paths();
                 let num_paths = self.hop.path_count();
@@ -497,8 +1120,17 @@ impl Widget for HopDetailView<'_> {
                         ""
                     };
 
+                    // Source port, so the flow can be reproduced or allowed through a firewall
+                    let port = self
+                        .hop
+                        .flow_paths
+                        .get(flow_id)
+                        .and_then(|f| f.src_port)
+                        .map(|p| format!(" (:{})", p))
+                        .unwrap_or_default();
+
                     lines.push(Line::from(vec![
-                        Span::raw(format!("    Flow {}: ", flow_id)),
+                        Span::raw(format!("    Flow {}{}: ", flow_id, port)),
                         Span::raw(format!("{}{}", responder_ip, hostname)),
                         Span::styled(marker, Style::default().fg(self.theme.shortcut)),
                     ]));
@@ -508,7 +1140,14 @@ impl Widget for HopDetailView<'_> {
                 lines.push(Line::from(""));
                 lines.push(Line::from(vec![
                     Span::styled("  Flows: ", Style::default().fg(self.theme.text_dim)),
-                    Span::raw(format!("{} (single path)", self.hop.flow_paths.len())),
+                    Span::raw(match self.hop.ecmp {
+                        Some(ecmp) => format!(
+                            "{} (single path, {:.0}% confidence)",
+                            self.hop.flow_paths.len(),
+                            ecmp.confidence * 100.0
+                        ),
+                        None => format!("{} (single path)", self.hop.flow_paths.len()),
+                    }),
                 ]));
             }
 
@@ -527,18 +1166,52 @@ impl Widget for HopDetailView<'_> {
                             .as_ref()
                             .map(|h| format!(" ({})", h))
                             .unwrap_or_default();
+                        let loss = self
+                            .hop
+                            .responder_loss(*ip)
+                            .map(|l| {
+                                format!(
+                                    ", loss {}{}",
+                                    if l.estimated { "~" } else { "" },
+                                    nf.percent(l.loss_pct, 1)
+                                )
+                            })
+                            .unwrap_or_default();
+                        let seen = other_stats
+                            .last_seen
+                            .map(|last| format!(", seen {} ago", format_age(now - last)))
+                            .unwrap_or_default();
+                        // Alternates that stopped answering fade out entirely
+                        let name_style = if self.hop.is_stale(other_stats, stale_after, flows) {
+                            Style::default().fg(self.theme.text_dim)
+                        } else {
+                            Style::default()
+                        };
                         lines.push(Line::from(vec![
                             Span::raw("    "),
-                            Span::raw(format!("{}{}", ip, hostname)),
+                            Span::styled(format!("{}{}", ip, hostname), name_style),
                             Span::styled(
                                 format!(
-                                    " - {} responses, avg {:.1}ms",
-                                    other_stats.received,
-                                    other_stats.avg_rtt().as_secs_f64() * 1000.0
+                                    " - {} responses{}, avg {}{}",
+                                    nf.count(other_stats.received),
+                                    loss,
+                                    nf.rtt(other_stats.avg_rtt(), 1),
+                                    seen
                                 ),
                                 Style::default().fg(self.theme.text_dim),
                             ),
                         ]));
+                        let codes = outcome_spans(
+                            self.hop,
+                            *ip,
+                            (inner.width as usize).saturating_sub(8),
+                            self.theme,
+                        );
+                        if !codes.is_empty() {
+                            let mut spans = vec![Span::raw("      ")];
+                            spans.extend(codes);
+                            lines.push(Line::from(spans));
+                        }
                     }
                 }
             }
@@ -546,9 +1219,31 @@ impl Widget for HopDetailView<'_> {
             lines.push(Line::from("  No responses received at this TTL"));
         }
 
+        if !self.notes.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![Span::styled(
+                "  Notes:",
+                Style::default().fg(self.theme.text_dim),
+            )]));
+            for note in &self.notes {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "    {} ",
+                            self.config
+                                .map(|c| c.format_time(&note.at, "%H:%M:%S"))
+                                .unwrap_or_else(|| note.at.format("%H:%M:%S").to_string())
+                        ),
+                        Style::default().fg(self.theme.text_dim),
+                    ),
+                    Span::raw(note.text.clone()),
+                ]));
+            }
+        }
+
         lines.push(Line::from(""));
         lines.push(Line::from(vec![Span::styled(
-            "  [Esc/Enter/q] back",
+            "  [r] redo lookups  [Esc/Enter/q] back",
             Style::default().fg(self.theme.text_dim),
         )]));
 
@@ -1,11 +1,17 @@
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Rect};
+use ratatui::layout::Rect;
 use ratatui::style::{Modifier, Style};
-use ratatui::widgets::{Block, Borders, Cell, Row, Table, Widget};
+use ratatui::widgets::{Block, Borders, Cell, Row, Widget};
 
-use crate::state::{PmtudPhase, Session};
+use crate::state::window::window_label;
+use crate::state::{LoadPhase, PmtudPhase, Session, SloStatus, trim_host};
+use crate::tui::i18n::strings;
+use crate::tui::layout::{Column, TableLayout};
 use crate::tui::theme::Theme;
-use crate::tui::widgets::loss_sparkline_string;
+use crate::tui::widgets::{
+    ColumnWidth, SparklineSource, VirtualTable, loss_rate_sparkline_string, loss_sparkline_string,
+    sparkline_string,
+};
 
 /// Truncate a string to max_len characters, adding ellipsis if truncated
 fn truncate_with_ellipsis(s: &str, max_len: usize) -> String {
@@ -19,6 +25,20 @@ fn truncate_with_ellipsis(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Header gauge for the SLO error budget, e.g. ` [SLO ██████░░ 75% ok]`
+fn slo_gauge(status: &SloStatus) -> String {
+    const CELLS: usize = 8;
+    let budget = status.budget();
+    let filled = (budget * CELLS as f64).round() as usize;
+    format!(
+        " [SLO {}{} {:.0}% {}]",
+        "\u{2588}".repeat(filled),
+        "\u{2591}".repeat(CELLS - filled),
+        budget * 100.0,
+        if status.met() { "ok" } else { "MISS" }
+    )
+}
+
 /// Main table view showing all hops
 pub struct MainView<'a> {
     session: &'a Session,
@@ -29,6 +49,12 @@ pub struct MainView<'a> {
     target_index: Option<usize>,
     /// Total number of targets
     num_targets: usize,
+    /// Show sliding-window stats (--stats-window) instead of cumulative
+    windowed: bool,
+    /// First displayed hop row (see `layout::scroll_offset`)
+    scroll: usize,
+    /// What the sparkline column plots
+    sparkline: SparklineSource,
 }
 
 impl<'a> MainView<'a> {
@@ -45,6 +71,9 @@ impl<'a> MainView<'a> {
             theme,
             target_index: None,
             num_targets: 1,
+            windowed: false,
+            scroll: 0,
+            sparkline: SparklineSource::default(),
         }
     }
 
@@ -56,6 +85,40 @@ impl<'a> MainView<'a> {
         }
         self
     }
+
+    /// Show stats over the session's sliding window, if it has one
+    pub fn with_windowed(mut self, windowed: bool) -> Self {
+        self.windowed = windowed && self.session.config.stats_window.is_some();
+        self
+    }
+
+    /// Start the table at this hop row
+    pub fn with_scroll(mut self, scroll: usize) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    /// Plot `source` in the sparkline column
+    pub fn with_sparkline(mut self, source: SparklineSource) -> Self {
+        self.sparkline = source;
+        self
+    }
+
+    /// Number of hop rows the table has for `session`
+    pub fn row_count(session: &Session) -> usize {
+        let max_display_ttl = session.dest_ttl.unwrap_or(session.config.max_ttl);
+        session
+            .hops
+            .iter()
+            .filter(|h| h.sent > 0 && h.ttl <= max_display_ttl)
+            .count()
+    }
+
+    /// Hop rows that fit in a view `height` cells tall (borders and header
+    /// take three)
+    pub fn visible_rows(height: u16) -> usize {
+        height.saturating_sub(3) as usize
+    }
 }
 
 impl Widget for MainView<'_> {
@@ -74,8 +137,21 @@ impl Widget for MainView<'_> {
             String::new()
         };
 
-        let status = if self.paused { " [PAUSED]" } else { "" };
-        let nat_warn = if self.session.has_nat() { " [NAT]" } else { "" };
+        let text = strings(self.session.config.lang);
+        let status = if self.paused { text.paused_badge } else { "" };
+        let window_status = match self.session.config.stats_window {
+            Some(length) if self.windowed => format!(" [last {}]", window_label(length)),
+            _ => String::new(),
+        };
+        let throttle_status = match self.session.throttle.as_ref() {
+            Some(t) if t.active() => format!(" [THROTTLED {} pps]", t.rate),
+            _ => String::new(),
+        };
+        // Quoted packets were rewritten from this hop on
+        let nat_warn = match self.session.first_nat_hop() {
+            Some(ttl) => format!(" [NAT @{}]", ttl),
+            None => String::new(),
+        };
         let has_rate_limit = self
             .session
             .hops
@@ -86,12 +162,70 @@ impl Widget for MainView<'_> {
         let asym_warn = if has_asymmetry { " [ASYM]" } else { "" };
         let has_ttl_manip = self.session.hops.iter().any(|h| h.has_ttl_manip());
         let ttl_warn = if has_ttl_manip { " [TTL!]" } else { "" };
+        let tunnels = self.session.inferred_tunnels();
+        let mpls_warn = if tunnels.is_empty() { "" } else { " [MPLS?]" };
+        let routing_loop = self.session.routing_loop.as_ref();
+        let loop_warn = routing_loop
+            .map(|l| format!(" [LOOP {}-{}]", l.first_ttl, l.last_ttl))
+            .unwrap_or_default();
+        // First place the DSCP marking was rewritten, e.g. [DSCP 46>0 @5]
+        let remarks = self.session.dscp_remarks();
+        let dscp_warn = match remarks.first() {
+            Some(r) if remarks.len() > 1 => {
+                format!(
+                    " [DSCP {}>{} @{} +{}]",
+                    r.from,
+                    r.to,
+                    r.ttl,
+                    remarks.len() - 1
+                )
+            }
+            Some(r) => format!(" [DSCP {}>{} @{}]", r.from, r.to, r.ttl),
+            None => String::new(),
+        };
+        // First place the ECN marking was cleared or rewritten (--ecn)
+        let ecn_warn = match self.session.ecn_changes().first() {
+            Some(c) if c.bleached() => format!(" [ECN cleared @{}]", c.ttl),
+            Some(c) => format!(" [ECN {}>{} @{}]", c.from.as_str(), c.to.as_str(), c.ttl),
+            None => String::new(),
+        };
+
+        // --redirects warn: the first hop may not be the one shown
+        let redirect_warn = self
+            .session
+            .redirect_warning()
+            .map(|r| format!(" [REDIRECT→{}]", r.gateway))
+            .unwrap_or_default();
+
+        // Warning if destination not found and using default max_ttl=30;
+        // with --gap-limit holding probing back, where it stops instead
+        let max_ttl_warn = match self.session.gap_stop() {
+            Some(stop) => format!(" [GAP: stop at {}]", stop),
+            None if self.session.dest_ttl.is_none() && self.session.config.max_ttl == 30 => {
+                " [max_ttl=30]".to_string()
+            }
+            None => String::new(),
+        };
 
-        // Warning if destination not found and using default max_ttl=30
-        let max_ttl_warn = if self.session.dest_ttl.is_none() && self.session.config.max_ttl == 30 {
-            " [max_ttl=30]"
+        // -f: the hops before the first TTL aren't probed
+        let first_ttl = self.session.config.first_ttl;
+        let range_status = if first_ttl > 1 {
+            format!(" [TTL {}-{}]", first_ttl, self.session.config.max_ttl)
         } else {
-            ""
+            String::new()
+        };
+
+        // --adaptive: hops currently probed less often
+        let paced = self
+            .session
+            .hops
+            .iter()
+            .filter(|h| h.pace.backed_off())
+            .count();
+        let pace_status = if paced > 0 {
+            format!(" [PACED {}]", paced)
+        } else {
+            String::new()
         };
 
         // PMTUD status indicator
@@ -107,8 +241,26 @@ impl Widget for MainView<'_> {
                     .map(|mtu| format!(" [MTU: {}]", mtu))
                     .unwrap_or_default(),
             })
+            .or_else(|| {
+                // Without --pmtud, the MSS and Frag Needed hints give a bound
+                let estimate = self.session.path_mtu()?;
+                Some(format!(" [MTU≤{}]", estimate.mtu))
+            })
             .unwrap_or_default();
 
+        // SLO error budget gauge
+        let slo_status = self.session.slo.as_ref().map(slo_gauge).unwrap_or_default();
+
+        // Latency-under-load test: baseline phase, then the running grade
+        let load_status = match self.session.load.as_ref().map(|l| l.phase) {
+            Some(LoadPhase::Idle) => " [LOAD: baseline]".to_string(),
+            Some(LoadPhase::Loaded | LoadPhase::Done) => match self.session.load_result() {
+                Some(r) => format!(" [LOAD: {} {:+.0}ms]", r.grade, r.delta_ms),
+                None => " [LOAD]".to_string(),
+            },
+            None => String::new(),
+        };
+
         let probe_count = self.session.total_sent;
         let interval_ms = self.session.config.interval.as_millis();
 
@@ -145,19 +297,30 @@ impl Widget for MainView<'_> {
         };
 
         let title = format!(
-            "ttl \u{2500}\u{2500} {}{}{} \u{2500}\u{2500} {} probes \u{2500}\u{2500} {}ms interval{}{}{}{}{}{}{}",
+            "ttl \u{2500}\u{2500} {}{}{} \u{2500}\u{2500} {} \u{2500}\u{2500} {}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
             target_indicator,
             target_str,
             routing_str,
-            probe_count,
-            interval_ms,
+            (text.probes)(probe_count),
+            (text.interval)(interval_ms as u64),
             status,
+            throttle_status,
+            window_status,
             nat_warn,
             rl_warn,
             asym_warn,
             ttl_warn,
+            mpls_warn,
+            loop_warn,
+            dscp_warn,
+            ecn_warn,
+            redirect_warn,
             max_ttl_warn,
-            pmtud_status
+            range_status,
+            pace_status,
+            pmtud_status,
+            slo_status,
+            load_status
         );
 
         let block = Block::default()
@@ -170,217 +333,303 @@ impl Widget for MainView<'_> {
 
         // Check if multi-flow mode is enabled (Paris/Dublin traceroute)
         let multi_flow = self.session.config.flows > 1;
+        let nf = &self.session.config.number_format;
 
-        // Build header - add "Paths" column if multi-flow enabled
-        let mut header_cells = vec![
-            Cell::from("#").style(Style::default().bold()),
-            Cell::from("Host").style(Style::default().bold()),
-            Cell::from("ASN").style(Style::default().bold()),
-            Cell::from("Loss%").style(Style::default().bold()),
-            Cell::from("Sent").style(Style::default().bold()),
-            Cell::from("Avg").style(Style::default().bold()),
-            Cell::from("Min").style(Style::default().bold()),
-            Cell::from("Max").style(Style::default().bold()),
-            Cell::from("StdDev").style(Style::default().bold()),
-            Cell::from("Jitter").style(Style::default().bold()),
-        ];
-        if multi_flow {
-            header_cells.push(Cell::from("NAT").style(Style::default().bold()));
-            header_cells.push(Cell::from("Paths").style(Style::default().bold()));
-        }
-        header_cells.push(Cell::from("").style(Style::default().bold())); // Sparkline
-
-        let header = Row::new(header_cells).height(1);
+        // Columns that fit the current width (recomputed on resize)
+        let df_sweep = self.session.df_sweep.as_ref();
+        let layout = TableLayout::fit(inner.width, multi_flow, df_sweep.is_some());
+        let header_cells: Vec<Cell> = layout
+            .columns
+            .iter()
+            .map(|&c| match c {
+                Column::Sparkline => self.sparkline.title(),
+                c => text.column(c),
+            })
+            .map(|title| Cell::from(title).style(Style::default().bold()))
+            .collect();
 
         // Build rows - only show hops up to the destination
         let max_display_ttl = self.session.dest_ttl.unwrap_or(self.session.config.max_ttl);
-        let rows: Vec<Row> = self
+        let caused_loss = self.session.attributable_loss();
+        let hops: Vec<_> = self
             .session
             .hops
             .iter()
             .filter(|h| h.sent > 0 && h.ttl <= max_display_ttl)
-            .enumerate()
-            .map(|(idx, hop)| {
-                let is_selected = self.selected == Some(idx);
-
-                let (host, asn_display) = if let Some(stats) = hop.primary_stats() {
-                    let display = if let Some(ref hostname) = stats.hostname {
-                        hostname.clone()
-                    } else {
-                        stats.ip.to_string()
-                    };
-                    let asn = if let Some(ref asn_info) = stats.asn {
-                        truncate_with_ellipsis(&asn_info.name, 12)
-                    } else {
-                        String::new()
-                    };
-                    // Add indicators:
-                    // ! = route flap (single-flow only)
-                    // ~ = asymmetric routing (single-flow only)
-                    // ^ = TTL manipulation (all flow modes)
-                    let has_flap = !multi_flow && !hop.route_changes.is_empty();
-                    let has_asym = !multi_flow && hop.has_asymmetry();
-                    let has_ttl = hop.has_ttl_manip();
-                    // Build indicator string
-                    let mut ind = String::new();
-                    if has_flap {
-                        ind.push('!');
-                    }
-                    if has_asym {
-                        ind.push('~');
-                    }
-                    if has_ttl {
-                        ind.push('^');
-                    }
-                    let indicators = if ind.is_empty() {
-                        String::new()
-                    } else {
-                        format!(" {}", ind)
-                    };
-                    // Truncate to leave room for indicators
-                    // IPv6 addresses need more space (up to 39 chars vs 15 for IPv4)
-                    let base_len: usize = if stats.ip.is_ipv6() { 42 } else { 28 };
-                    let max_len = base_len.saturating_sub(indicators.len());
-                    let truncated = truncate_with_ellipsis(&display, max_len);
-                    (format!("{}{}", truncated, indicators), asn)
-                } else if hop.received == 0 {
-                    ("* * *".to_string(), String::new())
+            .collect();
+        let row = |idx: usize| {
+            let hop = hops[idx];
+
+            let (host, asn_display) = if let Some(stats) = hop.primary_stats() {
+                let display = stats
+                    .display_name(self.session.config.label_mode)
+                    .unwrap_or_else(|| stats.ip.to_string());
+                let asn = if let Some(ref asn_info) = stats.asn {
+                    truncate_with_ellipsis(&asn_info.name, 12)
                 } else {
-                    ("???".to_string(), String::new())
+                    String::new()
                 };
-
-                // Generate sparkline from hop-level results (shows both responses and timeouts)
-                let recent: Vec<_> = hop.recent_results.iter().cloned().collect();
-                let sparkline = loss_sparkline_string(&recent, 10);
-
-                // Color sparkline based on recent loss rate
-                let recent_loss = if recent.is_empty() {
-                    0.0
+                // Add indicators:
+                // ! = route flap (single-flow only)
+                // ~ = asymmetric routing (single-flow only)
+                // ^ = TTL manipulation (all flow modes)
+                // = = inside (or just after a hidden) suspected MPLS tunnel
+                // # = DSCP remarked before this hop
+                // @ = inside a routing loop
+                // ECMP ×N = N next hops across flows ("+" while more may be unseen)
+                let has_flap = !multi_flow && !hop.route_changes.is_empty();
+                let has_asym = !multi_flow && hop.has_asymmetry();
+                let has_ttl = hop.has_ttl_manip();
+                let in_tunnel = tunnels.iter().any(|t| t.contains(hop.ttl));
+                let remarked = remarks.iter().any(|r| r.ttl == hop.ttl);
+                let in_loop = routing_loop.is_some_and(|l| l.contains(hop.ttl));
+                // Build indicator string
+                let mut ind = String::new();
+                if has_flap {
+                    ind.push('!');
+                }
+                if has_asym {
+                    ind.push('~');
+                }
+                if has_ttl {
+                    ind.push('^');
+                }
+                if in_tunnel {
+                    ind.push('=');
+                }
+                if remarked {
+                    ind.push('#');
+                }
+                if in_loop {
+                    ind.push('@');
+                }
+                let mut indicators = if ind.is_empty() {
+                    String::new()
                 } else {
-                    let failures = recent.iter().filter(|&&r| !r).count();
-                    (failures as f64 / recent.len() as f64) * 100.0
+                    format!(" {}", ind)
                 };
-                let sparkline_color = if recent_loss > 50.0 {
-                    self.theme.error
-                } else if recent_loss > 10.0 {
-                    self.theme.warning
+                if let Some(badge) = hop.ecmp.filter(|_| multi_flow).and_then(|e| e.badge()) {
+                    indicators.push(' ');
+                    indicators.push_str(&badge);
+                }
+                // Truncate to the column, leaving room for indicators
+                let max_len =
+                    (layout.host_width as usize).saturating_sub(indicators.chars().count());
+                let truncated = trim_host(&display, max_len, self.session.config.host_trim);
+                (format!("{}{}", truncated, indicators), asn)
+            } else if hop.received == 0 {
+                ("* * *".to_string(), String::new())
+            } else {
+                ("???".to_string(), String::new())
+            };
+
+            // A primary that stopped answering (--stale-after) is grayed out
+            let config = &self.session.config;
+            let host_style = match hop.primary_stats() {
+                Some(stats) if hop.is_stale(stats, config.stale_after, config.flows) => {
+                    Style::default().fg(self.theme.text_dim)
+                }
+                _ => Style::default(),
+            };
+
+            // Loss views use hop-level results (responses and timeouts);
+            // RTT and jitter follow the primary responder
+            let recent: Vec<_> = hop.recent_results.iter().cloned().collect();
+            let width = layout.sparkline_width as usize;
+            let sparkline = match self.sparkline {
+                SparklineSource::Replies => loss_sparkline_string(&recent, width),
+                SparklineSource::LossRate => loss_rate_sparkline_string(&recent, width),
+                SparklineSource::Rtt => hop
+                    .primary_stats()
+                    .map(|s| {
+                        let rtts: Vec<_> = s.recent.iter().copied().collect();
+                        sparkline_string(&rtts, width)
+                    })
+                    .unwrap_or_default(),
+                SparklineSource::Jitter => hop
+                    .primary_stats()
+                    .map(|s| {
+                        let deltas: Vec<_> = s.recent_jitter.iter().map(|&d| Some(d)).collect();
+                        sparkline_string(&deltas, width)
+                    })
+                    .unwrap_or_default(),
+            };
+
+            // Color sparkline based on recent loss rate
+            let recent_loss = if recent.is_empty() {
+                0.0
+            } else {
+                let failures = recent.iter().filter(|&&r| !r).count();
+                (failures as f64 / recent.len() as f64) * 100.0
+            };
+            let sparkline_color = if recent_loss > 50.0 {
+                self.theme.error
+            } else if recent_loss > 10.0 {
+                self.theme.warning
+            } else {
+                self.theme.success
+            };
+
+            let window = if self.windowed {
+                hop.window_summary()
+            } else {
+                None
+            };
+            let loss = window.map_or_else(|| hop.loss_pct(), |w| w.loss_pct());
+
+            let (avg, min, max, stddev, jitter) = if let Some(w) = window {
+                if w.received > 0 && hop.primary.is_some() {
+                    (
+                        nf.rtt_bare(w.avg_rtt, 1),
+                        nf.rtt_bare(w.min_rtt, 1),
+                        nf.rtt_bare(w.max_rtt, 1),
+                        nf.rtt_bare(w.stddev, 1),
+                        nf.rtt_bare(w.jitter_avg, 1),
+                    )
                 } else {
-                    self.theme.success
-                };
-
-                let (avg, min, max, stddev, jitter) = if let Some(stats) = hop.primary_stats() {
-                    if stats.received > 0 {
-                        (
-                            format!("{:.1}", stats.avg_rtt().as_secs_f64() * 1000.0),
-                            format!("{:.1}", stats.min_rtt.as_secs_f64() * 1000.0),
-                            format!("{:.1}", stats.max_rtt.as_secs_f64() * 1000.0),
-                            format!("{:.1}", stats.stddev().as_secs_f64() * 1000.0),
-                            format!("{:.1}", stats.jitter().as_secs_f64() * 1000.0),
-                        )
-                    } else {
-                        ("-".into(), "-".into(), "-".into(), "-".into(), "-".into())
-                    }
+                    ("-".into(), "-".into(), "-".into(), "-".into(), "-".into())
+                }
+            } else if let Some(stats) = hop.primary_stats() {
+                if stats.received > 0 {
+                    (
+                        nf.rtt_bare(stats.avg_rtt(), 1),
+                        nf.rtt_bare(stats.min_rtt, 1),
+                        nf.rtt_bare(stats.max_rtt, 1),
+                        nf.rtt_bare(stats.stddev(), 1),
+                        nf.rtt_bare(stats.jitter(), 1),
+                    )
                 } else {
                     ("-".into(), "-".into(), "-".into(), "-".into(), "-".into())
-                };
-
-                // Determine if rate limiting is suspected
-                let rate_limited = hop
-                    .rate_limit
-                    .as_ref()
-                    .map(|r| r.suspected)
-                    .unwrap_or(false);
-
-                let loss_style = if rate_limited {
-                    // Rate limited: show in different color to indicate it's not real loss
-                    Style::default().fg(self.theme.shortcut)
-                } else if hop.loss_pct() > 50.0 {
-                    Style::default().fg(self.theme.error)
-                } else if hop.loss_pct() > 10.0 {
+                }
+            } else {
+                ("-".into(), "-".into(), "-".into(), "-".into(), "-".into())
+            };
+
+            // Determine if rate limiting is suspected
+            let rate_limited = hop
+                .rate_limit
+                .as_ref()
+                .map(|r| r.suspected)
+                .unwrap_or(false);
+
+            let loss_style = if rate_limited {
+                // Rate limited: show in different color to indicate it's not real loss
+                Style::default().fg(self.theme.shortcut)
+            } else if loss > 50.0 {
+                Style::default().fg(self.theme.error)
+            } else if loss > 10.0 {
+                Style::default().fg(self.theme.warning)
+            } else {
+                Style::default().fg(self.theme.success)
+            };
+
+            // Format loss with "RL" indicator if rate limited
+            let loss_display = if rate_limited {
+                format!("{}RL", nf.percent(loss, 0))
+            } else {
+                nf.percent(loss, 1)
+            };
+
+            // Loss that starts at this hop and carries to the destination
+            let (caused_display, caused_style) = match caused_loss.get(&hop.ttl) {
+                Some(&pct) if pct >= 0.05 => (
+                    nf.percent(pct, 1),
+                    Style::default().fg(if pct > 10.0 {
+                        self.theme.error
+                    } else {
+                        self.theme.warning
+                    }),
+                ),
+                Some(_) => (nf.percent(0.0, 1), Style::default().fg(self.theme.text_dim)),
+                None => ("-".into(), Style::default().fg(self.theme.text_dim)),
+            };
+
+            let mut cells = vec![
+                (Column::Ttl, Cell::from(hop.ttl.to_string())),
+                (Column::Host, Cell::from(host).style(host_style)),
+                (
+                    Column::Asn,
+                    Cell::from(asn_display).style(Style::default().fg(self.theme.text_dim)),
+                ),
+                (Column::Loss, Cell::from(loss_display).style(loss_style)),
+                (Column::Attr, Cell::from(caused_display).style(caused_style)),
+                (Column::Sent, Cell::from(nf.count(hop.sent))),
+                (Column::Avg, Cell::from(avg)),
+                (Column::Min, Cell::from(min)),
+                (Column::Max, Cell::from(max)),
+                (Column::StdDev, Cell::from(stddev)),
+                (Column::Jitter, Cell::from(jitter)),
+            ];
+
+            // Add "NAT" and "Paths" columns if multi-flow mode
+            if multi_flow {
+                // NAT indicator
+                let nat_display = if hop.has_nat() { "!" } else { "" };
+                let nat_style = if hop.has_nat() {
                     Style::default().fg(self.theme.warning)
                 } else {
-                    Style::default().fg(self.theme.success)
-                };
-
-                // Format loss with "RL" indicator if rate limited
-                let loss_display = if rate_limited {
-                    format!("{:.0}%RL", hop.loss_pct())
-                } else {
-                    format!("{:.1}%", hop.loss_pct())
+                    Style::default()
                 };
+                cells.push((Column::Nat, Cell::from(nat_display).style(nat_style)));
 
-                let row_style = if is_selected {
-                    Style::default()
-                        .bg(self.theme.highlight_bg)
-                        .add_modifier(Modifier::BOLD)
+                // Paths (ECMP detection)
+                let path_count = hop.path_count();
+                let paths_style = if hop.has_ecmp() {
+                    // ECMP detected - highlight with warning color
+                    Style::default().fg(self.theme.warning)
                 } else {
                     Style::default()
                 };
+                cells.push((
+                    Column::Paths,
+                    Cell::from(path_count.to_string()).style(paths_style),
+                ));
+            }
 
-                let mut cells = vec![
-                    Cell::from(hop.ttl.to_string()),
-                    Cell::from(host),
-                    Cell::from(asn_display).style(Style::default().fg(self.theme.text_dim)),
-                    Cell::from(loss_display).style(loss_style),
-                    Cell::from(hop.sent.to_string()),
-                    Cell::from(avg),
-                    Cell::from(min),
-                    Cell::from(max),
-                    Cell::from(stddev),
-                    Cell::from(jitter),
-                ];
-
-                // Add "NAT" and "Paths" columns if multi-flow mode
-                if multi_flow {
-                    // NAT indicator
-                    let nat_display = if hop.has_nat() { "!" } else { "" };
-                    let nat_style = if hop.has_nat() {
-                        Style::default().fg(self.theme.warning)
-                    } else {
-                        Style::default()
-                    };
-                    cells.push(Cell::from(nat_display).style(nat_style));
-
-                    // Paths (ECMP detection)
-                    let path_count = hop.path_count();
-                    let paths_style = if hop.has_ecmp() {
-                        // ECMP detected - highlight with warning color
-                        Style::default().fg(self.theme.warning)
-                    } else {
-                        Style::default()
-                    };
-                    cells.push(Cell::from(path_count.to_string()).style(paths_style));
-                }
+            if let Some(sweep) = df_sweep {
+                let size = sweep
+                    .max_unfragmented(hop.ttl)
+                    .map_or_else(|| "-".to_string(), |s| s.to_string());
+                // Refused at a size on the way here: the limit is upstream
+                let style = match sweep.hops.get(&hop.ttl).and_then(|h| h.blocked) {
+                    Some(_) => Style::default().fg(self.theme.warning),
+                    None => Style::default(),
+                };
+                cells.push((Column::MaxSize, Cell::from(size).style(style)));
+            }
 
-                cells.push(Cell::from(sparkline).style(Style::default().fg(sparkline_color)));
+            cells.push((
+                Column::Sparkline,
+                Cell::from(sparkline).style(Style::default().fg(sparkline_color)),
+            ));
+            let cells: Vec<Cell> = cells
+                .into_iter()
+                .filter(|(column, _)| layout.shows(*column))
+                .map(|(_, cell)| cell)
+                .collect();
+
+            Row::new(cells)
+        };
 
-                Row::new(cells).style(row_style)
+        // Host absorbs any rounding slack
+        let widths: Vec<ColumnWidth> = layout
+            .columns
+            .iter()
+            .zip(layout.widths())
+            .map(|(&column, width)| match column {
+                Column::Host => ColumnWidth::Min(width),
+                _ => ColumnWidth::Fixed(width),
             })
             .collect();
 
-        // Build column widths - conditional on multi-flow mode
-        let mut widths: Vec<Constraint> = vec![
-            Constraint::Length(3),  // #
-            Constraint::Min(16),    // Host
-            Constraint::Length(13), // ASN
-            Constraint::Length(7),  // Loss%
-            Constraint::Length(5),  // Sent
-            Constraint::Length(7),  // Avg
-            Constraint::Length(7),  // Min
-            Constraint::Length(7),  // Max
-            Constraint::Length(7),  // StdDev
-            Constraint::Length(7),  // Jitter
-        ];
-        if multi_flow {
-            widths.push(Constraint::Length(4)); // NAT
-            widths.push(Constraint::Length(6)); // Paths
-        }
-        widths.push(Constraint::Length(11)); // Sparkline
-
-        let table = Table::new(rows, widths)
-            .header(header)
-            .row_highlight_style(Style::default().bg(self.theme.highlight_bg));
-
-        table.render(inner, buf);
+        VirtualTable::new(header_cells, widths, hops.len(), row)
+            .offset(self.scroll)
+            .selected(self.selected)
+            .highlight_style(
+                Style::default()
+                    .bg(self.theme.highlight_bg)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .render(inner, buf);
     }
 }
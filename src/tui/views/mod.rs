@@ -1,7 +1,15 @@
+pub mod compare;
+pub mod diagnostics;
 pub mod help;
 pub mod hop;
 pub mod main;
+pub mod overview;
+pub mod timeline;
 
+pub use compare::*;
+pub use diagnostics::*;
 pub use help::*;
 pub use hop::*;
 pub use main::*;
+pub use overview::*;
+pub use timeline::*;
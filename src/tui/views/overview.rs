@@ -0,0 +1,147 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Widget};
+
+use crate::state::Session;
+use crate::tui::theme::Theme;
+use crate::tui::widgets::{ColumnWidth, VirtualTable, sparkline_string, table_capacity};
+
+/// Width of the destination RTT sparkline
+const SPARKLINE_WIDTH: usize = 20;
+
+/// Dashboard overlay for multi-target runs: one row per target
+///
+/// Reachability, loss and p95 RTT at the destination, route changes across
+/// all hops, and the destination's recent RTTs. Enter opens the selected
+/// target in the main view.
+pub struct OverviewView<'a> {
+    sessions: Vec<&'a Session>,
+    selected: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> OverviewView<'a> {
+    pub fn new(sessions: Vec<&'a Session>, selected: usize, theme: &'a Theme) -> Self {
+        Self {
+            sessions,
+            selected,
+            theme,
+        }
+    }
+
+    /// Status text and colour for a target
+    fn status(&self, session: &Session) -> (&'static str, ratatui::style::Color) {
+        if session.in_outage() {
+            ("DOWN", self.theme.error)
+        } else if session.dest_ttl.is_some() {
+            ("up", self.theme.success)
+        } else if session.total_sent > 0 {
+            ("no reply", self.theme.warning)
+        } else {
+            ("-", self.theme.text_dim)
+        }
+    }
+}
+
+impl Widget for OverviewView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width = area.width.saturating_sub(6);
+        let popup_height = area.height.saturating_sub(4);
+        let popup_x = (area.width - popup_width) / 2 + area.x;
+        let popup_y = (area.height - popup_height) / 2 + area.y;
+        let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+        Clear.render(popup_area, buf);
+
+        let block = Block::default()
+            .title(format!(" Overview ({} targets) ", self.sessions.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border));
+
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        let header: Vec<Cell> = ["#", "Target", "Status", "Loss", "p95", "Changes", "Recent"]
+            .into_iter()
+            .map(|title| {
+                Cell::from(title).style(
+                    Style::default()
+                        .fg(self.theme.header)
+                        .add_modifier(Modifier::BOLD),
+                )
+            })
+            .collect();
+        let widths = vec![
+            ColumnWidth::Fixed(3),
+            ColumnWidth::Weighted(1),
+            ColumnWidth::Fixed(8),
+            ColumnWidth::Fixed(6),
+            ColumnWidth::Fixed(9),
+            ColumnWidth::Fixed(7),
+            ColumnWidth::Fixed(SPARKLINE_WIDTH as u16),
+        ];
+        let row = |i: usize| {
+            let session = self.sessions[i];
+            let nf = &session.config.number_format;
+            let dest = session.dest_ttl.and_then(|ttl| session.hop(ttl));
+            let loss = dest
+                .map(|h| nf.percent(h.loss_pct(), 1))
+                .unwrap_or_else(|| "-".to_string());
+            let p95 = dest
+                .and_then(|h| h.primary_stats())
+                .and_then(|s| s.p95())
+                .map(|d| nf.rtt(d, 1))
+                .unwrap_or_else(|| "-".to_string());
+            let changes: u64 = session.hops.iter().map(|h| h.flap_count).sum();
+            let sparkline = dest
+                .and_then(|h| h.primary_stats())
+                .map(|s| {
+                    let rtts: Vec<_> = s.recent.iter().copied().collect();
+                    sparkline_string(&rtts, SPARKLINE_WIDTH)
+                })
+                .unwrap_or_default();
+            let (status, status_color) = self.status(session);
+
+            Row::new(vec![
+                Cell::from(format!("{:>3}", i + 1)),
+                Cell::from(session.target.original.clone()),
+                Cell::from(status).style(Style::default().fg(status_color)),
+                Cell::from(format!("{:>6}", loss)),
+                Cell::from(format!("{:>9}", p95)),
+                Cell::from(format!("{:>7}", changes)),
+                Cell::from(sparkline).style(Style::default().fg(self.theme.success)),
+            ])
+            .style(Style::default().fg(self.theme.text))
+        };
+
+        // Keep the selected row in view, footer below
+        let table_area = Rect {
+            height: inner.height.saturating_sub(2),
+            ..inner
+        };
+        let visible = table_capacity(table_area.height).max(1);
+        let offset = (self.selected + 1).saturating_sub(visible);
+        VirtualTable::new(header, widths, self.sessions.len(), row)
+            .offset(offset)
+            .selected(Some(self.selected))
+            .highlight_style(Style::default().bg(self.theme.highlight_bg))
+            .render(table_area, buf);
+
+        let footer_area = Rect {
+            y: table_area.y + table_area.height,
+            height: inner.height - table_area.height,
+            ..inner
+        };
+        let lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "  [↑/↓] select  [Enter] open  [Esc/o/q] back",
+                Style::default().fg(self.theme.text_dim),
+            )]),
+        ];
+
+        Paragraph::new(lines).render(footer_area, buf);
+    }
+}
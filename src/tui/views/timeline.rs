@@ -0,0 +1,89 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use crate::state::{EventKind, Session};
+use crate::tui::theme::Theme;
+
+/// Event timeline overlay (path changes, outages, alerts)
+pub struct TimelineView<'a> {
+    session: &'a Session,
+    theme: &'a Theme,
+}
+
+impl<'a> TimelineView<'a> {
+    pub fn new(session: &'a Session, theme: &'a Theme) -> Self {
+        Self { session, theme }
+    }
+}
+
+impl Widget for TimelineView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width = area.width.saturating_sub(10).min(90);
+        let popup_height = area.height.saturating_sub(6).min(25);
+        let popup_x = (area.width - popup_width) / 2 + area.x;
+        let popup_y = (area.height - popup_height) / 2 + area.y;
+        let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+        Clear.render(popup_area, buf);
+
+        let block = Block::default()
+            .title(format!(" Events ({}) ", self.session.events.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border));
+
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        let mut lines = Vec::new();
+        if self.session.events.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "  No events yet",
+                Style::default().fg(self.theme.text_dim),
+            )));
+        } else {
+            // Show the most recent events that fit, oldest first
+            let visible = (inner.height as usize).saturating_sub(2).max(1);
+            let skip = self.session.events.len().saturating_sub(visible);
+            for event in self.session.events.iter().skip(skip) {
+                let color = match event.kind {
+                    EventKind::OutageStart { .. }
+                    | EventKind::Alert { .. }
+                    | EventKind::RoutingLoop { .. } => self.theme.error,
+                    EventKind::PathChange { .. }
+                    | EventKind::PrefixChange { .. }
+                    | EventKind::ReturnTtlChange { .. }
+                    | EventKind::IxChange { .. }
+                    | EventKind::DestinationExpiry { .. }
+                    | EventKind::Redirect { .. }
+                    | EventKind::LatencySpike { .. } => self.theme.warning,
+                    EventKind::OutageEnd { .. } | EventKind::DestinationReached { .. } => {
+                        self.theme.success
+                    }
+                    EventKind::ConfigChange { .. } => self.theme.text_dim,
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "  {} ",
+                            self.session.config.format_time(&event.at, "%H:%M:%S")
+                        ),
+                        Style::default().fg(self.theme.text_dim),
+                    ),
+                    Span::styled(event.kind.describe(), Style::default().fg(color)),
+                ]));
+            }
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            "  [Esc/l/q] back",
+            Style::default().fg(self.theme.text_dim),
+        )]));
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
@@ -0,0 +1,568 @@
+//! Read-only TUI in a browser (`--serve-tui`)
+//!
+//! Serves a small page that polls the main view, rendered server-side
+//! into a ratatui buffer sized to the browser window and sent as styled
+//! HTML. No terminal emulator or websocket is involved, so a long-running
+//! trace can be watched from a browser without reattaching tmux. There is
+//! no authentication, so the listener is loopback-only (reach it through an
+//! SSH tunnel) unless `--allow-remote` is given. The view is read-only: Tab
+//! in the page only switches which target is shown to that viewer.
+//!
+//! `--metrics` runs the same server with only the `/metrics` route, so a
+//! Prometheus scraper can collect path signatures without the view being
+//! exposed.
+
+use anyhow::{Context, Result};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier};
+use ratatui::widgets::Widget;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+use crate::export::render_metrics;
+use crate::trace::receiver::SessionMap;
+use crate::tui::theme::Theme;
+use crate::tui::views::MainView;
+
+/// Prometheus text exposition format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Largest request head accepted
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// How long a client gets to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Frame size limits (columns, rows)
+const COLS: (u16, u16) = (40, 300);
+const ROWS: (u16, u16) = (10, 150);
+
+/// Default frame size when the page doesn't ask for one
+const DEFAULT_SIZE: (u16, u16) = (120, 40);
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>ttl</title>
+<style>
+html,body{margin:0;height:100%;background:#000;color:#ccc}
+pre{margin:0;padding:4px;font:14px/1.2 ui-monospace,Menlo,Consolas,monospace;white-space:pre}
+#m{position:absolute;visibility:hidden}
+</style></head><body>
+<pre id="s">connecting…</pre><pre id="m">X</pre>
+<script>
+let target = 0, targets = 1;
+function size() {
+  const m = document.getElementById("m").getBoundingClientRect();
+  return [Math.floor((innerWidth - 8) / m.width), Math.floor((innerHeight - 8) / m.height)];
+}
+async function tick() {
+  const [cols, rows] = size();
+  try {
+    const r = await fetch(`frame?cols=${cols}&rows=${rows}&target=${target}`, {cache: "no-store"});
+    targets = Number(r.headers.get("X-Targets")) || 1;
+    document.getElementById("s").innerHTML = await r.text();
+  } catch (e) {
+    document.getElementById("s").textContent = "disconnected, retrying…";
+  }
+  setTimeout(tick, 1000);
+}
+addEventListener("keydown", e => {
+  if (e.key === "Tab") {
+    e.preventDefault();
+    target = (target + (e.shiftKey ? targets - 1 : 1)) % targets;
+  }
+});
+tick();
+</script></body></html>
+"#;
+
+/// Render the main view of target `index` into a `cols` x `rows` buffer
+pub fn render_frame(
+    sessions: &SessionMap,
+    targets: &[IpAddr],
+    index: usize,
+    theme: &Theme,
+    (cols, rows): (u16, u16),
+) -> Buffer {
+    let area = Rect::new(0, 0, cols, rows);
+    let mut buf = Buffer::empty(area);
+    let index = index.min(targets.len().saturating_sub(1));
+    let sessions = sessions.read();
+    if let Some(state) = targets.get(index).and_then(|ip| sessions.get(ip)) {
+        let session = state.read();
+        MainView::new(&session, None, false, theme)
+            .with_target_info(index + 1, targets.len())
+            .render(area, &mut buf);
+    }
+    buf
+}
+
+/// CSS color for a terminal color (None = the page default)
+fn css_color(color: Color) -> Option<String> {
+    const BASIC: [&str; 16] = [
+        "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+        "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+    ];
+    let indexed = |n: u8| match n {
+        0..=15 => BASIC[n as usize].to_string(),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(n / 36),
+                level(n / 6 % 6),
+                level(n % 6)
+            )
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", v, v, v)
+        }
+    };
+    Some(match color {
+        Color::Reset => return None,
+        Color::Black => indexed(0),
+        Color::Red => indexed(1),
+        Color::Green => indexed(2),
+        Color::Yellow => indexed(3),
+        Color::Blue => indexed(4),
+        Color::Magenta => indexed(5),
+        Color::Cyan => indexed(6),
+        Color::Gray => indexed(7),
+        Color::DarkGray => indexed(8),
+        Color::LightRed => indexed(9),
+        Color::LightGreen => indexed(10),
+        Color::LightYellow => indexed(11),
+        Color::LightBlue => indexed(12),
+        Color::LightMagenta => indexed(13),
+        Color::LightCyan => indexed(14),
+        Color::White => indexed(15),
+        Color::Indexed(n) => indexed(n),
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    })
+}
+
+/// Inline CSS for a cell's colors and modifiers
+fn css_style(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let (mut fg, mut bg) = (css_color(fg), css_color(bg));
+    if modifier.contains(Modifier::REVERSED) {
+        (fg, bg) = (
+            bg.or_else(|| Some("#000000".to_string())),
+            fg.or_else(|| Some("#cccccc".to_string())),
+        );
+    }
+    let mut css = String::new();
+    if let Some(fg) = fg {
+        let _ = write!(css, "color:{};", fg);
+    }
+    if let Some(bg) = bg {
+        let _ = write!(css, "background:{};", bg);
+    }
+    if modifier.contains(Modifier::BOLD) {
+        css.push_str("font-weight:bold;");
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        css.push_str("font-style:italic;");
+    }
+    if modifier.contains(Modifier::UNDERLINED) {
+        css.push_str("text-decoration:underline;");
+    }
+    if modifier.contains(Modifier::DIM) {
+        css.push_str("opacity:0.6;");
+    }
+    css
+}
+
+fn escape_into(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Buffer as HTML: one line per row, runs of equally styled cells in one span
+pub fn buffer_to_html(buf: &Buffer) -> String {
+    let area = buf.area;
+    let mut html = String::new();
+    for y in area.top()..area.bottom() {
+        let mut run = String::new();
+        let mut run_style: Option<String> = None;
+        let flush = |html: &mut String, run: &mut String, style: &Option<String>| {
+            if run.is_empty() {
+                return;
+            }
+            match style.as_deref() {
+                Some("") | None => html.push_str(run),
+                Some(css) => {
+                    let _ = write!(html, "<span style=\"{}\">{}</span>", css, run);
+                }
+            }
+            run.clear();
+        };
+        for x in area.left()..area.right() {
+            let cell = &buf[(x, y)];
+            let style = css_style(cell.fg, cell.bg, cell.modifier);
+            if run_style.as_ref() != Some(&style) {
+                flush(&mut html, &mut run, &run_style);
+                run_style = Some(style);
+            }
+            escape_into(&mut run, cell.symbol());
+        }
+        flush(&mut html, &mut run, &run_style);
+        html.push('\n');
+    }
+    html
+}
+
+/// Buffer as plain text, trailing spaces trimmed (for curl)
+pub fn buffer_to_text(buf: &Buffer) -> String {
+    let area = buf.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let line: String = (area.left()..area.right())
+            .map(|x| buf[(x, y)].symbol())
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Split `/path?a=1&b=2` into the path and its query parameters
+fn parse_target(target: &str) -> (&str, HashMap<&str, &str>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect();
+    (path, params)
+}
+
+fn frame_size(params: &HashMap<&str, &str>) -> (u16, u16) {
+    let get = |key: &str, (min, max): (u16, u16), default: u16| {
+        params
+            .get(key)
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(default)
+            .clamp(min, max)
+    };
+    (
+        get("cols", COLS, DEFAULT_SIZE.0),
+        get("rows", ROWS, DEFAULT_SIZE.1),
+    )
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+    targets: usize,
+}
+
+fn respond(
+    request_line: &str,
+    sessions: &SessionMap,
+    targets: &[IpAddr],
+    theme: &Theme,
+) -> Response {
+    let not_found = Response {
+        status: "404 Not Found",
+        content_type: "text/plain; charset=utf-8",
+        body: "not found\n".to_string(),
+        targets: targets.len(),
+    };
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Response {
+            status: "400 Bad Request",
+            body: "bad request\n".to_string(),
+            ..not_found
+        };
+    };
+    if method != "GET" {
+        return Response {
+            status: "405 Method Not Allowed",
+            body: "read-only\n".to_string(),
+            ..not_found
+        };
+    }
+
+    let (path, params) = parse_target(target);
+    let index = params
+        .get("target")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let frame = || render_frame(sessions, targets, index, theme, frame_size(&params));
+    match path {
+        "/" => Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: PAGE.to_string(),
+            ..not_found
+        },
+        "/frame" => Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: buffer_to_html(&frame()),
+            ..not_found
+        },
+        "/frame.txt" => Response {
+            status: "200 OK",
+            content_type: "text/plain; charset=utf-8",
+            body: buffer_to_text(&frame()),
+            ..not_found
+        },
+        "/metrics" => Response {
+            status: "200 OK",
+            content_type: METRICS_CONTENT_TYPE,
+            body: metrics(sessions, targets),
+            ..not_found
+        },
+        _ => not_found,
+    }
+}
+
+/// Path signature metrics for every target, in target order
+fn metrics(sessions: &SessionMap, targets: &[IpAddr]) -> String {
+    let sessions = sessions.read();
+    let states: Vec<_> = targets
+        .iter()
+        .filter_map(|ip| sessions.get(ip))
+        .map(|state| state.read())
+        .collect();
+    render_metrics(states.iter().map(|state| &**state))
+}
+
+/// `--metrics`: only the metrics route
+fn respond_metrics(request_line: &str, sessions: &SessionMap, targets: &[IpAddr]) -> Response {
+    let mut parts = request_line.split_whitespace();
+    let target = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => target,
+        _ => {
+            return Response {
+                status: "405 Method Not Allowed",
+                content_type: "text/plain; charset=utf-8",
+                body: "read-only\n".to_string(),
+                targets: targets.len(),
+            };
+        }
+    };
+    match parse_target(target).0 {
+        "/metrics" => Response {
+            status: "200 OK",
+            content_type: METRICS_CONTENT_TYPE,
+            body: metrics(sessions, targets),
+            targets: targets.len(),
+        },
+        _ => Response {
+            status: "404 Not Found",
+            content_type: "text/plain; charset=utf-8",
+            body: "not found\n".to_string(),
+            targets: targets.len(),
+        },
+    }
+}
+
+async fn serve_client(mut stream: TcpStream, respond: impl FnOnce(&str) -> Response) -> Result<()> {
+    let mut request = Vec::new();
+    let mut chunk = [0u8; 1024];
+    tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&chunk[..n]);
+        }
+        Ok::<_, std::io::Error>(())
+    })
+    .await
+    .context("request timed out")??;
+
+    let head = String::from_utf8_lossy(&request);
+    let response = respond(head.lines().next().unwrap_or(""));
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+         X-Targets: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.targets
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Bind the `--serve-tui` or `--metrics` listener (done up front so a bad
+/// address fails before tracing starts)
+pub async fn bind(addr: SocketAddr, option: &str) -> Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {} for {}", addr, option))
+}
+
+/// Serve the browser view until cancelled
+pub async fn run_web_tui(
+    listener: TcpListener,
+    sessions: SessionMap,
+    targets: Vec<IpAddr>,
+    theme: Theme,
+    cancel: CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                let sessions = sessions.clone();
+                let targets = targets.clone();
+                let theme = theme.clone();
+                tokio::spawn(async move {
+                    // A client that goes away mid-request is its own problem
+                    let respond = |line: &str| respond(line, &sessions, &targets, &theme);
+                    let _ = serve_client(stream, respond).await;
+                });
+            }
+        }
+    }
+}
+
+/// Serve path signature metrics until cancelled (`--metrics`)
+pub async fn run_metrics_server(
+    listener: TcpListener,
+    sessions: SessionMap,
+    targets: Vec<IpAddr>,
+    cancel: CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                let sessions = sessions.clone();
+                let targets = targets.clone();
+                tokio::spawn(async move {
+                    let respond = |line: &str| respond_metrics(line, &sessions, &targets);
+                    let _ = serve_client(stream, respond).await;
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::{Session, Target};
+    use parking_lot::RwLock;
+    use ratatui::style::Style;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_buffer_to_html() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buf.set_string(0, 0, "a<b", Style::default().fg(Color::Red));
+        buf.set_string(0, 1, "ok", Style::default().add_modifier(Modifier::BOLD));
+        let html = buffer_to_html(&buf);
+        let lines: Vec<&str> = html.lines().collect();
+        assert_eq!(lines[0], "<span style=\"color:#cd0000;\">a&lt;b</span>   ");
+        assert_eq!(lines[1], "<span style=\"font-weight:bold;\">ok</span>    ");
+        assert_eq!(buffer_to_text(&buf), "a<b\nok\n");
+    }
+
+    #[test]
+    fn test_css_color() {
+        assert_eq!(css_color(Color::Reset), None);
+        assert_eq!(css_color(Color::Rgb(1, 2, 255)).unwrap(), "#0102ff");
+        assert_eq!(css_color(Color::Indexed(196)).unwrap(), "#ff0000");
+        assert_eq!(css_color(Color::Indexed(232)).unwrap(), "#080808");
+    }
+
+    #[test]
+    fn test_respond() {
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let session = Session::new(Target::new("example.com".into(), ip), Config::default());
+        let sessions: SessionMap = Arc::new(RwLock::new(HashMap::from([(
+            ip,
+            Arc::new(RwLock::new(session)),
+        )])));
+        let theme = Theme::default();
+
+        let page = respond("GET / HTTP/1.1", &sessions, &[ip], &theme);
+        assert_eq!(page.status, "200 OK");
+        assert!(page.body.contains("frame?cols="));
+
+        let frame = respond(
+            "GET /frame.txt?cols=80&rows=12&target=5 HTTP/1.1",
+            &sessions,
+            &[ip],
+            &theme,
+        );
+        assert_eq!(frame.body.lines().count(), 12);
+        assert!(frame.body.contains("192.0.2.1"));
+
+        let metrics = respond("GET /metrics HTTP/1.1", &sessions, &[ip], &theme);
+        assert!(
+            metrics
+                .content_type
+                .starts_with("text/plain; version=0.0.4")
+        );
+        assert!(metrics.body.contains(
+            "ttl_path_signature_changes_total{target=\"example.com\",ip=\"192.0.2.1\"} 0"
+        ));
+
+        assert_eq!(
+            respond("POST / HTTP/1.1", &sessions, &[ip], &theme).status,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(
+            respond("GET /x HTTP/1.1", &sessions, &[ip], &theme).status,
+            "404 Not Found"
+        );
+    }
+
+    #[test]
+    fn test_respond_metrics() {
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let session = Session::new(Target::new("example.com".into(), ip), Config::default());
+        let sessions: SessionMap = Arc::new(RwLock::new(HashMap::from([(
+            ip,
+            Arc::new(RwLock::new(session)),
+        )])));
+
+        let metrics = respond_metrics("GET /metrics HTTP/1.1", &sessions, &[ip]);
+        assert_eq!(metrics.content_type, METRICS_CONTENT_TYPE);
+        assert!(metrics.body.contains("ttl_path_signature_changes_total{"));
+        // The view itself isn't served
+        for path in ["/", "/frame", "/frame.txt"] {
+            let line = format!("GET {} HTTP/1.1", path);
+            assert_eq!(
+                respond_metrics(&line, &sessions, &[ip]).status,
+                "404 Not Found"
+            );
+        }
+        assert_eq!(
+            respond_metrics("POST /metrics HTTP/1.1", &sessions, &[ip]).status,
+            "405 Method Not Allowed"
+        );
+    }
+
+    #[test]
+    fn test_frame_size_clamped() {
+        let (_, params) = parse_target("/frame?cols=5000&rows=x");
+        assert_eq!(frame_size(&params), (COLS.1, DEFAULT_SIZE.1));
+    }
+}
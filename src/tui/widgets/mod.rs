@@ -1,3 +1,5 @@
 pub mod sparkline;
+pub mod table;
 
 pub use sparkline::*;
+pub use table::*;
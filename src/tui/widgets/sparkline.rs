@@ -2,11 +2,52 @@ use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Style};
 use ratatui::widgets::Widget;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 /// Unicode block characters for sparkline
 const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
+/// Probes per point of the rolling loss rate sparkline
+const LOSS_RATE_SPAN: usize = 10;
+
+/// What the main table's sparkline column plots
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SparklineSource {
+    /// Reply (█) or timeout (×) per probe
+    #[default]
+    Replies,
+    /// Primary responder's recent RTTs
+    Rtt,
+    /// Primary responder's RTT change between consecutive replies
+    Jitter,
+    /// Share of the last few probes lost, per probe
+    LossRate,
+}
+
+impl SparklineSource {
+    /// Next source, for the `s` key
+    pub fn next(self) -> Self {
+        match self {
+            Self::Replies => Self::Rtt,
+            Self::Rtt => Self::Jitter,
+            Self::Jitter => Self::LossRate,
+            Self::LossRate => Self::Replies,
+        }
+    }
+
+    /// Column header
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Replies => "Replies",
+            Self::Rtt => "RTT",
+            Self::Jitter => "Jitter",
+            Self::LossRate => "Loss rate",
+        }
+    }
+}
+
 /// A sparkline widget for displaying RTT history
 #[allow(dead_code)]
 pub struct RttSparkline<'a> {
@@ -103,6 +144,21 @@ pub fn loss_sparkline_string(data: &[bool], width: usize) -> String {
         .collect()
 }
 
+/// Generate sparkline string for the rolling loss rate: each point is the
+/// share of the `LOSS_RATE_SPAN` probes up to it that timed out, from ▁
+/// (none) to █ (all)
+pub fn loss_rate_sparkline_string(data: &[bool], width: usize) -> String {
+    let start = data.len().saturating_sub(width);
+    (start..data.len())
+        .map(|i| {
+            let span = &data[(i + 1).saturating_sub(LOSS_RATE_SPAN)..=i];
+            let lost = span.iter().filter(|&&success| !success).count();
+            let idx = (lost as f64 / span.len() as f64 * 7.0).round() as usize;
+            BLOCKS[idx.min(7)]
+        })
+        .collect()
+}
+
 /// Generate sparkline string from RTT data
 pub fn sparkline_string(data: &[Option<Duration>], width: usize) -> String {
     if data.is_empty() {
@@ -142,3 +198,39 @@ pub fn sparkline_string(data: &[Option<Duration>], width: usize) -> String {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loss_rate_sparkline_string() {
+        assert_eq!(loss_rate_sparkline_string(&[], 10), "");
+        assert_eq!(loss_rate_sparkline_string(&[true; 20], 5), "▁▁▁▁▁");
+
+        // One loss, then recovery: the rate steps up and stays up until
+        // the loss leaves the span
+        let mut data = vec![true; 5];
+        data.push(false);
+        data.extend([true; 12]);
+        let line: Vec<char> = loss_rate_sparkline_string(&data, 18).chars().collect();
+        assert_eq!(line[4], '▁');
+        assert_eq!(line[5], '▂');
+        assert_eq!(line[14], '▂');
+        assert_eq!(line[15], '▁');
+
+        assert_eq!(loss_rate_sparkline_string(&[false; 3], 10), "███");
+    }
+
+    #[test]
+    fn test_sparkline_source_cycles() {
+        let mut source = SparklineSource::default();
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            seen.push(source.title());
+            source = source.next();
+        }
+        assert_eq!(source, SparklineSource::Replies);
+        assert_eq!(seen, ["Replies", "RTT", "Jitter", "Loss rate"]);
+    }
+}
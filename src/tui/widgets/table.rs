@@ -0,0 +1,153 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::Style;
+use ratatui::widgets::{Cell, Row, StatefulWidget, Table, TableState, Widget};
+
+/// How a `VirtualTable` column is sized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnWidth {
+    /// Exactly this many cells
+    Fixed(u16),
+    /// At least this many cells, plus any rounding slack
+    Min(u16),
+    /// A share of the width left after the other columns, by weight
+    Weighted(u16),
+}
+
+impl ColumnWidth {
+    fn constraint(self) -> Constraint {
+        match self {
+            ColumnWidth::Fixed(w) => Constraint::Length(w),
+            ColumnWidth::Min(w) => Constraint::Min(w),
+            ColumnWidth::Weighted(w) => Constraint::Fill(w),
+        }
+    }
+}
+
+/// Table that builds only the rows in view
+///
+/// Rows come from a callback taking the row index, called for the rows
+/// between `offset` and the bottom of the area, so long hop or target
+/// lists cost the same to draw as short ones. The highlight style is
+/// patched over the selected row's own style.
+pub struct VirtualTable<'a, F> {
+    header: Vec<Cell<'a>>,
+    widths: Vec<ColumnWidth>,
+    len: usize,
+    row: F,
+    offset: usize,
+    selected: Option<usize>,
+    highlight: Style,
+}
+
+impl<'a, F: FnMut(usize) -> Row<'a>> VirtualTable<'a, F> {
+    pub fn new(header: Vec<Cell<'a>>, widths: Vec<ColumnWidth>, len: usize, row: F) -> Self {
+        Self {
+            header,
+            widths,
+            len,
+            row,
+            offset: 0,
+            selected: None,
+            highlight: Style::default(),
+        }
+    }
+
+    /// First row to draw
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn highlight_style(mut self, style: Style) -> Self {
+        self.highlight = style;
+        self
+    }
+}
+
+/// Rows a `VirtualTable` shows under its header in `height` cells
+pub fn table_capacity(height: u16) -> usize {
+    height.saturating_sub(1) as usize
+}
+
+impl<'a, F: FnMut(usize) -> Row<'a>> Widget for VirtualTable<'a, F> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let start = self.offset.min(self.len);
+        let end = start
+            .saturating_add(table_capacity(area.height))
+            .min(self.len);
+        let rows: Vec<Row> = (start..end).map(&mut self.row).collect();
+        let widths = self.widths.iter().map(|w| w.constraint());
+
+        // Rows are already windowed, so the state's offset stays at zero
+        let mut state = TableState::default().with_selected(
+            self.selected
+                .filter(|s| (start..end).contains(s))
+                .map(|s| s - start),
+        );
+        StatefulWidget::render(
+            Table::new(rows, widths)
+                .header(Row::new(self.header).height(1))
+                .row_highlight_style(self.highlight),
+            area,
+            buf,
+            &mut state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_virtual_table_builds_visible_rows() {
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buf = Buffer::empty(area);
+        let mut built = Vec::new();
+        VirtualTable::new(
+            vec![Cell::from("#"), Cell::from("Name")],
+            vec![ColumnWidth::Fixed(3), ColumnWidth::Weighted(1)],
+            1000,
+            |idx| {
+                built.push(idx);
+                Row::new(vec![Cell::from(idx.to_string()), Cell::from("row")])
+            },
+        )
+        .offset(500)
+        .selected(Some(501))
+        .highlight_style(Style::default().bg(Color::Blue))
+        .render(area, &mut buf);
+
+        // Header plus three rows
+        assert_eq!(built, vec![500, 501, 502]);
+        let line = |y| {
+            (0..area.width)
+                .map(|x| buf[(x, y)].symbol())
+                .collect::<String>()
+        };
+        assert!(line(1).starts_with("500 row"));
+        assert_eq!(buf[(0, 2)].bg, Color::Blue);
+        assert_ne!(buf[(0, 1)].bg, Color::Blue);
+    }
+
+    #[test]
+    fn test_virtual_table_past_end() {
+        let area = Rect::new(0, 0, 10, 5);
+        let mut buf = Buffer::empty(area);
+        let mut calls = 0;
+        VirtualTable::new(vec![Cell::from("#")], vec![ColumnWidth::Min(3)], 2, |idx| {
+            calls += 1;
+            Row::new(vec![Cell::from(idx.to_string())])
+        })
+        .offset(5)
+        .render(area, &mut buf);
+        assert_eq!(calls, 0);
+    }
+}